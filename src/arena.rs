@@ -0,0 +1,160 @@
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+/// An id of a value stored in an `Arena`: the index of its slot, and the generation the slot
+/// was in when the value was inserted.
+pub trait ArenaId: Copy {
+    fn from_parts(index: usize, generation: u32) -> Self;
+    fn index(self) -> usize;
+    fn generation(self) -> u32;
+}
+
+#[derive(Clone, Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// A generational arena: a vec whose slots are reused once their value is removed.
+///
+/// Removing a value bumps the generation of its slot, so ids of removed values stop resolving
+/// instead of pointing at whatever value reuses the slot, and the ids of the other values never
+/// change.
+#[derive(Clone, Debug)]
+pub struct Arena<I, T> {
+    slots: Vec<Slot<T>>,
+    /// indices of the empty slots, the next insert reusing the last one
+    free: Vec<usize>,
+    _id: PhantomData<fn() -> I>,
+}
+
+impl<I: ArenaId, T> Default for Arena<I, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ArenaId, T> Arena<I, T> {
+    /// Creates an arena holding no values.
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            _id: PhantomData,
+        }
+    }
+
+    /// Stores `value` in an empty slot, or a new one if there is none, and returns its id.
+    pub fn insert(&mut self, value: T) -> I {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index];
+            slot.value = Some(value);
+            return I::from_parts(index, slot.generation);
+        }
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        I::from_parts(self.slots.len() - 1, 0)
+    }
+
+    /// Removes and returns the value of `id`, freeing its slot.
+    ///
+    /// Returns `None` if `id` was already removed.
+    pub fn remove(&mut self, id: I) -> Option<T> {
+        let slot = self.slots.get_mut(id.index())?;
+        if slot.generation != id.generation() {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation += 1;
+        self.free.push(id.index());
+        Some(value)
+    }
+
+    pub fn get(&self, id: I) -> Option<&T> {
+        self.slots
+            .get(id.index())
+            .filter(|slot| slot.generation == id.generation())?
+            .value
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: I) -> Option<&mut T> {
+        self.slots
+            .get_mut(id.index())
+            .filter(|slot| slot.generation == id.generation())?
+            .value
+            .as_mut()
+    }
+
+    /// Removes every value, freeing their slots.
+    ///
+    /// Unlike replacing the arena with a new one, this keeps the generations, so the ids of the
+    /// removed values stay stale once the slots are reused.
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                slot.generation += 1;
+                self.free.push(index);
+            }
+        }
+    }
+
+    /// Returns whether `id` refers to a value that wasn't removed.
+    pub fn contains(&self, id: I) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Returns the id of the value in the slot at `index`, if it holds one.
+    pub fn id_at(&self, index: usize) -> Option<I> {
+        let slot = self.slots.get(index)?;
+        slot.value
+            .as_ref()
+            .map(|_| I::from_parts(index, slot.generation))
+    }
+
+    /// Returns the number of values stored.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Returns the number of slots, one past the highest index an id can have.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the ids of the values stored, by slot index.
+    pub fn ids(&self) -> Vec<I> {
+        self.iter().map(|(id, _)| id).collect()
+    }
+
+    /// Iterates over the values stored and their ids, by slot index.
+    pub fn iter(&self) -> impl Iterator<Item = (I, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let value = slot.value.as_ref()?;
+            Some((I::from_parts(index, slot.generation), value))
+        })
+    }
+
+    /// Iterates over the values stored, by slot index.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+}
+
+impl<I: ArenaId, T> Index<I> for Arena<I, T> {
+    type Output = T;
+
+    /// Panics if `id` was removed.
+    fn index(&self, id: I) -> &T {
+        self.get(id).expect("arena id was removed")
+    }
+}
+
+impl<I: ArenaId, T> IndexMut<I> for Arena<I, T> {
+    /// Panics if `id` was removed.
+    fn index_mut(&mut self, id: I) -> &mut T {
+        self.get_mut(id).expect("arena id was removed")
+    }
+}
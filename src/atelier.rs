@@ -0,0 +1,312 @@
+use crate::source_provider::SourceProvider;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tower_lsp::lsp_types::Url;
+
+/// Scheme of the uris of classes downloaded from a server, which are never edited.
+///
+/// It is the scheme the VS Code ObjectScript extension uses for read-only server documents.
+pub const READ_ONLY_SCHEME: &str = "isfs-readonly";
+
+/// Returns `true` if `url` is a class downloaded from a server (see `AtelierConfig`).
+pub fn is_read_only_url(url: &Url) -> bool {
+    url.scheme() == READ_ONLY_SCHEME
+}
+
+/// Connection to an InterSystems IRIS server, from the `atelier` initialization option.
+///
+/// Classes the workspace references but doesn't define (e.g. `%Library.Persistent`) are
+/// downloaded through its Atelier REST API and indexed as read-only documents.
+#[derive(Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AtelierConfig {
+    pub host: String,
+    /// Web server port of the instance.
+    pub port: u16,
+    pub namespace: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Connects with `https` instead of `http`.
+    pub https: bool,
+    /// Seconds to wait for each response.
+    pub timeout: u64,
+    /// Directory downloaded classes are saved to, and read back from when the server can't be
+    /// reached.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for AtelierConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 52773,
+            namespace: "USER".to_string(),
+            username: None,
+            password: None,
+            https: false,
+            timeout: 10,
+            cache_dir: None,
+        }
+    }
+}
+
+// the password is left out, since configs are logged
+impl fmt::Debug for AtelierConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtelierConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("namespace", &self.namespace)
+            .field("username", &self.username)
+            .field("https", &self.https)
+            .field("timeout", &self.timeout)
+            .field("cache_dir", &self.cache_dir)
+            .finish()
+    }
+}
+
+impl AtelierConfig {
+    /// Returns the read-only uri a downloaded class is indexed under, e.g.
+    /// `isfs-readonly://localhost/%Library/Persistent.cls?ns=USER`.
+    pub fn document_url(&self, class_name: &str) -> Option<Url> {
+        let mut url = Url::parse(&format!("{READ_ONLY_SCHEME}://{}/", self.host)).ok()?;
+        url.set_path(&format!("/{}.cls", class_name.replace('.', "/")));
+        url.set_query(Some(&format!("ns={}", self.namespace)));
+        Some(url)
+    }
+
+    /// Returns the Atelier API url of the class `class_name`.
+    fn api_url(&self, class_name: &str) -> Option<Url> {
+        let scheme = if self.https { "https" } else { "http" };
+        let mut url = Url::parse(&format!("{scheme}://{}:{}/", self.host, self.port)).ok()?;
+        url.set_path(&format!(
+            "/api/atelier/v1/{}/doc/{class_name}.cls",
+            self.namespace
+        ));
+        Some(url)
+    }
+
+    /// Returns the value of the basic `Authorization` header sending the credentials, if a
+    /// username is set.
+    ///
+    /// Credentials go in a header rather than the url, since urls end up in error messages.
+    pub fn authorization(&self) -> Option<String> {
+        let username = self.username.as_ref()?;
+        let password = self.password.as_deref().unwrap_or_default();
+        let credentials = format!("{username}:{password}");
+        Some(format!("Basic {}", base64_encode(credentials.as_bytes())))
+    }
+}
+
+/// Encodes `bytes` as standard, padded base64.
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (n, &byte)| {
+            group | u32::from(byte) << (16 - 8 * n)
+        });
+        for n in 0..4 {
+            if n <= chunk.len() {
+                encoded.push(ALPHABET[((group >> (18 - 6 * n)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Returns the name of the class at a read-only `url` (see `AtelierConfig::document_url`).
+pub fn class_name_from_url(url: &Url) -> Option<String> {
+    let path = url.path().strip_prefix('/')?.strip_suffix(".cls")?;
+    let path = percent_decode(path);
+    (!path.is_empty()).then(|| path.replace('/', "."))
+}
+
+/// Decodes the `%XX` escapes of a uri path, e.g. the `%25` of a `%Library` package.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Extracts the source of a document from an Atelier `GET doc` response: the lines of
+/// `result.content`, or the errors of `status.errors` if the server reported any.
+pub fn document_content(response: &Value) -> Result<String, String> {
+    let errors: Vec<String> = response["status"]["errors"]
+        .as_array()
+        .map(|errors| {
+            errors
+                .iter()
+                .map(|error| match error.get("error").and_then(Value::as_str) {
+                    Some(message) => message.to_string(),
+                    None => error.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    match &response["result"]["content"] {
+        Value::Array(lines) => Ok(lines
+            .iter()
+            .map(|line| line.as_str().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        Value::String(content) => Ok(content.clone()),
+        _ => Err("the response has no document content".to_string()),
+    }
+}
+
+/// `SourceProvider` downloading classes from an IRIS server through the Atelier REST API.
+///
+/// Only the read-only uris of `AtelierConfig::document_url` can be read; nothing is listed,
+/// since classes are downloaded on demand. Requests block, so use it from a blocking thread.
+pub struct AtelierSourceProvider {
+    config: AtelierConfig,
+    agent: ureq::Agent,
+    /// Set once the server couldn't be reached, after which only the cache is read.
+    unreachable: AtomicBool,
+}
+
+impl fmt::Debug for AtelierSourceProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtelierSourceProvider")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl AtelierSourceProvider {
+    pub fn new(config: AtelierConfig) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(config.timeout))
+            .build();
+        Self {
+            config,
+            agent,
+            unreachable: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the path of the cached copy of the class `class_name`, if caching is enabled.
+    fn cache_path(&self, class_name: &str) -> Option<PathBuf> {
+        let cache_dir = self.config.cache_dir.as_ref()?;
+        Some(
+            cache_dir
+                .join(&self.config.namespace)
+                .join(format!("{class_name}.cls")),
+        )
+    }
+
+    /// Downloads the source of the class `class_name`. Fails with `NotConnected` if the server
+    /// can't be reached, now or by an earlier download.
+    ///
+    /// Errors leave out the url of the request.
+    fn download(&self, class_name: &str) -> io::Result<String> {
+        if self.unreachable.load(Ordering::Relaxed) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "the server couldn't be reached",
+            ));
+        }
+        let api_url = self
+            .config
+            .api_url(class_name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid server address"))?;
+        let mut request = self.agent.get(api_url.as_str());
+        if let Some(authorization) = self.config.authorization() {
+            request = request.set("Authorization", &authorization);
+        }
+        let response = match request.call() {
+            Ok(response) => response,
+            // the body of an error response still reports what went wrong
+            Err(ureq::Error::Status(status, response)) => {
+                let message = response
+                    .into_string()
+                    .ok()
+                    .and_then(|body| serde_json::from_str::<Value>(&body).ok())
+                    .and_then(|body| document_content(&body).err())
+                    .unwrap_or_else(|| format!("status {status}"));
+                return Err(io::Error::other(format!("{class_name}: {message}")));
+            }
+            // the `Display` of a transport error includes the url
+            Err(ureq::Error::Transport(transport)) => {
+                self.unreachable.store(true, Ordering::Relaxed);
+                let message = match transport.message() {
+                    Some(message) => format!("{}: {message}", transport.kind()),
+                    None => transport.kind().to_string(),
+                };
+                return Err(io::Error::new(io::ErrorKind::NotConnected, message));
+            }
+        };
+        let body: Value = serde_json::from_str(&response.into_string()?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        document_content(&body).map_err(io::Error::other)
+    }
+}
+
+impl SourceProvider for AtelierSourceProvider {
+    fn list_files(&self, _root: &Url) -> Vec<Url> {
+        Vec::new()
+    }
+
+    fn read_file(&self, url: &Url) -> io::Result<String> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+        if !is_read_only_url(url) {
+            return Err(invalid("not a server document url"));
+        }
+        let class_name = class_name_from_url(url).ok_or_else(|| invalid("not a class url"))?;
+        let cache_path = self.cache_path(&class_name);
+        match self.download(&class_name) {
+            Ok(code) => {
+                if let Some(path) = cache_path {
+                    let saved = path
+                        .parent()
+                        .map_or(Ok(()), fs::create_dir_all)
+                        .and_then(|_| fs::write(&path, &code));
+                    if let Err(e) = saved {
+                        eprintln!("Warning: Failed to cache class {class_name} at {path:?}: {e}");
+                    }
+                }
+                Ok(code)
+            }
+            // the server is unreachable, so fall back to the copy saved last time
+            Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                match cache_path.and_then(|path| fs::read_to_string(path).ok()) {
+                    Some(code) => {
+                        eprintln!("Warning: Using the cached copy of class {class_name}: {e}");
+                        Ok(code)
+                    }
+                    None => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
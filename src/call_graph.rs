@@ -0,0 +1,176 @@
+use crate::common::{start_of_function, successful_exit, ts_range_to_lsp_range};
+use crate::diagnostics::DIAGNOSTIC_SOURCE;
+use crate::parse_structures::ClassId;
+use crate::rules::{rule_code, rule_code_description, RECURSIVE_METHOD_RULE};
+use std::collections::{HashMap, HashSet};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use tree_sitter::Range;
+
+/// A method in the call graph: the class declaring it and its name.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CallGraphNode {
+    pub class: ClassId,
+    pub method: String,
+}
+
+/// A call from one method to another.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CallEdge {
+    pub caller: CallGraphNode,
+    pub callee: CallGraphNode,
+    /// Range of the call, in the document of the caller's class.
+    pub call_range: Range,
+}
+
+/// Method-level call graph of a workspace, built from the resolved call sites of every class.
+#[derive(Clone, Debug, Default)]
+pub struct CallGraph {
+    /// caller -> calls it makes, in document order
+    pub outgoing: HashMap<CallGraphNode, Vec<CallEdge>>,
+    /// callee -> calls made to it
+    pub incoming: HashMap<CallGraphNode, Vec<CallEdge>>,
+}
+
+impl CallGraph {
+    /// Builds the graph from its edges.
+    pub fn new(edges: Vec<CallEdge>) -> Self {
+        let mut graph = CallGraph::default();
+        for edge in edges {
+            graph
+                .incoming
+                .entry(edge.callee.clone())
+                .or_default()
+                .push(edge.clone());
+            graph
+                .outgoing
+                .entry(edge.caller.clone())
+                .or_default()
+                .push(edge);
+        }
+        graph
+    }
+
+    /// Groups the methods that take part in recursion: each group is a cycle of the graph (a
+    /// strongly connected component), so a method calling itself is a group of one, and methods
+    /// calling each other share a group. Methods that aren't recursive are in no group.
+    pub fn recursive_groups(&self) -> Vec<Vec<CallGraphNode>> {
+        start_of_function("CallGraph", "recursive_groups");
+        // iterative Tarjan, so deep call chains can't overflow the stack
+        let mut nodes: Vec<&CallGraphNode> = self.outgoing.keys().collect();
+        nodes.sort_by(|a, b| (a.class.0, &a.method).cmp(&(b.class.0, &b.method)));
+        let mut index: HashMap<&CallGraphNode, usize> = HashMap::new();
+        let mut low_link: HashMap<&CallGraphNode, usize> = HashMap::new();
+        let mut on_stack: HashSet<&CallGraphNode> = HashSet::new();
+        let mut stack: Vec<&CallGraphNode> = Vec::new();
+        let mut groups = Vec::new();
+        for root in nodes {
+            if index.contains_key(root) {
+                continue;
+            }
+            // (node, position of the next outgoing edge to visit)
+            let mut work = vec![(root, 0)];
+            while let Some(&(node, next)) = work.last() {
+                if next == 0 && !index.contains_key(node) {
+                    index.insert(node, index.len());
+                    low_link.insert(node, index[node]);
+                    stack.push(node);
+                    on_stack.insert(node);
+                }
+                let edges = self.outgoing.get(node).map_or(&[][..], Vec::as_slice);
+                if let Some(edge) = edges.get(next) {
+                    if let Some(top) = work.last_mut() {
+                        top.1 += 1;
+                    }
+                    let callee = &edge.callee;
+                    if !index.contains_key(callee) {
+                        work.push((callee, 0));
+                    } else if on_stack.contains(callee) {
+                        let low = low_link[node].min(index[callee]);
+                        low_link.insert(node, low);
+                    }
+                    continue;
+                }
+                work.pop();
+                if let Some(&(parent, _)) = work.last() {
+                    let low = low_link[parent].min(low_link[node]);
+                    low_link.insert(parent, low);
+                }
+                if low_link[node] != index[node] {
+                    continue;
+                }
+                let mut group = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(member);
+                    group.push(member.clone());
+                    if member == node {
+                        break;
+                    }
+                }
+                if group.len() > 1 || self.calls(node, node) {
+                    group.reverse();
+                    groups.push(group);
+                }
+            }
+        }
+        successful_exit("CallGraph", "recursive_groups");
+        groups
+    }
+
+    /// Returns every recursive method, mapped to the other methods it is mutually recursive with
+    /// (empty for a method that only calls itself).
+    pub fn recursive_methods(&self) -> HashMap<CallGraphNode, Vec<CallGraphNode>> {
+        let mut recursive = HashMap::new();
+        for group in self.recursive_groups() {
+            for node in &group {
+                let others = group.iter().filter(|other| *other != node).cloned();
+                recursive.insert(node.clone(), others.collect());
+            }
+        }
+        recursive
+    }
+
+    /// Returns `true` if `caller` calls `callee` directly.
+    fn calls(&self, caller: &CallGraphNode, callee: &CallGraphNode) -> bool {
+        self.outgoing
+            .get(caller)
+            .is_some_and(|edges| edges.iter().any(|edge| edge.callee == *callee))
+    }
+}
+
+/// A method of a document that takes part in recursion.
+#[derive(Clone, Debug)]
+pub struct RecursiveMethod {
+    pub name: String,
+    /// Range of the method name.
+    pub range: Range,
+    /// Qualified names (`Pkg.Cls.Method`) of the methods it is mutually recursive with; empty
+    /// when it only calls itself.
+    pub cycle: Vec<String>,
+}
+
+/// Reports each recursive method as an informational diagnostic.
+pub fn recursion_diagnostics(content: &str, methods: &[RecursiveMethod]) -> Vec<Diagnostic> {
+    methods
+        .iter()
+        .map(|method| {
+            let message = if method.cycle.is_empty() {
+                format!("Method {:?} calls itself recursively", method.name)
+            } else {
+                format!(
+                    "Method {:?} is mutually recursive with {}",
+                    method.name,
+                    method.cycle.join(", ")
+                )
+            };
+            Diagnostic {
+                range: ts_range_to_lsp_range(content, method.range),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                code: rule_code(RECURSIVE_METHOD_RULE),
+                code_description: rule_code_description(RECURSIVE_METHOD_RULE),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
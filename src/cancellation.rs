@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Tells a computation running on a blocking thread that the request it serves was cancelled,
+/// so it can stop early instead of finishing work nobody will read (see `run_cancellable`).
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Returns a guard cancelling the token when it is dropped.
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+/// Cancels its `CancellationToken` when dropped.
+#[derive(Debug)]
+pub struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Runs `task` on Tokio's blocking thread pool with a token that is cancelled if the returned
+/// future is dropped before `task` finishes.
+///
+/// tower-lsp drops the future of a request when the client sends `$/cancelRequest`, so a
+/// `task` checking the token stops soon after its request is cancelled. Returns `None` if the
+/// task panicked.
+pub async fn run_cancellable<T, F>(task: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&CancellationToken) -> T + Send + 'static,
+{
+    let token = CancellationToken::new();
+    let _guard = token.cancel_on_drop();
+    let handle = tokio::task::spawn_blocking(move || task(&token));
+    match handle.await {
+        Ok(result) => Some(result),
+        Err(join_err) => {
+            eprintln!("run_cancellable spawn_blocking failed: {join_err:?}");
+            None
+        }
+    }
+}
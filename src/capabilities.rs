@@ -0,0 +1,250 @@
+use crate::commands::all_commands;
+use crate::common::position_encoding;
+use crate::completion::trigger_characters;
+use crate::config::{Config, SyncKind};
+use crate::semantic_tokens::legend;
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::{
+    CallHierarchyServerCapability, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
+    CodeLensOptions, CompletionOptions, DocumentOnTypeFormattingOptions, ExecuteCommandOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+    LinkedEditingRangeServerCapabilities, OneOf, SemanticTokensFullOptions, SemanticTokensOptions,
+    SemanticTokensServerCapabilities, ServerCapabilities, SignatureHelpOptions,
+    TextDocumentClientCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions,
+};
+
+/// A config-gated capability that is registered dynamically when the client supports it, so it
+/// can be registered or unregistered as the configuration changes at runtime.
+pub struct DynamicFeature {
+    /// Request method, also used as the registration id.
+    pub method: &'static str,
+    /// Whether a workspace config enables the feature.
+    pub enabled: fn(&Config) -> bool,
+    /// Whether the client supports dynamic registration of the feature.
+    pub client_supports: fn(&TextDocumentClientCapabilities) -> bool,
+    /// Registration options sent with `client/registerCapability`.
+    pub register_options: fn() -> Value,
+}
+
+impl DynamicFeature {
+    /// Returns `true` if the feature should be registered dynamically instead of advertised in
+    /// `initialize`.
+    pub fn is_dynamic(&self, client: Option<&TextDocumentClientCapabilities>) -> bool {
+        client.is_some_and(self.client_supports)
+    }
+
+    /// Returns `true` if any of `configs` enables the feature.
+    pub fn enabled_in(&self, configs: &[Config]) -> bool {
+        configs.iter().any(self.enabled)
+    }
+}
+
+/// Code actions: the debug-write and unresolved-reference quick fixes, `source.fixAll` applying
+/// the debug-write fixes, unused variable removals, and unambiguous imports, and the override
+/// stubs, which are always offered.
+pub const CODE_ACTION_FEATURE: DynamicFeature = DynamicFeature {
+    method: "textDocument/codeAction",
+    enabled: |_| true,
+    client_supports: |client| {
+        client
+            .code_action
+            .as_ref()
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false)
+    },
+    register_options: || {
+        json!({
+            "documentSelector": null,
+            "codeActionKinds": [
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::REFACTOR,
+                CodeActionKind::SOURCE_FIX_ALL,
+            ],
+        })
+    },
+};
+
+/// Code lenses: the class statistics lens, and the method metrics and reference count lenses.
+pub const CODE_LENS_FEATURE: DynamicFeature = DynamicFeature {
+    method: "textDocument/codeLens",
+    enabled: |cfg| cfg.class_stats_lens || cfg.method_metrics || cfg.reference_count_lens,
+    client_supports: |client| {
+        client
+            .code_lens
+            .as_ref()
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false)
+    },
+    register_options: || json!({ "documentSelector": null, "resolveProvider": false }),
+};
+
+/// Returns `true` if `cfg` enables a formatting pass; abbreviation expansion is the only one.
+fn formatting_enabled(cfg: &Config) -> bool {
+    cfg.enable_formatting && cfg.expand_command_abbreviations
+}
+
+/// Document formatting: expands abbreviated commands.
+pub const FORMATTING_FEATURE: DynamicFeature = DynamicFeature {
+    method: "textDocument/formatting",
+    enabled: formatting_enabled,
+    client_supports: |client| {
+        client
+            .formatting
+            .as_ref()
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false)
+    },
+    register_options: || json!({ "documentSelector": null }),
+};
+
+/// Range formatting: expands the abbreviated commands of the selected lines.
+pub const RANGE_FORMATTING_FEATURE: DynamicFeature = DynamicFeature {
+    method: "textDocument/rangeFormatting",
+    enabled: formatting_enabled,
+    client_supports: |client| {
+        client
+            .range_formatting
+            .as_ref()
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false)
+    },
+    register_options: || json!({ "documentSelector": null }),
+};
+
+/// On-type formatting: expands the abbreviated commands of a line once it is completed, indents
+/// the new line, and re-indents a block once its `}` is typed.
+pub const ON_TYPE_FORMATTING_FEATURE: DynamicFeature = DynamicFeature {
+    method: "textDocument/onTypeFormatting",
+    enabled: |cfg| formatting_enabled(cfg) || (cfg.enable_formatting && cfg.indent_on_type),
+    client_supports: |client| {
+        client
+            .on_type_formatting
+            .as_ref()
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false)
+    },
+    register_options: || {
+        json!({
+            "documentSelector": null,
+            "firstTriggerCharacter": "\n",
+            "moreTriggerCharacter": ["}"],
+        })
+    },
+};
+
+/// Hover: method signatures and class summaries, which are always offered, plus the config-gated
+/// heuristic matches for methods called on untyped object references, method metrics,
+/// list/piece element types, and `$SYSTEM` API docs.
+pub const HOVER_FEATURE: DynamicFeature = DynamicFeature {
+    method: "textDocument/hover",
+    enabled: |_| true,
+    client_supports: |client| {
+        client
+            .hover
+            .as_ref()
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false)
+    },
+    register_options: || json!({ "documentSelector": null }),
+};
+
+/// Every `DynamicFeature`.
+pub const DYNAMIC_FEATURES: [DynamicFeature; 6] = [
+    CODE_ACTION_FEATURE,
+    CODE_LENS_FEATURE,
+    FORMATTING_FEATURE,
+    RANGE_FORMATTING_FEATURE,
+    ON_TYPE_FORMATTING_FEATURE,
+    HOVER_FEATURE,
+];
+
+/// Builds the capabilities returned from `initialize`.
+///
+/// Only requests that have a handler in `lsp.rs` are advertised, so clients don't send requests
+/// the server would ignore; when a new handler lands, advertise it here. `configs` holds the
+/// client's initialization options first, followed by the config of every workspace: the sync
+/// kind comes from the client's options, and a config-gated feature is advertised when any
+/// workspace enables it. Features the `client` can register dynamically are left out; they are
+/// registered after `initialized` instead (see `DYNAMIC_FEATURES`).
+pub fn server_capabilities(
+    configs: &[Config],
+    client: Option<&TextDocumentClientCapabilities>,
+) -> ServerCapabilities {
+    let sync_kind = configs
+        .first()
+        .map_or(SyncKind::Incremental, |cfg| cfg.sync_kind);
+    let advertise =
+        |feature: &DynamicFeature| !feature.is_dynamic(client) && feature.enabled_in(configs);
+
+    ServerCapabilities {
+        // the encoding negotiated in `initialize` (see `PositionEncoding::negotiate`)
+        position_encoding: Some(position_encoding().kind()),
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(match sync_kind {
+                    SyncKind::Incremental => TextDocumentSyncKind::INCREMENTAL,
+                    SyncKind::Full => TextDocumentSyncKind::FULL,
+                }),
+                ..Default::default()
+            },
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        document_highlight_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(trigger_characters()),
+            resolve_provider: Some(false),
+            ..Default::default()
+        }),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+            ..Default::default()
+        }),
+        hover_provider: advertise(&HOVER_FEATURE).then_some(HoverProviderCapability::Simple(true)),
+        code_action_provider: advertise(&CODE_ACTION_FEATURE).then(|| {
+            CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![
+                    CodeActionKind::QUICKFIX,
+                    CodeActionKind::REFACTOR,
+                    CodeActionKind::SOURCE_FIX_ALL,
+                ]),
+                ..Default::default()
+            })
+        }),
+        code_lens_provider: advertise(&CODE_LENS_FEATURE).then_some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: all_commands(),
+            ..Default::default()
+        }),
+        // TODO: dotted statement formatting
+        document_formatting_provider: advertise(&FORMATTING_FEATURE).then_some(OneOf::Left(true)),
+        document_range_formatting_provider: advertise(&RANGE_FORMATTING_FEATURE)
+            .then_some(OneOf::Left(true)),
+        document_on_type_formatting_provider: advertise(&ON_TYPE_FORMATTING_FEATURE).then(|| {
+            DocumentOnTypeFormattingOptions {
+                first_trigger_character: "\n".to_string(),
+                more_trigger_character: Some(vec!["}".to_string()]),
+            }
+        }),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: legend(),
+                range: Some(true),
+                full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+}
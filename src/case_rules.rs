@@ -0,0 +1,94 @@
+/// Kinds of ObjectScript identifiers, which differ in whether their case is significant.
+///
+/// Class and member names, as well as local variables, are case-sensitive: `##class(Foo.Bar)`
+/// does not resolve to a class declared as `foo.Bar`. Commands (`Set`, `SET`, `s`) and class or
+/// member keywords (`ProcedureBlock`, `procedureblock`) are not.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IdentifierKind {
+    Class,
+    Method,
+    Property,
+    Parameter,
+    Variable,
+    Command,
+    Keyword,
+}
+
+impl IdentifierKind {
+    /// Returns `true` if two names of this kind only refer to the same thing when their case matches.
+    pub fn is_case_sensitive(&self) -> bool {
+        !matches!(self, IdentifierKind::Command | IdentifierKind::Keyword)
+    }
+
+    /// Returns `true` if names of this kind must be unique regardless of case.
+    ///
+    /// The class compiler rejects two classes, or two members of a class, whose names only differ
+    /// in case, even though references to them are resolved case-sensitively.
+    pub fn is_unique_ignoring_case(&self) -> bool {
+        matches!(
+            self,
+            IdentifierKind::Class
+                | IdentifierKind::Method
+                | IdentifierKind::Property
+                | IdentifierKind::Parameter
+        )
+    }
+}
+
+/// Returns `true` if a reference spelled `referenced` resolves to the declaration `declared`.
+pub fn names_match(kind: IdentifierKind, declared: &str, referenced: &str) -> bool {
+    if kind.is_case_sensitive() {
+        declared == referenced
+    } else {
+        declared.eq_ignore_ascii_case(referenced)
+    }
+}
+
+/// Returns `true` if `a` and `b` are spelled the same apart from letter case.
+pub fn differs_only_in_case(a: &str, b: &str) -> bool {
+    a != b && a.eq_ignore_ascii_case(b)
+}
+
+/// Returns the declared name in `declared` that `referenced` matches only when case is ignored.
+pub fn find_case_mismatch<'a, S: AsRef<str> + 'a>(
+    declared: impl IntoIterator<Item = &'a S>,
+    referenced: &str,
+) -> Option<&'a S> {
+    declared
+        .into_iter()
+        .find(|name| differs_only_in_case(name.as_ref(), referenced))
+}
+
+/// Checks whether renaming `old_name` to `new_name` is allowed, given the other `existing` names
+/// of the same kind in the same namespace (the workspace's classes, or a class' members).
+///
+/// Case-only renames are real renames for case-sensitive kinds (every reference has to be
+/// rewritten), but are rejected for case-insensitive kinds, where they would change nothing.
+/// Returns the reason as an error message if the rename is rejected.
+pub fn check_rename<'a, S: AsRef<str> + 'a>(
+    kind: IdentifierKind,
+    old_name: &str,
+    new_name: &str,
+    existing: impl IntoIterator<Item = &'a S>,
+) -> Result<(), String> {
+    if new_name.is_empty() {
+        return Err("New name must not be empty".to_string());
+    }
+    if names_match(kind, old_name, new_name) {
+        return Err(format!("{new_name:?} is the same name as {old_name:?}"));
+    }
+    let conflict = existing.into_iter().map(AsRef::as_ref).find(|name| {
+        *name != old_name
+            && if kind.is_unique_ignoring_case() {
+                name.eq_ignore_ascii_case(new_name)
+            } else {
+                names_match(kind, name, new_name)
+            }
+    });
+    if let Some(conflict) = conflict {
+        return Err(format!(
+            "{new_name:?} conflicts with existing name {conflict:?}"
+        ));
+    }
+    Ok(())
+}
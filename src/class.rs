@@ -1,17 +1,18 @@
+use crate::class_reference::documatic_markdown;
 use crate::common::{
-    generic_exit_statements, generic_skipping_statements, get_keyword, get_node_children,
-    get_string_at_byte_range, start_of_function, successful_exit,
+    braced_content, doc_comment_above, generic_exit_statements, generic_skipping_statements,
+    get_keyword, get_node_children, get_string_at_byte_range, keyword_flags, keyword_value,
+    split_member_declaration, start_of_function, successful_exit,
 };
+use crate::diagnostics::{byte_range, MemberKind};
 use crate::method::initial_build_method;
 use crate::parse_structures::{
-    Class, Language, Method, MethodType, PrivateMethodId, PublicMethodId,
+    Class, ClassMemberDefinition, ClassParameter, ClassProperty, ClassStatements, ClassXData,
+    Language, MemberDefinitionId, Method, MethodType, ParameterId, PrivateMethodId, PropertyId,
+    PublicMethodId, XDataId,
 };
 use std::collections::HashMap;
 use tree_sitter::{Node, Range};
-/*
-For Simplicity, I am not including the logic for parsing
-include or include_gen files. This feature can be added later.
- */
 impl Class {
     /// Creates a new `Class` with the given name and empty semantic state.
     ///
@@ -20,16 +21,25 @@ impl Class {
         Self {
             name,
             imports: Vec::new(),
+            includes: Vec::new(),
+            include_generators: Vec::new(),
             inherited_classes: Vec::new(),
+            superclass_names: Vec::new(),
             inheritance_direction: "left".to_string(),
             is_procedure_block: None,
             default_language: None,
+            declared_keywords: (None, None),
+            sql_table_name: None,
             private_methods: HashMap::new(),
             public_methods: HashMap::new(),
             private_properties: HashMap::new(),
             public_properties: HashMap::new(),
             parameters: HashMap::new(),
+            xdata_blocks: HashMap::new(),
+            member_definitions: HashMap::new(),
             method_calls: Vec::new(),
+            generated_by: false,
+            description: None,
             active: true,
         }
     }
@@ -41,30 +51,39 @@ impl Class {
     pub fn clear(&mut self, class_name: String, active: bool) {
         self.name = class_name;
         self.imports = Vec::new();
+        self.includes = Vec::new();
+        self.include_generators = Vec::new();
         self.inherited_classes = Vec::new();
+        self.superclass_names = Vec::new();
         self.inheritance_direction = "left".to_string();
         self.is_procedure_block = None;
         self.default_language = None;
+        self.declared_keywords = (None, None);
+        self.sql_table_name = None;
         self.private_methods = HashMap::new();
         self.public_methods = HashMap::new();
         self.private_properties = HashMap::new();
         self.public_properties = HashMap::new();
         self.parameters = HashMap::new();
+        self.xdata_blocks = HashMap::new();
+        self.member_definitions = HashMap::new();
         self.method_calls = Vec::new();
+        self.generated_by = false;
+        self.description = None;
         self.active = active;
     }
 
     /// Performs the first-pass parse of a class definition node into this `Class`.
     ///
-    /// Extracts class keywords (ProcedureBlock, Language, InheritanceDirection) and collects
-    /// method definitions from the class body. Does not compute imports, include files, or
-    /// inherited/transitive semantics; those are handled later.
+    /// Extracts class keywords (ProcedureBlock, Language, InheritanceDirection, SqlTableName) and
+    /// collects method, property, parameter, and XData definitions from the class body. Does not
+    /// compute imports, include files, or inherited/transitive semantics; those are handled later.
     ///
-    /// Returns the parsed methods and their source ranges.
-    pub fn initial_build(&mut self, node: Node, content: &str) -> Vec<(Method, Range)> {
+    /// Returns the parsed members; the caller assigns their ids.
+    pub fn initial_build(&mut self, node: Node, content: &str) -> ClassStatements {
         start_of_function("Class", "initial_build");
         let class_children = get_node_children(node);
-        let mut methods = Vec::new();
+        let mut statements = ClassStatements::default();
         if class_children.len() < 2 {
             eprintln!(
                 "initial_build: expected class_definition node, got kind={} named_children={}",
@@ -72,11 +91,21 @@ impl Class {
                 class_children.len()
             );
             generic_exit_statements("Class", "initial_build");
-            return Vec::new();
+            return statements;
         }
+        self.description = doc_comment_above(content, class_children[1].start_position().row);
         // skip keyword_class and class_name
         for node in class_children.iter().skip(2) {
             match node.kind() {
+                "class_extends" => {
+                    // the first child is the `Extends` keyword
+                    self.superclass_names = get_node_children(*node)
+                        .iter()
+                        .skip(1)
+                        .filter_map(|superclass| content.get(superclass.byte_range()))
+                        .map(str::to_string)
+                        .collect();
+                }
                 "class_keywords" => {
                     self.initial_build_class_keywords(node.clone(), content);
                 }
@@ -84,6 +113,40 @@ impl Class {
                     let children = get_node_children(node.clone());
                     // each child is a class statement
                     for child in children {
+                        match child
+                            .named_child(0)
+                            .map(|statement_type| statement_type.kind())
+                        {
+                            Some("property") => {
+                                statements
+                                    .properties
+                                    .extend(Self::handle_class_statement_property(child, content));
+                                continue;
+                            }
+                            Some("parameter") => {
+                                statements
+                                    .parameters
+                                    .extend(Self::handle_class_statement_parameter(child, content));
+                                continue;
+                            }
+                            Some("xdata") => {
+                                statements
+                                    .xdata_blocks
+                                    .extend(Self::handle_class_statement_xdata(child, content));
+                                continue;
+                            }
+                            Some("method" | "classmethod") | None => {}
+                            Some(kind) => {
+                                if let Some(kind) = MemberKind::from_statement_kind(kind) {
+                                    statements.member_definitions.extend(
+                                        Self::handle_class_statement_member(child, content, kind),
+                                    );
+                                } else {
+                                    eprintln!("Unimplemented class statement {:?}", kind);
+                                }
+                                continue;
+                            }
+                        }
                         let Some((method, method_range)) =
                             self.handle_class_statement_method(child, content)
                         else {
@@ -98,7 +161,7 @@ impl Class {
                             continue;
                         };
 
-                        methods.push((method, method_range));
+                        statements.methods.push((method, method_range));
                     }
                 }
                 _ => {
@@ -109,7 +172,94 @@ impl Class {
             }
         }
         successful_exit("Class", "initial_build");
-        methods
+        statements
+    }
+
+    /// Parses a `class_statement` node holding a `Property Name As Type [ keywords ];`
+    /// declaration into a `ClassProperty`, ranged over the property name.
+    ///
+    /// The type and the `Private` keyword are read from the text following the name.
+    fn handle_class_statement_property(node: Node, content: &str) -> Option<ClassProperty> {
+        start_of_function("Class", "handle_class_statement_property");
+        let Some((name_node, declaration)) = member_declaration(node, content) else {
+            generic_exit_statements("Class", "handle_class_statement_property");
+            return None;
+        };
+        let (property_type, keywords, _) = split_member_declaration(declaration);
+        let is_private = keyword_flags(keywords)
+            .into_iter()
+            .any(|(keyword, value)| keyword.eq_ignore_ascii_case("Private") && value);
+        let sql_field_name = keyword_value(keywords, "SqlFieldName").map(str::to_string);
+        successful_exit("Class", "handle_class_statement_property");
+        Some(ClassProperty {
+            name: content.get(name_node.byte_range())?.to_string(),
+            property_type,
+            is_public: !is_private,
+            sql_field_name,
+            range: name_node.range(),
+        })
+    }
+
+    /// Parses a `class_statement` node holding a `Parameter NAME As Type [ keywords ] = value;`
+    /// declaration into a `ClassParameter`, ranged over the parameter name.
+    fn handle_class_statement_parameter(node: Node, content: &str) -> Option<ClassParameter> {
+        start_of_function("Class", "handle_class_statement_parameter");
+        let Some((name_node, declaration)) = member_declaration(node, content) else {
+            generic_exit_statements("Class", "handle_class_statement_parameter");
+            return None;
+        };
+        let (property_type, _, default_argument_value) = split_member_declaration(declaration);
+        successful_exit("Class", "handle_class_statement_parameter");
+        Some(ClassParameter {
+            name: content.get(name_node.byte_range())?.to_string(),
+            property_type,
+            default_argument_value,
+            range: name_node.range(),
+        })
+    }
+
+    /// Parses a `class_statement` node holding an `XData Name [ MimeType = ... ] { ... }` block
+    /// into a `ClassXData`, ranged over the block name, with the range of its raw content.
+    fn handle_class_statement_xdata(node: Node, content: &str) -> Option<ClassXData> {
+        start_of_function("Class", "handle_class_statement_xdata");
+        let Some((name_node, declaration)) = member_declaration(node, content) else {
+            generic_exit_statements("Class", "handle_class_statement_xdata");
+            return None;
+        };
+        let (_, keywords, _) = split_member_declaration(declaration);
+        let Some((start, end)) = braced_content(declaration) else {
+            eprintln!("Failed to find the content of XData block {:?}", node);
+            generic_exit_statements("Class", "handle_class_statement_xdata");
+            return None;
+        };
+        let offset = name_node.end_byte();
+        successful_exit("Class", "handle_class_statement_xdata");
+        Some(ClassXData {
+            name: content.get(name_node.byte_range())?.to_string(),
+            mime_type: keyword_value(keywords, "MimeType").map(str::to_string),
+            range: name_node.range(),
+            content_range: byte_range(content, offset + start, offset + end),
+        })
+    }
+
+    /// Parses a `class_statement` node holding an index, foreign key, trigger, query, projection,
+    /// or storage definition of `kind` into a `ClassMemberDefinition`, ranged over its name.
+    fn handle_class_statement_member(
+        node: Node,
+        content: &str,
+        kind: MemberKind,
+    ) -> Option<ClassMemberDefinition> {
+        start_of_function("Class", "handle_class_statement_member");
+        let Some((name_node, _)) = member_declaration(node, content) else {
+            generic_exit_statements("Class", "handle_class_statement_member");
+            return None;
+        };
+        successful_exit("Class", "handle_class_statement_member");
+        Some(ClassMemberDefinition {
+            name: content.get(name_node.byte_range())?.to_string(),
+            kind,
+            range: name_node.range(),
+        })
     }
 
     /// Parses a `class_statement` node and returns the corresponding `Method` and its `Range`.
@@ -158,7 +308,8 @@ impl Class {
     /// Parses class-level keywords and updates `is_procedure_block`, `default_language`,
     /// and `inheritance_direction` accordingly.
     ///
-    /// Currently recognizes ProcedureBlock, Language (tsql/objectscript), and Inheritance (right).
+    /// Currently recognizes ProcedureBlock, Language (tsql/objectscript), Inheritance (right), and
+    /// GeneratedBy.
     /// Unrecognized or unsupported keyword values are logged and skipped.
     fn initial_build_class_keywords(&mut self, node: Node, content: &str) {
         start_of_function("Class", "initial_build_class_keywords");
@@ -166,6 +317,7 @@ impl Class {
         let procedure_block = get_keyword("class_keyword", "procedure");
         let language_keyword = get_keyword("class_keyword", "language");
         let inheritance_keyword = get_keyword("class_keyword", "inheritance");
+        let generated_by_keyword = get_keyword("class_keyword", "generatedby");
         // each node here is a class_keyword
         for node in class_keywords_children.iter() {
             let Some(keyword) = node.named_child(0) else {
@@ -199,6 +351,8 @@ impl Class {
                             self.default_language = Some(Language::TSql);
                         } else if text.eq_ignore_ascii_case("objectscript") {
                             self.default_language = Some(Language::Objectscript);
+                        } else if text.eq_ignore_ascii_case("python") {
+                            self.default_language = Some(Language::Python);
                         } else {
                             if let Some(s) = get_string_at_byte_range(content, keyword.byte_range())
                             {
@@ -240,11 +394,53 @@ impl Class {
                         continue;
                     }
                 }
+            } else if keyword.kind() == generated_by_keyword {
+                self.generated_by = true;
+            } else if let Some(table_name) = content
+                .get(keyword.byte_range())
+                .and_then(|text| keyword_value(text, "SqlTableName"))
+            {
+                self.sql_table_name = Some(table_name.to_string());
             }
         }
+        self.declared_keywords = (self.is_procedure_block, self.default_language.clone());
         successful_exit("Class", "initial_build_class_keywords");
     }
 
+    /// Renders the summary shown when hovering the class name: its package, superclasses,
+    /// class-wide keywords, and description (rendered from Documatic markup).
+    pub fn hover_summary(&self) -> String {
+        let mut value = format!("**Class** `{}`", self.name);
+        if let Some((package, _)) = self.name.rsplit_once('.') {
+            value.push_str(&format!("\n\nPackage `{package}`"));
+        }
+        if !self.superclass_names.is_empty() {
+            let names: Vec<String> = self
+                .superclass_names
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect();
+            value.push_str(&format!("\n\nExtends {}", names.join(", ")));
+        }
+        let mut keywords = Vec::new();
+        match self.is_procedure_block {
+            Some(true) => keywords.push("ProcedureBlock".to_string()),
+            Some(false) => keywords.push("Not ProcedureBlock".to_string()),
+            None => {}
+        }
+        if let Some(language) = &self.default_language {
+            keywords.push(format!("Language = {}", language.keyword()));
+        }
+        if !keywords.is_empty() {
+            value.push_str(&format!("\n\n`[ {} ]`", keywords.join(", ")));
+        }
+        if let Some(description) = &self.description {
+            value.push_str("\n\n---\n\n");
+            value.push_str(&documatic_markdown(description));
+        }
+        value
+    }
+
     /// Returns the `PublicMethodId` for `method_name`, if this class declares it as public.
     ///
     /// Logs and returns `None` if the method is not present in `public_methods`.
@@ -265,6 +461,41 @@ impl Class {
         }
     }
 
+    /// Returns the `ParameterId` for `parameter_name`, if this class declares it.
+    pub fn get_parameter_id(&self, parameter_name: &str) -> Option<&ParameterId> {
+        self.parameters.get(parameter_name)
+    }
+
+    /// Returns the `XDataId` for `xdata_name`, if this class declares an XData block by that name.
+    pub fn get_xdata_id(&self, xdata_name: &str) -> Option<&XDataId> {
+        self.xdata_blocks.get(xdata_name)
+    }
+
+    /// Returns the `MemberDefinitionId` of the index, foreign key, trigger, query, projection, or
+    /// storage definition of `kind` named `name`, if this class declares it.
+    pub fn get_member_definition_id(
+        &self,
+        kind: MemberKind,
+        name: &str,
+    ) -> Option<&MemberDefinitionId> {
+        self.member_definitions.get(&(kind, name.to_string()))
+    }
+
+    /// Returns the `PropertyId` for `property_name`, if this class declares it, public or private.
+    pub fn get_property_id(&self, property_name: &str) -> Option<&PropertyId> {
+        self.public_properties
+            .get(property_name)
+            .or_else(|| self.private_properties.get(property_name))
+    }
+
+    /// Returns the name of the SQL table this class projects: `SqlTableName` if given, otherwise
+    /// the short class name.
+    pub fn sql_table_name(&self) -> &str {
+        self.sql_table_name
+            .as_deref()
+            .unwrap_or_else(|| self.name.rsplit('.').next().unwrap_or(&self.name))
+    }
+
     /// Returns the `PrivateMethodId` for `method_name`, if this class declares it as private.
     ///
     /// Logs and returns `None` if the method is not present in `private_methods`.
@@ -285,3 +516,60 @@ impl Class {
         }
     }
 }
+
+impl ClassProperty {
+    /// Renders the declaration of this property, e.g. `Property Total As %Numeric [ Private ]`.
+    pub fn signature(&self) -> String {
+        let mut signature = format!("Property {}", self.name);
+        if let Some(property_type) = &self.property_type {
+            signature.push_str(&format!(" As {property_type}"));
+        }
+        if !self.is_public {
+            signature.push_str(" [ Private ]");
+        }
+        signature
+    }
+
+    /// Returns the name of the SQL column this property projects: `SqlFieldName` if given,
+    /// otherwise the property name.
+    pub fn sql_field_name(&self) -> &str {
+        self.sql_field_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl ClassParameter {
+    /// Renders the declaration of this parameter, e.g. `Parameter VERSION As %String = "1.2"`.
+    pub fn signature(&self) -> String {
+        let mut signature = format!("Parameter {}", self.name);
+        if let Some(parameter_type) = &self.property_type {
+            signature.push_str(&format!(" As {parameter_type}"));
+        }
+        if let Some(value) = &self.default_argument_value {
+            signature.push_str(&format!(" = {value}"));
+        }
+        signature
+    }
+}
+
+/// Finds the name node of the member declared by the `class_statement` `node` and the text of
+/// its declaration following the name, up to the end of the statement.
+fn member_declaration<'a>(node: Node<'a>, content: &'a str) -> Option<(Node<'a>, &'a str)> {
+    let Some(statement_type) = node.named_child(0) else {
+        eprintln!("Failed to get statement type from node : {:?}", node);
+        return None;
+    };
+    // child(1) is the name, or a definition starting with the name
+    let mut name_node = statement_type.named_child(1);
+    while let Some(definition) = name_node.filter(|n| n.kind().ends_with("_definition")) {
+        name_node = definition.named_child(0);
+    }
+    let Some(name_node) = name_node else {
+        eprintln!("Failed to get member name from node {:?}", statement_type);
+        return None;
+    };
+    let Some(declaration) = content.get(name_node.end_byte()..statement_type.end_byte()) else {
+        eprintln!("Failed to get text of member {:?}", statement_type);
+        return None;
+    };
+    Some((name_node, declaration))
+}
@@ -0,0 +1,564 @@
+use crate::common::{doc_comment_above, word_at_offset};
+
+/// A method of a `ReferenceClass`.
+#[derive(Debug)]
+pub struct ReferenceMethod {
+    pub name: &'static str,
+    /// The declaration, e.g. `Method %Save(related As %Integer = 1) As %Status`.
+    pub signature: &'static str,
+    /// Documatic markup (see `documatic_markdown`).
+    pub doc: &'static str,
+}
+
+/// A system class of the bundled class reference snapshot.
+#[derive(Debug)]
+pub struct ReferenceClass {
+    /// The full class name, e.g. `%Library.Persistent`.
+    pub name: &'static str,
+    /// The primary superclass, whose methods are inherited.
+    pub superclass: Option<&'static str>,
+    /// Documatic markup (see `documatic_markdown`).
+    pub doc: &'static str,
+    pub methods: &'static [ReferenceMethod],
+}
+
+impl ReferenceClass {
+    /// Returns the method declared (not inherited) as `name`; method names are case-sensitive.
+    pub fn method(&self, name: &str) -> Option<&'static ReferenceMethod> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    /// Renders the class hover, laid out like `Class::hover_summary`.
+    pub fn hover(&self) -> String {
+        let mut value = format!("**Class** `{}`", self.name);
+        if let Some((package, _)) = self.name.rsplit_once('.') {
+            value.push_str(&format!("\n\nPackage `{package}`"));
+        }
+        if let Some(superclass) = self.superclass {
+            value.push_str(&format!("\n\nExtends `{superclass}`"));
+        }
+        value.push_str("\n\n---\n\n");
+        value.push_str(&documatic_markdown(self.doc));
+        value
+    }
+}
+
+/// Snapshot of the InterSystems class reference for the most used system classes, documenting
+/// them when no server is configured (see `AtelierConfig`) or it can't be reached.
+pub const CLASS_REFERENCE: [ReferenceClass; 10] = [
+    ReferenceClass {
+        name: "%Library.RegisteredObject",
+        superclass: Some("%Library.Base"),
+        doc: "The <class>%RegisteredObject</class> class is the base class of every object \
+            class: it provides the in-memory behavior of objects, such as creation with \
+            <method>%New</method> and validation.",
+        methods: &[
+            ReferenceMethod {
+                name: "%ClassName",
+                signature: "ClassMethod %ClassName(fullname As %Boolean = 0) As %String",
+                doc: "Returns the name of the class. If <var>fullname</var> is true, the \
+                    name includes the package.",
+            },
+            ReferenceMethod {
+                name: "%ConstructClone",
+                signature: "Method %ConstructClone(deep As %Integer = 0, ByRef cloned As \
+                    %String, location As %String) As %RegisteredObject",
+                doc: "Clones the object. If <var>deep</var> is 1, the objects it references \
+                    are cloned too; otherwise they are shared by the clone.",
+            },
+            ReferenceMethod {
+                name: "%IsA",
+                signature: "ClassMethod %IsA(isclass As %String) As %Integer",
+                doc: "Returns 1 if the class is <var>isclass</var> or one of its \
+                    subclasses, and 0 otherwise.",
+            },
+            ReferenceMethod {
+                name: "%New",
+                signature: "ClassMethod %New(initvalue As %RawString) As %ObjectHandle",
+                doc: "Creates a new instance of the class and returns it, or the empty string \
+                    if it couldn't be created.<p><var>initvalue</var> is passed to the \
+                    <method>%OnNew</method> callback, if the class defines one.",
+            },
+            ReferenceMethod {
+                name: "%ValidateObject",
+                signature: "Method %ValidateObject(force As %Integer = 0) As %Status",
+                doc: "Validates the property values of the object, such as required \
+                    properties and the parameters of their types, and returns a \
+                    <class>%Status</class> describing the first errors found.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%Library.SwizzleObject",
+        superclass: Some("%Library.RegisteredObject"),
+        doc: "The <class>%SwizzleObject</class> class is the abstract base class of the \
+            objects that can be stored: persistent and serial objects.",
+        methods: &[],
+    },
+    ReferenceClass {
+        name: "%Library.Persistent",
+        superclass: Some("%Library.SwizzleObject"),
+        doc: "The <class>%Persistent</class> class is the base class of objects stored in \
+            the database.<p>Each persistent class is also projected as an SQL table, whose \
+            rows are the stored objects, identified by their <var>ID</var>.",
+        methods: &[
+            ReferenceMethod {
+                name: "%DeleteId",
+                signature: "ClassMethod %DeleteId(id As %String, concurrency As %Integer = \
+                    -1) As %Status",
+                doc: "Deletes the stored object with ID <var>id</var> from the database.",
+            },
+            ReferenceMethod {
+                name: "%ExistsId",
+                signature: "ClassMethod %ExistsId(id As %String) As %Boolean",
+                doc: "Returns true if an object with ID <var>id</var> is stored in the \
+                    extent of the class.",
+            },
+            ReferenceMethod {
+                name: "%Id",
+                signature: "Method %Id() As %String",
+                doc: "Returns the ID of the object, or the empty string if it hasn't been \
+                    saved yet.",
+            },
+            ReferenceMethod {
+                name: "%OpenId",
+                signature: "ClassMethod %OpenId(id As %String, concurrency As %Integer = -1, \
+                    ByRef sc As %Status) As %ObjectHandle",
+                doc: "Loads the object with ID <var>id</var> from the database and returns \
+                    it, or the empty string if it doesn't exist; the error is returned in \
+                    <var>sc</var>.",
+            },
+            ReferenceMethod {
+                name: "%Reload",
+                signature: "Method %Reload() As %Status",
+                doc: "Reloads the property values of the object from the database, \
+                    discarding its unsaved changes.",
+            },
+            ReferenceMethod {
+                name: "%Save",
+                signature: "Method %Save(related As %Integer = 1) As %Status",
+                doc: "Stores the object in the database, after validating it.<p>If \
+                    <var>related</var> is true, the objects it references are saved too, \
+                    all in one transaction.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%Library.SerialObject",
+        superclass: Some("%Library.SwizzleObject"),
+        doc: "The <class>%SerialObject</class> class is the base class of objects stored \
+            as part of the persistent objects that reference them, rather than on their \
+            own.",
+        methods: &[],
+    },
+    ReferenceClass {
+        name: "%Library.DynamicObject",
+        superclass: Some("%Library.DynamicAbstractObject"),
+        doc: "A dynamic object holds JSON-like data: an unordered set of key/value pairs. \
+            It is usually created with a JSON literal, e.g. \
+            <example>set obj = {\"name\": \"Smith\"}</example>",
+        methods: &[
+            ReferenceMethod {
+                name: "%FromJSON",
+                signature: "ClassMethod %FromJSON(str) As %DynamicAbstractObject",
+                doc: "Parses the JSON text of <var>str</var>, a string or a stream, into a \
+                    new dynamic object or array.",
+            },
+            ReferenceMethod {
+                name: "%Get",
+                signature: "Method %Get(key As %String, default As %Any, type As %String) \
+                    As %Any",
+                doc: "Returns the value of <var>key</var>, or <var>default</var> if the \
+                    object doesn't have it.",
+            },
+            ReferenceMethod {
+                name: "%IsDefined",
+                signature: "Method %IsDefined(key As %String) As %Boolean",
+                doc: "Returns true if the object has a value for <var>key</var>.",
+            },
+            ReferenceMethod {
+                name: "%Remove",
+                signature: "Method %Remove(key As %String) As %DynamicAbstractObject",
+                doc: "Removes <var>key</var> and its value from the object.",
+            },
+            ReferenceMethod {
+                name: "%Set",
+                signature: "Method %Set(key As %String, value As %Any, type As %String) As \
+                    %DynamicAbstractObject",
+                doc: "Sets the value of <var>key</var>, converting it to the JSON type \
+                    <var>type</var> if one is given, and returns the object.",
+            },
+            ReferenceMethod {
+                name: "%Size",
+                signature: "Method %Size() As %Integer",
+                doc: "Returns the number of key/value pairs of the object.",
+            },
+            ReferenceMethod {
+                name: "%ToJSON",
+                signature: "Method %ToJSON(outstrm As %Stream.Object) As %String",
+                doc: "Serializes the object as JSON text, written to <var>outstrm</var> if \
+                    it is given and returned otherwise.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%Library.DynamicArray",
+        superclass: Some("%Library.DynamicAbstractObject"),
+        doc: "A dynamic array holds JSON-like data: an ordered list of values, indexed \
+            from 0. It is usually created with a JSON literal, e.g. \
+            <example>set list = [1, \"two\", {}]</example>",
+        methods: &[
+            ReferenceMethod {
+                name: "%Get",
+                signature: "Method %Get(key As %Integer, default As %Any, type As %String) \
+                    As %Any",
+                doc: "Returns the value at index <var>key</var>, or <var>default</var> if \
+                    the index is out of range.",
+            },
+            ReferenceMethod {
+                name: "%Pop",
+                signature: "Method %Pop() As %Any",
+                doc: "Removes the last value of the array and returns it.",
+            },
+            ReferenceMethod {
+                name: "%Push",
+                signature: "Method %Push(value As %Any, type As %String) As %DynamicArray",
+                doc: "Appends <var>value</var> to the array and returns the array.",
+            },
+            ReferenceMethod {
+                name: "%Size",
+                signature: "Method %Size() As %Integer",
+                doc: "Returns the number of values of the array.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%Library.File",
+        superclass: Some("%Library.RegisteredObject"),
+        doc: "The <class>%File</class> class provides access to the files and directories \
+            of the server's file system.",
+        methods: &[
+            ReferenceMethod {
+                name: "CreateDirectoryChain",
+                signature: "ClassMethod CreateDirectoryChain(name As %String, ByRef return \
+                    As %Integer) As %Boolean",
+                doc: "Creates the directory <var>name</var> and any missing parent \
+                    directories, returning true on success.",
+            },
+            ReferenceMethod {
+                name: "Delete",
+                signature: "ClassMethod Delete(filename As %String, ByRef return As \
+                    %Integer) As %Boolean",
+                doc: "Deletes the file <var>filename</var>, returning true on success; the \
+                    operating system error code is returned in <var>return</var>.",
+            },
+            ReferenceMethod {
+                name: "DirectoryExists",
+                signature: "ClassMethod DirectoryExists(filename As %String) As %Boolean",
+                doc: "Returns true if the directory <var>filename</var> exists.",
+            },
+            ReferenceMethod {
+                name: "Exists",
+                signature: "ClassMethod Exists(filename As %String, ByRef return As \
+                    %Integer) As %Boolean",
+                doc: "Returns true if the file <var>filename</var> exists.",
+            },
+            ReferenceMethod {
+                name: "NormalizeFilename",
+                signature: "ClassMethod NormalizeFilename(filename As %String, directory As \
+                    %String = \"\") As %String",
+                doc: "Returns the full path of <var>filename</var>, relative to \
+                    <var>directory</var> or to the current directory.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%SQL.Statement",
+        superclass: Some("%Library.RegisteredObject"),
+        doc: "The <class>%SQL.Statement</class> class prepares and executes dynamic SQL \
+            statements, e.g. <example>set stmt = ##class(%SQL.Statement).%New()\nset sc = \
+            stmt.%Prepare(\"SELECT Name FROM Sample.Person\")\nset rs = stmt.%Execute()\
+            </example>",
+        methods: &[
+            ReferenceMethod {
+                name: "%ExecDirect",
+                signature: "ClassMethod %ExecDirect(ByRef statement As %SQL.Statement, sql \
+                    As %String, args...) As %SQL.StatementResult",
+                doc: "Prepares and executes <var>sql</var> in one call, with <var>args</var> \
+                    as the values of its <b>?</b> parameters.",
+            },
+            ReferenceMethod {
+                name: "%Execute",
+                signature: "Method %Execute(args...) As %SQL.StatementResult",
+                doc: "Executes the prepared statement with <var>args</var> as the values of \
+                    its <b>?</b> parameters. Check the <var>%SQLCODE</var> property of the \
+                    result for errors.",
+            },
+            ReferenceMethod {
+                name: "%Prepare",
+                signature: "Method %Prepare(statement As %String, checkPriv As %Boolean = 1) \
+                    As %Status",
+                doc: "Prepares the SQL text <var>statement</var>, which may be an array of \
+                    lines passed by reference, for execution.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%Net.HttpRequest",
+        superclass: Some("%Library.RegisteredObject"),
+        doc: "The <class>%Net.HttpRequest</class> class sends HTTP requests. Set the \
+            <property>Server</property> property, then call <method>Get</method>, \
+            <method>Post</method>, or <method>Send</method>; the response is then in the \
+            <property>HttpResponse</property> property.",
+        methods: &[
+            ReferenceMethod {
+                name: "Get",
+                signature: "Method Get(location As %String = \"\", test As %Integer = 0, \
+                    reset As %Boolean = 1) As %Status",
+                doc: "Sends a GET request for <var>location</var>.",
+            },
+            ReferenceMethod {
+                name: "Post",
+                signature: "Method Post(location As %String = \"\", test As %Integer = 0, \
+                    reset As %Boolean = 1) As %Status",
+                doc: "Sends a POST request for <var>location</var>, with the \
+                    <property>EntityBody</property> stream as its body.",
+            },
+            ReferenceMethod {
+                name: "Send",
+                signature: "Method Send(type As %String, location As %String, test As \
+                    %Integer = 0, reset As %Boolean = 1) As %Status",
+                doc: "Sends a request of the HTTP method <var>type</var> for \
+                    <var>location</var>.",
+            },
+            ReferenceMethod {
+                name: "SetHeader",
+                signature: "Method SetHeader(name As %String, value As %String) As %Status",
+                doc: "Sets the HTTP header <var>name</var> of the request.",
+            },
+        ],
+    },
+    ReferenceClass {
+        name: "%Exception.AbstractException",
+        superclass: Some("%Library.SystemBase"),
+        doc: "The <class>%Exception.AbstractException</class> class is the base class of \
+            the exceptions thrown with <b>throw</b> and caught with <b>catch</b>.",
+        methods: &[
+            ReferenceMethod {
+                name: "AsStatus",
+                signature: "Method AsStatus() As %Status",
+                doc: "Converts the exception to a <class>%Status</class>.",
+            },
+            ReferenceMethod {
+                name: "DisplayString",
+                signature: "Method DisplayString(level As %Integer = 0) As %String",
+                doc: "Returns the exception as text, for display.",
+            },
+            ReferenceMethod {
+                name: "Log",
+                signature: "Method Log() As %Status",
+                doc: "Records the exception in the application error log.",
+            },
+        ],
+    },
+];
+
+/// Returns the class of the bundled class reference named `name` (a full name, see
+/// `qualified_class_name`).
+pub fn reference_class(name: &str) -> Option<&'static ReferenceClass> {
+    CLASS_REFERENCE.iter().find(|class| class.name == name)
+}
+
+/// A reference to a system class, or to a method called on one, found by
+/// `system_class_reference_at`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SystemClassReference {
+    /// The class as written, e.g. `%Persistent`.
+    pub class_name: String,
+    /// The method called, for `##class(%Name).Method`.
+    pub method: Option<String>,
+    /// Byte range of the class name or of the method name.
+    pub range: std::ops::Range<usize>,
+}
+
+/// Finds the system (`%`) class named at byte `offset` of `content`, or the method called on one
+/// with `##class(%Name).Method`, where the cursor is on the method name.
+///
+/// This is lexical, like `label_reference_at`; whether the class exists is left to the caller.
+pub fn system_class_reference_at(content: &str, offset: usize) -> Option<SystemClassReference> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '%' || c == '.';
+    let std::ops::Range { start, end } = word_at_offset(content, offset, is_name);
+    let text = content[start..end].trim_end_matches('.');
+    let end = start + text.len();
+    if let Some(method) = text.strip_prefix('.') {
+        // `##class(%Name).Method`
+        let before = content[..start].strip_suffix(')')?;
+        let qualifier = before.to_ascii_lowercase().rfind("##class(")? + "##class(".len();
+        let class_name = before[qualifier..].trim();
+        let is_class_name = class_name.starts_with('%')
+            && class_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '%' || c == '.');
+        if !is_class_name || method.is_empty() || method.contains('.') {
+            return None;
+        }
+        return Some(SystemClassReference {
+            class_name: class_name.to_string(),
+            method: Some(method.to_string()),
+            range: end - method.len()..end,
+        });
+    }
+    text.starts_with('%').then(|| SystemClassReference {
+        class_name: text.to_string(),
+        method: None,
+        range: start..end,
+    })
+}
+
+/// Finds the declaration of the method `method_name` in the class source `content`, returning
+/// it without its keywords and body (e.g. `ClassMethod %ExistsId(id As %String) As %Boolean`)
+/// and its `///` description.
+///
+/// This is lexical: the declaration is the line starting with `Method` or `ClassMethod` and the
+/// name.
+pub fn method_declaration(content: &str, method_name: &str) -> Option<(String, Option<String>)> {
+    content.lines().enumerate().find_map(|(row, line)| {
+        let line = line.trim_start();
+        let (keyword, rest) = line.split_once(' ')?;
+        let is_method = ["method", "classmethod"]
+            .iter()
+            .any(|method| keyword.eq_ignore_ascii_case(method));
+        let name_end = rest.find('(')?;
+        if !is_method || rest[..name_end].trim() != method_name {
+            return None;
+        }
+        let end = [line.find(" ["), line.find('{')]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(line.len());
+        Some((
+            line[..end].trim_end().to_string(),
+            doc_comment_above(content, row),
+        ))
+    })
+}
+
+/// Renders Documatic markup, the HTML subset of class reference descriptions, as Markdown.
+///
+/// Paragraphs, line breaks, emphasis, lists, and `<example>` blocks are kept; class, method,
+/// property, and argument names become code spans; other tags are dropped, keeping their text.
+/// Text without markup is returned unchanged.
+pub fn documatic_markdown(text: &str) -> String {
+    let mut markdown = String::new();
+    let mut in_example = false;
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        markdown.push_str(&decode_entities(&rest[..open]));
+        let after = &rest[open + 1..];
+        let tag = after
+            .find(['<', '>'])
+            .filter(|&close| after.as_bytes()[close] == b'>')
+            .map(|close| &after[..close])
+            .filter(|tag| {
+                tag.trim_start_matches('/')
+                    .starts_with(|c: char| c.is_ascii_alphabetic())
+            });
+        let Some(tag) = tag else {
+            // a `<` that doesn't start a tag, e.g. `a < b`
+            markdown.push('<');
+            rest = after;
+            continue;
+        };
+        rest = &after[tag.len() + 1..];
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if in_example && !(closing && name == "example") {
+            markdown.push_str(&format!("<{tag}>"));
+            continue;
+        }
+        match name.as_str() {
+            "p" | "ul" | "ol" | "div" | "blockquote" => paragraph_break(&mut markdown),
+            "br" => markdown.push_str("  \n"),
+            "b" | "strong" => markdown.push_str("**"),
+            "i" | "em" => markdown.push('_'),
+            "var" | "class" | "method" | "property" | "parameter" | "query" | "code" | "tt" => {
+                markdown.push('`')
+            }
+            "li" if !closing => {
+                if !markdown.is_empty() && !markdown.ends_with('\n') {
+                    markdown.push('\n');
+                }
+                markdown.push_str("- ");
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                if closing {
+                    markdown.push_str("**");
+                    paragraph_break(&mut markdown);
+                } else {
+                    paragraph_break(&mut markdown);
+                    markdown.push_str("**");
+                }
+            }
+            "example" if closing => {
+                if !markdown.ends_with('\n') {
+                    markdown.push('\n');
+                }
+                markdown.push_str("```");
+                paragraph_break(&mut markdown);
+                in_example = false;
+            }
+            "example" => {
+                let language = tag_attribute(tag, "language")
+                    .unwrap_or("objectscript")
+                    .to_ascii_lowercase();
+                paragraph_break(&mut markdown);
+                markdown.push_str(&format!("```{language}\n"));
+                in_example = true;
+                // the code starts on the line after the tag
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+            }
+            _ => {}
+        }
+    }
+    markdown.push_str(&decode_entities(rest));
+    while markdown.contains("\n\n\n") {
+        markdown = markdown.replace("\n\n\n", "\n\n");
+    }
+    markdown.trim().to_string()
+}
+
+/// Ends the current Markdown paragraph, unless it is empty.
+fn paragraph_break(markdown: &mut String) {
+    if !markdown.is_empty() && !markdown.ends_with("\n\n") {
+        let trimmed = markdown.trim_end_matches([' ', '\n']).len();
+        markdown.truncate(trimmed);
+        markdown.push_str("\n\n");
+    }
+}
+
+/// Returns the value of the attribute `name` of the HTML `tag` (the text between `<` and `>`).
+fn tag_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.to_ascii_lowercase().find(&format!("{name}="))? + name.len() + 1;
+    let value = &tag[start..];
+    match value.strip_prefix(['"', '\'']) {
+        Some(quoted) => quoted.split(['"', '\'']).next(),
+        None => value.split_whitespace().next(),
+    }
+}
+
+/// Decodes the HTML entities Documatic descriptions use.
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,64 @@
+use tower_lsp::lsp_types::{CodeLens, Command, Position, Range};
+
+/// Summary counts shown by the class statistics code lens.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ClassStats {
+    /// Public and private methods declared in the class.
+    pub methods: usize,
+    /// Public and private properties declared in the class.
+    pub properties: usize,
+    /// Lines in the class document.
+    pub lines: usize,
+    /// Active classes that list this class in their `Extends` clause.
+    pub subclasses: usize,
+}
+
+impl ClassStats {
+    /// Renders the lens title, e.g. `12 methods · 8 properties · 430 lines · 3 subclasses`.
+    pub fn title(&self) -> String {
+        [
+            plural(self.methods, "method", "methods"),
+            plural(self.properties, "property", "properties"),
+            plural(self.lines, "line", "lines"),
+            plural(self.subclasses, "subclass", "subclasses"),
+        ]
+        .join(" · ")
+    }
+
+    /// Builds the (display-only) code lens shown on the class header at `position`.
+    pub fn to_code_lens(self, position: Position) -> CodeLens {
+        CodeLens {
+            range: Range {
+                start: position,
+                end: position,
+            },
+            command: Some(Command {
+                title: self.title(),
+                command: String::new(),
+                arguments: None,
+            }),
+            data: None,
+        }
+    }
+}
+
+/// Builds the (display-only) code lens showing the `count` call sites of the method declared at
+/// `position`, e.g. `3 references`.
+pub fn reference_count_lens(count: usize, position: Position) -> CodeLens {
+    CodeLens {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        command: Some(Command {
+            title: plural(count, "reference", "references"),
+            command: String::new(),
+            arguments: None,
+        }),
+        data: None,
+    }
+}
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    format!("{count} {}", if count == 1 { singular } else { plural })
+}
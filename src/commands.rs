@@ -0,0 +1,111 @@
+use serde_json::Value;
+use tower_lsp::lsp_types::Url;
+
+/// Returns a JSON dump of a document's `ScopeTree` (scopes, definitions, references).
+///
+/// Arguments: `[uri]`, the document to inspect.
+pub const SHOW_SCOPES: &str = "objectscript.showScopes";
+
+/// Records a snapshot of the project's symbols.
+///
+/// Arguments: `[uri, label?]`, any document or folder of the workspace and an optional label.
+pub const TAKE_SNAPSHOT: &str = "objectscript.takeSnapshot";
+
+/// Lists the retained project index snapshots.
+///
+/// Arguments: `[uri]`, any document or folder of the workspace.
+pub const LIST_SNAPSHOTS: &str = "objectscript.listSnapshots";
+
+/// Diffs two project index snapshots: which symbols were added, removed, or changed.
+///
+/// Arguments: `[uri, from?, to?]`; the two most recent snapshots are compared by default.
+pub const DIFF_SNAPSHOTS: &str = "objectscript.diffSnapshots";
+
+/// Applies every auto-fixable diagnostic in every document of the workspace, one edit per file,
+/// reporting work-done progress while it runs.
+///
+/// Arguments: `[uri]`, any document or folder of the workspace.
+pub const FIX_ALL_IN_WORKSPACE: &str = "objectscript.fixAllInWorkspace";
+
+/// Exports the project's classes and class members as a ctags file.
+///
+/// Arguments: `[uri, path?]`; the tags are written to `path` (relative to the workspace root)
+/// when it is given, and returned as text otherwise.
+pub const EXPORT_TAGS: &str = "objectscript.exportTags";
+
+/// Reports how often each class and public method is referenced across the workspace, from the
+/// resolved call sites and `Extends` lists, most referenced first, to find hot APIs and dead
+/// code before refactoring.
+///
+/// Arguments: `[uri, format?, path?]`; `format` is `json` (default) or `csv`. The report is
+/// written to `path` (relative to the workspace root) when it is given, and returned otherwise.
+pub const EXPORT_USAGE: &str = "objectscript.exportUsage";
+
+/// Returns the Markdown documentation of a lint rule; diagnostics link to it through their
+/// `codeDescription`.
+///
+/// Arguments: `[ruleId?]`; every rule is documented when it is omitted.
+pub const RULE_DOCS: &str = "objectscript.ruleDocs";
+
+/// Runs the server's self-check (grammar and node-types versions, bundled `%SYSTEM` stubs,
+/// writable directories, and the negotiated position encoding) and returns a plain-text report
+/// to attach to bug reports.
+///
+/// Arguments: none.
+pub const DOCTOR: &str = "objectscript.doctor";
+
+/// Renames a package and its sub-packages across the workspace (e.g. `Legacy` to
+/// `MyApp.Legacy`): class headers, references, and `Import` lists are rewritten and the class
+/// files are moved. Nothing is applied while any renamed class would collide with an existing
+/// one.
+///
+/// Arguments: `[uri, from, to, dryRun?]`; with `dryRun` the affected files are returned without
+/// applying anything.
+pub const RENAME_PACKAGE: &str = "objectscript.renamePackage";
+
+/// Creates the file of a class the workspace references but doesn't define, with an empty class
+/// body, next to the referencing class file, and indexes it.
+///
+/// Arguments: `[uri, className]`, the referencing document and the full name of the new class.
+pub const CREATE_CLASS: &str = "objectscript.createClass";
+
+/// Dumps what the server has indexed as JSON: the classes of the global semantic model with
+/// their members, the class table, and the override index.
+///
+/// Arguments: `[uri, path?]`; the dump is written to `path` (relative to the workspace root)
+/// when it is given, and returned otherwise.
+pub const DUMP_INDEX: &str = "objectscript.dumpIndex";
+
+/// Every command advertised through `ServerCapabilities::execute_command_provider`.
+pub fn all_commands() -> Vec<String> {
+    [
+        SHOW_SCOPES,
+        TAKE_SNAPSHOT,
+        LIST_SNAPSHOTS,
+        DIFF_SNAPSHOTS,
+        FIX_ALL_IN_WORKSPACE,
+        EXPORT_TAGS,
+        EXPORT_USAGE,
+        RULE_DOCS,
+        DOCTOR,
+        RENAME_PACKAGE,
+        CREATE_CLASS,
+        DUMP_INDEX,
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+/// Parses the argument at `index` of a `workspace/executeCommand` request as a document uri.
+///
+/// Accepts either a plain uri string or an object with a `uri` field (as sent for a
+/// `TextDocumentIdentifier`).
+pub fn uri_argument(arguments: &[Value], index: usize) -> Option<Url> {
+    let argument = arguments.get(index)?;
+    let uri = match argument {
+        Value::String(uri) => uri.as_str(),
+        Value::Object(map) => map.get("uri")?.as_str()?,
+        _ => return None,
+    };
+    Url::parse(uri).ok()
+}
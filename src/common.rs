@@ -1,14 +1,21 @@
+use crate::case_rules::{names_match, IdentifierKind};
+use crate::document::TextBuffer;
+use crate::interner::Name;
 use crate::override_index::OverrideIndex;
 use crate::parse_structures::{
-    ClassId, CodeMode, Language, MethodCallSite, ReturnType, UnresolvedCallSite, VarType,
+    ClassId, CodeMode, Language, MemberVisibility, MethodCallSite, MethodType, ReturnType,
+    UnresolvedCallSite, VarType,
 };
 use crate::scope_structures::{ClassGlobalSymbolId, ScopeId};
 use crate::scope_tree::ScopeTree;
+use parking_lot::RwLock;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::ops::Range as CoreRange;
-use tower_lsp::lsp_types::{Position, Range as LspRange, Url};
-use tree_sitter::{Node, Point, Range as TsRange, Range, Tree};
+use tower_lsp::lsp_types::{
+    Position, PositionEncodingKind, Range as LspRange, TextDocumentContentChangeEvent, Url,
+};
+use tree_sitter::{InputEdit, Node, Point, Range as TsRange, Range, Tree};
 
 pub fn print_statements_exit_method_overrides_fn(
     method_name: &str,
@@ -26,14 +33,76 @@ pub fn print_statements_exit_method_overrides_fn(
     eprintln!();
 }
 
+/// The unit LSP `Position::character` counts columns in, agreed with the client in
+/// `initialize`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum PositionEncoding {
+    Utf8,
+    /// The default, which every client supports.
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+static POSITION_ENCODING: RwLock<PositionEncoding> = RwLock::new(PositionEncoding::Utf16);
+
+impl PositionEncoding {
+    /// Picks the first of the client's `general.positionEncodings` the server supports, or
+    /// `None` if it supports none of them. Clients that don't send any only support UTF-16.
+    pub fn negotiate(client_encodings: Option<&[PositionEncodingKind]>) -> Option<Self> {
+        let Some(encodings) = client_encodings else {
+            return Some(Self::Utf16);
+        };
+        encodings.iter().find_map(|kind| match kind.as_str() {
+            "utf-8" => Some(Self::Utf8),
+            "utf-16" => Some(Self::Utf16),
+            "utf-32" => Some(Self::Utf32),
+            _ => None,
+        })
+    }
+
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    /// Number of units `c` takes up.
+    pub fn char_len(self, c: char) -> usize {
+        match self {
+            Self::Utf8 => c.len_utf8(),
+            Self::Utf16 => c.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// Number of units `text` takes up, e.g. to step back over a typed prefix.
+    pub fn units(self, text: &str) -> u32 {
+        text.chars().map(|c| self.char_len(c) as u32).sum()
+    }
+}
+
+/// Sets the encoding negotiated with the client, used by every position conversion.
+pub fn set_position_encoding(encoding: PositionEncoding) {
+    *POSITION_ENCODING.write() = encoding;
+}
+
+pub fn position_encoding() -> PositionEncoding {
+    *POSITION_ENCODING.read()
+}
+
+/// Converts a Tree-sitter `Point` (row + UTF-8 byte column) into an LSP `Position` whose
+/// `character` is counted in the negotiated `position_encoding`.
 pub fn point_to_lsp_position(text: &str, p: Point) -> Position {
     // start_of_function("COMMON (no struct)", "point_to_lsp_position");
     let starts = line_starts(text);
     let (line_start, _line_end_incl, line_end_excl) = line_bounds(text, &starts, p.row);
 
-    // If point is on EOF row, map to UTF-16 character 0
+    // If point is on EOF row, map to character 0
     if line_start == text.len() && line_end_excl == text.len() {
-        eprintln!("Info: Point is on EOF row, mapping to UTF-16 character 0");
+        eprintln!("Info: Point is on EOF row, mapping to character 0");
         return Position {
             line: p.row as u32,
             character: 0,
@@ -46,8 +115,9 @@ pub fn point_to_lsp_position(text: &str, p: Point) -> Position {
 
     let line = &text[line_start..line_end_excl];
 
+    let encoding = position_encoding();
     let mut bytes = 0usize;
-    let mut utf16_units = 0u32;
+    let mut units = 0u32;
 
     for ch in line.chars() {
         let ch_bytes = ch.len_utf8();
@@ -55,7 +125,7 @@ pub fn point_to_lsp_position(text: &str, p: Point) -> Position {
             break;
         }
         bytes += ch_bytes;
-        utf16_units += ch.len_utf16() as u32;
+        units += encoding.char_len(ch) as u32;
         if bytes == target_col {
             break;
         }
@@ -63,13 +133,13 @@ pub fn point_to_lsp_position(text: &str, p: Point) -> Position {
 
     // eprintln!(
     //     "Position is: line: {}, character: {}",
-    //     p.row as u32, utf16_units
+    //     p.row as u32, units
     // );
     // successful_exit("COMMON (no struct)", "point_to_lsp_position");
 
     Position {
         line: p.row as u32,
-        character: utf16_units,
+        character: units,
     }
 }
 
@@ -162,14 +232,15 @@ fn line_bounds(text: &str, starts: &[usize], row: usize) -> (usize, usize, usize
     (start, end_incl, end_excl)
 }
 
-/// Converts an LSP `Position` (line + UTF-16 character offset) into a Tree-sitter `Point`
+/// Converts an LSP `Position` (line + character offset) into a Tree-sitter `Point`
 /// (row + UTF-8 byte column) for the given source `text`.
 ///
-/// LSP positions encode `character` as a count of UTF-16 code units from the start of the line.
+/// LSP positions encode `character` as a count of units of the negotiated `position_encoding`
+/// (UTF-16 code units by default) from the start of the line.
 /// Tree-sitter points encode `column` as a byte offset (UTF-8) from the start of the line.
 /// This function bridges those two coordinate systems by:
 /// 1) locating the requested line bounds in `text`, and
-/// 2) walking the line’s Unicode scalar values to convert a UTF-16 offset into a UTF-8 byte column.
+/// 2) walking the line’s Unicode scalar values to convert the offset into a UTF-8 byte column.
 ///
 /// If `position.line` is at or beyond EOF (as determined by `line_bounds`), this returns an
 /// EOF-like point with `{ row, column: 0 }`.
@@ -195,7 +266,8 @@ pub fn position_to_point(text: &str, position: Position) -> Point {
 
     let line = &text[line_start..line_end_excl];
 
-    // Convert UTF-16 units to byte offset within this line
+    // Convert encoding units to byte offset within this line
+    let encoding = position_encoding();
     let mut remaining = position.character as usize;
     let mut col_bytes = 0usize;
 
@@ -203,11 +275,11 @@ pub fn position_to_point(text: &str, position: Position) -> Point {
         if remaining == 0 {
             break;
         }
-        let u16 = ch.len_utf16();
-        if remaining < u16 {
-            break; // don't split a surrogate pair
+        let len = encoding.char_len(ch);
+        if remaining < len {
+            break; // don't split a surrogate pair or a UTF-8 sequence
         }
-        remaining -= u16;
+        remaining -= len;
         col_bytes += ch.len_utf8();
     }
     // successful_exit("COMMON (no struct)", "position_to_point");
@@ -221,7 +293,7 @@ pub fn position_to_point(text: &str, position: Position) -> Point {
 ///
 /// Tree-sitter ranges are expressed as start/end `Point`s where the `column` is a UTF-8 byte
 /// offset within the line. LSP ranges are expressed as start/end `Position`s where the
-/// `character` is a UTF-16 code-unit offset within the line.
+/// `character` is an offset in units of the negotiated `position_encoding`.
 ///
 /// This function performs the conversion by translating both `start_point` and `end_point`
 /// via `point_to_lsp_position`.
@@ -292,6 +364,33 @@ pub fn advance_point(mut row: usize, mut column: usize, changed_text: &str) -> P
     Point { row, column }
 }
 
+/// Applies the content changes of one `textDocument/didChange` notification to `text`, in order.
+///
+/// The shape of each change is detected individually: clients that only support full sync send
+/// changes without a range, and each of those replaces the whole text. Returns the `InputEdit`s
+/// of the ranged changes so the previous tree can be edited for an incremental reparse, or
+/// `None` if any change replaced the whole text (the old tree no longer matches the new text).
+pub fn apply_content_changes(
+    text: &mut TextBuffer,
+    changes: &[TextDocumentContentChangeEvent],
+) -> Option<Vec<InputEdit>> {
+    // start_of_function("COMMON (no struct)", "apply_content_changes");
+    let mut edits = Some(Vec::new());
+    for change in changes {
+        let Some(range) = change.range else {
+            *text = TextBuffer::from(change.text.as_str());
+            edits = None;
+            continue;
+        };
+        let input_edit = text.edit(range.start, range.end, change.text.as_str());
+        if let Some(edits) = &mut edits {
+            edits.push(input_edit);
+        }
+    }
+    // successful_exit("COMMON (no struct)", "apply_content_changes");
+    edits
+}
+
 /// Returns a Vec of all named children nodes for a given Tree Sitter Node.
 pub fn get_node_children(node: Node) -> Vec<Node> {
     // start_of_function("COMMON (no struct)", "get_node_children");
@@ -324,6 +423,36 @@ pub fn find_class_definition(root: Node) -> Option<Node> {
     }
 }
 
+/// Returns the `method_definition` nodes of the class defined below `root`, with their method type.
+///
+/// Each `class_statement` of a `class_body` has the statement type (`method`, `classmethod`, ...)
+/// as child 0, whose child 1 is the definition.
+pub fn method_definition_nodes(root: Node) -> Vec<(Node, MethodType)> {
+    let Some(class_definition) = find_class_definition(root) else {
+        return Vec::new();
+    };
+    let mut definitions = Vec::new();
+    for class_body in get_node_children(class_definition) {
+        if class_body.kind() != "class_body" {
+            continue;
+        }
+        for statement in get_node_children(class_body) {
+            let Some(statement_type) = statement.named_child(0) else {
+                continue;
+            };
+            let method_type = match statement_type.kind() {
+                "method" => MethodType::InstanceMethod,
+                "classmethod" => MethodType::ClassMethod,
+                _ => continue,
+            };
+            if let Some(method_definition) = statement_type.named_child(1) {
+                definitions.push((method_definition, method_type));
+            }
+        }
+    }
+    definitions
+}
+
 /// Extracts the class name from a parsed Tree-sitter root `node`.
 ///
 /// Finds the `class_definition` node (via `find_class_definition`), then reads the class name
@@ -369,6 +498,54 @@ pub fn get_string_at_byte_range(content: &str, range: CoreRange<usize>) -> Optio
     Some(s.to_string())
 }
 
+/// Returns the byte range of the word at byte `offset` of `content`: the run of characters
+/// matching `is_name_char` that contains or ends at `offset`, empty if there is none.
+///
+/// `offset` is clamped to `content` and moved back to a character boundary, and the word is
+/// scanned by whole characters, so the range never splits a multi-byte character.
+pub fn word_at_offset(
+    content: &str,
+    offset: usize,
+    is_name_char: impl Fn(char) -> bool,
+) -> CoreRange<usize> {
+    let mut offset = offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let start = offset
+        - content[..offset]
+            .chars()
+            .rev()
+            .take_while(|&c| is_name_char(c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+    let end = offset
+        + content[offset..]
+            .chars()
+            .take_while(|&c| is_name_char(c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+    start..end
+}
+
+/// Returns the `///` description comment on the lines directly above line `row` of `content`,
+/// without the `///` markers, or `None` if there is none.
+pub fn doc_comment_above(content: &str, row: usize) -> Option<String> {
+    let lines: Vec<&str> = content.lines().take(row).collect();
+    let comment: Vec<&str> = lines
+        .iter()
+        .rev()
+        .map(|line| line.trim_start())
+        .take_while(|line| line.starts_with("///"))
+        .map(|line| {
+            let text = &line[3..];
+            text.strip_prefix(' ').unwrap_or(text).trim_end()
+        })
+        .collect();
+    let description = comment.into_iter().rev().collect::<Vec<_>>().join("\n");
+    (!description.trim().is_empty()).then(|| description.trim().to_string())
+}
+
 /// Infers a `VarType` from the first child of an `expr_atom`-like node.
 ///
 /// Matches common literal/identifier forms and extracts names from `content` using the node’s
@@ -696,12 +873,61 @@ pub fn point_in_range(pos: Point, start: Point, end: Point) -> bool {
     false
 }
 
+/// Returns how deep `workspace` is (in path segments) if it contains `document`, or `None`.
+///
+/// `file://` uris are compared as file system paths. Other schemes (e.g. `isfs://`) must share
+/// the scheme and authority with the workspace, and the document path must start with every
+/// path segment of the workspace path.
+pub fn workspace_depth(workspace: &Url, document: &Url) -> Option<usize> {
+    if let (Ok(ws_path), Ok(doc_path)) = (workspace.to_file_path(), document.to_file_path()) {
+        return doc_path
+            .starts_with(&ws_path)
+            .then(|| ws_path.components().count());
+    }
+    if workspace.scheme() != document.scheme() || workspace.authority() != document.authority() {
+        return None;
+    }
+    let ws_segments: Vec<&str> = workspace
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let mut doc_segments = document.path_segments()?;
+    for ws_segment in &ws_segments {
+        if doc_segments.next() != Some(*ws_segment) {
+            return None;
+        }
+    }
+    Some(ws_segments.len())
+}
+
 /// Returns `true` if `node` is treated as a scope boundary in `.cls` parsing.
 /// Returns `false` otherwise.
 pub fn cls_is_scope_node(node: Node) -> bool {
     node.kind() == "classmethod" || node.kind() == "method"
 }
 
+/// Returns the method name node of the method call on an object reference (`obj.Method()`)
+/// containing `node`, if `node` is inside that name.
+///
+/// Relative-dot calls (`..Method()`) are not returned: their class is the current class.
+pub fn oref_method_name_node(node: Node) -> Option<Node> {
+    let mut current = node;
+    // the name is an identifier (possibly wrapped) directly below the `oref_method` node
+    for _ in 0..4 {
+        if current.kind() == "oref_method" {
+            if current.parent()?.kind() == "relative_dot_method" {
+                return None;
+            }
+            let name = current.named_child(0)?;
+            let inside =
+                name.start_byte() <= node.start_byte() && node.end_byte() <= name.end_byte();
+            return inside.then_some(name);
+        }
+        current = current.parent()?;
+    }
+    None
+}
+
 /// Walks up the syntax tree from `node` to find the enclosing `method_definition` and returns
 /// that method’s name (sliced from `content`).
 ///
@@ -964,6 +1190,142 @@ pub fn build_method_calls(
     out
 }
 
+/// Parses the `Internal` and `ServerOnly` keywords out of a member keyword list
+/// (`[ Internal, Not ServerOnly, Private ]`); `ServerOnly = 1` and `Internal = 0` are accepted
+/// too.
+///
+/// This works on the text of the list rather than the keyword nodes, so it applies to every kind
+/// of member, including those whose keywords the grammar doesn't name.
+pub(crate) fn member_visibility(keywords: &str) -> MemberVisibility {
+    let mut visibility = MemberVisibility::default();
+    for (name, value) in keyword_flags(keywords) {
+        if name.eq_ignore_ascii_case("Internal") {
+            visibility.internal = value;
+        } else if name.eq_ignore_ascii_case("ServerOnly") {
+            visibility.server_only = value;
+        }
+    }
+    visibility
+}
+
+/// Splits the text of a member keyword list (`[ Internal, Not ServerOnly, MaxLen = 0 ]`) into
+/// the keyword names and whether each is set: `Not Name` and `Name = 0` clear it, any other
+/// value sets it.
+pub(crate) fn keyword_flags(keywords: &str) -> Vec<(&str, bool)> {
+    keyword_list(keywords)
+        .into_iter()
+        .map(|part| match part.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim() != "0"),
+            None => match part.get(..4) {
+                Some(not) if not.eq_ignore_ascii_case("not ") => (part[4..].trim(), false),
+                _ => (part, true),
+            },
+        })
+        .collect()
+}
+
+/// Returns the value given to the keyword `name` in the text of a member keyword list, e.g.
+/// `application/json` for `MimeType` in `[ MimeType = "application/json" ]`, without quotes.
+pub(crate) fn keyword_value<'a>(keywords: &'a str, name: &str) -> Option<&'a str> {
+    keyword_list(keywords).into_iter().find_map(|part| {
+        let (keyword, value) = part.split_once('=')?;
+        names_match(IdentifierKind::Keyword, name, keyword.trim())
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Splits the text of a member keyword list into its trimmed keywords, at the commas outside
+/// strings and parentheses.
+fn keyword_list(keywords: &str) -> Vec<&str> {
+    let keywords = keywords
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut parts = Vec::new();
+    let mut part_start = 0;
+    for (i, c) in keywords.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '{' if !in_string => depth += 1,
+            ')' | '}' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                parts.push(keywords[part_start..i].trim());
+                part_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(keywords[part_start..].trim());
+    parts
+}
+
+/// Splits what follows the name in a property or parameter declaration, e.g.
+/// ` As list Of %String(MAXLEN = 50) [ Private, Required ];` or ` As %String [ Final ] = "a";`,
+/// into the declared type (`list Of %String`, without its parameters), the text of the keyword
+/// list (`[ Private, Required ]`, empty if there is none), and the value assigned after `=`.
+pub(crate) fn split_member_declaration(
+    declaration: &str,
+) -> (Option<String>, &str, Option<String>) {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut type_end = None;
+    let mut keywords = "";
+    let mut keywords_end = 0;
+    let mut value = None;
+    for (i, c) in declaration.char_indices() {
+        if i < keywords_end {
+            continue;
+        }
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '{' if !in_string => depth += 1,
+            ')' | '}' if !in_string => depth -= 1,
+            '[' if !in_string && depth == 0 => {
+                type_end.get_or_insert(i);
+                let list = &declaration[i..];
+                keywords = list.find(']').map_or(list, |close| &list[..=close]);
+                keywords_end = i + keywords.len();
+            }
+            '=' if !in_string && depth == 0 => {
+                type_end.get_or_insert(i);
+                let rest = declaration[i + 1..].trim_end();
+                value = Some(rest.strip_suffix(';').unwrap_or(rest).trim().to_string());
+                break;
+            }
+            ';' if !in_string && depth == 0 => {
+                type_end.get_or_insert(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    // the type parameters, e.g. `(MAXLEN = 50)`, aren't part of the type name
+    let type_declaration = declaration[..type_end.unwrap_or(declaration.len())]
+        .split('(')
+        .next()
+        .unwrap_or_default();
+    let words: Vec<&str> = type_declaration.split_whitespace().collect();
+    let member_type = match words.split_first() {
+        Some((keyword, type_words))
+            if keyword.eq_ignore_ascii_case("As") && !type_words.is_empty() =>
+        {
+            Some(type_words.join(" "))
+        }
+        _ => None,
+    };
+    (member_type, keywords, value)
+}
+
+/// Finds the content between the outermost braces of `text`, e.g. the body of an XData block,
+/// as the byte offsets just after its `{` and at its `}`.
+pub(crate) fn braced_content(text: &str) -> Option<(usize, usize)> {
+    let start = text.find('{')? + 1;
+    let end = text.rfind('}').filter(|end| *end >= start)?;
+    Some((start, end))
+}
+
 /// Parses a `method_keywords` node and extracts semantic flags for a method.
 ///
 /// Returns a tuple of:
@@ -1197,7 +1559,7 @@ pub(crate) fn generic_skipping_statements(
 /// Any call that cannot be resolved remains with `callee_symbol = None`. The returned call sites
 /// retain the original `call_range` and `arg_ranges` for later navigation/highlighting.
 pub fn build_method_calls_from_unresolved(
-    classes_map: HashMap<String, ClassId>,
+    classes_map: HashMap<Name, ClassId>,
     idx: OverrideIndex,
     unresolved_call_site: Vec<UnresolvedCallSite>,
     method_name: String,
@@ -1207,10 +1569,10 @@ pub fn build_method_calls_from_unresolved(
         .into_iter()
         .map(|call| {
             let callee_symbol = classes_map
-                .get(&call.callee_class)
+                .get(call.callee_class.as_str())
                 .copied()
                 .and_then(|callee_class_id| idx.effective_public_methods.get(&callee_class_id))
-                .and_then(|tbl| tbl.get(&call.callee_method).copied());
+                .and_then(|tbl| tbl.get(call.callee_method.as_str()).copied());
 
             MethodCallSite {
                 caller_method: method_name.clone(),
@@ -1225,3 +1587,28 @@ pub fn build_method_calls_from_unresolved(
     successful_exit("COMMON (no struct)", "build_method_calls_from_unresolved");
     new_sites
 }
+
+/// Re-resolves the `callee_symbol` of already built `MethodCallSite`s against a new override index.
+///
+/// Used for classes whose source did not change but whose callees may have been re-indexed
+/// (e.g. a superclass was edited and its `PublicMethodId`s were reassigned).
+///
+/// Returns `true` if any call site's resolved symbol changed.
+pub fn resolve_method_call_sites(
+    classes_map: &HashMap<Name, ClassId>,
+    idx: &OverrideIndex,
+    call_sites: &mut [MethodCallSite],
+) -> bool {
+    let mut changed = false;
+    for site in call_sites.iter_mut() {
+        let callee_symbol = classes_map
+            .get(site.callee_class.as_str())
+            .and_then(|callee_class_id| idx.effective_public_methods.get(callee_class_id))
+            .and_then(|tbl| tbl.get(site.callee_method.as_str()).copied());
+        if callee_symbol != site.callee_symbol {
+            site.callee_symbol = callee_symbol;
+            changed = true;
+        }
+    }
+    changed
+}
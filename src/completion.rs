@@ -0,0 +1,395 @@
+use crate::common::position_encoding;
+use crate::config::MethodInsertStyle;
+use crate::diagnostics::is_simple_variable_name;
+use crate::parse_structures::{ClassParameter, ClassProperty, Method, MethodType};
+use std::collections::BTreeMap;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, InsertTextFormat,
+    Position, Range, TextEdit,
+};
+
+/// Characters that make a completion request worth answering even when triggered automatically.
+///
+/// `(` opens `##class(`, where every workspace class is offered, `$` starts an intrinsic
+/// function, and `#` a class parameter reference (`..#NAME`).
+pub fn trigger_characters() -> Vec<String> {
+    vec![
+        ".".to_string(),
+        "(".to_string(),
+        "$".to_string(),
+        "#".to_string(),
+    ]
+}
+
+/// Returns the dotted class name being typed at the end of `line_before_cursor`, if the cursor is
+/// in a class-name position.
+///
+/// Recognized positions are `##class(`, `Extends` (including inside a parenthesized list), and the
+/// type after `As` / `Of`, e.g. `Property p As MyApp.` or `Set x = ##class(MyApp.Da`.
+pub fn class_name_prefix(line_before_cursor: &str) -> Option<&str> {
+    let name_start = line_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '.' || *c == '%')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line_before_cursor.len());
+    let prefix = &line_before_cursor[name_start..];
+    let before = line_before_cursor[..name_start]
+        .trim_end()
+        .to_ascii_lowercase();
+
+    let in_class_position = before.ends_with("##class(")
+        || ends_with_word(&before, "extends")
+        || ends_with_word(&before, "as")
+        || ends_with_word(&before, "of")
+        || ((before.ends_with('(') || before.ends_with(','))
+            && before.rsplit_once("extends").is_some_and(|(_, list)| {
+                list.trim_start().starts_with('(') && !list.contains(')')
+            }));
+    in_class_position.then_some(prefix)
+}
+
+/// Returns `true` if `text` ends with the whole word `word`.
+fn ends_with_word(text: &str, word: &str) -> bool {
+    let Some(rest) = text.strip_suffix(word) else {
+        return false;
+    };
+    rest.chars()
+        .last()
+        .is_none_or(|c| !(c.is_ascii_alphanumeric() || c == '%'))
+}
+
+/// Completes the next package segment or class name for the partially typed `prefix`.
+///
+/// For `MyApp.Da`, every class under `MyApp.` contributes either its next package segment (e.g.
+/// `Data` for `MyApp.Data.Person`) or its class name (e.g. `Dashboard` for `MyApp.Dashboard`).
+/// Items only replace the segment after the last `.`, which ends at the cursor `position`.
+pub fn package_segment_completions<'a, S: AsRef<str> + 'a>(
+    prefix: &str,
+    class_names: impl IntoIterator<Item = &'a S>,
+    position: Position,
+) -> Vec<CompletionItem> {
+    let (package, partial) = match prefix.rsplit_once('.') {
+        Some((package, partial)) => (Some(package), partial),
+        None => (None, prefix),
+    };
+    // segment -> (is class, full name), sorted so clients get a stable order
+    let mut segments: BTreeMap<&str, (bool, &str)> = BTreeMap::new();
+    for class_name in class_names {
+        let class_name = class_name.as_ref();
+        let rest = match package {
+            Some(package) => {
+                let Some(rest) = class_name
+                    .strip_prefix(package)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                else {
+                    continue;
+                };
+                rest
+            }
+            None => class_name,
+        };
+        if !rest
+            .to_ascii_lowercase()
+            .starts_with(&partial.to_ascii_lowercase())
+        {
+            continue;
+        }
+        match rest.split_once('.') {
+            Some((segment, _)) => {
+                segments.entry(segment).or_insert((false, class_name));
+            }
+            None => {
+                // a class and a package can share a name; prefer showing the class
+                segments.insert(rest, (true, class_name));
+            }
+        }
+    }
+
+    let typed_segment_len = position_encoding().units(partial);
+    let replace_range = Range {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_segment_len),
+        },
+        end: position,
+    };
+    segments
+        .into_iter()
+        .map(|(segment, (is_class, full_name))| CompletionItem {
+            label: segment.to_string(),
+            kind: Some(if is_class {
+                CompletionItemKind::CLASS
+            } else {
+                CompletionItemKind::MODULE
+            }),
+            detail: Some(match (is_class, package) {
+                (true, _) => full_name.to_string(),
+                (false, Some(package)) => format!("package {package}.{segment}"),
+                (false, None) => format!("package {segment}"),
+            }),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: replace_range,
+                new_text: segment.to_string(),
+            })),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Returns `true` if a class name typed at the end of `line_before_cursor` directly follows
+/// `##class(`, i.e. is a class method qualifier.
+pub fn is_class_qualifier_position(line_before_cursor: &str, prefix: &str) -> bool {
+    line_before_cursor
+        .strip_suffix(prefix)
+        .is_some_and(|before| before.to_ascii_lowercase().ends_with("##class("))
+}
+
+/// Completes class names inside `##class(` with the closing `).` of the qualifier.
+pub fn close_class_qualifiers(items: &mut [CompletionItem]) {
+    for item in items
+        .iter_mut()
+        .filter(|item| item.kind == Some(CompletionItemKind::CLASS))
+    {
+        if let Some(CompletionTextEdit::Edit(edit)) = &mut item.text_edit {
+            edit.new_text.push_str(").");
+        }
+    }
+}
+
+/// Sets the commit characters of class name completions inside `##class(`: `.` accepts a package
+/// segment and `)` a class name. With snippet support, class names also insert the closing `)`.
+///
+/// Runs after `close_class_qualifiers`, whose items already end with `).` and are left as is.
+pub fn commit_class_qualifiers(items: &mut [CompletionItem], snippets: bool) {
+    for item in items.iter_mut() {
+        if item.kind != Some(CompletionItemKind::CLASS) {
+            item.commit_characters = Some(vec![".".to_string()]);
+            continue;
+        }
+        let Some(CompletionTextEdit::Edit(edit)) = &mut item.text_edit else {
+            continue;
+        };
+        if edit.new_text.ends_with(").") {
+            continue;
+        }
+        item.commit_characters = Some(vec![")".to_string()]);
+        if snippets {
+            edit.new_text.push_str(")$0");
+            item.insert_text_format = Some(InsertTextFormat::SNIPPET);
+        }
+    }
+}
+
+/// Whose methods a method completion offers.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MethodTarget<'a> {
+    /// `##class(Pkg.Cls).`: the class methods of the named class.
+    ClassMethodsOf(&'a str),
+    /// `..`: the methods of the current class.
+    CurrentClass,
+    /// `oref.`: the members of the class of the object held by the local variable.
+    Oref(&'a str),
+}
+
+/// Returns the method name being typed at the end of `line_before_cursor` and whose methods it
+/// names, if the cursor follows `##class(Pkg.Cls).`, `..`, or a local variable and `.`.
+pub fn method_name_prefix(line_before_cursor: &str) -> Option<(MethodTarget<'_>, &str)> {
+    let name_start = line_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '%')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line_before_cursor.len());
+    let prefix = &line_before_cursor[name_start..];
+    let before = line_before_cursor[..name_start].strip_suffix('.')?;
+    if before.ends_with('.') {
+        return Some((MethodTarget::CurrentClass, prefix));
+    }
+    let Some(qualifier) = before.strip_suffix(')') else {
+        let variable_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '%')
+            .last()?
+            .0;
+        let variable = &before[variable_start..];
+        // `^global.`, `$system.`, `#dim.`, and `a.b.` (a member, or a package) aren't orefs
+        let after_other_name = before[..variable_start].ends_with(['.', '^', '$', '#', '&']);
+        return (is_simple_variable_name(variable) && !after_other_name)
+            .then_some((MethodTarget::Oref(variable), prefix));
+    };
+    let class_start = qualifier.to_ascii_lowercase().rfind("##class(")? + "##class(".len();
+    let class_name = qualifier[class_start..].trim();
+    let is_class_name = !class_name.is_empty()
+        && class_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '%');
+    is_class_name.then_some((MethodTarget::ClassMethodsOf(class_name), prefix))
+}
+
+/// Completes the properties whose names start with `partial`, each paired with the class
+/// declaring it.
+///
+/// Items replace the typed name, which ends at the cursor `position`.
+pub fn property_completions(
+    properties: &[(ClassProperty, String)],
+    partial: &str,
+    position: Position,
+) -> Vec<CompletionItem> {
+    let typed_len = position_encoding().units(partial);
+    let replace_range = Range {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_len),
+        },
+        end: position,
+    };
+    let partial = partial.to_ascii_lowercase();
+    properties
+        .iter()
+        .filter(|(property, _)| property.name.to_ascii_lowercase().starts_with(&partial))
+        .map(|(property, class_name)| {
+            let detail = match &property.property_type {
+                Some(property_type) => format!("{property_type} (Property of {class_name})"),
+                None => format!("Property of {class_name}"),
+            };
+            CompletionItem {
+                label: property.name.clone(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                detail: Some(detail),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: replace_range,
+                    new_text: property.name.clone(),
+                })),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Returns the parameter name being typed at the end of `line_before_cursor` and the class whose
+/// parameters it names, `None` for the current class, if the cursor follows `..#` or
+/// `##class(Pkg.Cls).#`.
+pub fn parameter_name_prefix(line_before_cursor: &str) -> Option<(Option<&str>, &str)> {
+    let name_start = line_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '%')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line_before_cursor.len());
+    let prefix = &line_before_cursor[name_start..];
+    let before = line_before_cursor[..name_start].strip_suffix('#')?;
+    // what precedes the `#` is an empty method name after `..` or `##class(Pkg.Cls).`
+    match method_name_prefix(before)? {
+        (MethodTarget::CurrentClass, "") => Some((None, prefix)),
+        (MethodTarget::ClassMethodsOf(class_name), "") => Some((Some(class_name), prefix)),
+        _ => None,
+    }
+}
+
+/// Completes the class parameters whose names start with `partial`, each paired with the class
+/// declaring it.
+///
+/// Items replace the typed name, which ends at the cursor `position`.
+pub fn parameter_completions(
+    parameters: &[(ClassParameter, String)],
+    partial: &str,
+    position: Position,
+) -> Vec<CompletionItem> {
+    let typed_len = position_encoding().units(partial);
+    let replace_range = Range {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_len),
+        },
+        end: position,
+    };
+    let partial = partial.to_ascii_lowercase();
+    parameters
+        .iter()
+        .filter(|(parameter, _)| parameter.name.to_ascii_lowercase().starts_with(&partial))
+        .map(|(parameter, class_name)| CompletionItem {
+            label: parameter.name.clone(),
+            kind: Some(CompletionItemKind::CONSTANT),
+            detail: Some(parameter.signature()),
+            documentation: Some(Documentation::String(format!("Declared in {class_name}"))),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: replace_range,
+                new_text: parameter.name.clone(),
+            })),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Completes the methods whose names start with `partial`, inserting them as configured by
+/// `style`.
+///
+/// Snippets are only inserted when the client supports them (`snippets`); otherwise
+/// `MethodInsertStyle::Snippet` inserts the name plus `()`. Items replace the typed name, which
+/// ends at the cursor `position`.
+pub fn method_completions(
+    methods: &[Method],
+    partial: &str,
+    position: Position,
+    style: MethodInsertStyle,
+    snippets: bool,
+) -> Vec<CompletionItem> {
+    let typed_len = position_encoding().units(partial);
+    let replace_range = Range {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_len),
+        },
+        end: position,
+    };
+    let partial = partial.to_ascii_lowercase();
+    methods
+        .iter()
+        .filter(|method| method.name.to_ascii_lowercase().starts_with(&partial))
+        .map(|method| {
+            let (new_text, format) = match style {
+                MethodInsertStyle::Name => (method.name.clone(), InsertTextFormat::PLAIN_TEXT),
+                MethodInsertStyle::Snippet if snippets => {
+                    let tab_stops: Vec<String> = method
+                        .arguments
+                        .iter()
+                        .enumerate()
+                        .map(|(i, argument)| format!("${{{}:{}}}", i + 1, argument))
+                        .collect();
+                    (
+                        format!("{}({})$0", method.name, tab_stops.join(", ")),
+                        InsertTextFormat::SNIPPET,
+                    )
+                }
+                MethodInsertStyle::Parens | MethodInsertStyle::Snippet => {
+                    (format!("{}()", method.name), InsertTextFormat::PLAIN_TEXT)
+                }
+            };
+            let keyword = match method.method_type {
+                MethodType::ClassMethod => "ClassMethod",
+                MethodType::InstanceMethod => "Method",
+            };
+            let mut detail = format!("{keyword} {}({})", method.name, method.arguments.join(", "));
+            let labels = method.visibility.labels();
+            if !labels.is_empty() {
+                detail.push_str(&format!(" [{}]", labels.join(", ")));
+            }
+            CompletionItem {
+                label: method.name.clone(),
+                kind: Some(CompletionItemKind::METHOD),
+                detail: Some(detail),
+                insert_text_format: Some(format),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: replace_range,
+                    new_text,
+                })),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
@@ -1,4 +1,11 @@
+use crate::atelier::AtelierConfig;
 use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Name of the optional per-workspace config file, looked up in the workspace root.
+pub const WORKSPACE_CONFIG_FILE: &str = ".objectscript-lsp.json";
+
 /// Workspace/user configuration flags for the language server.
 ///
 /// Deserialized from config input (with defaults via `#[serde(default)]`) and used to enable
@@ -13,10 +20,140 @@ pub struct Config {
     pub(crate) enable_formatting: bool,
 
     /// Enables lint/diagnostic checks.
-    enable_lint: bool,
+    pub(crate) enable_lint: bool,
+
+    /// Reports literals passed, assigned, or returned where the declared argument, variable, or
+    /// return type expects another kind of value (see `type_check`). Requires `enable_lint`.
+    pub(crate) enable_strict_mode: bool,
+
+    /// Glob patterns (matched against the class name and the file name) identifying generated
+    /// classes, e.g. `*.Gen.cls` or `MyApp.WSDL.*`.
+    pub(crate) generated_class_patterns: Vec<String>,
+
+    /// How diagnostics are reported for generated classes (`"hint"` or `"off"`).
+    pub(crate) generated_class_severity: GeneratedClassSeverity,
+
+    /// Opt-in lint that flags `write`/`zwrite` debugging statements in class methods.
+    pub(crate) lint_debug_writes: bool,
+
+    /// Replacement offered by the debug-write quick fix; `{message}` is substituted with the
+    /// written expressions, e.g. `$$$LOGINFO({message})`.
+    pub(crate) debug_write_replacement: String,
+
+    /// Number of project index snapshots kept for `objectscript.diffSnapshots`; 0 disables them.
+    pub(crate) index_snapshot_limit: usize,
+
+    /// Shows a code lens with method/property/line/subclass counts on each class header.
+    pub(crate) class_stats_lens: bool,
+
+    /// Shows a code lens with the number of call sites of each public method above it.
+    pub(crate) reference_count_lens: bool,
+
+    /// Document sync kind advertised to the client (`"incremental"` or `"full"`). Full-text
+    /// changes are accepted either way.
+    pub(crate) sync_kind: SyncKind,
+
+    /// Expands single-letter command abbreviations (`s`, `w`, `d`, `i`, `q`) into full commands
+    /// when formatting and when a line is completed. Requires `enable_formatting`.
+    pub(crate) expand_command_abbreviations: bool,
+
+    /// Indents a new line to the level of its block, and re-indents a block once its `}` is
+    /// typed. Requires `enable_formatting`.
+    pub(crate) indent_on_type: bool,
+
+    /// Resolves methods called on object references whose class can't be inferred by matching
+    /// the method name against every class in the workspace (marked as heuristic in hover).
+    pub(crate) heuristic_oref_methods: bool,
+
+    /// What accepting a method completion inserts: `"name"`, `"parens"` (name plus `()`), or
+    /// `"snippet"` (argument tab stops; falls back to `"parens"` without snippet support).
+    pub(crate) method_insert_style: MethodInsertStyle,
+
+    /// Completing a class name right after `##class(` also inserts the closing `).`, so class
+    /// method completion can follow immediately.
+    pub(crate) complete_class_qualifier: bool,
+
+    /// Shows each method's cyclomatic complexity and nesting depth in a code lens and on hover.
+    pub(crate) method_metrics: bool,
+
+    /// Warns about methods whose cyclomatic complexity exceeds this; 0 disables the warning.
+    /// Requires `enable_lint`.
+    pub(crate) max_method_complexity: usize,
+
+    /// Warns about methods nesting commands deeper than this; 0 disables the warning. Requires
+    /// `enable_lint`.
+    pub(crate) max_method_nesting: usize,
 
-    /// Enables stricter parsing/diagnostic behavior when supported.
-    enable_strict_mode: bool,
+    /// Shows the type (and value, when known) of the element returned by `$list`, `$listget`, and
+    /// `$piece` calls on hover, following variables built from literals and `$listbuild`.
+    pub(crate) element_type_hover: bool,
+
+    /// Number of watched-file events within one second treated as a bulk change (e.g. a branch
+    /// switch), which re-indexes the workspace in the background instead of file by file; 0
+    /// always updates file by file.
+    pub(crate) bulk_change_threshold: usize,
+
+    /// Completes, documents (hover and signature help), and checks the argument counts of calls
+    /// into the `$SYSTEM` API from the bundled `%SYSTEM` stubs.
+    pub(crate) system_api_stubs: bool,
+
+    /// Reports references to classes, methods, include files, and routines that aren't defined
+    /// in the workspace (see `objectscript/unresolved`). Requires `enable_lint`.
+    pub(crate) lint_unresolved_references: bool,
+
+    /// Reports methods that call themselves, directly or through a cycle of calls, as
+    /// informational diagnostics. Requires `enable_lint`.
+    pub(crate) lint_recursion: bool,
+
+    /// Completes `Internal` members of classes other than the current one. They are hidden by
+    /// default, since they aren't meant to be used outside the class.
+    pub(crate) complete_internal_members: bool,
+
+    /// Maximum number of symbols a workspace symbol search returns, best matches first; 0
+    /// returns every match.
+    pub(crate) workspace_symbol_limit: usize,
+
+    /// IRIS server to download classes the workspace references but doesn't define from (e.g.
+    /// `%Library.Persistent`), indexed as read-only documents. Unset by default.
+    pub(crate) atelier: Option<AtelierConfig>,
+
+    /// Documents system (`%`) classes and the methods called on them on hover, from the classes
+    /// downloaded from the `atelier` server or else from the bundled class reference snapshot.
+    pub(crate) class_reference_hover: bool,
+}
+
+/// Reporting policy for diagnostics in generated classes.
+///
+/// Generated classes still contribute symbols for navigation either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratedClassSeverity {
+    /// Report every diagnostic as a hint.
+    Hint,
+    /// Don't report diagnostics at all.
+    Off,
+}
+
+/// Document sync kind advertised in `ServerCapabilities::text_document_sync`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncKind {
+    /// Clients send only the changed ranges.
+    Incremental,
+    /// Clients send the whole document on every change.
+    Full,
+}
+
+/// Text inserted when a method completion is accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MethodInsertStyle {
+    /// Just the method name.
+    Name,
+    /// The name followed by `()`.
+    Parens,
+    /// The name followed by the arguments as snippet tab stops.
+    Snippet,
 }
 
 impl Default for Config {
@@ -27,6 +164,63 @@ impl Default for Config {
             enable_formatting: true,
             enable_lint: true,
             enable_snippets: true,
+            generated_class_patterns: vec!["*.Gen.cls".to_string()],
+            generated_class_severity: GeneratedClassSeverity::Hint,
+            lint_debug_writes: false,
+            debug_write_replacement: "$$$LOGINFO({message})".to_string(),
+            index_snapshot_limit: 10,
+            class_stats_lens: false,
+            reference_count_lens: false,
+            sync_kind: SyncKind::Incremental,
+            expand_command_abbreviations: false,
+            indent_on_type: true,
+            heuristic_oref_methods: true,
+            method_insert_style: MethodInsertStyle::Parens,
+            complete_class_qualifier: false,
+            method_metrics: false,
+            max_method_complexity: 0,
+            max_method_nesting: 0,
+            element_type_hover: true,
+            bulk_change_threshold: 200,
+            system_api_stubs: true,
+            lint_unresolved_references: false,
+            lint_recursion: false,
+            complete_internal_members: false,
+            workspace_symbol_limit: 100,
+            atelier: None,
+            class_reference_hover: true,
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration for a workspace.
+    ///
+    /// Settings from the client's `initialization_options` are overlaid with the keys of the
+    /// workspace's `.objectscript-lsp.json` (if `root` has one), so the file wins for every key it
+    /// sets. Unset keys keep their defaults, and unreadable or invalid input is logged and ignored.
+    pub fn load(initialization_options: Option<Value>, root: Option<&Path>) -> Self {
+        let mut merged = match initialization_options {
+            Some(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        if let Some(path) = root.map(|root| root.join(WORKSPACE_CONFIG_FILE)) {
+            if path.is_file() {
+                let file_value = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+                match file_value {
+                    Some(Value::Object(map)) => merged.extend(map),
+                    _ => eprintln!(
+                        "Warning: ignoring workspace config file {:?}, expected a JSON object",
+                        path
+                    ),
+                }
+            }
         }
+        serde_json::from_value(Value::Object(merged)).unwrap_or_else(|e| {
+            eprintln!("Warning: invalid configuration, using defaults: {e}");
+            Self::default()
+        })
     }
 }
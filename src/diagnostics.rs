@@ -0,0 +1,1734 @@
+use crate::case_rules::{find_case_mismatch, names_match, IdentifierKind};
+use crate::common::{
+    braced_content, find_class_definition, generic_exit_statements, get_node_children,
+    member_visibility, method_definition_nodes, start_of_function, successful_exit,
+    ts_range_to_lsp_range,
+};
+use crate::config::{Config, GeneratedClassSeverity};
+use crate::interner::Name;
+use crate::method::initial_build_method;
+use crate::override_index::OverrideIndex;
+use crate::parse_structures::{
+    ArgumentMode, ClassId, MemberVisibility, MethodCallSite, MethodType,
+};
+use crate::rules::{
+    rule_code, rule_code_description, ARGUMENT_COUNT_RULE, CASE_MISMATCH_RULE,
+    DISPATCH_MISMATCH_RULE, DUPLICATE_MEMBER_RULE, INHERITANCE_CYCLE_RULE, MEMBER_COLLISION_RULE,
+    UNBALANCED_CONSTRUCT_RULE,
+};
+use crate::system_api::argument_count_text;
+use crate::variable::{argument_mode, declared_argument_name};
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location,
+    SymbolKind, Url,
+};
+use tree_sitter::{Node, Point, Range, Tree};
+
+/// Value of `Diagnostic::source` for every diagnostic produced by this server.
+pub const DIAGNOSTIC_SOURCE: &str = "objectscript-lsp";
+
+/// Collects a diagnostic for every `ERROR` and `MISSING` node in `tree`, describing what is
+/// expected or unexpected and in which construct (see `missing_message` and `unexpected_message`).
+///
+/// Subtrees without errors are skipped entirely, so this is cheap for documents that parse cleanly.
+pub fn syntax_diagnostics(content: &str, tree: &Tree) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "syntax_diagnostics");
+    let mut diagnostics = Vec::new();
+    collect_syntax_errors(tree.root_node(), content, &mut diagnostics);
+    successful_exit("DIAGNOSTICS (no struct)", "syntax_diagnostics");
+    diagnostics
+}
+
+/// Recursively walks the error-containing parts of the tree below `node`.
+fn collect_syntax_errors(node: Node, content: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if !node.has_error() {
+        return;
+    }
+    if node.is_error() || node.is_missing() {
+        // the innermost construct the error is in, e.g. the method body
+        let mut context = None;
+        let mut ancestor = node.parent();
+        while let Some(current) = ancestor {
+            context = construct_name(current.kind());
+            if context.is_some() {
+                break;
+            }
+            ancestor = current.parent();
+        }
+        let message = if node.is_missing() {
+            missing_message(node.kind(), context.as_deref())
+        } else {
+            unexpected_message(
+                content.get(node.byte_range()).unwrap_or_default(),
+                context.as_deref(),
+            )
+        };
+        diagnostics.push(Diagnostic {
+            range: ts_range_to_lsp_range(content, node.range()),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message,
+            ..Default::default()
+        });
+        // nested errors inside an ERROR node are just noise
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_syntax_errors(child, content, diagnostics);
+    }
+}
+
+/// Describes the construct of a node of `kind` for syntax error messages, or `None` for nodes
+/// too small to be worth naming.
+fn construct_name(kind: &str) -> Option<String> {
+    let name = match kind {
+        "class_definition" | "class_body" => "class body",
+        "method_definition" => "method signature",
+        "method" | "classmethod" | "core_method_body_content" => "method body",
+        "arguments" => "argument list",
+        "class_keywords" | "method_keywords" => "keyword list",
+        "property" => "property definition",
+        "parameter" => "parameter definition",
+        "xdata" => "XData block",
+        "index" => "index definition",
+        "foreignkey" => "foreign key definition",
+        "trigger" => "trigger definition",
+        "query" => "query definition",
+        "projection" => "projection definition",
+        "storage" => "Storage definition",
+        "string_literal" => "string",
+        kind => {
+            let command = kind.strip_prefix("command_")?;
+            return Some(format!("`{}` command", command.to_ascii_uppercase()));
+        }
+    };
+    Some(name.to_string())
+}
+
+/// Message of a `MISSING` node of `kind` inside the construct `context`, e.g. "expected `}` to
+/// close method body".
+pub fn missing_message(kind: &str, context: Option<&str>) -> String {
+    let is_token = !kind.chars().any(|c| c.is_ascii_alphanumeric());
+    let mut message = if is_token {
+        format!("Syntax error: expected `{kind}`")
+    } else {
+        format!("Syntax error: expected {}", kind.replace('_', " "))
+    };
+    if let Some(context) = context {
+        let closes = matches!(kind, "}" | ")" | "]" | "\"");
+        message.push_str(if closes { " to close " } else { " in " });
+        message.push_str(context);
+    }
+    message
+}
+
+/// Message of an `ERROR` node covering `text` inside the construct `context`, quoting the start
+/// of the text.
+pub fn unexpected_message(text: &str, context: Option<&str>) -> String {
+    const MAX_QUOTED: usize = 30;
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty());
+    let mut message = match line {
+        Some(line) if line.chars().count() > MAX_QUOTED => {
+            let quoted: String = line.chars().take(MAX_QUOTED).collect();
+            format!("Syntax error: unexpected `{quoted}...`")
+        }
+        Some(line) => format!("Syntax error: unexpected `{line}`"),
+        None => "Syntax error: unexpected input".to_string(),
+    };
+    if let Some(context) = context {
+        message.push_str(" in ");
+        message.push_str(context);
+    }
+    message
+}
+
+/// An opening bracket tracked by `bracket_diagnostics`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Opener {
+    /// `##class(`
+    Class,
+    /// `&sql(`, whose contents are scanned as SQL.
+    Sql,
+    /// `$select(` / `$s(`
+    Select,
+    /// Any other `(`.
+    Paren,
+    /// `{`
+    Brace,
+}
+
+impl Opener {
+    /// Targeted message for an opener that is never closed, or `None` for plain brackets.
+    fn unterminated_message(&self) -> Option<&'static str> {
+        match self {
+            Opener::Class => Some("Unterminated ##class( ... ): missing closing )"),
+            Opener::Sql => Some("Unterminated &sql block: missing closing )"),
+            Opener::Select => Some("Unterminated $select( ... ): missing closing )"),
+            Opener::Paren | Opener::Brace => None,
+        }
+    }
+}
+
+/// Builds a Tree-sitter range for `content[start..end]`, where neither offset is past a newline.
+pub(crate) fn byte_range(content: &str, start: usize, end: usize) -> Range {
+    let point = |byte: usize| {
+        let line_start = content[..byte].rfind('\n').map_or(0, |i| i + 1);
+        let row = content[..line_start].matches('\n').count();
+        Point::new(row, byte - line_start)
+    };
+    Range {
+        start_byte: start,
+        end_byte: end,
+        start_point: point(start),
+        end_point: point(end),
+    }
+}
+
+/// Builds the diagnostic for an `opener` at `content[start..end]` that is never closed.
+///
+/// Returns `None` for plain brackets, which are left to the generic syntax diagnostics.
+fn unterminated_diagnostic(
+    content: &str,
+    opener: Opener,
+    start: usize,
+    end: usize,
+) -> Option<Diagnostic> {
+    let message = opener.unterminated_message()?;
+    Some(Diagnostic {
+        range: ts_range_to_lsp_range(content, byte_range(content, start, end)),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: rule_code(UNBALANCED_CONSTRUCT_RULE),
+        code_description: rule_code_description(UNBALANCED_CONSTRUCT_RULE),
+        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        message: message.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Returns `true` if `content[i..]` starts with `pattern`, ignoring ASCII case.
+pub(crate) fn starts_with_ignore_case(content: &str, i: usize, pattern: &str) -> bool {
+    content
+        .as_bytes()
+        .get(i..i + pattern.len())
+        .is_some_and(|slice| slice.eq_ignore_ascii_case(pattern.as_bytes()))
+}
+
+/// Returns the byte offset of the end of the line containing `i` (the `\n` or `content.len()`).
+pub(crate) fn end_of_line(content: &str, i: usize) -> usize {
+    content[i..].find('\n').map_or(content.len(), |n| i + n)
+}
+
+/// Returns the byte offset just past the string literal opened by the `quote` at `i`, where a
+/// doubled quote is an escaped quote. Unterminated strings end at the end of their line.
+pub(crate) fn skip_string(content: &str, i: usize, quote: u8) -> usize {
+    let bytes = content.as_bytes();
+    let mut j = i + 1;
+    while j < bytes.len() && bytes[j] != b'\n' {
+        if bytes[j] == quote {
+            if bytes.get(j + 1) == Some(&quote) {
+                j += 2;
+                continue;
+            }
+            return j + 1;
+        }
+        j += 1;
+    }
+    j
+}
+
+/// The string and comment rules used by `skip_comment` and `skip_string_or_comment`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    /// `"` strings, and `;`, `//`, `#;` and `/* */` comments.
+    ObjectScript,
+    /// `'` and `"` strings, and `--` and `/* */` comments.
+    Sql,
+}
+
+/// Returns the byte offset just past the comment starting at byte `i`, or `None` if no comment
+/// starts there. Line comments end at the end of their line, and an unclosed `/*` comment at
+/// the end of `content`.
+///
+/// Bytes are compared rather than `str` slices, so `i` may be inside a multi-byte character.
+pub(crate) fn skip_comment(content: &str, i: usize, dialect: Dialect) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let rest = bytes.get(i..)?;
+    if rest.starts_with(b"/*") {
+        let close = bytes[i + 2..].windows(2).position(|pair| pair == b"*/");
+        return Some(close.map_or(bytes.len(), |n| i + 2 + n + 2));
+    }
+    let line_comment = match dialect {
+        Dialect::ObjectScript => {
+            rest.starts_with(b";") || rest.starts_with(b"//") || rest.starts_with(b"#;")
+        }
+        Dialect::Sql => rest.starts_with(b"--"),
+    };
+    line_comment.then(|| end_of_line(content, i))
+}
+
+/// Returns the byte offset just past the string literal or comment starting at byte `i`, or
+/// `None` if neither starts there. Like `skip_comment`, `i` may be inside a multi-byte character.
+pub(crate) fn skip_string_or_comment(content: &str, i: usize, dialect: Dialect) -> Option<usize> {
+    match (content.as_bytes().get(i)?, dialect) {
+        (b'"', _) => Some(skip_string(content, i, b'"')),
+        (b'\'', Dialect::Sql) => Some(skip_string(content, i, b'\'')),
+        _ => skip_comment(content, i, dialect),
+    }
+}
+
+/// Validates `##class( ... )`, embedded `&sql( ... )`, and `$select( ... )` constructs and
+/// reports the ones that are never closed, plus closing parentheses that match nothing.
+///
+/// This is a lexical scan (strings and comments are skipped, `&sql` bodies are scanned with SQL
+/// quoting and comment rules), so it gives precise messages for the most common typos that
+/// otherwise only surface as generic syntax-error ranges.
+pub fn bracket_diagnostics(content: &str) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "bracket_diagnostics");
+    let bytes = content.as_bytes();
+    let mut diagnostics = Vec::new();
+    // (opener, start byte, end byte of the opening token)
+    let mut stack: Vec<(Opener, usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let in_sql = stack.iter().any(|(opener, _, _)| *opener == Opener::Sql);
+        let b = bytes[i];
+        let dialect = if in_sql {
+            Dialect::Sql
+        } else {
+            Dialect::ObjectScript
+        };
+        if let Some(next) = skip_string_or_comment(content, i, dialect) {
+            i = next;
+            continue;
+        }
+
+        // brackets
+        let special = [
+            ("##class(", Opener::Class),
+            ("&sql(", Opener::Sql),
+            ("$select(", Opener::Select),
+            ("$s(", Opener::Select),
+        ];
+        // `$$select(` is a call to an extrinsic function, not `$select(`
+        let after_dollar = i > 0 && bytes[i - 1] == b'$';
+        if let Some(&(token, opener)) = special.iter().find(|(token, _)| {
+            !in_sql
+                && (!after_dollar || !token.starts_with('$'))
+                && starts_with_ignore_case(content, i, token)
+        }) {
+            stack.push((opener, i, i + token.len()));
+            i += token.len();
+            continue;
+        }
+        match b {
+            b'(' => stack.push((Opener::Paren, i, i + 1)),
+            b'{' if !in_sql => stack.push((Opener::Brace, i, i + 1)),
+            b')' => match stack.last() {
+                Some((Opener::Brace, _, _)) | None => {
+                    diagnostics.push(Diagnostic {
+                        range: ts_range_to_lsp_range(content, byte_range(content, i, i + 1)),
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        code: rule_code(UNBALANCED_CONSTRUCT_RULE),
+                        code_description: rule_code_description(UNBALANCED_CONSTRUCT_RULE),
+                        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                        message: "Unmatched closing )".to_string(),
+                        ..Default::default()
+                    });
+                }
+                Some(_) => {
+                    stack.pop();
+                }
+            },
+            b'}' if !in_sql => {
+                // a block ends: anything still open inside it was never closed
+                while let Some((opener, start, end)) = stack.pop() {
+                    if opener == Opener::Brace {
+                        break;
+                    }
+                    diagnostics.extend(unterminated_diagnostic(content, opener, start, end));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    for (opener, start, end) in stack {
+        diagnostics.extend(unterminated_diagnostic(content, opener, start, end));
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "bracket_diagnostics");
+    diagnostics
+}
+
+/// Lints unresolved method calls whose class or method name only differs in case from a
+/// declaration.
+///
+/// Class and method names are case-sensitive, so such calls fail at runtime even though they
+/// look right; the warning names the declared spelling.
+pub fn case_mismatch_diagnostics(
+    content: &str,
+    call_sites: &[MethodCallSite],
+    classes: &HashMap<Name, ClassId>,
+    idx: &OverrideIndex,
+) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "case_mismatch_diagnostics");
+    let mut diagnostics = Vec::new();
+    for call_site in call_sites.iter().filter(|c| c.callee_symbol.is_none()) {
+        let message = match classes.get(call_site.callee_class.as_str()) {
+            None => find_case_mismatch(classes.keys(), &call_site.callee_class).map(|declared| {
+                format!(
+                    "Class name {:?} differs in case from its declaration {:?}",
+                    call_site.callee_class, declared
+                )
+            }),
+            Some(class_id) => idx
+                .effective_public_methods
+                .get(class_id)
+                .and_then(|methods| find_case_mismatch(methods.keys(), &call_site.callee_method))
+                .map(|declared| {
+                    format!(
+                        "Method name {:?} differs in case from its declaration {:?} in class {:?}",
+                        call_site.callee_method, declared, call_site.callee_class
+                    )
+                }),
+        };
+        let Some(message) = message else {
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            range: ts_range_to_lsp_range(content, call_site.call_range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(CASE_MISMATCH_RULE),
+            code_description: rule_code_description(CASE_MISMATCH_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message,
+            ..Default::default()
+        });
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "case_mismatch_diagnostics");
+    diagnostics
+}
+
+/// Kind of a member declared in a class body.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemberKind {
+    Method,
+    Property,
+    Parameter,
+    Index,
+    ForeignKey,
+    Trigger,
+    Query,
+    Projection,
+    Storage,
+}
+
+impl MemberKind {
+    /// Returns the kind of member declared by a class statement whose statement type node is of
+    /// `kind`, e.g. `MemberKind::Method` for `classmethod`; `None` for XData blocks and unknown
+    /// statements.
+    pub fn from_statement_kind(kind: &str) -> Option<Self> {
+        let member_kind = match kind {
+            "method" | "classmethod" => MemberKind::Method,
+            "property" => MemberKind::Property,
+            "parameter" => MemberKind::Parameter,
+            "index" => MemberKind::Index,
+            "foreignkey" => MemberKind::ForeignKey,
+            "trigger" => MemberKind::Trigger,
+            "query" => MemberKind::Query,
+            "projection" => MemberKind::Projection,
+            "storage" => MemberKind::Storage,
+            _ => return None,
+        };
+        Some(member_kind)
+    }
+
+    /// Returns the identifier kind of the names of members of this kind, for the ones with their
+    /// own case rules (see `IdentifierKind`).
+    pub fn identifier_kind(self) -> Option<IdentifierKind> {
+        match self {
+            MemberKind::Method => Some(IdentifierKind::Method),
+            MemberKind::Property => Some(IdentifierKind::Property),
+            MemberKind::Parameter => Some(IdentifierKind::Parameter),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of symbol members of this kind are listed as in the outline and in
+    /// workspace symbol search.
+    pub fn symbol_kind(&self) -> SymbolKind {
+        match self {
+            MemberKind::Method => SymbolKind::METHOD,
+            MemberKind::Property => SymbolKind::PROPERTY,
+            MemberKind::Parameter => SymbolKind::CONSTANT,
+            MemberKind::Index | MemberKind::ForeignKey => SymbolKind::KEY,
+            MemberKind::Trigger => SymbolKind::EVENT,
+            MemberKind::Query => SymbolKind::FUNCTION,
+            MemberKind::Projection => SymbolKind::INTERFACE,
+            MemberKind::Storage => SymbolKind::STRUCT,
+        }
+    }
+
+    /// Returns the member kind as written in class definitions.
+    fn label(&self) -> &'static str {
+        match self {
+            MemberKind::Method => "Method",
+            MemberKind::Property => "Property",
+            MemberKind::Parameter => "Parameter",
+            MemberKind::Index => "Index",
+            MemberKind::ForeignKey => "ForeignKey",
+            MemberKind::Trigger => "Trigger",
+            MemberKind::Query => "Query",
+            MemberKind::Projection => "Projection",
+            MemberKind::Storage => "Storage",
+        }
+    }
+}
+
+/// A member declared in a class body: a method, property, parameter, index, foreign key,
+/// trigger, query, projection, or storage definition.
+#[derive(Clone, Debug)]
+pub struct ClassMember {
+    pub name: String,
+    pub kind: MemberKind,
+    /// Range of the member name.
+    pub range: Range,
+    /// Range of the whole member declaration, including its body.
+    pub declaration_range: Range,
+    /// `Internal` and `ServerOnly` keywords.
+    pub visibility: MemberVisibility,
+}
+
+/// A `ClassMember` declared by a superclass, with the location of its declaration.
+#[derive(Clone, Debug)]
+pub struct InheritedMember {
+    /// Name of the superclass declaring the member.
+    pub class_name: String,
+    pub member: ClassMember,
+    pub location: Location,
+}
+
+/// Collects the members declared in the class body of `tree`, apart from XData blocks (see
+/// `xdata_blocks`).
+pub fn class_members(content: &str, tree: &Tree) -> Vec<ClassMember> {
+    let mut members = Vec::new();
+    for (statement, statement_type) in member_statements(tree) {
+        let Some(kind) = MemberKind::from_statement_kind(statement_type.kind()) else {
+            continue;
+        };
+        let Some(name_node) = member_name(statement_type) else {
+            continue;
+        };
+        let Some(name) = content.get(name_node.byte_range()) else {
+            continue;
+        };
+        let visibility = member_keywords(statement_type)
+            .and_then(|keywords| content.get(keywords.byte_range()))
+            .map_or_else(MemberVisibility::default, member_visibility);
+        members.push(ClassMember {
+            name: name.to_string(),
+            kind,
+            range: name_node.range(),
+            declaration_range: statement.range(),
+            visibility,
+        });
+    }
+    members
+}
+
+/// Collects the XData blocks declared in the class body of `tree`, as their name, the range of
+/// the name, and the range of the whole block.
+pub fn xdata_blocks(content: &str, tree: &Tree) -> Vec<(String, Range, Range)> {
+    member_statements(tree)
+        .into_iter()
+        .filter(|(_, statement_type)| statement_type.kind() == "xdata")
+        .filter_map(|(statement, statement_type)| {
+            let name_node = member_name(statement_type)?;
+            let name = content.get(name_node.byte_range())?;
+            Some((name.to_string(), name_node.range(), statement.range()))
+        })
+        .collect()
+}
+
+/// Lists the statements of the class body of `tree`, each with its statement type (its first
+/// child, e.g. a `property` node).
+pub fn member_statements(tree: &Tree) -> Vec<(Node<'_>, Node<'_>)> {
+    let Some(class_definition) = find_class_definition(tree.root_node()) else {
+        return Vec::new();
+    };
+    let mut cursor = class_definition.walk();
+    let class_bodies: Vec<Node> = class_definition
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "class_body")
+        .collect();
+    let mut statements = Vec::new();
+    for class_body in class_bodies {
+        let mut cursor = class_body.walk();
+        for statement in class_body.named_children(&mut cursor) {
+            if let Some(statement_type) = statement.named_child(0) {
+                statements.push((statement, statement_type));
+            }
+        }
+    }
+    statements
+}
+
+/// Finds the name of the member statement `statement_type`: its child(1) is the name or the
+/// definition starting with the name.
+fn member_name(statement_type: Node) -> Option<Node> {
+    let mut name_node = statement_type.named_child(1);
+    while let Some(node) = name_node.filter(|n| n.kind().ends_with("_definition")) {
+        name_node = node.named_child(0);
+    }
+    name_node
+}
+
+/// Finds the keyword list (`[ ... ]`) of the member statement `statement_type`, looking into its
+/// `*_definition` nodes but not into the member body.
+fn member_keywords(statement_type: Node) -> Option<Node> {
+    let mut cursor = statement_type.walk();
+    let children: Vec<Node> = statement_type.named_children(&mut cursor).collect();
+    children.into_iter().find_map(|child| {
+        if child.kind().ends_with("keywords") {
+            Some(child)
+        } else if child.kind().ends_with("_definition") {
+            member_keywords(child)
+        } else {
+            None
+        }
+    })
+}
+
+/// Warns about members that share their name with an inherited member of a different kind,
+/// e.g. a property named like an inherited method or parameter.
+///
+/// ObjectScript resolves such names confusingly, so the warning points at both declarations.
+/// `inherited` is ordered from the nearest superclass, and only the first collision of each
+/// member is reported.
+pub fn member_collision_diagnostics(
+    content: &str,
+    members: &[ClassMember],
+    inherited: &[InheritedMember],
+) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "member_collision_diagnostics");
+    let mut diagnostics = Vec::new();
+    for member in members {
+        let Some(collision) = inherited
+            .iter()
+            .find(|other| other.member.name == member.name && other.member.kind != member.kind)
+        else {
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            range: ts_range_to_lsp_range(content, member.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(MEMBER_COLLISION_RULE),
+            code_description: rule_code_description(MEMBER_COLLISION_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!(
+                "{} {:?} has the same name as the {} inherited from {:?}",
+                member.kind.label(),
+                member.name,
+                collision.member.kind.label().to_lowercase(),
+                collision.class_name
+            ),
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: collision.location.clone(),
+                message: format!(
+                    "{} {:?} declared in {:?}",
+                    collision.member.kind.label(),
+                    collision.member.name,
+                    collision.class_name
+                ),
+            }]),
+            ..Default::default()
+        });
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "member_collision_diagnostics");
+    diagnostics
+}
+
+/// Reports members declaring the same name as an earlier member of the same kind in the class
+/// at `url`; methods and class methods share their names, and names that must be unique
+/// regardless of case (see `IdentifierKind::is_unique_ignoring_case`) are compared ignoring it.
+///
+/// The class doesn't compile, and the indexes keep only one of the declarations, so this is an
+/// error on every declaration after the first, pointing at the first one.
+pub fn duplicate_member_diagnostics(
+    content: &str,
+    url: &Url,
+    members: &[ClassMember],
+) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "duplicate_member_diagnostics");
+    let mut diagnostics = Vec::new();
+    let mut first_declarations: HashMap<(MemberKind, String), &ClassMember> = HashMap::new();
+    for member in members {
+        let ignore_case = member
+            .kind
+            .identifier_kind()
+            .is_some_and(|kind| kind.is_unique_ignoring_case());
+        let name = if ignore_case {
+            member.name.to_ascii_lowercase()
+        } else {
+            member.name.clone()
+        };
+        let Some(first) = first_declarations.get(&(member.kind, name.clone())) else {
+            first_declarations.insert((member.kind, name), member);
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            range: ts_range_to_lsp_range(content, member.range),
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: rule_code(DUPLICATE_MEMBER_RULE),
+            code_description: rule_code_description(DUPLICATE_MEMBER_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!(
+                "{} {:?} is already declared in this class",
+                member.kind.label(),
+                member.name
+            ),
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: url.clone(),
+                    range: ts_range_to_lsp_range(content, first.range),
+                },
+                message: format!("first declaration of {:?}", first.name),
+            }]),
+            ..Default::default()
+        });
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "duplicate_member_diagnostics");
+    diagnostics
+}
+
+/// Reports the superclass through which a class inherits from itself, given the `superclasses`
+/// of its `Extends` clause (see `superclass_names`) and the class names of its inheritance
+/// `cycle`, starting with the class and each followed by its superclass in the cycle.
+pub fn inheritance_cycle_diagnostics(
+    content: &str,
+    superclasses: &[(String, Range)],
+    cycle: &[&str],
+) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "inheritance_cycle_diagnostics");
+    let Some(first) = cycle.first() else {
+        generic_exit_statements("DIAGNOSTICS (no struct)", "inheritance_cycle_diagnostics");
+        return Vec::new();
+    };
+    // a class extending itself directly is its own superclass in the cycle
+    let superclass = cycle.get(1).unwrap_or(first);
+    let chain = cycle
+        .iter()
+        .chain(std::iter::once(first))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    let diagnostics = superclasses
+        .iter()
+        .filter(|(name, _)| name == superclass)
+        .map(|(_, range)| Diagnostic {
+            range: ts_range_to_lsp_range(content, *range),
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: rule_code(INHERITANCE_CYCLE_RULE),
+            code_description: rule_code_description(INHERITANCE_CYCLE_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!("Class {first:?} inherits from itself: {chain}"),
+            ..Default::default()
+        })
+        .collect();
+    successful_exit("DIAGNOSTICS (no struct)", "inheritance_cycle_diagnostics");
+    diagnostics
+}
+
+/// Returns the keyword declaring methods of `method_type`.
+fn method_type_label(method_type: &MethodType) -> &'static str {
+    match method_type {
+        MethodType::ClassMethod => "ClassMethod",
+        MethodType::InstanceMethod => "Method",
+    }
+}
+
+/// A method overriding an inherited method of the other kind (class vs instance method).
+#[derive(Clone, Debug)]
+pub struct DispatchMismatch {
+    pub name: String,
+    /// Kind of the overriding method.
+    pub method_type: MethodType,
+    /// Range of the overriding method's name.
+    pub range: Range,
+    /// Name of the superclass declaring the overridden method.
+    pub base_class: String,
+    pub base_location: Location,
+}
+
+/// Lints methods that override an inherited method of the other kind, e.g. a `ClassMethod`
+/// overriding an instance `Method`.
+pub fn dispatch_mismatch_diagnostics(
+    content: &str,
+    mismatches: &[DispatchMismatch],
+) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "dispatch_mismatch_diagnostics");
+    let diagnostics = mismatches
+        .iter()
+        .map(|mismatch| {
+            let base_label = match mismatch.method_type {
+                MethodType::ClassMethod => MethodType::InstanceMethod,
+                MethodType::InstanceMethod => MethodType::ClassMethod,
+            };
+            let base_label = method_type_label(&base_label);
+            Diagnostic {
+                range: ts_range_to_lsp_range(content, mismatch.range),
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: rule_code(DISPATCH_MISMATCH_RULE),
+                code_description: rule_code_description(DISPATCH_MISMATCH_RULE),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message: format!(
+                    "{} {:?} overrides the {} inherited from {:?}",
+                    method_type_label(&mismatch.method_type),
+                    mismatch.name,
+                    base_label,
+                    mismatch.base_class
+                ),
+                related_information: Some(vec![DiagnosticRelatedInformation {
+                    location: mismatch.base_location.clone(),
+                    message: format!(
+                        "{} {:?} declared in {:?}",
+                        base_label, mismatch.name, mismatch.base_class
+                    ),
+                }]),
+                ..Default::default()
+            }
+        })
+        .collect();
+    successful_exit("DIAGNOSTICS (no struct)", "dispatch_mismatch_diagnostics");
+    diagnostics
+}
+
+/// A method call passing more arguments than the method declares, or leaving out arguments it
+/// requires.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArityMismatch {
+    /// Qualified name of the called method, e.g. `MyApp.Order.Total`.
+    pub method: String,
+    /// Range of the call.
+    pub range: Range,
+    /// Number of argument positions the call fills or skips, e.g. 3 for `Total(a,,c)`.
+    pub passed: usize,
+    /// Number of arguments the method declares; `None` if it takes a variable number
+    /// (`args...`).
+    pub declared: Option<usize>,
+    /// Names of the required arguments the call leaves out.
+    pub missing: Vec<String>,
+}
+
+/// Finds the calls of `call_sites` whose arguments don't fit the method they resolve to.
+/// `declarations` returns the argument declarations of that method (see
+/// `Method::argument_declarations`).
+///
+/// An argument is required unless it has a default value or is an `Output` argument, whose
+/// value the caller may ignore. Skipped positions (`Total(a,,c)`) count as left out.
+pub fn find_arity_mismatches(
+    content: &str,
+    call_sites: &[MethodCallSite],
+    declarations: impl Fn(&MethodCallSite) -> Option<Vec<String>>,
+) -> Vec<ArityMismatch> {
+    start_of_function("DIAGNOSTICS (no struct)", "find_arity_mismatches");
+    let mut mismatches = Vec::new();
+    for call_site in call_sites {
+        let Some(declarations) = declarations(call_site) else {
+            continue;
+        };
+        let call = content
+            .get(call_site.call_range.start_byte..call_site.call_range.end_byte)
+            .unwrap_or_default();
+        // the argument ranges tell which positions are filled; commas before them, which
+        // position each fills
+        let arguments_start = call
+            .find(&format!(".{}(", call_site.callee_method))
+            .map(|n| call_site.call_range.start_byte + n + call_site.callee_method.len() + 2);
+        let filled: Vec<usize> = match arguments_start {
+            Some(start) => call_site
+                .arg_ranges
+                .iter()
+                .filter_map(|range| Some(top_level_commas(content.get(start..range.start_byte)?)))
+                .collect(),
+            None => Vec::new(),
+        };
+        let arguments = arguments_start
+            .and_then(|start| content.get(start..call_site.call_range.end_byte))
+            .map(argument_list)
+            .unwrap_or_default();
+        let passed = if arguments.trim().is_empty() {
+            0
+        } else {
+            top_level_commas(arguments) + 1
+        };
+        let names: Vec<&str> = declarations
+            .iter()
+            .map(|declaration| declared_argument_name(declaration).unwrap_or_default())
+            .collect();
+        let is_variadic = names.last().is_some_and(|name| name.ends_with("..."));
+        let declared = (!is_variadic).then_some(declarations.len());
+        let missing: Vec<String> = declarations
+            .iter()
+            .zip(&names)
+            .enumerate()
+            .filter(|(position, (declaration, name))| {
+                !name.ends_with("...")
+                    && !declaration.contains('=')
+                    && argument_mode(declaration) != ArgumentMode::Output
+                    && !filled.contains(position)
+            })
+            .map(|(_, (_, name))| name.to_string())
+            .collect();
+        if declared.is_some_and(|declared| passed > declared) || !missing.is_empty() {
+            mismatches.push(ArityMismatch {
+                method: format!("{}.{}", call_site.callee_class, call_site.callee_method),
+                range: call_site.call_range,
+                passed,
+                declared,
+                missing,
+            });
+        }
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "find_arity_mismatches");
+    mismatches
+}
+
+/// Returns the arguments at the start of `text`, up to the parenthesis closing the list.
+fn argument_list(text: &str) -> &str {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    for (n, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string && depth == 0 => return &text[..n],
+            ')' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Counts the commas of `text` outside parentheses and string literals.
+fn top_level_commas(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut commas = 0;
+    for c in text.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => commas += 1,
+            _ => {}
+        }
+    }
+    commas
+}
+
+/// Warns about calls passing more arguments than the called method declares or leaving out
+/// arguments it requires (see `find_arity_mismatches`).
+pub fn arity_diagnostics(content: &str, mismatches: &[ArityMismatch]) -> Vec<Diagnostic> {
+    start_of_function("DIAGNOSTICS (no struct)", "arity_diagnostics");
+    let diagnostics = mismatches
+        .iter()
+        .map(|mismatch| {
+            let message = match mismatch.declared {
+                Some(declared) if mismatch.passed > declared => format!(
+                    "{} takes at most {}; {} passed",
+                    mismatch.method,
+                    argument_count_text(declared),
+                    mismatch.passed
+                ),
+                _ => {
+                    let names: Vec<String> = mismatch
+                        .missing
+                        .iter()
+                        .map(|name| format!("{name:?}"))
+                        .collect();
+                    let noun = if names.len() == 1 {
+                        "argument"
+                    } else {
+                        "arguments"
+                    };
+                    format!(
+                        "{} is called without its required {noun} {}",
+                        mismatch.method,
+                        names.join(", ")
+                    )
+                }
+            };
+            Diagnostic {
+                range: ts_range_to_lsp_range(content, mismatch.range),
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: rule_code(ARGUMENT_COUNT_RULE),
+                code_description: rule_code_description(ARGUMENT_COUNT_RULE),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message,
+                ..Default::default()
+            }
+        })
+        .collect();
+    successful_exit("DIAGNOSTICS (no struct)", "arity_diagnostics");
+    diagnostics
+}
+
+/// Rule ID (`Diagnostic::code`) of the debug-write lint, also used to match quick fixes to its
+/// diagnostics.
+pub const DEBUG_WRITE_CODE: &str = "debug-write";
+
+/// A `write`/`zwrite` debugging statement found by `find_debug_writes`.
+#[derive(Clone, Debug)]
+pub struct DebugWrite {
+    /// Source range of the whole command.
+    pub range: Range,
+    /// Logging statement to replace the command with, if it could be converted.
+    pub replacement: Option<String>,
+}
+
+/// Finds every `write`/`zwrite` command in `tree`.
+///
+/// `template` is the configured logging statement; its `{message}` placeholder is substituted
+/// with the written expressions to build each `DebugWrite::replacement`.
+pub fn find_debug_writes(content: &str, tree: &Tree, template: &str) -> Vec<DebugWrite> {
+    start_of_function("DIAGNOSTICS (no struct)", "find_debug_writes");
+    let mut writes = Vec::new();
+    collect_debug_writes(tree.root_node(), content, template, &mut writes);
+    successful_exit("DIAGNOSTICS (no struct)", "find_debug_writes");
+    writes
+}
+
+/// Recursively collects `write`/`zwrite` commands below `node`.
+fn collect_debug_writes(node: Node, content: &str, template: &str, writes: &mut Vec<DebugWrite>) {
+    if node.kind().starts_with("command_") {
+        let Some(text) = content.get(node.byte_range()) else {
+            return;
+        };
+        // the command word, without a postconditional (`w:debug x`)
+        let command = text
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let is_zwrite = matches!(command.as_str(), "zw" | "zwrite");
+        if is_zwrite || matches!(command.as_str(), "w" | "write") {
+            writes.push(DebugWrite {
+                range: node.range(),
+                replacement: debug_write_replacement(text, is_zwrite, template),
+            });
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_debug_writes(child, content, template, writes);
+    }
+}
+
+/// Converts the text of a `write`/`zwrite` command into the configured logging statement.
+///
+/// Format controls (`!`, `#`, `?n`, `/mnemonic`) are dropped and the remaining expressions are
+/// concatenated; `zwrite x` logs `"x="_x`. Returns `None` for commands that can't be converted
+/// faithfully: postconditionals, argumentless commands, or commands writing nothing but format
+/// controls.
+pub(crate) fn debug_write_replacement(
+    text: &str,
+    is_zwrite: bool,
+    template: &str,
+) -> Option<String> {
+    let (command, arguments) = text.split_once(char::is_whitespace)?;
+    if command.contains(':') {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for argument in split_top_level_arguments(arguments.trim()) {
+        if is_zwrite {
+            parts.push(format!("\"{argument}=\"_{argument}"));
+        } else if !argument.starts_with(['!', '#', '?', '/']) {
+            parts.push(argument.to_string());
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    let separator = if is_zwrite { "_\", \"_" } else { "_" };
+    Some(template.replace("{message}", &parts.join(separator)))
+}
+
+/// Splits a command argument list on the commas that are not inside strings or parentheses.
+pub(crate) fn split_top_level_arguments(arguments: &str) -> Vec<&str> {
+    let mut parts = split_top_level(arguments);
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Splits `arguments` on the commas that are not inside strings or parentheses, keeping empty
+/// (omitted) arguments so positions are preserved.
+pub(crate) fn split_top_level(arguments: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut in_string, mut start) = (0usize, false, 0);
+    for (i, c) in arguments.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                parts.push(arguments[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(arguments[start..].trim());
+    parts
+}
+
+/// Builds the debug-write lint diagnostics for the statements found by `find_debug_writes`.
+pub fn debug_write_diagnostics(content: &str, writes: &[DebugWrite]) -> Vec<Diagnostic> {
+    writes
+        .iter()
+        .map(|write| Diagnostic {
+            range: ts_range_to_lsp_range(content, write.range),
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            code: rule_code(DEBUG_WRITE_CODE),
+            code_description: rule_code_description(DEBUG_WRITE_CODE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: "Debugging write statement; consider using the configured logging call"
+                .to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Rule ID (`Diagnostic::code`) of the dead store hint.
+pub const DEAD_STORE_CODE: &str = "dead-store";
+
+/// A `set` whose value is overwritten or killed before any read, found by `find_dead_stores`.
+#[derive(Clone, Debug)]
+pub struct DeadStore {
+    pub name: String,
+    /// Range of the assigned variable in the dead `set`.
+    pub range: Range,
+    /// Range of the variable in the `set` or `kill` discarding the value.
+    pub discarded_at: Range,
+    /// Whether the value is discarded by a `kill` rather than another `set`.
+    pub killed: bool,
+}
+
+/// Returns the local variable name of an `lvn` text, without subscripts.
+pub(crate) fn lvn_base_name(text: &str) -> &str {
+    text.split('(').next().unwrap_or_default().trim()
+}
+
+/// Returns `true` if `text` is an unsubscripted local variable name.
+pub(crate) fn is_simple_variable_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '%') && chars.all(char::is_alphanumeric)
+}
+
+/// Collects the base names of the local variables referenced below `node`.
+fn collect_lvn_reads(node: Node, content: &str, reads: &mut Vec<String>) {
+    if node.kind() == "lvn" {
+        if let Some(text) = content.get(node.byte_range()) {
+            reads.push(lvn_base_name(text).to_string());
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_lvn_reads(child, content, reads);
+    }
+}
+
+/// Returns `true` if a command node contains another command (a block or a command argument).
+fn has_nested_command(node: Node) -> bool {
+    let mut cursor = node.walk();
+    let nested = node
+        .children(&mut cursor)
+        .any(|child| child.kind().starts_with("command_") || has_nested_command(child));
+    nested
+}
+
+/// Finds `set x = ...` statements whose value is overwritten or killed before any read.
+///
+/// Only the straight-line, top-level statements of procedure-block methods are analyzed, in
+/// order: any statement that could read a variable indirectly or out of order (blocks, `do`,
+/// `goto`, `xecute`, indirection, postconditionals, labels) ends the tracking of every pending
+/// store. Public variables (`PublicList`, `%` names) and subscripted nodes are never reported.
+/// Values never read at all are left to `find_unused_variables`.
+pub fn find_dead_stores(content: &str, tree: &Tree, class_procedure_block: bool) -> Vec<DeadStore> {
+    start_of_function("DIAGNOSTICS (no struct)", "find_dead_stores");
+    let mut dead_stores = Vec::new();
+    for (method_definition, method_type) in method_definition_nodes(tree.root_node()) {
+        let Some((method, _)) = initial_build_method(method_definition, method_type, content)
+        else {
+            continue;
+        };
+        if !method.is_procedure_block.unwrap_or(class_procedure_block) {
+            continue;
+        }
+        let is_tracked = |name: &str| {
+            !name.starts_with('%') && !method.public_variables_declared.iter().any(|p| p == name)
+        };
+        for body in get_node_children(method_definition) {
+            if body.kind() != "core_method_body_content" {
+                continue;
+            }
+            // variable -> range of its last store not read yet
+            let mut pending: HashMap<String, Range> = HashMap::new();
+            for statement in get_node_children(body) {
+                if statement.kind().contains("comment") {
+                    continue;
+                }
+                let Some(command) = statement
+                    .named_child(0)
+                    .filter(|command| command.kind().starts_with("command_"))
+                else {
+                    pending.clear();
+                    continue;
+                };
+                let text = content.get(command.byte_range()).unwrap_or_default();
+                let postconditional = command
+                    .named_child(0)
+                    .and_then(|keyword| content.get(keyword.end_byte()..))
+                    .is_some_and(|rest| rest.starts_with(':'));
+                let opaque = postconditional
+                    || text.contains('@')
+                    || has_nested_command(command)
+                    || matches!(
+                        command.kind(),
+                        "command_do" | "command_goto" | "command_xecute" | "command_job"
+                    );
+                if opaque {
+                    pending.clear();
+                    continue;
+                }
+                match command.kind() {
+                    "command_set" => {
+                        for set_argument in get_node_children(command).into_iter().skip(1) {
+                            // child(0) is the target; `set (a, b) = ...`, `$piece` and
+                            // subscripted targets don't replace a whole variable
+                            let target = set_argument.named_child(0).filter(|target| {
+                                content
+                                    .get(target.byte_range())
+                                    .is_some_and(is_simple_variable_name)
+                            });
+                            // the value is evaluated before it is stored (`set x = x + 1`)
+                            let mut reads = Vec::new();
+                            match (target, set_argument.named_child(1)) {
+                                (Some(_), Some(value)) => {
+                                    collect_lvn_reads(value, content, &mut reads)
+                                }
+                                _ => collect_lvn_reads(set_argument, content, &mut reads),
+                            }
+                            for read in reads {
+                                pending.remove(&read);
+                            }
+                            let Some(target) = target else {
+                                continue;
+                            };
+                            let name = content.get(target.byte_range()).unwrap_or_default();
+                            if !is_tracked(name) {
+                                continue;
+                            }
+                            if let Some(range) = pending.insert(name.to_string(), target.range()) {
+                                dead_stores.push(DeadStore {
+                                    name: name.to_string(),
+                                    range,
+                                    discarded_at: target.range(),
+                                    killed: false,
+                                });
+                            }
+                        }
+                    }
+                    "command_kill" => {
+                        let arguments: Vec<Node> =
+                            get_node_children(command).into_iter().skip(1).collect();
+                        // argumentless and exclusive (`kill (a, b)`) kills discard everything
+                        let kills_all = arguments.is_empty()
+                            || arguments.iter().any(|argument| {
+                                content
+                                    .get(argument.byte_range())
+                                    .is_some_and(|text| text.starts_with('('))
+                            });
+                        if kills_all {
+                            pending.clear();
+                            continue;
+                        }
+                        for argument in arguments {
+                            let mut names = Vec::new();
+                            collect_lvn_reads(argument, content, &mut names);
+                            // only `kill x` discards the value; `kill x(1)` keeps the rest
+                            let name = content
+                                .get(argument.byte_range())
+                                .filter(|text| is_simple_variable_name(text));
+                            if let Some((name, range)) =
+                                name.and_then(|name| Some((name, pending.remove(name)?)))
+                            {
+                                dead_stores.push(DeadStore {
+                                    name: name.to_string(),
+                                    range,
+                                    discarded_at: argument.range(),
+                                    killed: true,
+                                });
+                            }
+                            for name in names {
+                                pending.remove(&name);
+                            }
+                        }
+                    }
+                    kind => {
+                        let mut reads = Vec::new();
+                        collect_lvn_reads(command, content, &mut reads);
+                        for read in reads {
+                            pending.remove(&read);
+                        }
+                        // the rest of the body is only reached through labels
+                        if matches!(kind, "command_quit" | "command_return") {
+                            pending.clear();
+                        }
+                    }
+                }
+            }
+        }
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "find_dead_stores");
+    dead_stores
+}
+
+/// Builds the dead store hints for the stores found by `find_dead_stores` in the document at
+/// `url`.
+pub fn dead_store_diagnostics(content: &str, url: &Url, stores: &[DeadStore]) -> Vec<Diagnostic> {
+    stores
+        .iter()
+        .map(|store| {
+            let discarded_by = if store.killed {
+                "killed"
+            } else {
+                "overwritten"
+            };
+            Diagnostic {
+                range: ts_range_to_lsp_range(content, store.range),
+                severity: Some(DiagnosticSeverity::HINT),
+                code: rule_code(DEAD_STORE_CODE),
+                code_description: rule_code_description(DEAD_STORE_CODE),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message: format!(
+                    "Value assigned to {:?} is {discarded_by} before it is read",
+                    store.name
+                ),
+                related_information: Some(vec![DiagnosticRelatedInformation {
+                    location: Location::new(
+                        url.clone(),
+                        ts_range_to_lsp_range(content, store.discarded_at),
+                    ),
+                    message: format!("{:?} {discarded_by} here", store.name),
+                }]),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Rule ID (`Diagnostic::code`) of the unused variable hint.
+pub const UNUSED_VARIABLE_CODE: &str = "unused-variable";
+
+/// A `set` of a private variable that nothing ever reads, found by `find_unused_variables`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnusedVariable {
+    pub name: String,
+    /// Range of the assigned variable in the `set`.
+    pub range: Range,
+    /// Range to delete to remove the assignment, if its value can't have side effects (see
+    /// `assignment_removal`).
+    pub removal: Option<Range>,
+}
+
+/// Finds the private variables of procedure-block methods that are set but never read: every
+/// `set x = ...` of a variable no other statement of the method references.
+///
+/// Any reference counts as a read, including subscripted sets, `kill`, passing by reference,
+/// and embedded SQL host variables (`:x`). Arguments, public variables (`PublicList`, `%`
+/// names), and methods using indirection or `xecute` are never reported.
+pub fn find_unused_variables(
+    content: &str,
+    tree: &Tree,
+    class_procedure_block: bool,
+) -> Vec<UnusedVariable> {
+    start_of_function("DIAGNOSTICS (no struct)", "find_unused_variables");
+    let mut unused = Vec::new();
+    for (method_definition, method_type) in method_definition_nodes(tree.root_node()) {
+        let Some((method, _)) = initial_build_method(method_definition, method_type, content)
+        else {
+            continue;
+        };
+        if !method.is_procedure_block.unwrap_or(class_procedure_block) {
+            continue;
+        }
+        for body in get_node_children(method_definition) {
+            if body.kind() != "core_method_body_content" {
+                continue;
+            }
+            let text = content.get(body.byte_range()).unwrap_or_default();
+            if text.contains('@') || text.to_ascii_lowercase().contains("xecute") {
+                continue;
+            }
+            let mut sets: Vec<UnusedVariable> = Vec::new();
+            let mut reads = Vec::new();
+            let mut stack = vec![body];
+            while let Some(node) = stack.pop() {
+                if node.kind() == "command_set" {
+                    // child(0) is the keyword; each set_argument is `target = value`
+                    let set_arguments: Vec<Node> =
+                        get_node_children(node).into_iter().skip(1).collect();
+                    for (i, set_argument) in set_arguments.iter().enumerate() {
+                        let target = set_argument.named_child(0).filter(|target| {
+                            content
+                                .get(target.byte_range())
+                                .is_some_and(is_simple_variable_name)
+                        });
+                        match (target, set_argument.named_child(1)) {
+                            (Some(target), Some(value)) => {
+                                let name = content.get(target.byte_range()).unwrap_or_default();
+                                sets.push(UnusedVariable {
+                                    name: name.to_string(),
+                                    range: target.range(),
+                                    removal: assignment_removal(
+                                        content,
+                                        node,
+                                        &set_arguments,
+                                        i,
+                                        value,
+                                    ),
+                                });
+                                stack.push(value);
+                            }
+                            _ => stack.push(*set_argument),
+                        }
+                    }
+                    continue;
+                }
+                if node.kind() == "lvn" {
+                    if let Some(text) = content.get(node.byte_range()) {
+                        reads.push(lvn_base_name(text).to_string());
+                    }
+                }
+                let mut cursor = node.walk();
+                stack.extend(node.children(&mut cursor));
+            }
+            let is_unused = |name: &str| {
+                !name.starts_with('%')
+                    && !method.arguments.iter().any(|argument| argument == name)
+                    && !method.public_variables_declared.iter().any(|p| p == name)
+                    && !reads.iter().any(|read| read == name)
+                    && !text.contains(&format!(":{name}"))
+            };
+            unused.extend(sets.into_iter().filter(|set| is_unused(&set.name)));
+        }
+    }
+    unused.sort_by_key(|variable| variable.range.start_byte);
+    successful_exit("DIAGNOSTICS (no struct)", "find_unused_variables");
+    unused
+}
+
+/// Returns the range to delete to remove the assignment `set_arguments[i]` of the `set` command
+/// `command`, or `None` if its `value` may have side effects (a call, indirection, or anything
+/// with a `$` or `.` other than a number) or the command has a postconditional.
+///
+/// The only assignment of a `set` is removed with its command, and with its line if nothing else
+/// is on it; otherwise the assignment is removed with the comma separating it from a neighbour.
+fn assignment_removal(
+    content: &str,
+    command: Node,
+    set_arguments: &[Node],
+    i: usize,
+    value: Node,
+) -> Option<Range> {
+    let value_text = content.get(value.byte_range())?;
+    if value_text.contains(['(', '$', '@', '.']) && value_text.parse::<f64>().is_err() {
+        return None;
+    }
+    let postconditional = command
+        .named_child(0)
+        .and_then(|keyword| content.get(keyword.end_byte()..))
+        .is_some_and(|rest| rest.starts_with(':'));
+    if postconditional {
+        return None;
+    }
+    let (start, end) = match set_arguments {
+        [_] => {
+            let (start, end) = (command.start_byte(), command.end_byte());
+            let line_start = content[..start].rfind('\n').map_or(0, |n| n + 1);
+            let line_end = content[end..].find('\n').map_or(content.len(), |n| end + n);
+            if content[line_start..start].trim().is_empty()
+                && content[end..line_end].trim().is_empty()
+            {
+                (line_start, (line_end + 1).min(content.len()))
+            } else {
+                let rest = &content[end..line_end];
+                (start, end + rest.len() - rest.trim_start().len())
+            }
+        }
+        _ if i > 0 => (set_arguments[i - 1].end_byte(), set_arguments[i].end_byte()),
+        _ => (
+            set_arguments[i].start_byte(),
+            set_arguments.get(i + 1)?.start_byte(),
+        ),
+    };
+    Some(byte_range(content, start, end))
+}
+
+/// Builds the hints for the variables found by `find_unused_variables`, tagged as unnecessary
+/// so editors gray them out.
+pub fn unused_variable_diagnostics(content: &str, variables: &[UnusedVariable]) -> Vec<Diagnostic> {
+    variables
+        .iter()
+        .map(|variable| Diagnostic {
+            range: ts_range_to_lsp_range(content, variable.range),
+            severity: Some(DiagnosticSeverity::HINT),
+            code: rule_code(UNUSED_VARIABLE_CODE),
+            code_description: rule_code_description(UNUSED_VARIABLE_CODE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!("Variable {:?} is set but never used", variable.name),
+            tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Rule ID (`Diagnostic::code`) of the unreachable code hint.
+pub const UNREACHABLE_CODE: &str = "unreachable-code";
+
+/// Commands (and their abbreviations) that always leave the block they are in.
+const EXIT_COMMANDS: [(&str, &str); 5] = [
+    ("quit", "quit"),
+    ("q", "quit"),
+    ("return", "return"),
+    ("ret", "return"),
+    ("throw", "throw"),
+];
+
+/// Statements that can't run because they follow an unconditional `quit`, `return`, or `throw`
+/// in the same block, found by `find_unreachable_code`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnreachableCode {
+    /// The exiting command, without abbreviation: `quit`, `return`, or `throw`.
+    pub after: &'static str,
+    /// Range from the start of the first unreachable statement to the end of the last one.
+    pub range: Range,
+}
+
+/// Finds the unreachable statements of the methods of `tree` (see `unreachable_code_in`).
+pub fn find_unreachable_code(content: &str, tree: &Tree) -> Vec<UnreachableCode> {
+    start_of_function("DIAGNOSTICS (no struct)", "find_unreachable_code");
+    let mut unreachable = Vec::new();
+    for (method_definition, _) in method_definition_nodes(tree.root_node()) {
+        let start = method_definition.start_byte();
+        let Some((body_start, body_end)) = content
+            .get(method_definition.byte_range())
+            .and_then(braced_content)
+        else {
+            continue;
+        };
+        unreachable.extend(unreachable_code_in(
+            content,
+            start + body_start..start + body_end,
+        ));
+    }
+    successful_exit("DIAGNOSTICS (no struct)", "find_unreachable_code");
+    unreachable
+}
+
+/// Finds the statements of the method body at `content[bytes]` that follow a line starting
+/// with an unconditional `quit`, `return`, or `throw` in the same block: everything up to the
+/// `}` closing the block, including nested blocks.
+///
+/// This is a lexical scan of whole lines. A postconditional (`quit:done`) makes the command
+/// conditional, and a label (a line starting at column 0) or a preprocessor directive (`#if`)
+/// makes the code after it reachable again; commands following the exit on its own line aren't
+/// reported.
+pub fn unreachable_code_in(content: &str, bytes: std::ops::Range<usize>) -> Vec<UnreachableCode> {
+    let Some(body) = content.get(bytes.clone()) else {
+        return Vec::new();
+    };
+    let mut unreachable = Vec::new();
+    let mut depth = 0usize;
+    // depth of the block the last exit is in, and the exiting command
+    let mut exit: Option<(usize, &'static str)> = None;
+    let mut region: Option<(usize, usize)> = None;
+    let mut flush = |after: &'static str, region: &mut Option<(usize, usize)>| {
+        if let Some((start, end)) = region.take() {
+            unreachable.push(UnreachableCode {
+                after,
+                range: byte_range(content, start, end),
+            });
+        }
+    };
+    let mut line_start = bytes.start;
+    for line in body.split_inclusive('\n') {
+        let start = line_start;
+        line_start += line.len();
+        let code = line.trim_end();
+        let trimmed = code.trim_start();
+        if let Some((exit_depth, after)) = exit {
+            let is_label = !code.is_empty()
+                && !code.starts_with(char::is_whitespace)
+                && !trimmed.starts_with('}');
+            let is_directive = trimmed.starts_with('#') && !trimmed.starts_with("#;");
+            if is_label || is_directive || (depth == exit_depth && trimmed.starts_with('}')) {
+                flush(after, &mut region);
+                exit = None;
+            } else if !trimmed.is_empty() && !is_comment_line(trimmed) {
+                let statement_start = start + code.len() - trimmed.len();
+                let first = region.map_or(statement_start, |(first, _)| first);
+                region = Some((first, start + code.len()));
+            }
+        }
+        let (opens, closes) = brace_counts(code);
+        depth = (depth + opens).saturating_sub(closes);
+        if let Some((exit_depth, after)) = exit {
+            if depth < exit_depth {
+                flush(after, &mut region);
+                exit = None;
+            }
+        }
+        if exit.is_none() && opens == 0 && closes == 0 {
+            exit = exit_command(trimmed).map(|after| (depth, after));
+        }
+    }
+    if let Some((_, after)) = exit {
+        flush(after, &mut region);
+    }
+    unreachable
+}
+
+/// Returns the command `statement` starts with if it is an exit without a postconditional, e.g.
+/// `quit` for `q sc`.
+fn exit_command(statement: &str) -> Option<&'static str> {
+    let length = statement
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(statement.len());
+    let (keyword, rest) = statement.split_at(length);
+    if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    EXIT_COMMANDS
+        .iter()
+        .find(|(command, _)| names_match(IdentifierKind::Command, command, keyword))
+        .map(|(_, after)| *after)
+}
+
+/// Returns `true` if the trimmed line `line` is only a comment.
+fn is_comment_line(line: &str) -> bool {
+    ["//", ";", "#;", "/*", "*"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+/// Counts the `{` and `}` of the line `code`, outside string literals and comments.
+pub(crate) fn brace_counts(code: &str) -> (usize, usize) {
+    let mut counts = (0, 0);
+    let mut in_string = false;
+    for (n, c) in code.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            ';' => break,
+            '/' if code[n..].starts_with("//") => break,
+            '{' => counts.0 += 1,
+            '}' => counts.1 += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// Builds the hints for the code found by `find_unreachable_code`, tagged as unnecessary so
+/// editors gray it out.
+pub fn unreachable_code_diagnostics(
+    content: &str,
+    unreachable: &[UnreachableCode],
+) -> Vec<Diagnostic> {
+    unreachable
+        .iter()
+        .map(|code| Diagnostic {
+            range: ts_range_to_lsp_range(content, code.range),
+            severity: Some(DiagnosticSeverity::HINT),
+            code: rule_code(UNREACHABLE_CODE),
+            code_description: rule_code_description(UNREACHABLE_CODE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!("Unreachable code after an unconditional `{}`", code.after),
+            tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Matches `text` against a glob `pattern` where `*` matches any run of characters and `?`
+/// matches exactly one. Matching is case-insensitive, like ObjectScript class names.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in pattern, and the text position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // let the last `*` swallow one more character and retry
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Returns `true` if the class `class_name` defined in `url` is considered generated code.
+///
+/// A class is generated if it declares the `GeneratedBy` class keyword, or if any of
+/// `config.generated_class_patterns` matches its class name or its file name.
+pub fn is_generated_class(
+    config: &Config,
+    class_name: &str,
+    url: &Url,
+    generated_by: bool,
+) -> bool {
+    if generated_by {
+        return true;
+    }
+    let file_name = url.path().rsplit('/').next().unwrap_or_default();
+    config
+        .generated_class_patterns
+        .iter()
+        .any(|pattern| glob_matches(pattern, class_name) || glob_matches(pattern, file_name))
+}
+
+/// Applies the configured generated-class policy to a document's diagnostics.
+///
+/// With `GeneratedClassSeverity::Hint` every diagnostic is downgraded to a hint; with
+/// `GeneratedClassSeverity::Off` they are dropped.
+pub fn downgrade_generated_diagnostics(
+    diagnostics: Vec<Diagnostic>,
+    severity: GeneratedClassSeverity,
+) -> Vec<Diagnostic> {
+    match severity {
+        GeneratedClassSeverity::Off => Vec::new(),
+        GeneratedClassSeverity::Hint => diagnostics
+            .into_iter()
+            .map(|mut diagnostic| {
+                diagnostic.severity = Some(DiagnosticSeverity::HINT);
+                diagnostic
+            })
+            .collect(),
+    }
+}
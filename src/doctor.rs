@@ -0,0 +1,243 @@
+use crate::common::{start_of_function, successful_exit, PositionEncoding};
+use crate::system_api::SYSTEM_CLASSES;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tower_lsp::lsp_types::PositionEncodingKind;
+use tree_sitter::{Language, Parser, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+use tree_sitter_objectscript::{
+    LANGUAGE_OBJECTSCRIPT, LANGUAGE_OBJECTSCRIPT_CORE, OBJECTSCRIPT_NODE_TYPES,
+};
+
+/// Node kinds the indexer looks up by name; a grammar without them parses, but indexes nothing.
+const REQUIRED_NODE_KINDS: [&str; 7] = [
+    "class_definition",
+    "class_body",
+    "class_statement",
+    "class_extends",
+    "classmethod",
+    "method_definition",
+    "query",
+];
+
+/// Class the grammar self-check parses.
+const SAMPLE_CLASS: &str = "Class Doctor.Check Extends %RegisteredObject\n{\n\n\
+    ClassMethod Run() As %Status\n{\n    quit $$$OK\n}\n}\n";
+
+/// Outcome of one `objectscript.doctor` check.
+#[derive(Clone, Debug)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    /// What was found, or what is wrong and how it shows.
+    pub detail: String,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, ok: bool, detail: String) -> Self {
+        Self { name, ok, detail }
+    }
+}
+
+/// Checks that both embedded grammars have an ABI version the tree-sitter runtime supports and
+/// that the class grammar parses a small class without errors.
+pub fn check_grammar() -> DoctorCheck {
+    start_of_function("DOCTOR (no struct)", "check_grammar");
+    let supported = MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION;
+    let mut problems = Vec::new();
+    let mut versions = Vec::new();
+    for (name, language) in [
+        ("class", Language::new(LANGUAGE_OBJECTSCRIPT)),
+        ("routine", Language::new(LANGUAGE_OBJECTSCRIPT_CORE)),
+    ] {
+        let abi = language.abi_version();
+        let version = language.metadata().map_or(String::new(), |metadata| {
+            format!(
+                " v{}.{}.{}",
+                metadata.major_version, metadata.minor_version, metadata.patch_version
+            )
+        });
+        versions.push(format!("{name}{version} (ABI {abi})"));
+        if !supported.contains(&abi) {
+            problems.push(format!(
+                "{name} grammar ABI {abi} is outside the supported range {}..={}",
+                supported.start(),
+                supported.end()
+            ));
+        }
+    }
+    if problems.is_empty() {
+        let mut parser = Parser::new();
+        match parser.set_language(&LANGUAGE_OBJECTSCRIPT.into()) {
+            Err(e) => problems.push(format!("failed to load the class grammar: {e}")),
+            Ok(()) => match parser.parse(SAMPLE_CLASS, None) {
+                None => problems.push("the test parse was cancelled".to_string()),
+                Some(tree) if tree.root_node().has_error() => {
+                    problems.push("the test class parsed with errors".to_string())
+                }
+                Some(_) => {}
+            },
+        }
+    }
+    successful_exit("DOCTOR (no struct)", "check_grammar");
+    let ok = problems.is_empty();
+    let detail = if ok {
+        format!("{}; test parse succeeded", versions.join(", "))
+    } else {
+        format!("{}; {}", versions.join(", "), problems.join("; "))
+    };
+    DoctorCheck::new("grammar", ok, detail)
+}
+
+/// Checks that the embedded node-types registry is valid JSON, that every named node type it
+/// lists exists in the class grammar, and that the node kinds the indexer relies on are there.
+pub fn check_node_types() -> DoctorCheck {
+    start_of_function("DOCTOR (no struct)", "check_node_types");
+    let Ok(Value::Array(node_types)) = serde_json::from_str::<Value>(OBJECTSCRIPT_NODE_TYPES)
+    else {
+        successful_exit("DOCTOR (no struct)", "check_node_types");
+        return DoctorCheck::new(
+            "node types",
+            false,
+            "node-types.json isn't a JSON array".to_string(),
+        );
+    };
+    let language = Language::new(LANGUAGE_OBJECTSCRIPT);
+    let named: Vec<&str> = node_types
+        .iter()
+        .filter(|node_type| node_type.get("named").and_then(Value::as_bool) == Some(true))
+        .filter_map(|node_type| node_type.get("type").and_then(Value::as_str))
+        .collect();
+    // supertypes (e.g. `_expression`) are listed but never produced
+    let unknown: Vec<&str> = named
+        .iter()
+        .copied()
+        .filter(|kind| !kind.starts_with('_') && language.id_for_node_kind(kind, true) == 0)
+        .collect();
+    let missing: Vec<&str> = REQUIRED_NODE_KINDS
+        .into_iter()
+        .filter(|kind| !named.contains(kind))
+        .collect();
+    let mut problems = Vec::new();
+    if !unknown.is_empty() {
+        problems.push(format!(
+            "not in the grammar: {} (node-types.json is out of date)",
+            unknown.join(", ")
+        ));
+    }
+    if !missing.is_empty() {
+        problems.push(format!(
+            "missing: {} (classes won't be indexed)",
+            missing.join(", ")
+        ));
+    }
+    successful_exit("DOCTOR (no struct)", "check_node_types");
+    if problems.is_empty() {
+        let detail = format!("{} named node types, all in the grammar", named.len());
+        DoctorCheck::new("node types", true, detail)
+    } else {
+        DoctorCheck::new("node types", false, problems.join("; "))
+    }
+}
+
+/// Reports the bundled `%SYSTEM` stubs, and whether any workspace uses them
+/// (`system_api_stubs`).
+pub fn check_system_stubs(enabled: bool) -> DoctorCheck {
+    let methods: usize = SYSTEM_CLASSES.iter().map(|class| class.methods.len()).sum();
+    let ok = !SYSTEM_CLASSES.is_empty() && methods > 0;
+    let mut detail = format!(
+        "{} classes, {methods} methods bundled",
+        SYSTEM_CLASSES.len()
+    );
+    if !enabled {
+        detail.push_str("; disabled by `system_api_stubs` in every workspace");
+    }
+    DoctorCheck::new("$SYSTEM stubs", ok, detail)
+}
+
+/// Checks that the server can write to each workspace root, where `objectscript.exportTags`
+/// writes its files.
+///
+/// The server keeps its index in memory and has no cache directory of its own, so the workspace
+/// roots are the only places it writes to. Non-file workspaces (`None`) are read and written
+/// through the client and are skipped.
+pub fn check_writable(roots: &[Option<&Path>]) -> DoctorCheck {
+    start_of_function("DOCTOR (no struct)", "check_writable");
+    let mut problems = Vec::new();
+    let mut checked = 0;
+    for root in roots.iter().flatten() {
+        checked += 1;
+        let probe = root.join(".objectscript-lsp-doctor");
+        match fs::write(&probe, b"") {
+            Ok(()) => {
+                if let Err(e) = fs::remove_file(&probe) {
+                    eprintln!("Warning: failed to remove {}: {e}", probe.display());
+                }
+            }
+            Err(e) => problems.push(format!("{}: {e}", root.display())),
+        }
+    }
+    successful_exit("DOCTOR (no struct)", "check_writable");
+    if problems.is_empty() {
+        let detail = format!(
+            "no cache directory (the index is in memory); {checked} workspace root(s) writable"
+        );
+        DoctorCheck::new("writable directories", true, detail)
+    } else {
+        let detail = format!("not writable: {}", problems.join("; "));
+        DoctorCheck::new("writable directories", false, detail)
+    }
+}
+
+/// Checks that the client offered a position encoding the server supports.
+///
+/// `client_encodings` is `general.positionEncodings` from the client's capabilities; clients
+/// that don't send it only support UTF-16.
+pub fn check_position_encoding(client_encodings: Option<&[PositionEncodingKind]>) -> DoctorCheck {
+    match PositionEncoding::negotiate(client_encodings) {
+        Some(encoding) if client_encodings.is_none() => DoctorCheck::new(
+            "position encoding",
+            true,
+            format!("{} (client default)", encoding.kind().as_str()),
+        ),
+        Some(encoding) => DoctorCheck::new(
+            "position encoding",
+            true,
+            format!("{} (negotiated)", encoding.kind().as_str()),
+        ),
+        None => {
+            let offered: Vec<&str> = client_encodings
+                .unwrap_or_default()
+                .iter()
+                .map(PositionEncodingKind::as_str)
+                .collect();
+            DoctorCheck::new(
+                "position encoding",
+                false,
+                format!(
+                    "the server supports utf-8, utf-16 and utf-32 but the client only offered \
+                     {}; positions on lines with non-ASCII characters may be off",
+                    offered.join(", ")
+                ),
+            )
+        }
+    }
+}
+
+/// Renders the checks as a plain-text report to paste into bug reports.
+pub fn render_report(checks: &[DoctorCheck]) -> String {
+    let mut report = format!(
+        "objectscript-lsp {} self-check\n\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for check in checks {
+        let status = if check.ok { "ok" } else { "FAIL" };
+        report.push_str(&format!("[{status}] {}: {}\n", check.name, check.detail));
+    }
+    let failed = checks.iter().filter(|check| !check.ok).count();
+    report.push_str(&match failed {
+        0 => "\nNo problems found.\n".to_string(),
+        n => format!("\n{n} problem(s) found.\n"),
+    });
+    report
+}
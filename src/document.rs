@@ -1,6 +1,122 @@
+use crate::common::{advance_point, position_encoding};
 use crate::parse_structures::{ClassId, FileType, LocalSemanticModelId};
 use crate::scope_tree::*;
-use tree_sitter::Tree;
+use crate::symbol_index::SymbolLocationIndex;
+use ropey::Rope;
+use std::fmt::Debug;
+use std::sync::{Arc, OnceLock};
+use tower_lsp::lsp_types::Position;
+use tree_sitter::{InputEdit, Parser, Point, Tree};
+
+/// The text of a document, stored as a rope so edits and position lookups don't copy or scan
+/// the whole text, and cheap to clone.
+///
+/// Most analyses need the text as one `&str`; `as_str` builds it on first use after an edit and
+/// shares it between clones. Lines are only split at `\n`, like Tree-sitter rows.
+#[derive(Clone, Default)]
+pub struct TextBuffer {
+    rope: Rope,
+    /// The whole text, built from `rope` on first use.
+    text: OnceLock<Arc<str>>,
+}
+
+impl Debug for TextBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextBuffer")
+            .field("len", &self.rope.len_bytes())
+            .field("lines", &self.rope.len_lines())
+            .finish()
+    }
+}
+
+impl From<&str> for TextBuffer {
+    fn from(text: &str) -> Self {
+        Self {
+            rope: Rope::from_str(text),
+            text: OnceLock::from(Arc::from(text)),
+        }
+    }
+}
+
+impl From<String> for TextBuffer {
+    fn from(text: String) -> Self {
+        Self {
+            rope: Rope::from_str(&text),
+            text: OnceLock::from(Arc::from(text)),
+        }
+    }
+}
+
+impl TextBuffer {
+    /// Returns the whole text.
+    pub fn as_str(&self) -> &str {
+        self.text.get_or_init(|| Arc::from(self.rope.to_string()))
+    }
+
+    /// Converts an LSP `position` (columns in the negotiated `position_encoding`) into a byte
+    /// offset and a Tree-sitter `Point`.
+    ///
+    /// Like `position_to_point`, a column past the end of its line is clamped to the line's end,
+    /// and a line past the end of the text maps to the end of the text.
+    pub fn position_to_byte(&self, position: Position) -> (usize, Point) {
+        let row = position.line as usize;
+        if row >= self.rope.len_lines() {
+            return (self.rope.len_bytes(), Point { row, column: 0 });
+        }
+        let line_start = self.rope.line_to_byte(row);
+        let encoding = position_encoding();
+        let mut remaining = position.character as usize;
+        let mut column = 0;
+        for c in self.rope.line(row).chars() {
+            let len = encoding.char_len(c);
+            // don't split a surrogate pair
+            if c == '\n' || remaining < len {
+                break;
+            }
+            remaining -= len;
+            column += c.len_utf8();
+        }
+        (line_start + column, Point { row, column })
+    }
+
+    /// Replaces the text between the LSP positions `start` and `end` with `new_text`, returning
+    /// the `InputEdit` to apply to the previous tree.
+    pub fn edit(&mut self, start: Position, end: Position, new_text: &str) -> InputEdit {
+        let (start_byte, start_position) = self.position_to_byte(start);
+        let (old_end_byte, old_end_position) = self.position_to_byte(end);
+        let old_end_byte = old_end_byte.max(start_byte);
+        let start_char = self.rope.byte_to_char(start_byte);
+        self.rope
+            .remove(start_char..self.rope.byte_to_char(old_end_byte));
+        self.rope.insert(start_char, new_text);
+        self.text = OnceLock::new();
+        InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte: start_byte + new_text.len(),
+            start_position,
+            old_end_position,
+            new_end_position: advance_point(start_position.row, start_position.column, new_text),
+        }
+    }
+
+    /// Parses the text with `parser`, reading it chunk by chunk from the rope; see
+    /// `Parser::parse`.
+    pub fn parse(&self, parser: &mut Parser, old_tree: Option<&Tree>) -> Option<Tree> {
+        let len = self.rope.len_bytes();
+        parser.parse_with_options(
+            &mut |byte, _| {
+                if byte >= len {
+                    return &[][..];
+                }
+                let (chunk, chunk_start, _, _) = self.rope.chunk_at_byte(byte);
+                &chunk.as_bytes()[byte - chunk_start..]
+            },
+            old_tree,
+            None,
+        )
+    }
+}
 
 /// Holds the current text (`content`), its parsed Tree-sitter syntax tree (`tree`),
 /// derived semantic and scope state (scope tree + loca). `version` is `None` until the
@@ -8,7 +124,7 @@ use tree_sitter::Tree;
 #[derive(Clone, Debug)]
 pub struct Document {
     /// Full file contents.
-    pub(crate) content: String,
+    pub(crate) content: TextBuffer,
     /// Latest Tree-Sitter tree for this file.
     pub(crate) tree: Tree,
     /// LSP document version, `None` until document is opened.
@@ -23,6 +139,8 @@ pub struct Document {
     pub(crate) class_id: Option<ClassId>,
     /// Name of Class
     pub(crate) class_name: String,
+    /// Maps byte ranges to the symbols/references at that location, rebuilt after each analysis.
+    pub(crate) symbol_index: SymbolLocationIndex,
 }
 
 impl Document {
@@ -32,7 +150,7 @@ impl Document {
     /// initial `ScopeTree`. Semantic ids (`local_semantic_model_id`, `class_id`) are set to `None`
     /// and filled in during indexing/build steps.
     pub fn new(
-        content: String,
+        content: TextBuffer,
         tree: Tree,
         file_type: FileType,
         class_name: String,
@@ -48,6 +166,7 @@ impl Document {
             local_semantic_model_id: None,
             class_id: None,
             class_name,
+            symbol_index: SymbolLocationIndex::new(),
         }
     }
 }
@@ -0,0 +1,270 @@
+use crate::common::{get_node_children, start_of_function, successful_exit};
+use crate::diagnostics::{is_simple_variable_name, split_top_level};
+use tree_sitter::{Node, Point, Range, Tree};
+
+/// Functions returning elements of a list.
+const LIST_FUNCTIONS: [&str; 4] = ["$list", "$li", "$listget", "$lg"];
+
+/// Functions returning pieces of a delimited string.
+const PIECE_FUNCTIONS: [&str; 2] = ["$piece", "$p"];
+
+/// Functions building a list.
+const LISTBUILD_FUNCTIONS: [&str; 2] = ["$listbuild", "$lb"];
+
+/// How many variable assignments and nested calls `resolve_value` follows before giving up, so
+/// self-referencing assignments (`set x = $list(x, 2)`) terminate.
+const MAX_RESOLVE_DEPTH: usize = 8;
+
+/// What is statically known about the value of an expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KnownValue {
+    /// A `$listbuild` list, as the source text of its elements (empty for omitted elements).
+    List(Vec<String>),
+    /// A string literal, unquoted.
+    String(String),
+    /// A numeric literal.
+    Number(String),
+}
+
+impl KnownValue {
+    /// Name of the value's type, e.g. `%List` or `%Integer`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            KnownValue::List(_) => "%List",
+            KnownValue::String(_) => "%String",
+            KnownValue::Number(number) if number.parse::<i64>().is_ok() => "%Integer",
+            KnownValue::Number(_) => "%Numeric",
+        }
+    }
+
+    /// Renders the value as ObjectScript source.
+    pub fn source(&self) -> String {
+        match self {
+            KnownValue::List(elements) => format!("$lb({})", elements.join(",")),
+            KnownValue::String(value) => format!("\"{}\"", value.replace('"', "\"\"")),
+            KnownValue::Number(number) => number.clone(),
+        }
+    }
+}
+
+/// Splits `text` of the form `$name(arguments)` into the lowercase function name and its
+/// arguments.
+///
+/// Returns `None` if `text` isn't a single intrinsic function call (extrinsic `$$` calls
+/// included).
+pub fn function_call(text: &str) -> Option<(String, Vec<&str>)> {
+    let text = text.trim();
+    let open = text.find('(')?;
+    let name = &text[..open];
+    let letters = name.strip_prefix('$')?;
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let arguments = text[open + 1..].strip_suffix(')')?;
+    // the final parenthesis must close the opening one: `$p(a)_$p(b)` is not a single call
+    let (mut depth, mut in_string) = (0usize, false);
+    for c in arguments.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    if depth != 0 || in_string {
+        return None;
+    }
+    Some((name.to_ascii_lowercase(), split_top_level(arguments)))
+}
+
+/// Parses a string literal (where `""` is an escaped quote) into its value.
+fn string_literal(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let value = inner.replace("\"\"", "\"");
+    // a lone quote inside means `text` is several strings, e.g. `"a"_"b"`
+    (value.matches('"').count() == inner.matches("\"\"").count()).then_some(value)
+}
+
+/// Returns `true` if `text` is a numeric literal.
+fn is_number_literal(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.')
+        && text.parse::<f64>().is_ok()
+}
+
+/// Parses an integer argument such as a list position or piece number.
+fn integer_argument(argument: Option<&&str>, default: i64) -> Option<i64> {
+    match argument {
+        None | Some(&"") => Some(default),
+        Some(text) => text.parse().ok(),
+    }
+}
+
+/// Resolves what is statically known about `expression`.
+///
+/// Literals, `$listbuild` lists, and `$list`/`$listget`/`$piece` calls on known values are
+/// resolved, following variables through `lookup`, which returns the expression last assigned to
+/// a variable. Returns `None` for anything else.
+pub fn resolve_value(
+    expression: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    depth: usize,
+) -> Option<KnownValue> {
+    if depth > MAX_RESOLVE_DEPTH {
+        return None;
+    }
+    let expression = expression.trim();
+    if let Some(value) = string_literal(expression) {
+        return Some(KnownValue::String(value));
+    }
+    if is_number_literal(expression) {
+        return Some(KnownValue::Number(expression.to_string()));
+    }
+    if is_simple_variable_name(expression) {
+        return resolve_value(lookup(expression)?.as_str(), lookup, depth + 1);
+    }
+    let (function, arguments) = function_call(expression)?;
+    let function = function.as_str();
+    if LISTBUILD_FUNCTIONS.contains(&function) {
+        return Some(KnownValue::List(
+            arguments.iter().map(|a| a.to_string()).collect(),
+        ));
+    }
+    let source = resolve_value(arguments.first()?, lookup, depth + 1)?;
+    if LIST_FUNCTIONS.contains(&function) {
+        let KnownValue::List(elements) = source else {
+            return None;
+        };
+        // positions are 1-based; -1 is the last element
+        let position = |argument: Option<&&str>, default: i64| {
+            let position = integer_argument(argument, default)?;
+            let index = if position == -1 {
+                elements.len().checked_sub(1)?
+            } else {
+                usize::try_from(position).ok()?.checked_sub(1)?
+            };
+            Some(index)
+        };
+        let from = position(arguments.get(1), 1)?;
+        // `$listget` takes a default instead of an end position
+        if arguments.len() > 2 && matches!(function, "$list" | "$li") {
+            let to = position(arguments.get(2), 1)?;
+            return Some(KnownValue::List(elements.get(from..=to)?.to_vec()));
+        }
+        let element = elements.get(from)?;
+        return resolve_value(element, lookup, depth + 1);
+    }
+    if PIECE_FUNCTIONS.contains(&function) {
+        let KnownValue::String(value) = source else {
+            return None;
+        };
+        let delimiter = string_literal(arguments.get(1)?)?;
+        if delimiter.is_empty() {
+            return None;
+        }
+        // piece 0 and below select nothing before piece 1
+        let from = integer_argument(arguments.get(2), 1)?;
+        let to = integer_argument(arguments.get(3), from)?;
+        let from = usize::try_from(from.max(1)).ok()?;
+        let Ok(to) = usize::try_from(to) else {
+            return Some(KnownValue::String(String::new()));
+        };
+        let pieces: Vec<&str> = value
+            .split(delimiter.as_str())
+            .skip(from - 1)
+            .take((to + 1).saturating_sub(from))
+            .collect();
+        return Some(KnownValue::String(pieces.join(delimiter.as_str())));
+    }
+    None
+}
+
+/// Returns the expression last assigned to `name` by a `set` in `method_definition` that ends
+/// before `before` (a byte offset).
+pub(crate) fn last_assignment(
+    method_definition: Node,
+    content: &str,
+    name: &str,
+    before: usize,
+) -> Option<String> {
+    last_assignment_at(method_definition, content, name, before).map(|(_, value)| value)
+}
+
+/// Like `last_assignment`, also returning the byte offset where the assignment starts.
+pub(crate) fn last_assignment_at(
+    method_definition: Node,
+    content: &str,
+    name: &str,
+    before: usize,
+) -> Option<(usize, String)> {
+    let mut assignment = None;
+    let mut stack = vec![method_definition];
+    while let Some(node) = stack.pop() {
+        if node.start_byte() >= before {
+            continue;
+        }
+        if node.kind() == "command_set" {
+            // child(0) is the keyword; each set_argument is `target = value`
+            for set_argument in get_node_children(node).into_iter().skip(1) {
+                let (Some(target), Some(value)) =
+                    (set_argument.named_child(0), set_argument.named_child(1))
+                else {
+                    continue;
+                };
+                if set_argument.end_byte() <= before
+                    && content.get(target.byte_range()) == Some(name)
+                    && assignment
+                        .as_ref()
+                        .is_none_or(|(start, _)| *start < set_argument.start_byte())
+                {
+                    assignment = Some((set_argument.start_byte(), value.byte_range()));
+                }
+            }
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+    let (start, value) = assignment?;
+    content.get(value).map(|value| (start, value.to_string()))
+}
+
+/// Builds the hover for the innermost `$list`/`$listget`/`$piece` call at `point`: the type of
+/// the element it returns and, when known, its value.
+///
+/// Variables are followed through the `set` commands of the enclosing method that precede the
+/// call. Returns the range of the call and the Markdown text.
+pub fn element_type_hover(content: &str, tree: &Tree, point: Point) -> Option<(Range, String)> {
+    start_of_function("ELEMENT TYPES (no struct)", "element_type_hover");
+    let mut node = tree
+        .root_node()
+        .named_descendant_for_point_range(point, point);
+    let mut call = None;
+    let mut method_definition = None;
+    while let Some(current) = node {
+        if current.kind() == "method_definition" {
+            method_definition = Some(current);
+            break;
+        }
+        let is_element_call = call.is_none()
+            && content
+                .get(current.byte_range())
+                .and_then(function_call)
+                .is_some_and(|(function, _)| {
+                    LIST_FUNCTIONS.contains(&function.as_str())
+                        || PIECE_FUNCTIONS.contains(&function.as_str())
+                });
+        if is_element_call {
+            call = Some(current);
+        }
+        node = current.parent();
+    }
+    let (call, method_definition) = (call?, method_definition?);
+    let lookup = |name: &str| last_assignment(method_definition, content, name, call.start_byte());
+    let value = resolve_value(content.get(call.byte_range())?, &lookup, 0)?;
+    let hover = format!(
+        "**Element type**: `{}`\n\nValue: `{}`",
+        value.type_name(),
+        value.source()
+    );
+    successful_exit("ELEMENT TYPES (no struct)", "element_type_hover");
+    Some((call.range(), hover))
+}
@@ -0,0 +1,496 @@
+use crate::common::{
+    method_definition_nodes, start_of_function, successful_exit, ts_range_to_lsp_range,
+};
+use crate::diagnostics::{
+    byte_range, skip_comment, skip_string, skip_string_or_comment, starts_with_ignore_case,
+    Dialect, DIAGNOSTIC_SOURCE,
+};
+use crate::method::initial_build_method;
+use crate::rules::{rule_code, rule_code_description, UNDEFINED_HOST_VARIABLE_RULE};
+use crate::scope_tree::ScopeTree;
+use std::ops::Range as CoreRange;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use tree_sitter::{Range, Tree};
+
+/// SQL keywords ending an `INTO` host variable list.
+const INTO_TERMINATORS: [&str; 9] = [
+    "FROM", "WHERE", "VALUES", "SET", "ORDER", "GROUP", "HAVING", "JOIN", "UNION",
+];
+
+/// SQL keywords ending a `FROM` table list.
+const FROM_TERMINATORS: [&str; 8] = [
+    "WHERE", "ON", "SET", "VALUES", "ORDER", "GROUP", "HAVING", "UNION",
+];
+
+/// SQL keywords, predicates, and niladic functions, which are neither tables nor columns.
+const SQL_KEYWORDS: [&str; 77] = [
+    "ALL",
+    "AND",
+    "AS",
+    "ASC",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CLOSE",
+    "COMMIT",
+    "CROSS",
+    "CURRENT",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURSOR",
+    "DECLARE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXISTS",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INNER",
+    "INSERT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "LEFT",
+    "LIKE",
+    "NOT",
+    "NULL",
+    "OF",
+    "ON",
+    "OPEN",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "RIGHT",
+    "ROLLBACK",
+    "SELECT",
+    "SET",
+    "START",
+    "THEN",
+    "TOP",
+    "TRANSACTION",
+    "TRUE",
+    "UNION",
+    "UPDATE",
+    "USING",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+    "WITH",
+    "WORK",
+    "%CONTAINS",
+    "%FOLLOWS",
+    "%INLIST",
+    "%MATCHES",
+    "%NOCHECK",
+    "%NOINDEX",
+    "%NOLOCK",
+    "%NOTRIGGER",
+    "%PATTERN",
+    "%STARTSWITH",
+    "%KEEP",
+    "%PROFILE",
+    "%NOJOURN",
+];
+
+/// A `:name` host variable in an embedded `&sql( ... )` block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HostVariable {
+    /// Name of the ObjectScript local variable, without the colon or member access.
+    pub name: String,
+    /// Range of the name (after the colon).
+    pub range: Range,
+    /// Whether the statement stores into the variable (`INTO :name`) rather than reading it.
+    pub is_write: bool,
+}
+
+/// What a `SqlReference` names.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SqlReferenceKind {
+    Table,
+    Column,
+}
+
+/// A table or column identifier in an embedded `&sql( ... )` block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SqlReference {
+    pub kind: SqlReferenceKind,
+    /// The identifier without quotes; a table may be qualified by its schema, e.g.
+    /// `Sample.Person`.
+    pub name: String,
+    /// For a table, its alias (`FROM Sample.Person p`); for a column, the table or alias
+    /// qualifying it (`p.Name`).
+    pub qualifier: Option<String>,
+    /// Index of the `&sql` block the identifier is in, among the blocks scanned, so that columns
+    /// can be matched with the tables of their statement.
+    pub block: usize,
+    /// Range of the identifier, including its schema or qualifier.
+    pub range: Range,
+}
+
+/// A token of an embedded SQL statement, as byte ranges of `content`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum SqlToken {
+    /// A word or a `"delimited"` identifier.
+    Word {
+        range: CoreRange<usize>,
+        quoted: bool,
+    },
+    /// A `:name` host variable, ranged over the name.
+    HostVariable(CoreRange<usize>),
+    /// A string or numeric literal.
+    Literal,
+    /// Any other character, e.g. `,`, `.`, or `(`.
+    Symbol(u8),
+}
+
+/// Finds the `&sql( ... )` blocks in `content[bytes]`, as the byte ranges of their statements
+/// (inside the parentheses), in document order.
+///
+/// This is a lexical scan like `bracket_diagnostics`: ObjectScript strings and comments are
+/// skipped outside the blocks, SQL strings and comments inside them. A block left open ends
+/// with the range.
+pub fn sql_blocks(content: &str, bytes: CoreRange<usize>) -> Vec<CoreRange<usize>> {
+    let text = content.as_bytes();
+    let end = bytes.end.min(text.len());
+    let mut blocks = Vec::new();
+    let mut i = bytes.start;
+    // parenthesis depth inside the current `&sql(` block, 0 outside of one
+    let mut depth = 0;
+    let mut block_start = 0;
+    while i < end {
+        let dialect = if depth == 0 {
+            Dialect::ObjectScript
+        } else {
+            Dialect::Sql
+        };
+        if let Some(next) = skip_string_or_comment(content, i, dialect) {
+            i = next;
+            continue;
+        }
+        if depth == 0 {
+            if starts_with_ignore_case(content, i, "&sql(") {
+                depth = 1;
+                i += "&sql(".len();
+                block_start = i;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        match text[i] {
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    blocks.push(block_start..i);
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    if depth > 0 {
+        blocks.push(block_start..end.max(block_start));
+    }
+    blocks
+}
+
+/// Splits the SQL statement at `content[block]` into tokens, skipping whitespace and comments.
+fn sql_tokens(content: &str, block: CoreRange<usize>) -> Vec<SqlToken> {
+    let text = content.as_bytes();
+    let end = block.end.min(text.len());
+    let mut tokens = Vec::new();
+    let mut i = block.start;
+    while i < end {
+        if let Some(next) = skip_comment(content, i, Dialect::Sql) {
+            i = next.min(end);
+            continue;
+        }
+        let b = text[i];
+        match b {
+            b'\'' => {
+                i = skip_string(content, i, b).min(end);
+                tokens.push(SqlToken::Literal);
+            }
+            b'"' => {
+                let start = i;
+                i = skip_string(content, i, b).min(end);
+                tokens.push(SqlToken::Word {
+                    range: start..i,
+                    quoted: true,
+                });
+            }
+            b':' => {
+                let start = i + 1;
+                let length = content[start..end]
+                    .char_indices()
+                    .take_while(|&(n, c)| c.is_alphanumeric() || (n == 0 && c == '%'))
+                    .map(|(n, c)| n + c.len_utf8())
+                    .last()
+                    .unwrap_or(0);
+                tokens.push(SqlToken::HostVariable(start..start + length));
+                i = start + length;
+            }
+            _ if b.is_ascii_digit() => {
+                i += content[i..end]
+                    .bytes()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == b'.')
+                    .count();
+                tokens.push(SqlToken::Literal);
+            }
+            _ if b.is_ascii_alphabetic() || b == b'%' || b == b'_' => {
+                let length = content[i..end]
+                    .bytes()
+                    .skip(1)
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == b'_')
+                    .count()
+                    + 1;
+                tokens.push(SqlToken::Word {
+                    range: i..i + length,
+                    quoted: false,
+                });
+                i += length;
+            }
+            _ if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                tokens.push(SqlToken::Symbol(b));
+                // keep to character boundaries
+                i += content[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    tokens
+}
+
+/// Finds the host variables of the `&sql( ... )` blocks in `content[bytes]`, in document order.
+///
+/// Host variables following `INTO` (up to the next clause) are writes; every other one is a
+/// read.
+pub fn find_host_variables(content: &str, bytes: CoreRange<usize>) -> Vec<HostVariable> {
+    let mut host_variables = Vec::new();
+    for block in sql_blocks(content, bytes) {
+        let mut in_into = false;
+        for token in sql_tokens(content, block) {
+            match token {
+                SqlToken::HostVariable(range) => {
+                    let name = &content[range.clone()];
+                    if name.starts_with(|c: char| c.is_alphabetic() || c == '%') {
+                        host_variables.push(HostVariable {
+                            name: name.to_string(),
+                            range: byte_range(content, range.start, range.end),
+                            is_write: in_into,
+                        });
+                    }
+                }
+                SqlToken::Word {
+                    range,
+                    quoted: false,
+                } => {
+                    let word = &content[range];
+                    if word.eq_ignore_ascii_case("INTO") {
+                        in_into = true;
+                    } else if INTO_TERMINATORS
+                        .iter()
+                        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+                    {
+                        in_into = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    host_variables
+}
+
+/// Finds the table and column identifiers of the `&sql( ... )` blocks in `content[bytes]`, in
+/// document order.
+///
+/// Tables are the names following `FROM` (and the commas of its list), `JOIN`, `UPDATE`, and
+/// `INSERT INTO`, with the alias after them. Columns are the other names that aren't keywords,
+/// function calls, cursor names, or select-list aliases; a name qualified with `.` is a column
+/// of the table or alias before the dot.
+pub fn find_sql_references(content: &str, bytes: CoreRange<usize>) -> Vec<SqlReference> {
+    let mut references = Vec::new();
+    for (block, statement) in sql_blocks(content, bytes).into_iter().enumerate() {
+        let tokens = sql_tokens(content, statement);
+        let word = |n: usize| match tokens.get(n) {
+            Some(SqlToken::Word { range, .. }) => Some(range.clone()),
+            _ => None,
+        };
+        let name = |range: &CoreRange<usize>| content[range.clone()].trim_matches('"').to_string();
+        let is_keyword = |n: usize| match tokens.get(n) {
+            Some(SqlToken::Word {
+                range,
+                quoted: false,
+            }) => SQL_KEYWORDS
+                .iter()
+                .any(|keyword| content[range.clone()].eq_ignore_ascii_case(keyword)),
+            _ => false,
+        };
+        let keyword_is = |n: usize, keyword: &str| {
+            is_keyword(n)
+                && word(n).is_some_and(|range| content[range].eq_ignore_ascii_case(keyword))
+        };
+        let mut expect_table = false;
+        let mut in_from_list = false;
+        // index in `references` of the table an alias may follow
+        let mut aliased_table = None;
+        // the next word is an alias (after `AS`) or a cursor name
+        let mut skip_word = false;
+        let mut n = 0;
+        while n < tokens.len() {
+            let Some(first) = word(n) else {
+                match tokens[n] {
+                    SqlToken::Symbol(b',') if in_from_list => expect_table = true,
+                    SqlToken::Symbol(b'(') => {}
+                    _ => expect_table = false,
+                }
+                aliased_table = None;
+                skip_word = false;
+                n += 1;
+                continue;
+            };
+            if is_keyword(n) {
+                expect_table = ["FROM", "JOIN", "UPDATE"]
+                    .iter()
+                    .any(|keyword| keyword_is(n, keyword))
+                    || (keyword_is(n, "INTO") && word(n + 1).is_some() && !is_keyword(n + 1));
+                in_from_list = keyword_is(n, "FROM") || (in_from_list && !expect_table);
+                if keyword_is(n, "AS") {
+                    skip_word = aliased_table.is_none();
+                } else {
+                    aliased_table = None;
+                    skip_word = ["DECLARE", "OPEN", "FETCH", "CLOSE"]
+                        .iter()
+                        .any(|keyword| keyword_is(n, keyword));
+                }
+                if FROM_TERMINATORS
+                    .iter()
+                    .any(|keyword| keyword_is(n, keyword))
+                {
+                    in_from_list = false;
+                }
+                n += 1;
+                continue;
+            }
+            // the words of a dotted name, e.g. `Sample.Person` or `p.Name`
+            let mut last = first.clone();
+            let mut parts = vec![name(&first)];
+            while tokens.get(n + 1) == Some(&SqlToken::Symbol(b'.')) {
+                let Some(next) = word(n + 2) else {
+                    break;
+                };
+                parts.push(name(&next));
+                last = next;
+                n += 2;
+            }
+            n += 1;
+            let range = byte_range(content, first.start, last.end);
+            if expect_table {
+                references.push(SqlReference {
+                    kind: SqlReferenceKind::Table,
+                    name: parts.join("."),
+                    qualifier: None,
+                    block,
+                    range,
+                });
+                aliased_table = Some(references.len() - 1);
+                expect_table = false;
+            } else if let Some(table) = aliased_table.take() {
+                references[table].qualifier = parts.pop();
+            } else if std::mem::take(&mut skip_word)
+                || tokens.get(n) == Some(&SqlToken::Symbol(b'('))
+            {
+                // an alias, a cursor name, or a function call
+            } else {
+                let column = parts.pop().unwrap_or_default();
+                references.push(SqlReference {
+                    kind: SqlReferenceKind::Column,
+                    name: column,
+                    qualifier: (!parts.is_empty()).then(|| parts.join(".")),
+                    block,
+                    range,
+                });
+            }
+        }
+    }
+    references
+}
+
+/// Finds the host variables that refer to private variables: those of procedure-block methods
+/// that aren't `%` variables or declared in the method's `PublicList`.
+///
+/// `class_procedure_block` is the class' `ProcedureBlock` keyword, used by methods that don't set
+/// their own.
+pub fn private_host_variables(
+    content: &str,
+    tree: &Tree,
+    class_procedure_block: bool,
+) -> Vec<HostVariable> {
+    start_of_function("EMBEDDED SQL (no struct)", "private_host_variables");
+    let mut host_variables = Vec::new();
+    for (method_definition, method_type) in method_definition_nodes(tree.root_node()) {
+        let Some((method, _)) = initial_build_method(method_definition, method_type, content)
+        else {
+            continue;
+        };
+        if !method.is_procedure_block.unwrap_or(class_procedure_block) {
+            continue;
+        }
+        host_variables.extend(
+            find_host_variables(content, method_definition.byte_range())
+                .into_iter()
+                .filter(|host_variable| {
+                    !host_variable.name.starts_with('%')
+                        && !method
+                            .public_variables_declared
+                            .contains(&host_variable.name)
+                }),
+        );
+    }
+    successful_exit("EMBEDDED SQL (no struct)", "private_host_variables");
+    host_variables
+}
+
+/// Warns about host variables read by embedded SQL that are never defined: neither an argument,
+/// nor set or stored into (`INTO`) in their method, nor a public variable in scope.
+pub fn undefined_host_variable_diagnostics(
+    content: &str,
+    host_variables: &[HostVariable],
+    scope_tree: &ScopeTree,
+) -> Vec<Diagnostic> {
+    host_variables
+        .iter()
+        .filter(|host_variable| {
+            let pos = host_variable.range.start_point;
+            let name = host_variable.name.as_str();
+            !host_variable.is_write
+                && scope_tree.get_variable_symbol_id(pos, name).is_none()
+                && scope_tree.pub_variable_in_scope(pos, name).is_none()
+        })
+        .map(|host_variable| Diagnostic {
+            range: ts_range_to_lsp_range(content, host_variable.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(UNDEFINED_HOST_VARIABLE_RULE),
+            code_description: rule_code_description(UNDEFINED_HOST_VARIABLE_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!("Host variable :{} is never defined", host_variable.name),
+            ..Default::default()
+        })
+        .collect()
+}
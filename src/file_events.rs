@@ -0,0 +1,39 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Time window over which watched-file events are counted to detect a bulk change.
+pub const BULK_CHANGE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Counts recent watched-file events to tell bulk changes (a `git checkout`, a branch switch)
+/// apart from ordinary saves.
+#[derive(Debug, Default)]
+pub struct BulkChangeDetector {
+    /// (time received, number of events) of each batch within the last `BULK_CHANGE_WINDOW`.
+    batches: VecDeque<(Instant, usize)>,
+}
+
+impl BulkChangeDetector {
+    /// Creates a detector that has seen no events.
+    pub fn new() -> Self {
+        Self {
+            batches: VecDeque::new(),
+        }
+    }
+
+    /// Records `count` events received at `now`.
+    ///
+    /// Returns `true` if at least `threshold` events were received within `BULK_CHANGE_WINDOW`; a
+    /// threshold of 0 never reports a bulk change.
+    pub fn record(&mut self, now: Instant, count: usize, threshold: usize) -> bool {
+        while self
+            .batches
+            .front()
+            .is_some_and(|(received, _)| now.duration_since(*received) > BULK_CHANGE_WINDOW)
+        {
+            self.batches.pop_front();
+        }
+        self.batches.push_back((now, count));
+        let recent: usize = self.batches.iter().map(|(_, count)| count).sum();
+        threshold > 0 && recent >= threshold
+    }
+}
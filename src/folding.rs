@@ -0,0 +1,99 @@
+use crate::common::{find_class_definition, start_of_function, successful_exit};
+use crate::diagnostics::member_statements;
+use std::cmp::Reverse;
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind};
+use tree_sitter::{Range, Tree};
+
+/// Computes the folding ranges of a document: the class and its members spanning several lines
+/// (methods, XData blocks, Storage definitions, ...), and the folds of
+/// `lexical_folding_ranges`.
+///
+/// At most one range starts on each line, the outermost one.
+pub fn folding_ranges(content: &str, tree: &Tree) -> Vec<FoldingRange> {
+    start_of_function("FOLDING (no struct)", "folding_ranges");
+    let mut ranges = Vec::new();
+    if let Some(class_definition) = find_class_definition(tree.root_node()) {
+        ranges.extend(block_fold(content, class_definition.range()));
+    }
+    for (statement, _) in member_statements(tree) {
+        ranges.extend(block_fold(content, statement.range()));
+    }
+    ranges.extend(lexical_folding_ranges(content));
+    ranges.sort_by_key(|range| (range.start_line, Reverse(range.end_line)));
+    ranges.dedup_by_key(|range| range.start_line);
+    successful_exit("FOLDING (no struct)", "folding_ranges");
+    ranges
+}
+
+/// Folds the lines of the block at `range`, keeping its closing `}` visible when it is on a line
+/// of its own; `None` if that leaves nothing to fold.
+fn block_fold(content: &str, range: Range) -> Option<FoldingRange> {
+    let mut end_line = range.end_point.row;
+    let text = content.get(..range.end_byte)?;
+    if range.end_point.column == 0 {
+        // the block ends with its line break
+        end_line = end_line.checked_sub(1)?;
+    }
+    let last_line = text.trim_end_matches(['\r', '\n']).rsplit('\n').next()?;
+    if last_line.trim() == "}" {
+        end_line = end_line.checked_sub(1)?;
+    }
+    line_fold(range.start_point.row, end_line, None)
+}
+
+/// Finds the folds that can be told from the text alone: runs of `///` comment lines, and
+/// `#if`/`#ifdef`/`#ifndef` regions up to the line before their `#endif`.
+pub fn lexical_folding_ranges(content: &str) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    let mut comment_start = None;
+    let mut open_conditions = Vec::new();
+    let mut line_count = 0;
+    for (line_number, line) in content.lines().enumerate() {
+        line_count = line_number + 1;
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("///") {
+            comment_start.get_or_insert(line_number);
+        } else if let Some(start) = comment_start.take() {
+            ranges.extend(line_fold(
+                start,
+                line_number - 1,
+                Some(FoldingRangeKind::Comment),
+            ));
+        }
+        let directive = trimmed.get(..6).unwrap_or(trimmed).to_ascii_lowercase();
+        if directive.starts_with("#if") {
+            open_conditions.push(line_number);
+        } else if directive.starts_with("#endif") {
+            if let Some(start) = open_conditions.pop() {
+                ranges.extend(line_fold(
+                    start,
+                    line_number.saturating_sub(1),
+                    Some(FoldingRangeKind::Region),
+                ));
+            }
+        }
+    }
+    if let Some(start) = comment_start {
+        ranges.extend(line_fold(
+            start,
+            line_count - 1,
+            Some(FoldingRangeKind::Comment),
+        ));
+    }
+    ranges.sort_by_key(|range| range.start_line);
+    ranges
+}
+
+/// A fold of the lines `start_line` to `end_line`, or `None` if they are a single line.
+fn line_fold(
+    start_line: usize,
+    end_line: usize,
+    kind: Option<FoldingRangeKind>,
+) -> Option<FoldingRange> {
+    (end_line > start_line).then(|| FoldingRange {
+        start_line: start_line as u32,
+        end_line: end_line as u32,
+        kind,
+        ..Default::default()
+    })
+}
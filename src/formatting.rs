@@ -0,0 +1,197 @@
+use crate::case_rules::{names_match, IdentifierKind};
+use crate::common::{
+    braced_content, method_definition_nodes, start_of_function, successful_exit,
+    ts_range_to_lsp_range,
+};
+use crate::diagnostics::{brace_counts, byte_range};
+use crate::method::initial_build_method;
+use crate::parse_structures::Language;
+use std::ops::RangeInclusive;
+use tower_lsp::lsp_types::TextEdit;
+use tree_sitter::{Node, Tree};
+
+/// Single-letter command abbreviations expanded by `command_abbreviation_edits`, as
+/// `(abbreviation, command)`.
+pub const ABBREVIATED_COMMANDS: [(&str, &str); 5] = [
+    ("s", "set"),
+    ("w", "write"),
+    ("d", "do"),
+    ("i", "if"),
+    ("q", "quit"),
+];
+
+/// Builds the edits expanding abbreviated commands (see `ABBREVIATED_COMMANDS`) into full
+/// commands.
+///
+/// Only commands starting on one of `rows` are expanded when they are given (used on-type, for
+/// the line the user just completed, and for range formatting); otherwise the whole document
+/// is. The expansion keeps the case of the abbreviation, so `S` becomes `SET`.
+pub fn command_abbreviation_edits(
+    content: &str,
+    tree: &Tree,
+    rows: Option<RangeInclusive<usize>>,
+) -> Vec<TextEdit> {
+    start_of_function("FORMATTING (no struct)", "command_abbreviation_edits");
+    let mut edits = Vec::new();
+    collect_abbreviated_commands(tree.root_node(), content, rows.as_ref(), &mut edits);
+    successful_exit("FORMATTING (no struct)", "command_abbreviation_edits");
+    edits
+}
+
+/// Recursively collects the abbreviated command keywords below `node`.
+fn collect_abbreviated_commands(
+    node: Node,
+    content: &str,
+    rows: Option<&RangeInclusive<usize>>,
+    edits: &mut Vec<TextEdit>,
+) {
+    if let Some(rows) = rows {
+        if node.end_position().row < *rows.start() || node.start_position().row > *rows.end() {
+            return;
+        }
+    }
+    if let Some(command) = node.kind().strip_prefix("command_") {
+        // child(0) is the command keyword, without its postconditional
+        let keyword = node
+            .named_child(0)
+            .filter(|keyword| rows.is_none_or(|rows| rows.contains(&keyword.start_position().row)));
+        if let Some(keyword) = keyword {
+            let text = content.get(keyword.byte_range()).unwrap_or_default();
+            let expansion = ABBREVIATED_COMMANDS
+                .iter()
+                .find(|(abbreviation, full)| {
+                    *full == command && names_match(IdentifierKind::Command, abbreviation, text)
+                })
+                .map(|(_, full)| {
+                    if text.chars().all(|c| c.is_ascii_uppercase()) {
+                        full.to_ascii_uppercase()
+                    } else {
+                        full.to_string()
+                    }
+                });
+            if let Some(new_text) = expansion {
+                edits.push(TextEdit {
+                    range: ts_range_to_lsp_range(content, keyword.range()),
+                    new_text,
+                });
+            }
+        }
+    }
+    // commands nest (e.g. a `do` block inside an `if`)
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_abbreviated_commands(child, content, rows, edits);
+    }
+}
+
+/// A line of a method body, with the brace depth at its start (the body itself is depth 0).
+struct BodyLine<'a> {
+    row: usize,
+    start: usize,
+    text: &'a str,
+    depth: isize,
+}
+
+/// Builds the edits indenting lines of the ObjectScript method body containing `row` to their
+/// block level: one `indent` per enclosing pair of braces, the body's own included, and one
+/// less for a line starting with `}`.
+///
+/// With `closed_block` (a `}` was just typed at the end of `row`), every line of the block it
+/// closes is re-indented; otherwise only `row` is (a line just started). Labels, which must
+/// start in the first column, and blank lines of a closed block are left alone, as are methods
+/// in other languages (whose language is theirs, or else `class_language`), since Python
+/// indentation is significant.
+pub fn block_indentation_edits(
+    content: &str,
+    tree: &Tree,
+    class_language: Option<&Language>,
+    row: usize,
+    closed_block: bool,
+    indent: &str,
+) -> Vec<TextEdit> {
+    start_of_function("FORMATTING (no struct)", "block_indentation_edits");
+    let body = method_definition_nodes(tree.root_node())
+        .into_iter()
+        .filter(|(definition, _)| {
+            definition.start_position().row < row && row <= definition.end_position().row
+        })
+        .find_map(|(definition, method_type)| {
+            let (method, _) = initial_build_method(definition, method_type, content)?;
+            let language = method.language.as_ref().or(class_language);
+            if language.is_some_and(|language| *language != Language::Objectscript) {
+                return None;
+            }
+            let start = definition.start_byte();
+            let (body_start, body_end) = content
+                .get(definition.byte_range())
+                .and_then(braced_content)?;
+            // the closing brace of the body is re-indented too
+            Some((start + body_start, start + body_end + 1))
+        });
+    let Some((body_start, body_end)) = body else {
+        successful_exit("FORMATTING (no struct)", "block_indentation_edits");
+        return Vec::new();
+    };
+
+    let mut lines = Vec::new();
+    let first_row = content[..body_start].matches('\n').count();
+    let mut start = body_start;
+    let mut depth = 0;
+    for (row, line) in (first_row..).zip(content[body_start..body_end].split_inclusive('\n')) {
+        let text = line.trim_end_matches(['\n', '\r']);
+        lines.push(BodyLine {
+            row,
+            start,
+            text,
+            depth,
+        });
+        let (opened, closed) = brace_counts(text);
+        depth += opened as isize - closed as isize;
+        start += line.len();
+    }
+    // the rest of the line of the body's `{` is never indented
+    let lines = lines.get(1..).unwrap_or_default();
+    let Some(last) = lines.iter().position(|line| line.row == row) else {
+        successful_exit("FORMATTING (no struct)", "block_indentation_edits");
+        return Vec::new();
+    };
+    let first = if closed_block {
+        let line = &lines[last];
+        let Some(brace) = line.text.rfind('}') else {
+            successful_exit("FORMATTING (no struct)", "block_indentation_edits");
+            return Vec::new();
+        };
+        let (opened, closed) = brace_counts(&line.text[..brace]);
+        let inner_depth = line.depth + opened as isize - closed as isize;
+        // the block starts after the line opening it
+        lines[..last]
+            .iter()
+            .rposition(|line| line.depth < inner_depth)
+            .map_or(0, |opening| opening + 1)
+    } else {
+        last
+    };
+
+    let mut edits = Vec::new();
+    for line in &lines[first..=last] {
+        let code = line.text.trim_start_matches([' ', '\t']);
+        let is_label = line.text.len() == code.len() && !code.is_empty() && !code.starts_with('}');
+        if is_label || (closed_block && code.is_empty()) {
+            continue;
+        }
+        let level = line.depth + 1 - isize::from(code.starts_with('}'));
+        let new_text = indent.repeat(level.max(0) as usize);
+        let whitespace = &line.text[..line.text.len() - code.len()];
+        if whitespace != new_text {
+            edits.push(TextEdit {
+                range: ts_range_to_lsp_range(
+                    content,
+                    byte_range(content, line.start, line.start + whitespace.len()),
+                ),
+                new_text,
+            });
+        }
+    }
+    successful_exit("FORMATTING (no struct)", "block_indentation_edits");
+    edits
+}
@@ -0,0 +1,108 @@
+/// Returns `true` if `c` separates the segments of a qualified name, e.g. `MyApp.B:TestWrite`.
+fn is_separator(c: char) -> bool {
+    c == '.' || c == ':'
+}
+
+/// Scores how well `query` fuzzy-matches `candidate`, or returns `None` if it doesn't match.
+///
+/// The characters of `query` must appear in `candidate` in order, ignoring case; a `.` or `:` in
+/// the query matches either separator, so `mya.b.test` matches `MyApp.B:TestWrite`. Matches at
+/// the start of a segment or of a camel-case word, and runs of consecutive matches, score
+/// higher, as does `candidate` starting with `query`. An empty query matches everything with a
+/// score of 0.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let matches = |q: char, c: char| {
+        if is_separator(q) {
+            is_separator(c)
+        } else {
+            c.eq_ignore_ascii_case(&q)
+        }
+    };
+    let starts_word = |i: usize| {
+        i.checked_sub(1).map(|i| chars[i]).is_none_or(|before| {
+            is_separator(before) || (before.is_ascii_lowercase() && chars[i].is_ascii_uppercase())
+        })
+    };
+    // consecutive matches score higher; skipped characters cost a little, capped so long names
+    // aren't ruled out
+    let gap_score = |gap: usize| if gap == 0 { 3 } else { -(gap.min(3) as i32) };
+    // best[i]: the best score of matching the query so far with its last character at `i`,
+    // found by dynamic programming so word starts are preferred over earlier occurrences
+    let mut best: Vec<Option<i32>> = Vec::new();
+    for (n, q) in query.chars().enumerate() {
+        let mut next = vec![None; chars.len()];
+        // best score of the matches at least 4 characters back, whose gaps all cost the cap
+        let mut far: Option<i32> = None;
+        for i in 0..chars.len() {
+            if n > 0 && i >= 4 {
+                far = far.max(best[i - 4]);
+            }
+            if !matches(q, chars[i]) {
+                continue;
+            }
+            let previous = if n == 0 {
+                Some(gap_score(i))
+            } else {
+                (1..=3.min(i))
+                    .filter_map(|back| Some(best[i - back]? + gap_score(back - 1)))
+                    .max()
+                    .max(far.map(|score| score + gap_score(3)))
+            };
+            let bonus = if starts_word(i) { 5 } else { 1 };
+            next[i] = previous.map(|score| score + bonus);
+        }
+        best = next;
+    }
+    let mut score = best.into_iter().flatten().max()?;
+    let query_lowercase = query.to_ascii_lowercase();
+    let candidate_lowercase = candidate.to_ascii_lowercase();
+    if candidate_lowercase == query_lowercase {
+        score += 20;
+    } else if candidate_lowercase.starts_with(&query_lowercase) {
+        score += 10;
+    }
+    Some(score)
+}
+
+/// Returns the Levenshtein distance between `a` and `b`, ignoring case: the number of
+/// characters to insert, delete, or replace to turn one into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let b: Vec<char> = b.chars().map(|c| c.to_ascii_lowercase()).collect();
+    // previous[j]: the distance between the prefix of `a` handled so far and `b[..j]`
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(ca != cb);
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns the (at most three) `candidates` closest to the misspelled `name`, closest first:
+/// those within an edit distance of a third of its length, and at least 1.
+pub fn closest_names<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut closest: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    closest.sort();
+    closest.dedup();
+    closest
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
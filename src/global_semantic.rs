@@ -1,12 +1,14 @@
+use crate::arena::Arena;
 use crate::common::{generic_exit_statements, start_of_function, successful_exit};
+use crate::interner::{Interner, Name};
 use crate::override_index::OverrideIndex;
 use crate::parse_structures::{
-    Class, ClassId, DfsState, Language, LocalSemanticModelId, Method,
-    MethodRef, PrivateMethodId, PublicMethodId, PublicMethodRef, PublicVarId, Variable,
+    Class, ClassId, DfsState, Language, LocalSemanticModelId, Method, MethodHandle, MethodRef,
+    MethodType, PrivateMethodId, PublicMethodId, PublicMethodRef, PublicVarId, Variable,
 };
 use crate::scope_structures::{
-    ClassGlobalSymbol, ClassGlobalSymbolId, MethodGlobalSymbol, MethodGlobalSymbolId,
-    VariableGlobalSymbol, VariableGlobalSymbolId,
+    ClassGlobalSymbol, ClassGlobalSymbolId, GlobalSymbolId, MethodGlobalSymbol,
+    MethodGlobalSymbolId, VariableGlobalSymbol, VariableGlobalSymbolId,
 };
 use std::collections::HashMap;
 use tower_lsp::lsp_types::Url;
@@ -19,17 +21,23 @@ pub struct GlobalSemanticModel {
     /// Stores public variables per class.
     pub variables: HashMap<ClassId, Vec<Variable>>,
     /// Stores all classes in a workspace.
-    pub classes: Vec<Class>,
+    pub classes: Arena<ClassId, Class>,
     /// Stores public methods per class.
     pub methods: HashMap<ClassId, Vec<Method>>,
     /// Stores all local semantic models in a workspace.
-    pub private: Vec<LocalSemanticModel>,
+    pub private: Arena<LocalSemanticModelId, LocalSemanticModel>,
     /// Stores all class symbols in a workspace.
-    pub class_defs: Vec<ClassGlobalSymbol>,
+    pub class_defs: Arena<ClassGlobalSymbolId, ClassGlobalSymbol>,
     /// Stores Method Global Symbols per Class Global Symbol
-    pub method_defs: HashMap<ClassGlobalSymbolId, Vec<MethodGlobalSymbol>>,
+    pub method_defs: HashMap<ClassGlobalSymbolId, Arena<MethodGlobalSymbolId, MethodGlobalSymbol>>,
     /// Stores Variable Global Symbols per Class Global Symbol
-    pub(crate) variable_defs: HashMap<ClassGlobalSymbolId, Vec<VariableGlobalSymbol>>,
+    pub(crate) variable_defs:
+        HashMap<ClassGlobalSymbolId, Arena<VariableGlobalSymbolId, VariableGlobalSymbol>>,
+    /// Maps Url -> every global symbol (class, public method, public variable) defined in that document.
+    pub(crate) url_symbols: HashMap<Url, Vec<GlobalSymbolId>>,
+    /// Interns the class and method names the override index and `ProjectData`'s lookup maps are
+    /// keyed by.
+    pub(crate) names: Interner,
 }
 
 impl GlobalSemanticModel {
@@ -40,12 +48,14 @@ impl GlobalSemanticModel {
     pub fn new() -> Self {
         Self {
             variables: HashMap::new(),
-            classes: Vec::new(),
+            classes: Arena::new(),
             methods: HashMap::new(),
-            private: Vec::new(),
-            class_defs: Vec::new(),
+            private: Arena::new(),
+            class_defs: Arena::new(),
             method_defs: HashMap::new(),
             variable_defs: HashMap::new(),
+            url_symbols: HashMap::new(),
+            names: Interner::new(),
         }
     }
 
@@ -74,7 +84,6 @@ impl GlobalSemanticModel {
     /// corresponds to the index that the Class is stored.
     pub fn new_class(&mut self, class: Class) -> ClassId {
         start_of_function("GlobalSemanticModel", "new_class");
-        let id = ClassId(self.classes.len());
         let classes = &mut self.classes;
         eprintln!(
             "Info: Current classes in global semantic model are {sep} {:?} {sep}",
@@ -85,7 +94,7 @@ impl GlobalSemanticModel {
             "Info: Adding class {} to global semantic model",
             class.name.clone()
         );
-        classes.push(class);
+        let id = classes.insert(class);
         successful_exit("GlobalSemanticModel", "new_class");
         id
     }
@@ -107,39 +116,38 @@ impl GlobalSemanticModel {
         successful_exit("GlobalSemanticModel", "new_method");
     }
 
-    /// Appends a new `LocalSemanticModel` to the global store `self.private` and returns its stable id.
+    /// Adds a new `LocalSemanticModel` to the global store `self.private` and returns its stable id.
     ///
-    /// The returned `LocalSemanticModelId` is the index in the internal `private` vector.
+    /// The returned `LocalSemanticModelId` may reuse the slot of a removed local semantic model.
     pub fn new_local_semantic(
         &mut self,
         local_semantic: LocalSemanticModel,
     ) -> LocalSemanticModelId {
         start_of_function("GlobalSemanticModel", "new_local_semantic");
-        let id = LocalSemanticModelId(self.private.len());
         let lsms = &mut self.private;
         eprintln!(
             "Info: Current local semantic models are {sep} {:?} {sep}",
             lsms,
             sep = "\n\n"
         );
-        lsms.push(local_semantic);
+        let id = lsms.insert(local_semantic);
         successful_exit("GlobalSemanticModel", "new_local_semantic");
         id
     }
 
     /// Returns a mutable reference to the local semantic model with the given id.
     ///
-    /// Logs a warning and returns `None` if `lsm_id` is out of bounds.
+    /// Logs a warning and returns `None` if `lsm_id` was removed.
     pub fn get_local_semantic_mut(
         &mut self,
         lsm_id: LocalSemanticModelId,
     ) -> Option<&mut LocalSemanticModel> {
-        let result = self.private.get_mut(lsm_id.0);
+        let result = self.private.get_mut(lsm_id);
         start_of_function("GlobalSemanticModel", "get_local_semantic_mut");
         match result {
             None => {
                 generic_exit_statements("GlobalSemanticModel", "get_local_semantic_mut");
-                eprintln!("Warning: Failed to get local semantic model: Index {:?} out of bounds for local semantic models vector: {sep} {:?} {sep}", lsm_id.0,result, sep= "\n");
+                eprintln!("Warning: Failed to get local semantic model: {:?} was removed from the local semantic models arena: {sep} {:?} {sep}", lsm_id, result, sep= "\n");
                 result
             }
             Some(_) => {
@@ -172,16 +180,16 @@ impl GlobalSemanticModel {
         Some(method)
     }
 
-    /// Returns an immutable reference to the class at `index` in the classes table.
+    /// Returns an immutable reference to the class `class_id` in the classes table.
     ///
-    /// Logs a warning and returns `None` if `index` is out of bounds.
-    pub fn get_class(&self, index: usize) -> Option<&Class> {
+    /// Logs a warning and returns `None` if the class was removed.
+    pub fn get_class(&self, class_id: ClassId) -> Option<&Class> {
         start_of_function("GlobalSemanticModel", "get_class");
-        let result = self.classes.get(index);
+        let result = self.classes.get(class_id);
 
         match result {
             None => {
-                eprintln!("Warning: Index {:?} is out of range, failed to get class from the classes vec: {:?}", index, self.classes);
+                eprintln!("Warning: Class {:?} was removed, failed to get class from the classes arena: {:?}", class_id, self.classes);
                 generic_exit_statements("GlobalSemanticModel", "get_class");
                 result
             }
@@ -192,13 +200,17 @@ impl GlobalSemanticModel {
         }
     }
 
-    /// Returns the `ClassGlobalSymbol` at `index` in the class symbol table.
+    /// Returns the `ClassGlobalSymbol` `symbol_id` in the class symbol table.
     ///
-    /// Logs a warning and returns `None` if `index` is out of bounds.
-    pub fn get_class_symbol(&self, index: usize, class_name: &str) -> Option<&ClassGlobalSymbol> {
+    /// Logs a warning and returns `None` if the class symbol was removed.
+    pub fn get_class_symbol(
+        &self,
+        symbol_id: ClassGlobalSymbolId,
+        class_name: &str,
+    ) -> Option<&ClassGlobalSymbol> {
         start_of_function("GlobalSemanticModel", "get_class_symbol");
-        let Some(class_global_symbol) = self.class_defs.get(index) else {
-            eprintln!("Warning: Index {:?} is out of range, failed to get class symbol from the class defs vec: {:?} for class named {:?}", index, self.class_defs, class_name);
+        let Some(class_global_symbol) = self.class_defs.get(symbol_id) else {
+            eprintln!("Warning: Class symbol {:?} was removed, failed to get it from the class defs arena: {:?} for class named {:?}", symbol_id, self.class_defs, class_name);
             generic_exit_statements("GlobalSemanticModel", "get_class_symbol");
             return None;
         };
@@ -238,13 +250,13 @@ impl GlobalSemanticModel {
 
     /// Returns the `MethodGlobalSymbol` for a class symbol by symbol index.
     ///
-    /// Logs and returns `None` if the class has no method symbols recorded or `method_symbol_id` is
-    /// out of bounds.
+    /// Logs and returns `None` if the class has no method symbols recorded or `method_symbol_id`
+    /// was removed.
     pub fn get_method_symbol(
         &self,
         class_symbol_id: ClassGlobalSymbolId,
         class_name: &str,
-        method_symbol_id: usize,
+        method_symbol_id: MethodGlobalSymbolId,
     ) -> Option<&MethodGlobalSymbol> {
         start_of_function("GlobalSemanticModel", "get_method_symbol");
         let Some(method_symbols) = self.method_defs.get(&class_symbol_id) else {
@@ -254,7 +266,7 @@ impl GlobalSemanticModel {
         };
 
         let Some(sym) = method_symbols.get(method_symbol_id) else {
-            eprintln!("Warning: Index {:?} is out of range, failed to get method symbol from the method symbols vec: {:?} for class named: {:?}", method_symbol_id, method_symbols, class_name);
+            eprintln!("Warning: {:?} was removed, failed to get method symbol from the method symbols arena: {:?} for class named: {:?}", method_symbol_id, method_symbols, class_name);
             generic_exit_statements("GlobalSemanticModel", "get_method_symbol");
             return None;
         };
@@ -264,13 +276,13 @@ impl GlobalSemanticModel {
 
     /// Returns an immutable reference to the local semantic model with the given id.
     ///
-    /// Logs a warning and returns `None` if `lsm_id` is out of bounds.
+    /// Logs a warning and returns `None` if `lsm_id` was removed.
     pub fn get_local_semantic(&self, lsm_id: LocalSemanticModelId) -> Option<&LocalSemanticModel> {
         start_of_function("GlobalSemanticModel", "get_local_semantic");
-        let result = self.private.get(lsm_id.0);
+        let result = self.private.get(lsm_id);
         match result {
             None => {
-                eprintln!("Warning: Failed to get local semantic model: Index {:?} out of bounds for local semantic models vector: {sep} {:?} {sep}", lsm_id.0, self.private, sep= "\n");
+                eprintln!("Warning: Failed to get local semantic model: {:?} was removed from the local semantic models arena: {sep} {:?} {sep}", lsm_id, self.private, sep= "\n");
                 generic_exit_statements("GlobalSemanticModel", "get_local_semantic");
                 result
             }
@@ -281,13 +293,13 @@ impl GlobalSemanticModel {
         }
     }
 
-    /// Returns the `VariableGlobalSymbol` for a class symbol by symbol index.
+    /// Returns the `VariableGlobalSymbol` for a class symbol by symbol id.
     ///
-    /// Logs and returns `None` if the class has no variable symbols recorded or `index` is out of bounds.
+    /// Logs and returns `None` if the class has no variable symbols recorded or `id` was removed.
     pub fn get_variable_symbol(
         &self,
         class_symbol_id: &ClassGlobalSymbolId,
-        index: usize,
+        id: VariableGlobalSymbolId,
         class_name: &str,
     ) -> Option<&VariableGlobalSymbol> {
         start_of_function("GlobalSemanticModel", "get_variable_symbol");
@@ -296,8 +308,8 @@ impl GlobalSemanticModel {
             generic_exit_statements("GlobalSemanticModel", "get_variable_symbol");
             return None;
         };
-        let Some(var_symbol) = var_symbols.get(index) else {
-            eprintln!("Warning: Index {:?} is out of range, failed to get variable symbol from the methods vec: {:?} for class named: {:?}", index, var_symbols, class_name);
+        let Some(var_symbol) = var_symbols.get(id) else {
+            eprintln!("Warning: {:?} was removed, failed to get variable symbol from the variable symbols arena: {:?} for class named: {:?}", id, var_symbols, class_name);
             generic_exit_statements("GlobalSemanticModel", "get_variable_symbol");
             return None;
         };
@@ -316,7 +328,7 @@ impl GlobalSemanticModel {
         local_semantic_model_id: LocalSemanticModelId,
     ) {
         start_of_function("GlobalSemanticModel", "reset_doc_semantics");
-        let Some(class) = self.classes.get_mut(class_id.0) else {
+        let Some(class) = self.classes.get_mut(class_id) else {
             eprintln!("Error: class named {:?} not found", class_name);
             generic_exit_statements("GlobalSemanticModel", "reset_doc_semantics");
             return;
@@ -325,7 +337,7 @@ impl GlobalSemanticModel {
         self.methods.remove(&class_id);
         self.variables.remove(&class_id);
         // reset everything in the local semantic model
-        let Some(local_semantic_model) = self.private.get_mut(local_semantic_model_id.0) else {
+        let Some(local_semantic_model) = self.private.get_mut(local_semantic_model_id) else {
             eprintln!("In reset doc semantics, Error: local model not found");
             generic_exit_statements("GlobalSemanticModel", "reset_doc_semantics");
             return;
@@ -337,16 +349,52 @@ impl GlobalSemanticModel {
     /// Marks the class symbol as inactive and removes all method/variable symbols for the document.
     pub fn remove_document_symbols(&mut self, class_symbol_id: ClassGlobalSymbolId) {
         start_of_function("GlobalSemanticModel", "remove_document_symbols");
-        let Some(class_symbol) = self.class_defs.get_mut(class_symbol_id.0) else {
+        let Some(class_symbol) = self.class_defs.get_mut(class_symbol_id) else {
             eprintln!("In remove_document_symbols, Error: class symbol not found");
             generic_exit_statements("GlobalSemanticModel", "remove_document_symbols");
             return;
         };
         class_symbol.alive = false;
-        // remove all the method and variables symbols defined in given class
+        let url = class_symbol.url.clone();
+        // remove all the method and variables symbols defined in given class, keeping the arenas
+        // so the removed ids stay stale when the document's symbols are added again
+        if let Some(method_symbols) = self.method_defs.get_mut(&class_symbol_id) {
+            method_symbols.clear();
+        }
+        if let Some(var_symbols) = self.variable_defs.get_mut(&class_symbol_id) {
+            var_symbols.clear();
+        }
+        self.unindex_symbols(&url, |symbol_id| {
+            symbol_id.class_symbol_id() == class_symbol_id
+                && !matches!(symbol_id, GlobalSymbolId::Class(_))
+        });
+        successful_exit("GlobalSemanticModel", "remove_document_symbols");
+    }
+
+    /// Drops everything known about a class whose document was removed: its symbols (including
+    /// the class symbol's location), public methods and variables, and local semantic model.
+    ///
+    /// The class, class symbol, and local model slots are freed for the next added document;
+    /// their ids stop resolving instead of pointing at whatever reuses the slots.
+    pub fn remove_class(
+        &mut self,
+        class_id: ClassId,
+        class_symbol_id: ClassGlobalSymbolId,
+        local_semantic_model_id: LocalSemanticModelId,
+    ) {
+        start_of_function("GlobalSemanticModel", "remove_class");
+        self.remove_document_symbols(class_symbol_id);
+        if let Some(class_symbol) = self.class_defs.remove(class_symbol_id) {
+            let url = class_symbol.url;
+            self.unindex_symbols(&url, |id| *id == GlobalSymbolId::Class(class_symbol_id));
+        }
         self.method_defs.remove(&class_symbol_id);
         self.variable_defs.remove(&class_symbol_id);
-        successful_exit("GlobalSemanticModel", "remove_document_symbols");
+        self.classes.remove(class_id);
+        self.methods.remove(&class_id);
+        self.variables.remove(&class_id);
+        self.private.remove(local_semantic_model_id);
+        successful_exit("GlobalSemanticModel", "remove_class");
     }
 
     /// Updates an existing class symbol’s metadata and marks it as alive.
@@ -358,7 +406,7 @@ impl GlobalSemanticModel {
         symbol_id: ClassGlobalSymbolId,
     ) {
         start_of_function("GlobalSemanticModel", "update_class_symbol");
-        let Some(symbol) = self.class_defs.get_mut(symbol_id.0) else {
+        let Some(symbol) = self.class_defs.get_mut(symbol_id) else {
             eprintln!("In update_class_symbol, Error: class symbol not found");
             generic_exit_statements("GlobalSemanticModel", "update_class_symbol");
             return;
@@ -366,7 +414,12 @@ impl GlobalSemanticModel {
         symbol.alive = true;
         symbol.name = name;
         symbol.location = range;
-        symbol.url = url;
+        let old_url = std::mem::replace(&mut symbol.url, url.clone());
+        let class_symbol = GlobalSymbolId::Class(symbol_id);
+        if old_url != url {
+            self.unindex_symbols(&old_url, |id| *id == class_symbol);
+            self.index_symbol(url, class_symbol);
+        }
         successful_exit("GlobalSemanticModel", "update_class_symbol");
     }
 
@@ -378,14 +431,14 @@ impl GlobalSemanticModel {
         url: Url,
     ) -> ClassGlobalSymbolId {
         start_of_function("GlobalSemanticModel", "new_class_symbol");
-        let id = ClassGlobalSymbolId(self.class_defs.len());
         eprintln!("Info: Adding new class symbol for class named {:?}", name);
-        self.class_defs.push(ClassGlobalSymbol {
+        let id = self.class_defs.insert(ClassGlobalSymbol {
             name,
-            url,
+            url: url.clone(),
             location: range,
             alive: true,
         });
+        self.index_symbol(url, GlobalSymbolId::Class(id));
         successful_exit("GlobalSemanticModel", "new_class_symbol");
         id
     }
@@ -402,15 +455,16 @@ impl GlobalSemanticModel {
     ) -> Option<MethodGlobalSymbolId> {
         start_of_function("GlobalSemanticModel", "new_method_symbol");
         eprintln!("Info: Adding new public method symbol for method named {:?} for url path {:?}", name, url.path());
-        let defs = self.method_defs
+        let id = self
+            .method_defs
             .entry(class_symbol_id)
-            .or_insert(Vec::new());
-        let id = MethodGlobalSymbolId(defs.len());
-        defs.push(MethodGlobalSymbol {
+            .or_default()
+            .insert(MethodGlobalSymbol {
                 name,
-                url,
+                url: url.clone(),
                 location: range,
             });
+        self.index_symbol(url, GlobalSymbolId::Method(class_symbol_id, id));
         successful_exit("GlobalSemanticModel", "new_method_symbol");
         Some(id)
     }
@@ -429,26 +483,69 @@ impl GlobalSemanticModel {
     ) -> Option<VariableGlobalSymbolId> {
         start_of_function("GlobalSemanticModel", "new_variable_symbol");
         eprintln!("Info: Adding new public variable symbol for variable named {:?} for url path {:?}", name, url.path());
-        let defs = self.variable_defs
+        let id = self
+            .variable_defs
             .entry(class_symbol_id)
-            .or_insert(Vec::new());
-        let id: VariableGlobalSymbolId = VariableGlobalSymbolId(defs.len());
-        defs.push(VariableGlobalSymbol {
-            name,
-            url,
-            location: range,
-            var_dependencies,
-            property_dependencies,
-        });
+            .or_default()
+            .insert(VariableGlobalSymbol {
+                name,
+                url: url.clone(),
+                location: range,
+                var_dependencies,
+                property_dependencies,
+            });
+        self.index_symbol(url, GlobalSymbolId::Variable(class_symbol_id, id));
         successful_exit("GlobalSemanticModel", "new_variable_symbol");
         Some(id)
     }
 
+    /// Records `symbol_id` in the url lookup map.
+    fn index_symbol(&mut self, url: Url, symbol_id: GlobalSymbolId) {
+        self.url_symbols.entry(url).or_default().push(symbol_id);
+    }
+
+    /// Removes every symbol in document `url` matching `should_remove` from the url lookup map.
+    fn unindex_symbols(&mut self, url: &Url, should_remove: impl Fn(&GlobalSymbolId) -> bool) {
+        let Some(symbol_ids) = self.url_symbols.get_mut(url) else {
+            return;
+        };
+        symbol_ids.retain(|id| !should_remove(id));
+        if symbol_ids.is_empty() {
+            self.url_symbols.remove(url);
+        }
+    }
+
+    /// Returns every global symbol defined in the document at `url`.
+    pub fn get_document_symbol_ids(&self, url: &Url) -> &[GlobalSymbolId] {
+        self.url_symbols
+            .get(url)
+            .map(|ids| ids.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns the definition range of a global symbol, if it still exists.
+    pub fn get_global_symbol_range(&self, symbol_id: GlobalSymbolId) -> Option<Range> {
+        match symbol_id {
+            GlobalSymbolId::Class(id) => self.class_defs.get(id).map(|sym| sym.location),
+            GlobalSymbolId::Method(class_symbol_id, id) => self
+                .method_defs
+                .get(&class_symbol_id)
+                .and_then(|defs| defs.get(id))
+                .map(|sym| sym.location),
+            GlobalSymbolId::Variable(class_symbol_id, id) => self
+                .variable_defs
+                .get(&class_symbol_id)
+                .and_then(|defs| defs.get(id))
+                .map(|sym| sym.location),
+        }
+    }
+
     /// Computes effective class keyword values (procedure block + default language) from inheritance.
     ///
-    /// Fills only missing (`None`) values using the primary parent (leftmost) transitively, with
-    /// cycle protection via DFS state/memoization.
-    pub fn class_keyword_inheritance(&mut self) {
+    /// Fills only the values a class doesn't declare (see `Class::declared_keywords`) using the
+    /// primary parent (leftmost) transitively, with cycle protection via DFS state/memoization.
+    /// If `only` is provided, only those classes are updated; their ancestors are still read.
+    pub fn class_keyword_inheritance(&mut self, only: Option<&[ClassId]>) {
         start_of_function("GlobalSemanticModel", "class_keyword_inheritance");
         #[derive(Clone)]
         struct Snap {
@@ -457,13 +554,17 @@ impl GlobalSemanticModel {
             primary_parent: Option<ClassId>, // leftmost only
         }
 
-        let snaps: Vec<Snap> = self
-            .classes
-            .iter()
-            .map(|c| Snap {
-                declared_pb: c.is_procedure_block,
-                declared_lang: c.default_language.clone(),
-                primary_parent: c.inherited_classes.get(0).copied(),
+        // one snap per slot, the empty slots of removed classes declaring nothing
+        let snaps: Vec<Snap> = (0..self.classes.slot_count())
+            .map(|i| {
+                let class = self.classes.id_at(i).and_then(|id| self.classes.get(id));
+                Snap {
+                    declared_pb: class.and_then(|c| c.declared_keywords.0),
+                    declared_lang: class.and_then(|c| c.declared_keywords.1.clone()),
+                    primary_parent: class
+                        .and_then(|c| c.inherited_classes.get(0).copied())
+                        .filter(|parent| self.classes.contains(*parent)),
+                }
             })
             .collect();
 
@@ -514,18 +615,18 @@ impl GlobalSemanticModel {
             (pb, lang)
         }
 
-        // ---- Phase B: apply (only fill None) ----
-        for i in 0..n {
-            let (eff_pb, eff_lang) = dfs(i, &snaps, &mut memo, &mut state);
-
-            let cls = &mut self.classes[i];
-
-            if cls.is_procedure_block.is_none() {
-                cls.is_procedure_block = eff_pb;
-            }
-            if cls.default_language.is_none() {
-                cls.default_language = eff_lang;
-            }
+        // ---- Phase B: apply (declared values win over inherited ones) ----
+        let class_ids: Vec<ClassId> = match only {
+            Some(class_ids) => class_ids.to_vec(),
+            None => self.classes.ids(),
+        };
+        for class_id in class_ids {
+            let Some(cls) = self.classes.get_mut(class_id) else {
+                continue;
+            };
+            let (eff_pb, eff_lang) = dfs(class_id.0, &snaps, &mut memo, &mut state);
+            cls.is_procedure_block = eff_pb;
+            cls.default_language = eff_lang;
         }
         successful_exit("GlobalSemanticModel", "class_keyword_inheritance");
     }
@@ -533,67 +634,125 @@ impl GlobalSemanticModel {
     /// Builds an override/dispatch index for methods across the inheritance graph.
     ///
     /// Produces:
-    /// - per-class effective public method table,
-    /// - override relationships (`overrides` / `overridden_by`) for public and private declarations.
+    /// - per-class effective public method table, and effective table of all methods,
+    /// - override relationships (`overrides` / `overridden_by`) for public and private declarations,
+    ///   including private methods shadowing inherited private methods,
+    /// - overrides that switch between class and instance methods (`dispatch_mismatches`),
+    /// - custom `<Prop>Get`/`<Prop>Set` accessors of properties (`property_accessors` /
+    ///   `accessor_properties`).
+    ///
+    /// `local_models` maps each class to the local semantic model holding its private methods;
+    /// private methods of classes without one are treated as instance methods. `properties` maps
+    /// each class to the names of the properties it declares.
     ///
     /// IMPORTANT: `class.inherited_classes` must contain direct parents only when called.
-    pub fn build_override_index(&self) -> OverrideIndex {
+    pub fn build_override_index(
+        &self,
+        local_models: &HashMap<ClassId, LocalSemanticModelId>,
+        properties: &HashMap<ClassId, Vec<String>>,
+    ) -> OverrideIndex {
         start_of_function("GlobalSemanticModel", "build_override_index");
         #[derive(Clone)]
         struct ClassSnap {
+            id: ClassId,
             parents: Vec<ClassId>,
             inheritance_direction: String, // "left" or "right"
-            public_methods: Vec<(String, PublicMethodId)>, // declared public methods in this class
-            private_methods: Vec<(String, PrivateMethodId)>,
+            public_methods: Vec<(Name, PublicMethodId)>, // declared public methods in this class
+            private_methods: Vec<(Name, PrivateMethodId)>,
+            method_types: HashMap<Name, MethodType>, // declared method name -> dispatch
         }
 
-        let snaps: Vec<ClassSnap> = self
-            .classes
-            .iter()
-            .map(|c| ClassSnap {
-                parents: c.inherited_classes.clone(),
-                inheritance_direction: c.inheritance_direction.clone(),
-                public_methods: c
+        // one snap per slot, `None` for the empty slots of removed classes
+        let snaps: Vec<Option<ClassSnap>> = (0..self.classes.slot_count())
+            .map(|i| {
+                let id = self.classes.id_at(i)?;
+                let c = self.classes.get(id)?;
+                let public_methods = self.methods.get(&id);
+                let private_methods = local_models
+                    .get(&id)
+                    .and_then(|lsm_id| self.private.get(*lsm_id))
+                    .map(|lsm| &lsm.methods);
+                let method_types = c
                     .public_methods
                     .iter()
-                    .map(|(n, id)| (n.clone(), *id))
-                    .collect(),
-                private_methods: c
-                    .private_methods
-                    .iter()
-                    .map(|(n, id)| (n.clone(), *id))
-                    .collect(),
+                    .filter_map(|(n, id)| {
+                        let method = public_methods?.get(id.0)?;
+                        Some((self.names.intern(n), method.method_type.clone()))
+                    })
+                    .chain(c.private_methods.iter().map(|(n, id)| {
+                        let method_type = private_methods
+                            .and_then(|methods| methods.get(id.0))
+                            .map_or(MethodType::InstanceMethod, |m| m.method_type.clone());
+                        (self.names.intern(n), method_type)
+                    }))
+                    .collect();
+                Some(ClassSnap {
+                    id,
+                    // superclasses removed since inheritance was computed are ignored
+                    parents: c
+                        .inherited_classes
+                        .iter()
+                        .copied()
+                        .filter(|parent| self.classes.contains(*parent))
+                        .collect(),
+                    inheritance_direction: c.inheritance_direction.clone(),
+                    public_methods: c
+                        .public_methods
+                        .iter()
+                        .map(|(n, id)| (self.names.intern(n), *id))
+                        .collect(),
+                    private_methods: c
+                        .private_methods
+                        .iter()
+                        .map(|(n, id)| (self.names.intern(n), *id))
+                        .collect(),
+                    method_types,
+                })
             })
             .collect();
 
         let n = snaps.len();
-        let mut memo: Vec<Option<HashMap<String, MethodRef>>> = vec![None; n];
+        let mut memo: Vec<Option<HashMap<Name, MethodRef>>> = vec![None; n];
         let mut state: Vec<DfsState> = vec![DfsState::Unvisited; n];
         let mut index = OverrideIndex::new();
 
+        // `path`: the classes being visited, each followed by the superclass it is visiting
         fn dfs(
             idx: usize,
-            snaps: &Vec<ClassSnap>,
-            memo: &mut Vec<Option<HashMap<String, MethodRef>>>,
+            snaps: &Vec<Option<ClassSnap>>,
+            memo: &mut Vec<Option<HashMap<Name, MethodRef>>>,
             state: &mut Vec<DfsState>,
+            path: &mut Vec<ClassId>,
             index: &mut OverrideIndex,
-        ) -> HashMap<String, MethodRef> {
+        ) -> HashMap<Name, MethodRef> {
             if let Some(cached) = memo[idx].clone() {
                 return cached;
             }
             if state[idx] == DfsState::Visiting {
                 eprintln!("Cycle detected in inheritance graph");
+                // the cycle is the part of the path from the class back to it
+                let start = path.iter().position(|id| id.0 == idx).unwrap_or(0);
+                let cycle = &path[start..];
+                for k in 0..cycle.len() {
+                    let rotated = cycle[k..].iter().chain(&cycle[..k]);
+                    index
+                        .inheritance_cycles
+                        .entry(cycle[k])
+                        .or_insert_with(|| rotated.copied().collect());
+                }
                 generic_exit_statements("GlobalSemanticModel", "build_override_index");
                 return HashMap::new();
             }
 
+            let Some(snap) = &snaps[idx] else {
+                return HashMap::new();
+            };
+            let cls_id = snap.id;
             state[idx] = DfsState::Visiting;
-
-            let cls_id = ClassId(idx);
-            let snap = &snaps[idx];
+            path.push(cls_id);
 
             // inherited effective table
-            let mut table: HashMap<String, MethodRef> = HashMap::new();
+            let mut table: HashMap<Name, MethodRef> = HashMap::new();
 
             let parent_iter: Box<dyn Iterator<Item = &ClassId>> =
                 if snap.inheritance_direction == "right" {
@@ -603,67 +762,58 @@ impl GlobalSemanticModel {
                 };
 
             for parent in parent_iter {
-                let parent_table = dfs(parent.0, snaps, memo, state, index);
+                let parent_table = dfs(parent.0, snaps, memo, state, path, index);
                 for (name, mref) in parent_table {
                     table.entry(name).or_insert(mref); // first wins
                 }
             }
 
-            // overlay declared methods for this class
-            for (name, child_mid) in &snap.public_methods {
-                let child_ref = MethodRef {
-                    class: cls_id,
-                    pub_id: Some(*child_mid),
-                    priv_id: None,
-                };
-
-                if let Some(base_ref) = table.get(name).copied() {
-                    if let Some(base_pid) = base_ref.pub_id {
-                        let base_pub = PublicMethodRef {
-                            class: base_ref.class,
-                            id: base_pid,
-                        };
-                        index.overrides.insert(child_ref, base_pub);
-                        index
-                            .overridden_by
-                            .entry(base_pub)
-                            .or_default()
-                            .push(child_ref);
-                    }
-                }
-
-                table.insert(name.clone(), child_ref); // child wins
-            }
-
-            for (name, child_mid) in &snap.private_methods {
-                let child_ref = MethodRef {
-                    class: cls_id,
-                    pub_id: None,
-                    priv_id: Some(*child_mid),
-                };
-
-                if let Some(base_ref) = table.get(name).copied() {
-                    let Some(id) = base_ref.pub_id else {
-                        eprintln!("Warning: Expected Base Ref to have a Public Method Id");
-                        generic_exit_statements("GlobalSemanticModel", "build_override_index");
-                        return table;
+            // overlay declared methods for this class, public and private alike
+            let declared = snap
+                .public_methods
+                .iter()
+                .map(|(name, child_mid)| {
+                    let child_ref = MethodRef {
+                        class: cls_id,
+                        pub_id: Some(*child_mid),
+                        priv_id: None,
                     };
-                    let base_ref = PublicMethodRef {
-                        class: base_ref.class,
-                        id,
+                    (name, child_ref)
+                })
+                .chain(snap.private_methods.iter().map(|(name, child_mid)| {
+                    let child_ref = MethodRef {
+                        class: cls_id,
+                        pub_id: None,
+                        priv_id: Some(*child_mid),
                     };
-                    index.overrides.insert(child_ref, base_ref);
-                    index
-                        .overridden_by
-                        .entry(base_ref)
-                        .or_default()
-                        .push(child_ref);
+                    (name, child_ref)
+                }));
+            for (name, child_ref) in declared {
+                if let Some(base) = table
+                    .get(name)
+                    .copied()
+                    .and_then(MethodHandle::from_method_ref)
+                {
+                    index.overrides.insert(child_ref, base);
+                    index.overridden_by.entry(base).or_default().push(child_ref);
+                    let base_type = snaps[base.class().0]
+                        .as_ref()
+                        .and_then(|base_snap| base_snap.method_types.get(name));
+                    if let (Some(child_type), Some(base_type)) =
+                        (snap.method_types.get(name), base_type)
+                    {
+                        if child_type != base_type {
+                            index
+                                .dispatch_mismatches
+                                .insert(child_ref, (base, child_type.clone()));
+                        }
+                    }
                 }
 
                 table.insert(name.clone(), child_ref); // child wins
             }
 
-            let effective_public: HashMap<String, PublicMethodRef> = table
+            let effective_public: HashMap<Name, PublicMethodRef> = table
                 .iter()
                 .filter_map(|(name, mref)| {
                     mref.pub_id.map(|pid| {
@@ -677,18 +827,85 @@ impl GlobalSemanticModel {
                     })
                 })
                 .collect();
+            let effective: HashMap<Name, MethodHandle> = table
+                .iter()
+                .filter_map(|(name, mref)| {
+                    MethodHandle::from_method_ref(*mref).map(|handle| (name.clone(), handle))
+                })
+                .collect();
 
             index
                 .effective_public_methods
                 .insert(cls_id, effective_public);
+            index.effective_methods.insert(cls_id, effective);
 
+            path.pop();
             state[idx] = DfsState::Done;
             memo[idx] = Some(table.clone());
             table
         }
 
+        let mut path = Vec::new();
         for i in 0..n {
-            let _ = dfs(i, &snaps, &mut memo, &mut state, &mut index);
+            let _ = dfs(i, &snaps, &mut memo, &mut state, &mut path, &mut index);
+        }
+
+        // link accessors to the nearest property they access: the class's own, then inherited
+        for snap in snaps.iter().flatten() {
+            let declared = snap
+                .public_methods
+                .iter()
+                .map(|(name, id)| (name, Some(*id), None))
+                .chain(
+                    snap.private_methods
+                        .iter()
+                        .map(|(name, id)| (name, None, Some(*id))),
+                );
+            for (name, pub_id, priv_id) in declared {
+                let Some(property) = name
+                    .strip_suffix("Get")
+                    .or_else(|| name.strip_suffix("Set"))
+                    .filter(|property| !property.is_empty())
+                else {
+                    continue;
+                };
+                let mut visited = Vec::new();
+                let mut queue = vec![snap.id];
+                let mut next = 0;
+                let owner = loop {
+                    let Some(&class_id) = queue.get(next) else {
+                        break None;
+                    };
+                    next += 1;
+                    if visited.contains(&class_id) {
+                        continue;
+                    }
+                    visited.push(class_id);
+                    let declares = properties
+                        .get(&class_id)
+                        .is_some_and(|names| names.iter().any(|other| other == property));
+                    if declares {
+                        break Some(class_id);
+                    }
+                    let parents = snaps[class_id.0].iter().flat_map(|s| &s.parents);
+                    queue.extend(parents.copied());
+                };
+                let Some(owner) = owner else {
+                    continue;
+                };
+                let accessor = MethodRef {
+                    class: snap.id,
+                    pub_id,
+                    priv_id,
+                };
+                let key = (owner, property.to_string());
+                index
+                    .property_accessors
+                    .entry(key.clone())
+                    .or_default()
+                    .push(accessor);
+                index.accessor_properties.insert(accessor, key);
+            }
         }
         successful_exit("GlobalSemanticModel", "build_override_index");
         index
@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Share of the progress the file scan of `ProjectState::index_workspace_scope` accounts for;
+/// the inheritance build after it takes the rest.
+const FILE_SCAN_PERCENTAGE: usize = 90;
+
+/// Progress of `ProjectState::index_workspace_scope`, updated from the blocking indexing thread
+/// and polled by the server to report work-done progress.
+#[derive(Debug, Default)]
+pub struct IndexProgress {
+    /// Number of source files to index, archive items included.
+    files: AtomicUsize,
+    /// Number of those files indexed so far.
+    indexed: AtomicUsize,
+    /// Whether the scan is done and inheritance and variables are being built.
+    building_inheritance: AtomicBool,
+}
+
+impl IndexProgress {
+    /// Creates the progress of an indexing that hasn't started.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts over for an indexing of `files` files.
+    pub fn start(&self, files: usize) {
+        self.files.store(files, Ordering::Release);
+        self.indexed.store(0, Ordering::Release);
+        self.building_inheritance.store(false, Ordering::Release);
+    }
+
+    /// Records that one more file was indexed.
+    pub fn file_indexed(&self) {
+        self.indexed.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Records that every file was scanned and the inheritance build started.
+    pub fn start_inheritance_build(&self) {
+        self.building_inheritance.store(true, Ordering::Release);
+    }
+
+    /// Returns the message and percentage to report, e.g. `412/1890 files` at 19%.
+    pub fn report(&self) -> (String, u32) {
+        let files = self.files.load(Ordering::Acquire);
+        if self.building_inheritance.load(Ordering::Acquire) {
+            return (
+                format!("Building class inheritance ({files} files)"),
+                FILE_SCAN_PERCENTAGE as u32,
+            );
+        }
+        let indexed = self.indexed.load(Ordering::Acquire).min(files);
+        let percentage = indexed * FILE_SCAN_PERCENTAGE / files.max(1);
+        (format!("{indexed}/{files} files"), percentage as u32)
+    }
+}
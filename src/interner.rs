@@ -0,0 +1,89 @@
+use parking_lot::Mutex;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An interned class, method, or variable name.
+///
+/// Cloning a `Name` only bumps a reference count, and names interned by the same `Interner`
+/// share one allocation. A `Name` hashes and compares like the `str` it holds, so maps keyed by
+/// `Name` can be looked up with a `&str`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Name(Arc<str>);
+
+impl Name {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+/// Hands out one shared `Name` per distinct string.
+///
+/// Clones of an `Interner` share its table, so the copies of a semantic model taken for
+/// snapshots keep reusing the names the model interned. Names stay in the table until `purge`
+/// finds them unused.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    names: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    /// Creates an interner holding no names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the `Name` for `name`, allocating it the first time `name` is interned.
+    pub fn intern(&self, name: &str) -> Name {
+        let mut names = self.names.lock();
+        if let Some(existing) = names.get(name) {
+            return Name(existing.clone());
+        }
+        let interned: Arc<str> = Arc::from(name);
+        names.insert(interned.clone());
+        Name(interned)
+    }
+
+    /// Drops the names no `Name` refers to any more, such as those of removed classes and
+    /// methods, so the table doesn't keep every name ever interned. Returns how many were dropped.
+    pub fn purge(&self) -> usize {
+        let mut names = self.names.lock();
+        let before = names.len();
+        names.retain(|name| Arc::strong_count(name) > 1);
+        before - names.len()
+    }
+}
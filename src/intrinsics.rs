@@ -0,0 +1,422 @@
+use crate::common::position_encoding;
+use crate::config::MethodInsertStyle;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, InsertTextFormat,
+    MarkupContent, MarkupKind, Position, Range, TextEdit,
+};
+
+/// An ObjectScript intrinsic (`$`) function.
+#[derive(Debug)]
+pub struct IntrinsicFunction {
+    /// Full name, e.g. `$PIECE`.
+    pub name: &'static str,
+    /// Abbreviated name, e.g. `$P`, if the function has one.
+    pub abbreviation: Option<&'static str>,
+    pub arguments: &'static [&'static str],
+    /// Number of leading `arguments` that can't be omitted.
+    pub required: usize,
+    pub doc: &'static str,
+}
+
+impl IntrinsicFunction {
+    /// Renders the function signature with its optional arguments in brackets, e.g.
+    /// `$PIECE(string, delimiter[, from[, to]])`.
+    pub fn signature(&self) -> String {
+        let mut signature = format!("{}(", self.name);
+        for (i, argument) in self.arguments.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            if i < self.required {
+                signature.push_str(&format!("{separator}{argument}"));
+            } else {
+                signature.push_str(&format!("[{separator}{argument}"));
+            }
+        }
+        signature.push_str(&"]".repeat(self.arguments.len().saturating_sub(self.required)));
+        signature.push(')');
+        signature
+    }
+}
+
+/// The commonly used intrinsic functions, sorted by name.
+pub const INTRINSIC_FUNCTIONS: [IntrinsicFunction; 40] = [
+    IntrinsicFunction {
+        name: "$ASCII",
+        abbreviation: Some("$A"),
+        arguments: &["string", "position"],
+        required: 1,
+        doc: "Returns the character code of the character at `position` (default 1) of `string`.",
+    },
+    IntrinsicFunction {
+        name: "$BIT",
+        abbreviation: None,
+        arguments: &["bitstring", "position"],
+        required: 2,
+        doc: "Returns the bit (0 or 1) at `position` of `bitstring`.",
+    },
+    IntrinsicFunction {
+        name: "$CASE",
+        abbreviation: None,
+        arguments: &["target", "value:result"],
+        required: 2,
+        doc: "Returns the `result` paired with the first `value` equal to `target`, or the `:default` result.",
+    },
+    IntrinsicFunction {
+        name: "$CHAR",
+        abbreviation: Some("$C"),
+        arguments: &["code"],
+        required: 1,
+        doc: "Returns the string made of the characters with the given codes.",
+    },
+    IntrinsicFunction {
+        name: "$DATA",
+        abbreviation: Some("$D"),
+        arguments: &["variable", "target"],
+        required: 1,
+        doc: "Returns 0 if `variable` is undefined, 1 if it has a value, 10 if it only has descendants, and 11 if it has both.",
+    },
+    IntrinsicFunction {
+        name: "$EXTRACT",
+        abbreviation: Some("$E"),
+        arguments: &["string", "from", "to"],
+        required: 1,
+        doc: "Returns the characters of `string` from position `from` (default 1) to `to` (default `from`).",
+    },
+    IntrinsicFunction {
+        name: "$FIND",
+        abbreviation: Some("$F"),
+        arguments: &["string", "substring", "position"],
+        required: 2,
+        doc: "Returns the position just after the first `substring` in `string` at or after `position`, or 0.",
+    },
+    IntrinsicFunction {
+        name: "$FNUMBER",
+        abbreviation: Some("$FN"),
+        arguments: &["number", "format", "decimals"],
+        required: 2,
+        doc: "Formats `number`, e.g. with `,` thousands separators or `decimals` digits.",
+    },
+    IntrinsicFunction {
+        name: "$GET",
+        abbreviation: Some("$G"),
+        arguments: &["variable", "default"],
+        required: 1,
+        doc: "Returns the value of `variable`, or `default` (an empty string if omitted) if it is undefined.",
+    },
+    IntrinsicFunction {
+        name: "$INCREMENT",
+        abbreviation: Some("$I"),
+        arguments: &["variable", "increment"],
+        required: 1,
+        doc: "Atomically adds `increment` (default 1) to `variable` and returns the new value.",
+    },
+    IntrinsicFunction {
+        name: "$ISOBJECT",
+        abbreviation: None,
+        arguments: &["expression"],
+        required: 1,
+        doc: "Returns 1 if `expression` is an object reference, 0 if it isn't, and -1 for an invalid reference.",
+    },
+    IntrinsicFunction {
+        name: "$ISVALIDNUM",
+        abbreviation: None,
+        arguments: &["number", "scale", "min", "max"],
+        required: 1,
+        doc: "Returns 1 if `number` is a valid number within `min` and `max`, 0 otherwise.",
+    },
+    IntrinsicFunction {
+        name: "$JUSTIFY",
+        abbreviation: Some("$J"),
+        arguments: &["expression", "width", "decimals"],
+        required: 2,
+        doc: "Right-aligns `expression` in `width` characters, rounded to `decimals` digits.",
+    },
+    IntrinsicFunction {
+        name: "$LENGTH",
+        abbreviation: Some("$L"),
+        arguments: &["string", "delimiter"],
+        required: 1,
+        doc: "Returns the number of characters of `string`, or its number of `delimiter`-separated pieces.",
+    },
+    IntrinsicFunction {
+        name: "$LIST",
+        abbreviation: Some("$LI"),
+        arguments: &["list", "from", "to"],
+        required: 1,
+        doc: "Returns the element at position `from` (default 1) of `list`, or a sublist up to `to`.",
+    },
+    IntrinsicFunction {
+        name: "$LISTBUILD",
+        abbreviation: Some("$LB"),
+        arguments: &["element"],
+        required: 0,
+        doc: "Builds a list of the given elements.",
+    },
+    IntrinsicFunction {
+        name: "$LISTDATA",
+        abbreviation: Some("$LD"),
+        arguments: &["list", "position"],
+        required: 1,
+        doc: "Returns 1 if the element at `position` of `list` has a value, 0 otherwise.",
+    },
+    IntrinsicFunction {
+        name: "$LISTFIND",
+        abbreviation: Some("$LF"),
+        arguments: &["list", "value", "start"],
+        required: 2,
+        doc: "Returns the position of the first element of `list` equal to `value` after `start`, or 0.",
+    },
+    IntrinsicFunction {
+        name: "$LISTFROMSTRING",
+        abbreviation: Some("$LFS"),
+        arguments: &["string", "delimiter"],
+        required: 1,
+        doc: "Builds a list of the `delimiter`-separated (default `,`) pieces of `string`.",
+    },
+    IntrinsicFunction {
+        name: "$LISTGET",
+        abbreviation: Some("$LG"),
+        arguments: &["list", "position", "default"],
+        required: 1,
+        doc: "Returns the element at `position` of `list`, or `default` if it is missing or has no value.",
+    },
+    IntrinsicFunction {
+        name: "$LISTLENGTH",
+        abbreviation: Some("$LL"),
+        arguments: &["list"],
+        required: 1,
+        doc: "Returns the number of elements of `list`.",
+    },
+    IntrinsicFunction {
+        name: "$LISTNEXT",
+        abbreviation: None,
+        arguments: &["list", "pointer", "value"],
+        required: 3,
+        doc: "Sets `value` to the next element of `list` after `pointer`; returns 0 at the end of the list.",
+    },
+    IntrinsicFunction {
+        name: "$LISTTOSTRING",
+        abbreviation: Some("$LTS"),
+        arguments: &["list", "delimiter", "flags"],
+        required: 1,
+        doc: "Joins the elements of `list` with `delimiter` (default `,`).",
+    },
+    IntrinsicFunction {
+        name: "$LISTVALID",
+        abbreviation: Some("$LV"),
+        arguments: &["expression"],
+        required: 1,
+        doc: "Returns 1 if `expression` is a valid list, 0 otherwise.",
+    },
+    IntrinsicFunction {
+        name: "$NUMBER",
+        abbreviation: Some("$NUM"),
+        arguments: &["number", "format", "min", "max"],
+        required: 1,
+        doc: "Validates and converts `number`, returning an empty string if it is invalid.",
+    },
+    IntrinsicFunction {
+        name: "$ORDER",
+        abbreviation: Some("$O"),
+        arguments: &["variable", "direction", "target"],
+        required: 1,
+        doc: "Returns the next subscript of `variable` at its level, or the previous one if `direction` is -1.",
+    },
+    IntrinsicFunction {
+        name: "$PIECE",
+        abbreviation: Some("$P"),
+        arguments: &["string", "delimiter", "from", "to"],
+        required: 2,
+        doc: "Returns the `delimiter`-separated pieces of `string` from `from` (default 1) to `to`.",
+    },
+    IntrinsicFunction {
+        name: "$QUERY",
+        abbreviation: Some("$Q"),
+        arguments: &["reference", "direction", "target"],
+        required: 1,
+        doc: "Returns the next node of the array containing `reference`, as a full reference.",
+    },
+    IntrinsicFunction {
+        name: "$RANDOM",
+        abbreviation: Some("$R"),
+        arguments: &["range"],
+        required: 1,
+        doc: "Returns a random integer from 0 to `range` - 1.",
+    },
+    IntrinsicFunction {
+        name: "$REPLACE",
+        abbreviation: None,
+        arguments: &["string", "old", "new", "start", "count", "case"],
+        required: 3,
+        doc: "Replaces the occurrences of `old` in `string` with `new`.",
+    },
+    IntrinsicFunction {
+        name: "$REVERSE",
+        abbreviation: Some("$RE"),
+        arguments: &["string"],
+        required: 1,
+        doc: "Returns the characters of `string` in reverse order.",
+    },
+    IntrinsicFunction {
+        name: "$SELECT",
+        abbreviation: Some("$S"),
+        arguments: &["condition:value"],
+        required: 1,
+        doc: "Returns the `value` paired with the first true `condition`.",
+    },
+    IntrinsicFunction {
+        name: "$TRANSLATE",
+        abbreviation: Some("$TR"),
+        arguments: &["string", "identifier", "associator"],
+        required: 2,
+        doc: "Replaces each character of `identifier` in `string` with the character at the same position of `associator`, or removes it.",
+    },
+    IntrinsicFunction {
+        name: "$ZCONVERT",
+        abbreviation: Some("$ZCVT"),
+        arguments: &["string", "mode", "table"],
+        required: 2,
+        doc: "Converts the case (`U`, `L`, `W`, `S`) or the encoding (`I`, `O`) of `string`.",
+    },
+    IntrinsicFunction {
+        name: "$ZDATE",
+        abbreviation: Some("$ZD"),
+        arguments: &["hdate", "dformat"],
+        required: 1,
+        doc: "Formats the `$HOROLOG` date `hdate` as a date string in the `dformat` format.",
+    },
+    IntrinsicFunction {
+        name: "$ZDATEH",
+        abbreviation: Some("$ZDH"),
+        arguments: &["date", "dformat"],
+        required: 1,
+        doc: "Converts the date string `date` to a `$HOROLOG` date.",
+    },
+    IntrinsicFunction {
+        name: "$ZDATETIME",
+        abbreviation: Some("$ZDT"),
+        arguments: &["hdatetime", "dformat", "tformat", "precision"],
+        required: 1,
+        doc: "Formats the `$HOROLOG` date and time `hdatetime` as a date and time string.",
+    },
+    IntrinsicFunction {
+        name: "$ZDATETIMEH",
+        abbreviation: Some("$ZDTH"),
+        arguments: &["datetime", "dformat", "tformat"],
+        required: 1,
+        doc: "Converts the date and time string `datetime` to the `$HOROLOG` format.",
+    },
+    IntrinsicFunction {
+        name: "$ZSTRIP",
+        abbreviation: None,
+        arguments: &["string", "action", "remchar", "keepchar"],
+        required: 2,
+        doc: "Removes characters from `string`, e.g. `<>W` strips leading and trailing whitespace.",
+    },
+    IntrinsicFunction {
+        name: "$ZTIMESTAMP",
+        abbreviation: None,
+        arguments: &[],
+        required: 0,
+        doc: "Returns the current UTC date and time in `$HOROLOG` format, with fractional seconds.",
+    },
+];
+
+/// Returns the function name typed after `$` at the end of `line_before_cursor` (without the
+/// `$`), if the cursor is in an intrinsic function name.
+///
+/// `$$label` calls an extrinsic function, and `$` inside a name (`a$b`) starts no function.
+pub fn intrinsic_function_prefix(line_before_cursor: &str) -> Option<&str> {
+    let name_start = line_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric())
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line_before_cursor.len());
+    let before = line_before_cursor[..name_start].strip_suffix('$')?;
+    if before.ends_with(|c: char| c == '$' || c == '%' || c == '^' || c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(&line_before_cursor[name_start..])
+}
+
+/// Completes the intrinsic functions whose names, or abbreviations, start with `partial`.
+///
+/// Names are inserted in lowercase when `partial` has lowercase letters, and in uppercase
+/// otherwise; they are inserted as configured by `style`, snippets only when the client supports
+/// them (`snippets`). Items replace the `$` and the typed name, which ends at the cursor
+/// `position`.
+pub fn intrinsic_function_completions(
+    partial: &str,
+    position: Position,
+    style: MethodInsertStyle,
+    snippets: bool,
+) -> Vec<CompletionItem> {
+    // the `$` is replaced too
+    let typed_len = position_encoding().units(partial) + 1;
+    let replace_range = Range {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_len),
+        },
+        end: position,
+    };
+    let lowercase = partial.chars().any(|c| c.is_ascii_lowercase());
+    let typed = format!("${}", partial.to_ascii_uppercase());
+    INTRINSIC_FUNCTIONS
+        .iter()
+        .filter(|function| {
+            function.name.starts_with(&typed)
+                || function
+                    .abbreviation
+                    .is_some_and(|abbreviation| abbreviation.starts_with(&typed))
+        })
+        .map(|function| {
+            let name = if lowercase {
+                function.name.to_ascii_lowercase()
+            } else {
+                function.name.to_string()
+            };
+            let (new_text, format) = match style {
+                MethodInsertStyle::Name => (name.clone(), InsertTextFormat::PLAIN_TEXT),
+                MethodInsertStyle::Snippet if snippets => {
+                    let tab_stops: Vec<String> = function
+                        .arguments
+                        .iter()
+                        .take(function.required)
+                        .enumerate()
+                        .map(|(i, argument)| format!("${{{}:{}}}", i + 1, argument))
+                        .collect();
+                    // `$` starts a tab stop in snippets
+                    (
+                        format!("\\{name}({})$0", tab_stops.join(", ")),
+                        InsertTextFormat::SNIPPET,
+                    )
+                }
+                MethodInsertStyle::Parens | MethodInsertStyle::Snippet => {
+                    (format!("{name}()"), InsertTextFormat::PLAIN_TEXT)
+                }
+            };
+            let mut documentation = function.doc.to_string();
+            if let Some(abbreviation) = function.abbreviation {
+                documentation.push_str(&format!("\n\nAbbreviation: `{abbreviation}`"));
+            }
+            CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(function.signature()),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: documentation,
+                })),
+                filter_text: Some(name),
+                insert_text_format: Some(format),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: replace_range,
+                    new_text,
+                })),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
@@ -1,20 +1,30 @@
 use crate::common::{generic_exit_statements, start_of_function, successful_exit};
-use crate::parse_structures::{Variable, PrivateVarId, Method, PrivateMethodId, ClassProperty};
+use crate::parse_structures::{
+    ClassMemberDefinition, ClassParameter, ClassProperty, ClassXData, MemberDefinitionId, Method,
+    ParameterId, PrivateMethodId, PrivateVarId, PropertyId, Variable, XDataId,
+};
 impl LocalSemanticModel {
     /// Creates a new, empty `LocalSemanticModel` with `active` set to `true`.
     pub fn new() -> Self {
         Self {
             methods: Vec::new(),
             properties: Vec::new(),
+            parameters: Vec::new(),
+            xdata_blocks: Vec::new(),
+            member_definitions: Vec::new(),
             variables: Vec::new(),
             active: true,
         }
     }
 
-    /// Clears all stored methods/properties/variables and marks the model as inactive.
+    /// Clears all stored methods/properties/parameters/XData blocks/member definitions/variables
+    /// and marks the model as inactive.
     pub fn clear(&mut self) {
         self.methods.clear();
         self.properties.clear();
+        self.parameters.clear();
+        self.xdata_blocks.clear();
+        self.member_definitions.clear();
         self.variables.clear();
         self.active = false;
     }
@@ -49,6 +59,96 @@ impl LocalSemanticModel {
         id
     }
 
+    /// Adds a property of the class to this model and returns its `PropertyId`.
+    ///
+    /// The returned id is the index of the property in the internal `properties` vector; public
+    /// and private properties share it.
+    pub(crate) fn new_property(&mut self, property: ClassProperty) -> PropertyId {
+        start_of_function("LocalSemanticModel", "new_property");
+        let id = PropertyId(self.properties.len());
+        eprintln!(
+            "Info: Adding Property {:?} to local semantic model",
+            property.name.as_str()
+        );
+        self.properties.push(property);
+        successful_exit("LocalSemanticModel", "new_property");
+        id
+    }
+
+    /// Returns the property at `property_id`, or `None` if the index is out of bounds.
+    pub(crate) fn get_property(&self, property_id: PropertyId) -> Option<&ClassProperty> {
+        self.properties.get(property_id.0)
+    }
+
+    /// Adds a parameter of the class to this model and returns its `ParameterId`.
+    ///
+    /// The returned id is the index of the parameter in the internal `parameters` vector.
+    pub(crate) fn new_parameter(&mut self, parameter: ClassParameter) -> ParameterId {
+        start_of_function("LocalSemanticModel", "new_parameter");
+        let id = ParameterId(self.parameters.len());
+        eprintln!(
+            "Info: Adding Parameter {:?} to local semantic model",
+            parameter.name.as_str()
+        );
+        self.parameters.push(parameter);
+        successful_exit("LocalSemanticModel", "new_parameter");
+        id
+    }
+
+    /// Returns the parameter at `parameter_id`, or `None` if the index is out of bounds.
+    pub(crate) fn get_parameter(&self, parameter_id: ParameterId) -> Option<&ClassParameter> {
+        self.parameters.get(parameter_id.0)
+    }
+
+    /// Adds an XData block of the class to this model and returns its `XDataId`.
+    ///
+    /// The returned id is the index of the block in the internal `xdata_blocks` vector.
+    pub(crate) fn new_xdata(&mut self, xdata: ClassXData) -> XDataId {
+        start_of_function("LocalSemanticModel", "new_xdata");
+        let id = XDataId(self.xdata_blocks.len());
+        eprintln!(
+            "Info: Adding XData {:?} to local semantic model",
+            xdata.name.as_str()
+        );
+        self.xdata_blocks.push(xdata);
+        successful_exit("LocalSemanticModel", "new_xdata");
+        id
+    }
+
+    /// Returns the XData block at `xdata_id`, or `None` if the index is out of bounds.
+    pub(crate) fn get_xdata(&self, xdata_id: XDataId) -> Option<&ClassXData> {
+        self.xdata_blocks.get(xdata_id.0)
+    }
+
+    /// Adds an index, foreign key, trigger, query, projection, or storage definition of the class
+    /// to this model and returns its `MemberDefinitionId`.
+    ///
+    /// The returned id is the index of the definition in the internal `member_definitions`
+    /// vector.
+    pub(crate) fn new_member_definition(
+        &mut self,
+        definition: ClassMemberDefinition,
+    ) -> MemberDefinitionId {
+        start_of_function("LocalSemanticModel", "new_member_definition");
+        let id = MemberDefinitionId(self.member_definitions.len());
+        eprintln!(
+            "Info: Adding {:?} {:?} to local semantic model",
+            definition.kind,
+            definition.name.as_str()
+        );
+        self.member_definitions.push(definition);
+        successful_exit("LocalSemanticModel", "new_member_definition");
+        id
+    }
+
+    /// Returns the member definition at `definition_id`, or `None` if the index is out of bounds.
+    pub(crate) fn get_member_definition(
+        &self,
+        definition_id: MemberDefinitionId,
+    ) -> Option<&ClassMemberDefinition> {
+        self.member_definitions.get(definition_id.0)
+    }
+
     /// Returns an immutable reference to the private/local method at `private_method_id`.
     ///
     /// Logs a warning and returns `None` if the index is out of bounds.
@@ -82,13 +182,17 @@ impl LocalSemanticModel {
     }
 }
 
-/// Per-document private semantic state (methods, properties, variables).
+/// Per-document private semantic state (methods, properties, parameters, XData blocks, other
+/// member definitions, variables).
 ///
 /// This is used for private members that should not be shared across classes globally.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct LocalSemanticModel {
     pub methods: Vec<Method>,
     pub properties: Vec<ClassProperty>,
+    pub parameters: Vec<ClassParameter>,
+    pub xdata_blocks: Vec<ClassXData>,
+    pub member_definitions: Vec<ClassMemberDefinition>,
     pub variables: Vec<Variable>,
     pub active: bool,
 }
@@ -1,32 +1,85 @@
+use crate::atelier::is_read_only_url;
+use crate::cancellation::run_cancellable;
+use crate::capabilities::server_capabilities;
+use crate::case_rules::{check_rename, IdentifierKind};
+use crate::commands::{
+    all_commands, uri_argument, CREATE_CLASS, DIFF_SNAPSHOTS, DOCTOR, DUMP_INDEX, EXPORT_TAGS,
+    EXPORT_USAGE, FIX_ALL_IN_WORKSPACE, LIST_SNAPSHOTS, RENAME_PACKAGE, RULE_DOCS, SHOW_SCOPES,
+    TAKE_SNAPSHOT,
+};
 use crate::common::{
-    advance_point, generic_exit_statements, generic_skipping_statements, get_class_name_from_root,
-    get_string_at_byte_range, method_name_from_identifier_node, point_to_byte, position_to_point,
-    start_of_function, successful_exit, ts_range_to_lsp_range,
+    apply_content_changes, generic_exit_statements, generic_skipping_statements,
+    get_class_name_from_root, get_string_at_byte_range, method_name_from_identifier_node,
+    oref_method_name_node, point_to_byte, point_to_lsp_position, position_to_point,
+    set_position_encoding, start_of_function, successful_exit, ts_range_to_lsp_range,
+    PositionEncoding,
+};
+use crate::completion::{
+    class_name_prefix, close_class_qualifiers, commit_class_qualifiers,
+    is_class_qualifier_position, method_completions, method_name_prefix,
+    package_segment_completions, parameter_completions, parameter_name_prefix,
+    property_completions, MethodTarget,
 };
 use crate::config::Config;
+use crate::diagnostics::{is_simple_variable_name, DEBUG_WRITE_CODE};
+use crate::doctor::{
+    check_grammar, check_node_types, check_position_encoding, check_system_stubs, check_writable,
+    render_report,
+};
+use crate::document::TextBuffer;
+use crate::intrinsics::{intrinsic_function_completions, intrinsic_function_prefix};
+use crate::macros::{macro_completions, macro_prefix};
+use crate::package_rename::{is_class_name, is_package_name};
 use crate::parse_structures::FileType;
+use crate::response_cache::ResponseKey;
+use crate::rules::{rule_code, rule_docs, UNRESOLVED_REFERENCE_RULE};
+use crate::semantic_diff::{semantic_diff, SemanticDiffParams};
+use crate::semantic_tokens::token_edits;
 use crate::server::BackendWrapper;
+use crate::source_provider::{ClientSourceProvider, FsSourceProvider, SourceProvider};
+use crate::sql_surface::SqlSurfaceParams;
+use crate::system_api::{system_api_completions, system_api_prefix};
+use crate::tags::render_ctags;
+use crate::unresolved::UnresolvedParams;
+use crate::usage::{render_usage_csv, render_usage_json};
 use crate::workspace::ProjectState;
 use parking_lot::RwLock;
 use serde_json;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tower_lsp::jsonrpc::Result;
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::request::{GotoImplementationParams, GotoImplementationResponse};
 use tower_lsp::lsp_types::{
-    CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidChangeWatchedFilesRegistrationOptions, DidOpenTextDocumentParams, FileSystemWatcher,
-    GlobPattern, GotoDefinitionParams, GotoDefinitionResponse, ImplementationProviderCapability,
-    InitializeParams, InitializeResult, InitializedParams, Location, MessageType, OneOf,
-    Registration, ServerCapabilities, ServerInfo, TextDocumentClientCapabilities,
-    TextDocumentSyncCapability, TextDocumentSyncKind, WatchKind,
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    CodeLens, CodeLensParams, Command, CompletionParams, CompletionResponse,
+    DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentHighlight, DocumentHighlightKind,
+    DocumentHighlightParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
+    DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandParams, FileSystemWatcher,
+    FoldingRange, FoldingRangeParams, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, InitializeParams, InitializeResult, InitializedParams,
+    LinkedEditingRangeParams, LinkedEditingRanges, Location, MarkupContent, MarkupKind,
+    MessageType, NumberOrString, Position, PositionEncodingKind, Range, ReferenceParams,
+    Registration, RenameParams, SemanticTokens, SemanticTokensDelta, SemanticTokensDeltaParams,
+    SemanticTokensFullDeltaResult, SemanticTokensParams, SemanticTokensRangeParams,
+    SemanticTokensRangeResult, SemanticTokensResult, ServerInfo, SignatureHelp,
+    SignatureHelpParams, SymbolInformation, TextDocumentClientCapabilities,
+    TextDocumentPositionParams, TextEdit, Url, WorkspaceEdit, WorkspaceSymbolParams,
 };
 use tower_lsp::LanguageServer;
-use tree_sitter::{InputEdit, Tree};
+use tree_sitter::{Point, Tree};
 
 static ENABLE_SNIPPETS: AtomicBool = AtomicBool::new(false);
 static CLIENT_CAPABILITIES: RwLock<Option<TextDocumentClientCapabilities>> = RwLock::new(None);
+static CLIENT_POSITION_ENCODINGS: RwLock<Option<Vec<PositionEncodingKind>>> = RwLock::new(None);
 
 fn set_client_text_document(text_document: Option<TextDocumentClientCapabilities>) {
     let mut data = CLIENT_CAPABILITIES.write();
@@ -38,23 +91,14 @@ pub fn get_client_capabilities() -> Option<TextDocumentClientCapabilities> {
     data.clone()
 }
 
-fn build_caps(cfg: &Config) -> ServerCapabilities {
-    ServerCapabilities {
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(
-            TextDocumentSyncKind::INCREMENTAL,
-        )),
-        definition_provider: Some(OneOf::Left(true)),
-        references_provider: Some(OneOf::Left(true)),
-        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
-        document_formatting_provider: cfg.enable_formatting.then_some(OneOf::Left(true)),
-        document_symbol_provider: Some(OneOf::Left(true)),
-        workspace_symbol_provider: Some(OneOf::Left(true)),
-        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
-
-        // TODO: need to do dotted statement formatting
-        // document_formatting_provider: cfg.enable_formatting.then_some(OneOf::Left(true)),
-        ..Default::default()
-    }
+fn set_client_position_encodings(encodings: Option<Vec<PositionEncodingKind>>) {
+    let mut data = CLIENT_POSITION_ENCODINGS.write();
+    *data = encodings;
+}
+
+pub fn get_client_position_encodings() -> Option<Vec<PositionEncodingKind>> {
+    let data = CLIENT_POSITION_ENCODINGS.read();
+    data.clone()
 }
 
 pub fn are_snippets_enabled() -> bool {
@@ -76,32 +120,61 @@ impl LanguageServer for BackendWrapper {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
         start_of_function("LSP", "initialize");
         // negotiate w/ client to set config for formatting, lint, snippets
-        let negotiations: Config = params
-            .initialization_options
+        let initialization_options = params.initialization_options;
+        let negotiations: Config = initialization_options
+            .clone()
             .and_then(|v| serde_json::from_value::<Config>(v).ok())
             .unwrap_or_default();
 
         // set negotiated config
         ENABLE_SNIPPETS.store(negotiations.enable_snippets, Ordering::Relaxed);
         set_client_text_document(params.capabilities.text_document);
+        set_client_position_encodings(
+            params
+                .capabilities
+                .general
+                .and_then(|general| general.position_encodings),
+        );
+        set_position_encoding(
+            PositionEncoding::negotiate(get_client_position_encodings().as_deref())
+                .unwrap_or_default(),
+        );
 
+        // capabilities reflect the client's options and every workspace's config
+        let mut configs = vec![negotiations];
         if let Some(folders) = params.workspace_folders {
             for folder in folders {
-                let Ok(project_root) = folder.uri.to_file_path() else {
+                // non-file workspaces (e.g. isfs://) are read through the client
+                let project_root = folder.uri.to_file_path().ok();
+                let sources: Arc<dyn SourceProvider> = if project_root.is_some() {
+                    Arc::new(FsSourceProvider)
+                } else {
                     self.0
                         .client
-                        .log_message(MessageType::ERROR, "Failed to get project root path")
+                        .log_message(
+                            MessageType::INFO,
+                            format!(
+                                "Reading non-file workspace {} through the client",
+                                folder.uri
+                            ),
+                        )
                         .await;
-                    continue;
+                    Arc::new(ClientSourceProvider::new(self.0.client.clone()))
                 };
                 // create projectState and set the projectRoot
                 let state = ProjectState::new();
-                if state.project_root_path.set(Some(project_root)).is_err() {
+                let config = Config::load(initialization_options.clone(), project_root.as_deref());
+                configs.push(config.clone());
+                state.data_mut().config = config;
+                if state.project_root_path.set(project_root).is_err()
+                    || state.project_root_uri.set(folder.uri.clone()).is_err()
+                    || state.source_provider.set(sources).is_err()
+                {
                     self.0
                         .client
                         .log_message(
                             MessageType::WARNING,
-                            "project_root_path was already set; ignoring duplicate initialize",
+                            "project root was already set; ignoring duplicate initialize",
                         )
                         .await;
                 }
@@ -112,7 +185,7 @@ impl LanguageServer for BackendWrapper {
         }
         successful_exit("LSP", "initialize");
         Ok(InitializeResult {
-            capabilities: build_caps(&negotiations),
+            capabilities: server_capabilities(&configs, get_client_capabilities().as_ref()),
             server_info: Some(ServerInfo {
                 name: "objectscript-lsp".to_string(),
                 version: Some(env!("CARGO_PKG_VERSION").to_string()),
@@ -121,16 +194,16 @@ impl LanguageServer for BackendWrapper {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        // register watchers for any .cls, .mac, and .inc files in the workspace
+        // register the watchers each workspace's sources ask for
         start_of_function("LSP", "initialized");
-        let globs = ["**/*.cls", "**/*.mac", "**/*.inc"];
-        let watchers = globs
-            .into_iter()
-            .map(|g| FileSystemWatcher {
-                glob_pattern: GlobPattern::String(g.to_string()).into(),
-                kind: Some(WatchKind::Create | WatchKind::Change | WatchKind::Delete),
-            })
-            .collect();
+        let mut watchers: Vec<FileSystemWatcher> = Vec::new();
+        for (root, project) in self.0.projects.read().iter() {
+            for watcher in project.sources().watch(root) {
+                if !watchers.contains(&watcher) {
+                    watchers.push(watcher);
+                }
+            }
+        }
         let options = DidChangeWatchedFilesRegistrationOptions { watchers };
 
         let register_options = match serde_json::to_value(options) {
@@ -155,6 +228,8 @@ impl LanguageServer for BackendWrapper {
             .register_capability(vec![registration])
             .await
             .ok();
+        // config-gated features the client registers dynamically
+        self.0.sync_dynamic_capabilities().await;
 
         if let Ok(Some(folders)) = self.0.client.workspace_folders().await {
             for workspace in folders {
@@ -171,121 +246,27 @@ impl LanguageServer for BackendWrapper {
         &self,
         params: GotoDefinitionParams,
     ) -> Result<Option<GotoDefinitionResponse>> {
-        start_of_function("LSP", "goto_definition");
-        let uri = params.text_document_position_params.text_document.uri;
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .clone();
         let position = params.text_document_position_params.position;
+        let response = self.uncached_goto_definition(params);
+        self.cached_response("textDocument/definition", &uri, Some(position), response)
+            .await
+    }
 
-        let mut locations: Vec<Location> = Vec::new();
-        let Some(project) = self.0.get_project_from_document_url(&uri) else {
-            self.0
-                .client
-                .log_message(MessageType::ERROR, "Failed to get project from document")
-                .await;
-            generic_exit_statements("LSP", "goto_definition");
-            return Ok(None);
-        };
-        let doc_snapshot: Option<(String, Tree)> = {
-            let data = project.data.read();
-            data.documents
-                .get(&uri)
-                .map(|d| (d.content.clone(), d.tree.clone()))
-        };
-
-        let (content, tree) = match doc_snapshot {
-            Some(v) => v,
-            None => {
-                self.0
-                    .client
-                    .log_message(MessageType::ERROR, "Failed to get document")
-                    .await;
-                return Ok(None);
-            }
-        };
-        let content = content.as_str();
-        // find what node is at that position
-        // convert position to point, and find smallest node that has the range of that Point
-        let point = position_to_point(content, position);
-
-        let Some(node) = tree
-            .root_node()
-            .named_descendant_for_point_range(point, point)
-        else {
-            eprintln!(
-                "Error: failed to get node that encapsulates point: {:?}",
-                point
-            );
-            generic_exit_statements("LSP", "goto_definition");
-            return Ok(None);
-        };
-
-        let Some(symbol_string) = content.get(node.byte_range()) else {
-            eprintln!(
-                "Error: failed to get string content of the node: {:?}",
-                node
-            );
-            generic_exit_statements("LSP", "goto_definition");
-            return Ok(None);
-        };
-
-        if node.kind() == "objectscript_identifier" {
-            // get method name
-            let Some(method_name) = method_name_from_identifier_node(node, content, 0) else {
-                generic_exit_statements("LSP", "goto_definition");
-                return Ok(None);
-            };
-
-            self.0
-                .client
-                .log_message(
-                    MessageType::INFO,
-                    format!("Getting definitions for symbol: {}", symbol_string),
-                )
-                .await;
-            let data = project.data.read();
-
-            // get location of symbol
-            for (url, range) in data.get_variable_symbol_location(
-                uri,
-                point,
-                symbol_string.to_string(),
-                method_name,
-            ) {
-                let Some(document) = data.documents.get(&url) else {
-                    eprintln!("Error: Couldn't get document content");
-                    generic_skipping_statements("goto_definition", url.path(), "Symbol location");
-                    continue;
-                };
-                let document_content = document.content.as_str();
-                let lsp_range = ts_range_to_lsp_range(document_content, range);
-                let location = Location {
-                    uri: url.clone(),
-                    range: lsp_range,
-                };
-                locations.push(location);
-            }
-
-            return if locations.is_empty() {
-                eprintln!("Error: Symbol is not defined in this workspace.");
-                successful_exit("LSP", "goto_definition");
-                Ok(None)
-            } else if locations.len() == 1 {
-                successful_exit("LSP", "goto_definition");
-                Ok(Some(GotoDefinitionResponse::Scalar(locations[0].clone())))
-            } else {
-                successful_exit("LSP", "goto_definition");
-                Ok(Some(GotoDefinitionResponse::Array(locations)))
-            };
-        }
-
-        self.0
-            .client
-            .log_message(
-                MessageType::ERROR,
-                format!("goto_definition not yet implemented for: {:?}", node.kind()),
-            )
-            .await;
-        successful_exit("LSP", "goto_definition");
-        Ok(None)
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .clone();
+        let position = params.text_document_position_params.position;
+        let response = self.uncached_hover(params);
+        self.cached_response("textDocument/hover", &uri, Some(position), response)
+            .await
     }
 
     async fn goto_implementation(
@@ -304,7 +285,7 @@ impl LanguageServer for BackendWrapper {
             generic_exit_statements("LSP", "goto_implementation");
             return Ok(None);
         };
-        let doc_snapshot: Option<(String, Tree)> = {
+        let doc_snapshot: Option<(TextBuffer, Tree)> = {
             let data = project.data.read();
             data.documents
                 .get(&uri)
@@ -469,100 +450,1013 @@ impl LanguageServer for BackendWrapper {
         }
     }
 
-    async fn shutdown(&self) -> Result<()> {
-        // need to look more into if this is good for doing nothing
-        exit(0)
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        start_of_function("LSP", "document_highlight");
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "document_highlight");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "document_highlight");
+            return Ok(None);
+        };
+        let content = document.content.as_str();
+        let point = position_to_point(content, position);
+        let highlights: Vec<DocumentHighlight> = data
+            .get_variable_highlights(&uri, point)
+            .into_iter()
+            .map(|(range, is_write)| DocumentHighlight {
+                range: ts_range_to_lsp_range(content, range),
+                kind: Some(if is_write {
+                    DocumentHighlightKind::WRITE
+                } else {
+                    DocumentHighlightKind::READ
+                }),
+            })
+            .collect();
+        successful_exit("LSP", "document_highlight");
+        Ok((!highlights.is_empty()).then_some(highlights))
     }
 
-    async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        start_of_function("LSP", "did_open");
-        let uri = params.text_document.uri;
-        let path = uri.path();
-        if !path.ends_with(".cls") && !path.ends_with(".mac") && !path.ends_with(".inc") {
-            return;
-        }
-        let file_type = if path.ends_with(".cls") {
-            FileType::Cls
-        } else if path.ends_with(".mac") {
-            FileType::Mac
-        } else {
-            FileType::Inc
+    async fn linked_editing_range(
+        &self,
+        params: LinkedEditingRangeParams,
+    ) -> Result<Option<LinkedEditingRanges>> {
+        start_of_function("LSP", "linked_editing_range");
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "linked_editing_range");
+            return Ok(None);
         };
+        let ranges = project.data.read().get_linked_label_ranges(&uri, position);
+        successful_exit("LSP", "linked_editing_range");
+        Ok(ranges.map(|ranges| LinkedEditingRanges {
+            ranges,
+            // label names, e.g. `Run` or `%Init`
+            word_pattern: Some("%?[A-Za-z0-9]+".to_string()),
+        }))
+    }
 
-        if file_type == FileType::Cls {
-            self.0.handle_did_open(
-                uri,
-                params.text_document.text,
-                file_type,
-                params.text_document.version,
-            );
-        }
-        successful_exit("LSP", "did_open");
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        start_of_function("LSP", "references");
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "references");
+            return Ok(None);
+        };
+        let include_declaration = params.context.include_declaration;
+        // scanning every document for label calls can take a while, so it stops on cancellation
+        let locations = run_cancellable(move |cancel| {
+            let data = project.data.read();
+            // routine labels, called from a routine or a class
+            let label_locations =
+                data.get_label_references(&uri, position, include_declaration, cancel);
+            if !label_locations.is_empty() {
+                return label_locations;
+            }
+            let Some(document) = data.documents.get(&uri) else {
+                return Vec::new();
+            };
+            let point = position_to_point(document.content.as_str(), position);
+            data.get_method_references_at(&uri, point, include_declaration)
+                .into_iter()
+                .filter_map(|(url, range)| {
+                    let document = data.documents.get(&url)?;
+                    Some(Location {
+                        range: ts_range_to_lsp_range(document.content.as_str(), range),
+                        uri: url,
+                    })
+                })
+                .collect()
+        })
+        .await
+        .unwrap_or_default();
+        successful_exit("LSP", "references");
+        Ok((!locations.is_empty()).then_some(locations))
     }
 
-    async fn did_change(&self, params: DidChangeTextDocumentParams) {
-        self.0
-            .client
-            .log_message(MessageType::INFO, "Did Change called")
-            .await;
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        start_of_function("LSP", "semantic_tokens_full");
         let uri = params.text_document.uri;
-        let path = uri.path();
-        if !path.ends_with(".cls") {
-            return;
-        }
         let Some(project) = self.0.get_project_from_document_url(&uri) else {
-            return;
+            generic_exit_statements("LSP", "semantic_tokens_full");
+            return Ok(None);
         };
-        let Some((file_type, mut old_text, old_version, mut old_tree)) =
-            project.get_document_info(&uri)
-        else {
-            let new_version = params.text_document.version;
-            let file_type = FileType::Cls;
-            // Try to get current cached doc
-
-            // Base text: prefer disk if available, otherwise empty.
-            let mut text = if let Ok(p) = uri.to_file_path() {
-                std::fs::read_to_string(p).unwrap_or_default()
-            } else {
-                String::new()
-            };
+        let tokens = project.semantic_tokens(&uri);
+        successful_exit("LSP", "semantic_tokens_full");
+        Ok(tokens.map(|tokens| {
+            SemanticTokensResult::Tokens(SemanticTokens {
+                result_id: Some(tokens.result_id),
+                data: tokens.data.to_vec(),
+            })
+        }))
+    }
 
-            // Apply ranged changes to the base text (Zed may send initial full contents as range edit).
-            for change in &params.content_changes {
-                let Some(range) = change.range else {
-                    // (Zed likely won't do this, but handle it anyway)
-                    text = change.text.clone();
-                    continue;
-                };
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        start_of_function("LSP", "semantic_tokens_full_delta");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "semantic_tokens_full_delta");
+            return Ok(None);
+        };
+        let previous = project.semantic_tokens.get(&uri);
+        let Some(tokens) = project.semantic_tokens(&uri) else {
+            generic_exit_statements("LSP", "semantic_tokens_full_delta");
+            return Ok(None);
+        };
+        successful_exit("LSP", "semantic_tokens_full_delta");
+        // the client may ask relative to tokens we no longer have, then it gets them all again
+        match previous.filter(|previous| previous.result_id == params.previous_result_id) {
+            Some(previous) => Ok(Some(SemanticTokensFullDeltaResult::TokensDelta(
+                SemanticTokensDelta {
+                    result_id: Some(tokens.result_id),
+                    edits: token_edits(&previous.data, &tokens.data),
+                },
+            ))),
+            None => Ok(Some(SemanticTokensFullDeltaResult::Tokens(
+                SemanticTokens {
+                    result_id: Some(tokens.result_id),
+                    data: tokens.data.to_vec(),
+                },
+            ))),
+        }
+    }
 
-                let start_point = position_to_point(&text, range.start);
-                let start_byte = point_to_byte(&text, start_point);
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        start_of_function("LSP", "semantic_tokens_range");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "semantic_tokens_range");
+            return Ok(None);
+        };
+        let tokens = project
+            .data
+            .read()
+            .get_semantic_tokens(&uri, Some(params.range));
+        successful_exit("LSP", "semantic_tokens_range");
+        Ok(tokens.map(|data| {
+            SemanticTokensRangeResult::Tokens(SemanticTokens {
+                result_id: None,
+                data,
+            })
+        }))
+    }
 
-                let end_point = position_to_point(&text, range.end);
-                let end_byte = point_to_byte(&text, end_point);
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        start_of_function("LSP", "symbol");
+        let projects: Vec<Arc<ProjectState>> = self.0.projects.read().values().cloned().collect();
+        // each workspace applies its own `workspace_symbol_limit`
+        let mut symbols: Vec<(i32, SymbolInformation)> = run_cancellable(move |cancel| {
+            projects
+                .iter()
+                .flat_map(|project| {
+                    project
+                        .data
+                        .read()
+                        .get_workspace_symbols(&params.query, cancel)
+                })
+                .collect()
+        })
+        .await
+        .unwrap_or_default();
+        symbols.sort_by(|(a, _), (b, _)| b.cmp(a));
+        successful_exit("LSP", "symbol");
+        Ok(Some(
+            symbols.into_iter().map(|(_, symbol)| symbol).collect(),
+        ))
+    }
 
-                text.replace_range(start_byte..end_byte, &change.text);
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        start_of_function("LSP", "rename");
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "rename");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "rename");
+            return Ok(None);
+        };
+        if is_read_only_url(&uri) {
+            generic_exit_statements("LSP", "rename");
+            return Err(Error::invalid_params(
+                "Classes downloaded from the server are read-only",
+            ));
+        }
+        let content = document.content.as_str();
+        let point = position_to_point(content, position);
+        // private variables are renamed in this document, where all their occurrences are
+        if let Some((definitions, references)) = data.get_private_variable_occurrences(&uri, point)
+        {
+            if !is_simple_variable_name(params.new_name.as_str()) {
+                generic_exit_statements("LSP", "rename");
+                return Err(Error::invalid_params(format!(
+                    "{:?} is not a valid local variable name",
+                    params.new_name
+                )));
             }
-
-            let parsed: Option<Tree> = {
-                let mut parser = project.parsers.cls.lock();
-                parser.parse(&text, None)
-            }; // lock guard drops here
-
-            let new_tree = match parsed {
-                Some(t) => t,
-                None => {
-                    self.0
-                        .client
-                        .log_message(
-                            MessageType::WARNING,
-                            "Incremental parse failed.".to_string(),
-                        )
-                        .await;
-                    return;
-                }
+            let Some((old_name, names_in_scope)) = data.get_private_variable_names(&uri, point)
+            else {
+                generic_exit_statements("LSP", "rename");
+                return Ok(None);
             };
-
+            // the scope tree isn't rebuilt while the parse has errors, so its ranges may be stale
+            let is_stale = definitions.iter().chain(&references).any(|range| {
+                content.get(range.start_byte..range.end_byte) != Some(old_name.as_str())
+            });
+            if is_stale {
+                generic_exit_statements("LSP", "rename");
+                return Err(Error::invalid_params(
+                    "The document has syntax errors, fix them before renaming variables",
+                ));
+            }
+            if let Err(message) = check_rename(
+                IdentifierKind::Variable,
+                &old_name,
+                &params.new_name,
+                &names_in_scope,
+            ) {
+                generic_exit_statements("LSP", "rename");
+                return Err(Error::invalid_params(message));
+            }
+            let edits = definitions
+                .into_iter()
+                .chain(references)
+                .map(|range| TextEdit {
+                    range: ts_range_to_lsp_range(content, range),
+                    new_text: params.new_name.clone(),
+                })
+                .collect();
+            successful_exit("LSP", "rename");
+            return Ok(Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(uri.clone(), edits)])),
+                ..Default::default()
+            }));
+        }
+        // classes are renamed across the workspace, along with their files
+        let Some(class_name) = data.get_class_name_at(&uri, point) else {
+            successful_exit("LSP", "rename");
+            return Ok(None);
+        };
+        if data.is_read_only_class(&class_name) {
+            generic_exit_statements("LSP", "rename");
+            return Err(Error::invalid_params(format!(
+                "Class {class_name} is read-only, it was downloaded from the server"
+            )));
+        }
+        if !is_class_name(params.new_name.as_str()) {
+            generic_exit_statements("LSP", "rename");
+            return Err(Error::invalid_params(format!(
+                "{:?} is not a valid class name",
+                params.new_name
+            )));
+        }
+        // class names must be unique even ignoring case, though a case-only rename is allowed
+        if let Err(message) = check_rename(
+            IdentifierKind::Class,
+            &class_name,
+            &params.new_name,
+            data.classes.keys(),
+        ) {
+            generic_exit_statements("LSP", "rename");
+            return Err(Error::invalid_params(message));
+        }
+        let rename = data.get_class_rename(&class_name, &params.new_name);
+        successful_exit("LSP", "rename");
+        Ok(Some(rename.to_workspace_edit()))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        start_of_function("LSP", "completion");
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "completion");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "completion");
+            return Ok(None);
+        };
+        let content = document.content.as_str();
+        let point = position_to_point(content, position);
+        let line_start = point_to_byte(content, Point::new(point.row, 0));
+        let cursor = point_to_byte(content, point);
+        let Some(line_before_cursor) = content.get(line_start..cursor) else {
+            generic_exit_statements("LSP", "completion");
+            return Ok(None);
+        };
+
+        if let Some((target, partial)) = system_api_prefix(line_before_cursor) {
+            if !data.config.system_api_stubs {
+                generic_exit_statements("LSP", "completion");
+                return Ok(None);
+            }
+            let items = system_api_completions(
+                &target,
+                partial,
+                position,
+                data.config.method_insert_style,
+                are_snippets_enabled(),
+            );
+            successful_exit("LSP", "completion");
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+        if let Some(partial) = macro_prefix(line_before_cursor) {
+            let macros = data.get_visible_macros(&uri);
+            let items = macro_completions(&macros, partial, position);
+            successful_exit("LSP", "completion");
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+        if let Some(partial) = intrinsic_function_prefix(line_before_cursor) {
+            let items = intrinsic_function_completions(
+                partial,
+                position,
+                data.config.method_insert_style,
+                are_snippets_enabled(),
+            );
+            successful_exit("LSP", "completion");
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+        if let Some(prefix) = class_name_prefix(line_before_cursor) {
+            let mut items = package_segment_completions(prefix, data.classes.keys(), position);
+            if is_class_qualifier_position(line_before_cursor, prefix) {
+                if data.config.complete_class_qualifier {
+                    close_class_qualifiers(&mut items);
+                }
+                commit_class_qualifiers(&mut items, are_snippets_enabled());
+            }
+            successful_exit("LSP", "completion");
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+        if let Some((class_name, partial)) = parameter_name_prefix(line_before_cursor) {
+            let class_name = class_name.unwrap_or(document.class_name.as_str());
+            let parameters = data.get_completion_parameters(class_name);
+            let items = parameter_completions(&parameters, partial, position);
+            successful_exit("LSP", "completion");
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+        if let Some((target, partial)) = method_name_prefix(line_before_cursor) {
+            let mut properties = Vec::new();
+            let methods = match target {
+                MethodTarget::ClassMethodsOf(class_name) => {
+                    let mut methods = data.get_completion_methods(class_name, true);
+                    if class_name != document.class_name && !data.config.complete_internal_members {
+                        methods.retain(|method| !method.visibility.internal);
+                    }
+                    methods
+                }
+                MethodTarget::CurrentClass => {
+                    data.get_completion_methods(document.class_name.as_str(), false)
+                }
+                MethodTarget::Oref(variable) => {
+                    let Some(class_name) = data.get_oref_class(&uri, point, variable) else {
+                        successful_exit("LSP", "completion");
+                        return Ok(None);
+                    };
+                    properties = data.get_completion_properties(&class_name);
+                    if class_name != document.class_name {
+                        properties.retain(|(property, _)| property.is_public);
+                    }
+                    let mut methods = data.get_completion_methods(&class_name, false);
+                    if class_name != document.class_name && !data.config.complete_internal_members {
+                        methods.retain(|method| !method.visibility.internal);
+                    }
+                    methods
+                }
+            };
+            let mut items = method_completions(
+                &methods,
+                partial,
+                position,
+                data.config.method_insert_style,
+                are_snippets_enabled(),
+            );
+            items.extend(property_completions(&properties, partial, position));
+            successful_exit("LSP", "completion");
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+        successful_exit("LSP", "completion");
+        Ok(None)
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        start_of_function("LSP", "signature_help");
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "signature_help");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "signature_help");
+            return Ok(None);
+        };
+        let point = position_to_point(document.content.as_str(), position);
+        let help = data.get_system_api_signature_help(&uri, point);
+        successful_exit("LSP", "signature_help");
+        Ok(help)
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        start_of_function("LSP", "prepare_call_hierarchy");
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "prepare_call_hierarchy");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "prepare_call_hierarchy");
+            return Ok(None);
+        };
+        let point = position_to_point(document.content.as_str(), position);
+        let items = data.prepare_call_hierarchy(&uri, point);
+        successful_exit("LSP", "prepare_call_hierarchy");
+        Ok((!items.is_empty()).then_some(items))
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        start_of_function("LSP", "incoming_calls");
+        let Some(project) = self.0.get_project_from_document_url(&params.item.uri) else {
+            generic_exit_statements("LSP", "incoming_calls");
+            return Ok(None);
+        };
+        let calls = run_cancellable(move |cancel| {
+            project.data.read().get_incoming_calls(&params.item, cancel)
+        })
+        .await
+        .unwrap_or_default();
+        successful_exit("LSP", "incoming_calls");
+        Ok(Some(calls))
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        start_of_function("LSP", "outgoing_calls");
+        let Some(project) = self.0.get_project_from_document_url(&params.item.uri) else {
+            generic_exit_statements("LSP", "outgoing_calls");
+            return Ok(None);
+        };
+        let calls = project.data.read().get_outgoing_calls(&params.item);
+        successful_exit("LSP", "outgoing_calls");
+        Ok(Some(calls))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        start_of_function("LSP", "code_action");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "code_action");
+            return Ok(None);
+        };
+        // `source.fixAll` is only offered when asked for (e.g. by code actions on save)
+        let requested = |kind: &CodeActionKind| {
+            params.context.only.as_ref().is_some_and(|only| {
+                only.iter()
+                    .any(|requested| kind.as_str().starts_with(requested.as_str()))
+            })
+        };
+        if requested(&CodeActionKind::SOURCE_FIX_ALL) {
+            let edits = project.data.read().get_fix_all_edits(&uri);
+            if edits.is_empty() {
+                successful_exit("LSP", "code_action");
+                return Ok(None);
+            }
+            let action = CodeAction {
+                title: "Fix all auto-fixable problems".to_string(),
+                kind: Some(CodeActionKind::SOURCE_FIX_ALL),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            successful_exit("LSP", "code_action");
+            return Ok(Some(vec![CodeActionOrCommand::CodeAction(action)]));
+        }
+        let (fixes, class_name_fixes, method_name_fixes, missing_classes, stubs) = {
+            let data = project.data.read();
+            (
+                data.get_debug_write_fixes(&uri, params.range),
+                data.get_class_name_fixes(&uri, params.range),
+                data.get_method_name_fixes(&uri, params.range),
+                data.get_missing_class_references(&uri, params.range),
+                data.get_override_stubs(&uri, params.range.start),
+            )
+        };
+        let mut actions: Vec<CodeActionOrCommand> = fixes
+            .into_iter()
+            .map(|(range, new_text)| {
+                let diagnostics = params
+                    .context
+                    .diagnostics
+                    .iter()
+                    .filter(|diagnostic| {
+                        diagnostic.range == range
+                            && diagnostic.code
+                                == Some(NumberOrString::String(DEBUG_WRITE_CODE.to_string()))
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let edit = WorkspaceEdit {
+                    changes: Some(HashMap::from([(
+                        uri.clone(),
+                        vec![TextEdit { range, new_text }],
+                    )])),
+                    ..Default::default()
+                };
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: "Replace debugging write with logging call".to_string(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: (!diagnostics.is_empty()).then_some(diagnostics),
+                    edit: Some(edit),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let unresolved_diagnostics = |range: Range| {
+            let diagnostics = params
+                .context
+                .diagnostics
+                .iter()
+                .filter(|diagnostic| {
+                    diagnostic.range == range
+                        && diagnostic.code == rule_code(UNRESOLVED_REFERENCE_RULE)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            (!diagnostics.is_empty()).then_some(diagnostics)
+        };
+        actions.extend(class_name_fixes.into_iter().map(|(range, title, edits)| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: unresolved_diagnostics(range),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        }));
+        actions.extend(method_name_fixes.into_iter().map(|(range, name, edit)| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Did you mean `{name}`?"),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: unresolved_diagnostics(range),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        }));
+        // the file is created by a command, so the new class is indexed as soon as it exists
+        actions.extend(missing_classes.into_iter().map(|(range, class_name)| {
+            let title = format!("Create class {class_name}");
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: unresolved_diagnostics(range),
+                command: Some(Command {
+                    title,
+                    command: CREATE_CLASS.to_string(),
+                    arguments: Some(vec![json!(uri), json!(class_name)]),
+                }),
+                ..Default::default()
+            })
+        }));
+        actions.extend(stubs.into_iter().map(|(method, class_name, edit)| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Override method {} (from {class_name})", method.name),
+                kind: Some(CodeActionKind::REFACTOR),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        }));
+        successful_exit("LSP", "code_action");
+        Ok((!actions.is_empty()).then_some(actions))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let uri = params.text_document.uri.clone();
+        let response = self.uncached_document_symbol(params);
+        self.cached_response("textDocument/documentSymbol", &uri, None, response)
+            .await
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let uri = params.text_document.uri.clone();
+        let response = self.uncached_folding_range(params);
+        self.cached_response("textDocument/foldingRange", &uri, None, response)
+            .await
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        start_of_function("LSP", "code_lens");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "code_lens");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let mut lenses: Vec<CodeLens> = data.get_class_stats_lens(&uri).into_iter().collect();
+        lenses.extend(data.get_reference_count_lenses(&uri));
+        if let Some(document) = data.documents.get(&uri) {
+            lenses.extend(data.get_method_metrics(&uri).iter().map(|metrics| {
+                metrics.to_code_lens(point_to_lsp_position(
+                    document.content.as_str(),
+                    metrics.range.start_point,
+                ))
+            }));
+        }
+        successful_exit("LSP", "code_lens");
+        Ok((!lenses.is_empty()).then_some(lenses))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        start_of_function("LSP", "formatting");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "formatting");
+            return Ok(None);
+        };
+        let edits = project
+            .data
+            .read()
+            .get_command_abbreviation_edits(&uri, None);
+        successful_exit("LSP", "formatting");
+        Ok(Some(edits))
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        start_of_function("LSP", "range_formatting");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "range_formatting");
+            return Ok(None);
+        };
+        let (start, end) = (params.range.start, params.range.end);
+        // a selection of whole lines ends at the start of the next line
+        let end_row = if end.character == 0 && end.line > start.line {
+            end.line - 1
+        } else {
+            end.line
+        };
+        let rows = start.line as usize..=end_row as usize;
+        let edits = project
+            .data
+            .read()
+            .get_command_abbreviation_edits(&uri, Some(rows));
+        successful_exit("LSP", "range_formatting");
+        Ok(Some(edits))
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        start_of_function("LSP", "on_type_formatting");
+        let uri = params.text_document_position.text_document.uri;
+        let row = params.text_document_position.position.line as usize;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "on_type_formatting");
+            return Ok(None);
+        };
+        let indent = if params.options.insert_spaces {
+            " ".repeat(params.options.tab_size as usize)
+        } else {
+            "\t".to_string()
+        };
+        let data = project.data.read();
+        let edits = if params.ch == "}" {
+            data.get_indentation_edits(&uri, row, true, &indent)
+        } else {
+            // the trigger is a newline, so the completed line is the one above the cursor
+            let mut edits = match row.checked_sub(1) {
+                Some(completed) => {
+                    data.get_command_abbreviation_edits(&uri, Some(completed..=completed))
+                }
+                None => Vec::new(),
+            };
+            edits.extend(data.get_indentation_edits(&uri, row, false, &indent));
+            edits
+        };
+        successful_exit("LSP", "on_type_formatting");
+        Ok(Some(edits))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        start_of_function("LSP", "execute_command");
+        let command = params.command.as_str();
+        if !all_commands().iter().any(|known| known == command) {
+            self.0
+                .client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Unknown command: {:?}", params.command),
+                )
+                .await;
+            generic_exit_statements("LSP", "execute_command");
+            return Ok(None);
+        }
+        // the self-check covers the whole server, not a project
+        if command == DOCTOR {
+            let projects: Vec<Arc<ProjectState>> =
+                self.0.projects.read().values().cloned().collect();
+            let stubs_enabled = projects.is_empty()
+                || projects
+                    .iter()
+                    .any(|project| project.data.read().config.system_api_stubs);
+            let roots: Vec<Option<&Path>> = projects
+                .iter()
+                .map(|project| project.project_root_path.get().and_then(Option::as_deref))
+                .collect();
+            let encodings = get_client_position_encodings();
+            let report = render_report(&[
+                check_grammar(),
+                check_node_types(),
+                check_system_stubs(stubs_enabled),
+                check_writable(&roots),
+                check_position_encoding(encodings.as_deref()),
+            ]);
+            successful_exit("LSP", "execute_command");
+            return Ok(Some(Value::String(report)));
+        }
+        // rule docs are static, so they don't need a project
+        if command == RULE_DOCS {
+            let id = params.arguments.first().and_then(Value::as_str);
+            let docs = rule_docs(id);
+            if docs.is_none() {
+                self.0
+                    .client
+                    .log_message(MessageType::WARNING, format!("Unknown rule: {id:?}"))
+                    .await;
+            }
+            successful_exit("LSP", "execute_command");
+            return Ok(docs.map(Value::String));
+        }
+        // every other command takes a document (or workspace folder) uri as its first argument
+        let Some(uri) = uri_argument(&params.arguments, 0) else {
+            self.0
+                .client
+                .log_message(
+                    MessageType::ERROR,
+                    format!("{command} expects a document uri as its first argument"),
+                )
+                .await;
+            generic_exit_statements("LSP", "execute_command");
+            return Ok(None);
+        };
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            self.0
+                .client
+                .log_message(MessageType::ERROR, "Failed to get project from document")
+                .await;
+            generic_exit_statements("LSP", "execute_command");
+            return Ok(None);
+        };
+        let result = match command {
+            SHOW_SCOPES => project.data.read().get_scope_tree_json(&uri),
+            TAKE_SNAPSHOT => {
+                let label = params
+                    .arguments
+                    .get(1)
+                    .and_then(Value::as_str)
+                    .unwrap_or("manual snapshot")
+                    .to_string();
+                project.data_mut().take_snapshot(label).map(Value::from)
+            }
+            LIST_SNAPSHOTS => Some(project.data.read().get_snapshots_json()),
+            DIFF_SNAPSHOTS => {
+                let from = params.arguments.get(1).and_then(Value::as_u64);
+                let to = params.arguments.get(2).and_then(Value::as_u64);
+                project.data.read().get_snapshot_diff_json(from, to)
+            }
+            FIX_ALL_IN_WORKSPACE => Some(self.0.fix_all_in_workspace(&project).await),
+            CREATE_CLASS => match params.arguments.get(1).and_then(Value::as_str) {
+                Some(class_name) if is_class_name(class_name) => {
+                    self.0.create_class(&project, &uri, class_name).await
+                }
+                _ => {
+                    self.0
+                        .client
+                        .log_message(
+                            MessageType::ERROR,
+                            format!("{command} expects a class name after the uri"),
+                        )
+                        .await;
+                    None
+                }
+            },
+            RENAME_PACKAGE => {
+                let argument = |index: usize| params.arguments.get(index).and_then(Value::as_str);
+                match (argument(1), argument(2)) {
+                    (Some(from), Some(to)) if is_package_name(from) && is_package_name(to) => {
+                        let dry_run = params.arguments.get(3).and_then(Value::as_bool);
+                        let rename = project.data.read().get_package_rename(from, to);
+                        let dry_run = dry_run.unwrap_or(false);
+                        Some(self.0.rename_package(rename, dry_run).await)
+                    }
+                    _ => {
+                        self.0
+                            .client
+                            .log_message(
+                                MessageType::ERROR,
+                                format!("{command} expects two package names after the uri"),
+                            )
+                            .await;
+                        None
+                    }
+                }
+            }
+            DUMP_INDEX => {
+                let dump = project.data.read().get_index_dump_json();
+                match params.arguments.get(1).and_then(Value::as_str) {
+                    None => Some(dump),
+                    Some(path) => {
+                        let text = serde_json::to_string_pretty(&dump).unwrap_or_default();
+                        self.write_export(&project, path, text, "index dump").await
+                    }
+                }
+            }
+            EXPORT_TAGS => {
+                let tags = render_ctags(project.data.read().get_tag_entries(project.root_uri()));
+                match params.arguments.get(1).and_then(Value::as_str) {
+                    None => Some(Value::String(tags)),
+                    Some(path) => self.write_export(&project, path, tags, "tags").await,
+                }
+            }
+            EXPORT_USAGE => {
+                let entries = project.data.read().get_usage_entries(project.root_uri());
+                let format = params.arguments.get(1).and_then(Value::as_str);
+                let report = match format.unwrap_or("json") {
+                    "json" => Some(render_usage_json(entries)),
+                    "csv" => Some(Value::String(render_usage_csv(entries))),
+                    other => {
+                        self.0
+                            .client
+                            .log_message(
+                                MessageType::ERROR,
+                                format!("Unknown usage report format {other:?}"),
+                            )
+                            .await;
+                        None
+                    }
+                };
+                match (report, params.arguments.get(2).and_then(Value::as_str)) {
+                    (Some(report), Some(path)) => {
+                        let text = match report {
+                            Value::String(csv) => csv,
+                            json => serde_json::to_string_pretty(&json).unwrap_or_default(),
+                        };
+                        self.write_export(&project, path, text, "usage report")
+                            .await
+                    }
+                    (report, _) => report,
+                }
+            }
+            _ => None,
+        };
+        successful_exit("LSP", "execute_command");
+        Ok(result)
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        // need to look more into if this is good for doing nothing
+        exit(0)
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        start_of_function("LSP", "did_open");
+        let uri = params.text_document.uri;
+        let path = uri.path();
+        if !path.ends_with(".cls") && !path.ends_with(".mac") && !path.ends_with(".inc") {
+            return;
+        }
+        let file_type = if path.ends_with(".cls") {
+            FileType::Cls
+        } else if path.ends_with(".mac") {
+            FileType::Mac
+        } else {
+            FileType::Inc
+        };
+
+        if file_type == FileType::Cls {
+            self.0.handle_did_open(
+                uri.clone(),
+                params.text_document.text,
+                file_type,
+                params.text_document.version,
+            );
+            self.0.publish_diagnostics(uri).await;
+        } else {
+            self.0.handle_did_open(
+                uri,
+                params.text_document.text,
+                file_type,
+                params.text_document.version,
+            );
+        }
+        successful_exit("LSP", "did_open");
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        self.0
+            .client
+            .log_message(MessageType::INFO, "Did Change called")
+            .await;
+        let uri = params.text_document.uri;
+        let path = uri.path();
+        if !path.ends_with(".cls") {
+            return;
+        }
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            return;
+        };
+        let Some((file_type, mut old_text, old_version, mut old_tree)) =
+            project.get_document_info(&uri)
+        else {
+            let new_version = params.text_document.version;
+            let file_type = FileType::Cls;
+            // Try to get current cached doc
+
+            // Base text: prefer the workspace's sources if available, otherwise empty.
+            let sources = project.sources();
+            let source_uri = uri.clone();
+            let mut text: TextBuffer =
+                tokio::task::spawn_blocking(move || sources.read_file(&source_uri))
+                    .await
+                    .ok()
+                    .and_then(|read| read.ok())
+                    .unwrap_or_default()
+                    .into();
+
+            // Apply the changes to the base text (Zed may send initial full contents as range edit).
+            apply_content_changes(&mut text, &params.content_changes);
+
+            let parsed: Option<Tree> = {
+                let mut parser = project.parsers.cls.lock();
+                text.parse(&mut parser, None)
+            }; // lock guard drops here
+
+            let new_tree = match parsed {
+                Some(t) => t,
+                None => {
+                    self.0
+                        .client
+                        .log_message(
+                            MessageType::WARNING,
+                            "Incremental parse failed.".to_string(),
+                        )
+                        .await;
+                    return;
+                }
+            };
+
             // Insert/update doc record so future incremental changes work
             {
                 let Some(class_name) =
@@ -571,7 +1465,7 @@ impl LanguageServer for BackendWrapper {
                     eprintln!("Error: Failed to get class name");
                     return;
                 };
-                let mut data = project.data.write();
+                let mut data = project.data_mut();
                 data.add_document_if_absent(
                     uri.clone(),
                     text.clone(),
@@ -581,123 +1475,701 @@ impl LanguageServer for BackendWrapper {
                     Some(new_version),
                 );
             }
+            self.0.publish_diagnostics(uri).await;
+            return;
+        };
+
+        let new_version = params.text_document.version;
+        if new_version < old_version {
+            self.0
+                .client
+                .log_message(
+                    MessageType::ERROR,
+                    "New version {new_version} is less than old version {old_version}",
+                )
+                .await;
+        }
+
+        // full-text changes (from clients that only do full sync) can arrive even though
+        // incremental sync is advertised, so the shape is detected per change
+        let edits = apply_content_changes(&mut old_text, &params.content_changes);
+        let did_full_replace = edits.is_none();
+        self.0
+            .client
+            .log_message(
+                MessageType::INFO,
+                format!("Full Replace: {:?}", did_full_replace),
+            )
+            .await;
+        // Incremental edits: keep the old tree in sync so it can be reused for reparsing.
+        for input_edit in edits.iter().flatten() {
+            old_tree.edit(input_edit);
+        }
+
+        let parsed: Option<Tree> = {
+            if file_type == FileType::Cls {
+                let mut parser = project.parsers.cls.lock();
+                if did_full_replace {
+                    old_text.parse(&mut parser, None)
+                } else {
+                    old_text.parse(&mut parser, Some(&old_tree))
+                }
+            } else {
+                let mut parser = project.parsers.routine.lock();
+                if did_full_replace {
+                    old_text.parse(&mut parser, None)
+                } else {
+                    old_text.parse(&mut parser, Some(&old_tree))
+                }
+            }
+        }; // lock guard drops here
+
+        let new_tree = match parsed {
+            Some(t) => t,
+            None => {
+                self.0
+                    .client
+                    .log_message(
+                        MessageType::WARNING,
+                        "Incremental parse failed.".to_string(),
+                    )
+                    .await;
+                return;
+            }
+        };
+
+        {
+            let mut data = project.data_mut();
+            if let Some(doc) = data.documents.get_mut(&uri) {
+                doc.content = old_text;
+                doc.tree = new_tree;
+                doc.version = Some(new_version);
+                doc.file_type = file_type;
+            }
+        }
+
+        // semantic analysis and diagnostics wait until the user pauses typing
+        self.0.schedule_revalidation(uri);
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        start_of_function("LSP", "did_change_configuration");
+        // clients send either the whole settings tree or just the `objectscript` section
+        let settings = match params.settings {
+            Value::Object(mut map) if map.contains_key("objectscript") => {
+                map.remove("objectscript").unwrap_or_default()
+            }
+            settings => settings,
+        };
+        let projects: Vec<Arc<ProjectState>> = self.0.projects.read().values().cloned().collect();
+        let mut open_documents = Vec::new();
+        for project in &projects {
+            let config = Config::load(Some(settings.clone()), project.root_path());
+            let mut data = project.data_mut();
+            data.config = config;
+            open_documents.extend(data.get_open_document_urls());
+        }
+        if let Ok(negotiations) = serde_json::from_value::<Config>(settings) {
+            ENABLE_SNIPPETS.store(negotiations.enable_snippets, Ordering::Relaxed);
+        }
+
+        // toggled features are (un)registered so the client UI matches what the server does
+        self.0.sync_dynamic_capabilities().await;
+        // lint settings may have changed
+        for uri in open_documents {
+            self.0.publish_diagnostics(uri).await;
+        }
+        successful_exit("LSP", "did_change_configuration");
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        start_of_function("LSP", "did_change_watched_files");
+        // bulk changes (e.g. a branch switch) and deletions re-index the workspace in the
+        // background instead of updating it file by file
+        for root in self.0.apply_watched_files(params.changes).await {
+            self.0
+                .client
+                .log_message(
+                    MessageType::INFO,
+                    format!("Re-indexing workspace {root} after file changes"),
+                )
+                .await;
+            let backend = Arc::clone(&self.0);
+            tokio::spawn(async move {
+                backend.reindex_workspace(&root).await;
+            });
+        }
+        successful_exit("LSP", "did_change_watched_files");
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        start_of_function("LSP", "did_close");
+        let uri = params.text_document.uri;
+        self.0.cancel_revalidation(&uri);
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "did_close");
             return;
         };
+        let sources = project.sources();
+        let url = uri.clone();
+        let closed = tokio::task::spawn_blocking(move || {
+            project.handle_document_closed(sources.as_ref(), &url);
+        });
+        if let Err(join_err) = closed.await {
+            eprintln!("handle_document_closed spawn_blocking failed: {join_err:?}");
+        }
+        // diagnostics are only kept up to date for open documents
+        self.0
+            .client
+            .publish_diagnostics(uri, Vec::new(), None)
+            .await;
+        successful_exit("LSP", "did_close");
+    }
+}
+
+/// Custom request returning every location of the override chain of the method at a position.
+pub const OVERRIDE_CHAIN_REQUEST: &str = "objectscript/overrideChain";
+
+/// Custom request returning the structural diff between two versions of a class.
+pub const SEMANTIC_DIFF_REQUEST: &str = "objectscript/semanticDiff";
+
+/// Custom request returning the class members projected to SQL, optionally by SQL name.
+pub const SQL_SURFACE_REQUEST: &str = "objectscript/sqlSurface";
+
+/// Custom request returning the references that can't be resolved in the workspace.
+pub const UNRESOLVED_REQUEST: &str = "objectscript/unresolved";
+
+impl BackendWrapper {
+    /// Return the cached response of `method` for the document `uri` (at `position`), or
+    /// compute it with `compute` and cache it.
+    ///
+    /// Only responses for documents the project tracks are cached, keyed by their version; the
+    /// cache is cleared whenever the project changes (see `ProjectState::data_mut`).
+    async fn cached_response<T, F>(
+        &self,
+        method: &'static str,
+        uri: &Url,
+        position: Option<Position>,
+        compute: F,
+    ) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Future<Output = Result<T>>,
+    {
+        let Some(project) = self.0.get_project_from_document_url(uri) else {
+            return compute.await;
+        };
+        // taken first, so a change while computing keeps the response out of the cache
+        let generation = project.response_cache.generation();
+        let Some(version) = project.data.read().documents.get(uri).map(|d| d.version) else {
+            return compute.await;
+        };
+        let key = ResponseKey::new(method, uri, version, position);
+        if let Some(response) = project.response_cache.get::<T>(&key) {
+            return Ok(response);
+        }
+        let response = compute.await?;
+        project
+            .response_cache
+            .insert(key, generation, response.clone());
+        Ok(response)
+    }
+
+    /// Compute the response of `textDocument/definition`; see `goto_definition`.
+    async fn uncached_goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        start_of_function("LSP", "goto_definition");
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let mut locations: Vec<Location> = Vec::new();
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            self.0
+                .client
+                .log_message(MessageType::ERROR, "Failed to get project from document")
+                .await;
+            generic_exit_statements("LSP", "goto_definition");
+            return Ok(None);
+        };
+        // macros and routine labels, used from a routine or a class
+        let macro_or_label = {
+            let data = project.data.read();
+            data.get_macro_definition(&uri, position)
+                .or_else(|| data.get_label_definition(&uri, position))
+        };
+        if let Some(location) = macro_or_label {
+            successful_exit("LSP", "goto_definition");
+            return Ok(Some(GotoDefinitionResponse::Scalar(location)));
+        }
+        let doc_snapshot: Option<(TextBuffer, Tree)> = {
+            let data = project.data.read();
+            data.documents
+                .get(&uri)
+                .map(|d| (d.content.clone(), d.tree.clone()))
+        };
+
+        let (content, tree) = match doc_snapshot {
+            Some(v) => v,
+            None => {
+                self.0
+                    .client
+                    .log_message(MessageType::ERROR, "Failed to get document")
+                    .await;
+                return Ok(None);
+            }
+        };
+        let content = content.as_str();
+        // find what node is at that position
+        // convert position to point, and find smallest node that has the range of that Point
+        let point = position_to_point(content, position);
+
+        let Some(node) = tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)
+        else {
+            eprintln!(
+                "Error: failed to get node that encapsulates point: {:?}",
+                point
+            );
+            generic_exit_statements("LSP", "goto_definition");
+            return Ok(None);
+        };
+
+        let Some(symbol_string) = content.get(node.byte_range()) else {
+            eprintln!(
+                "Error: failed to get string content of the node: {:?}",
+                node
+            );
+            generic_exit_statements("LSP", "goto_definition");
+            return Ok(None);
+        };
+
+        // properties resolve to their declaration and any custom accessors
+        let property_locations = project.data.read().get_property_definitions(&uri, point);
+        if !property_locations.is_empty() {
+            successful_exit("LSP", "goto_definition");
+            return Ok(Some(GotoDefinitionResponse::Array(property_locations)));
+        }
+
+        let parameter_location = project.data.read().get_parameter_definition(&uri, point);
+        if let Some(location) = parameter_location {
+            successful_exit("LSP", "goto_definition");
+            return Ok(Some(GotoDefinitionResponse::Scalar(location)));
+        }
+
+        // method calls resolve to the declaration, inherited or not
+        let method_definition = {
+            let data = project.data.read();
+            data.get_method_call_definition(&uri, point)
+                .and_then(|(url, range)| {
+                    let document = data.documents.get(&url)?;
+                    Some(Location {
+                        uri: url,
+                        range: ts_range_to_lsp_range(document.content.as_str(), range),
+                    })
+                })
+        };
+        if let Some(location) = method_definition {
+            successful_exit("LSP", "goto_definition");
+            return Ok(Some(GotoDefinitionResponse::Scalar(location)));
+        }
+
+        if node.kind() == "objectscript_identifier" {
+            // get method name
+            let Some(method_name) = method_name_from_identifier_node(node, content, 0) else {
+                generic_exit_statements("LSP", "goto_definition");
+                return Ok(None);
+            };
 
-        let new_version = params.text_document.version;
-        if new_version < old_version {
             self.0
                 .client
                 .log_message(
-                    MessageType::ERROR,
-                    "New version {new_version} is less than old version {old_version}",
+                    MessageType::INFO,
+                    format!("Getting definitions for symbol: {}", symbol_string),
                 )
                 .await;
+            let data = project.data.read();
+
+            // get location of symbol
+            for (url, range) in data.get_variable_symbol_location(
+                uri,
+                point,
+                symbol_string.to_string(),
+                method_name,
+            ) {
+                let Some(document) = data.documents.get(&url) else {
+                    eprintln!("Error: Couldn't get document content");
+                    generic_skipping_statements("goto_definition", url.path(), "Symbol location");
+                    continue;
+                };
+                let document_content = document.content.as_str();
+                let lsp_range = ts_range_to_lsp_range(document_content, range);
+                let location = Location {
+                    uri: url.clone(),
+                    range: lsp_range,
+                };
+                locations.push(location);
+            }
+
+            return if locations.is_empty() {
+                eprintln!("Error: Symbol is not defined in this workspace.");
+                successful_exit("LSP", "goto_definition");
+                Ok(None)
+            } else if locations.len() == 1 {
+                successful_exit("LSP", "goto_definition");
+                Ok(Some(GotoDefinitionResponse::Scalar(locations[0].clone())))
+            } else {
+                successful_exit("LSP", "goto_definition");
+                Ok(Some(GotoDefinitionResponse::Array(locations)))
+            };
         }
 
-        let full_snapshot = params
-            .content_changes
-            .iter()
-            .rev()
-            .find(|c| c.range.is_none())
-            .map(|c| c.text.clone());
+        // fall back to the document's symbol location index (method calls, private methods, ...)
+        let definition = {
+            let data = project.data.read();
+            data.get_symbol_at(&uri, point)
+                .and_then(|symbol| data.get_symbol_definition(&uri, &symbol))
+                .and_then(|(url, range)| {
+                    let document = data.documents.get(&url)?;
+                    Some(Location {
+                        uri: url.clone(),
+                        range: ts_range_to_lsp_range(document.content.as_str(), range),
+                    })
+                })
+        };
+        if let Some(location) = definition {
+            successful_exit("LSP", "goto_definition");
+            return Ok(Some(GotoDefinitionResponse::Scalar(location)));
+        }
+
+        // the class of an object reference usually can't be inferred: offer every class
+        // declaring a method with that name
+        if let Some(name_node) = oref_method_name_node(node) {
+            let data = project.data.read();
+            let locations: Vec<Location> = data
+                .get_heuristic_method_candidates(symbol_string)
+                .into_iter()
+                .filter_map(|(_, url, range)| {
+                    let document = data.documents.get(&url)?;
+                    Some(Location {
+                        uri: url.clone(),
+                        range: ts_range_to_lsp_range(document.content.as_str(), range),
+                    })
+                })
+                .collect();
+            if !locations.is_empty() {
+                eprintln!(
+                    "Heuristic: resolved oref method {:?} to {} candidate(s)",
+                    content.get(name_node.byte_range()),
+                    locations.len()
+                );
+                successful_exit("LSP", "goto_definition");
+                return Ok(Some(GotoDefinitionResponse::Array(locations)));
+            }
+        }
 
-        let did_full_replace = full_snapshot.is_some();
         self.0
             .client
             .log_message(
-                MessageType::INFO,
-                format!("Full Replace: {:?}", did_full_replace),
+                MessageType::ERROR,
+                format!("goto_definition not yet implemented for: {:?}", node.kind()),
             )
             .await;
-        if let Some(new_full_text) = full_snapshot {
-            // Full replace: overwrite text, DO NOT edit the old tree incrementally.
-            old_text = new_full_text;
-        } else {
-            // Incremental edits: apply each ranged edit sequentially.
-            for change in &params.content_changes {
-                let range = change
-                    .range
-                    .expect("no full snapshot, so all changes must have ranges");
-                let new_text = change.text.as_str();
-
-                let start_position = position_to_point(old_text.as_str(), range.start);
-                let start_byte = point_to_byte(old_text.as_str(), start_position);
-
-                let old_end_position = position_to_point(old_text.as_str(), range.end);
-                let old_end_byte = point_to_byte(old_text.as_str(), old_end_position);
-
-                let new_end_byte = start_byte + new_text.len();
-                let new_end_position =
-                    advance_point(start_position.row, start_position.column, new_text);
-
-                let input_edit = InputEdit {
-                    start_byte,
-                    old_end_byte,
-                    new_end_byte,
-                    start_position,
-                    old_end_position,
-                    new_end_position,
-                };
-                old_text.replace_range(start_byte..old_end_byte, new_text);
-                old_tree.edit(&input_edit);
+        successful_exit("LSP", "goto_definition");
+        Ok(None)
+    }
+
+    /// Compute the response of `textDocument/hover`; see `hover`.
+    async fn uncached_hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        start_of_function("LSP", "hover");
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "hover");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        // routine labels, called from a routine or a class
+        if let Some((range, value)) = data.get_label_hover(&uri, position) {
+            successful_exit("LSP", "hover");
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: Some(range),
+            }));
+        }
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "hover");
+            return Ok(None);
+        };
+        let content = document.content.as_str();
+        let point = position_to_point(content, position);
+        let signature_hover = data.get_method_signature_hover(&uri, point);
+        let hovered_metrics = data
+            .get_method_metrics(&uri)
+            .into_iter()
+            .find(|metrics| metrics.range.start_point <= point && point <= metrics.range.end_point);
+        if let Some(metrics) = hovered_metrics {
+            let mut value = format!("**{}**: {}", metrics.name, metrics.title());
+            if let Some((_, signature)) = &signature_hover {
+                value = format!("{signature}\n\n{value}");
+            }
+            if let Some(summary) = data.get_override_summary(&uri, &metrics.name) {
+                value.push_str("\n\n");
+                value.push_str(&summary);
+            }
+            if let Some(note) = data.get_member_visibility_note(&uri, &metrics.name) {
+                value.push_str("\n\n");
+                value.push_str(&note);
             }
+            successful_exit("LSP", "hover");
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: Some(ts_range_to_lsp_range(content, metrics.range)),
+            }));
+        }
+        let api_hover = data
+            .get_python_hover(&uri, point)
+            .or_else(|| data.get_element_type_hover(&uri, point))
+            .or_else(|| data.get_embedded_sql_hover(&uri, point))
+            .or_else(|| data.get_system_api_hover(&uri, point))
+            .or_else(|| data.get_class_reference_hover(&uri, point))
+            .or_else(|| data.get_argument_hover(&uri, point))
+            .or_else(|| data.get_property_hover(&uri, point))
+            .or_else(|| data.get_parameter_hover(&uri, point))
+            .or_else(|| data.get_macro_hover(&uri, point))
+            .or(signature_hover)
+            .or_else(|| data.get_class_hover(&uri, point));
+        if let Some((range, value)) = api_hover {
+            successful_exit("LSP", "hover");
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: Some(ts_range_to_lsp_range(content, range)),
+            }));
+        }
+        let Some(name_node) = document
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)
+            .and_then(oref_method_name_node)
+        else {
+            successful_exit("LSP", "hover");
+            return Ok(None);
+        };
+        let Some(method_name) = content.get(name_node.byte_range()) else {
+            generic_exit_statements("LSP", "hover");
+            return Ok(None);
+        };
+        let candidates = data.get_heuristic_method_candidates(method_name);
+        if candidates.is_empty() {
+            successful_exit("LSP", "hover");
+            return Ok(None);
         }
+        let classes: Vec<String> = candidates
+            .iter()
+            .map(|(class_name, _, _)| format!("- `{class_name}`"))
+            .collect();
+        let value = format!(
+            "**Heuristic match**: the class of this object reference couldn't be inferred.\n\n\
+             `{method_name}` is declared in:\n{}",
+            classes.join("\n")
+        );
+        successful_exit("LSP", "hover");
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(ts_range_to_lsp_range(content, name_node.range())),
+        }))
+    }
 
-        let parsed: Option<Tree> = {
-            if file_type == FileType::Cls {
-                let mut parser = project.parsers.cls.lock();
-                if did_full_replace {
-                    parser.parse(&old_text, None)
-                } else {
-                    parser.parse(&old_text, Some(&old_tree))
-                }
-            } else {
-                let mut parser = project.parsers.routine.lock();
-                if did_full_replace {
-                    parser.parse(&old_text, None)
-                } else {
-                    parser.parse(&old_text, Some(&old_tree))
-                }
-            }
-        }; // lock guard drops here
+    /// Compute the response of `textDocument/documentSymbol`; see `document_symbol`.
+    async fn uncached_document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        start_of_function("LSP", "document_symbol");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "document_symbol");
+            return Ok(None);
+        };
+        let symbols = project.data.read().get_document_symbols(&uri);
+        successful_exit("LSP", "document_symbol");
+        Ok(symbols.map(DocumentSymbolResponse::Nested))
+    }
 
-        let new_tree = match parsed {
-            Some(t) => t,
-            None => {
+    /// Compute the response of `textDocument/foldingRange`; see `folding_range`.
+    async fn uncached_folding_range(
+        &self,
+        params: FoldingRangeParams,
+    ) -> Result<Option<Vec<FoldingRange>>> {
+        start_of_function("LSP", "folding_range");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "folding_range");
+            return Ok(None);
+        };
+        let ranges = project.data.read().get_folding_ranges(&uri);
+        successful_exit("LSP", "folding_range");
+        Ok(ranges)
+    }
+
+    /// Write the `text` exported by a command to `path`, relative to the workspace root, and
+    /// return the path written; failures are logged to the client.
+    async fn write_export(
+        &self,
+        project: &ProjectState,
+        path: &str,
+        text: String,
+        what: &str,
+    ) -> Option<Value> {
+        let path = match project.root_path() {
+            Some(root) => root.join(path),
+            None => PathBuf::from(path),
+        };
+        match std::fs::write(&path, text) {
+            Ok(()) => Some(Value::String(path.display().to_string())),
+            Err(e) => {
                 self.0
                     .client
                     .log_message(
-                        MessageType::WARNING,
-                        "Incremental parse failed.".to_string(),
+                        MessageType::ERROR,
+                        format!("Failed to write {what} to {}: {e}", path.display()),
                     )
                     .await;
-                return;
+                None
             }
+        }
+    }
+
+    /// Handle `objectscript/overrideChain`.
+    ///
+    /// Returns the base method of the chain containing the method at the given position followed
+    /// by every override, so a client can show them in a single peek view instead of issuing
+    /// separate definition and implementation requests per entry.
+    pub async fn override_chain(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<Vec<Location>>> {
+        start_of_function("LSP", "override_chain");
+        let uri = params.text_document.uri;
+        let Some(project) = self.0.get_project_from_document_url(&uri) else {
+            generic_exit_statements("LSP", "override_chain");
+            return Ok(None);
+        };
+        let data = project.data.read();
+        let Some(document) = data.documents.get(&uri) else {
+            generic_exit_statements("LSP", "override_chain");
+            return Ok(None);
         };
+        let content = document.content.as_str();
+        let point = position_to_point(content, params.position);
+        let Some(method_name) = document
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)
+            .and_then(|node| method_name_from_identifier_node(node, content, 0))
+        else {
+            generic_exit_statements("LSP", "override_chain");
+            return Ok(None);
+        };
+        let locations: Vec<Location> = data
+            .get_override_chain(&uri, &method_name)
+            .into_iter()
+            .filter_map(|(uri, range)| {
+                let content = data.documents.get(&uri)?.content.as_str();
+                let range = ts_range_to_lsp_range(content, range);
+                Some(Location { uri, range })
+            })
+            .collect();
+        successful_exit("LSP", "override_chain");
+        Ok((!locations.is_empty()).then_some(locations))
+    }
 
-        {
-            let mut data = project.data.write();
-            if let Some(doc) = data.documents.get_mut(&uri) {
-                doc.content = old_text.clone();
-                doc.tree = new_tree.clone();
-                doc.version = Some(new_version);
-                doc.file_type = file_type.clone();
+    /// Handle `objectscript/semanticDiff`.
+    ///
+    /// Compares two versions of a class (see `semantic_diff`) without touching the workspace
+    /// index, so review tooling can ask about changes that aren't on disk.
+    pub async fn semantic_diff(&self, params: SemanticDiffParams) -> Result<Value> {
+        start_of_function("LSP", "semantic_diff");
+        let Some(diff) = semantic_diff(params.old_text.as_str(), params.new_text.as_str()) else {
+            generic_exit_statements("LSP", "semantic_diff");
+            return Err(Error::invalid_params(
+                "both oldText and newText must contain a class definition",
+            ));
+        };
+        successful_exit("LSP", "semantic_diff");
+        Ok(diff)
+    }
+
+    /// Handle `objectscript/sqlSurface`.
+    ///
+    /// Lists the class methods and class queries of every workspace that are projected to SQL as
+    /// stored procedures, with their SQL names and source locations. With a `query`, only the
+    /// projections with that SQL name are returned, so a client can navigate from an identifier
+    /// in SQL back to the ObjectScript source.
+    pub async fn sql_surface(&self, params: SqlSurfaceParams) -> Result<Vec<Value>> {
+        start_of_function("LSP", "sql_surface");
+        let mut surface = Vec::new();
+        for project in self.0.projects.read().values() {
+            let data = project.data.read();
+            for (uri, projection) in data.get_sql_projections(params.query.as_deref()) {
+                let Some(document) = data.documents.get(&uri) else {
+                    continue;
+                };
+                let range = ts_range_to_lsp_range(document.content.as_str(), projection.range);
+                surface.push(json!({
+                    "sqlName": projection.qualified_name(),
+                    "schema": projection.schema,
+                    "name": projection.name,
+                    "kind": projection.kind.label(),
+                    "className": projection.class_name,
+                    "member": projection.member_name,
+                    "location": Location { uri, range },
+                }));
             }
         }
+        successful_exit("LSP", "sql_surface");
+        Ok(surface)
+    }
 
-        if new_tree.root_node().has_error() {
-            self.0
-                .client
-                .log_message(
-                    MessageType::ERROR,
-                    format!("New Tree has Errors: {:?}", new_tree.root_node().to_sexp()),
-                )
-                .await;
-        } else {
-            project.update_document(uri, new_tree, file_type, new_version, old_text.as_str());
+    /// Handle `objectscript/unresolved`.
+    ///
+    /// Lists the class, method, include file, and routine references of every workspace that
+    /// don't resolve to anything in it, so migrations and dead-code cleanup can be tracked as the
+    /// count goes down. `uri` and `kind` narrow the list to one document or one kind.
+    pub async fn unresolved(&self, params: UnresolvedParams) -> Result<Vec<Value>> {
+        start_of_function("LSP", "unresolved");
+        let mut unresolved = Vec::new();
+        for project in self.0.projects.read().values() {
+            let data = project.data.read();
+            for (uri, reference) in
+                data.get_unresolved_table(params.uri.as_ref(), params.kind.as_deref())
+            {
+                let Some(document) = data.documents.get(&uri) else {
+                    continue;
+                };
+                let range = ts_range_to_lsp_range(document.content.as_str(), reference.range);
+                unresolved.push(json!({
+                    "kind": reference.kind.label(),
+                    "name": reference.name,
+                    "location": Location { uri, range },
+                }));
+            }
         }
+        successful_exit("LSP", "unresolved");
+        Ok(unresolved)
     }
-
-    // async fn did_close(&self, params: DidCloseTextDocumentParams) {}
 }
@@ -0,0 +1,172 @@
+use crate::common::{position_encoding, word_at_offset};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, MarkupContent,
+    MarkupKind, Position, Range, TextEdit, Url,
+};
+
+/// A macro defined by a `#define` (or `#def1arg`) line of an include file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroDefinition {
+    pub name: String,
+    /// The parenthesized argument list, e.g. `(%a,%b)`, if the macro takes arguments.
+    pub arguments: Option<String>,
+    /// The replacement text, which may be empty.
+    pub value: String,
+    /// 0-based line of the directive.
+    pub line: usize,
+}
+
+impl MacroDefinition {
+    /// Renders the directive defining the macro, e.g. `#define Log(%msg) do ...`.
+    pub fn directive(&self) -> String {
+        let arguments = self.arguments.as_deref().unwrap_or_default();
+        format!("#define {}{} {}", self.name, arguments, self.value)
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// The macros and nested `#include`s of one include file, as stored in
+/// `ProjectData::include_macros`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IncludeMacros {
+    pub macros: Vec<MacroDefinition>,
+    /// Names of the include files pulled in with `#include`, whose macros are visible too.
+    pub includes: Vec<String>,
+    /// Url of the file, set once it is stored in the project.
+    pub url: Option<Url>,
+}
+
+/// Scans the text of an include file for its `#define`, `#def1arg` and `#include` lines.
+///
+/// This is lexical: preprocessor lines are recognized wherever they appear, and a line ending
+/// with `##continue` is joined with the next one.
+pub fn include_macros(content: &str) -> IncludeMacros {
+    let mut include_macros = IncludeMacros::default();
+    let mut lines = content.lines().enumerate();
+    while let Some((line_number, line)) = lines.next() {
+        let mut line = line.trim().to_string();
+        while let Some(continued) = line.strip_suffix("##continue") {
+            let next = lines.next().map(|(_, next)| next).unwrap_or_default();
+            line = format!("{}{}", continued, next.trim());
+        }
+        let Some(directive) = line.strip_prefix('#') else {
+            continue;
+        };
+        let keyword_end = directive
+            .find(char::is_whitespace)
+            .unwrap_or(directive.len());
+        let rest = directive[keyword_end..].trim_start();
+        match directive[..keyword_end].to_ascii_lowercase().as_str() {
+            "define" | "def1arg" => {
+                let name_end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '%'))
+                    .unwrap_or(rest.len());
+                if name_end == 0 {
+                    continue;
+                }
+                let after_name = &rest[name_end..];
+                let (arguments, value) = match after_name.find(')') {
+                    Some(end) if after_name.starts_with('(') => {
+                        (Some(after_name[..=end].to_string()), &after_name[end + 1..])
+                    }
+                    _ => (None, after_name),
+                };
+                include_macros.macros.push(MacroDefinition {
+                    name: rest[..name_end].to_string(),
+                    arguments,
+                    value: value.trim().to_string(),
+                    line: line_number,
+                });
+            }
+            "include" => {
+                let name: String = rest
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '%')
+                    .collect();
+                if !name.is_empty() {
+                    include_macros.includes.push(name);
+                }
+            }
+            _ => {}
+        }
+    }
+    include_macros
+}
+
+/// Finds the `$$$Name` macro whose name (or `$$$`) is at byte `offset` of `content`, returning
+/// the name and the byte range of the whole reference.
+pub fn macro_name_at(content: &str, offset: usize) -> Option<(String, std::ops::Range<usize>)> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '%' || c == '$';
+    let std::ops::Range { start, end } = word_at_offset(content, offset, is_name);
+    let text = &content[start..end];
+    let dollars = text.find("$$$")?;
+    let name = &text[dollars + 3..];
+    if name.is_empty() || name.contains('$') {
+        return None;
+    }
+    Some((name.to_string(), start + dollars..end))
+}
+
+/// Returns the partially typed macro name if the cursor is right after `$$$` and an optional
+/// name, e.g. `Type` in `set x = $$$Type`.
+pub fn macro_prefix(line_before_cursor: &str) -> Option<&str> {
+    let name_start = line_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '%')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line_before_cursor.len());
+    line_before_cursor[..name_start].strip_suffix("$$$")?;
+    Some(&line_before_cursor[name_start..])
+}
+
+/// Completes the macros whose names start with `partial` (case-insensitively), each paired
+/// with the name of the include file defining it. Items replace the typed name, which ends at
+/// the cursor `position`.
+pub fn macro_completions(
+    macros: &[(&MacroDefinition, String)],
+    partial: &str,
+    position: Position,
+) -> Vec<CompletionItem> {
+    let replace_range = Range {
+        start: Position {
+            line: position.line,
+            character: position
+                .character
+                .saturating_sub(position_encoding().units(partial)),
+        },
+        end: position,
+    };
+    let partial = partial.to_ascii_lowercase();
+    macros
+        .iter()
+        .filter(|(definition, _)| definition.name.to_ascii_lowercase().starts_with(&partial))
+        .map(|(definition, include)| {
+            let signature = format!(
+                "{}{}",
+                definition.name,
+                definition.arguments.as_deref().unwrap_or_default()
+            );
+            let mut documentation = format!("Defined in `{include}.inc`");
+            if !definition.value.is_empty() {
+                documentation.push_str(&format!("\n\n```objectscript\n{}\n```", definition.value));
+            }
+            CompletionItem {
+                label: definition.name.clone(),
+                kind: Some(CompletionItemKind::CONSTANT),
+                detail: Some(format!("$$${signature}")),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: documentation,
+                })),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: replace_range,
+                    new_text: definition.name.clone(),
+                })),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
@@ -1,28 +1,78 @@
+use crate::lsp::{
+    OVERRIDE_CHAIN_REQUEST, SEMANTIC_DIFF_REQUEST, SQL_SURFACE_REQUEST, UNRESOLVED_REQUEST,
+};
 use crate::server::BackendWrapper;
-use std::sync::Arc;
 use tower_lsp::{LspService, Server};
+mod arena;
+mod atelier;
 mod backend_testing;
+mod call_graph;
+mod cancellation;
+mod capabilities;
+mod case_rules;
 mod class;
+mod class_reference;
+mod code_lens;
+mod commands;
 mod common;
+mod completion;
 mod config;
+mod diagnostics;
+mod doctor;
 mod document;
+mod element_types;
+mod embedded_sql;
+mod file_events;
+mod folding;
+mod formatting;
+mod fuzzy;
 mod global_semantic;
+mod index_progress;
+mod interner;
+mod intrinsics;
 mod local_semantic;
 mod lsp;
+mod macros;
 mod method;
+mod metrics;
+mod new_class;
+mod oref_types;
 mod override_index;
+mod package_rename;
 mod parse_structures;
+mod python;
+mod reference_index;
+mod response_cache;
+mod routine;
+mod rules;
 mod scope_structures;
 mod scope_tree;
+mod semantic_diff;
+mod semantic_tokens;
 mod server;
+mod snapshot;
+mod source_provider;
+mod sql_surface;
+mod symbol_index;
+mod system_api;
+mod tags;
 mod test;
+mod type_check;
+mod unresolved;
+mod usage;
 mod variable;
 mod workspace;
+mod xml_export;
 
 #[tokio::main]
 async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
-    let (service, socket) = LspService::new(|client| Arc::new(BackendWrapper::new(client)));
+    let (service, socket) = LspService::build(BackendWrapper::new)
+        .custom_method(OVERRIDE_CHAIN_REQUEST, BackendWrapper::override_chain)
+        .custom_method(SEMANTIC_DIFF_REQUEST, BackendWrapper::semantic_diff)
+        .custom_method(SQL_SURFACE_REQUEST, BackendWrapper::sql_surface)
+        .custom_method(UNRESOLVED_REQUEST, BackendWrapper::unresolved)
+        .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
 }
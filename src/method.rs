@@ -3,8 +3,13 @@ use crate::common::{
     find_return_type, generic_exit_statements, generic_skipping_statements, get_node_children,
     get_string_at_byte_range, start_of_function, successful_exit,
 };
-use crate::parse_structures::{CodeMode, Language, Method, MethodType, ReturnType, Variable};
-use crate::variable::{build_variable_from_argument, build_variable_from_set_argument_rhs};
+use crate::parse_structures::{
+    ArgumentMode, CodeMode, Language, MemberVisibility, Method, MethodType, ReturnType, Variable,
+};
+use crate::variable::{
+    argument_mode, argument_name_node, build_variable_from_argument,
+    build_variable_from_set_argument_rhs, declared_argument_name,
+};
 use std::collections::HashMap;
 use tree_sitter::{Node, Range};
 
@@ -33,11 +38,15 @@ pub fn initial_build_method(
     };
     let method_range = node.range();
     let mut method_return_type = None;
+    let mut return_type_name = None;
     let mut is_procedure_block = None;
     let mut language = None;
     let mut codemode = None;
     let mut is_public = true;
     let mut public_variables = Vec::new();
+    let mut arguments = Vec::new();
+    let mut argument_declarations = Vec::new();
+    let mut visibility = MemberVisibility::default();
     let children = get_node_children(node.clone());
     for node in children[1..].iter() {
         match node.kind() {
@@ -60,9 +69,26 @@ pub fn initial_build_method(
                     );
                     continue;
                 };
+                return_type_name = Some(typename.clone());
                 method_return_type = find_return_type(typename);
             }
+            "arguments" => {
+                // each child is an argument, whose first child (after any modifier) is its name
+                arguments = get_node_children(*node)
+                    .into_iter()
+                    .filter_map(|argument| argument_name_node(argument, content))
+                    .filter_map(|name| get_string_at_byte_range(content, name.byte_range()))
+                    .collect();
+                argument_declarations = get_node_children(*node)
+                    .into_iter()
+                    .filter_map(|argument| content.get(argument.byte_range()))
+                    .map(|argument| argument.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .collect();
+            }
             "method_keywords" => {
+                if let Some(text) = content.get(node.byte_range()) {
+                    visibility = common::member_visibility(text);
+                }
                 let Some((
                     is_procedure_block_val,
                     language_val,
@@ -88,7 +114,7 @@ pub fn initial_build_method(
             }
         }
     }
-    let method = Method::new(
+    let mut method = Method::new(
         method_name,
         is_procedure_block,
         language,
@@ -98,6 +124,10 @@ pub fn initial_build_method(
         public_variables,
         method_type,
     );
+    method.arguments = arguments;
+    method.argument_declarations = argument_declarations;
+    method.return_type_name = return_type_name;
+    method.visibility = visibility;
     successful_exit("COMMON: No struct", "initial_build_method");
     Some((method, method_range))
 }
@@ -123,11 +153,104 @@ impl Method {
             private_variables: HashMap::new(),
             public_variables: HashMap::new(),
             is_public,
+            declared_keywords: (is_procedure_block, language.clone()),
             is_procedure_block,
             language,
             code_mode,
             public_variables_declared: public_variables,
+            arguments: Vec::new(),
+            argument_declarations: Vec::new(),
+            return_type_name: None,
+            visibility: MemberVisibility::default(),
+        }
+    }
+
+    /// Renders the declaration of the method shown in hovers, e.g.
+    /// `ClassMethod Total(ByRef count As %Integer) As %Status [ Private, CodeMode = expression ]`.
+    ///
+    /// Only keywords changing how the method is called are listed: `Private`, and `Language` and
+    /// `CodeMode` when they aren't the defaults.
+    pub fn signature(&self) -> String {
+        let method_type = match self.method_type {
+            MethodType::InstanceMethod => "Method",
+            MethodType::ClassMethod => "ClassMethod",
+        };
+        let mut signature = format!(
+            "{method_type} {}({})",
+            self.name,
+            self.argument_declarations.join(", ")
+        );
+        if let Some(return_type) = &self.return_type_name {
+            signature.push_str(&format!(" As {return_type}"));
+        }
+        let mut keywords = Vec::new();
+        if !self.is_public {
+            keywords.push("Private".to_string());
+        }
+        if let Some(language) = self
+            .language
+            .as_ref()
+            .filter(|language| **language != Language::Objectscript)
+        {
+            keywords.push(format!("Language = {}", language.keyword()));
+        }
+        let code_mode = match self.code_mode {
+            CodeMode::Call => Some("call"),
+            CodeMode::Expression => Some("expression"),
+            CodeMode::ObjectGenerator => Some("objectgenerator"),
+            CodeMode::Code => None,
+        };
+        if let Some(code_mode) = code_mode {
+            keywords.push(format!("CodeMode = {code_mode}"));
+        }
+        if !keywords.is_empty() {
+            signature.push_str(&format!(" [ {} ]", keywords.join(", ")));
         }
+        signature
+    }
+
+    /// Renders a method overriding this one by calling it through `##super`, e.g.
+    /// `Method Save(ByRef id) As %Status\n{\n    Quit ##super(.id)\n}`.
+    ///
+    /// The stub keeps the arguments, the return type, and `Private`; its body is ObjectScript, so
+    /// `Language` and `CodeMode` are dropped. `ByRef` and `Output` arguments are passed on by
+    /// reference, and a method without a return type uses `Do` instead of `Quit`.
+    pub fn override_stub(&self) -> String {
+        let method_type = match self.method_type {
+            MethodType::InstanceMethod => "Method",
+            MethodType::ClassMethod => "ClassMethod",
+        };
+        let mut declaration = format!(
+            "{method_type} {}({})",
+            self.name,
+            self.argument_declarations.join(", ")
+        );
+        if let Some(return_type) = &self.return_type_name {
+            declaration.push_str(&format!(" As {return_type}"));
+        }
+        if !self.is_public {
+            declaration.push_str(" [ Private ]");
+        }
+        let arguments: Vec<String> = self
+            .argument_declarations
+            .iter()
+            .filter_map(|argument| {
+                let name = declared_argument_name(argument)?;
+                Some(match argument_mode(argument) {
+                    ArgumentMode::ByValue => name.to_string(),
+                    ArgumentMode::ByRef | ArgumentMode::Output => format!(".{name}"),
+                })
+            })
+            .collect();
+        let command = if self.return_type_name.is_some() {
+            "Quit"
+        } else {
+            "Do"
+        };
+        format!(
+            "{declaration}\n{{\n    {command} ##super({})\n}}",
+            arguments.join(", ")
+        )
     }
 
     /// Parses a method definition node to extract variables and their dependencies.
@@ -138,6 +261,7 @@ impl Method {
     ///
     /// Returns a list of `(variable, definition_range, var_dependencies, property_dependencies)`.
     /// Visibility (public vs private) is inferred from ProcedureBlock and `public_variables_declared`.
+    /// The body of a Python method is skipped; only its arguments are collected.
     pub fn build_method_variables_and_ref(
         &self,
         node: Node,
@@ -151,9 +275,9 @@ impl Method {
                 let children = get_node_children(node.clone());
                 for node in children {
                     // each node is an argument (aka variable)
-                    let Some(variable_name_node) = node.named_child(0) else {
+                    let Some(variable_name_node) = argument_name_node(node, content) else {
                         eprintln!(
-                            "Warning: failed to get child node (index 0) for node: {:?}",
+                            "Warning: failed to get argument name node for node: {:?}",
                             node.kind()
                         );
                         generic_skipping_statements(
@@ -201,7 +325,7 @@ impl Method {
                         ));
                     }
                 }
-            } else if node.kind() == "core_method_body_content" {
+            } else if node.kind() == "core_method_body_content" && !self.is_python() {
                 let children = get_node_children(node.clone());
                 for statement in children {
                     let Some(node) = statement.named_child(0) else {
@@ -306,22 +430,26 @@ impl Method {
         variables
     }
 
+    /// Whether the method is written in Python (`Language = python`, its own or the class's once
+    /// `update_keywords` ran), so its body isn't ObjectScript.
+    pub fn is_python(&self) -> bool {
+        self.language == Some(Language::Python)
+    }
+
     /// Applies inherited class keywords to this method when not explicitly set.
     ///
     /// - Inherits `ProcedureBlock=false` only when the method has no explicit setting.
     /// - Inherits the class `default_language` when the method language is unset.
+    ///
+    /// The keywords are taken from `declared_keywords`, so this can run again when the class
+    /// keywords change.
     pub fn update_keywords(&mut self, is_procedure_block: bool, default_language: Language) {
         start_of_function("Method", "update_keywords");
+        let (declared_procedure_block, declared_language) = self.declared_keywords.clone();
         // inherit class keywords if not specified and class keyword isn't the default value
-        if self.is_procedure_block.is_none() && is_procedure_block == false {
-            // inherit the class keyword when it isn't the default
-            self.is_procedure_block = Some(is_procedure_block);
-        }
-
-        if self.language.is_none() {
-            // inherit the class keyword when it isn't the default
-            self.language = Some(default_language.clone());
-        }
+        self.is_procedure_block =
+            declared_procedure_block.or((!is_procedure_block).then_some(false));
+        self.language = Some(declared_language.unwrap_or(default_language));
         successful_exit("Method", "update_keywords");
     }
 }
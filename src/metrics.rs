@@ -0,0 +1,163 @@
+use crate::common::{
+    method_definition_nodes, start_of_function, successful_exit, ts_range_to_lsp_range,
+};
+use crate::diagnostics::DIAGNOSTIC_SOURCE;
+use crate::rules::{rule_code, rule_code_description, METHOD_COMPLEXITY_RULE};
+use tower_lsp::lsp_types::{CodeLens, Command, Diagnostic, DiagnosticSeverity, Position};
+use tree_sitter::{Node, Range, Tree};
+
+/// Commands (node kinds without the `command_` prefix) that branch or loop, each adding a
+/// decision point.
+const DECISION_COMMANDS: [&str; 5] = ["if", "elseif", "for", "while", "catch"];
+
+/// Functions choosing between several values, each adding a decision point.
+const SELECT_FUNCTIONS: [&str; 3] = ["$select", "$s", "$case"];
+
+/// Complexity metrics of one method.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MethodMetrics {
+    pub name: String,
+    /// Range of the method name.
+    pub range: Range,
+    /// 1 + the number of decision points: branching and looping commands, postconditionals,
+    /// and `$select` / `$case` expressions.
+    pub cyclomatic: usize,
+    /// Deepest nesting of commands inside command blocks.
+    pub nesting_depth: usize,
+}
+
+impl MethodMetrics {
+    /// Renders the summary shown in hovers and code lenses, e.g.
+    /// `Cyclomatic complexity 7 · nesting depth 3`.
+    pub fn title(&self) -> String {
+        format!(
+            "Cyclomatic complexity {} · nesting depth {}",
+            self.cyclomatic, self.nesting_depth
+        )
+    }
+
+    /// Builds the (display-only) code lens shown on the method at `position`.
+    pub fn to_code_lens(&self, position: Position) -> CodeLens {
+        CodeLens {
+            range: tower_lsp::lsp_types::Range {
+                start: position,
+                end: position,
+            },
+            command: Some(Command {
+                title: self.title(),
+                command: String::new(),
+                arguments: None,
+            }),
+            data: None,
+        }
+    }
+}
+
+/// Computes the metrics of every method in the class defined by `tree`.
+pub fn method_metrics(content: &str, tree: &Tree) -> Vec<MethodMetrics> {
+    start_of_function("METRICS (no struct)", "method_metrics");
+    let mut metrics = Vec::new();
+    for (method_definition, _) in method_definition_nodes(tree.root_node()) {
+        let Some(name_node) = method_definition.named_child(0) else {
+            continue;
+        };
+        let Some(name) = content.get(name_node.byte_range()) else {
+            continue;
+        };
+        let mut decisions = 0;
+        let mut nesting_depth = 0;
+        count_decisions(
+            method_definition,
+            content,
+            0,
+            &mut decisions,
+            &mut nesting_depth,
+        );
+        metrics.push(MethodMetrics {
+            name: name.to_string(),
+            range: name_node.range(),
+            cyclomatic: 1 + decisions,
+            nesting_depth,
+        });
+    }
+    successful_exit("METRICS (no struct)", "method_metrics");
+    metrics
+}
+
+/// Recursively counts the decision points below `node`, where `depth` is the number of enclosing
+/// commands.
+fn count_decisions(
+    node: Node,
+    content: &str,
+    depth: usize,
+    decisions: &mut usize,
+    nesting_depth: &mut usize,
+) {
+    let mut depth = depth;
+    if let Some(command) = node.kind().strip_prefix("command_") {
+        *nesting_depth = (*nesting_depth).max(depth);
+        depth += 1;
+        if DECISION_COMMANDS.contains(&command) {
+            *decisions += 1;
+        }
+        // child(0) is the command keyword; a postconditional (`quit:done`) follows it directly
+        let postconditional = node
+            .named_child(0)
+            .and_then(|keyword| content.get(keyword.end_byte()..))
+            .is_some_and(|rest| rest.starts_with(':'));
+        if postconditional {
+            *decisions += 1;
+        }
+    } else if node.child_count() == 0 {
+        let text = content.get(node.byte_range()).unwrap_or_default();
+        if SELECT_FUNCTIONS
+            .iter()
+            .any(|function| text.eq_ignore_ascii_case(function))
+        {
+            *decisions += 1;
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        count_decisions(child, content, depth, decisions, nesting_depth);
+    }
+}
+
+/// Warns about methods whose complexity or nesting exceeds the configured thresholds; a
+/// threshold of 0 disables its warning.
+pub fn complexity_diagnostics(
+    content: &str,
+    metrics: &[MethodMetrics],
+    max_complexity: usize,
+    max_nesting: usize,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for method in metrics {
+        let mut problems = Vec::new();
+        if max_complexity > 0 && method.cyclomatic > max_complexity {
+            problems.push(format!(
+                "cyclomatic complexity {} exceeds {max_complexity}",
+                method.cyclomatic
+            ));
+        }
+        if max_nesting > 0 && method.nesting_depth > max_nesting {
+            problems.push(format!(
+                "nesting depth {} exceeds {max_nesting}",
+                method.nesting_depth
+            ));
+        }
+        if problems.is_empty() {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            range: ts_range_to_lsp_range(content, method.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(METHOD_COMPLEXITY_RULE),
+            code_description: rule_code_description(METHOD_COMPLEXITY_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!("Method {:?}: {}", method.name, problems.join(", ")),
+            ..Default::default()
+        });
+    }
+    diagnostics
+}
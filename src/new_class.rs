@@ -0,0 +1,59 @@
+use crate::package_rename::renamed_class_path;
+use tower_lsp::lsp_types::{
+    CreateFile, CreateFileOptions, DocumentChangeOperation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Position, Range, ResourceOp, TextDocumentEdit,
+    TextEdit, Url, WorkspaceEdit,
+};
+
+/// Returns the source of a new, empty class named `class_name`.
+pub fn class_skeleton(class_name: &str) -> String {
+    format!("Class {class_name} Extends %RegisteredObject\n{{\n\n}}\n")
+}
+
+/// Returns the uri of the file a new class `class_name` is created in, laid out like the class
+/// file at `url` of the class `document_class`: in package folders (`src/MyApp/NewThing.cls`)
+/// or named after the full class name (`src/MyApp.NewThing.cls`).
+///
+/// Falls back to the package folders under `root` when the file at `url` is named any other way.
+pub fn new_class_url(
+    url: &Url,
+    document_class: &str,
+    class_name: &str,
+    root: Option<&Url>,
+) -> Option<Url> {
+    let path = renamed_class_path(url.path(), document_class, class_name).or_else(|| {
+        let root = root?.path().trim_end_matches('/');
+        Some(format!("{root}/{}.cls", class_name.replace('.', "/")))
+    })?;
+    let mut new_url = url.clone();
+    new_url.set_path(&path);
+    Some(new_url)
+}
+
+/// Builds the workspace edit creating the class file at `url` (failing if it already exists)
+/// and filling it with `content`.
+pub fn create_class_edit(url: &Url, content: &str) -> WorkspaceEdit {
+    let create = DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+        uri: url.clone(),
+        options: Some(CreateFileOptions {
+            overwrite: Some(false),
+            ignore_if_exists: Some(false),
+        }),
+        annotation_id: None,
+    }));
+    let start = Position::new(0, 0);
+    let insert = DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier {
+            uri: url.clone(),
+            version: None,
+        },
+        edits: vec![OneOf::Left(TextEdit {
+            range: Range::new(start, start),
+            new_text: content.to_string(),
+        })],
+    });
+    WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Operations(vec![create, insert])),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,236 @@
+use crate::common::{get_node_children, method_definition_nodes, point_to_byte};
+use crate::element_types::last_assignment_at;
+use crate::variable::argument_name_node;
+use tree_sitter::{Node, Point, Tree};
+
+/// Class methods returning an instance of the class they are called on.
+const CONSTRUCTORS: [&str; 3] = ["%new", "%openid", "%open"];
+
+/// Splits a class method call `##class(Pkg.Cls).Method(...)` into the class and method names.
+pub fn class_method_call_parts(expression: &str) -> Option<(&str, &str)> {
+    let rest = expression
+        .get(.."##class(".len())
+        .filter(|keyword| keyword.eq_ignore_ascii_case("##class("))
+        .map(|_| &expression["##class(".len()..])?;
+    let (class_name, rest) = rest.split_once(')')?;
+    let class_name = class_name.trim();
+    let method_name = rest.strip_prefix('.')?;
+    let method_name = method_name
+        .split_once('(')
+        .map_or(method_name, |(name, _)| name);
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '%')
+    };
+    (is_name(class_name) && is_name(method_name)).then_some((class_name, method_name))
+}
+
+/// A link of an expression chain such as `order.Customer.Address()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChainMember {
+    /// A method call, `.Name(...)`.
+    Method,
+    /// A property, `.Name`.
+    Property,
+}
+
+/// Where an expression chain starts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChainHead<'a> {
+    /// `##class(Pkg.Cls)`, which is followed by a class method call.
+    Class(&'a str),
+    /// `..` or `$this`: the class whose method is being edited.
+    CurrentClass,
+    /// A local variable.
+    Variable(&'a str),
+}
+
+/// Splits an expression of the form `head.Member(...).Member...` into where it starts and the
+/// members it goes through, e.g. `..Find(id).Customer` into `CurrentClass`, `Find` (a method)
+/// and `Customer` (a property).
+///
+/// Returns `None` for anything else, e.g. an expression with an operator.
+pub fn expression_chain(expression: &str) -> Option<(ChainHead<'_>, Vec<(&str, ChainMember)>)> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '%';
+    let (head, mut rest) = if expression.starts_with("..") {
+        (ChainHead::CurrentClass, &expression[1..])
+    } else if expression
+        .get(.."$this".len())
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("$this"))
+        && !expression["$this".len()..].starts_with(is_name_char)
+    {
+        (ChainHead::CurrentClass, &expression["$this".len()..])
+    } else if let Some((class_name, _)) = class_method_call_parts(expression) {
+        let rest = &expression[expression.find(')')? + 1..];
+        (ChainHead::Class(class_name), rest)
+    } else {
+        let length = expression
+            .find(|c: char| !is_name_char(c))
+            .unwrap_or(expression.len());
+        let name = &expression[..length];
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '%') {
+            return None;
+        }
+        (ChainHead::Variable(name), &expression[length..])
+    };
+    let mut members = Vec::new();
+    while !rest.is_empty() {
+        let link = rest.strip_prefix('.')?;
+        let length = link.find(|c: char| !is_name_char(c)).unwrap_or(link.len());
+        if length == 0 {
+            return None;
+        }
+        let (name, after) = link.split_at(length);
+        if after.starts_with('(') {
+            members.push((name, ChainMember::Method));
+            rest = &after[closing_parenthesis(after)? + 1..];
+        } else {
+            members.push((name, ChainMember::Property));
+            rest = after;
+        }
+    }
+    if matches!(head, ChainHead::Class(_)) && members.is_empty() {
+        return None;
+    }
+    Some((head, members))
+}
+
+/// Returns the byte offset of the parenthesis closing the one `text` starts with, skipping
+/// string literals.
+fn closing_parenthesis(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    for (n, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(n);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Infers the class of the object held by the local variable `name` at `point`, from what the
+/// enclosing method says about it, in order:
+/// - the `set` last assigning it before `point`, when its value is a chain of members (see
+///   `expression_chain`): `##class(Pkg.Cls).%New()` (or `%OpenId`, `%Open`) yields `Pkg.Cls`,
+///   `..` and `$this` start from `class_name`, another variable is followed through its own
+///   assignments, and each member is looked up with `member_type(class_name, member_name,
+///   kind)`, which returns a method's return type or a property's type;
+/// - a `#dim name As Pkg.Cls` declaration;
+/// - the declared type of the argument `name`.
+///
+/// Returns the class name as written, which may need resolving against the class's package.
+pub fn infer_oref_type(
+    content: &str,
+    tree: &Tree,
+    point: Point,
+    name: &str,
+    class_name: &str,
+    member_type: impl Fn(&str, &str, ChainMember) -> Option<String>,
+) -> Option<String> {
+    let cursor = point_to_byte(content, point);
+    let contains_cursor = |node: &Node| node.start_byte() <= cursor && cursor <= node.end_byte();
+    let (method_definition, _) = method_definition_nodes(tree.root_node())
+        .into_iter()
+        .find(|(definition, _)| contains_cursor(definition))?;
+    let scope = OrefScope {
+        method_definition,
+        content,
+        class_name,
+        member_type: &member_type,
+    };
+    scope.variable_type(name, cursor)
+}
+
+/// What `infer_oref_type` resolves variables against.
+struct OrefScope<'a> {
+    method_definition: Node<'a>,
+    content: &'a str,
+    class_name: &'a str,
+    member_type: &'a dyn Fn(&str, &str, ChainMember) -> Option<String>,
+}
+
+impl OrefScope<'_> {
+    /// Infers the class of the variable `name` before the byte offset `before`.
+    fn variable_type(&self, name: &str, before: usize) -> Option<String> {
+        if let Some((start, value)) =
+            last_assignment_at(self.method_definition, self.content, name, before)
+        {
+            // variables in the value are followed from before the assignment, so `set p = p.Next`
+            // doesn't recurse forever
+            return self.expression_type(value.trim(), start);
+        }
+        let method_text = self
+            .content
+            .get(self.method_definition.start_byte()..before)?;
+        dim_type(method_text, name)
+            .or_else(|| argument_type(self.method_definition, self.content, name))
+    }
+
+    /// Infers the class of the object `expression` evaluates to at the byte offset `at`.
+    fn expression_type(&self, expression: &str, at: usize) -> Option<String> {
+        let (head, members) = expression_chain(expression)?;
+        let mut members = members.into_iter();
+        let mut class_name = match head {
+            ChainHead::Class(class_name) => {
+                let (method_name, _) = members.next()?;
+                if CONSTRUCTORS.contains(&method_name.to_ascii_lowercase().as_str()) {
+                    class_name.to_string()
+                } else {
+                    (self.member_type)(class_name, method_name, ChainMember::Method)?
+                }
+            }
+            ChainHead::CurrentClass => self.class_name.to_string(),
+            ChainHead::Variable(name) => self.variable_type(name, at)?,
+        };
+        for (member_name, kind) in members {
+            class_name = (self.member_type)(&class_name, member_name, kind)?;
+        }
+        Some(class_name)
+    }
+}
+
+/// Returns the type given to `name` by the last `#dim` line of `text`, e.g.
+/// `#dim order, line As MyApp.Order`.
+pub(crate) fn dim_type(text: &str, name: &str) -> Option<String> {
+    text.lines().rev().find_map(|line| {
+        let line = line.trim_start();
+        let declaration = line
+            .get(..4)
+            .filter(|keyword| keyword.eq_ignore_ascii_case("#dim"))
+            .map(|_| &line[4..])?;
+        let lower = declaration.to_ascii_lowercase();
+        let as_index = lower.find(" as ")?;
+        let declares_name = declaration[..as_index]
+            .split(',')
+            .any(|declared| declared.trim() == name);
+        let type_name = declaration[as_index + 4..].split_whitespace().next()?;
+        declares_name.then(|| type_name.to_string())
+    })
+}
+
+/// Returns the declared type of the argument `name` of `method_definition`.
+fn argument_type(method_definition: Node, content: &str, name: &str) -> Option<String> {
+    let arguments = get_node_children(method_definition)
+        .into_iter()
+        .find(|child| child.kind() == "arguments")?;
+    let argument = get_node_children(arguments).into_iter().find(|argument| {
+        argument_name_node(*argument, content)
+            .and_then(|argument_name| content.get(argument_name.byte_range()))
+            == Some(name)
+    })?;
+    let type_name = get_node_children(argument)
+        .into_iter()
+        .find(|child| child.kind() == "argument_type")?
+        .named_child(1)?;
+    content.get(type_name.byte_range()).map(str::to_string)
+}
@@ -1,4 +1,5 @@
-use crate::parse_structures::{ClassId, MethodRef, PublicMethodRef};
+use crate::interner::Name;
+use crate::parse_structures::{ClassId, MethodHandle, MethodRef, MethodType, PublicMethodRef};
 use std::collections::HashMap;
 
 /// Stores information about what superclass methods get overwritten, and by which subclass.
@@ -7,13 +8,33 @@ use std::collections::HashMap;
 #[derive(Default, Debug)]
 pub struct OverrideIndex {
     /// Stores the Method Id that a class sees for each public method name
-    pub effective_public_methods: HashMap<ClassId, HashMap<String, PublicMethodRef>>,
+    pub effective_public_methods: HashMap<ClassId, HashMap<Name, PublicMethodRef>>,
 
-    /// subclass method ref (the method that overwites the superclass one) -> superclass method ref
-    pub overrides: HashMap<MethodRef, PublicMethodRef>,
+    /// Stores the method each class sees for each method name, public or private (private
+    /// methods are inherited too, and shadow inherited methods of the same name)
+    pub effective_methods: HashMap<ClassId, HashMap<Name, MethodHandle>>,
 
-    /// superclass method ref -> subclass method refs (subclass methods that overwrote the superclass)
-    pub overridden_by: HashMap<PublicMethodRef, Vec<MethodRef>>,
+    /// subclass method ref (the method that overwites the superclass one) -> superclass method
+    pub overrides: HashMap<MethodRef, MethodHandle>,
+
+    /// superclass method -> subclass method refs (subclass methods that overwrote the superclass)
+    pub overridden_by: HashMap<MethodHandle, Vec<MethodRef>>,
+
+    /// Overrides that change how the method is dispatched: a `ClassMethod` overriding an instance
+    /// `Method`, or the other way around.
+    /// subclass method ref -> (superclass method, kind of the subclass method)
+    pub dispatch_mismatches: HashMap<MethodRef, (MethodHandle, MethodType)>,
+
+    /// (class declaring a property, property name) -> its custom `<Prop>Get`/`<Prop>Set` accessor
+    /// methods, declared in that class or its subclasses
+    pub property_accessors: HashMap<(ClassId, String), Vec<MethodRef>>,
+
+    /// accessor method ref -> (class declaring the property, property name)
+    pub accessor_properties: HashMap<MethodRef, (ClassId, String)>,
+
+    /// class inheriting from itself -> the classes of its inheritance cycle, starting with it and
+    /// each followed by its superclass in the cycle. The edge closing a cycle is ignored.
+    pub inheritance_cycles: HashMap<ClassId, Vec<ClassId>>,
 }
 
 impl OverrideIndex {
@@ -23,20 +44,32 @@ impl OverrideIndex {
     pub fn new() -> Self {
         Self {
             effective_public_methods: HashMap::new(),
+            effective_methods: HashMap::new(),
             overrides: HashMap::new(),
             overridden_by: HashMap::new(),
+            dispatch_mismatches: HashMap::new(),
+            property_accessors: HashMap::new(),
+            accessor_properties: HashMap::new(),
+            inheritance_cycles: HashMap::new(),
         }
     }
 
     /// Returns a deep clone of the override index.
     ///
-    /// Clones all internal maps (`effective_public_methods`, `overrides`, `overridden_by`).
+    /// Clones all internal maps (`effective_public_methods`, `effective_methods`, `overrides`,
+    /// `overridden_by`, `dispatch_mismatches`, `property_accessors`, `accessor_properties`,
+    /// `inheritance_cycles`).
     /// Note: this duplicates `Clone` behavior; consider deriving `Clone` on `OverrideIndex` instead.
     pub(crate) fn clone(&self) -> OverrideIndex {
         Self {
             effective_public_methods: self.effective_public_methods.clone(),
+            effective_methods: self.effective_methods.clone(),
             overrides: self.overrides.clone(),
             overridden_by: self.overridden_by.clone(),
+            dispatch_mismatches: self.dispatch_mismatches.clone(),
+            property_accessors: self.property_accessors.clone(),
+            accessor_properties: self.accessor_properties.clone(),
+            inheritance_cycles: self.inheritance_cycles.clone(),
         }
     }
 }
@@ -0,0 +1,319 @@
+use crate::common::ts_range_to_lsp_range;
+use crate::diagnostics::{
+    byte_range, skip_string, skip_string_or_comment, starts_with_ignore_case, Dialect,
+};
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    RenameFile, ResourceOp, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+use tree_sitter::Range;
+
+/// Edits renaming a package (and every package under it) across a workspace.
+#[derive(Clone, Debug, Default)]
+pub struct PackageRename {
+    pub from: String,
+    pub to: String,
+    /// Text edits of each affected document, sorted by uri.
+    pub edits: Vec<(Url, Vec<TextEdit>)>,
+    /// Class files to move, from their old uri to their new one.
+    pub file_renames: Vec<(Url, Url)>,
+    /// Classes of the package whose new name is already taken by a workspace class.
+    pub conflicts: Vec<String>,
+}
+
+impl PackageRename {
+    /// Builds the workspace edit: every text edit first, then the file renames, so the edits
+    /// still apply to the old uris.
+    pub fn to_workspace_edit(&self) -> WorkspaceEdit {
+        let mut operations: Vec<DocumentChangeOperation> = self
+            .edits
+            .iter()
+            .map(|(uri, edits)| {
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: uri.clone(),
+                        version: None,
+                    },
+                    edits: edits.iter().cloned().map(OneOf::Left).collect(),
+                })
+            })
+            .collect();
+        operations.extend(self.file_renames.iter().map(|(old_uri, new_uri)| {
+            DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+                old_uri: old_uri.clone(),
+                new_uri: new_uri.clone(),
+                options: None,
+                annotation_id: None,
+            }))
+        }));
+        WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        }
+    }
+
+    /// Lists the affected files as the dry-run response of `objectscript.renamePackage`.
+    pub fn to_preview_json(&self) -> Value {
+        let mut files: Vec<Value> = self
+            .edits
+            .iter()
+            .map(|(uri, edits)| {
+                let new_uri = self
+                    .file_renames
+                    .iter()
+                    .find(|(old_uri, _)| old_uri == uri)
+                    .map(|(_, new_uri)| new_uri.to_string());
+                json!({ "uri": uri.to_string(), "edits": edits.len(), "newUri": new_uri })
+            })
+            .collect();
+        // a moved file may have nothing to edit if its class was never named in the code
+        for (old_uri, new_uri) in &self.file_renames {
+            if !self.edits.iter().any(|(uri, _)| uri == old_uri) {
+                files.push(json!({
+                    "uri": old_uri.to_string(),
+                    "edits": 0,
+                    "newUri": new_uri.to_string(),
+                }));
+            }
+        }
+        json!({
+            "from": self.from,
+            "to": self.to,
+            "files": files,
+            "conflicts": self.conflicts,
+        })
+    }
+}
+
+/// Returns `true` if `name` is a valid, non-system package name: dot-separated identifiers,
+/// each starting with a letter.
+pub fn is_package_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Returns `true` if `name` is a valid, non-system class name: a package name (see
+/// `is_package_name`) with at least a package and a class part.
+pub fn is_class_name(name: &str) -> bool {
+    name.contains('.') && is_package_name(name)
+}
+
+/// Returns the name `class_name` gets when `from` is renamed to `to`, or `None` if the class
+/// isn't in the package `from` or one of its sub-packages.
+pub fn renamed_class_name(class_name: &str, from: &str, to: &str) -> Option<String> {
+    let rest = class_name.strip_prefix(from)?.strip_prefix('.')?;
+    Some(format!("{to}.{rest}"))
+}
+
+/// Returns the path the class file at `path` moves to when its class `class_name` is renamed to
+/// `new_class_name`.
+///
+/// Files stored in package folders (`src/Legacy/Orders.cls`) move to the folders of the new
+/// package, and files named after the full class name (`src/Legacy.Orders.cls`) are renamed in
+/// place. Files named any other way stay where they are (`None`).
+pub fn renamed_class_path(path: &str, class_name: &str, new_class_name: &str) -> Option<String> {
+    for separator in ['/', '.'] {
+        let suffix = format!("/{}.cls", class_name.replace('.', &separator.to_string()));
+        if let Some(directory) = path.strip_suffix(&suffix) {
+            let new_name = new_class_name.replace('.', &separator.to_string());
+            return Some(format!("{directory}/{new_name}.cls"));
+        }
+    }
+    None
+}
+
+/// Finds the references to the package `package` in `content`: the ranges of the package part
+/// of each class name in it (`Legacy` in `Legacy.Orders`), and bare package names in `Import`
+/// statements.
+///
+/// This is a lexical scan like `routine_references`. Strings and comments are skipped, and so is
+/// embedded SQL, where `Legacy.Orders` is a table in the `Legacy` schema rather than a class.
+/// Names following `^` (globals, e.g. storage locations), `$`, or `.` (members) aren't class
+/// names.
+pub fn package_references(content: &str, package: &str) -> Vec<Range> {
+    let bytes = content.as_bytes();
+    let mut references = Vec::new();
+    scan_code(content, |i| {
+        if !bytes[i..].starts_with(package.as_bytes()) || !is_name_start(bytes, i) {
+            return None;
+        }
+        let end = i + package.len();
+        let qualifies_class = bytes.get(end) == Some(&b'.')
+            && bytes.get(end + 1).is_some_and(|b| b.is_ascii_alphabetic());
+        let is_import = !bytes
+            .get(end)
+            .is_some_and(|&b| is_name_byte(b) || b == b'.')
+            && is_import_line(content, i);
+        if qualifies_class || is_import {
+            references.push(byte_range(content, i, end));
+        }
+        Some(end)
+    });
+    references
+}
+
+/// Finds the references to the class `class_name` in `content`: its full name, and, when
+/// `short_names` is set (the class's package is the document's own or imported), its name
+/// without the package where only a class can be named: after `##class(`, `As` or `Of`, and in
+/// the `Extends` list of the class header.
+///
+/// Like `package_references`, this is a lexical scan skipping strings, comments and embedded
+/// SQL.
+pub fn class_references(content: &str, class_name: &str, short_names: bool) -> Vec<Range> {
+    let bytes = content.as_bytes();
+    let short_name = class_name.rsplit('.').next().unwrap_or(class_name);
+    let mut references = Vec::new();
+    scan_code(content, |i| {
+        if !is_name_start(bytes, i) {
+            return None;
+        }
+        let end = if bytes[i..].starts_with(class_name.as_bytes()) {
+            i + class_name.len()
+        } else if short_names
+            && bytes[i..].starts_with(short_name.as_bytes())
+            && is_class_position(content, i)
+        {
+            i + short_name.len()
+        } else {
+            return None;
+        };
+        if bytes
+            .get(end)
+            .is_some_and(|&b| is_name_byte(b) || b == b'.')
+        {
+            return None;
+        }
+        references.push(byte_range(content, i, end));
+        Some(end)
+    });
+    references
+}
+
+/// Returns `true` if `content` has an `Import` statement naming `package`.
+pub fn imports_package(content: &str, package: &str) -> bool {
+    package_references(content, package)
+        .iter()
+        .any(|range| is_import_line(content, range.start_byte))
+}
+
+/// Builds the edits renaming the class references (see `class_references`) of `content` from
+/// `from` to `to`. Names without a package are replaced by the new name without its package if
+/// the class stays in its package, and by the full new name otherwise.
+pub fn class_rename_edits(content: &str, from: &str, to: &str, short_names: bool) -> Vec<TextEdit> {
+    let package = |name: &str| {
+        name.rsplit_once('.')
+            .map(|(package, _)| package.to_string())
+    };
+    let short_to = if package(from) == package(to) {
+        to.rsplit('.').next().unwrap_or(to)
+    } else {
+        to
+    };
+    class_references(content, from, short_names)
+        .into_iter()
+        .map(|range| TextEdit {
+            range: ts_range_to_lsp_range(content, range),
+            new_text: if range.end_byte - range.start_byte == from.len() {
+                to.to_string()
+            } else {
+                short_to.to_string()
+            },
+        })
+        .collect()
+}
+
+/// Builds the edits renaming the package references (see `package_references`) of `content`.
+pub fn package_rename_edits(content: &str, from: &str, to: &str) -> Vec<TextEdit> {
+    package_references(content, from)
+        .into_iter()
+        .map(|range| TextEdit {
+            range: ts_range_to_lsp_range(content, range),
+            new_text: to.to_string(),
+        })
+        .collect()
+}
+
+/// Returns the byte offset just past the `)` closing the `(` at `open`, skipping strings;
+/// unclosed parentheses end at the end of `content`.
+fn skip_parentheses(content: &str, open: usize) -> usize {
+    let bytes = content.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                i = skip_string(content, i, quote);
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    content.len()
+}
+
+/// Returns `true` if the line containing byte `i` is an `Import` statement.
+fn is_import_line(content: &str, i: usize) -> bool {
+    let line_start = content[..i].rfind('\n').map_or(0, |n| n + 1);
+    let line = content[line_start..i].trim_start();
+    line.get(..6)
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("Import"))
+        && line[6..].starts_with(|c: char| c.is_whitespace() || c == '(')
+}
+
+/// Calls `visit` with the offset of every byte of `content` that is code, skipping strings,
+/// comments and embedded SQL. `visit` returns the offset to resume from after a match, or
+/// `None` to move to the next byte.
+fn scan_code(content: &str, mut visit: impl FnMut(usize) -> Option<usize>) {
+    let bytes = content.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(next) = skip_string_or_comment(content, i, Dialect::ObjectScript) {
+            i = next;
+            continue;
+        }
+        if starts_with_ignore_case(content, i, "&sql(") {
+            i = skip_parentheses(content, i + 4);
+            continue;
+        }
+        i = visit(i).unwrap_or(i + 1);
+    }
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'%'
+}
+
+/// Returns `true` if a name may start at byte `i`: names following `^` (globals, e.g. storage
+/// locations), `$`, or `.` (members) aren't class or package names.
+fn is_name_start(bytes: &[u8], i: usize) -> bool {
+    i == 0 || !(is_name_byte(bytes[i - 1]) || b"^$.".contains(&bytes[i - 1]))
+}
+
+/// Returns `true` if byte `i` is where only a class name can be written: after `##class(`,
+/// `As` or `Of`, or in the `Extends` list of a class header.
+fn is_class_position(content: &str, i: usize) -> bool {
+    let line_start = content[..i].rfind('\n').map_or(0, |n| n + 1);
+    let before = content[line_start..i].to_ascii_lowercase();
+    let trimmed = before.trim_end();
+    let ends_with_word = |word: &str| {
+        trimmed
+            .strip_suffix(word)
+            .is_some_and(|rest| rest.ends_with(char::is_whitespace))
+    };
+    trimmed.ends_with("##class(")
+        || ends_with_word("as")
+        || ends_with_word("of")
+        || (before.trim_start().starts_with("class ") && before.contains(" extends"))
+}
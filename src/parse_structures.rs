@@ -1,10 +1,26 @@
+use crate::arena::ArenaId;
+use crate::diagnostics::MemberKind;
 use std::collections::HashMap;
 use std::hash::Hash;
 use tree_sitter::Range;
 
-/// Stores the Index into `GlobalSemanticModel::classes`.
+/// Stores the slot index and generation of a class in the `GlobalSemanticModel::classes` arena.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct ClassId(pub usize);
+pub struct ClassId(pub usize, pub u32);
+
+impl ArenaId for ClassId {
+    fn from_parts(index: usize, generation: u32) -> Self {
+        ClassId(index, generation)
+    }
+
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn generation(self) -> u32 {
+        self.1
+    }
+}
 
 /// Stores the Index into the per-class public method vec in `GlobalSemanticModel::methods::ClassId`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -22,15 +38,40 @@ pub struct PublicVarId(pub usize);
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct PrivateVarId(pub usize);
 
+/// Stores the Index into `LocalSemanticModel::properties`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct PropertyId(pub usize);
 
+/// Stores the Index into `LocalSemanticModel::parameters`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct ParameterId(pub usize);
 
-/// Index into `GlobalSemanticModel::private`, the vec that holds all local semantic models in a workspace.
+/// Stores the Index into `LocalSemanticModel::xdata_blocks`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct XDataId(pub usize);
+
+/// Stores the Index into `LocalSemanticModel::member_definitions`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MemberDefinitionId(pub usize);
+
+/// Slot index and generation in `GlobalSemanticModel::private`, the arena that holds all local
+/// semantic models in a workspace.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct LocalSemanticModelId(pub usize);
+pub struct LocalSemanticModelId(pub usize, pub u32);
+
+impl ArenaId for LocalSemanticModelId {
+    fn from_parts(index: usize, generation: u32) -> Self {
+        LocalSemanticModelId(index, generation)
+    }
+
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn generation(self) -> u32 {
+        self.1
+    }
+}
 
 /// Key used to identify a method by type and name (and later, signature).
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -67,7 +108,56 @@ pub struct PublicMethodRef {
     pub id: PublicMethodId,
 }
 
-// TODO: UNIMPLEMENTED: foreignkey, relationships, storage, query, index, trigger, xdata, projection
+/// Handle to a method implementation that can be overridden: a public method of the global
+/// semantic model, or a private method of its class's `LocalSemanticModel`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MethodHandle {
+    Public(PublicMethodRef),
+    Local { class: ClassId, id: PrivateMethodId },
+}
+
+impl MethodHandle {
+    /// Converts a `MethodRef`, returning `None` if it has neither a public nor a private id.
+    pub fn from_method_ref(method_ref: MethodRef) -> Option<Self> {
+        match (method_ref.pub_id, method_ref.priv_id) {
+            (Some(id), _) => Some(MethodHandle::Public(PublicMethodRef {
+                class: method_ref.class,
+                id,
+            })),
+            (None, Some(id)) => Some(MethodHandle::Local {
+                class: method_ref.class,
+                id,
+            }),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the class the method is declared in.
+    pub fn class(&self) -> ClassId {
+        match self {
+            MethodHandle::Public(method_ref) => method_ref.class,
+            MethodHandle::Local { class, .. } => *class,
+        }
+    }
+
+    /// Converts the handle back into a `MethodRef`.
+    pub fn to_method_ref(self) -> MethodRef {
+        match self {
+            MethodHandle::Public(method_ref) => MethodRef {
+                class: method_ref.class,
+                pub_id: Some(method_ref.id),
+                priv_id: None,
+            },
+            MethodHandle::Local { class, id } => MethodRef {
+                class,
+                pub_id: None,
+                priv_id: Some(id),
+            },
+        }
+    }
+}
+
+// TODO: UNIMPLEMENTED: relationships
 /// Semantic representation of a parsed ObjectScript class.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Class {
@@ -75,19 +165,30 @@ pub struct Class {
     pub name: String,
     /// Imported classes referenced by this class.
     pub imports: Vec<ClassId>, // list of class names
-    // format: Include (macro file name) ex: include hannah for macro file hannah.inc
-    // pub include: Vec<String>, // include files are inherited by subclasses, include files bring in macros at compile time
-    // pub include_gen: Vec<String>, // this specifies include files to be generated
+    /// Include files named by the `Include` statements, as written (e.g. `hannah` for
+    /// `hannah.inc`). Subclasses inherit them along with their macros.
+    pub includes: Vec<String>,
+    /// Include files named by the `IncludeGenerator` statements, as written, whose macros are
+    /// available to method generators.
+    pub include_generators: Vec<String>,
     // if inheritance keyword == left, leftmost supersedes all (default)
     // if inheritancedirection == right, right supersedes
     /// Direct parent classes in the `Extends` list.
     pub inherited_classes: Vec<ClassId>,
+    /// Names in the `Extends` list as written, including classes outside the workspace.
+    pub superclass_names: Vec<String>,
     /// Inheritance conflict resolution direction (`left`, or `right`, default is `left`).
     pub inheritance_direction: String,
     /// Optional ProcedureBlock default for this class; If defined, methods will inherit this keyword if they don't specify it themselves.
     pub is_procedure_block: Option<bool>,
     /// Optional default Language keyword for this class.
     pub default_language: Option<Language>,
+    /// `ProcedureBlock` and `Language` as declared by the class itself, before
+    /// `class_keyword_inheritance` filled in the inherited values.
+    pub declared_keywords: (Option<bool>, Option<Language>),
+    /// Value of the `SqlTableName` keyword: the name of the SQL table the class projects, when
+    /// it isn't the short class name.
+    pub sql_table_name: Option<String>,
     /// Stores method name -> id for each private method in this class.
     pub private_methods: HashMap<String, PrivateMethodId>,
     /// Stores method name -> id for each public method in this class.
@@ -98,8 +199,17 @@ pub struct Class {
     pub public_properties: HashMap<String, PropertyId>,
     /// Stores parameter name -> id for each parameter in this class.
     pub parameters: HashMap<String, ParameterId>,
+    /// Stores XData block name -> id for each XData block in this class.
+    pub xdata_blocks: HashMap<String, XDataId>,
+    /// Stores (kind, name) -> id for each index, foreign key, trigger, query, projection, and
+    /// storage definition in this class.
+    pub member_definitions: HashMap<(MemberKind, String), MemberDefinitionId>,
     /// Stores all method calls to external classes for this class.
     pub method_calls: Vec<MethodCallSite>,
+    /// Whether this class declares the `GeneratedBy` keyword (it is generated by another class).
+    pub generated_by: bool,
+    /// The `///` description comment above the class definition.
+    pub description: Option<String>,
     /// Whether this class entry is considered live/usable (e.g., false after removal).
     pub active: bool,
 }
@@ -113,12 +223,27 @@ pub enum Language {
     ISpl,
 }
 
+impl Language {
+    /// The value of the `Language` keyword selecting this language.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Language::Objectscript => "objectscript",
+            Language::TSql => "tsql",
+            Language::Python => "python",
+            Language::ISpl => "ispl",
+        }
+    }
+}
+
 /// Semantic representation of a class property declaration.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ClassProperty {
     pub name: String,
     pub property_type: Option<String>,
     pub is_public: bool,
+    /// Value of the `SqlFieldName` keyword: the name of the SQL column the property projects,
+    /// when it isn't the property name.
+    pub sql_field_name: Option<String>,
     pub range: Range,
 }
 
@@ -131,6 +256,39 @@ pub struct ClassParameter {
     pub range: Range,
 }
 
+/// Semantic representation of an `XData Name [ MimeType = ... ] { ... }` block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassXData {
+    pub name: String,
+    /// Value of the `MimeType` keyword, e.g. `application/json`; XML when absent.
+    pub mime_type: Option<String>,
+    /// Range of the block name.
+    pub range: Range,
+    /// Range of the raw content between the braces of the block.
+    pub content_range: Range,
+}
+
+/// A class member tracked by name only: an `Index`, `ForeignKey`, `Trigger`, `Query`,
+/// `Projection`, or `Storage` definition.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassMemberDefinition {
+    pub name: String,
+    pub kind: MemberKind,
+    /// Range of the member name.
+    pub range: Range,
+}
+
+/// The members parsed from a class body by `Class::initial_build`, before they are given ids.
+#[derive(Clone, Debug, Default)]
+pub struct ClassStatements {
+    /// Methods, each with the range of its definition.
+    pub methods: Vec<(Method, Range)>,
+    pub properties: Vec<ClassProperty>,
+    pub parameters: Vec<ClassParameter>,
+    pub xdata_blocks: Vec<ClassXData>,
+    pub member_definitions: Vec<ClassMemberDefinition>,
+}
+
 /// Distinguishes instance methods from class methods.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum MethodType {
@@ -138,6 +296,43 @@ pub enum MethodType {
     ClassMethod,
 }
 
+/// `Internal` and `ServerOnly` keywords of a class member, which mark it as not meant for API
+/// consumers: `Internal` members are left out of the class reference, and `ServerOnly` methods
+/// aren't projected to clients (Java, .NET, ...).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemberVisibility {
+    pub internal: bool,
+    pub server_only: bool,
+}
+
+impl MemberVisibility {
+    /// Returns the keywords that are set, as written in class definitions.
+    pub fn labels(&self) -> Vec<&'static str> {
+        let mut labels = Vec::new();
+        if self.internal {
+            labels.push("Internal");
+        }
+        if self.server_only {
+            labels.push("ServerOnly");
+        }
+        labels
+    }
+
+    /// Describes the keywords that are set for the hover of a member of `class_name`.
+    pub fn hover_note(&self, class_name: &str) -> Option<String> {
+        let mut notes = Vec::new();
+        if self.internal {
+            notes.push(format!(
+                "**Internal**: not meant for use outside `{class_name}`"
+            ));
+        }
+        if self.server_only {
+            notes.push("**ServerOnly**: not projected to client languages".to_string());
+        }
+        (!notes.is_empty()).then(|| notes.join("\n\n"))
+    }
+}
+
 /// Semantic Representation of an ObjectScript Method.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Method {
@@ -157,10 +352,21 @@ pub struct Method {
     pub is_procedure_block: Option<bool>,
     /// Stores language of method. If None, method defaults to ObjectScript.
     pub language: Option<Language>,
+    /// `ProcedureBlock` and `Language` as declared by the method itself, before
+    /// `update_keywords` applied the class keywords.
+    pub declared_keywords: (Option<bool>, Option<Language>),
     /// Stores CodeMode of method. If None, method defaults to Code.
     pub code_mode: CodeMode,
     /// Names declared in `PublicList(...)` of ProcedureBlocks.
     pub public_variables_declared: Vec<String>,
+    /// Names of the formal arguments, in declaration order.
+    pub arguments: Vec<String>,
+    /// Formal arguments as declared (e.g. `ByRef count As %Integer = 0`), in declaration order.
+    pub argument_declarations: Vec<String>,
+    /// Return type as written in the `As` clause, e.g. `%Status`.
+    pub return_type_name: Option<String>,
+    /// `Internal` and `ServerOnly` keywords.
+    pub visibility: MemberVisibility,
 }
 
 /// CodeMode keyword values supported for methods.
@@ -219,6 +425,19 @@ pub struct Variable {
     pub var_type: Vec<VarType>,
     /// Whether variable is public or not.
     pub is_public: bool,
+    /// How the argument is passed, if the variable originated from a method argument.
+    pub argument_mode: Option<ArgumentMode>,
+}
+
+/// How a method argument is passed, from its `ByRef` or `Output` modifier.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ArgumentMode {
+    /// No modifier: the argument is passed by value (unless the caller uses `.arg`).
+    ByValue,
+    /// `ByRef`: the caller passes the variable by reference, so changes are visible to it.
+    ByRef,
+    /// `Output`: passed by reference, to return a value; its value on entry isn't used.
+    Output,
 }
 
 /// Normalized return/type categories recognized.
@@ -0,0 +1,190 @@
+use crate::common::{
+    braced_content, method_definition_nodes, start_of_function, successful_exit,
+    ts_range_to_lsp_range,
+};
+use crate::diagnostics::byte_range;
+use crate::method::initial_build_method;
+use crate::parse_structures::Language;
+use tower_lsp::lsp_types::{DocumentSymbol, SymbolKind};
+use tree_sitter::{Range, Tree};
+
+/// The body of a method written in Python, which the ObjectScript analyses skip.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PythonBody {
+    pub method_name: String,
+    /// Range of the text between the method's braces.
+    pub range: Range,
+    pub identifiers: Vec<PythonIdentifier>,
+}
+
+/// A name defined at the top level of a Python method body: a function, a class, an imported
+/// module or name, or an assigned variable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PythonIdentifier {
+    pub name: String,
+    /// `FUNCTION`, `CLASS`, `MODULE`, or `VARIABLE`.
+    pub kind: SymbolKind,
+    /// Range of the name.
+    pub range: Range,
+    /// The trimmed line defining the name, e.g. `def total(rows):`.
+    pub definition: String,
+}
+
+/// Finds the bodies of the methods of `tree` whose language is Python: their own `Language`
+/// keyword, or else `class_language`, the class-wide one.
+pub fn python_bodies(
+    content: &str,
+    tree: &Tree,
+    class_language: Option<&Language>,
+) -> Vec<PythonBody> {
+    start_of_function("PYTHON (no struct)", "python_bodies");
+    let mut bodies = Vec::new();
+    for (method_definition, method_type) in method_definition_nodes(tree.root_node()) {
+        let Some((method, _)) = initial_build_method(method_definition, method_type, content)
+        else {
+            continue;
+        };
+        if method.language.as_ref().or(class_language) != Some(&Language::Python) {
+            continue;
+        }
+        let start = method_definition.start_byte();
+        let Some((body_start, body_end)) = content
+            .get(method_definition.byte_range())
+            .and_then(braced_content)
+        else {
+            continue;
+        };
+        bodies.push(PythonBody {
+            method_name: method.name,
+            range: byte_range(content, start + body_start, start + body_end),
+            identifiers: python_identifiers(content, start + body_start..start + body_end),
+        });
+    }
+    successful_exit("PYTHON (no struct)", "python_bodies");
+    bodies
+}
+
+/// Finds the names defined at the top level of the Python code at `content[bytes]`: the lines
+/// at the body's least indentation that are `def`, `class`, `import`, `from ... import`, or an
+/// assignment.
+///
+/// This is a lexical scan, like `routine_labels`; nested definitions and names bound in other
+/// ways (`for`, `with`, ...) are left out.
+pub fn python_identifiers(content: &str, bytes: std::ops::Range<usize>) -> Vec<PythonIdentifier> {
+    let Some(body) = content.get(bytes.clone()) else {
+        return Vec::new();
+    };
+    let indentation = |line: &str| line.len() - line.trim_start().len();
+    let top_level = body
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(indentation)
+        .min()
+        .unwrap_or(0);
+    let mut identifiers = Vec::new();
+    let mut line_start = bytes.start;
+    for line in body.split_inclusive('\n') {
+        let start = line_start;
+        line_start += line.len();
+        let code = line.trim_end();
+        if code.trim().is_empty() || indentation(code) != top_level {
+            continue;
+        }
+        let statement = code.trim_start();
+        let statement_start = start + indentation(code);
+        let mut push = |offset: usize, name: &str, kind: SymbolKind| {
+            let name_start = statement_start + offset;
+            identifiers.push(PythonIdentifier {
+                name: name.to_string(),
+                kind,
+                range: byte_range(content, name_start, name_start + name.len()),
+                definition: statement.to_string(),
+            });
+        };
+        let keyword = statement.split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "def" | "class" => {
+                let rest = &statement[keyword.len()..];
+                let offset = keyword.len() + rest.len() - rest.trim_start().len();
+                let name = python_name(&statement[offset..]);
+                let kind = if keyword == "def" {
+                    SymbolKind::FUNCTION
+                } else {
+                    SymbolKind::CLASS
+                };
+                if !name.is_empty() {
+                    push(offset, name, kind);
+                }
+            }
+            "import" | "from" => {
+                // the names bound are those after `import`, or their `as` aliases
+                let Some(import) = statement
+                    .find(" import ")
+                    .map(|n| n + " import ".len())
+                    .or_else(|| (keyword == "import").then_some("import ".len()))
+                else {
+                    continue;
+                };
+                let mut offset = import;
+                for part in statement[import..].split(',') {
+                    let trimmed = part.trim_start_matches([' ', '(']);
+                    let name_offset = offset + part.len() - trimmed.len();
+                    offset += part.len() + 1;
+                    let bound = match trimmed.find(" as ") {
+                        Some(alias) => {
+                            let alias_start = alias + 4;
+                            (
+                                name_offset + alias_start,
+                                python_name(&trimmed[alias_start..]),
+                            )
+                        }
+                        // `import a.b` binds `a`
+                        None => (name_offset, python_name(trimmed)),
+                    };
+                    if !bound.1.is_empty() {
+                        push(bound.0, bound.1, SymbolKind::MODULE);
+                    }
+                }
+            }
+            _ => {
+                let name = python_name(statement);
+                let after_name = statement[name.len()..].trim_start();
+                let is_assignment = (after_name.starts_with('=') && !after_name.starts_with("=="))
+                    || (after_name.starts_with(':') && after_name.contains('='));
+                if !name.is_empty() && is_assignment {
+                    push(0, name, SymbolKind::VARIABLE);
+                }
+            }
+        }
+    }
+    identifiers
+}
+
+/// Returns the Python identifier `text` starts with, possibly empty.
+fn python_name(text: &str) -> &str {
+    let length = text
+        .char_indices()
+        .take_while(|&(n, c)| c == '_' || c.is_alphabetic() || (n > 0 && c.is_numeric()))
+        .map(|(n, c)| n + c.len_utf8())
+        .last()
+        .unwrap_or(0);
+    &text[..length]
+}
+
+/// Builds the outline children of a Python method body: its top-level names, in source order.
+#[allow(deprecated)]
+pub fn python_symbols(content: &str, body: &PythonBody) -> Vec<DocumentSymbol> {
+    body.identifiers
+        .iter()
+        .map(|identifier| DocumentSymbol {
+            name: identifier.name.clone(),
+            detail: Some(identifier.definition.clone()),
+            kind: identifier.kind,
+            tags: None,
+            deprecated: None,
+            range: ts_range_to_lsp_range(content, identifier.range),
+            selection_range: ts_range_to_lsp_range(content, identifier.range),
+            children: None,
+        })
+        .collect()
+}
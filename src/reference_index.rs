@@ -0,0 +1,98 @@
+use crate::common::{start_of_function, successful_exit};
+use crate::parse_structures::{ClassId, MethodCallSite, PublicMethodRef};
+use std::collections::HashMap;
+use tree_sitter::Range;
+
+/// A single resolved reference (call edge) from a caller method to a public method.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferenceEdge {
+    /// Class containing the call.
+    pub caller_class: ClassId,
+    /// Name of the method containing the call.
+    pub caller_method: String,
+    /// Range covering the method call.
+    pub call_range: Range,
+}
+
+/// Stores, for each public method, the call sites that reference it.
+///
+/// The index is maintained incrementally: whenever the call sites of a class are rebuilt or
+/// re-resolved, only the edges contributed by that class are replaced. This keeps reference
+/// queries and reference counts O(answer) instead of scanning every class' `method_calls`.
+#[derive(Clone, Default, Debug)]
+pub struct ReferenceIndex {
+    /// callee method ref -> call edges that resolve to it.
+    pub incoming: HashMap<PublicMethodRef, Vec<ReferenceEdge>>,
+    /// caller class -> callee method refs that class currently contributes edges to.
+    pub outgoing: HashMap<ClassId, Vec<PublicMethodRef>>,
+}
+
+impl ReferenceIndex {
+    /// Creates an empty `ReferenceIndex`.
+    pub fn new() -> Self {
+        Self {
+            incoming: HashMap::new(),
+            outgoing: HashMap::new(),
+        }
+    }
+
+    /// Removes every edge contributed by `caller_class`.
+    ///
+    /// Only the callee entries listed in `outgoing[caller_class]` are touched.
+    pub fn remove_class(&mut self, caller_class: ClassId) {
+        let Some(callees) = self.outgoing.remove(&caller_class) else {
+            return;
+        };
+        for callee in callees {
+            let Some(edges) = self.incoming.get_mut(&callee) else {
+                continue;
+            };
+            edges.retain(|edge| edge.caller_class != caller_class);
+            if edges.is_empty() {
+                self.incoming.remove(&callee);
+            }
+        }
+    }
+
+    /// Replaces the edges contributed by `caller_class` with the resolved entries of `call_sites`.
+    ///
+    /// Call sites whose `callee_symbol` is `None` are not recorded.
+    pub fn update_class(&mut self, caller_class: ClassId, call_sites: &[MethodCallSite]) {
+        start_of_function("ReferenceIndex", "update_class");
+        self.remove_class(caller_class);
+        let mut callees = Vec::new();
+        for site in call_sites {
+            let Some(callee) = site.callee_symbol else {
+                continue;
+            };
+            self.incoming
+                .entry(callee)
+                .or_default()
+                .push(ReferenceEdge {
+                    caller_class,
+                    caller_method: site.caller_method.clone(),
+                    call_range: site.call_range,
+                });
+            if !callees.contains(&callee) {
+                callees.push(callee);
+            }
+        }
+        if !callees.is_empty() {
+            self.outgoing.insert(caller_class, callees);
+        }
+        successful_exit("ReferenceIndex", "update_class");
+    }
+
+    /// Returns every recorded call edge that resolves to `callee`.
+    pub fn references(&self, callee: &PublicMethodRef) -> &[ReferenceEdge] {
+        self.incoming
+            .get(callee)
+            .map(|edges| edges.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns the number of call sites that resolve to `callee`.
+    pub fn reference_count(&self, callee: &PublicMethodRef) -> usize {
+        self.references(callee).len()
+    }
+}
@@ -0,0 +1,102 @@
+use parking_lot::Mutex;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tower_lsp::lsp_types::{Position, Url};
+
+/// Most responses kept; the cache is emptied when it grows past this.
+const MAX_ENTRIES: usize = 4096;
+
+/// Identifies a request whose response depends only on the project state.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ResponseKey {
+    /// LSP method, e.g. `textDocument/hover`.
+    method: &'static str,
+    uri: Url,
+    /// Version of the document the response was computed for.
+    version: Option<i32>,
+    /// `(line, character)` of the request, for position-based requests.
+    position: Option<(u32, u32)>,
+}
+
+impl ResponseKey {
+    pub fn new(
+        method: &'static str,
+        uri: &Url,
+        version: Option<i32>,
+        position: Option<Position>,
+    ) -> Self {
+        Self {
+            method,
+            uri: uri.clone(),
+            version,
+            position: position.map(|position| (position.line, position.character)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// Bumped by every `clear`, so responses computed before it aren't stored after it.
+    generation: u64,
+    entries: HashMap<ResponseKey, Arc<dyn Any + Send + Sync>>,
+}
+
+/// Responses of pure requests (hover, definition, document symbols, folding ranges), so clients
+/// re-requesting them while nothing changed (e.g. when scrolling) don't redo the tree walks.
+///
+/// Responses are keyed by document version, and the whole cache is cleared by every change to
+/// the project (see `ProjectState::data_mut`), since editing one class can change the responses
+/// in another.
+#[derive(Default)]
+pub struct ResponseCache {
+    state: Mutex<CacheState>,
+}
+
+impl Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("ResponseCache")
+            .field("generation", &state.generation)
+            .field("entries", &state.entries.len())
+            .finish()
+    }
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current generation, to pass to `insert` once the response is computed.
+    pub fn generation(&self) -> u64 {
+        self.state.lock().generation
+    }
+
+    /// Returns the cached response of `key`, if any.
+    pub fn get<T: Clone + 'static>(&self, key: &ResponseKey) -> Option<T> {
+        let state = self.state.lock();
+        state.entries.get(key)?.downcast_ref::<T>().cloned()
+    }
+
+    /// Caches `response`, computed while the cache was at `generation`; it is dropped if the
+    /// project changed since.
+    pub fn insert<T: Send + Sync + 'static>(&self, key: ResponseKey, generation: u64, response: T) {
+        let mut state = self.state.lock();
+        if state.generation != generation {
+            return;
+        }
+        if state.entries.len() >= MAX_ENTRIES {
+            state.entries.clear();
+        }
+        state.entries.insert(key, Arc::new(response));
+    }
+
+    /// Drops every cached response.
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.generation += 1;
+        state.entries.clear();
+    }
+}
@@ -0,0 +1,169 @@
+use crate::common::{ts_range_to_lsp_range, word_at_offset};
+use crate::diagnostics::byte_range;
+use crate::macros::{include_macros, IncludeMacros};
+use tower_lsp::lsp_types::{DocumentSymbol, SymbolKind};
+use tree_sitter::Range;
+
+/// A label (tag) of a `.mac` routine: a name starting a line, optionally with a formal
+/// parameter list, e.g. `Run(count, verbose=0)`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoutineLabel {
+    pub name: String,
+    /// Names of the formal parameters, without default values.
+    pub parameters: Vec<String>,
+    /// Range of the label name.
+    pub range: Range,
+    /// Range of the label and the lines up to the next label.
+    pub declaration_range: Range,
+}
+
+impl RoutineLabel {
+    /// Renders the label as it is called, e.g. `Run(count,verbose)`.
+    pub fn signature(&self) -> String {
+        if self.parameters.is_empty() {
+            return self.name.clone();
+        }
+        format!("{}({})", self.name, self.parameters.join(","))
+    }
+}
+
+/// The labels and macros of a `.mac` routine, as stored in `ProjectData::routines`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoutineModel {
+    /// Dotted path of the routine file (see `routine_path_name`).
+    pub name: String,
+    pub content: String,
+    pub labels: Vec<RoutineLabel>,
+    /// The macros the routine defines and the include files it `#include`s.
+    pub macros: IncludeMacros,
+}
+
+impl RoutineModel {
+    /// Scans `content` for the labels and macros of the routine at the dotted path `name`.
+    pub fn new(name: String, content: String) -> Self {
+        let labels = routine_labels(&content);
+        let macros = include_macros(&content);
+        Self {
+            name,
+            content,
+            labels,
+            macros,
+        }
+    }
+
+    /// Returns the label called `name`; labels are case-sensitive.
+    pub fn get_label(&self, name: &str) -> Option<&RoutineLabel> {
+        self.labels.iter().find(|label| label.name == name)
+    }
+}
+
+/// Finds the labels of a routine: the names starting a line, skipping the `ROUTINE` header line
+/// of UDL files, comment lines, and preprocessor lines.
+///
+/// This is a lexical scan, like `include_macros`; code lines are indented, so anything else
+/// starting a line is a label.
+pub fn routine_labels(content: &str) -> Vec<RoutineLabel> {
+    // (start of the line, length of the name, parameters) of each label
+    let mut found: Vec<(usize, usize, Vec<String>)> = Vec::new();
+    let mut line_start = 0;
+    for (line_number, line) in content.split_inclusive('\n').enumerate() {
+        let start = line_start;
+        line_start += line.len();
+        let is_header = line_number == 0
+            && line
+                .get(..8)
+                .is_some_and(|word| word.eq_ignore_ascii_case("ROUTINE "));
+        let name_length = line
+            .char_indices()
+            .take_while(|&(n, c)| c.is_ascii_alphanumeric() || (n == 0 && c == '%'))
+            .count();
+        if is_header || name_length == 0 {
+            continue;
+        }
+        let after_name = &line[name_length..];
+        let parameters = match after_name.find(')') {
+            Some(end) if after_name.starts_with('(') => after_name[1..end]
+                .split(',')
+                .filter_map(|parameter| parameter.split('=').next())
+                .map(|parameter| parameter.trim().to_string())
+                .filter(|parameter| !parameter.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        };
+        found.push((start, name_length, parameters));
+    }
+    let mut labels = Vec::new();
+    for (n, (start, name_length, parameters)) in found.iter().enumerate() {
+        // each label runs up to the next one, without the line break before it
+        let next = found.get(n + 1).map_or(content.len(), |(next, _, _)| *next);
+        let end = content[..next].trim_end_matches(['\r', '\n']).len();
+        labels.push(RoutineLabel {
+            name: content[*start..start + name_length].to_string(),
+            parameters: parameters.clone(),
+            range: byte_range(content, *start, start + name_length),
+            declaration_range: byte_range(content, *start, end.max(start + name_length)),
+        });
+    }
+    labels
+}
+
+/// A call of a routine label found by `label_reference_at`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LabelReference {
+    /// The label called, `None` for `^Routine`.
+    pub label: Option<String>,
+    /// The routine called (without the `^`), `None` for a label of the same routine.
+    pub routine: Option<String>,
+    /// Byte range of the whole call, e.g. `Run^MyApp.Jobs`.
+    pub range: std::ops::Range<usize>,
+}
+
+/// Finds the label call at byte `offset` of `content`: `Label`, `Label^Routine`, or `^Routine`,
+/// where the cursor may be on the label or on the routine name. `None` if the cursor isn't on
+/// a name.
+///
+/// This is lexical; whether `^Name` calls a routine or is a global is left to the caller (see
+/// `routine_references`).
+pub fn label_reference_at(content: &str, offset: usize) -> Option<LabelReference> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '%' || c == '.' || c == '^';
+    let std::ops::Range { start, end } = word_at_offset(content, offset, is_name);
+    let text = &content[start..end];
+    let reference = text.trim_start_matches('.');
+    let non_empty = |name: &str| (!name.is_empty()).then(|| name.to_string());
+    let (label, routine) = match reference.split_once('^') {
+        Some((label, routine)) => (non_empty(label), non_empty(routine)),
+        None => (non_empty(reference), None),
+    };
+    if label.is_none() && routine.is_none() {
+        return None;
+    }
+    if label.as_deref().is_some_and(|label| label.contains('.')) {
+        // labels don't contain dots, so this is e.g. a class or a property chain
+        return None;
+    }
+    Some(LabelReference {
+        label,
+        routine,
+        range: start + text.len() - reference.len()..end,
+    })
+}
+
+/// Builds the outline of a routine: its labels, in source order, with their parameters as
+/// detail.
+#[allow(deprecated)]
+pub fn routine_symbols(routine: &RoutineModel) -> Vec<DocumentSymbol> {
+    routine
+        .labels
+        .iter()
+        .map(|label| DocumentSymbol {
+            name: label.name.clone(),
+            detail: (!label.parameters.is_empty()).then(|| label.signature()),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range: ts_range_to_lsp_range(&routine.content, label.declaration_range),
+            selection_range: ts_range_to_lsp_range(&routine.content, label.range),
+            children: None,
+        })
+        .collect()
+}
@@ -0,0 +1,255 @@
+use crate::commands::RULE_DOCS;
+use crate::diagnostics::{
+    DEAD_STORE_CODE, DEBUG_WRITE_CODE, UNREACHABLE_CODE, UNUSED_VARIABLE_CODE,
+};
+use tower_lsp::lsp_types::{CodeDescription, NumberOrString, Url};
+
+/// Documentation of one lint rule, rendered by the `objectscript.ruleDocs` command.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// Stable rule ID, used as `Diagnostic::code`.
+    pub id: &'static str,
+    pub title: &'static str,
+    /// Why the rule exists and how to fix what it reports.
+    pub explanation: &'static str,
+    /// ObjectScript snippet showing code the rule reports.
+    pub example: &'static str,
+}
+
+impl Rule {
+    /// Renders the rule documentation as Markdown.
+    pub fn to_markdown(&self) -> String {
+        format!(
+            "# {} (`{}`)\n\n{}\n\n## Example\n\n```objectscript\n{}\n```\n",
+            self.title, self.id, self.explanation, self.example
+        )
+    }
+}
+
+/// Rule ID of the unterminated and unmatched `##class(`, `&sql(`, and `$select(` lint.
+pub const UNBALANCED_CONSTRUCT_RULE: &str = "unbalanced-construct";
+
+/// Rule ID of the class and method name case mismatch lint.
+pub const CASE_MISMATCH_RULE: &str = "case-mismatch";
+
+/// Rule ID of the inherited member name collision lint.
+pub const MEMBER_COLLISION_RULE: &str = "member-collision";
+
+/// Rule ID of the duplicate member declaration error.
+pub const DUPLICATE_MEMBER_RULE: &str = "duplicate-member";
+
+/// Rule ID of the inheritance cycle error.
+pub const INHERITANCE_CYCLE_RULE: &str = "inheritance-cycle";
+
+/// Rule ID of the class method / instance method override lint.
+pub const DISPATCH_MISMATCH_RULE: &str = "dispatch-mismatch";
+
+/// Rule ID of the method complexity threshold lint.
+pub const METHOD_COMPLEXITY_RULE: &str = "method-complexity";
+
+/// Rule ID of the undefined embedded SQL host variable lint.
+pub const UNDEFINED_HOST_VARIABLE_RULE: &str = "undefined-host-variable";
+
+/// Rule ID of the `$SYSTEM` API argument count lint.
+pub const SYSTEM_API_ARGUMENTS_RULE: &str = "system-api-arguments";
+
+/// Rule ID of the recursive method lint.
+pub const RECURSIVE_METHOD_RULE: &str = "recursive-method";
+
+/// Rule ID of the unresolved reference lint.
+pub const UNRESOLVED_REFERENCE_RULE: &str = "unresolved-reference";
+
+/// Rule ID of the method call argument count lint.
+pub const ARGUMENT_COUNT_RULE: &str = "argument-count";
+
+/// Rule ID of the strict-mode type mismatch lint.
+pub const TYPE_MISMATCH_RULE: &str = "type-mismatch";
+
+/// Every lint rule, in the order they are listed by `objectscript.ruleDocs`.
+pub const RULES: [Rule; 17] = [
+    Rule {
+        id: UNBALANCED_CONSTRUCT_RULE,
+        title: "Unbalanced ##class, &sql, or $select",
+        explanation: "A `##class(`, embedded `&sql(`, or `$select(` is never closed, or a `)` \
+            closes nothing. The parser recovers from these far from the actual typo, so they are \
+            reported at the opening token instead. Add the missing `)` or remove the extra one.",
+        example: "    set x = $select(a:1, 1:0\n    &sql(SELECT Name INTO :name FROM Sample.Person",
+    },
+    Rule {
+        id: CASE_MISMATCH_RULE,
+        title: "Case mismatch",
+        explanation: "Class and method names are case-sensitive: a call whose name only differs \
+            in case from a declaration fails at runtime with `<CLASS DOES NOT EXIST>` or \
+            `<METHOD DOES NOT EXIST>`. Use the declared spelling.",
+        example: "    do ##class(Sample.person).Lookup()",
+    },
+    Rule {
+        id: MEMBER_COLLISION_RULE,
+        title: "Inherited member name collision",
+        explanation: "A member has the same name as a member of another kind (e.g. a property \
+            and a method) inherited from a superclass. Which one a reference resolves to is \
+            confusing and may change with the class hierarchy; rename one of them.",
+        example: "Class Sample.Employee Extends Sample.Person\n{\n\
+            /// Sample.Person has a property Name\n\
+            Method Name() As %String\n{\n    quit \"\"\n}\n}",
+    },
+    Rule {
+        id: DUPLICATE_MEMBER_RULE,
+        title: "Duplicate member",
+        explanation: "Two members of the same kind share a name in one class, e.g. a `Method` and \
+            a `ClassMethod`, or two `Parameter`s. The class doesn't compile, and until it is \
+            fixed only the first declaration is indexed. Rename or remove the later one.",
+        example: "Parameter VERSION = 1;\n\nParameter VERSION = 2;",
+    },
+    Rule {
+        id: INHERITANCE_CYCLE_RULE,
+        title: "Inheritance cycle",
+        explanation: "A class extends one of its own subclasses, directly or through other \
+            classes, so it inherits from itself. None of the classes of the cycle compile. The \
+            server ignores the superclass closing the cycle; remove it from the `Extends` list.",
+        example: "Class Sample.A Extends Sample.B\n{\n}\n\nClass Sample.B Extends Sample.A\n{\n}",
+    },
+    Rule {
+        id: DISPATCH_MISMATCH_RULE,
+        title: "Class method / instance method override",
+        explanation: "A `ClassMethod` overrides an instance `Method` inherited from a superclass, \
+            or the other way around. Calls dispatched through the superclass expect the \
+            inherited kind, so the override either can't be called the same way or has no \
+            object to work on. Keep the kind of the overridden method, or rename the override.",
+        example: "Class Sample.Employee Extends Sample.Person\n{\n\
+            /// Sample.Person has an instance method Describe\n\
+            ClassMethod Describe() As %String\n{\n    quit \"\"\n}\n}",
+    },
+    Rule {
+        id: DEBUG_WRITE_CODE,
+        title: "Debugging write",
+        explanation: "`write` and `zwrite` statements are usually debugging leftovers that write \
+            to the current device of whoever calls the code. Use the configured logging call \
+            instead; the quick fix converts the statement.",
+        example: "    zwrite request\n    write \"got here\", !",
+    },
+    Rule {
+        id: DEAD_STORE_CODE,
+        title: "Dead store",
+        explanation: "A private variable is set, then overwritten or killed before its value is \
+            read, so the first `set` has no effect. Remove it, or read the value where it was \
+            meant to be used.",
+        example: "    set total = 0\n    set total = ..Sum(items)",
+    },
+    Rule {
+        id: UNUSED_VARIABLE_CODE,
+        title: "Unused variable",
+        explanation: "A private variable is set but never read anywhere in the method, so \
+            computing its value is wasted work, or the value was meant to be used and isn't. \
+            Editors gray out the assignments. Remove them, or use the value.",
+        example: "    set count = ..Count(items)\n    quit ..Sum(items)",
+    },
+    Rule {
+        id: UNREACHABLE_CODE,
+        title: "Unreachable code",
+        explanation: "Statements follow an unconditional `quit`, `return`, or `throw` in the same \
+            block, so they never run: the exit leaves the block first. Editors gray them out. \
+            Remove them, make the exit conditional with a postconditional (`quit:done`), or add \
+            the label that is meant to reach them.",
+        example: "    quit sc\n    set ^Log($increment(^Log)) = \"done\"",
+    },
+    Rule {
+        id: METHOD_COMPLEXITY_RULE,
+        title: "Method complexity",
+        explanation: "The method's cyclomatic complexity or nesting depth exceeds \
+            `max_method_complexity` or `max_method_nesting`. Deeply branching methods are hard to \
+            test and review; extract parts of them into smaller methods.",
+        example: "    if a {\n        for i=1:1:n {\n            if b quit:done\n        }\n    }",
+    },
+    Rule {
+        id: UNDEFINED_HOST_VARIABLE_RULE,
+        title: "Undefined host variable",
+        explanation: "Embedded SQL reads a host variable that is never defined: it is not an \
+            argument, not set or stored into with `INTO` in the method, and not a public \
+            variable in scope. In a procedure block it is always undefined when the query runs.",
+        example: "    &sql(SELECT Name INTO :name FROM Sample.Person WHERE ID = :id)",
+    },
+    Rule {
+        id: SYSTEM_API_ARGUMENTS_RULE,
+        title: "$SYSTEM API arguments",
+        explanation: "A call to a `$SYSTEM` method passes more arguments than the method \
+            declares, or omits one it requires. Extra arguments are a `<PARAMETER>` error at \
+            runtime. Only the methods the server ships stubs for are checked.",
+        example: "    set sc = $SYSTEM.OBJ.Load()\n    set ok = $SYSTEM.Status.IsOK(sc, 1)",
+    },
+    Rule {
+        id: UNRESOLVED_REFERENCE_RULE,
+        title: "Unresolved reference",
+        explanation: "A class, method, include file, or routine referenced here isn't defined \
+            anywhere in the workspace, so it fails to compile or fails at runtime unless it is \
+            provided by another namespace. System (`%`) names are never reported. Use the \
+            `objectscript/unresolved` request to list these across the workspace.",
+        example: "Include MyApp.Missing\n\n    do ##class(MyApp.Removed).Run()\n    do ^OLDROUTINE",
+    },
+    Rule {
+        id: RECURSIVE_METHOD_RULE,
+        title: "Recursive method",
+        explanation: "The method calls itself, directly or through other methods calling it \
+            back. This is informational: recursion is often intended, but in converted legacy \
+            code it can hide unbounded loops, and deep recursion exhausts the process stack \
+            (`<FRAMESTACK>`). The call hierarchy marks these methods too.",
+        example: "ClassMethod Walk(node As %String)\n{\n    do ..Walk($order(^Tree(node)))\n}",
+    },
+    Rule {
+        id: ARGUMENT_COUNT_RULE,
+        title: "Argument count",
+        explanation: "A call passes more arguments than the method declares, which is a \
+            `<PARAMETER>` error at runtime, or leaves out an argument that has no default value, \
+            which the method then finds undefined. `Output` arguments and arguments of methods \
+            taking a variable number (`args...`) may be left out.",
+        example: "ClassMethod Total(items As %List, rate As %Numeric)\n{\n}\n\n\
+            do ..Total(items)\n    do ..Total(items, 1, 2)",
+    },
+    Rule {
+        id: TYPE_MISMATCH_RULE,
+        title: "Type mismatch",
+        explanation: "A literal is passed as an argument, assigned to a variable, or returned \
+            where the declared type can't hold it, e.g. a non-numeric string where `%Integer` is \
+            expected, or a JSON object where `%DynamicArray` is. ObjectScript converts it \
+            silently, usually to `0` or an invalid value. Only reported in strict mode; numbers \
+            written as strings and the empty string are always accepted.",
+        example: "ClassMethod Total(count As %Integer) As %Integer\n{\n    quit \"none\"\n}\n\n\
+            do ..Total(\"ten\")",
+    },
+];
+
+/// Returns the rule with the ID `id`.
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+/// Builds the `Diagnostic::code` of the rule `id`.
+pub fn rule_code(id: &str) -> Option<NumberOrString> {
+    Some(NumberOrString::String(id.to_string()))
+}
+
+/// Builds the `Diagnostic::code_description` of the rule `id`: a `command:` link running
+/// `objectscript.ruleDocs` on the rule, so clients can click through from a diagnostic to its
+/// documentation.
+pub fn rule_code_description(id: &str) -> Option<CodeDescription> {
+    let rule = rule(id)?;
+    // command uris take their arguments as a url-encoded JSON array
+    let href = format!("command:{RULE_DOCS}?%5B%22{}%22%5D", rule.id);
+    Some(CodeDescription {
+        href: Url::parse(&href).ok()?,
+    })
+}
+
+/// Renders the documentation of the rule `id`, or of every rule when `id` is `None`.
+pub fn rule_docs(id: Option<&str>) -> Option<String> {
+    match id {
+        Some(id) => rule(id).map(Rule::to_markdown),
+        None => Some(
+            RULES
+                .iter()
+                .map(Rule::to_markdown)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    }
+}
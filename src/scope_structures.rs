@@ -1,3 +1,4 @@
+use crate::arena::ArenaId;
 use tower_lsp::lsp_types::Url;
 use tree_sitter::Range;
 
@@ -9,17 +10,84 @@ pub struct ScopeId(pub usize);
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct VariableSymbolId(pub usize);
 
-/// Stores the index into the per-class public variable symbol vec in `GlobalSemanticModel::variable_defs::ClassGlobalSymbolId`.
+/// Stores the slot index and generation of a public variable symbol in its class' arena in
+/// `GlobalSemanticModel::variable_defs`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct VariableGlobalSymbolId(pub usize);
+pub struct VariableGlobalSymbolId(pub usize, pub u32);
 
-/// Stores the index into `GlobalSemanticModel::class_defs`.
+impl ArenaId for VariableGlobalSymbolId {
+    fn from_parts(index: usize, generation: u32) -> Self {
+        VariableGlobalSymbolId(index, generation)
+    }
+
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn generation(self) -> u32 {
+        self.1
+    }
+}
+
+/// Stores the slot index and generation of a class symbol in the `GlobalSemanticModel::class_defs`
+/// arena.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct ClassGlobalSymbolId(pub usize);
+pub struct ClassGlobalSymbolId(pub usize, pub u32);
+
+impl ArenaId for ClassGlobalSymbolId {
+    fn from_parts(index: usize, generation: u32) -> Self {
+        ClassGlobalSymbolId(index, generation)
+    }
 
-/// Stores the index into the per-class public method symbol vec in `GlobalSemanticModel::method_defs::ClassGlobalSymbolId`.
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn generation(self) -> u32 {
+        self.1
+    }
+}
+
+/// Stores the slot index and generation of a public method symbol in its class' arena in
+/// `GlobalSemanticModel::method_defs`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct MethodGlobalSymbolId(pub usize);
+pub struct MethodGlobalSymbolId(pub usize, pub u32);
+
+impl ArenaId for MethodGlobalSymbolId {
+    fn from_parts(index: usize, generation: u32) -> Self {
+        MethodGlobalSymbolId(index, generation)
+    }
+
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn generation(self) -> u32 {
+        self.1
+    }
+}
+
+/// Identifies any global (workspace-level) symbol, keyed by the class symbol that owns it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GlobalSymbolId {
+    /// A class symbol in `GlobalSemanticModel::class_defs`.
+    Class(ClassGlobalSymbolId),
+    /// A public method symbol in `GlobalSemanticModel::method_defs[ClassGlobalSymbolId]`.
+    Method(ClassGlobalSymbolId, MethodGlobalSymbolId),
+    /// A public variable symbol in `GlobalSemanticModel::variable_defs[ClassGlobalSymbolId]`.
+    Variable(ClassGlobalSymbolId, VariableGlobalSymbolId),
+}
+
+impl GlobalSymbolId {
+    /// Returns the class symbol that owns this symbol.
+    pub fn class_symbol_id(&self) -> ClassGlobalSymbolId {
+        match self {
+            GlobalSymbolId::Class(id) => *id,
+            GlobalSymbolId::Method(id, _) => *id,
+            GlobalSymbolId::Variable(id, _) => *id,
+        }
+    }
+}
 
 /// A variable definition symbol for a public variable (definition site + dependency metadata).
 #[derive(Clone, Debug)]
@@ -1,5 +1,7 @@
 use crate::common::{generic_exit_statements, point_in_range, start_of_function, successful_exit};
 use crate::scope_structures::*;
+use crate::symbol_index::{SymbolEntry, SymbolRef};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use tree_sitter::{Point, Range};
 
@@ -35,28 +37,16 @@ impl Scope {
         }
     }
 
-    /// Look up a private variable definition in this scope by name and return its source range.
+    /// Return the source range of the first definition (argument or `set`) of the private
+    /// variable `variable_name` in this scope.
     ///
-    /// Logs a warning and returns `None` if the name is not present or the stored symbol id is
-    /// out of bounds for `variable_symbols`.
-    fn get_variable_symbol(&self, variable_name: &str) -> Option<Range> {
-        start_of_function("Scope", "get_variable_symbol");
-        let Some(variable_symbol_id) = self.private_variable_defs.get(variable_name) else {
-            eprintln!(
-                "Warning: Couldn't find variable name: {:?} in private variable defs hashmap: {:?}",
-                variable_name, self.private_variable_defs
-            );
-            return None;
-        };
-
-        let Some(variable_symbol) = self.variable_symbols.get(variable_symbol_id.0) else {
-            eprintln!("Warning: Failed to get variable symbol for variable named {:?}. Index {:?} is out of range in this scopes variable symbols vec: {:?}", variable_name, variable_symbol_id.0, self.variable_symbols);
-            generic_exit_statements("Scope", "get_variable_symbol");
-            return None;
-        };
-
-        successful_exit("Scope", "get_variable_symbol");
-        Some(variable_symbol.location)
+    /// `private_variable_defs` maps a name to its latest definition, so this scans
+    /// `variable_symbols`, which are stored in source order.
+    fn first_variable_definition(&self, variable_name: &str) -> Option<Range> {
+        self.variable_symbols
+            .iter()
+            .find(|symbol| symbol.name == variable_name)
+            .map(|symbol| symbol.location)
     }
 
     /// Define a new private variable symbol in this scope and return its `VariableSymbolId`.
@@ -255,6 +245,93 @@ impl ScopeTree {
         Some(sym_id)
     }
 
+    /// Collect a `SymbolEntry` for every private method definition and every private variable
+    /// definition/reference recorded in this document.
+    pub fn symbol_entries(&self) -> Vec<SymbolEntry> {
+        let mut entries = Vec::new();
+        for (name, method_symbol) in &self.private_method_defs {
+            entries.push(SymbolEntry {
+                range: method_symbol.location,
+                symbol: SymbolRef::PrivateMethod(name.clone()),
+            });
+        }
+        for (&scope_id, scope) in &self.scopes {
+            for (i, variable_symbol) in scope.variable_symbols.iter().enumerate() {
+                let variable_symbol_id = VariableSymbolId(i);
+                entries.push(SymbolEntry {
+                    range: variable_symbol.location,
+                    symbol: SymbolRef::PrivateVariable(scope_id, variable_symbol_id),
+                });
+                for &reference in &variable_symbol.references {
+                    entries.push(SymbolEntry {
+                        range: reference,
+                        symbol: SymbolRef::PrivateVariableReference(scope_id, variable_symbol_id),
+                    });
+                }
+            }
+        }
+        entries
+    }
+
+    /// Render this scope tree as JSON for debugging variable resolution.
+    ///
+    /// Every scope is listed in id order with its bounds, parent/children, private variable
+    /// definitions (with their references) and the names of public variables defined in it. All
+    /// points are zero-based Tree-sitter `{ row, column }` pairs (column is a UTF-8 byte offset).
+    pub fn to_json(&self) -> Value {
+        let point = |p: Point| json!({ "row": p.row, "column": p.column });
+        let range = |r: Range| json!({ "start": point(r.start_point), "end": point(r.end_point) });
+
+        let mut scope_ids: Vec<&ScopeId> = self.scopes.keys().collect();
+        scope_ids.sort_by_key(|id| id.0);
+        let scopes: Vec<Value> = scope_ids
+            .into_iter()
+            .filter_map(|id| self.scopes.get(id).map(|scope| (id, scope)))
+            .map(|(id, scope)| {
+                let private_variables: Vec<Value> = scope
+                    .variable_symbols
+                    .iter()
+                    .map(|sym| {
+                        let references: Vec<Value> =
+                            sym.references.iter().map(|r| range(*r)).collect();
+                        json!({
+                            "name": sym.name,
+                            "definition": range(sym.location),
+                            "references": references,
+                            "varDependencies": sym.var_dependencies,
+                            "propertyDependencies": sym.property_dependencies,
+                        })
+                    })
+                    .collect();
+                let mut public_variables: Vec<&String> = scope.public_var_defs.keys().collect();
+                public_variables.sort();
+                let children: Vec<usize> = scope.children.iter().map(|child| child.0).collect();
+                json!({
+                    "id": id.0,
+                    "parent": scope.parent.map(|parent| parent.0),
+                    "children": children,
+                    "start": point(scope.start),
+                    "end": point(scope.end),
+                    "privateVariables": private_variables,
+                    "publicVariables": public_variables,
+                })
+            })
+            .collect();
+
+        let mut private_methods: Vec<&MethodSymbol> = self.private_method_defs.values().collect();
+        private_methods.sort_by_key(|sym| sym.location.start_byte);
+        let private_methods: Vec<Value> = private_methods
+            .into_iter()
+            .map(|sym| json!({ "name": sym.name, "definition": range(sym.location) }))
+            .collect();
+
+        json!({
+            "root": self.root.0,
+            "privateMethods": private_methods,
+            "scopes": scopes,
+        })
+    }
+
     /// Get a mutable reference to the innermost scope containing `point`.
     ///
     /// Logs a warning and returns `None` if no containing scope is found.
@@ -310,25 +387,66 @@ impl ScopeTree {
         successful_exit("Scope", "new_public_var_symbol");
     }
 
-    /// Look up a private variable definition visible at `pos` by name.
-    pub fn get_variable_definition(&self, pos: Point, variable_name: &str) -> Option<Range> {
-        start_of_function("Scope", "get_variable_definition");
-        let Some(scope) = self.get_scope(pos) else {
-            generic_exit_statements("Scope", "get_variable_definition");
-            return None;
-        };
-
-        let result = scope.get_variable_symbol(variable_name);
-        match result {
-            Some(_) => {
-                successful_exit("Scope", "get_variable_definition");
-                result
-            }
-            None => {
-                generic_exit_statements("Scope", "get_variable_definition");
-                result
+    /// Find the declaration of the private variable `variable_name` visible at `pos`: its first
+    /// definition (argument or `set`) in the innermost scope defining it, walking up the scope
+    /// chain from the scope containing `pos`.
+    pub fn find_declaration(&self, pos: Point, variable_name: &str) -> Option<Range> {
+        start_of_function("Scope", "find_declaration");
+        let mut scope_id = self.find_current_scope(pos);
+        while let Some(scope) = scope_id.and_then(|id| self.scopes.get(&id)) {
+            if let Some(range) = scope.first_variable_definition(variable_name) {
+                successful_exit("Scope", "find_declaration");
+                return Some(range);
             }
+            scope_id = scope.parent;
         }
+        eprintln!(
+            "Warning: Couldn't find a definition of variable {:?} in the scopes containing {:?}",
+            variable_name, pos
+        );
+        generic_exit_statements("Scope", "find_declaration");
+        None
+    }
+
+    /// Look up the id of the private variable named `variable_name` defined in the scope containing
+    /// `pos`.
+    pub fn get_variable_symbol_id(
+        &self,
+        pos: Point,
+        variable_name: &str,
+    ) -> Option<(ScopeId, VariableSymbolId)> {
+        let scope_id = self.find_current_scope(pos)?;
+        let &variable_symbol_id = self
+            .scopes
+            .get(&scope_id)?
+            .private_variable_defs
+            .get(variable_name)?;
+        Some((scope_id, variable_symbol_id))
+    }
+
+    /// Record `range` as a reference to the private variable named `variable_name` defined in the
+    /// scope containing `range.start_point`.
+    ///
+    /// Returns `false` if no such variable is defined there.
+    pub fn add_variable_reference(&mut self, variable_name: &str, range: Range) -> bool {
+        start_of_function("Scope", "add_variable_reference");
+        let Some((scope_id, variable_symbol_id)) =
+            self.get_variable_symbol_id(range.start_point, variable_name)
+        else {
+            generic_exit_statements("Scope", "add_variable_reference");
+            return false;
+        };
+        let Some(variable_symbol) = self
+            .scopes
+            .get_mut(&scope_id)
+            .and_then(|scope| scope.variable_symbols.get_mut(variable_symbol_id.0))
+        else {
+            generic_exit_statements("Scope", "add_variable_reference");
+            return false;
+        };
+        variable_symbol.references.push(range);
+        successful_exit("Scope", "add_variable_reference");
+        true
     }
 
     /// Find the innermost scope containing `pos` by descending from the root into matching children.
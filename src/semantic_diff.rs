@@ -0,0 +1,170 @@
+use crate::common::{
+    find_class_definition, get_class_name_from_root, get_node_children, start_of_function,
+    successful_exit,
+};
+use crate::diagnostics::{class_members, MemberKind};
+use crate::parse_structures::{Class, Method, MethodType};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use tree_sitter::{Parser, Tree};
+use tree_sitter_objectscript::LANGUAGE_OBJECTSCRIPT;
+
+/// Parameters of the `objectscript/semanticDiff` request.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticDiffParams {
+    /// Class definition before the change.
+    pub old_text: String,
+    /// Class definition after the change.
+    pub new_text: String,
+}
+
+/// API-level outline of a class: the parts of a class definition `semantic_diff` compares.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClassOutline {
+    pub name: String,
+    /// Superclass names, in `Extends` order.
+    pub superclasses: Vec<String>,
+    /// Class keyword -> value.
+    pub keywords: BTreeMap<String, String>,
+    /// Method name -> signature.
+    pub methods: BTreeMap<String, String>,
+    pub properties: BTreeSet<String>,
+    pub parameters: BTreeSet<String>,
+}
+
+/// Parses `content` as a class definition.
+///
+/// Returns `None` if the grammar can't be loaded or the text doesn't define a class.
+fn parse_class(content: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    if let Err(e) = parser.set_language(&LANGUAGE_OBJECTSCRIPT.into()) {
+        eprintln!("Error: failed to load the ObjectScript grammar: {e}");
+        return None;
+    }
+    let tree = parser.parse(content, None)?;
+    find_class_definition(tree.root_node())?;
+    Some(tree)
+}
+
+/// Describes a method's signature: type, arguments, and the keywords that affect callers.
+fn method_signature(method: &Method) -> String {
+    let method_type = match method.method_type {
+        MethodType::ClassMethod => "ClassMethod",
+        MethodType::InstanceMethod => "Method",
+    };
+    format!(
+        "{method_type} {}({}) Returns={:?} Public={} ProcedureBlock={:?} Language={:?} CodeMode={:?}",
+        method.name,
+        method.arguments.join(", "),
+        method.return_type,
+        method.is_public,
+        method.is_procedure_block,
+        method.language,
+        method.code_mode
+    )
+}
+
+/// Builds the `ClassOutline` of the class defined in `content`, using the same class builder as
+/// indexing (`Class::initial_build`).
+pub fn outline_class(content: &str) -> Option<ClassOutline> {
+    start_of_function("SEMANTIC DIFF (no struct)", "outline_class");
+    let tree = parse_class(content)?;
+    let class_definition = find_class_definition(tree.root_node())?;
+    let name = get_class_name_from_root(content, tree.root_node())?;
+
+    let mut class = Class::new(name.clone());
+    let methods = class
+        .initial_build(class_definition, content)
+        .methods
+        .into_iter()
+        .map(|(method, _)| (method.name.clone(), method_signature(&method)))
+        .collect();
+    let superclasses = get_node_children(class_definition)
+        .into_iter()
+        .filter(|node| node.kind() == "class_extends")
+        .flat_map(|node| get_node_children(node).into_iter().skip(1))
+        .filter_map(|node| content.get(node.byte_range()))
+        .map(str::to_string)
+        .collect();
+    let mut keywords = BTreeMap::new();
+    keywords.insert(
+        "ProcedureBlock".to_string(),
+        format!("{:?}", class.is_procedure_block),
+    );
+    keywords.insert(
+        "Language".to_string(),
+        format!("{:?}", class.default_language),
+    );
+    keywords.insert(
+        "Inheritance".to_string(),
+        class.inheritance_direction.clone(),
+    );
+    keywords.insert("GeneratedBy".to_string(), class.generated_by.to_string());
+
+    let mut outline = ClassOutline {
+        name,
+        superclasses,
+        keywords,
+        methods,
+        ..Default::default()
+    };
+    for member in class_members(content, &tree) {
+        match member.kind {
+            MemberKind::Property => {
+                outline.properties.insert(member.name);
+            }
+            MemberKind::Parameter => {
+                outline.parameters.insert(member.name);
+            }
+            _ => {}
+        }
+    }
+    successful_exit("SEMANTIC DIFF (no struct)", "outline_class");
+    Some(outline)
+}
+
+/// Diffs two maps as `{ added, removed, changed: [{ name, before, after }] }`.
+fn diff_maps(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Value {
+    let added: Vec<&String> = new.keys().filter(|name| !old.contains_key(*name)).collect();
+    let removed: Vec<&String> = old.keys().filter(|name| !new.contains_key(*name)).collect();
+    let changed: Vec<Value> = new
+        .iter()
+        .filter_map(|(name, after)| {
+            let before = old.get(name).filter(|before| *before != after)?;
+            Some(json!({ "name": name, "before": before, "after": after }))
+        })
+        .collect();
+    json!({ "added": added, "removed": removed, "changed": changed })
+}
+
+/// Diffs two sets as `{ added, removed }`.
+fn diff_sets(old: &BTreeSet<String>, new: &BTreeSet<String>) -> Value {
+    json!({
+        "added": new.difference(old).collect::<Vec<_>>(),
+        "removed": old.difference(new).collect::<Vec<_>>(),
+    })
+}
+
+/// Computes the structural diff between two versions of a class definition.
+///
+/// Reports API-level changes (methods added, removed, or with a changed signature; class
+/// keywords; superclasses; properties and parameters) rather than changed lines, for review
+/// tooling. Returns `None` if either text doesn't define a class.
+pub fn semantic_diff(old_text: &str, new_text: &str) -> Option<Value> {
+    let old = outline_class(old_text)?;
+    let new = outline_class(new_text)?;
+    Some(json!({
+        "className": { "before": old.name, "after": new.name },
+        "superclasses": {
+            "before": old.superclasses,
+            "after": new.superclasses,
+            "changed": old.superclasses != new.superclasses,
+        },
+        "keywords": diff_maps(&old.keywords, &new.keywords)["changed"],
+        "methods": diff_maps(&old.methods, &new.methods),
+        "properties": diff_sets(&old.properties, &new.properties),
+        "parameters": diff_sets(&old.parameters, &new.parameters),
+    }))
+}
@@ -0,0 +1,345 @@
+use crate::common::ts_range_to_lsp_range;
+use crate::diagnostics::byte_range;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower_lsp::lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit,
+    SemanticTokensLegend, Url,
+};
+use tree_sitter::{Range, Tree};
+
+/// Token types of the legend, in the order of `TokenType`.
+const TOKEN_TYPES: [SemanticTokenType; 9] = [
+    SemanticTokenType::CLASS,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::MACRO,
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+];
+
+/// Index of a token type in the legend.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TokenType {
+    Class,
+    Method,
+    /// A class parameter, declared with `Parameter` and used with `..#NAME`.
+    Parameter,
+    Property,
+    Variable,
+    Macro,
+    Keyword,
+    String,
+    Number,
+}
+
+/// Bit of the `declaration` modifier: the token declares its symbol.
+pub const DECLARATION: u32 = 1;
+/// Bit of the `public` modifier: a local variable that is public, e.g. `%name` or one in a
+/// `PublicList`.
+pub const PUBLIC: u32 = 1 << 1;
+/// Bit of the `global` modifier: a global (`^name`).
+pub const GLOBAL: u32 = 1 << 2;
+
+/// The legend advertised in `ServerCapabilities::semantic_tokens_provider`.
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: vec![
+            SemanticTokenModifier::DECLARATION,
+            SemanticTokenModifier::new("public"),
+            SemanticTokenModifier::new("global"),
+        ],
+    }
+}
+
+/// A semantic token before encoding: its source range, type, and modifier bits.
+#[derive(Clone, Debug)]
+pub struct RawToken {
+    pub range: Range,
+    pub token_type: TokenType,
+    pub modifiers: u32,
+}
+
+impl RawToken {
+    pub fn new(range: Range, token_type: TokenType, modifiers: u32) -> Self {
+        Self {
+            range,
+            token_type,
+            modifiers,
+        }
+    }
+}
+
+/// Collects the tokens that can be told from the syntax tree of `content` alone: class names,
+/// method calls, property and parameter references, variables, macros, keywords, and literals.
+/// `is_public_variable` tells whether a local variable name is public in the class.
+///
+/// Declarations of class members come from `class_members` instead.
+pub fn syntax_tokens(
+    content: &str,
+    tree: &Tree,
+    is_public_variable: impl Fn(&str) -> bool,
+) -> Vec<RawToken> {
+    let mut tokens = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if node.kind() != "string_literal" {
+            let mut cursor = node.walk();
+            stack.extend(node.children(&mut cursor));
+        }
+        let text = content.get(node.byte_range()).unwrap_or_default();
+        // the range of `len` bytes of the node starting `skip` bytes in
+        let part = |skip: usize, len: usize| {
+            let start = node.start_byte() + skip;
+            byte_range(content, start, start + len)
+        };
+        match node.kind() {
+            "class_definition" => {
+                // child(1) is the class name
+                if let Some(name) = node.named_child(1) {
+                    tokens.push(RawToken::new(name.range(), TokenType::Class, DECLARATION));
+                }
+            }
+            "class_extends" => {
+                // child(0) is the `Extends` keyword
+                let mut cursor = node.walk();
+                for name in node.named_children(&mut cursor).skip(1) {
+                    tokens.push(RawToken::new(name.range(), TokenType::Class, 0));
+                }
+            }
+            "class_method_call" => {
+                if let Some(name) = node.named_child(0).and_then(|class| class.named_child(1)) {
+                    tokens.push(RawToken::new(name.range(), TokenType::Class, 0));
+                }
+            }
+            "oref_method" => {
+                if let Some(name) = node.named_child(0) {
+                    tokens.push(RawToken::new(name.range(), TokenType::Method, 0));
+                }
+            }
+            "relative_dot_property" => {
+                let name = text.trim_start_matches('.');
+                let skip = text.len() - name.len();
+                tokens.push(RawToken::new(
+                    part(skip, name.len()),
+                    TokenType::Property,
+                    0,
+                ));
+            }
+            "relative_dot_parameter" | "class_parameter_ref" => {
+                if let Some(hash) = text.rfind('#') {
+                    let len = text.len() - hash - 1;
+                    tokens.push(RawToken::new(part(hash + 1, len), TokenType::Parameter, 0));
+                }
+            }
+            "instance_variable" => {
+                if let Some(name) = node.named_child(0) {
+                    tokens.push(RawToken::new(name.range(), TokenType::Property, 0));
+                }
+            }
+            "lvn" | "gvn" => {
+                let name = text.split('(').next().unwrap_or_default().trim_end();
+                let modifiers = if node.kind() == "gvn" {
+                    GLOBAL
+                } else if is_public_variable(name) {
+                    PUBLIC
+                } else {
+                    0
+                };
+                tokens.push(RawToken::new(
+                    part(0, name.len()),
+                    TokenType::Variable,
+                    modifiers,
+                ));
+            }
+            "string_literal" => {
+                tokens.push(RawToken::new(node.range(), TokenType::String, 0));
+            }
+            "numeric_literal" => {
+                tokens.push(RawToken::new(node.range(), TokenType::Number, 0));
+            }
+            kind if kind.starts_with("keyword_") => {
+                tokens.push(RawToken::new(node.range(), TokenType::Keyword, 0));
+            }
+            kind if kind.starts_with("command_") => {
+                // the command name (`set`, `s`) is the first, anonymous, child
+                if let Some(command) = node.child(0).filter(|child| !child.is_named()) {
+                    tokens.push(RawToken::new(command.range(), TokenType::Keyword, 0));
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.extend(macro_tokens(content, tree));
+    tokens
+}
+
+/// Finds the `$$$Name` macros of `content`, outside strings and comments.
+fn macro_tokens(content: &str, tree: &Tree) -> Vec<RawToken> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = content[offset..].find("$$$") {
+        let start = offset + found;
+        let name_len = content[start + 3..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '%'))
+            .unwrap_or(content.len() - start - 3);
+        offset = start + 3 + name_len;
+        let mut node = tree.root_node().descendant_for_byte_range(start, start);
+        let mut in_code = node.is_some();
+        while let Some(current) = node {
+            if current.kind() == "string_literal" || current.kind().contains("comment") {
+                in_code = false;
+                break;
+            }
+            node = current.parent();
+        }
+        if name_len > 0 && in_code {
+            tokens.push(RawToken::new(
+                byte_range(content, start, offset),
+                TokenType::Macro,
+                0,
+            ));
+        }
+    }
+    tokens
+}
+
+/// Encodes `tokens` in the relative format of `textDocument/semanticTokens`, in source order.
+///
+/// Tokens spanning several lines and tokens overlapping an earlier one are dropped, since
+/// clients don't need to support either.
+pub fn encode_tokens(content: &str, mut tokens: Vec<RawToken>) -> Vec<SemanticToken> {
+    tokens.sort_by_key(|token| (token.range.start_byte, token.range.end_byte));
+    let mut encoded = Vec::with_capacity(tokens.len());
+    let mut previous_end = 0;
+    let (mut line, mut character) = (0, 0);
+    for token in tokens {
+        if token.range.start_byte < previous_end
+            || token.range.start_point.row != token.range.end_point.row
+            || token.range.start_byte == token.range.end_byte
+        {
+            continue;
+        }
+        previous_end = token.range.end_byte;
+        let range = ts_range_to_lsp_range(content, token.range);
+        let delta_line = range.start.line - line;
+        let delta_start = if delta_line == 0 {
+            range.start.character - character
+        } else {
+            range.start.character
+        };
+        encoded.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: range.end.character - range.start.character,
+            token_type: token.token_type as u32,
+            token_modifiers_bitset: token.modifiers,
+        });
+        line = range.start.line;
+        character = range.start.character;
+    }
+    encoded
+}
+
+/// Computes the edits turning the `previous` tokens into the `current` ones, for
+/// `semanticTokens/full/delta`: a single edit replacing everything between their common prefix
+/// and suffix, or none if they are equal.
+///
+/// Offsets count the integers of the flattened token array, five per token.
+pub fn token_edits(
+    previous: &[SemanticToken],
+    current: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(previous, current)| previous == current)
+        .count();
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(previous, current)| previous == current)
+        .count();
+    let deleted = previous.len() - prefix - suffix;
+    let inserted = &current[prefix..current.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return Vec::new();
+    }
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: (!inserted.is_empty()).then(|| inserted.to_vec()),
+    }]
+}
+
+/// The tokens last sent for a document.
+#[derive(Clone, Debug)]
+pub struct CachedTokens {
+    /// The `resultId` the tokens were sent with.
+    pub result_id: String,
+    /// Version of the document the tokens were computed for.
+    pub version: Option<i32>,
+    /// `ResponseCache` generation the tokens were computed at; tokens depend on other classes
+    /// too, e.g. for public variables.
+    pub generation: u64,
+    pub data: Arc<Vec<SemanticToken>>,
+}
+
+#[derive(Debug, Default)]
+struct TokensState {
+    next_result_id: u64,
+    entries: HashMap<Url, CachedTokens>,
+}
+
+/// The last tokens sent for each document, which `semanticTokens/full/delta` requests are
+/// answered relative to.
+///
+/// Unlike `ResponseCache`, entries outlive project changes: a delta is only useful against
+/// tokens computed before the change.
+#[derive(Debug, Default)]
+pub struct SemanticTokensCache {
+    state: Mutex<TokensState>,
+}
+
+impl SemanticTokensCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the tokens last sent for `url`, if any.
+    pub fn get(&self, url: &Url) -> Option<CachedTokens> {
+        self.state.lock().entries.get(url).cloned()
+    }
+
+    /// Records `data` as the tokens sent for `url`, replacing the previous ones, and returns them
+    /// with a new result id.
+    pub fn insert(
+        &self,
+        url: &Url,
+        version: Option<i32>,
+        generation: u64,
+        data: Vec<SemanticToken>,
+    ) -> CachedTokens {
+        let mut state = self.state.lock();
+        state.next_result_id += 1;
+        let tokens = CachedTokens {
+            result_id: state.next_result_id.to_string(),
+            version,
+            generation,
+            data: Arc::new(data),
+        };
+        state.entries.insert(url.clone(), tokens.clone());
+        tokens
+    }
+
+    /// Forgets the tokens sent for `url`, e.g. once the document is closed.
+    pub fn remove(&self, url: &Url) {
+        self.state.lock().entries.remove(url);
+    }
+}
@@ -1,18 +1,87 @@
-use crate::common::{
-    generic_exit_statements, generic_skipping_statements, get_class_name_from_root,
-    start_of_function, successful_exit,
-};
+use crate::atelier::{is_read_only_url, AtelierSourceProvider};
+use crate::capabilities::DYNAMIC_FEATURES;
+use crate::common::{generic_exit_statements, start_of_function, successful_exit, workspace_depth};
+use crate::config::Config;
+use crate::lsp::get_client_capabilities;
+use crate::new_class::{class_skeleton, create_class_edit};
+use crate::package_rename::PackageRename;
 use crate::parse_structures::FileType;
 use crate::workspace::ProjectState;
-use parking_lot::RwLock;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use parking_lot::{Mutex, RwLock};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tower_lsp::lsp_types::{MessageType, Url};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tower_lsp::lsp_types::notification::Progress;
+use tower_lsp::lsp_types::request::WorkDoneProgressCreate;
+use tower_lsp::lsp_types::{
+    FileEvent, MessageType, NumberOrString, ProgressParams, ProgressParamsValue, Registration,
+    Unregistration, Url, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams,
+    WorkDoneProgressEnd, WorkDoneProgressReport, WorkspaceEdit,
+};
 use tower_lsp::Client;
-use tree_sitter::Parser;
-use tree_sitter_objectscript::{LANGUAGE_OBJECTSCRIPT, LANGUAGE_OBJECTSCRIPT_CORE};
-use walkdir::WalkDir;
+
+/// Prefix of the work-done progress tokens of `Backend::fix_all_in_workspace`, each run adding
+/// its number from `FIX_ALL_RUNS` so that concurrent runs don't share a token.
+const FIX_ALL_PROGRESS_TOKEN: &str = "objectscript/fixAllInWorkspace";
+
+/// Number of `Backend::fix_all_in_workspace` runs started.
+static FIX_ALL_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// Work-done progress token of `Backend::reindex_workspace`.
+const REINDEX_PROGRESS_TOKEN: &str = "objectscript/reindexWorkspace";
+
+/// Work-done progress token of `Backend::index_workspace`.
+const INDEX_PROGRESS_TOKEN: &str = "objectscript/indexWorkspace";
+
+/// How often `Backend::index_workspace` reports the progress of the indexing thread.
+const INDEX_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a document must go without edits before `Backend::schedule_revalidation` analyzes
+/// it.
+const REVALIDATION_DELAY: Duration = Duration::from_millis(200);
+
+/// Runs at most one delayed task per key: scheduling a task cancels the one still pending (or
+/// running) for the same key.
+pub(crate) struct Debouncer<K> {
+    tasks: Mutex<HashMap<K, JoinHandle<()>>>,
+}
+
+impl<K: Eq + Hash> Debouncer<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `task` on the Tokio runtime after `delay`, unless another task is scheduled for `key`
+    /// (or it is cancelled) first.
+    pub(crate) fn schedule<F>(&self, key: K, delay: Duration, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            task.await;
+        });
+        let mut tasks = self.tasks.lock();
+        tasks.retain(|_, pending| !pending.is_finished());
+        if let Some(previous) = tasks.insert(key, handle) {
+            previous.abort();
+        }
+    }
+
+    /// Cancels the task scheduled for `key`, if any.
+    pub(crate) fn cancel(&self, key: &K) {
+        if let Some(pending) = self.tasks.lock().remove(key) {
+            pending.abort();
+        }
+    }
+}
 
 pub struct BackendWrapper(pub(crate) Arc<Backend>);
 impl BackendWrapper {
@@ -26,6 +95,10 @@ pub(crate) struct Backend {
     pub(crate) client: Client,
     /// Stores Url -> ProjectState for each Workspace.
     pub(crate) projects: Arc<RwLock<HashMap<Url, Arc<ProjectState>>>>,
+    /// Methods of the `DYNAMIC_FEATURES` currently registered with the client.
+    registered_features: Mutex<HashSet<&'static str>>,
+    /// Pending revalidation of each edited document; see `schedule_revalidation`.
+    revalidations: Debouncer<Url>,
 }
 
 impl Backend {
@@ -34,6 +107,8 @@ impl Backend {
         Self {
             client,
             projects: Arc::new(RwLock::new(HashMap::new())),
+            registered_features: Mutex::new(HashSet::new()),
+            revalidations: Debouncer::new(),
         }
     }
 
@@ -56,24 +131,20 @@ impl Backend {
 
     /// Find the workspace URI that most specifically contains the given document URI.
     ///
-    /// Converts the document URI to a file path and selects the registered workspace whose path is
-    /// the longest prefix of that document path (i.e., the deepest matching workspace).
+    /// Selects the registered workspace whose path is the longest prefix of the document path
+    /// (i.e., the deepest matching workspace). Workspaces with non-`file://` uris only match
+    /// documents of the same scheme and authority; when several match equally deep, the one whose
+    /// query (e.g. an `isfs://` namespace) equals the document's is preferred.
     fn find_parent_workspace(&self, uri: Url) -> Option<Url> {
         // start_of_function("Backend", "find_parent_workspace");
-        let doc_path: PathBuf = uri.to_file_path().ok()?;
-
         // find longest prefix
         let projects = self.projects.read();
 
         let parent = projects
             .keys()
             .filter_map(|ws_uri| {
-                let ws_path = ws_uri.to_file_path().ok()?;
-                if doc_path.starts_with(&ws_path) {
-                    Some((ws_path.components().count(), ws_uri.clone()))
-                } else {
-                    None
-                }
+                let depth = workspace_depth(ws_uri, &uri)?;
+                Some(((depth, ws_uri.query() == uri.query()), ws_uri.clone()))
             })
             .max_by_key(|(depth, _)| *depth)
             .map(|(_, ws_uri)| ws_uri);
@@ -84,8 +155,18 @@ impl Backend {
     /// Resolve the `ProjectState` associated with a document URI.
     ///
     /// This first finds the containing workspace (if any), then returns that project's state.
+    /// Classes downloaded from a server aren't in any workspace, so they belong to the project
+    /// that indexed them.
     pub(crate) fn get_project_from_document_url(&self, uri: &Url) -> Option<Arc<ProjectState>> {
         // start_of_function("Backend", "get_project_from_document_url");
+        if is_read_only_url(uri) {
+            return self
+                .projects
+                .read()
+                .values()
+                .find(|project| project.data.read().documents.contains_key(uri))
+                .cloned();
+        }
         let project_url = self.find_parent_workspace(uri.clone())?;
         let result = self.get_project(&project_url);
         // successful_exit("Backend", "get_project_from_document_url");
@@ -104,11 +185,107 @@ impl Backend {
         // successful_exit("Backend", "handle_did_open");
     }
 
-    /// Index all `.cls`, `.mac`, and `.inc` files under the workspace root containing `uri`.
+    /// Compute and publish the diagnostics for the document at `uri`.
+    ///
+    /// If no workspace tracks `uri`, this is a no-op.
+    pub(crate) async fn publish_diagnostics(&self, uri: Url) {
+        start_of_function("Backend", "publish_diagnostics");
+        let Some(project) = self.get_project_from_document_url(&uri) else {
+            generic_exit_statements("Backend", "publish_diagnostics");
+            return;
+        };
+        let (diagnostics, version) = {
+            let data = project.data.read();
+            let version = data.documents.get(&uri).and_then(|d| d.version);
+            (data.get_document_diagnostics(&uri), version)
+        };
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+        successful_exit("Backend", "publish_diagnostics");
+    }
+
+    /// Revalidate the document at `uri` once it went `REVALIDATION_DELAY` without edits: rebuild
+    /// its semantic model from the text and tree synced by `didChange`, then publish its
+    /// diagnostics.
+    ///
+    /// A revalidation still pending for the document is cancelled, so a burst of keystrokes is
+    /// only analyzed once.
+    pub(crate) fn schedule_revalidation(self: &Arc<Self>, uri: Url) {
+        let backend = Arc::clone(self);
+        self.revalidations
+            .schedule(uri.clone(), REVALIDATION_DELAY, async move {
+                backend.revalidate(uri).await;
+            });
+    }
+
+    /// Cancel the pending revalidation of the document at `uri`, e.g. once it is closed.
+    pub(crate) fn cancel_revalidation(&self, uri: &Url) {
+        self.revalidations.cancel(uri);
+    }
+
+    async fn revalidate(&self, uri: Url) {
+        start_of_function("Backend", "revalidate");
+        let Some(project) = self.get_project_from_document_url(&uri) else {
+            generic_exit_statements("Backend", "revalidate");
+            return;
+        };
+        let snapshot = project.data.read().documents.get(&uri).map(|document| {
+            (
+                document.content.clone(),
+                document.tree.clone(),
+                document.file_type.clone(),
+                document.version,
+            )
+        });
+        let Some((content, tree, file_type, Some(version))) = snapshot else {
+            generic_exit_statements("Backend", "revalidate");
+            return;
+        };
+        if tree.root_node().has_error() {
+            self.client
+                .log_message(
+                    MessageType::ERROR,
+                    "New Tree has Errors; the index keeps the last error-free version",
+                )
+                .await;
+        } else {
+            // rebuilding the model is CPU-heavy, so keep it off the async workers
+            let task_project = Arc::clone(&project);
+            let task_uri = uri.clone();
+            let handle = tokio::task::spawn_blocking(move || {
+                task_project.update_document(task_uri, tree, file_type, version, content);
+            });
+            if let Err(join_err) = handle.await {
+                eprintln!("update_document spawn_blocking failed: {join_err:?}");
+            }
+        }
+        self.publish_diagnostics(uri).await;
+        successful_exit("Backend", "revalidate");
+    }
+
+    /// Compute and publish the diagnostics of the documents extracted from the XML export
+    /// `archive`, reported against the archive file itself.
+    pub(crate) async fn publish_archive_diagnostics(&self, archive: Url) {
+        start_of_function("Backend", "publish_archive_diagnostics");
+        let Some(project) = self.get_project_from_document_url(&archive) else {
+            generic_exit_statements("Backend", "publish_archive_diagnostics");
+            return;
+        };
+        let diagnostics = project.data.read().get_archive_diagnostics(&archive);
+        self.client
+            .publish_diagnostics(archive, diagnostics, None)
+            .await;
+        successful_exit("Backend", "publish_archive_diagnostics");
+    }
+
+    /// Index all `.cls`, `.mac`, and `.inc` files (and XML export archives) under the workspace
+    /// root containing `uri`.
     ///
-    /// This runs filesystem walking and parsing on Tokio's blocking thread pool. Each file is read,
-    /// parsed with the appropriate Tree-sitter grammar, and inserted into the project's document
-    /// store if absent. After the scan, inheritance and variable information is built once.
+    /// Files are listed and read through the project's `SourceProvider`, so both `file://` and
+    /// remote (e.g. `isfs://`) workspaces are supported. The scan itself
+    /// (`ProjectState::index_workspace_scope`) runs on Tokio's blocking thread pool, while its
+    /// `IndexProgress` is reported as work-done progress.
     pub(crate) async fn index_workspace(&self, uri: &Url) {
         start_of_function("Backend", "index_workspace");
         let Some(project) = self.get_project_from_document_url(&uri) else {
@@ -119,155 +296,443 @@ impl Backend {
             generic_exit_statements("Backend", "index_workspace");
             return;
         };
-        let Some(root) = project.root_path() else {
+        let Some(root) = project.root_uri().cloned() else {
             self.client
-                .log_message(MessageType::ERROR, "project root path doesn't exist")
+                .log_message(MessageType::ERROR, "project root uri doesn't exist")
                 .await;
             generic_exit_statements("Backend", "index_workspace");
             return;
         };
-        let root = root.to_path_buf();
+        let sources = project.sources();
+        let atelier = project.data.read().config.atelier.clone();
+        let progress = self.create_progress(INDEX_PROGRESS_TOKEN).await;
+        let report = |value| self.report_progress(INDEX_PROGRESS_TOKEN, progress, value);
+        report(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: "Indexing ObjectScript workspace".to_string(),
+            percentage: Some(0),
+            ..Default::default()
+        }))
+        .await;
         // Run indexing on Tokio's blocking thread pool
-        let handle = tokio::task::spawn_blocking(move || {
-            let mut cls_parser = Parser::new();
-            if cls_parser
-                .set_language(&LANGUAGE_OBJECTSCRIPT.into())
-                .is_err()
-            {
-                eprintln!("Failed to load ObjectScript grammar");
-                generic_exit_statements("Backend", "index_workspace");
-                return;
+        let task_project = Arc::clone(&project);
+        let mut handle = tokio::task::spawn_blocking(move || {
+            task_project.index_workspace_scope(sources.as_ref(), &root);
+            if let Some(config) = atelier {
+                let remote = AtelierSourceProvider::new(config.clone());
+                task_project.fetch_remote_classes(&remote, &config);
             }
+        });
+        // Wait for completion (and handle join errors), reporting progress meanwhile
+        let mut ticks = tokio::time::interval(INDEX_PROGRESS_INTERVAL);
+        let mut last_report = None;
+        let joined = loop {
+            tokio::select! {
+                joined = &mut handle => break joined,
+                _ = ticks.tick() => {
+                    let current = project.index_progress.report();
+                    if last_report.as_ref() != Some(&current) {
+                        let (message, percentage) = current.clone();
+                        report(WorkDoneProgress::Report(WorkDoneProgressReport {
+                            message: Some(message),
+                            percentage: Some(percentage),
+                            ..Default::default()
+                        }))
+                        .await;
+                        last_report = Some(current);
+                    }
+                }
+            }
+        };
+        report(WorkDoneProgress::End(WorkDoneProgressEnd {
+            message: Some(format!(
+                "Indexed {} documents",
+                project.data.read().documents.len()
+            )),
+        }))
+        .await;
+        if let Err(join_err) = joined {
+            eprintln!("index_workspace_scope spawn_blocking failed: {join_err:?}");
+            generic_exit_statements("Backend", "index_workspace");
+            return;
+        }
+        // archives are never opened as documents, so report their diagnostics after indexing
+        let archives = self
+            .get_project_from_document_url(uri)
+            .map(|project| project.data.read().get_archive_urls())
+            .unwrap_or_default();
+        for archive in archives {
+            self.publish_archive_diagnostics(archive).await;
+        }
+        successful_exit("Backend", "index_workspace");
+    }
+
+    /// Register or unregister the config-gated `DYNAMIC_FEATURES` so the client only offers what
+    /// the current workspace configs enable.
+    ///
+    /// Features the client can't register dynamically were advertised (or not) in `initialize`
+    /// and are left alone.
+    pub(crate) async fn sync_dynamic_capabilities(&self) {
+        start_of_function("Backend", "sync_dynamic_capabilities");
+        let client_capabilities = get_client_capabilities();
+        let configs: Vec<Config> = self
+            .projects
+            .read()
+            .values()
+            .map(|project| project.data.read().config.clone())
+            .collect();
+        // (feature, whether it should be registered) for every feature whose state changes
+        let changes: Vec<_> = {
+            let registered = self.registered_features.lock();
+            DYNAMIC_FEATURES
+                .iter()
+                .filter(|feature| feature.is_dynamic(client_capabilities.as_ref()))
+                .map(|feature| (feature, feature.enabled_in(&configs)))
+                .filter(|(feature, enabled)| *enabled != registered.contains(feature.method))
+                .collect()
+        };
 
-            let mut routine_parser = Parser::new();
-            if routine_parser
-                .set_language(&LANGUAGE_OBJECTSCRIPT_CORE.into())
-                .is_err()
-            {
-                eprintln!("Failed to load ObjectScript Core grammar");
-                generic_exit_statements("Backend", "index_workspace");
-                return;
+        let registrations: Vec<Registration> = changes
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| Registration {
+                id: feature.method.to_string(),
+                method: feature.method.to_string(),
+                register_options: Some((feature.register_options)()),
+            })
+            .collect();
+        if !registrations.is_empty() {
+            match self.client.register_capability(registrations).await {
+                Ok(()) => self.registered_features.lock().extend(
+                    changes
+                        .iter()
+                        .filter(|(_, enabled)| *enabled)
+                        .map(|(feature, _)| feature.method),
+                ),
+                Err(e) => eprintln!("Error: failed to register dynamic capabilities: {e}"),
             }
+        }
 
-            let mut documents_already_existing = Vec::new();
-            for entry in WalkDir::new(&root).into_iter().filter_map(|e| e.ok()) {
-                let path = entry.path();
+        let unregistrations: Vec<Unregistration> = changes
+            .iter()
+            .filter(|(_, enabled)| !*enabled)
+            .map(|(feature, _)| Unregistration {
+                id: feature.method.to_string(),
+                method: feature.method.to_string(),
+            })
+            .collect();
+        if !unregistrations.is_empty() {
+            match self.client.unregister_capability(unregistrations).await {
+                Ok(()) => {
+                    let mut registered = self.registered_features.lock();
+                    for (feature, _) in changes.iter().filter(|(_, enabled)| !*enabled) {
+                        registered.remove(feature.method);
+                    }
+                }
+                Err(e) => eprintln!("Error: failed to unregister dynamic capabilities: {e}"),
+            }
+        }
+        successful_exit("Backend", "sync_dynamic_capabilities");
+    }
 
-                let Some(ext) = path.extension().and_then(|s| s.to_str()) else {
-                    continue;
-                };
+    /// Create the work-done progress `token` with the client.
+    ///
+    /// Progress is best effort: returns `false` if the client can't create the token, in which
+    /// case `report_progress` sends nothing.
+    async fn create_progress(&self, token: &str) -> bool {
+        self.client
+            .send_request::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: NumberOrString::String(token.to_string()),
+            })
+            .await
+            .is_ok()
+    }
 
-                let (filetype, use_core) = match ext {
-                    "cls" => (FileType::Cls, false),
-                    "inc" => (FileType::Inc, true),
-                    "mac" => (FileType::Mac, true),
-                    _ => continue,
-                };
+    /// Report `value` on the work-done progress `token`, if it was `created`.
+    async fn report_progress(&self, token: &str, created: bool, value: WorkDoneProgress) {
+        if created {
+            self.client
+                .send_notification::<Progress>(ProgressParams {
+                    token: NumberOrString::String(token.to_string()),
+                    value: ProgressParamsValue::WorkDone(value),
+                })
+                .await;
+        }
+    }
 
-                let code = match std::fs::read_to_string(path) {
-                    Ok(s) => s,
-                    Err(_) => {
-                        eprintln!("Error: Failed to read file contents: {}", path.display());
-                        let Some(path_as_str) = path.as_os_str().to_str() else {
-                            generic_skipping_statements(
-                                "index_workspace",
-                                "Couldn't get path str",
-                                "File Contents",
-                            );
-                            continue;
-                        };
-                        generic_skipping_statements(
-                            "index_workspace",
-                            path_as_str,
-                            "File contents for the following path",
-                        );
-                        continue;
+    /// Apply the watched-file `events` reported by the client to the projects containing them.
+    ///
+    /// Events are applied file by file unless a project received at least its
+    /// `bulk_change_threshold` events within `BULK_CHANGE_WINDOW` (e.g. on a branch switch) or
+    /// one of them can't be applied incrementally. The diagnostics of the open documents of
+    /// projects updated file by file are republished. Returns the roots of the projects that need
+    /// a full re-index (see `reindex_workspace`).
+    pub(crate) async fn apply_watched_files(&self, events: Vec<FileEvent>) -> Vec<Url> {
+        start_of_function("Backend", "apply_watched_files");
+        let mut by_project: HashMap<Url, Vec<FileEvent>> = HashMap::new();
+        for event in events {
+            if let Some(root) = self.find_parent_workspace(event.uri.clone()) {
+                by_project.entry(root).or_default().push(event);
+            }
+        }
+        let mut reindex = Vec::new();
+        for (root, events) in by_project {
+            let Some(project) = self.get_project(&root) else {
+                continue;
+            };
+            let threshold = project.data.read().config.bulk_change_threshold;
+            let bulk = project
+                .file_events
+                .lock()
+                .record(Instant::now(), events.len(), threshold);
+            if project.reindexing.load(Ordering::Acquire) {
+                // the running re-index may have read these files before they changed
+                project.reindex_pending.store(true, Ordering::Release);
+                continue;
+            }
+            if bulk {
+                reindex.push(root);
+                continue;
+            }
+            let task_project = Arc::clone(&project);
+            let sources = project.sources();
+            let handle = tokio::task::spawn_blocking(move || {
+                events
+                    .iter()
+                    .filter(|event| {
+                        !task_project.apply_watched_file_change(
+                            sources.as_ref(),
+                            &event.uri,
+                            event.typ,
+                        )
+                    })
+                    .count()
+            });
+            match handle.await {
+                Ok(0) => {
+                    // open documents may use the classes that changed on disk, e.g. inherit
+                    // from them
+                    let open_documents = project.data.read().get_open_document_urls();
+                    for url in open_documents {
+                        self.publish_diagnostics(url).await;
                     }
-                };
+                }
+                Ok(_) => reindex.push(root),
+                Err(join_err) => {
+                    eprintln!("apply_watched_file_change spawn_blocking failed: {join_err:?}");
+                    reindex.push(root);
+                }
+            }
+        }
+        successful_exit("Backend", "apply_watched_files");
+        reindex
+    }
 
-                let url = match Url::from_file_path(path) {
-                    Ok(u) => u,
-                    Err(_) => {
-                        eprintln!("Error: Failed to convert path to Url: {}", path.display());
-                        let Some(path_as_str) = path.as_os_str().to_str() else {
-                            generic_skipping_statements(
-                                "index_workspace",
-                                "Couldn't get path str",
-                                "Path",
-                            );
-                            continue;
-                        };
-                        generic_skipping_statements("index_workspace", path_as_str, "path");
-                        continue;
-                    }
-                };
+    /// Re-index the workspace at `root` in the background, reporting work-done progress, then
+    /// republish the diagnostics of its open documents and archives.
+    ///
+    /// The fresh index replaces the current one atomically (see
+    /// `ProjectState::reindex_workspace_scope`). If a re-index is already running, it runs once
+    /// more when it's done instead.
+    pub(crate) async fn reindex_workspace(&self, root: &Url) {
+        start_of_function("Backend", "reindex_workspace");
+        let Some(project) = self.get_project(root) else {
+            generic_exit_statements("Backend", "reindex_workspace");
+            return;
+        };
+        if project.reindexing.swap(true, Ordering::AcqRel) {
+            project.reindex_pending.store(true, Ordering::Release);
+            generic_exit_statements("Backend", "reindex_workspace");
+            return;
+        }
+        let progress = self.create_progress(REINDEX_PROGRESS_TOKEN).await;
+        self.report_progress(
+            REINDEX_PROGRESS_TOKEN,
+            progress,
+            WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: "Re-indexing workspace".to_string(),
+                message: Some("Many files changed at once".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await;
+        loop {
+            project.reindex_pending.store(false, Ordering::Release);
+            let task_project = Arc::clone(&project);
+            let task_root = project.root_uri().cloned().unwrap_or_else(|| root.clone());
+            let sources = project.sources();
+            let handle = tokio::task::spawn_blocking(move || {
+                task_project.reindex_workspace_scope(sources.as_ref(), &task_root);
+            });
+            if let Err(join_err) = handle.await {
+                eprintln!("reindex_workspace_scope spawn_blocking failed: {join_err:?}");
+                break;
+            }
+            if !project.reindex_pending.load(Ordering::Acquire) {
+                break;
+            }
+        }
+        project.reindexing.store(false, Ordering::Release);
+        self.report_progress(
+            REINDEX_PROGRESS_TOKEN,
+            progress,
+            WorkDoneProgress::End(WorkDoneProgressEnd {
+                message: Some(format!(
+                    "Indexed {} documents",
+                    project.data.read().documents.len()
+                )),
+            }),
+        )
+        .await;
 
-                let tree = if use_core {
-                    match routine_parser.parse(&code, None) {
-                        Some(t) => t,
-                        None => {
-                            eprintln!("Failed to parse file: {:?}", path);
-                            generic_skipping_statements(
-                                "index_workspace",
-                                code.as_str(),
-                                "File contents",
-                            );
-                            continue;
-                        }
-                    }
-                } else {
-                    match cls_parser.parse(&code, None) {
-                        Some(t) => t,
-                        None => {
-                            eprintln!("Failed to parse file: {:?}", path);
-                            generic_skipping_statements(
-                                "index_workspace",
-                                code.as_str(),
-                                "File contents",
-                            );
-                            continue;
-                        }
-                    }
-                };
+        let (open_documents, archives) = {
+            let data = project.data.read();
+            (data.get_open_document_urls(), data.get_archive_urls())
+        };
+        for url in open_documents {
+            self.publish_diagnostics(url).await;
+        }
+        for archive in archives {
+            self.publish_archive_diagnostics(archive).await;
+        }
+        successful_exit("Backend", "reindex_workspace");
+    }
 
-                // Only compute class_name for cls files; mac/inc don't have a class name.
-                let class_name = if filetype == FileType::Cls {
-                    get_class_name_from_root(code.as_str(), tree.root_node())
-                } else {
-                    Some("TODO".to_string())
-                };
+    /// Apply every auto-fixable diagnostic in the documents of `project`, one workspace edit per
+    /// file, reporting work-done progress as files are processed.
+    ///
+    /// Returns the number of files and edits applied as JSON.
+    pub(crate) async fn fix_all_in_workspace(&self, project: &ProjectState) -> Value {
+        start_of_function("Backend", "fix_all_in_workspace");
+        let mut urls: Vec<Url> = project.data.read().documents.keys().cloned().collect();
+        urls.sort();
 
-                let Some(class_name) = class_name else {
-                    eprintln!("No class Name");
-                    continue;
-                };
+        let run = FIX_ALL_RUNS.fetch_add(1, Ordering::Relaxed);
+        let token = format!("{FIX_ALL_PROGRESS_TOKEN}/{run}");
+        let progress = self.create_progress(&token).await;
+        let report = |value| self.report_progress(&token, progress, value);
+        report(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: "Fixing all auto-fixable diagnostics".to_string(),
+            percentage: Some(0),
+            ..Default::default()
+        }))
+        .await;
 
-                // Commit inside the ProjectData lock
-                {
-                    let mut data = project.data.write();
-                    let already_exists = data.add_document_if_absent(
-                        url.clone(),
-                        code,
-                        tree,
-                        filetype,
-                        class_name,
-                        None,
-                    );
-                    if already_exists {
-                        documents_already_existing.push(url);
+        let (mut files, mut edits) = (0, 0);
+        for (index, url) in urls.iter().enumerate() {
+            let file_edits = project.data.read().get_fix_all_edits(url);
+            if !file_edits.is_empty() {
+                let count = file_edits.len();
+                let edit = WorkspaceEdit {
+                    changes: Some(HashMap::from([(url.clone(), file_edits)])),
+                    ..Default::default()
+                };
+                match self.client.apply_edit(edit).await {
+                    Ok(response) if response.applied => {
+                        files += 1;
+                        edits += count;
                     }
+                    Ok(response) => eprintln!(
+                        "Warning: client did not apply fixes to {url}: {:?}",
+                        response.failure_reason
+                    ),
+                    Err(e) => eprintln!("Error: failed to apply fixes to {url}: {e}"),
                 }
             }
-            {
-                let mut data = project.data.write();
-                data.build_inheritance_and_variables(None, documents_already_existing);
+            report(WorkDoneProgress::Report(WorkDoneProgressReport {
+                message: Some(format!("{}/{} files", index + 1, urls.len())),
+                percentage: Some(((index + 1) * 100 / urls.len()) as u32),
+                ..Default::default()
+            }))
+            .await;
+        }
+
+        report(WorkDoneProgress::End(WorkDoneProgressEnd {
+            message: Some(format!("Applied {edits} fixes in {files} files")),
+        }))
+        .await;
+        successful_exit("Backend", "fix_all_in_workspace");
+        json!({ "files": files, "edits": edits })
+    }
+
+    /// Creates the file of the class `class_name` referenced from `uri` (see
+    /// `ProjectData::get_new_class_url`) through the client, then indexes it right away rather
+    /// than waiting for the file watcher.
+    ///
+    /// Returns the uri of the new file with an `applied` flag.
+    pub(crate) async fn create_class(
+        &self,
+        project: &ProjectState,
+        uri: &Url,
+        class_name: &str,
+    ) -> Option<Value> {
+        start_of_function("Backend", "create_class");
+        let new_url = project
+            .data
+            .read()
+            .get_new_class_url(uri, class_name, project.root_uri());
+        let Some(new_url) = new_url else {
+            self.client
+                .log_message(
+                    MessageType::ERROR,
+                    format!("Not creating class {class_name}: it exists already or has no path"),
+                )
+                .await;
+            generic_exit_statements("Backend", "create_class");
+            return None;
+        };
+        let content = class_skeleton(class_name);
+        let mut applied = false;
+        match self
+            .client
+            .apply_edit(create_class_edit(&new_url, &content))
+            .await
+        {
+            Ok(response) if response.applied => applied = true,
+            Ok(response) => eprintln!(
+                "Warning: client did not create class {class_name}: {:?}",
+                response.failure_reason
+            ),
+            Err(e) => eprintln!("Error: failed to create class {class_name}: {e}"),
+        }
+        if applied {
+            project.index_class_file(&new_url, content);
+        }
+        successful_exit("Backend", "create_class");
+        Some(json!({ "uri": new_url, "applied": applied }))
+    }
+
+    /// Applies a package rename, or only previews it when `dry_run` is set or when renamed
+    /// classes would collide with existing ones.
+    ///
+    /// Returns the preview (see `PackageRename::to_preview_json`) with an `applied` flag.
+    pub(crate) async fn rename_package(&self, rename: PackageRename, dry_run: bool) -> Value {
+        start_of_function("Backend", "rename_package");
+        let mut preview = rename.to_preview_json();
+        let mut applied = false;
+        if !dry_run && !rename.conflicts.is_empty() {
+            self.client
+                .log_message(
+                    MessageType::ERROR,
+                    format!(
+                        "Not renaming {} to {}: {} already exist",
+                        rename.from,
+                        rename.to,
+                        rename.conflicts.join(", ")
+                    ),
+                )
+                .await;
+        } else if !dry_run {
+            match self.client.apply_edit(rename.to_workspace_edit()).await {
+                Ok(response) if response.applied => applied = true,
+                Ok(response) => eprintln!(
+                    "Warning: client did not apply the rename of {}: {:?}",
+                    rename.from, response.failure_reason
+                ),
+                Err(e) => eprintln!("Error: failed to rename {}: {e}", rename.from),
             }
-        });
-        // Wait for completion (and handle join errors)
-        if let Err(join_err) = handle.await {
-            eprintln!("index_workspace_scope spawn_blocking failed: {join_err:?}");
-            generic_exit_statements("Backend", "index_workspace");
         }
-        successful_exit("Backend", "index_workspace");
+        preview["applied"] = Value::Bool(applied);
+        successful_exit("Backend", "rename_package");
+        preview
     }
 }
@@ -0,0 +1,209 @@
+use crate::common::{start_of_function, successful_exit};
+use crate::global_semantic::GlobalSemanticModel;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The project's symbols at one point in time.
+///
+/// Symbols are keyed by a qualified name (`Class Pkg.Cls`, `Method Pkg.Cls.Name`,
+/// `Property Pkg.Cls.Name`, `Parameter Pkg.Cls.Name`) and map to a signature string; two
+/// snapshots holding the same key with different signatures report that symbol as changed.
+#[derive(Clone, Debug)]
+pub struct IndexSnapshot {
+    /// Id of the snapshot, increasing in the order snapshots were taken.
+    pub id: u64,
+    /// Seconds since the Unix epoch when the snapshot was taken.
+    pub taken_at: u64,
+    /// Why the snapshot was taken (e.g. `workspace indexed` or a user-provided label).
+    pub label: String,
+    /// Qualified symbol name -> signature.
+    symbols: BTreeMap<String, String>,
+}
+
+impl IndexSnapshot {
+    /// Summarizes the snapshot (without its symbols) as JSON.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "takenAt": self.taken_at,
+            "label": self.label,
+            "symbolCount": self.symbols.len(),
+        })
+    }
+}
+
+/// Symbols that differ between two snapshots.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SnapshotDiff {
+    /// Symbols only present in the newer snapshot.
+    pub added: Vec<String>,
+    /// Symbols only present in the older snapshot.
+    pub removed: Vec<String>,
+    /// Symbols present in both, as `(name, old signature, new signature)`.
+    pub changed: Vec<(String, String, String)>,
+}
+
+impl SnapshotDiff {
+    /// Renders the diff as JSON.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "added": self.added,
+            "removed": self.removed,
+            "changed": self
+                .changed
+                .iter()
+                .map(|(name, old, new)| json!({ "symbol": name, "before": old, "after": new }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Bounded history of `IndexSnapshot`s; the oldest snapshot is dropped once `limit` is reached.
+#[derive(Clone, Debug, Default)]
+pub struct IndexSnapshots {
+    snapshots: VecDeque<IndexSnapshot>,
+    next_id: u64,
+}
+
+impl IndexSnapshots {
+    /// Creates an empty snapshot history.
+    pub fn new() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Records the current symbols of `gsm`, keeping at most `limit` snapshots.
+    ///
+    /// Returns the id of the new snapshot, or `None` if `limit` is 0 (snapshots disabled).
+    pub fn take(&mut self, gsm: &GlobalSemanticModel, label: String, limit: usize) -> Option<u64> {
+        start_of_function("IndexSnapshots", "take");
+        if limit == 0 {
+            successful_exit("IndexSnapshots", "take");
+            return None;
+        }
+        self.next_id += 1;
+        let id = self.next_id;
+        let taken_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.snapshots.push_back(IndexSnapshot {
+            id,
+            taken_at,
+            label,
+            symbols: capture_symbols(gsm),
+        });
+        while self.snapshots.len() > limit {
+            self.snapshots.pop_front();
+        }
+        successful_exit("IndexSnapshots", "take");
+        Some(id)
+    }
+
+    /// Returns the retained snapshots, oldest first.
+    pub fn list(&self) -> impl Iterator<Item = &IndexSnapshot> {
+        self.snapshots.iter()
+    }
+
+    /// Returns the retained snapshot with the given id.
+    pub fn get(&self, id: u64) -> Option<&IndexSnapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.id == id)
+    }
+
+    /// Returns the ids of the two most recent snapshots as `(older, newer)`.
+    pub fn latest_pair(&self) -> Option<(u64, u64)> {
+        let mut newest = self.snapshots.iter().rev();
+        let newer = newest.next()?.id;
+        let older = newest.next()?.id;
+        Some((older, newer))
+    }
+
+    /// Diffs snapshot `from` against snapshot `to`.
+    ///
+    /// Returns `None` if either snapshot is no longer retained.
+    pub fn diff(&self, from: u64, to: u64) -> Option<SnapshotDiff> {
+        let (old, new) = (&self.get(from)?.symbols, &self.get(to)?.symbols);
+        let mut diff = SnapshotDiff::default();
+        for (name, signature) in new {
+            match old.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(previous) if previous != signature => {
+                    diff.changed
+                        .push((name.clone(), previous.clone(), signature.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect();
+        Some(diff)
+    }
+}
+
+/// Collects the qualified names and signatures of every active class and its members.
+fn capture_symbols(gsm: &GlobalSemanticModel) -> BTreeMap<String, String> {
+    let mut symbols = BTreeMap::new();
+    for (class_id, class) in gsm.classes.iter() {
+        if !class.active {
+            continue;
+        }
+        let parents: Vec<&str> = class
+            .inherited_classes
+            .iter()
+            .filter_map(|parent| gsm.classes.get(*parent))
+            .map(|parent| parent.name.as_str())
+            .collect();
+        symbols.insert(
+            format!("Class {}", class.name),
+            format!(
+                "Extends ({}) ProcedureBlock={:?} Language={:?}",
+                parents.join(", "),
+                class.is_procedure_block,
+                class.default_language
+            ),
+        );
+        if let Some(methods) = gsm.methods.get(&class_id) {
+            for method in methods {
+                symbols.insert(
+                    format!("Method {}.{}", class.name, method.name),
+                    format!(
+                        "{:?} Public={} Returns={:?} ProcedureBlock={:?} Language={:?} CodeMode={:?}",
+                        method.method_type,
+                        method.is_public,
+                        method.return_type,
+                        method.is_procedure_block,
+                        method.language,
+                        method.code_mode
+                    ),
+                );
+            }
+        }
+        for name in class.private_methods.keys() {
+            symbols.insert(
+                format!("Method {}.{}", class.name, name),
+                "Private".to_string(),
+            );
+        }
+        for (properties, visibility) in [
+            (&class.public_properties, "Public"),
+            (&class.private_properties, "Private"),
+        ] {
+            for name in properties.keys() {
+                symbols.insert(
+                    format!("Property {}.{}", class.name, name),
+                    visibility.to_string(),
+                );
+            }
+        }
+        for name in class.parameters.keys() {
+            symbols.insert(format!("Parameter {}.{}", class.name, name), String::new());
+        }
+    }
+    symbols
+}
@@ -0,0 +1,217 @@
+use crate::common::workspace_depth;
+use crate::xml_export::{is_archive_url, ARCHIVE_EXTENSIONS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use tokio::runtime::Handle;
+use tower_lsp::jsonrpc::ErrorCode;
+use tower_lsp::lsp_types::request::Request;
+use tower_lsp::lsp_types::{FileSystemWatcher, GlobPattern, MessageType, Url, WatchKind};
+use tower_lsp::Client;
+use walkdir::WalkDir;
+
+/// File extensions of the ObjectScript sources the indexer understands.
+pub const SOURCE_EXTENSIONS: [&str; 3] = ["cls", "mac", "inc"];
+
+/// Access to the source files of a workspace.
+///
+/// The indexer reads files only through this trait, so workspaces that don't live on the local
+/// disk (e.g. the `isfs://` virtual filesystem of the VS Code ObjectScript extension) can be
+/// analyzed the same way as `file://` folders.
+pub trait SourceProvider: Debug + Send + Sync {
+    /// Lists the ObjectScript source files (see `SOURCE_EXTENSIONS`) and XML export archives (see
+    /// `ARCHIVE_EXTENSIONS`) under the workspace `root`.
+    fn list_files(&self, root: &Url) -> Vec<Url>;
+
+    /// Reads the contents of the file at `url`.
+    fn read_file(&self, url: &Url) -> io::Result<String>;
+
+    /// Returns the file watchers the client should register to report changes under `root`.
+    ///
+    /// Providers whose files the client can't watch return no watchers.
+    fn watch(&self, _root: &Url) -> Vec<FileSystemWatcher> {
+        Vec::new()
+    }
+}
+
+/// Returns `true` if the path of `url` ends in one of `SOURCE_EXTENSIONS`.
+pub fn is_source_url(url: &Url) -> bool {
+    url.path()
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| SOURCE_EXTENSIONS.contains(&ext))
+}
+
+/// Returns `true` if `url` is a source file or an export archive, i.e. should be listed by a
+/// `SourceProvider`.
+pub fn is_indexable_url(url: &Url) -> bool {
+    is_source_url(url) || is_archive_url(url)
+}
+
+/// `SourceProvider` for `file://` workspaces, backed by `std::fs`.
+#[derive(Debug, Default)]
+pub struct FsSourceProvider;
+
+impl SourceProvider for FsSourceProvider {
+    fn list_files(&self, root: &Url) -> Vec<Url> {
+        let Ok(root) = root.to_file_path() else {
+            eprintln!("Error: workspace root is not a file path: {root}");
+            return Vec::new();
+        };
+        WalkDir::new(&root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter_map(|entry| Url::from_file_path(entry.path()).ok())
+            .filter(is_indexable_url)
+            .collect()
+    }
+
+    fn read_file(&self, url: &Url) -> io::Result<String> {
+        let path = url
+            .to_file_path()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "not a file url"))?;
+        std::fs::read_to_string(path)
+    }
+
+    fn watch(&self, _root: &Url) -> Vec<FileSystemWatcher> {
+        SOURCE_EXTENSIONS
+            .into_iter()
+            .chain(ARCHIVE_EXTENSIONS)
+            .map(|ext| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(format!("**/*.{ext}")),
+                kind: Some(WatchKind::Create | WatchKind::Change | WatchKind::Delete),
+            })
+            .collect()
+    }
+}
+
+/// In-memory `SourceProvider`, used for test fixtures and for sources extracted from archives.
+#[derive(Debug, Default)]
+pub struct MemorySourceProvider {
+    files: HashMap<Url, String>,
+}
+
+impl MemorySourceProvider {
+    /// Creates an empty `MemorySourceProvider`.
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+        }
+    }
+
+    /// Adds (or replaces) the file at `url`.
+    pub fn insert(&mut self, url: Url, content: String) {
+        self.files.insert(url, content);
+    }
+}
+
+impl SourceProvider for MemorySourceProvider {
+    fn list_files(&self, root: &Url) -> Vec<Url> {
+        let mut files: Vec<Url> = self
+            .files
+            .keys()
+            .filter(|url| is_indexable_url(url) && workspace_depth(root, url).is_some())
+            .cloned()
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn read_file(&self, url: &Url) -> io::Result<String> {
+        self.files
+            .get(url)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, url.to_string()))
+    }
+}
+
+/// Parameters of the `objectscript/listFiles` and `objectscript/readFile` requests:
+/// `{ "uri": "isfs://server/user/" }`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourceUriParams {
+    /// Workspace root (for `listFiles`) or document (for `readFile`) uri.
+    pub uri: Url,
+}
+
+/// Server -> client request listing the ObjectScript documents under a workspace folder.
+///
+/// The client answers with the uris of the files under `uri`, at any depth, e.g.
+/// `["isfs://server/user/Demo/Person.cls"]`. Uris that aren't sources or export archives are
+/// ignored.
+pub enum ListFiles {}
+
+impl Request for ListFiles {
+    type Params = SourceUriParams;
+    type Result = Vec<Url>;
+    const METHOD: &'static str = "objectscript/listFiles";
+}
+
+/// Server -> client request returning the text of a document.
+///
+/// The client answers with the content of the file at `uri` as a string, or an error response
+/// if it can't be read.
+pub enum ReadFile {}
+
+impl Request for ReadFile {
+    type Params = SourceUriParams;
+    type Result = String;
+    const METHOD: &'static str = "objectscript/readFile";
+}
+
+/// `SourceProvider` for workspaces with non-`file://` uris.
+///
+/// The server can't reach virtual filesystems such as `isfs://` itself, so files are listed and
+/// read by asking the client through the `objectscript/listFiles` and `objectscript/readFile`
+/// requests. These are extensions of this server, so a client that doesn't implement them gets
+/// only the documents it opens analyzed, from their `didOpen` content. Calls block on the Tokio
+/// runtime and must be made from a blocking thread (e.g. inside `spawn_blocking`), never from an
+/// async task.
+#[derive(Debug)]
+pub struct ClientSourceProvider {
+    client: Client,
+    runtime: Handle,
+}
+
+impl ClientSourceProvider {
+    /// Creates a provider that forwards file access to `client`.
+    ///
+    /// Must be called from within the Tokio runtime.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            runtime: Handle::current(),
+        }
+    }
+}
+
+impl SourceProvider for ClientSourceProvider {
+    fn list_files(&self, root: &Url) -> Vec<Url> {
+        let params = SourceUriParams { uri: root.clone() };
+        match self
+            .runtime
+            .block_on(self.client.send_request::<ListFiles>(params))
+        {
+            Ok(files) => files.into_iter().filter(is_indexable_url).collect(),
+            Err(e) if e.code == ErrorCode::MethodNotFound => {
+                let message = format!(
+                    "The client doesn't implement {}, so only the documents it opens are analyzed in {root}",
+                    ListFiles::METHOD
+                );
+                self.runtime
+                    .block_on(self.client.log_message(MessageType::WARNING, message));
+                Vec::new()
+            }
+            Err(e) => {
+                eprintln!("Error: client failed to list files under {root}: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    fn read_file(&self, url: &Url) -> io::Result<String> {
+        let params = SourceUriParams { uri: url.clone() };
+        self.runtime
+            .block_on(self.client.send_request::<ReadFile>(params))
+            .map_err(|e| io::Error::other(e.to_string()))
+    }
+}
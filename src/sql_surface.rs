@@ -0,0 +1,163 @@
+use crate::common::{find_class_definition, get_node_children, start_of_function, successful_exit};
+use crate::diagnostics::split_top_level;
+use serde::Deserialize;
+use tree_sitter::{Node, Range, Tree};
+
+/// Parameters of the `objectscript/sqlSurface` request.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlSurfaceParams {
+    /// SQL identifier to look up, qualified (`Schema.Name`) or not; every projection is returned
+    /// when absent.
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+/// What a `SqlProjection` projects.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SqlProjectionKind {
+    /// A class method with `SqlProc`.
+    Procedure,
+    /// A class query with `SqlProc`.
+    Query,
+}
+
+impl SqlProjectionKind {
+    /// Name of the kind in `objectscript/sqlSurface` responses.
+    pub fn label(&self) -> &'static str {
+        match self {
+            SqlProjectionKind::Procedure => "procedure",
+            SqlProjectionKind::Query => "query",
+        }
+    }
+}
+
+/// A class member projected to SQL as a stored procedure.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SqlProjection {
+    /// SQL schema, derived from the package name.
+    pub schema: String,
+    /// Unqualified SQL name: `SqlName` if given, otherwise `<Class>_<Member>`.
+    pub name: String,
+    pub kind: SqlProjectionKind,
+    pub class_name: String,
+    pub member_name: String,
+    /// Range of the member name.
+    pub range: Range,
+}
+
+impl SqlProjection {
+    /// The schema-qualified SQL name, e.g. `Sample.Person_Lookup`.
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+
+    /// Returns `true` if the SQL identifier `query` (qualified or not, quoted or not) names this
+    /// projection. SQL identifiers are case-insensitive.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().replace('"', "");
+        query.eq_ignore_ascii_case(&self.name) || query.eq_ignore_ascii_case(&self.qualified_name())
+    }
+}
+
+/// Returns the default SQL schema of `class_name`: its package with `.` replaced by `_`, where
+/// the `User` package maps to `SQLUser`.
+pub fn default_schema(class_name: &str) -> String {
+    match class_name.rsplit_once('.') {
+        Some((package, _)) if !package.eq_ignore_ascii_case("User") => package.replace('.', "_"),
+        _ => "SQLUser".to_string(),
+    }
+}
+
+/// Reads the `SqlProc` and `SqlName` keywords of a member from the text of its `[ ... ]` keyword
+/// list, as `(sql_proc, sql_name)`.
+fn sql_keywords(keywords: &str) -> (bool, Option<String>) {
+    let keywords = keywords
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let mut sql_proc = false;
+    let mut sql_name = None;
+    for keyword in split_top_level(keywords) {
+        let (name, value) = match keyword.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (keyword, None),
+        };
+        if name.eq_ignore_ascii_case("SqlProc") {
+            sql_proc = value.is_none_or(|value| value != "0");
+        } else if name
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .eq_ignore_ascii_case("Not SqlProc")
+        {
+            sql_proc = false;
+        } else if name.eq_ignore_ascii_case("SqlName") {
+            sql_name = value.map(|value| value.trim_matches('"').to_string());
+        }
+    }
+    (sql_proc, sql_name)
+}
+
+/// Finds the keyword list node (`method_keywords`, `query_keywords`, ...) of a member definition.
+fn keywords_node(definition: Node) -> Option<Node> {
+    get_node_children(definition)
+        .into_iter()
+        .find(|child| child.kind().ends_with("_keywords"))
+}
+
+/// Finds the class methods and class queries of the class `class_name` defined by `tree` that are
+/// projected to SQL as stored procedures (`SqlProc`), with their projected names.
+pub fn sql_projections(content: &str, tree: &Tree, class_name: &str) -> Vec<SqlProjection> {
+    start_of_function("SQL SURFACE (no struct)", "sql_projections");
+    let mut projections = Vec::new();
+    let Some(class_definition) = find_class_definition(tree.root_node()) else {
+        successful_exit("SQL SURFACE (no struct)", "sql_projections");
+        return projections;
+    };
+    let short_name = class_name.rsplit('.').next().unwrap_or(class_name);
+    for class_body in get_node_children(class_definition) {
+        if class_body.kind() != "class_body" {
+            continue;
+        }
+        for statement in get_node_children(class_body) {
+            // class_statement -> statement type -> definition, whose child(0) is the name
+            let Some(statement_type) = statement.named_child(0) else {
+                continue;
+            };
+            let kind = match statement_type.kind() {
+                "classmethod" => SqlProjectionKind::Procedure,
+                "query" => SqlProjectionKind::Query,
+                _ => continue,
+            };
+            let Some(definition) = statement_type.named_child(1) else {
+                continue;
+            };
+            let (Some(name_node), Some(keywords)) =
+                (definition.named_child(0), keywords_node(definition))
+            else {
+                continue;
+            };
+            let (Some(member_name), Some(keywords)) = (
+                content.get(name_node.byte_range()),
+                content.get(keywords.byte_range()),
+            ) else {
+                continue;
+            };
+            let (sql_proc, sql_name) = sql_keywords(keywords);
+            if !sql_proc {
+                continue;
+            }
+            projections.push(SqlProjection {
+                schema: default_schema(class_name),
+                name: sql_name.unwrap_or_else(|| format!("{short_name}_{member_name}")),
+                kind,
+                class_name: class_name.to_string(),
+                member_name: member_name.to_string(),
+                range: name_node.range(),
+            });
+        }
+    }
+    successful_exit("SQL SURFACE (no struct)", "sql_projections");
+    projections
+}
@@ -0,0 +1,111 @@
+use crate::scope_structures::{GlobalSymbolId, ScopeId, VariableSymbolId};
+use tree_sitter::Range;
+
+/// The symbol (definition) or reference that occupies a range of a document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SymbolRef {
+    /// Definition of a class, public method, or public variable.
+    Global(GlobalSymbolId),
+    /// Definition of a private method, keyed by method name in the document's `ScopeTree`.
+    PrivateMethod(String),
+    /// Definition of a private variable in the given scope.
+    PrivateVariable(ScopeId, VariableSymbolId),
+    /// A use of a private variable defined in the given scope.
+    PrivateVariableReference(ScopeId, VariableSymbolId),
+    /// A method call, stored as the index into the document class' `Class::method_calls`.
+    MethodCall(usize),
+}
+
+/// A single entry in a `SymbolLocationIndex`.
+#[derive(Clone, Debug)]
+pub struct SymbolEntry {
+    /// Source range covered by the symbol or reference.
+    pub range: Range,
+    /// What is located at `range`.
+    pub symbol: SymbolRef,
+}
+
+/// Per-document interval tree mapping byte ranges to symbols and references.
+///
+/// Entries are kept sorted by start byte and viewed as an implicit balanced binary tree (the
+/// midpoint of each slice is the subtree root). `max_end[i]` stores the largest end byte in the
+/// subtree rooted at `i`, which lets point queries skip every subtree that ends before the cursor,
+/// so a lookup costs O(log n + k) for k overlapping entries.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolLocationIndex {
+    /// Entries sorted by `range.start_byte`.
+    entries: Vec<SymbolEntry>,
+    /// Maximum `range.end_byte` of the implicit subtree rooted at each entry.
+    max_end: Vec<usize>,
+}
+
+impl SymbolLocationIndex {
+    /// Creates an empty `SymbolLocationIndex`.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            max_end: Vec::new(),
+        }
+    }
+
+    /// Builds the index from an unordered list of entries.
+    pub fn from_entries(mut entries: Vec<SymbolEntry>) -> Self {
+        entries.sort_by_key(|entry| (entry.range.start_byte, entry.range.end_byte));
+        let mut max_end = vec![0; entries.len()];
+        Self::build_max_end(&entries, &mut max_end, 0, entries.len());
+        Self { entries, max_end }
+    }
+
+    /// Fills `max_end` for the implicit subtree over `entries[lo..hi]`, returning its max end byte.
+    fn build_max_end(
+        entries: &[SymbolEntry],
+        max_end: &mut [usize],
+        lo: usize,
+        hi: usize,
+    ) -> usize {
+        if lo >= hi {
+            return 0;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::build_max_end(entries, max_end, lo, mid);
+        let right = Self::build_max_end(entries, max_end, mid + 1, hi);
+        max_end[mid] = entries[mid].range.end_byte.max(left).max(right);
+        max_end[mid]
+    }
+
+    /// Collects every entry in `entries[lo..hi]` whose range contains `byte` (end inclusive, so
+    /// a cursor placed right after an identifier still resolves to it).
+    fn collect_at<'a>(&'a self, byte: usize, lo: usize, hi: usize, out: &mut Vec<&'a SymbolEntry>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] < byte {
+            return;
+        }
+        self.collect_at(byte, lo, mid, out);
+        let entry = &self.entries[mid];
+        if entry.range.start_byte > byte {
+            // every entry to the right starts even later
+            return;
+        }
+        if byte <= entry.range.end_byte {
+            out.push(entry);
+        }
+        self.collect_at(byte, mid + 1, hi, out);
+    }
+
+    /// Returns every entry whose range contains `byte`.
+    pub fn symbols_at(&self, byte: usize) -> Vec<&SymbolEntry> {
+        let mut out = Vec::new();
+        self.collect_at(byte, 0, self.entries.len(), &mut out);
+        out
+    }
+
+    /// Returns the innermost (smallest) entry whose range contains `byte`.
+    pub fn symbol_at(&self, byte: usize) -> Option<&SymbolEntry> {
+        self.symbols_at(byte)
+            .into_iter()
+            .min_by_key(|entry| entry.range.end_byte - entry.range.start_byte)
+    }
+}
@@ -0,0 +1,763 @@
+use crate::common::{position_encoding, start_of_function, successful_exit, ts_range_to_lsp_range};
+use crate::config::MethodInsertStyle;
+use crate::diagnostics::{
+    byte_range, end_of_line, skip_string, skip_string_or_comment, starts_with_ignore_case, Dialect,
+    DIAGNOSTIC_SOURCE,
+};
+use crate::rules::{rule_code, rule_code_description, SYSTEM_API_ARGUMENTS_RULE};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
+    Documentation, InsertTextFormat, MarkupContent, MarkupKind, ParameterInformation,
+    ParameterLabel, Position, Range as LspRange, SignatureHelp, SignatureInformation, TextEdit,
+};
+use tree_sitter::Range;
+
+/// An argument of a `SystemMethod`.
+#[derive(Debug)]
+pub struct SystemArgument {
+    pub name: &'static str,
+    /// Declared type, e.g. `%String`; `ByRef` arguments include the keyword.
+    pub type_name: &'static str,
+    /// Whether the argument has a default and may be omitted.
+    pub optional: bool,
+}
+
+/// A required argument.
+const fn arg(name: &'static str, type_name: &'static str) -> SystemArgument {
+    SystemArgument {
+        name,
+        type_name,
+        optional: false,
+    }
+}
+
+/// An optional argument.
+const fn opt(name: &'static str, type_name: &'static str) -> SystemArgument {
+    SystemArgument {
+        name,
+        type_name,
+        optional: true,
+    }
+}
+
+/// A class method of a `SystemClass`.
+#[derive(Debug)]
+pub struct SystemMethod {
+    pub name: &'static str,
+    pub arguments: &'static [SystemArgument],
+    /// Return type, empty for methods that return nothing.
+    pub return_type: &'static str,
+    pub doc: &'static str,
+}
+
+impl SystemMethod {
+    /// Renders the method signature, e.g. `IsOK(status As %Status) As %Boolean`.
+    pub fn signature(&self) -> String {
+        let arguments: Vec<String> = self
+            .arguments
+            .iter()
+            .map(|argument| format!("{} As {}", argument.name, argument.type_name))
+            .collect();
+        let mut signature = format!("{}({})", self.name, arguments.join(", "));
+        if !self.return_type.is_empty() {
+            signature.push_str(" As ");
+            signature.push_str(self.return_type);
+        }
+        signature
+    }
+
+    /// Number of arguments that can't be omitted.
+    pub fn required_arguments(&self) -> usize {
+        self.arguments
+            .iter()
+            .filter(|argument| !argument.optional)
+            .count()
+    }
+}
+
+/// A `%SYSTEM` class, reached as `$SYSTEM.<name>`.
+#[derive(Debug)]
+pub struct SystemClass {
+    /// Name after `$SYSTEM.`, e.g. `OBJ`.
+    pub name: &'static str,
+    pub doc: &'static str,
+    pub methods: &'static [SystemMethod],
+}
+
+impl SystemClass {
+    /// The full class name, e.g. `%SYSTEM.OBJ`.
+    pub fn class_name(&self) -> String {
+        format!("%SYSTEM.{}", self.name)
+    }
+
+    /// Returns the method named `name`; method names are case-sensitive.
+    pub fn method(&self, name: &str) -> Option<&'static SystemMethod> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+/// Stubs of the commonly used `%SYSTEM` classes.
+pub const SYSTEM_CLASSES: [SystemClass; 7] = [
+    SystemClass {
+        name: "Encryption",
+        doc: "Encoding, hashing, and encryption functions.",
+        methods: &[
+            SystemMethod {
+                name: "Base64Decode",
+                arguments: &[arg("text", "%String")],
+                return_type: "%String",
+                doc: "Decodes Base64-encoded `text`.",
+            },
+            SystemMethod {
+                name: "Base64Encode",
+                arguments: &[arg("text", "%String"), opt("flags", "%Integer")],
+                return_type: "%String",
+                doc: "Encodes `text` as Base64; `flags` 1 omits line breaks.",
+            },
+            SystemMethod {
+                name: "GenCryptRand",
+                arguments: &[arg("length", "%Integer"), opt("restrict", "%Boolean")],
+                return_type: "%String",
+                doc: "Returns `length` cryptographically secure random bytes.",
+            },
+            SystemMethod {
+                name: "MD5Hash",
+                arguments: &[arg("text", "%String")],
+                return_type: "%String",
+                doc: "Returns the MD5 hash of `text` as 16 raw bytes.",
+            },
+            SystemMethod {
+                name: "SHAHash",
+                arguments: &[arg("bitlength", "%Integer"), arg("text", "%String")],
+                return_type: "%String",
+                doc: "Returns the SHA hash of `text`; `bitlength` is 160, 224, 256, 384, or 512.",
+            },
+        ],
+    },
+    SystemClass {
+        name: "OBJ",
+        doc: "Loading, compiling, and exporting classes and routines.",
+        methods: &[
+            SystemMethod {
+                name: "Compile",
+                arguments: &[
+                    opt("classes", "%String"),
+                    opt("qspec", "%String"),
+                    opt("errorlog", "ByRef %String"),
+                    opt("recurse", "%Boolean"),
+                ],
+                return_type: "%Status",
+                doc: "Compiles the classes in `classes`, a comma-separated list or a \
+                    subscripted array, with the flags and qualifiers in `qspec`.",
+            },
+            SystemMethod {
+                name: "CompileAll",
+                arguments: &[opt("qspec", "%String"), opt("errorlog", "ByRef %String")],
+                return_type: "%Status",
+                doc: "Compiles every class in the current namespace.",
+            },
+            SystemMethod {
+                name: "Delete",
+                arguments: &[
+                    arg("classname", "%String"),
+                    opt("qspec", "%String"),
+                    opt("errorlog", "ByRef %String"),
+                ],
+                return_type: "%Status",
+                doc: "Deletes the class `classname` and its compiled routines.",
+            },
+            SystemMethod {
+                name: "DisplayError",
+                arguments: &[opt("status", "%Status")],
+                return_type: "%Status",
+                doc: "Writes the error text of `status`, or of the last error.",
+            },
+            SystemMethod {
+                name: "Export",
+                arguments: &[
+                    arg("items", "%String"),
+                    arg("filename", "%String"),
+                    opt("qspec", "%String"),
+                    opt("errorlog", "ByRef %String"),
+                    opt("charset", "%String"),
+                ],
+                return_type: "%Status",
+                doc: "Exports the classes and routines in `items` to the XML file `filename`.",
+            },
+            SystemMethod {
+                name: "Load",
+                arguments: &[
+                    arg("filename", "%String"),
+                    opt("qspec", "%String"),
+                    opt("errorlog", "ByRef %String"),
+                    opt("loadedlist", "ByRef %String"),
+                    opt("listonly", "%Boolean"),
+                ],
+                return_type: "%Status",
+                doc: "Loads the classes and routines of the file `filename`, compiling them when \
+                    `qspec` contains `c`.",
+            },
+            SystemMethod {
+                name: "ShowFlags",
+                arguments: &[],
+                return_type: "",
+                doc: "Writes the available compiler flags.",
+            },
+            SystemMethod {
+                name: "ShowQualifiers",
+                arguments: &[],
+                return_type: "",
+                doc: "Writes the available compiler qualifiers.",
+            },
+        ],
+    },
+    SystemClass {
+        name: "Process",
+        doc: "Information about and control of processes.",
+        methods: &[
+            SystemMethod {
+                name: "ClientIPAddress",
+                arguments: &[opt("pid", "%Integer")],
+                return_type: "%String",
+                doc: "Returns the IP address of the client connected to the process `pid`.",
+            },
+            SystemMethod {
+                name: "CurrentDirectory",
+                arguments: &[opt("directory", "%String")],
+                return_type: "%String",
+                doc: "Returns the process' current directory, changing it to `directory` if \
+                    given.",
+            },
+            SystemMethod {
+                name: "Terminate",
+                arguments: &[opt("pid", "%Integer"), opt("exitstatus", "%Integer")],
+                return_type: "%Integer",
+                doc: "Terminates the process `pid`, or the current process.",
+            },
+        ],
+    },
+    SystemClass {
+        name: "SQL",
+        doc: "Running SQL and managing tables and cached queries.",
+        methods: &[
+            SystemMethod {
+                name: "Execute",
+                arguments: &[
+                    arg("sql", "%String"),
+                    opt("selectmode", "%Integer"),
+                    opt("dialect", "%String"),
+                    opt("objectselectmode", "%Boolean"),
+                ],
+                return_type: "%SQL.StatementResult",
+                doc: "Prepares and executes the SQL statement `sql` and returns its result.",
+            },
+            SystemMethod {
+                name: "Purge",
+                arguments: &[opt("days", "%Integer")],
+                return_type: "%Status",
+                doc: "Purges the cached queries not used in the last `days` days.",
+            },
+            SystemMethod {
+                name: "PurgeForTable",
+                arguments: &[arg("table", "%String")],
+                return_type: "%Status",
+                doc: "Purges the cached queries that use the table `table`.",
+            },
+            SystemMethod {
+                name: "Shell",
+                arguments: &[],
+                return_type: "",
+                doc: "Starts the interactive SQL shell.",
+            },
+            SystemMethod {
+                name: "TuneTable",
+                arguments: &[
+                    arg("table", "%String"),
+                    opt("update", "%Boolean"),
+                    opt("display", "%Boolean"),
+                ],
+                return_type: "%Status",
+                doc: "Gathers the extent size and selectivity statistics of the table `table`.",
+            },
+        ],
+    },
+    SystemClass {
+        name: "Status",
+        doc: "Creating and inspecting `%Status` values.",
+        methods: &[
+            SystemMethod {
+                name: "AppendStatus",
+                arguments: &[arg("status1", "%Status"), arg("status2", "%Status")],
+                return_type: "%Status",
+                doc: "Combines two statuses into one holding the errors of both.",
+            },
+            SystemMethod {
+                name: "DisplayError",
+                arguments: &[arg("status", "%Status")],
+                return_type: "",
+                doc: "Writes the error text of `status`.",
+            },
+            SystemMethod {
+                name: "Error",
+                arguments: &[
+                    arg("errorcode", "%Integer"),
+                    opt("p1", "%String"),
+                    opt("p2", "%String"),
+                    opt("p3", "%String"),
+                    opt("p4", "%String"),
+                ],
+                return_type: "%Status",
+                doc: "Builds an error status for the message `errorcode` with parameters \
+                    `p1`-`p4`.",
+            },
+            SystemMethod {
+                name: "GetErrorText",
+                arguments: &[arg("status", "%Status"), opt("language", "%String")],
+                return_type: "%String",
+                doc: "Returns the error text of `status`.",
+            },
+            SystemMethod {
+                name: "IsError",
+                arguments: &[arg("status", "%Status")],
+                return_type: "%Boolean",
+                doc: "Returns `1` if `status` is an error.",
+            },
+            SystemMethod {
+                name: "IsOK",
+                arguments: &[arg("status", "%Status")],
+                return_type: "%Boolean",
+                doc: "Returns `1` if `status` is not an error.",
+            },
+            SystemMethod {
+                name: "OK",
+                arguments: &[],
+                return_type: "%Status",
+                doc: "Returns the success status.",
+            },
+        ],
+    },
+    SystemClass {
+        name: "Util",
+        doc: "Installation and environment utilities.",
+        methods: &[
+            SystemMethod {
+                name: "CreateGUID",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns a new GUID.",
+            },
+            SystemMethod {
+                name: "GetEnviron",
+                arguments: &[arg("variable", "%String")],
+                return_type: "%String",
+                doc: "Returns the value of the operating system environment variable `variable`.",
+            },
+            SystemMethod {
+                name: "InstallDirectory",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns the installation directory.",
+            },
+            SystemMethod {
+                name: "ManagerDirectory",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns the manager's directory (`mgr`).",
+            },
+        ],
+    },
+    SystemClass {
+        name: "Version",
+        doc: "Version information of the running instance.",
+        methods: &[
+            SystemMethod {
+                name: "GetMajor",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns the major version number.",
+            },
+            SystemMethod {
+                name: "GetMinor",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns the minor version number.",
+            },
+            SystemMethod {
+                name: "GetNumber",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns the version number, e.g. `2024.1`.",
+            },
+            SystemMethod {
+                name: "GetVersion",
+                arguments: &[],
+                return_type: "%String",
+                doc: "Returns the full version string.",
+            },
+        ],
+    },
+];
+
+/// Returns the `%SYSTEM` class reached as `$SYSTEM.<name>`; these names are case-insensitive.
+pub fn system_class(name: &str) -> Option<&'static SystemClass> {
+    SYSTEM_CLASSES
+        .iter()
+        .find(|class| class.name.eq_ignore_ascii_case(name))
+}
+
+/// What a `$SYSTEM` completion offers.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SystemApiTarget<'a> {
+    /// `$SYSTEM.`: the `%SYSTEM` classes.
+    Classes,
+    /// `$SYSTEM.<class>.`: the methods of the class.
+    MethodsOf(&'a str),
+}
+
+/// Returns the name being typed at the end of `line_before_cursor` and what it completes, if the
+/// cursor follows `$SYSTEM.` or `$SYSTEM.<class>.`.
+pub fn system_api_prefix(line_before_cursor: &str) -> Option<(SystemApiTarget<'_>, &str)> {
+    let name_start = line_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric())
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line_before_cursor.len());
+    let prefix = &line_before_cursor[name_start..];
+    let before = line_before_cursor[..name_start].strip_suffix('.')?;
+    if is_system_keyword_end(before) {
+        return Some((SystemApiTarget::Classes, prefix));
+    }
+    let (head, class) = before.rsplit_once('.')?;
+    let is_class_name = !class.is_empty() && class.chars().all(|c| c.is_ascii_alphanumeric());
+    (is_class_name && is_system_keyword_end(head))
+        .then_some((SystemApiTarget::MethodsOf(class), prefix))
+}
+
+/// Returns `true` if `text` ends with the `$SYSTEM` special variable (not `$$SYSTEM`).
+fn is_system_keyword_end(text: &str) -> bool {
+    let Some(start) = text.len().checked_sub("$system".len()) else {
+        return false;
+    };
+    starts_with_ignore_case(text, start, "$system")
+        && !text[..start].ends_with(|c: char| c == '$' || c.is_ascii_alphanumeric())
+}
+
+/// Completes the `%SYSTEM` classes or methods whose names start with `partial`, inserting methods
+/// as configured by `style`.
+///
+/// Snippets are only inserted when the client supports them (`snippets`). Items replace the
+/// typed name, which ends at the cursor `position`.
+pub fn system_api_completions(
+    target: &SystemApiTarget,
+    partial: &str,
+    position: Position,
+    style: MethodInsertStyle,
+    snippets: bool,
+) -> Vec<CompletionItem> {
+    let typed_len = position_encoding().units(partial);
+    let replace_range = LspRange {
+        start: Position {
+            line: position.line,
+            character: position.character.saturating_sub(typed_len),
+        },
+        end: position,
+    };
+    let partial = partial.to_ascii_lowercase();
+    let markdown = |value: String| {
+        Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }))
+    };
+    match target {
+        SystemApiTarget::Classes => SYSTEM_CLASSES
+            .iter()
+            .filter(|class| class.name.to_ascii_lowercase().starts_with(&partial))
+            .map(|class| CompletionItem {
+                label: class.name.to_string(),
+                kind: Some(CompletionItemKind::MODULE),
+                detail: Some(class.class_name()),
+                documentation: markdown(class.doc.to_string()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: replace_range,
+                    new_text: class.name.to_string(),
+                })),
+                ..Default::default()
+            })
+            .collect(),
+        SystemApiTarget::MethodsOf(class_name) => {
+            let Some(class) = system_class(class_name) else {
+                return Vec::new();
+            };
+            class
+                .methods
+                .iter()
+                .filter(|method| method.name.to_ascii_lowercase().starts_with(&partial))
+                .map(|method| {
+                    let (new_text, format) = match style {
+                        MethodInsertStyle::Name => {
+                            (method.name.to_string(), InsertTextFormat::PLAIN_TEXT)
+                        }
+                        MethodInsertStyle::Snippet if snippets => {
+                            let tab_stops: Vec<String> = method
+                                .arguments
+                                .iter()
+                                .take(method.required_arguments())
+                                .enumerate()
+                                .map(|(i, argument)| format!("${{{}:{}}}", i + 1, argument.name))
+                                .collect();
+                            (
+                                format!("{}({})$0", method.name, tab_stops.join(", ")),
+                                InsertTextFormat::SNIPPET,
+                            )
+                        }
+                        MethodInsertStyle::Parens | MethodInsertStyle::Snippet => {
+                            (format!("{}()", method.name), InsertTextFormat::PLAIN_TEXT)
+                        }
+                    };
+                    CompletionItem {
+                        label: method.name.to_string(),
+                        kind: Some(CompletionItemKind::METHOD),
+                        detail: Some(format!("{}.{}", class.class_name(), method.signature())),
+                        documentation: markdown(method.doc.to_string()),
+                        insert_text_format: Some(format),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                            range: replace_range,
+                            new_text,
+                        })),
+                        ..Default::default()
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// A `$SYSTEM.<class>` or `$SYSTEM.<class>.<method>` reference found by
+/// `find_system_references`.
+#[derive(Clone, Debug)]
+pub struct SystemReference {
+    pub class: String,
+    pub method: Option<String>,
+    /// Range of the whole dotted reference, from `$SYSTEM` to the last name.
+    pub range: Range,
+    /// Byte offset of the `(` opening the arguments of a method call.
+    pub open: Option<usize>,
+    /// Byte offset of the matching `)`, `None` if the call isn't closed on its line.
+    pub close: Option<usize>,
+    /// Byte offsets of the top-level commas between the parentheses.
+    pub commas: Vec<usize>,
+}
+
+impl SystemReference {
+    /// Number of arguments passed, counting omitted ones (`a,,c` passes 3); 0 for `()`.
+    pub fn argument_count(&self, content: &str) -> usize {
+        let (Some(open), Some(close)) = (self.open, self.close) else {
+            return 0;
+        };
+        if self.commas.is_empty() && content[open + 1..close].trim().is_empty() {
+            return 0;
+        }
+        self.commas.len() + 1
+    }
+
+    /// Resolves the stub of the referenced method.
+    pub fn system_method(&self) -> Option<(&'static SystemClass, &'static SystemMethod)> {
+        let class = system_class(&self.class)?;
+        Some((class, class.method(self.method.as_deref()?)?))
+    }
+}
+
+/// Reads the identifier starting at byte `i`, returning its end offset.
+fn identifier_end(content: &str, i: usize) -> usize {
+    i + content[i..]
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric())
+        .count()
+}
+
+/// Finds every `$SYSTEM` reference in `content`, in document order.
+///
+/// This is a lexical scan like `bracket_diagnostics`: strings and comments are skipped, and
+/// call arguments are delimited by parenthesis depth on the line of the call.
+pub fn find_system_references(content: &str) -> Vec<SystemReference> {
+    let bytes = content.as_bytes();
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(next) = skip_string_or_comment(content, i, Dialect::ObjectScript) {
+            i = next;
+            continue;
+        }
+        let after_word = i > 0 && (bytes[i - 1] == b'$' || bytes[i - 1].is_ascii_alphanumeric());
+        if after_word || !starts_with_ignore_case(content, i, "$system.") {
+            i += 1;
+            continue;
+        }
+        let class_start = i + "$system.".len();
+        let class_end = identifier_end(content, class_start);
+        if class_end == class_start {
+            i = class_start;
+            continue;
+        }
+        let mut reference = SystemReference {
+            class: content[class_start..class_end].to_string(),
+            method: None,
+            range: byte_range(content, i, class_end),
+            open: None,
+            close: None,
+            commas: Vec::new(),
+        };
+        let mut end = class_end;
+        if bytes.get(class_end) == Some(&b'.') {
+            let method_end = identifier_end(content, class_end + 1);
+            if method_end > class_end + 1 {
+                reference.method = Some(content[class_end + 1..method_end].to_string());
+                reference.range = byte_range(content, i, method_end);
+                end = method_end;
+            }
+        }
+        if reference.method.is_some() && bytes.get(end) == Some(&b'(') {
+            reference.open = Some(end);
+            let line_end = end_of_line(content, end);
+            let (mut j, mut depth) = (end + 1, 0usize);
+            while j < line_end {
+                match bytes[j] {
+                    b'"' => {
+                        j = skip_string(content, j, b'"');
+                        continue;
+                    }
+                    b'(' => depth += 1,
+                    b')' if depth == 0 => {
+                        reference.close = Some(j);
+                        break;
+                    }
+                    b')' => depth -= 1,
+                    b',' if depth == 0 => reference.commas.push(j),
+                    _ => {}
+                }
+                j += 1;
+            }
+        }
+        references.push(reference);
+        // arguments may hold further references
+        i = end;
+    }
+    references
+}
+
+/// Builds the Markdown hover for the `$SYSTEM` reference at byte offset `cursor`, with the
+/// range of the reference.
+pub fn system_api_hover(content: &str, cursor: usize) -> Option<(Range, String)> {
+    let reference = find_system_references(content)
+        .into_iter()
+        .find(|reference| {
+            reference.range.start_byte <= cursor && cursor <= reference.range.end_byte
+        })?;
+    let class = system_class(&reference.class)?;
+    let hover = match &reference.method {
+        None => format!("**{}**\n\n{}", class.class_name(), class.doc),
+        Some(method_name) => {
+            let method = class.method(method_name)?;
+            format!(
+                "```objectscript\nClassMethod {}.{}\n```\n\n{}",
+                class.class_name(),
+                method.signature(),
+                method.doc
+            )
+        }
+    };
+    Some((reference.range, hover))
+}
+
+/// Builds the signature help for the innermost `$SYSTEM` method call whose parentheses contain
+/// byte offset `cursor`, highlighting the argument being typed.
+pub fn system_api_signature_help(content: &str, cursor: usize) -> Option<SignatureHelp> {
+    let reference = find_system_references(content)
+        .into_iter()
+        .filter(|reference| {
+            reference.open.is_some_and(|open| open < cursor)
+                && reference.close.is_none_or(|close| cursor <= close)
+                && cursor <= end_of_line(content, reference.range.end_byte)
+        })
+        .max_by_key(|reference| reference.open)?;
+    let (class, method) = reference.system_method()?;
+    let active_parameter = reference
+        .commas
+        .iter()
+        .filter(|comma| **comma < cursor)
+        .count() as u32;
+    let parameters = method
+        .arguments
+        .iter()
+        .map(|argument| ParameterInformation {
+            label: ParameterLabel::Simple(format!("{} As {}", argument.name, argument.type_name)),
+            documentation: None,
+        })
+        .collect();
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: method.signature(),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("{}\n\n{}", class.class_name(), method.doc),
+            })),
+            parameters: Some(parameters),
+            active_parameter: Some(active_parameter),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active_parameter),
+    })
+}
+
+/// Renders an argument count, e.g. `1 argument` or `3 arguments`.
+pub(crate) fn argument_count_text(count: usize) -> String {
+    match count {
+        1 => "1 argument".to_string(),
+        _ => format!("{count} arguments"),
+    }
+}
+
+/// Warns about calls to stubbed `$SYSTEM` methods that pass more arguments than the method takes
+/// or omit required ones. Unknown classes and methods are not reported, since the stubs only
+/// cover part of the API.
+pub fn system_api_diagnostics(content: &str, references: &[SystemReference]) -> Vec<Diagnostic> {
+    start_of_function("SYSTEM API (no struct)", "system_api_diagnostics");
+    let mut diagnostics = Vec::new();
+    for reference in references.iter().filter(|r| r.close.is_some()) {
+        let Some((class, method)) = reference.system_method() else {
+            continue;
+        };
+        let passed = reference.argument_count(content);
+        let message = if passed > method.arguments.len() {
+            format!(
+                "{}.{} takes at most {}; {passed} passed",
+                class.class_name(),
+                method.name,
+                argument_count_text(method.arguments.len())
+            )
+        } else if passed < method.required_arguments() {
+            format!(
+                "{}.{} requires at least {}; {passed} passed",
+                class.class_name(),
+                method.name,
+                argument_count_text(method.required_arguments())
+            )
+        } else {
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            range: ts_range_to_lsp_range(content, reference.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(SYSTEM_API_ARGUMENTS_RULE),
+            code_description: rule_code_description(SYSTEM_API_ARGUMENTS_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message,
+            ..Default::default()
+        });
+    }
+    successful_exit("SYSTEM API (no struct)", "system_api_diagnostics");
+    diagnostics
+}
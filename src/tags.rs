@@ -0,0 +1,67 @@
+use crate::diagnostics::MemberKind;
+
+/// A symbol definition written to a ctags file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TagEntry {
+    /// Tag name: a class name or a member name.
+    pub name: String,
+    /// Path of the file defining the symbol, relative to the workspace root when possible.
+    pub path: String,
+    /// 1-based line of the definition.
+    pub line: usize,
+    pub kind: TagKind,
+    /// Class declaring the member, for member tags.
+    pub class_name: Option<String>,
+}
+
+/// Kind of a `TagEntry`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TagKind {
+    Class,
+    Member(MemberKind),
+}
+
+impl TagKind {
+    /// The single-letter ctags kind.
+    fn letter(&self) -> char {
+        match self {
+            TagKind::Class => 'c',
+            TagKind::Member(MemberKind::Method) => 'm',
+            TagKind::Member(MemberKind::Property) => 'p',
+            TagKind::Member(MemberKind::Parameter) => 'a',
+            TagKind::Member(MemberKind::Index) => 'i',
+            TagKind::Member(MemberKind::ForeignKey) => 'f',
+            TagKind::Member(MemberKind::Trigger) => 't',
+            TagKind::Member(MemberKind::Query) => 'q',
+            TagKind::Member(MemberKind::Projection) => 'j',
+            TagKind::Member(MemberKind::Storage) => 's',
+        }
+    }
+}
+
+/// Renders `entries` as an Exuberant/Universal ctags file (extended format, sorted by name), so
+/// editors and offline navigation tools can use the server's index.
+pub fn render_ctags(mut entries: Vec<TagEntry>) -> String {
+    entries.sort_by(|a, b| {
+        (a.name.as_str(), a.path.as_str(), a.line).cmp(&(b.name.as_str(), b.path.as_str(), b.line))
+    });
+    let mut tags = String::from(
+        "!_TAG_FILE_FORMAT\t2\t/extended format/\n\
+         !_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/\n\
+         !_TAG_PROGRAM_NAME\tobjectscript-lsp\t//\n",
+    );
+    for entry in entries {
+        tags.push_str(&format!(
+            "{}\t{}\t{};\"\t{}",
+            entry.name,
+            entry.path,
+            entry.line,
+            entry.kind.letter()
+        ));
+        if let Some(class_name) = entry.class_name {
+            tags.push_str(&format!("\tclass:{class_name}"));
+        }
+        tags.push('\n');
+    }
+    tags
+}
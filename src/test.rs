@@ -1,12 +1,121 @@
 #[cfg(test)]
 mod tests {
+    use crate::arena::Arena;
+    use crate::atelier::{class_name_from_url, document_content, is_read_only_url, AtelierConfig};
     use crate::backend_testing::BackendTester;
-    use crate::parse_structures::{FileType, Language};
+    use crate::call_graph::{CallEdge, CallGraph, CallGraphNode};
+    use crate::cancellation::{run_cancellable, CancellationToken};
+    use crate::capabilities::{
+        server_capabilities, CODE_ACTION_FEATURE, CODE_LENS_FEATURE, DYNAMIC_FEATURES,
+        FORMATTING_FEATURE, HOVER_FEATURE,
+    };
+    use crate::case_rules::{check_rename, names_match, IdentifierKind};
+    use crate::class_reference::{
+        documatic_markdown, method_declaration, reference_class, system_class_reference_at,
+    };
+    use crate::common::{
+        apply_content_changes, braced_content, doc_comment_above, keyword_flags, keyword_value,
+        member_visibility, split_member_declaration, word_at_offset, PositionEncoding,
+    };
+    use crate::completion::{
+        class_name_prefix, close_class_qualifiers, commit_class_qualifiers,
+        is_class_qualifier_position, method_completions, method_name_prefix,
+        package_segment_completions, parameter_completions, parameter_name_prefix,
+        property_completions, MethodTarget,
+    };
+    use crate::config::{Config, MethodInsertStyle, SyncKind, WORKSPACE_CONFIG_FILE};
+    use crate::diagnostics::{
+        arity_diagnostics, bracket_diagnostics, byte_range, debug_write_replacement,
+        duplicate_member_diagnostics, find_arity_mismatches, find_dead_stores, find_debug_writes,
+        glob_matches, inheritance_cycle_diagnostics, is_generated_class,
+        member_collision_diagnostics, missing_message, split_top_level_arguments,
+        unexpected_message, unreachable_code_in, ClassMember, InheritedMember, MemberKind,
+        DEAD_STORE_CODE, UNUSED_VARIABLE_CODE,
+    };
+    use crate::doctor::{check_position_encoding, check_system_stubs, render_report};
+    use crate::document::TextBuffer;
+    use crate::element_types::{resolve_value, KnownValue};
+    use crate::embedded_sql::{
+        find_host_variables, find_sql_references, SqlReference, SqlReferenceKind,
+    };
+    use crate::file_events::BulkChangeDetector;
+    use crate::folding::lexical_folding_ranges;
+    use crate::formatting::{block_indentation_edits, command_abbreviation_edits};
+    use crate::fuzzy::{closest_names, edit_distance, fuzzy_score};
+    use crate::global_semantic::GlobalSemanticModel;
+    use crate::index_progress::IndexProgress;
+    use crate::interner::Interner;
+    use crate::intrinsics::{intrinsic_function_completions, intrinsic_function_prefix};
+    use crate::local_semantic::LocalSemanticModel;
+    use crate::macros::{
+        include_macros, macro_completions, macro_name_at, macro_prefix, MacroDefinition,
+    };
+    use crate::metrics::{complexity_diagnostics, method_metrics};
+    use crate::new_class::{class_skeleton, new_class_url};
+    use crate::oref_types::{class_method_call_parts, expression_chain, ChainHead, ChainMember};
+    use crate::package_rename::{
+        class_rename_edits, imports_package, is_class_name, package_references, renamed_class_path,
+    };
+    use crate::parse_structures::{
+        ArgumentMode, Class, ClassId, ClassParameter, ClassProperty, CodeMode, FileType, Language,
+        LocalSemanticModelId, MemberVisibility, Method, MethodCallSite, MethodHandle, MethodRef,
+        MethodType, VarType,
+    };
+    use crate::python::python_identifiers;
+    use crate::response_cache::{ResponseCache, ResponseKey};
+    use crate::routine::{label_reference_at, routine_labels};
+    use crate::rules::{
+        rule_code, rule_code_description, rule_docs, RULES, TYPE_MISMATCH_RULE,
+        UNBALANCED_CONSTRUCT_RULE,
+    };
+    use crate::scope_structures::{ClassGlobalSymbolId, GlobalSymbolId, MethodGlobalSymbolId};
+    use crate::scope_tree::ScopeTree;
+    use crate::semantic_diff::semantic_diff;
+    use crate::semantic_tokens::{
+        encode_tokens, token_edits, RawToken, TokenType, DECLARATION, PUBLIC,
+    };
+    use crate::server::{BackendWrapper, Debouncer};
+    use crate::snapshot::IndexSnapshots;
+    use crate::source_provider::{
+        ClientSourceProvider, FsSourceProvider, MemorySourceProvider, SourceProvider,
+    };
+    use crate::sql_surface::default_schema;
+    use crate::symbol_index::{SymbolEntry, SymbolLocationIndex, SymbolRef};
+    use crate::system_api::{
+        find_system_references, system_api_diagnostics, system_api_prefix,
+        system_api_signature_help, SystemApiTarget,
+    };
+    use crate::tags::{render_ctags, TagEntry, TagKind};
+    use crate::type_check::{declared_type, literal_var_type};
+    use crate::unresolved::{
+        import_edit, import_names, local_label_calls, qualified_class_name, routine_label_calls,
+        routine_name_matches, routine_path_name, routine_references,
+    };
+    use crate::usage::{render_usage_csv, render_usage_json, UsageEntry, UsageKind};
+    use crate::variable::{argument_mode, variable_occurrences};
     use crate::workspace::ProjectState;
+    use crate::xml_export::{parse_export, ExportLineMap};
+    use futures::channel::mpsc;
+    use futures::{SinkExt, StreamExt};
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
     use std::env;
     use std::path::PathBuf;
-    use tower_lsp::lsp_types::Url;
-    use tree_sitter::Parser;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tower::Service;
+    use tower_lsp::jsonrpc;
+    use tower_lsp::lsp_types::{
+        CodeLensClientCapabilities, CompletionTextEdit, Diagnostic, DiagnosticTag,
+        DidChangeTextDocumentParams, DocumentFormattingClientCapabilities, FileChangeType,
+        FileEvent, FoldingRangeKind, GlobPattern, HoverClientCapabilities, InitializeParams,
+        InitializeResult, InsertTextFormat, Location, NumberOrString, OneOf, Position,
+        PositionEncodingKind, Range as LspRange, SemanticToken, ServerCapabilities, SymbolKind,
+        TextDocumentClientCapabilities, TextDocumentContentChangeEvent, TextDocumentSyncCapability,
+        TextDocumentSyncKind, Url, VersionedTextDocumentIdentifier,
+    };
+    use tower_lsp::{Client, LanguageServer, LspService};
+    use tree_sitter::{Parser, Point, Range};
     use tree_sitter_objectscript::LANGUAGE_OBJECTSCRIPT;
 
 //     PRINTING PARAMS: DidChangeTextDocumentParams {
@@ -64,6 +173,9 @@ mod tests {
         }
         let backend = BackendTester::new();
         let uri = Url::from_file_path(project_root).unwrap();
+        if state.project_root_uri.set(uri.clone()).is_err() {
+            eprintln!("failed to set the root uri");
+        }
         backend.add_project(uri.clone(), state);
         // println!("{:#?}", backend);
 
@@ -135,7 +247,7 @@ mod tests {
         //     }
         // }
 
-        project_state.update_document(document_url, tree, FileType::Cls, 1, content.as_str());
+        project_state.update_document(document_url, tree, FileType::Cls, 1, content.clone());
         let (project_gsm_variable_defs, project_public_variables, project_documents, class_defs, classes) = {
             let project_data = project_state.data.read();
             let project_gsm_variable_defs = project_data.global_semantic_model.variable_defs.clone();
@@ -198,7 +310,7 @@ mod tests {
         let classes = project_data.classes.clone();
         let gsm = project_data.global_semantic_model.clone();
         for (class_name, class_id) in classes {
-            let class = &gsm.classes[class_id.0];
+            let class = &gsm.classes[class_id];
             assert_eq!(class.is_procedure_block, Some(false));
             assert_eq!(class.default_language, Some(Language::Objectscript));
             assert_eq!(class.inheritance_direction, "right");
@@ -219,4 +331,3873 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_index_workspace_scope_from_memory_sources() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            root.join("Test/Memory.cls").unwrap(),
+            "Class Test.Memory\n{\n}\n".to_string(),
+        );
+        sources.insert(root.join("notes.txt").unwrap(), "not a source".to_string());
+        sources.insert(
+            Url::parse("isfs://server/other/Test/Other.cls").unwrap(),
+            "Class Test.Other\n{\n}\n".to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        assert_eq!(data.documents.len(), 1);
+        assert!(data.classes.contains_key("Test.Memory"));
+    }
+
+    #[test]
+    fn test_parallel_indexing_assigns_class_ids_in_listing_order() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        for n in 0..40 {
+            sources.insert(
+                root.join(&format!("Test/C{n:02}.cls")).unwrap(),
+                format!("Class Test.C{n:02}\n{{\n\nMethod Run()\n{{\n    quit\n}}\n}}\n"),
+            );
+        }
+        sources.insert(
+            root.join("Test/Util.inc").unwrap(),
+            "#define Twice(%x) %x*2\n".to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        assert_eq!(data.documents.len(), 40);
+        assert!(data.include_macros.contains_key("user.Test.Util"));
+        for n in 0..40 {
+            assert_eq!(
+                data.classes[format!("Test.C{n:02}").as_str()],
+                ClassId(n, 0)
+            );
+        }
+        assert_eq!(state.index_progress.report().1, 90);
+    }
+
+    #[test]
+    fn test_override_index_tracks_private_and_dispatch_overrides() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            root.join("Test/Base.cls").unwrap(),
+            "Class Test.Base\n{\n\nMethod Describe() [ Private ]\n{\n    quit\n}\n\nClassMethod Helper()\n{\n    quit\n}\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Sub.cls").unwrap(),
+            "Class Test.Sub Extends Test.Base\n{\n\nMethod Describe() [ Private ]\n{\n    quit\n}\n\nMethod Helper()\n{\n    quit\n}\n}\n".to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        let base_id = data.classes["Test.Base"];
+        let sub_id = data.classes["Test.Sub"];
+        let sub = &data.global_semantic_model.classes[sub_id];
+
+        let describe = MethodRef {
+            class: sub_id,
+            pub_id: None,
+            priv_id: sub.private_methods.get("Describe").copied(),
+        };
+        assert!(matches!(
+            data.override_index.overrides.get(&describe),
+            Some(MethodHandle::Local { class, .. }) if *class == base_id
+        ));
+        assert!(matches!(
+            data.override_index.effective_methods[&sub_id].get("Describe"),
+            Some(MethodHandle::Local { class, .. }) if *class == sub_id
+        ));
+
+        let helper = MethodRef {
+            class: sub_id,
+            pub_id: sub.public_methods.get("Helper").copied(),
+            priv_id: None,
+        };
+        assert_eq!(data.override_index.dispatch_mismatches.len(), 1);
+        assert_eq!(
+            data.override_index.dispatch_mismatches[&helper].1,
+            MethodType::InstanceMethod
+        );
+    }
+
+    #[test]
+    fn test_superclass_keyword_change_rebuilds_its_subclasses() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let base_url = root.join("Test/Base.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            base_url.clone(),
+            "Class Test.Base [ Not ProcedureBlock ]\n{\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Sub.cls").unwrap(),
+            "Class Test.Sub Extends Test.Base\n{\n\nMethod Run()\n{\n    quit\n}\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Other.cls").unwrap(),
+            "Class Test.Other [ Not ProcedureBlock ]\n{\n}\n".to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let run_procedure_block = |state: &ProjectState| {
+            let data = state.data.read();
+            let sub_id = data.classes["Test.Sub"];
+            let class = &data.global_semantic_model.classes[sub_id];
+            let run = class.public_methods["Run"];
+            let method = &data.global_semantic_model.methods[&sub_id][run.0];
+            (class.is_procedure_block, method.is_procedure_block)
+        };
+        assert_eq!(run_procedure_block(&state), (Some(false), Some(false)));
+        {
+            let data = state.data.read();
+            let base_id = data.classes["Test.Base"];
+            let dependents: Vec<ClassId> = data.dependent_classes(base_id);
+            assert_eq!(dependents, [base_id, data.classes["Test.Sub"]]);
+        }
+
+        state.handle_document_opened(
+            base_url,
+            "Class Test.Base [ ProcedureBlock ]\n{\n}\n".to_string(),
+            FileType::Cls,
+            2,
+        );
+        assert_eq!(run_procedure_block(&state), (Some(true), None));
+    }
+
+    #[test]
+    fn test_property_accessors_link_to_base_property() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let base_url = root.join("Test/Base.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            base_url.clone(),
+            "Class Test.Base\n{\n\nProperty Name As %String;\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Sub.cls").unwrap(),
+            "Class Test.Sub Extends Test.Base\n{\n\nMethod NameGet() As %String\n{\n    quit i%Name\n}\n}\n".to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        let base_id = data.classes["Test.Base"];
+        let accessors = &data.override_index.property_accessors[&(base_id, "Name".to_string())];
+        assert_eq!(accessors.len(), 1);
+        assert_eq!(accessors[0].class, data.classes["Test.Sub"]);
+
+        // on `Name` in `Property Name As %String;`
+        let point = Point::new(3, 10);
+        assert_eq!(data.get_property_definitions(&base_url, point).len(), 2);
+        let (_, hover) = data.get_property_hover(&base_url, point).unwrap();
+        assert!(hover.contains("`Test.Sub.NameGet`"));
+    }
+
+    #[test]
+    fn test_goto_definition_of_inherited_and_private_method_calls() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let base_url = root.join("Test/Base.cls").unwrap();
+        let sub_url = root.join("Test/Sub.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            base_url.clone(),
+            "Class Test.Base\n{\n\nClassMethod Log(msg)\n{\n    quit\n}\n}\n".to_string(),
+        );
+        sources.insert(
+            sub_url.clone(),
+            "Class Test.Sub Extends Test.Base\n{\n\nClassMethod Run()\n{\n\
+             \x20   set x = ##class(Test.Sub).Log(1)\n\
+             \x20   do ..Helper()\n\
+             }\n\n\
+             ClassMethod Helper() [ Private ]\n{\n    quit\n}\n}\n"
+                .to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        // on `Log` in `##class(Test.Sub).Log(1)`, inherited from Test.Base
+        let (url, range) = data
+            .get_method_call_definition(&sub_url, Point::new(5, 31))
+            .unwrap();
+        assert_eq!(url, base_url);
+        assert_eq!(range.start_point.row, 3);
+        // on `Helper` in `..Helper()`, a private method of the class
+        let (url, range) = data
+            .get_method_call_definition(&sub_url, Point::new(6, 11))
+            .unwrap();
+        assert_eq!(url, sub_url);
+        assert_eq!(range.start_point.row, 9);
+        // on the class name, not the method
+        assert!(data
+            .get_method_call_definition(&sub_url, Point::new(5, 22))
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_references_of_public_method_across_classes() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let base_url = root.join("Test/Base.cls").unwrap();
+        let user_url = root.join("Test/User.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            base_url.clone(),
+            "Class Test.Base\n{\n\nClassMethod Log(msg)\n{\n    quit\n}\n\n\
+             ClassMethod Run()\n{\n    do ##class(Test.Base).Log(1)\n}\n}\n"
+                .to_string(),
+        );
+        sources.insert(
+            user_url.clone(),
+            "Class Test.User\n{\n\nClassMethod Run()\n{\n\
+             \x20   do ##class(Test.Base).Log(2)\n\
+             }\n}\n"
+                .to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        // on `Log` in its declaration
+        let mut references = data.get_method_references_at(&base_url, Point::new(3, 13), false);
+        references.sort_by_key(|(url, _)| url.to_string());
+        let urls: Vec<&Url> = references.iter().map(|(url, _)| url).collect();
+        assert_eq!(urls, vec![&base_url, &user_url]);
+        // on `Log` at a call site, including the declaration
+        let references = data.get_method_references_at(&user_url, Point::new(5, 27), true);
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0], (base_url.clone(), references[0].1));
+        assert_eq!(references[0].1.start_point.row, 3);
+        assert!(data.get_reference_count_lenses(&base_url).is_empty());
+        drop(data);
+
+        state.data.write().config.reference_count_lens = true;
+        let data = state.data.read();
+        let lenses: Vec<(u32, String)> = data
+            .get_reference_count_lenses(&base_url)
+            .into_iter()
+            .map(|lens| (lens.range.start.line, lens.command.unwrap().title))
+            .collect();
+        assert_eq!(
+            lenses,
+            [
+                (3, "2 references".to_string()),
+                (8, "0 references".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_document_symbols_nest_members_and_xdata_under_class() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let url = root.join("Test/Outline.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            url.clone(),
+            "Class Test.Outline\n{\n\nParameter VERSION = 1;\n\nProperty Name As %String;\n\n\
+             ClassMethod Run()\n{\n    quit\n}\n\n\
+             XData Config\n{\n<config/>\n}\n}\n"
+                .to_string(),
+        );
+
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let symbols = state.data.read().get_document_symbols(&url).unwrap();
+        assert_eq!(symbols.len(), 1);
+        let class = &symbols[0];
+        assert_eq!(class.selection_range.start, Position::new(0, 6));
+        let children = class.children.as_ref().unwrap();
+        let outline: Vec<(&str, SymbolKind)> = children
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind))
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                ("VERSION", SymbolKind::CONSTANT),
+                ("Name", SymbolKind::PROPERTY),
+                ("Run", SymbolKind::METHOD),
+                ("Config", SymbolKind::STRUCT),
+            ]
+        );
+        // the method's range covers its body; its selection range, its name
+        assert_eq!(children[2].range.start.line, 7);
+        assert_eq!(children[2].range.end.line, 10);
+        assert_eq!(children[2].selection_range.start, Position::new(7, 12));
+    }
+
+    #[test]
+    fn test_parse_export_maps_lines_back_to_archive() {
+        let export = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<Export generator=\"IRIS\" version=\"26\">\n",
+            "<Class name=\"Demo.Person\">\n",
+            "<Super>%Persistent</Super>\n",
+            "<Method name=\"Greet\">\n",
+            "<ClassMethod>1</ClassMethod>\n",
+            "<FormalSpec>&amp;name:%String,count:%Integer=1</FormalSpec>\n",
+            "<Implementation><![CDATA[\n",
+            "  write \"hi \", name\n",
+            "]]></Implementation>\n",
+            "</Method>\n",
+            "</Class>\n",
+            "<Routine name=\"Demo\" type=\"MAC\"><![CDATA[Demo ;\n quit\n]]></Routine>\n",
+            "</Export>\n",
+        );
+        let items = parse_export(export);
+        assert_eq!(items.len(), 2);
+
+        let class = &items[0];
+        assert_eq!(class.document_name, "Demo.Person.cls");
+        let lines: Vec<&str> = class.content.lines().collect();
+        assert_eq!(lines[0], "Class Demo.Person Extends %Persistent");
+        assert_eq!(
+            lines[2],
+            "ClassMethod Greet(ByRef name As %String, count As %Integer = 1)"
+        );
+        assert_eq!(lines[4], "  write \"hi \", name");
+
+        let archive = Url::parse("file:///ws/export.xml").unwrap();
+        assert_eq!(
+            class.url(&archive).as_str(),
+            "file:///ws/export.xml/Demo.Person.cls"
+        );
+        let line_map = ExportLineMap::new(archive, class);
+        let mapped = line_map.to_archive_position(Position::new(4, 2));
+        assert_eq!(mapped, Position::new(8, 2));
+        let header = line_map.to_archive_position(Position::new(2, 5));
+        assert_eq!(header, Position::new(4, 0));
+
+        let routine = &items[1];
+        assert_eq!(routine.document_name, "Demo.mac");
+        assert_eq!(routine.content, "Demo ;\n quit\n");
+        assert_eq!(routine.lines[0], (12, Some(41)));
+    }
+
+    #[test]
+    fn test_apply_content_changes_detects_full_text_changes() {
+        let ranged = |line, start, end, text: &str| TextDocumentContentChangeEvent {
+            range: Some(tower_lsp::lsp_types::Range::new(
+                Position::new(line, start),
+                Position::new(line, end),
+            )),
+            range_length: None,
+            text: text.to_string(),
+        };
+        let full = |text: &str| TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: text.to_string(),
+        };
+
+        let mut text = TextBuffer::from("set x = 1\nset y = 2\n");
+        let edits = apply_content_changes(&mut text, &[ranged(1, 4, 5, "zz")]).unwrap();
+        assert_eq!(text.as_str(), "set x = 1\nset zz = 2\n");
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].start_byte, edits[0].old_end_byte), (14, 15));
+        assert_eq!(edits[0].new_end_position, Point::new(1, 6));
+
+        // a full-text change resets the text; later ranged changes apply on top of it
+        let mut text = TextBuffer::from("old");
+        let edits = apply_content_changes(
+            &mut text,
+            &[
+                ranged(0, 0, 1, "b"),
+                full("set a = 1"),
+                ranged(0, 4, 5, "b"),
+            ],
+        );
+        assert!(edits.is_none());
+        assert_eq!(text.as_str(), "set b = 1");
+    }
+
+    #[test]
+    fn test_text_buffer_edits_use_utf16_columns() {
+        let mut text = TextBuffer::from("set a = \"é😀\"\nset b = 2\n");
+        // `😀` is two UTF-16 units but four bytes
+        assert_eq!(
+            text.position_to_byte(Position::new(0, 12)),
+            (15, Point::new(0, 15))
+        );
+        // a column past the end of the line stops at its line break
+        assert_eq!(
+            text.position_to_byte(Position::new(0, 99)),
+            (16, Point::new(0, 16))
+        );
+        assert_eq!(text.position_to_byte(Position::new(5, 0)).0, 27);
+
+        let edit = text.edit(Position::new(0, 10), Position::new(0, 12), "x\ny");
+        assert_eq!(text.as_str(), "set a = \"éx\ny\"\nset b = 2\n");
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (11, 15, 14)
+        );
+        assert_eq!(edit.new_end_position, Point::new(1, 1));
+
+        let clone = text.clone();
+        text.edit(Position::new(2, 4), Position::new(2, 5), "c");
+        assert_eq!(text.as_str(), "set a = \"éx\ny\"\nset c = 2\n");
+        assert_eq!(clone.as_str(), "set a = \"éx\ny\"\nset b = 2\n");
+    }
+
+    #[test]
+    fn test_symbol_location_index_innermost() {
+        let range = |start_byte: usize, end_byte: usize| Range {
+            start_byte,
+            end_byte,
+            start_point: Point::new(0, start_byte),
+            end_point: Point::new(0, end_byte),
+        };
+        let entries = vec![
+            SymbolEntry {
+                range: range(40, 45),
+                symbol: SymbolRef::MethodCall(1),
+            },
+            SymbolEntry {
+                range: range(0, 100),
+                symbol: SymbolRef::PrivateMethod("Outer".to_string()),
+            },
+            SymbolEntry {
+                range: range(10, 20),
+                symbol: SymbolRef::MethodCall(0),
+            },
+        ];
+        let index = SymbolLocationIndex::from_entries(entries);
+        assert_eq!(index.symbols_at(15).len(), 2);
+        assert_eq!(
+            index.symbol_at(15).map(|entry| entry.symbol.clone()),
+            Some(SymbolRef::MethodCall(0))
+        );
+        assert_eq!(
+            index.symbol_at(45).map(|entry| entry.symbol.clone()),
+            Some(SymbolRef::MethodCall(1))
+        );
+        assert_eq!(
+            index.symbol_at(30).map(|entry| entry.symbol.clone()),
+            Some(SymbolRef::PrivateMethod("Outer".to_string()))
+        );
+        assert!(index.symbol_at(150).is_none());
+    }
+
+    #[test]
+    fn test_bracket_diagnostics_targets_unterminated_constructs() {
+        let content = "ClassMethod Test()\n{\n    &sql(SELECT Name INTO :name FROM Sample.Person WHERE Name = ')'\n    set x = ##class(Foo.Bar).Baz(\"(\") ; (\n}\n";
+        let diagnostics = bracket_diagnostics(content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Unterminated &sql block: missing closing )"
+        );
+        assert_eq!(diagnostics[0].range.start.line, 2);
+        assert_eq!(diagnostics[0].range.start.character, 4);
+        assert!(bracket_diagnostics("set x = $s(a:1, 1:$$s(2))").is_empty());
+    }
+
+    #[test]
+    fn test_lexical_scans_step_over_non_ascii_text() {
+        let content = "    set café = \"naïve\" ; déjà (\n    set x(\"ü\") = é // ü (\n    do ^Rtn\n    do Label\n    &sql(SELECT Nom INTO :nom FROM Café -- ü (\n    )\n    /* ü ( */\n";
+        assert!(bracket_diagnostics(content).is_empty());
+        let routines: Vec<String> = routine_references(content)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(routines, ["Rtn"]);
+        let labels: Vec<String> = local_label_calls(content)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(labels, ["Label"]);
+        let host_variables = find_host_variables(content, 0..content.len());
+        assert_eq!(host_variables.len(), 1);
+        assert_eq!(host_variables[0].name, "nom");
+        assert!(package_references(content, "Café").is_empty());
+    }
+
+    #[test]
+    fn test_method_completions_follow_insert_style() {
+        let (target, partial) = method_name_prefix("    do ##class(Sample.Person).Op").unwrap();
+        assert_eq!(target, MethodTarget::ClassMethodsOf("Sample.Person"));
+        assert_eq!(partial, "Op");
+        assert_eq!(
+            method_name_prefix("    set x = ..Na"),
+            Some((MethodTarget::CurrentClass, "Na"))
+        );
+        assert_eq!(
+            method_name_prefix("    set x = person.Na"),
+            Some((MethodTarget::Oref("person"), "Na"))
+        );
+
+        let mut method = Method::new(
+            "OpenById".to_string(),
+            None,
+            None,
+            CodeMode::Code,
+            true,
+            None,
+            Vec::new(),
+            MethodType::ClassMethod,
+        );
+        method.arguments = vec!["id".to_string(), "concurrency".to_string()];
+        let methods = [method];
+        let position = Position::new(0, 32);
+        let new_text = |style, snippets| {
+            let items = method_completions(&methods, "op", position, style, snippets);
+            match &items[0].text_edit {
+                Some(CompletionTextEdit::Edit(edit)) => edit.new_text.clone(),
+                _ => String::new(),
+            }
+        };
+        assert_eq!(new_text(MethodInsertStyle::Name, true), "OpenById");
+        assert_eq!(new_text(MethodInsertStyle::Parens, true), "OpenById()");
+        assert_eq!(
+            new_text(MethodInsertStyle::Snippet, true),
+            "OpenById(${1:id}, ${2:concurrency})$0"
+        );
+        // without client snippet support, snippets degrade to parentheses
+        assert_eq!(new_text(MethodInsertStyle::Snippet, false), "OpenById()");
+    }
+
+    #[test]
+    fn test_remaining_class_statements_are_member_kinds() {
+        let kinds = [
+            ("index", MemberKind::Index, SymbolKind::KEY, 'i'),
+            ("foreignkey", MemberKind::ForeignKey, SymbolKind::KEY, 'f'),
+            ("trigger", MemberKind::Trigger, SymbolKind::EVENT, 't'),
+            ("query", MemberKind::Query, SymbolKind::FUNCTION, 'q'),
+            (
+                "projection",
+                MemberKind::Projection,
+                SymbolKind::INTERFACE,
+                'j',
+            ),
+            ("storage", MemberKind::Storage, SymbolKind::STRUCT, 's'),
+        ];
+        for (statement_kind, member_kind, symbol_kind, letter) in kinds {
+            assert_eq!(
+                MemberKind::from_statement_kind(statement_kind),
+                Some(member_kind)
+            );
+            assert_eq!(member_kind.symbol_kind(), symbol_kind);
+            let tags = render_ctags(vec![TagEntry {
+                name: "Member".to_string(),
+                path: "src/Sample/Person.cls".to_string(),
+                line: 3,
+                kind: TagKind::Member(member_kind),
+                class_name: Some("Sample.Person".to_string()),
+            }]);
+            assert!(tags.ends_with(&format!(
+                "Member\tsrc/Sample/Person.cls\t3;\"\t{letter}\tclass:Sample.Person\n"
+            )));
+        }
+        // XData blocks are indexed on their own and relationships aren't parsed yet
+        assert_eq!(MemberKind::from_statement_kind("xdata"), None);
+        assert_eq!(MemberKind::from_statement_kind("relationship"), None);
+    }
+
+    #[test]
+    fn test_render_ctags_sorts_and_scopes_members() {
+        let entry = |name: &str, line, kind, class_name: Option<&str>| TagEntry {
+            name: name.to_string(),
+            path: "src/Sample/Person.cls".to_string(),
+            line,
+            kind,
+            class_name: class_name.map(str::to_string),
+        };
+        let tags = render_ctags(vec![
+            entry(
+                "Name",
+                4,
+                TagKind::Member(MemberKind::Property),
+                Some("Sample.Person"),
+            ),
+            entry("Sample.Person", 1, TagKind::Class, None),
+            entry(
+                "Greet",
+                6,
+                TagKind::Member(MemberKind::Method),
+                Some("Sample.Person"),
+            ),
+        ]);
+        let lines: Vec<&str> = tags.lines().filter(|l| !l.starts_with("!_TAG_")).collect();
+        assert_eq!(
+            lines,
+            [
+                "Greet\tsrc/Sample/Person.cls\t6;\"\tm\tclass:Sample.Person",
+                "Name\tsrc/Sample/Person.cls\t4;\"\tp\tclass:Sample.Person",
+                "Sample.Person\tsrc/Sample/Person.cls\t1;\"\tc",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_host_variables_in_embedded_sql() {
+        let content = "    set id = 1 ; not :sql\n    &sql(SELECT Name, Age INTO :name, :age FROM Sample.Person\n        WHERE ID = :id AND Name <> ':skip')\n    write name\n";
+        let host_variables = find_host_variables(content, 0..content.len());
+        let found: Vec<(&str, bool)> = host_variables
+            .iter()
+            .map(|host_variable| (host_variable.name.as_str(), host_variable.is_write))
+            .collect();
+        assert_eq!(found, [("name", true), ("age", true), ("id", false)]);
+        let id = &host_variables[2];
+        assert_eq!(&content[id.range.start_byte..id.range.end_byte], "id");
+        assert_eq!(id.range.start_point, Point::new(2, 20));
+    }
+
+    #[test]
+    fn test_resolve_list_and_piece_values() {
+        let assignments = HashMap::from([
+            ("row", "$lb(\"Smith,John\",42,$lb(1.5))"),
+            ("name", "$list(row)"),
+        ]);
+        let lookup = |name: &str| assignments.get(name).map(|value| value.to_string());
+        let resolve = |expression| resolve_value(expression, &lookup, 0);
+        assert_eq!(
+            resolve("$list(row,2)"),
+            Some(KnownValue::Number("42".to_string()))
+        );
+        assert_eq!(resolve("$li(row,2)").unwrap().type_name(), "%Integer");
+        assert_eq!(
+            resolve("$lg($list(row,-1),1)").unwrap().type_name(),
+            "%Numeric"
+        );
+        assert_eq!(
+            resolve("$piece(name,\",\",2)"),
+            Some(KnownValue::String("John".to_string()))
+        );
+        assert_eq!(
+            resolve("$p($list(row,1),\",\")").unwrap().source(),
+            "\"Smith\""
+        );
+        assert_eq!(resolve("$list(unknown,1)"), None);
+    }
+
+    #[test]
+    fn test_lint_diagnostics_link_rule_docs() {
+        let diagnostics = bracket_diagnostics("    set x = $select(a:1, 1:0\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String(
+                UNBALANCED_CONSTRUCT_RULE.to_string()
+            ))
+        );
+        let href = diagnostics[0]
+            .code_description
+            .as_ref()
+            .unwrap()
+            .href
+            .as_str();
+        assert_eq!(
+            href,
+            "command:objectscript.ruleDocs?%5B%22unbalanced-construct%22%5D"
+        );
+        assert!(rule_docs(Some(UNBALANCED_CONSTRUCT_RULE))
+            .unwrap()
+            .contains("## Example"));
+        assert!(RULES
+            .iter()
+            .all(|rule| rule_code_description(rule.id).is_some()));
+        assert_eq!(rule_docs(Some("no-such-rule")), None);
+    }
+
+    #[test]
+    fn test_bulk_change_detector_counts_events_within_window() {
+        let start = Instant::now();
+        let mut detector = BulkChangeDetector::new();
+        assert!(!detector.record(start, 150, 200));
+        assert!(detector.record(start + Duration::from_millis(500), 60, 200));
+        // the first batch has left the window
+        assert!(!detector.record(start + Duration::from_millis(1600), 100, 200));
+        assert!(!detector.record(start + Duration::from_millis(1700), 1000, 0));
+    }
+
+    #[test]
+    fn test_system_api_completion_and_argument_checks() {
+        assert_eq!(
+            system_api_prefix("    do $system.OB"),
+            Some((SystemApiTarget::Classes, "OB"))
+        );
+        assert_eq!(
+            system_api_prefix("    set sc = $SYSTEM.OBJ.Comp"),
+            Some((SystemApiTarget::MethodsOf("OBJ"), "Comp"))
+        );
+        assert_eq!(system_api_prefix("    do $$system.OBJ."), None);
+
+        let content = "    set sc = $SYSTEM.OBJ.Load()\n    set ok = $SYSTEM.Status.IsOK(sc, \"a,b\", 1)\n    do $SYSTEM.OBJ.Compile(\"Sample.*\", \"ck\")\n";
+        let references = find_system_references(content);
+        assert_eq!(references.len(), 3);
+        let messages: Vec<String> = system_api_diagnostics(content, &references)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "%SYSTEM.OBJ.Load requires at least 1 argument; 0 passed",
+                "%SYSTEM.Status.IsOK takes at most 1 argument; 3 passed",
+            ]
+        );
+
+        let cursor = content.find("\"ck\"").unwrap();
+        let help = system_api_signature_help(content, cursor).unwrap();
+        assert_eq!(help.active_parameter, Some(1));
+        assert!(help.signatures[0]
+            .label
+            .starts_with("Compile(classes As %String"));
+
+        let content =
+            "    set café = \"ü\" // $SYSTEM.OBJ.Load()\n    set x = é_$SYSTEM.Status.IsOK(sc)\n";
+        let references = find_system_references(content);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].class, "Status");
+        assert_eq!(references[0].argument_count(content), 1);
+    }
+
+    #[test]
+    fn test_position_encoding_negotiation_and_units() {
+        assert_eq!(
+            PositionEncoding::negotiate(None),
+            Some(PositionEncoding::Utf16)
+        );
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&[
+                PositionEncodingKind::new("utf-7"),
+                PositionEncodingKind::UTF32,
+                PositionEncodingKind::UTF8
+            ])),
+            Some(PositionEncoding::Utf32)
+        );
+        assert_eq!(PositionEncoding::negotiate(Some(&[])), None);
+
+        // `é` is two UTF-8 bytes, `😀` four bytes or two UTF-16 units
+        let text = "é😀x";
+        assert_eq!(PositionEncoding::Utf8.units(text), 7);
+        assert_eq!(PositionEncoding::Utf16.units(text), 4);
+        assert_eq!(PositionEncoding::Utf32.units(text), 3);
+    }
+
+    #[test]
+    fn test_doctor_reports_position_encoding_negotiation() {
+        assert!(check_position_encoding(None).ok);
+        assert!(
+            check_position_encoding(Some(&[
+                PositionEncodingKind::UTF8,
+                PositionEncodingKind::UTF16
+            ]))
+            .ok
+        );
+        let utf8_only = check_position_encoding(Some(&[PositionEncodingKind::UTF8]));
+        assert!(utf8_only.ok);
+        assert_eq!(utf8_only.detail, "utf-8 (negotiated)");
+        let unsupported = check_position_encoding(Some(&[PositionEncodingKind::new("utf-7")]));
+        assert!(!unsupported.ok);
+        assert!(unsupported.detail.contains("utf-7"));
+
+        let report = render_report(&[check_system_stubs(true), unsupported]);
+        assert!(report.contains("[ok] $SYSTEM stubs: "));
+        assert!(report.contains("[FAIL] position encoding: "));
+        assert!(report.ends_with("1 problem(s) found.\n"));
+    }
+
+    #[test]
+    fn test_call_graph_finds_direct_and_mutual_recursion() {
+        let node = |class: usize, method: &str| CallGraphNode {
+            class: ClassId(class, 0),
+            method: method.to_string(),
+        };
+        let edge = |caller: CallGraphNode, callee: CallGraphNode| CallEdge {
+            caller,
+            callee,
+            call_range: Range {
+                start_byte: 0,
+                end_byte: 0,
+                start_point: Point::new(0, 0),
+                end_point: Point::new(0, 0),
+            },
+        };
+        let graph = CallGraph::new(vec![
+            edge(node(0, "Even"), node(1, "Odd")),
+            edge(node(1, "Odd"), node(0, "Even")),
+            edge(node(0, "Walk"), node(0, "Walk")),
+            edge(node(0, "Main"), node(0, "Even")),
+            edge(node(0, "Main"), node(1, "Leaf")),
+        ]);
+        let recursive = graph.recursive_methods();
+        assert_eq!(recursive.len(), 3);
+        assert_eq!(recursive[&node(0, "Even")], [node(1, "Odd")]);
+        assert_eq!(recursive[&node(1, "Odd")], [node(0, "Even")]);
+        assert!(recursive[&node(0, "Walk")].is_empty());
+        assert!(!recursive.contains_key(&node(0, "Main")));
+        assert!(!recursive.contains_key(&node(1, "Leaf")));
+    }
+
+    #[test]
+    fn test_routine_references_skip_globals() {
+        let content = "    do ^Setup, Run^Jobs
+    set x = $$Get^%Util(1) + $$^Calc
+    set ^Data(1) = \"do ^Str\" ; do ^Comment
+    job:ok ^Worker
+";
+        let names: Vec<String> = routine_references(content)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["Setup", "Jobs", "%Util", "Calc", "Worker"]);
+
+        let url = Url::parse("file:///src/MyApp/Macros.inc").unwrap();
+        let (path_name, file_type) = routine_path_name(&url).unwrap();
+        assert_eq!(file_type, FileType::Inc);
+        assert!(routine_name_matches(&path_name, "MyApp.Macros"));
+        assert!(routine_name_matches(&path_name, "myapp.macros"));
+        assert!(!routine_name_matches(&path_name, "Macros.Extra"));
+    }
+
+    #[test]
+    fn test_class_rename_edits_full_and_short_names() {
+        let content = "Import Legacy
+Class Legacy.Line Extends (%Persistent, Orders)
+{
+Property Order As Legacy.Orders;
+Property Items As list Of Orders;
+ClassMethod Run() As Orders
+{
+    set x = ##class(Orders).%New(), y = ##class(Legacy.Orders).Find(\"Orders\")
+    set Orders = 1, z = Legacy.Orders.Other + obj.Orders + ^Orders(1)
+}
+}
+";
+        assert!(imports_package(content, "Legacy"));
+        assert!(!imports_package(content, "Leg"));
+        assert!(is_class_name("MyApp.Orders"));
+        assert!(!is_class_name("Orders"));
+        assert!(!is_class_name("MyApp.1Orders"));
+
+        let edits = class_rename_edits(content, "Legacy.Orders", "Legacy.Order", true);
+        let renamed: Vec<(u32, &str)> = edits
+            .iter()
+            .map(|edit| (edit.range.start.line, edit.new_text.as_str()))
+            .collect();
+        assert_eq!(
+            renamed,
+            [
+                (1, "Order"),
+                (3, "Legacy.Order"),
+                (4, "Order"),
+                (5, "Order"),
+                (7, "Order"),
+                (7, "Legacy.Order"),
+            ]
+        );
+        // moved to another package, names without a package get the full new name
+        let edits = class_rename_edits(content, "Legacy.Orders", "Sales.Orders", true);
+        assert_eq!(edits[0].new_text, "Sales.Orders");
+        // without the package imported, only full names are references
+        let edits = class_rename_edits(content, "Legacy.Orders", "Sales.Orders", false);
+        assert_eq!(edits.len(), 2);
+    }
+
+    #[test]
+    fn test_package_references_skip_globals_sql_and_members() {
+        let content = "Import (Legacy, Other)
+Class Legacy.Orders.Line Extends (%Persistent, Legacy.Base)
+{
+Property Order As Legacy.Orders.Order;
+ClassMethod Run()
+{
+    set x = ##class(Legacy.Util).Get(\"Legacy.Util\") ; Legacy.Comment
+    set ^Legacy.Data(1) = obj.Legacy.Name + $$$Legacy.Macro
+    &sql(SELECT Name FROM Legacy.Orders WHERE ID = 1)
+    set y = LegacyOld.Util + Legacy
+}
+}
+";
+        let references = package_references(content, "Legacy");
+        assert!(references
+            .iter()
+            .all(|range| &content[range.start_byte..range.end_byte] == "Legacy"));
+        let rows: Vec<usize> = references
+            .iter()
+            .map(|range| range.start_point.row)
+            .collect();
+        assert_eq!(rows, [0, 1, 1, 3, 6]);
+
+        assert_eq!(
+            renamed_class_path(
+                "/src/Legacy/Orders.cls",
+                "Legacy.Orders",
+                "MyApp.Legacy.Orders"
+            ),
+            Some("/src/MyApp/Legacy/Orders.cls".to_string())
+        );
+        assert_eq!(
+            renamed_class_path(
+                "/src/Legacy.Orders.cls",
+                "Legacy.Orders",
+                "MyApp.Legacy.Orders"
+            ),
+            Some("/src/MyApp.Legacy.Orders.cls".to_string())
+        );
+        assert_eq!(
+            renamed_class_path("/src/Orders.cls", "Legacy.Orders", "MyApp.Legacy.Orders"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_member_visibility_keywords() {
+        let visibility = member_visibility("[ Internal, PublicList = (a, b), ServerOnly = 1 ]");
+        assert!(visibility.internal && visibility.server_only);
+        assert_eq!(visibility.labels(), ["Internal", "ServerOnly"]);
+
+        let visibility = member_visibility("[ Not Internal, ServerOnly = 0, Private ]");
+        assert_eq!(visibility, MemberVisibility::default());
+        assert_eq!(visibility.hover_note("MyApp.Util"), None);
+
+        let note = member_visibility("[internal]")
+            .hover_note("MyApp.Util")
+            .unwrap();
+        assert!(note.contains("not meant for use outside `MyApp.Util`"));
+    }
+
+    #[test]
+    fn test_property_declarations_split_into_type_and_keywords() {
+        let (property_type, keywords, _) =
+            split_member_declaration(" As list Of %String(MAXLEN = 50) [ Private, Required ];");
+        assert_eq!(property_type.as_deref(), Some("list Of %String"));
+        assert_eq!(keywords, "[ Private, Required ]");
+        assert_eq!(
+            keyword_flags(keywords),
+            [("Private", true), ("Required", true)]
+        );
+
+        let (property_type, keywords, _) =
+            split_member_declaration(" As %String(VALUELIST = \",[a],b\");");
+        assert_eq!(property_type.as_deref(), Some("%String"));
+        assert_eq!(keywords, "");
+
+        let (property_type, keywords, _) = split_member_declaration(" [ Not Private ];");
+        assert_eq!(property_type, None);
+        assert_eq!(keyword_flags(keywords), [("Private", false)]);
+
+        let property = ClassProperty {
+            name: "Total".to_string(),
+            property_type: Some("%Numeric".to_string()),
+            is_public: false,
+            sql_field_name: None,
+            range: Range {
+                start_byte: 0,
+                end_byte: 0,
+                start_point: Point::new(0, 0),
+                end_point: Point::new(0, 0),
+            },
+        };
+        assert_eq!(
+            property.signature(),
+            "Property Total As %Numeric [ Private ]"
+        );
+    }
+
+    #[test]
+    fn test_parameter_declarations_and_completions() {
+        let (parameter_type, _, value) =
+            split_member_declaration(" As %String [ Final ] = \"a;b = c\";");
+        assert_eq!(parameter_type.as_deref(), Some("%String"));
+        assert_eq!(value.as_deref(), Some("\"a;b = c\""));
+        let (parameter_type, keywords, value) = split_member_declaration(" = 5;");
+        assert_eq!(
+            (parameter_type, keywords, value.as_deref()),
+            (None, "", Some("5"))
+        );
+
+        assert_eq!(
+            parameter_name_prefix("    set x = ..#VER"),
+            Some((None, "VER"))
+        );
+        assert_eq!(
+            parameter_name_prefix("    set x = ##class(MyApp.Base).#"),
+            Some((Some("MyApp.Base"), ""))
+        );
+        assert_eq!(parameter_name_prefix("    set x = ..VER"), None);
+        assert_eq!(parameter_name_prefix("    set x = obj.#VER"), None);
+
+        let parameter = ClassParameter {
+            name: "VERSION".to_string(),
+            property_type: Some("%String".to_string()),
+            default_argument_value: Some("\"1.2\"".to_string()),
+            range: Range {
+                start_byte: 0,
+                end_byte: 0,
+                start_point: Point::new(0, 0),
+                end_point: Point::new(0, 0),
+            },
+        };
+        let parameters = vec![(parameter, "MyApp.Base".to_string())];
+        let items = parameter_completions(&parameters, "ver", Position::new(3, 17));
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].detail.as_deref(),
+            Some("Parameter VERSION As %String = \"1.2\"")
+        );
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.range.start, Position::new(3, 14));
+    }
+
+    #[test]
+    fn test_xdata_keywords_and_content() {
+        let declaration = " [ MimeType = \"application/json\", Internal ]\n{\n{ \"a\": 1 }\n}";
+        let (_, keywords, _) = split_member_declaration(declaration);
+        assert_eq!(
+            keyword_value(keywords, "mimetype"),
+            Some("application/json")
+        );
+        assert_eq!(keyword_value(keywords, "Internal"), None);
+        let (start, end) = braced_content(declaration).unwrap();
+        assert_eq!(&declaration[start..end], "\n{ \"a\": 1 }\n");
+        assert_eq!(braced_content(" [ MimeType = text/xml ];"), None);
+    }
+
+    #[test]
+    fn test_response_cache_drops_responses_computed_before_a_change() {
+        let cache = ResponseCache::new();
+        let uri = Url::parse("file:///src/MyApp/Util.cls").unwrap();
+        let key = |version| ResponseKey::new("textDocument/hover", &uri, version, None);
+
+        let generation = cache.generation();
+        cache.insert(key(Some(1)), generation, Some("hover".to_string()));
+        assert_eq!(
+            cache.get::<Option<String>>(&key(Some(1))),
+            Some(Some("hover".to_string()))
+        );
+        assert_eq!(cache.get::<Option<String>>(&key(Some(2))), None);
+        assert_eq!(cache.get::<Vec<String>>(&key(Some(1))), None);
+
+        // computed before the change, stored after it
+        let generation = cache.generation();
+        cache.clear();
+        cache.insert(key(Some(1)), generation, Some("stale".to_string()));
+        assert_eq!(cache.get::<Option<String>>(&key(Some(1))), None);
+    }
+
+    #[test]
+    fn test_usage_report_lists_hot_apis_first() {
+        let entry = |kind, name: &str, references, subclasses| UsageEntry {
+            kind,
+            name: name.to_string(),
+            references,
+            external_references: references,
+            subclasses,
+            path: "src/My App, Legacy/Util.cls".to_string(),
+            line: 1,
+        };
+        let entries = vec![
+            entry(UsageKind::Method, "MyApp.Util.Unused", 0, None),
+            entry(UsageKind::Class, "MyApp.Util", 3, Some(1)),
+            entry(UsageKind::Method, "MyApp.Util.Log", 3, None),
+        ];
+        let csv = render_usage_csv(entries.clone());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "kind,name,references,external_references,subclasses,path,line"
+        );
+        assert_eq!(
+            lines[1],
+            "class,MyApp.Util,3,3,1,\"src/My App, Legacy/Util.cls\",1"
+        );
+        assert!(lines[2].starts_with("method,MyApp.Util.Log,3,3,,"));
+        assert!(lines[3].starts_with("method,MyApp.Util.Unused,0,0,,"));
+
+        let json = render_usage_json(entries);
+        assert_eq!(json[0]["subclasses"], 1);
+        assert_eq!(json[2]["name"], "MyApp.Util.Unused");
+    }
+
+    #[test]
+    fn test_variable_declaration_is_first_definition_in_scope_chain() {
+        let line_range = |row: usize, start: usize, end: usize| Range {
+            start_byte: 0,
+            end_byte: 0,
+            start_point: Point::new(row, start),
+            end_point: Point::new(row, end),
+        };
+        let mut scope_tree = ScopeTree::new(ClassGlobalSymbolId(0, 0));
+        let method =
+            scope_tree.add_scope(Point::new(3, 0), Point::new(10, 1), scope_tree.root, false);
+        scope_tree.add_scope(Point::new(4, 0), Point::new(9, 1), method, false);
+        // `Run(count)`, then `set count = count + 1` inside the nested scope
+        scope_tree.new_variable_symbol("count".to_string(), line_range(3, 16, 21), vec![], vec![]);
+        scope_tree.new_variable_symbol("total".to_string(), line_range(5, 8, 13), vec![], vec![]);
+        scope_tree.new_variable_symbol("total".to_string(), line_range(7, 8, 13), vec![], vec![]);
+
+        let inner = Point::new(8, 10);
+        assert_eq!(
+            scope_tree.find_declaration(inner, "total"),
+            Some(line_range(5, 8, 13))
+        );
+        assert_eq!(
+            scope_tree.find_declaration(inner, "count"),
+            Some(line_range(3, 16, 21))
+        );
+        assert_eq!(scope_tree.find_declaration(inner, "missing"), None);
+    }
+
+    #[test]
+    fn test_method_signature_lists_arguments_return_type_and_keywords() {
+        let mut method = Method::new(
+            "Total".to_string(),
+            None,
+            Some(Language::Python),
+            CodeMode::Expression,
+            false,
+            None,
+            Vec::new(),
+            MethodType::ClassMethod,
+        );
+        method.argument_declarations = vec![
+            "items As %List".to_string(),
+            "ByRef count As %Integer = 0".to_string(),
+        ];
+        method.return_type_name = Some("%Status".to_string());
+        assert_eq!(
+            method.signature(),
+            "ClassMethod Total(items As %List, ByRef count As %Integer = 0) As %Status \
+             [ Private, Language = python, CodeMode = expression ]"
+        );
+
+        let method = Method::new(
+            "Greet".to_string(),
+            None,
+            Some(Language::Objectscript),
+            CodeMode::Code,
+            true,
+            None,
+            Vec::new(),
+            MethodType::InstanceMethod,
+        );
+        assert_eq!(method.signature(), "Method Greet()");
+    }
+
+    #[test]
+    fn test_class_hover_summary_includes_description() {
+        let content = "Include Macros\n\n/// Orders placed by customers.\n///\n///   Totals are cached.\nClass MyApp.Orders Extends (%Persistent, MyApp.Base) [ Language = tsql ]\n{\n}\n";
+        let description = doc_comment_above(content, 5);
+        assert_eq!(
+            description.as_deref(),
+            Some("Orders placed by customers.\n\n  Totals are cached.")
+        );
+        assert_eq!(doc_comment_above(content, 1), None);
+
+        let mut class = Class::new("MyApp.Orders".to_string());
+        class.superclass_names = vec!["%Persistent".to_string(), "MyApp.Base".to_string()];
+        class.default_language = Some(Language::TSql);
+        class.description = description;
+        assert_eq!(
+            class.hover_summary(),
+            "**Class** `MyApp.Orders`\n\nPackage `MyApp`\n\n\
+             Extends `%Persistent`, `MyApp.Base`\n\n`[ Language = tsql ]`\n\n---\n\n\
+             Orders placed by customers.\n\n  Totals are cached."
+        );
+    }
+
+    #[test]
+    fn test_class_qualifier_completions_commit_and_close_paren() {
+        let line = "    set x = ##class(MyApp.";
+        let prefix = class_name_prefix(line).unwrap();
+        assert!(is_class_qualifier_position(line, prefix));
+        let classes = [
+            "MyApp.Orders".to_string(),
+            "MyApp.Data.Person".to_string(),
+            "Other.Util".to_string(),
+        ];
+        let mut items = package_segment_completions(prefix, &classes, Position::new(0, 26));
+        commit_class_qualifiers(&mut items, true);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["Data", "Orders"]);
+        assert_eq!(items[0].commit_characters, Some(vec![".".to_string()]));
+        assert_eq!(items[1].commit_characters, Some(vec![")".to_string()]));
+        assert_eq!(items[1].insert_text_format, Some(InsertTextFormat::SNIPPET));
+        let Some(CompletionTextEdit::Edit(edit)) = &items[1].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.new_text, "Orders)$0");
+
+        let mut items = package_segment_completions(prefix, &classes, Position::new(0, 26));
+        close_class_qualifiers(&mut items);
+        commit_class_qualifiers(&mut items, true);
+        assert_eq!(items[1].commit_characters, None);
+        let Some(CompletionTextEdit::Edit(edit)) = &items[1].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.new_text, "Orders).");
+    }
+
+    #[test]
+    fn test_oref_dot_completion_target_and_constructor_calls() {
+        assert_eq!(
+            method_name_prefix("    do order.%Sa"),
+            Some((MethodTarget::Oref("order"), "%Sa"))
+        );
+        assert!(method_name_prefix("    set x = ^Orders.Na").is_none());
+        assert!(method_name_prefix("    set x = $system.Na").is_none());
+        assert!(method_name_prefix("    set x = a.b.Na").is_none());
+        assert!(method_name_prefix("    set x = 1.5").is_none());
+
+        assert_eq!(
+            class_method_call_parts("##class(MyApp.Order).%OpenId(id)"),
+            Some(("MyApp.Order", "%OpenId"))
+        );
+        assert_eq!(
+            class_method_call_parts("##CLASS( MyApp.Order ).Find"),
+            Some(("MyApp.Order", "Find"))
+        );
+        assert_eq!(class_method_call_parts("..Find(id)"), None);
+
+        let property = |name: &str, property_type: Option<&str>| ClassProperty {
+            name: name.to_string(),
+            property_type: property_type.map(str::to_string),
+            is_public: true,
+            sql_field_name: None,
+            range: Range {
+                start_byte: 0,
+                end_byte: 0,
+                start_point: Point::new(0, 0),
+                end_point: Point::new(0, 0),
+            },
+        };
+        let properties = vec![
+            (property("Customer", None), "MyApp.Order".to_string()),
+            (property("Total", None), "MyApp.Base".to_string()),
+            (
+                property("TotalTax", Some("%Numeric")),
+                "MyApp.Base".to_string(),
+            ),
+        ];
+        let items = property_completions(&properties, "to", Position::new(3, 14));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].label, "Total");
+        assert_eq!(items[0].detail.as_deref(), Some("Property of MyApp.Base"));
+        assert_eq!(
+            items[1].detail.as_deref(),
+            Some("%Numeric (Property of MyApp.Base)")
+        );
+    }
+
+    #[test]
+    fn test_intrinsic_function_completions() {
+        assert_eq!(intrinsic_function_prefix("    set x = $p"), Some("p"));
+        assert_eq!(intrinsic_function_prefix("    set x = $"), Some(""));
+        assert_eq!(intrinsic_function_prefix("    set x = $$lab"), None);
+        assert_eq!(intrinsic_function_prefix("    set x = a$b"), None);
+
+        let position = Position::new(0, 15);
+        let items =
+            intrinsic_function_completions("lg", position, MethodInsertStyle::Snippet, true);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "$listget");
+        assert_eq!(
+            items[0].detail.as_deref(),
+            Some("$LISTGET(list[, position[, default]])")
+        );
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.new_text, "\\$listget(${1:list})$0");
+        assert_eq!(edit.range.start, Position::new(0, 12));
+
+        let items =
+            intrinsic_function_completions("ZDT", position, MethodInsertStyle::Parens, true);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["$ZDATETIME", "$ZDATETIMEH"]);
+    }
+
+    #[test]
+    fn test_include_file_macros_complete_after_dollars() {
+        let file = include_macros(
+            "#include MyApp.Common\n\
+             #define MaxRows 100\n\
+             #Define Log(%msg) do ##class(MyApp.Log).Write(%msg)\n\
+             #define Long 1+ ##continue\n\
+             2\n\
+             ROUTINE MyApp\n",
+        );
+        assert_eq!(file.includes, vec!["MyApp.Common"]);
+        assert_eq!(
+            file.macros,
+            vec![
+                MacroDefinition {
+                    name: "MaxRows".to_string(),
+                    arguments: None,
+                    value: "100".to_string(),
+                    line: 1,
+                },
+                MacroDefinition {
+                    name: "Log".to_string(),
+                    arguments: Some("(%msg)".to_string()),
+                    value: "do ##class(MyApp.Log).Write(%msg)".to_string(),
+                    line: 2,
+                },
+                MacroDefinition {
+                    name: "Long".to_string(),
+                    arguments: None,
+                    value: "1+ 2".to_string(),
+                    line: 3,
+                },
+            ]
+        );
+
+        assert_eq!(macro_prefix("    set x = $$$Ma"), Some("Ma"));
+        assert_eq!(macro_prefix("    set x = $$$"), Some(""));
+        assert_eq!(macro_prefix("    set x = $$Ma"), None);
+
+        let macros: Vec<(&MacroDefinition, String)> = file
+            .macros
+            .iter()
+            .map(|definition| (definition, "MyApp.Macros".to_string()))
+            .collect();
+        let items = macro_completions(&macros, "lo", Position::new(2, 17));
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["Log", "Long"]);
+        assert_eq!(items[0].detail.as_deref(), Some("$$$Log(%msg)"));
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.range.start, Position::new(2, 15));
+    }
+
+    #[test]
+    fn test_fuzzy_score_matches_segments_and_ranks_prefixes() {
+        assert!(fuzzy_score("mya.b.test", "MyApp.B:TestWrite").is_some());
+        assert!(fuzzy_score("mya.b:test", "MyApp.B.TestWrite").is_some());
+        assert!(fuzzy_score("myab.test", "MyApp.Base").is_none());
+        assert!(fuzzy_score("tw", "MyApp.B:TestWrite").is_some());
+        assert_eq!(fuzzy_score("", "MyApp.B"), Some(0));
+
+        let score = |candidate| fuzzy_score("person", candidate).unwrap();
+        assert!(score("Person") > score("PersonList"));
+        assert!(score("PersonList") > score("MyApp.Person"));
+        assert!(score("MyApp.Person") > score("MyApp.PublicErrorsOn"));
+    }
+
+    #[test]
+    fn test_encode_semantic_tokens_relative_and_without_overlaps() {
+        let content = "Class A.B\n{\n    set %x = \"é\", y = 1\n}\n";
+        let token = |start, end, token_type, modifiers| {
+            RawToken::new(byte_range(content, start, end), token_type, modifiers)
+        };
+        let tokens = vec![
+            token(20, 22, TokenType::Variable, PUBLIC),
+            token(6, 9, TokenType::Class, DECLARATION),
+            token(25, 29, TokenType::String, 0),
+            // overlaps the string
+            token(26, 28, TokenType::Macro, 0),
+            token(31, 32, TokenType::Variable, 0),
+            // spans two lines
+            token(10, 15, TokenType::Keyword, 0),
+        ];
+        let encoded: Vec<(u32, u32, u32, u32, u32)> = encode_tokens(content, tokens)
+            .into_iter()
+            .map(|token| {
+                (
+                    token.delta_line,
+                    token.delta_start,
+                    token.length,
+                    token.token_type,
+                    token.token_modifiers_bitset,
+                )
+            })
+            .collect();
+        assert_eq!(
+            encoded,
+            [
+                (0, 6, 3, TokenType::Class as u32, DECLARATION),
+                (2, 8, 2, TokenType::Variable as u32, PUBLIC),
+                // `é` is one UTF-16 code unit but two bytes
+                (0, 5, 3, TokenType::String as u32, 0),
+                (0, 5, 1, TokenType::Variable as u32, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semantic_token_edits_replace_between_common_prefix_and_suffix() {
+        let token = |delta_line, length| SemanticToken {
+            delta_line,
+            delta_start: 4,
+            length,
+            token_type: TokenType::Variable as u32,
+            token_modifiers_bitset: 0,
+        };
+        let previous = [token(0, 1), token(1, 2), token(1, 3), token(1, 4)];
+        assert!(token_edits(&previous, &previous).is_empty());
+
+        // the third token was renamed, a token added after it, and the last one deleted
+        let current = [token(0, 1), token(1, 2), token(1, 5), token(0, 6)];
+        let edits = token_edits(&previous, &current);
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].start, edits[0].delete_count), (10, 10));
+        assert_eq!(edits[0].data, Some(vec![token(1, 5), token(0, 6)]));
+
+        // the last two tokens were deleted
+        let edits = token_edits(&previous, &previous[..2]);
+        assert_eq!((edits[0].start, edits[0].delete_count), (10, 10));
+        assert_eq!(edits[0].data, None);
+    }
+
+    #[test]
+    fn test_syntax_error_messages_name_the_enclosing_construct() {
+        assert_eq!(
+            missing_message("}", Some("method body")),
+            "Syntax error: expected `}` to close method body"
+        );
+        assert_eq!(
+            missing_message("identifier", Some("argument list")),
+            "Syntax error: expected identifier in argument list"
+        );
+        assert_eq!(missing_message(";", None), "Syntax error: expected `;`");
+        assert_eq!(
+            unexpected_message("  set x = \n  quit", Some("`SET` command")),
+            "Syntax error: unexpected `set x =` in `SET` command"
+        );
+        assert_eq!(
+            unexpected_message("abcdefghijklmnopqrstuvwxyz0123456789", None),
+            "Syntax error: unexpected `abcdefghijklmnopqrstuvwxyz0123...`"
+        );
+        assert_eq!(
+            unexpected_message("", None),
+            "Syntax error: unexpected input"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_members_of_the_same_kind_are_errors() {
+        let content =
+            "Parameter A;\nMethod B() {}\nClassMethod B() {}\nProperty A;\nParameter A;\n";
+        let url = Url::parse("file:///workspace/Sample/Dup.cls").unwrap();
+        let member = |name: &str, kind, start| {
+            let range = byte_range(content, start, start + name.len());
+            ClassMember {
+                name: name.to_string(),
+                kind,
+                range,
+                declaration_range: range,
+                visibility: MemberVisibility::default(),
+            }
+        };
+        let members = [
+            member("A", MemberKind::Parameter, 10),
+            member("B", MemberKind::Method, 20),
+            member("B", MemberKind::Method, 39),
+            // a property may share its name with a parameter
+            member("A", MemberKind::Property, 55),
+            member("A", MemberKind::Parameter, 68),
+        ];
+        let diagnostics = duplicate_member_diagnostics(content, &url, &members);
+        let reported: Vec<(u32, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                (2, "Method \"B\" is already declared in this class"),
+                (4, "Parameter \"A\" is already declared in this class"),
+            ]
+        );
+        let first = &diagnostics[1].related_information.as_ref().unwrap()[0];
+        assert_eq!(first.location.range.start, Position::new(0, 10));
+    }
+
+    #[test]
+    fn test_members_colliding_with_inherited_members_of_another_kind() {
+        let content = "Property Save;\nMethod Name() {}\nParameter Total;\n";
+        let member = |name: &str, kind, start| {
+            let range = byte_range(content, start, start + name.len());
+            ClassMember {
+                name: name.to_string(),
+                kind,
+                range,
+                declaration_range: range,
+                visibility: MemberVisibility::default(),
+            }
+        };
+        let inherited = |class_name: &str, name: &str, kind, line| InheritedMember {
+            class_name: class_name.to_string(),
+            member: member(name, kind, 0),
+            location: Location::new(
+                Url::parse(&format!("file:///workspace/{class_name}.cls")).unwrap(),
+                LspRange::new(Position::new(line, 0), Position::new(line, 1)),
+            ),
+        };
+        let members = [
+            member("Save", MemberKind::Property, 9),
+            member("Name", MemberKind::Method, 22),
+            member("Total", MemberKind::Parameter, 42),
+        ];
+        // nearest superclass first
+        let superclasses = [
+            inherited("App.Base", "Save", MemberKind::Method, 3),
+            inherited("App.Root", "Save", MemberKind::Parameter, 7),
+            // overriding a member of the same kind is fine
+            inherited("App.Base", "Name", MemberKind::Method, 5),
+        ];
+        let diagnostics = member_collision_diagnostics(content, &members, &superclasses);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 9));
+        assert_eq!(
+            diagnostics[0].message,
+            "Property \"Save\" has the same name as the method inherited from \"App.Base\""
+        );
+        let related = &diagnostics[0].related_information.as_ref().unwrap()[0];
+        assert_eq!(related.location.range.start, Position::new(3, 0));
+        assert_eq!(related.message, "Method \"Save\" declared in \"App.Base\"");
+    }
+
+    #[test]
+    fn test_inheritance_cycles_are_recorded_instead_of_aborting_the_index() {
+        // A extends B, B extends C, C extends A, and D extends A
+        let mut model = GlobalSemanticModel::new();
+        for (name, parent) in [("A", 1), ("B", 2), ("C", 0), ("D", 0)] {
+            let mut class = Class::new(name.to_string());
+            class.inherited_classes = vec![ClassId(parent, 0)];
+            model.classes.insert(class);
+        }
+        let index = model.build_override_index(&HashMap::new(), &HashMap::new());
+        assert_eq!(
+            index.inheritance_cycles.get(&ClassId(1, 0)),
+            Some(&vec![ClassId(1, 0), ClassId(2, 0), ClassId(0, 0)])
+        );
+        assert_eq!(index.inheritance_cycles.len(), 3);
+        assert!(index.effective_methods.contains_key(&ClassId(3, 0)));
+
+        let content = "Class B Extends (%RegisteredObject, C)\n{\n}\n";
+        let superclasses = [
+            ("%RegisteredObject".to_string(), byte_range(content, 17, 34)),
+            ("C".to_string(), byte_range(content, 36, 37)),
+        ];
+        let diagnostics = inheritance_cycle_diagnostics(content, &superclasses, &["B", "C", "A"]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 36));
+        assert_eq!(
+            diagnostics[0].message,
+            "Class \"B\" inherits from itself: B -> C -> A -> B"
+        );
+    }
+
+    #[test]
+    fn test_removing_a_class_drops_its_symbols_but_keeps_other_ids() {
+        let range = |start_byte: usize| Range {
+            start_byte,
+            end_byte: start_byte + 1,
+            start_point: Point::new(0, start_byte),
+            end_point: Point::new(0, start_byte + 1),
+        };
+        let url_a = Url::parse("file:///ws/A.cls").unwrap();
+        let url_b = Url::parse("file:///ws/B.cls").unwrap();
+        let mut model = GlobalSemanticModel::new();
+        for (name, url) in [("A", &url_a), ("B", &url_b)] {
+            let class_symbol = model.new_class_symbol(name.to_string(), range(0), url.clone());
+            model.new_method_symbol("Run".to_string(), range(5), url.clone(), class_symbol);
+            model.new_class(Class::new(name.to_string()));
+            model.new_local_semantic(LocalSemanticModel::new());
+        }
+
+        model.remove_class(
+            ClassId(0, 0),
+            ClassGlobalSymbolId(0, 0),
+            LocalSemanticModelId(0, 0),
+        );
+        assert!(!model.url_symbols.contains_key(&url_a));
+        assert!(model.class_defs.get(ClassGlobalSymbolId(0, 0)).is_none());
+        assert!(model.classes.get(ClassId(0, 0)).is_none());
+        assert!(model.private.get(LocalSemanticModelId(0, 0)).is_none());
+        // B keeps its ids and symbols
+        assert_eq!(model.classes[ClassId(1, 0)].name, "B");
+        assert_eq!(model.url_symbols[&url_b].len(), 2);
+        assert!(model.method_defs.contains_key(&ClassGlobalSymbolId(1, 0)));
+
+        // re-adding B's symbols, as an update does, leaves the old method id stale
+        let class_b = ClassGlobalSymbolId(1, 0);
+        let run = GlobalSymbolId::Method(class_b, MethodGlobalSymbolId(0, 0));
+        model.remove_document_symbols(class_b);
+        let new_run = model
+            .new_method_symbol("Run".to_string(), range(7), url_b.clone(), class_b)
+            .unwrap();
+        assert_eq!(new_run, MethodGlobalSymbolId(0, 1));
+        assert!(model.get_global_symbol_range(run).is_none());
+    }
+
+    #[test]
+    fn test_arena_reuses_freed_slots_without_reviving_removed_ids() {
+        let mut classes: Arena<ClassId, Class> = Arena::new();
+        let a = classes.insert(Class::new("A".to_string()));
+        let b = classes.insert(Class::new("B".to_string()));
+        assert_eq!(
+            classes.remove(a).map(|class| class.name),
+            Some("A".to_string())
+        );
+        assert!(classes.remove(a).is_none());
+
+        // C takes A's slot, but A's id doesn't resolve to it
+        let c = classes.insert(Class::new("C".to_string()));
+        assert_eq!(c, ClassId(a.0, a.1 + 1));
+        assert!(classes.get(a).is_none());
+        assert_eq!(classes[c].name, "C");
+        assert_eq!(classes[b].name, "B");
+        assert_eq!(classes.ids(), vec![c, b]);
+        assert_eq!(classes.len(), 2);
+    }
+
+    #[test]
+    fn test_lexical_folding_ranges_for_doc_comments_and_conditionals() {
+        let content = "/// Runs the job.\n\
+            /// Returns a status.\n\
+            ClassMethod Run() As %Status\n\
+            {\n\
+            #ifdef DEBUG\n\
+            \x20   #if $$$Verbose\n\
+            \x20   write \"x\"\n\
+            \x20   #endif\n\
+            \x20   write \"y\"\n\
+            #EndIf\n\
+            \x20   /// single comment line\n\
+            \x20   quit $$$OK\n\
+            }";
+        let folds: Vec<(u32, u32, Option<FoldingRangeKind>)> = lexical_folding_ranges(content)
+            .into_iter()
+            .map(|fold| (fold.start_line, fold.end_line, fold.kind))
+            .collect();
+        assert_eq!(
+            folds,
+            [
+                (0, 1, Some(FoldingRangeKind::Comment)),
+                (4, 8, Some(FoldingRangeKind::Region)),
+                (5, 6, Some(FoldingRangeKind::Region)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closing_a_document_reverts_it_to_its_file() {
+        let url = Url::parse("isfs://server/user/MyApp.Macros.inc").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(url.clone(), "#define Saved 1\n".to_string());
+
+        let state = ProjectState::new();
+        state.handle_document_opened(
+            url.clone(),
+            "#define Saved 1\n#define Unsaved 2\n".to_string(),
+            FileType::Inc,
+            4,
+        );
+        let names = |state: &ProjectState| -> Vec<String> {
+            let data = state.data.read();
+            data.include_macros
+                .values()
+                .flat_map(|file| file.macros.iter())
+                .map(|definition| definition.name.clone())
+                .collect()
+        };
+        assert_eq!(names(&state), ["Saved", "Unsaved"]);
+
+        // the unsaved edits are discarded with the editor buffer
+        state.handle_document_closed(&sources, &url);
+        assert_eq!(names(&state), ["Saved"]);
+    }
+
+    #[test]
+    fn test_include_chain_follows_inherited_and_nested_includes() {
+        let state = ProjectState::new();
+        let url = |name: &str| Url::parse(&format!("isfs://server/user/{name}")).unwrap();
+        for (name, text) in [
+            ("MyApp.Base.inc", "#include MyApp.Common\n#define Base 1\n"),
+            ("MyApp.Common.inc", "#define Common 1\n#define Base 2\n"),
+            ("MyApp.Extra.inc", "#define Extra 1\n"),
+            ("MyApp.Gen.inc", "#define Gen 1\n"),
+        ] {
+            state.handle_document_opened(url(name), text.to_string(), FileType::Inc, 1);
+        }
+        state.handle_document_opened(
+            url("MyApp.Parent.cls"),
+            "Include MyApp.Base\n\nClass MyApp.Parent\n{\n}\n".to_string(),
+            FileType::Cls,
+            1,
+        );
+        state.handle_document_opened(
+            url("MyApp.Child.cls"),
+            "Include (MyApp.Extra, MyApp.Missing)\nIncludeGenerator MyApp.Gen\n\n\
+             Class MyApp.Child Extends MyApp.Parent\n{\n}\n"
+                .to_string(),
+            FileType::Cls,
+            1,
+        );
+
+        let data = state.data.read();
+        let class_id = data.classes["MyApp.Child"];
+        let class = data.global_semantic_model.get_class(class_id).unwrap();
+        assert_eq!(class.includes, ["MyApp.Extra", "MyApp.Missing"]);
+        assert_eq!(class.include_generators, ["MyApp.Gen"]);
+
+        let chain: Vec<&str> = data
+            .get_include_chain("MyApp.Child")
+            .into_iter()
+            .map(|(path_name, _)| path_name.as_str())
+            .collect();
+        assert_eq!(
+            chain,
+            ["user.MyApp.Extra", "user.MyApp.Base", "user.MyApp.Common"]
+        );
+
+        // the first file of the chain defining a macro wins
+        let macros: Vec<(&str, String)> = data
+            .get_visible_macros(&url("MyApp.Child.cls"))
+            .into_iter()
+            .map(|(definition, include)| (definition.name.as_str(), include))
+            .collect();
+        assert_eq!(
+            macros,
+            [
+                ("Extra", "MyApp.Extra".to_string()),
+                ("Base", "MyApp.Base".to_string()),
+                ("Common", "MyApp.Common".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_routine_labels_outline_and_definitions() {
+        let content = "ROUTINE MyApp.Jobs [Type=MAC]\n\
+                       Start ; entry point\n    do Run(1), Log^MyApp.Jobs(\"x\")\n    quit\n\
+                       Run(count, verbose=0) public {\n    set ^Data(count) = 1\n}\n\
+                       Log(msg)\n    write msg\n";
+        let labels = routine_labels(content);
+        let names: Vec<String> = labels.iter().map(|label| label.signature()).collect();
+        assert_eq!(names, ["Start", "Run(count,verbose)", "Log(msg)"]);
+        // a label runs up to the line before the next one
+        assert_eq!(
+            &content[labels[1].declaration_range.start_byte..labels[1].declaration_range.end_byte],
+            "Run(count, verbose=0) public {\n    set ^Data(count) = 1\n}"
+        );
+
+        let offset = content.find("Log^").unwrap() + 5;
+        let reference = label_reference_at(content, offset).unwrap();
+        assert_eq!(reference.label.as_deref(), Some("Log"));
+        assert_eq!(reference.routine.as_deref(), Some("MyApp.Jobs"));
+
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Jobs.mac").unwrap();
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Mac, 1);
+        let data = state.data.read();
+        let symbols = data.get_document_symbols(&url).unwrap();
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols[1].kind, SymbolKind::FUNCTION);
+        assert_eq!(symbols[1].detail.as_deref(), Some("Run(count,verbose)"));
+
+        let position = |needle: &str, skip: usize| {
+            let offset = content.find(needle).unwrap() + skip;
+            let line = content[..offset].matches('\n').count() as u32;
+            let character = (offset - content[..offset].rfind('\n').unwrap() - 1) as u32;
+            Position::new(line, character)
+        };
+        let definition = |position| data.get_label_definition(&url, position);
+        // a local call and a call through the routine name
+        assert_eq!(
+            definition(position("Run(1)", 1)).unwrap().range.start,
+            Position::new(4, 0)
+        );
+        assert_eq!(
+            definition(position("Log^", 8)).unwrap().range.start,
+            Position::new(7, 0)
+        );
+        // `^Data` is a global, not a routine
+        assert!(definition(position("^Data", 2)).is_none());
+    }
+
+    #[test]
+    fn test_macro_definitions_resolve_from_routines() {
+        let content = "ROUTINE MyApp.Jobs\n#include MyApp.Macros\n#define Local 1\n\
+                       Start\n    set x = $$$Local + $$$MaxRows, y = $$Run^MyApp.Jobs()\n";
+        let offset = content.find("$$$MaxRows").unwrap();
+        let (name, range) = macro_name_at(content, offset + 5).unwrap();
+        assert_eq!(name, "MaxRows");
+        assert_eq!(&content[range], "$$$MaxRows");
+        assert_eq!(
+            macro_name_at(content, offset),
+            Some(("MaxRows".to_string(), offset..offset + 10))
+        );
+        // an extrinsic function call isn't a macro
+        assert!(macro_name_at(content, content.find("$$Run").unwrap() + 3).is_none());
+
+        let state = ProjectState::new();
+        let include_url = Url::parse("file:///ws/MyApp.Macros.inc").unwrap();
+        let routine_url = Url::parse("file:///ws/MyApp.Jobs.mac").unwrap();
+        state.handle_document_opened(
+            include_url.clone(),
+            "\n#define MaxRows(%n) 100 ; rows\n".to_string(),
+            FileType::Inc,
+            1,
+        );
+        state.handle_document_opened(routine_url.clone(), content.to_string(), FileType::Mac, 1);
+        let data = state.data.read();
+        let definition = |needle: &str| {
+            let offset = content.find(needle).unwrap() + 4;
+            let line = content[..offset].matches('\n').count() as u32;
+            let character = (offset - content[..offset].rfind('\n').unwrap() - 1) as u32;
+            data.get_macro_definition(&routine_url, Position::new(line, character))
+                .unwrap()
+        };
+        let local = definition("$$$Local");
+        assert_eq!(local.uri, routine_url);
+        assert_eq!(local.range.start, Position::new(2, 0));
+        let included = definition("$$$MaxRows");
+        assert_eq!(included.uri, include_url);
+        assert_eq!(included.range.start, Position::new(1, 0));
+
+        let file = &data.include_macros["ws.MyApp.Macros"];
+        assert_eq!(file.macros[0].directive(), "#define MaxRows(%n) 100 ; rows");
+    }
+
+    #[test]
+    fn test_label_calls_resolve_across_routines() {
+        let jobs = "ROUTINE MyApp.Jobs\n\
+                    Start\n    do Run(1), Log^MyApp.Jobs(\"x\")\n    goto:done Run\n\
+                    Run(count) public {\n    set x = $$Total(count) + $$$Macro, ^Run = 1\n}\n\
+                    Total(n) quit n * 2\n";
+        let calls: Vec<(String, String)> = local_label_calls(jobs)
+            .into_iter()
+            .map(|(name, range)| (name, jobs[range.start_byte..range.end_byte].to_string()))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                ("Run".to_string(), "Run".to_string()),
+                ("Run".to_string(), "Run".to_string()),
+                ("Total".to_string(), "Total".to_string()),
+            ]
+        );
+        let report =
+            "ROUTINE MyApp.Report\nMain\n    set t = $$Total^MyApp.Jobs(5)\n    do ^MyApp.Jobs\n";
+        let routine_calls: Vec<(Option<String>, String, &str)> = routine_label_calls(report)
+            .into_iter()
+            .map(|(label, routine, range)| {
+                (label, routine, &report[range.start_byte..range.end_byte])
+            })
+            .collect();
+        assert_eq!(
+            routine_calls,
+            [
+                (
+                    Some("Total".to_string()),
+                    "MyApp.Jobs".to_string(),
+                    "Total^MyApp.Jobs"
+                ),
+                (None, "MyApp.Jobs".to_string(), "^MyApp.Jobs"),
+            ]
+        );
+
+        let state = ProjectState::new();
+        let jobs_url = Url::parse("file:///ws/MyApp.Jobs.mac").unwrap();
+        let report_url = Url::parse("file:///ws/MyApp.Report.mac").unwrap();
+        state.handle_document_opened(jobs_url.clone(), jobs.to_string(), FileType::Mac, 1);
+        state.handle_document_opened(report_url.clone(), report.to_string(), FileType::Mac, 1);
+        let data = state.data.read();
+        let on_total = Position::new(2, 14);
+        let (_, hover) = data.get_label_hover(&report_url, on_total).unwrap();
+        assert_eq!(
+            hover,
+            "```objectscript\nTotal(n)\n```\n\nLabel of routine `ws.MyApp.Jobs`"
+        );
+        assert_eq!(
+            data.get_label_definition(&report_url, on_total),
+            Some(Location {
+                uri: jobs_url.clone(),
+                range: LspRange::new(Position::new(7, 0), Position::new(7, 5)),
+            })
+        );
+        let references =
+            data.get_label_references(&report_url, on_total, true, &CancellationToken::new());
+        let mut found: Vec<(&Url, u32)> = references
+            .iter()
+            .map(|location| (&location.uri, location.range.start.line))
+            .collect();
+        found.sort();
+        assert_eq!(found, [(&jobs_url, 5), (&jobs_url, 7), (&report_url, 2)]);
+    }
+
+    #[test]
+    fn test_embedded_sql_references_map_to_classes_and_properties() {
+        let content =
+            "    &sql(DECLARE C1 CURSOR FOR SELECT p.Name, COUNT(*) AS total, Home_City\n\
+                       FROM Sample.Person AS p, Sample.Addresses a WHERE p.DOB > :since)\n\
+                       &sql(UPDATE People SET Name = 'x' -- not:a column\n WHERE %ID = :id)\n";
+        let references = find_sql_references(content, 0..content.len());
+        let found: Vec<(SqlReferenceKind, &str, Option<&str>, usize)> = references
+            .iter()
+            .map(|reference| {
+                (
+                    reference.kind,
+                    reference.name.as_str(),
+                    reference.qualifier.as_deref(),
+                    reference.block,
+                )
+            })
+            .collect();
+        use SqlReferenceKind::{Column, Table};
+        assert_eq!(
+            found,
+            [
+                (Column, "Name", Some("p"), 0),
+                (Column, "Home_City", None, 0),
+                (Table, "Sample.Person", Some("p"), 0),
+                (Table, "Sample.Addresses", Some("a"), 0),
+                (Column, "DOB", Some("p"), 0),
+                (Table, "People", None, 1),
+                (Column, "Name", None, 1),
+                (Column, "%ID", None, 1),
+            ]
+        );
+        let person = &references[2];
+        assert_eq!(
+            &content[person.range.start_byte..person.range.end_byte],
+            "Sample.Person"
+        );
+        // the host variables of both blocks are still found
+        let host_variables: Vec<String> = find_host_variables(content, 0..content.len())
+            .into_iter()
+            .map(|host_variable| host_variable.name)
+            .collect();
+        assert_eq!(host_variables, ["since", "id"]);
+
+        let state = ProjectState::new();
+        let url = |name: &str| Url::parse(&format!("file:///ws/{name}")).unwrap();
+        state.handle_document_opened(
+            url("Sample.Person.cls"),
+            "Class Sample.Person Extends %Persistent\n{\n\nProperty Name As %String;\n\n\
+             Property DOB As %Date [ SqlFieldName = DateOfBirth ];\n}\n"
+                .to_string(),
+            FileType::Cls,
+            1,
+        );
+        state.handle_document_opened(
+            url("User.Customer.cls"),
+            "Class User.Customer Extends %Persistent [ SqlTableName = People ]\n{\n\n\
+             Property Name As %String;\n}\n"
+                .to_string(),
+            FileType::Cls,
+            1,
+        );
+        let data = state.data.read();
+        assert_eq!(
+            data.get_sql_table_class("sample.person").as_deref(),
+            Some("Sample.Person")
+        );
+        assert_eq!(
+            data.get_sql_table_class("SQLUser.People").as_deref(),
+            Some("User.Customer")
+        );
+        assert_eq!(data.get_sql_table_class("Other.Person"), None);
+        let (class_name, property) = data
+            .resolve_sql_reference(&references, &references[0])
+            .unwrap();
+        assert_eq!(class_name, "Sample.Person");
+        assert_eq!(property.unwrap().0.name, "Name");
+        // columns resolve through SqlFieldName, not the property name
+        let dob = SqlReference {
+            name: "DateOfBirth".to_string(),
+            ..references[4].clone()
+        };
+        let (_, property) = data.resolve_sql_reference(&references, &dob).unwrap();
+        assert_eq!(property.unwrap().0.name, "DOB");
+        assert_eq!(
+            data.resolve_sql_reference(&references, &references[4]),
+            None
+        );
+        let (class_name, _) = data
+            .resolve_sql_reference(&references, &references[6])
+            .unwrap();
+        assert_eq!(class_name, "User.Customer");
+    }
+
+    #[test]
+    fn test_python_method_bodies_skip_objectscript_analysis() {
+        let body = "\n    import os, json as js\n    from math import (floor, ceil)\n    \
+                    limit: int = 10\n    def total(rows):\n        inner = 1\n        \
+                    return sum(rows)\n    if limit == 10:\n        pass\n";
+        let identifiers = python_identifiers(body, 0..body.len());
+        let identifiers: Vec<(&str, SymbolKind, &str)> = identifiers
+            .iter()
+            .map(|identifier| {
+                let range = identifier.range.start_byte..identifier.range.end_byte;
+                (identifier.name.as_str(), identifier.kind, &body[range])
+            })
+            .collect();
+        assert_eq!(
+            identifiers,
+            [
+                ("os", SymbolKind::MODULE, "os"),
+                ("js", SymbolKind::MODULE, "js"),
+                ("floor", SymbolKind::MODULE, "floor"),
+                ("ceil", SymbolKind::MODULE, "ceil"),
+                ("limit", SymbolKind::VARIABLE, "limit"),
+                ("total", SymbolKind::FUNCTION, "total"),
+            ]
+        );
+
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Py.cls").unwrap();
+        let content = "Class MyApp.Py\n{\n\nMethod Sum(rows) [ Language = python ]\n{\n    \
+                       import json\n    def total(rows):\n        return sum(rows)\n    \
+                       set = total(rows)\n    return json.dumps(set)\n}\n\n\
+                       Method Run()\n{\n    set x = 1\n    do ..Sum(x)\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        let bodies = data.get_python_bodies(&url);
+        assert_eq!(bodies.len(), 1);
+        assert_eq!(bodies[0].method_name, "Sum");
+        let names: Vec<&str> = bodies[0]
+            .identifiers
+            .iter()
+            .map(|identifier| identifier.name.as_str())
+            .collect();
+        assert_eq!(names, ["json", "total", "set"]);
+
+        // the Python body adds no variables or calls, and nothing is reported inside it
+        let class_id = data.classes["MyApp.Py"];
+        let class = data.global_semantic_model.get_class(class_id).unwrap();
+        assert!(class
+            .method_calls
+            .iter()
+            .all(|call| call.caller_method != "Sum"));
+        let python_lines = 5..10;
+        assert!(data
+            .get_document_diagnostics(&url)
+            .iter()
+            .all(|diagnostic| !python_lines.contains(&diagnostic.range.start.line)));
+
+        // the outline lists the top-level names under the method
+        let symbols = data.get_document_symbols(&url).unwrap();
+        let methods = symbols[0].children.as_ref().unwrap();
+        let children: Vec<&str> = methods[0]
+            .children
+            .iter()
+            .flatten()
+            .map(|symbol| symbol.name.as_str())
+            .collect();
+        assert_eq!(children, ["json", "total", "set"]);
+
+        let (_, hover) = data.get_python_hover(&url, Point::new(8, 11)).unwrap();
+        assert_eq!(
+            hover,
+            "```python\ndef total(rows):\n```\n\nDefined in Python method `Sum`"
+        );
+    }
+
+    #[test]
+    fn test_atelier_classes_are_indexed_read_only() {
+        let config: AtelierConfig =
+            serde_json::from_value(serde_json::json!({"host": "iris", "namespace": "APP"}))
+                .unwrap();
+        assert_eq!(config.port, 52773);
+        assert_eq!(config.authorization(), None);
+        let mut with_credentials = config.clone();
+        with_credentials.username = Some("_SYSTEM".to_string());
+        with_credentials.password = Some("SYS".to_string());
+        assert_eq!(
+            with_credentials.authorization().as_deref(),
+            Some("Basic X1NZU1RFTTpTWVM=")
+        );
+        let url = config.document_url("%Library.Persistent").unwrap();
+        assert!(is_read_only_url(&url));
+        assert_eq!(url.query(), Some("ns=APP"));
+        assert_eq!(
+            class_name_from_url(&url).as_deref(),
+            Some("%Library.Persistent")
+        );
+        assert_eq!(qualified_class_name("%Persistent"), "%Library.Persistent");
+        assert_eq!(qualified_class_name("%SYS.Task"), "%SYS.Task");
+
+        let response = serde_json::json!({
+            "status": {"errors": []},
+            "result": {"name": "%Library.Persistent.cls", "content": ["Class A", "{", "}"]}
+        });
+        assert_eq!(document_content(&response).unwrap(), "Class A\n{\n}");
+        let missing = serde_json::json!({
+            "status": {"errors": [{"error": "ERROR #5001: Class does not exist"}]},
+            "result": {}
+        });
+        assert_eq!(
+            document_content(&missing).unwrap_err(),
+            "ERROR #5001: Class does not exist"
+        );
+
+        let state = ProjectState::new();
+        let local = Url::parse("file:///ws/MyApp.Order.cls").unwrap();
+        let content = "Class MyApp.Order Extends %Persistent\n{\n}\n";
+        state.handle_document_opened(local.clone(), content.to_string(), FileType::Cls, 1);
+        let mut remote = MemorySourceProvider::new();
+        remote.insert(
+            url.clone(),
+            "Class %Library.Persistent Extends %Library.SwizzleObject\n{\n}\n".to_string(),
+        );
+        state.fetch_remote_classes(&remote, &config);
+
+        let data = state.data.read();
+        assert!(data.documents.contains_key(&url));
+        assert!(data.is_read_only_class("%Library.Persistent"));
+        assert!(!data.is_read_only_class("MyApp.Order"));
+        // the server doesn't have the superclass of the downloaded class, so it stays missing
+        assert_eq!(data.get_missing_class_names(), ["%Library.SwizzleObject"]);
+        let order = data.classes["MyApp.Order"];
+        let persistent = data.classes["%Library.Persistent"];
+        let order = data.global_semantic_model.get_class(order).unwrap();
+        assert_eq!(order.inherited_classes, [persistent]);
+        // read-only classes aren't renamed
+        let rename = data.get_package_rename("%Library", "Lib");
+        assert!(rename.file_renames.is_empty());
+        drop(data);
+
+        // the workspace sources don't list downloaded classes, so a re-index carries them over
+        let root = Url::parse("file:///ws/").unwrap();
+        state.reindex_workspace_scope(&MemorySourceProvider::new(), &root);
+        let data = state.data.read();
+        assert!(data.documents.contains_key(&url));
+        let order = data.classes["MyApp.Order"];
+        let persistent = data.classes["%Library.Persistent"];
+        let order = data.global_semantic_model.get_class(order).unwrap();
+        assert_eq!(order.inherited_classes, [persistent]);
+    }
+
+    #[test]
+    fn test_class_reference_hover_documents_system_classes() {
+        assert_eq!(
+            documatic_markdown(
+                "Saves the <b>object</b>; see <method>%Save</method> &amp; \
+                 <var>related</var>.<p>For example:\n<example>\nset sc = obj.%Save()\n\
+                 </example>\n<ul><li>one</li><li>two</li></ul>If a < b"
+            ),
+            "Saves the **object**; see `%Save` & `related`.\n\nFor example:\n\n\
+             ```objectscript\nset sc = obj.%Save()\n```\n\n- one\n- two\n\nIf a < b"
+        );
+        assert_eq!(documatic_markdown("Plain\n\n  text"), "Plain\n\n  text");
+
+        let content = "    set p = ##class(%Persistent).%OpenId(1)\n    set f = %Library.File\n";
+        let method = system_class_reference_at(content, 33).unwrap();
+        assert_eq!(method.class_name, "%Persistent");
+        assert_eq!(method.method.as_deref(), Some("%OpenId"));
+        assert_eq!(&content[method.range], "%OpenId");
+        let class = system_class_reference_at(content, 22).unwrap();
+        assert_eq!(
+            (class.class_name.as_str(), class.method),
+            ("%Persistent", None)
+        );
+        let class = system_class_reference_at(content, 62).unwrap();
+        assert_eq!(&content[class.range], "%Library.File");
+        assert_eq!(system_class_reference_at(content, 9), None);
+
+        let source = "Class %Library.File\n{\n\n/// Returns <b>1</b> if it exists.\n\
+                      ClassMethod Exists(filename As %String) As %Boolean [ Final ]\n{\n}\n}\n";
+        assert_eq!(
+            method_declaration(source, "Exists"),
+            Some((
+                "ClassMethod Exists(filename As %String) As %Boolean".to_string(),
+                Some("Returns <b>1</b> if it exists.".to_string())
+            ))
+        );
+        assert!(reference_class("%Library.Persistent").is_some());
+
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Run.cls").unwrap();
+        let content = "Class MyApp.Run\n{\n\nClassMethod Go()\n{\n    \
+                       set p = ##class(%Persistent).%New()\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        // %New is inherited from %RegisteredObject through %SwizzleObject
+        let (_, hover) = data
+            .get_class_reference_hover(&url, Point::new(5, 34))
+            .unwrap();
+        assert!(hover.starts_with(
+            "```objectscript\nClassMethod %New(initvalue As %RawString) As %ObjectHandle\n```\n\n\
+             Declared in `%Library.RegisteredObject`\n\n---\n\nCreates a new instance"
+        ));
+        let (_, hover) = data
+            .get_class_reference_hover(&url, Point::new(5, 22))
+            .unwrap();
+        assert!(hover.starts_with(
+            "**Class** `%Library.Persistent`\n\nPackage `%Library`\n\n\
+             Extends `%Library.SwizzleObject`"
+        ));
+    }
+
+    #[test]
+    fn test_method_arguments_are_scoped_variables_with_modes() {
+        assert_eq!(
+            argument_mode("ByRef count As %Integer = 0"),
+            ArgumentMode::ByRef
+        );
+        assert_eq!(argument_mode("output sc As %Status"), ArgumentMode::Output);
+        assert_eq!(argument_mode("items As %List"), ArgumentMode::ByValue);
+
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Totals.cls").unwrap();
+        let content = "Class MyApp.Totals\n{\n\nClassMethod Total(items As %List, \
+                       ByRef count As %Integer = 0, Output sc As %Status) As %Integer\n{\n    \
+                       set sc = 1\n    set count = count + $listlength(items)\n    \
+                       quit count\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        let (range, hover) = data.get_argument_hover(&url, Point::new(6, 18)).unwrap();
+        assert_eq!(range.start_point, Point::new(6, 16));
+        assert_eq!(
+            hover,
+            "```objectscript\nByRef count As %Integer = 0\n```\n\n\
+             Argument 2 of `Total`, passed by reference"
+        );
+        let (_, hover) = data.get_argument_hover(&url, Point::new(5, 8)).unwrap();
+        assert!(hover.ends_with("Argument 3 of `Total`, returning a value to the caller"));
+        // the argument declaration is the first definition of the variable
+        let (definitions, _) = data
+            .get_private_variable_occurrences(&url, Point::new(7, 10))
+            .unwrap();
+        assert_eq!(definitions[0].start_point, Point::new(3, 40));
+    }
+
+    #[test]
+    fn test_oref_types_follow_expression_chains() {
+        assert_eq!(
+            expression_chain("##class(Sample.Person).%OpenId(id).Home"),
+            Some((
+                ChainHead::Class("Sample.Person"),
+                vec![
+                    ("%OpenId", ChainMember::Method),
+                    ("Home", ChainMember::Property)
+                ]
+            ))
+        );
+        assert_eq!(
+            expression_chain("..Find(\"a)\").Spouse()"),
+            Some((
+                ChainHead::CurrentClass,
+                vec![
+                    ("Find", ChainMember::Method),
+                    ("Spouse", ChainMember::Method)
+                ]
+            ))
+        );
+        assert_eq!(
+            expression_chain("$this"),
+            Some((ChainHead::CurrentClass, vec![]))
+        );
+        assert_eq!(expression_chain("p + 1"), None);
+        assert_eq!(expression_chain("##class(Sample.Person)"), None);
+
+        let state = ProjectState::new();
+        let classes = [
+            (
+                "Sample.Address",
+                "Class Sample.Address\n{\n\nProperty City As %String;\n}\n",
+            ),
+            (
+                "Sample.Person",
+                "Class Sample.Person\n{\n\nProperty Home As Address;\n\n\
+                 Method Spouse() As Sample.Person\n{\n    quit \"\"\n}\n}\n",
+            ),
+            (
+                "Sample.App",
+                "Class Sample.App\n{\n\nClassMethod Run()\n{\n    \
+                 set p = ##class(Sample.Person).%New()\n    set home = p.Home\n    \
+                 set other = p.Spouse().Home\n    set q = p\n    \
+                 set p = p.Spouse()\n    quit\n}\n}\n",
+            ),
+        ];
+        for (class_name, content) in classes {
+            let url = Url::parse(&format!("file:///ws/{class_name}.cls")).unwrap();
+            state.handle_document_opened(url, content.to_string(), FileType::Cls, 1);
+        }
+        let data = state.data.read();
+        let url = Url::parse("file:///ws/Sample.App.cls").unwrap();
+        let oref_class = |variable: &str| data.get_oref_class(&url, Point::new(10, 4), variable);
+        assert_eq!(oref_class("p").as_deref(), Some("Sample.Person"));
+        // `Home` is declared `As Address`, in the package of `Sample.Person`
+        assert_eq!(oref_class("home").as_deref(), Some("Sample.Address"));
+        assert_eq!(oref_class("other").as_deref(), Some("Sample.Address"));
+        assert_eq!(oref_class("q").as_deref(), Some("Sample.Person"));
+    }
+
+    #[test]
+    fn test_strict_mode_reports_literal_type_mismatches() {
+        assert_eq!(literal_var_type("\"a\"\"b\""), Some(VarType::String));
+        assert_eq!(literal_var_type("\"a\" _ \"b\""), None);
+        assert_eq!(literal_var_type("-1.5E3"), Some(VarType::Number));
+        assert_eq!(literal_var_type("[1, 2]"), Some(VarType::JsonArrayLiteral));
+        assert_eq!(literal_var_type("x"), None);
+        assert_eq!(
+            declared_type("ByRef count As %Integer(MINVAL=0) = 1"),
+            Some("%Integer")
+        );
+        assert_eq!(declared_type("count"), None);
+
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Totals.cls").unwrap();
+        let content = "Class MyApp.Totals\n{\n\nClassMethod Total(count As %Integer, \
+                       ratio As %Numeric, label As %String) As %Integer\n{\n    \
+                       #dim items As %DynamicArray\n    set items = {}\n    \
+                       set count = \"12\"\n    do ..Total(\"ten\", 1.5, 3)\n    \
+                       quit 2.5\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        let messages: Vec<String> = data
+            .get_type_mismatches(&url)
+            .iter()
+            .map(|mismatch| mismatch.message())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Assigning %DynamicObject where %DynamicArray expected by variable \"items\"",
+                "Passing %String where %Integer expected by argument \"count\" of \"Total\"",
+                "Returning %Numeric where %Integer expected by method \"Total\"",
+            ]
+        );
+        assert!(data
+            .get_document_diagnostics(&url)
+            .iter()
+            .any(|diagnostic| diagnostic.code == rule_code(TYPE_MISMATCH_RULE)));
+    }
+
+    #[test]
+    fn test_arity_mismatches_count_skipped_and_extra_arguments() {
+        let content = "    do ..Total(a,,c)\n    do ..Total(1, $get(x, 2), 3, 4, 5)\n    \
+                       do ..Total(x,,,\"l,m\")\n";
+        let call_site = |call: &str, arguments: &[&str]| {
+            let start = content.find(call).unwrap();
+            let mut from = start + "..Total(".len();
+            let arg_ranges = arguments
+                .iter()
+                .map(|argument| {
+                    let argument_start = from + content[from..].find(argument).unwrap();
+                    from = argument_start + argument.len();
+                    byte_range(content, argument_start, from)
+                })
+                .collect();
+            MethodCallSite {
+                caller_method: "Run".to_string(),
+                callee_class: "MyApp.Totals".to_string(),
+                callee_method: "Total".to_string(),
+                callee_symbol: None,
+                call_range: byte_range(content, start, start + call.len()),
+                arg_ranges,
+            }
+        };
+        let call_sites = [
+            call_site("..Total(a,,c)", &["a", "c"]),
+            call_site(
+                "..Total(1, $get(x, 2), 3, 4, 5)",
+                &["1", "$get(x, 2)", "3", "4", "5"],
+            ),
+            call_site("..Total(x,,,\"l,m\")", &["x", "\"l,m\""]),
+        ];
+        let declarations = vec![
+            "items As %List".to_string(),
+            "rate As %Numeric = 1".to_string(),
+            "Output sc As %Status".to_string(),
+            "label".to_string(),
+        ];
+        let mismatches =
+            find_arity_mismatches(content, &call_sites, |_| Some(declarations.clone()));
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].passed, 3);
+        assert_eq!(mismatches[0].missing, ["label"]);
+        assert_eq!(mismatches[1].passed, 5);
+        assert!(mismatches[1].missing.is_empty());
+        let messages: Vec<String> = arity_diagnostics(content, &mismatches)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "MyApp.Totals.Total is called without its required argument \"label\"",
+                "MyApp.Totals.Total takes at most 4 arguments; 5 passed",
+            ]
+        );
+        // methods taking a variable number of arguments take any number
+        let variadic = vec!["items As %List".to_string(), "args...".to_string()];
+        assert!(find_arity_mismatches(content, &call_sites, |_| Some(variadic.clone())).is_empty());
+    }
+
+    #[test]
+    fn test_variables_set_but_never_read_are_dimmed() {
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Unused.cls").unwrap();
+        let content = "Class MyApp.Unused\n{\n\nClassMethod Run(ByRef out) As %Integer\n{\n    \
+                       set total = 0, count = 5\n    set unused = ..Sum(count), unused = 2\n    \
+                       set out = 1, list(1) = 2\n    set id = 3\n    \
+                       &sql(SELECT Name INTO :name FROM Sample.Person WHERE ID = :id)\n    \
+                       quit total\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        let diagnostics = data.get_document_diagnostics(&url);
+        let unused: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.code == rule_code(UNUSED_VARIABLE_CODE))
+            .collect();
+        let starts: Vec<Position> = unused.iter().map(|d| d.range.start).collect();
+        assert_eq!(starts, [Position::new(6, 8), Position::new(6, 31)]);
+        assert_eq!(
+            unused[0].message,
+            "Variable \"unused\" is set but never used"
+        );
+        assert_eq!(unused[0].tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+        // the overwritten value isn't also reported as a dead store
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.code != rule_code(DEAD_STORE_CODE)));
+        // fixing all removes the constant assignment, but not the call, which may have effects
+        let edits = data.get_fix_all_edits(&url);
+        let removed: Vec<(Position, Position, &str)> = edits
+            .iter()
+            .map(|edit| (edit.range.start, edit.range.end, edit.new_text.as_str()))
+            .collect();
+        assert_eq!(removed, [(Position::new(6, 29), Position::new(6, 41), "")]);
+    }
+
+    #[test]
+    fn test_dead_stores_stop_at_postconditionals_and_blocks() {
+        let content = "Class MyApp.Stores\n{\n\nMethod Run(x)\n{\n    \
+                       set a = 1\n    set a = 2\n    kill a\n    \
+                       set b = 1\n    set:x b = 2\n    set b = 3\n    \
+                       if x { set c = 1 }\n    set b = 4\n    \
+                       set d = 1, d = d + 1\n    write b, d\n}\n}\n";
+        let tree = parse_cls(content);
+        // (name, row of the dead set, row discarding it, whether it is killed)
+        let stores: Vec<(String, usize, usize, bool)> = find_dead_stores(content, &tree, true)
+            .into_iter()
+            .map(|store| {
+                let (row, discarded_at) = (store.range.start_point.row, store.discarded_at);
+                (store.name, row, discarded_at.start_point.row, store.killed)
+            })
+            .collect();
+        assert_eq!(
+            stores,
+            vec![
+                ("a".to_string(), 5, 6, false),
+                ("a".to_string(), 6, 7, true),
+            ]
+        );
+        // non-procedure-block methods can read any variable indirectly
+        assert!(find_dead_stores(content, &tree, false).is_empty());
+    }
+
+    #[test]
+    fn test_unreachable_code_after_unconditional_exits() {
+        let body = "\n    if done {\n        quit:x 1\n        set y = 2\n        return y\n\
+                    \n        // never\n        set z = 3\n        if z { set w = 1 }\n    \
+                    } else {\n        throw ex\n    }\n    q sc\n    write \"a;{\"\n\
+                    Label\n    set a = 1\n";
+        let unreachable: Vec<(&str, &str)> = unreachable_code_in(body, 0..body.len())
+            .iter()
+            .map(|code| {
+                (
+                    code.after,
+                    &body[code.range.start_byte..code.range.end_byte],
+                )
+            })
+            .collect();
+        assert_eq!(
+            unreachable,
+            [
+                ("return", "set z = 3\n        if z { set w = 1 }"),
+                ("quit", "write \"a;{\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_override_stub_calls_super() {
+        let mut method = Method::new(
+            "Total".to_string(),
+            None,
+            Some(Language::Python),
+            CodeMode::Expression,
+            false,
+            None,
+            Vec::new(),
+            MethodType::ClassMethod,
+        );
+        method.argument_declarations = vec![
+            "items As %List".to_string(),
+            "ByRef count As %Integer = 0".to_string(),
+            "Output total".to_string(),
+        ];
+        method.return_type_name = Some("%Status".to_string());
+        assert_eq!(
+            method.override_stub(),
+            "ClassMethod Total(items As %List, ByRef count As %Integer = 0, Output total) \
+             As %Status [ Private ]\n{\n    Quit ##super(items, .count, .total)\n}"
+        );
+
+        let method = Method::new(
+            "Greet".to_string(),
+            None,
+            Some(Language::Objectscript),
+            CodeMode::Code,
+            true,
+            None,
+            Vec::new(),
+            MethodType::InstanceMethod,
+        );
+        assert_eq!(
+            method.override_stub(),
+            "Method Greet()\n{\n    Do ##super()\n}"
+        );
+    }
+
+    #[test]
+    fn test_import_edit_adds_the_package_after_the_header_statements() {
+        let content = "Include Macros\nImport MyApp.Util\n\n/// Orders.\nClass MyApp.Orders Extends Base\n{\n}\n";
+        let tree = parse_cls(content);
+        let imports: Vec<String> = import_names(content, &tree)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(imports, vec!["MyApp.Util".to_string()]);
+        let edit = import_edit(content, &tree, "MyApp.Models");
+        assert_eq!(edit.range.start, Position::new(2, 0));
+        assert_eq!(edit.range.end, Position::new(2, 0));
+        assert_eq!(edit.new_text, "Import MyApp.Models\n");
+
+        let content = "Class MyApp.Orders Extends Base\n{\n}\n";
+        let edit = import_edit(content, &parse_cls(content), "MyApp.Models");
+        assert_eq!(edit.range.start, Position::new(0, 0));
+        assert_eq!(edit.new_text, "Import MyApp.Models\n\n");
+    }
+
+    #[test]
+    fn test_fix_all_imports_only_unambiguous_class_names() {
+        let state = ProjectState::new();
+        state.data.write().config.lint_unresolved_references = true;
+        for name in ["MyApp.Models.Base", "MyApp.Models.Item", "MyApp.Util.Item"] {
+            let url = Url::parse(&format!("file:///ws/{name}.cls")).unwrap();
+            let content = format!("Class {name}\n{{\n}}\n");
+            state.handle_document_opened(url, content, FileType::Cls, 1);
+        }
+        let url = Url::parse("file:///ws/MyApp.Orders.cls").unwrap();
+        let content = "Class MyApp.Orders Extends (Base, Item)\n{\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let edits = state.data.read().get_fix_all_edits(&url);
+        let texts: Vec<&str> = edits.iter().map(|edit| edit.new_text.as_str()).collect();
+        assert_eq!(texts, ["Import MyApp.Models\n\n"]);
+    }
+
+    #[test]
+    fn test_new_class_file_follows_the_referencing_class_layout() {
+        let root = Url::parse("file:///work/").unwrap();
+        let url = Url::parse("file:///work/src/MyApp/Orders.cls").unwrap();
+        let new_url = new_class_url(&url, "MyApp.Orders", "MyApp.Sub.NewThing", Some(&root));
+        assert_eq!(
+            new_url.unwrap().as_str(),
+            "file:///work/src/MyApp/Sub/NewThing.cls"
+        );
+
+        let url = Url::parse("file:///work/src/MyApp.Orders.cls").unwrap();
+        let new_url = new_class_url(&url, "MyApp.Orders", "MyApp.NewThing", Some(&root));
+        assert_eq!(
+            new_url.unwrap().as_str(),
+            "file:///work/src/MyApp.NewThing.cls"
+        );
+
+        // a file not named after its class falls back to the package folders under the root
+        let url = Url::parse("file:///work/misc/orders.cls").unwrap();
+        let new_url = new_class_url(&url, "MyApp.Orders", "MyApp.NewThing", Some(&root));
+        assert_eq!(new_url.unwrap().as_str(), "file:///work/MyApp/NewThing.cls");
+        assert_eq!(
+            new_class_url(&url, "MyApp.Orders", "MyApp.NewThing", None),
+            None
+        );
+
+        assert_eq!(
+            class_skeleton("MyApp.NewThing"),
+            "Class MyApp.NewThing Extends %RegisteredObject\n{\n\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_closest_names_suggest_misspelled_methods() {
+        assert_eq!(edit_distance("Save", "%Save"), 1);
+        assert_eq!(edit_distance("GetTotal", "gettotal"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+
+        let methods = ["%Save", "%Delete", "%OpenId", "Save", "SaveAll", "Total"];
+        assert_eq!(closest_names("%Sav", methods), vec!["%Save"]);
+        assert_eq!(closest_names("SaveAl", methods), vec!["SaveAll", "Save"]);
+        assert_eq!(closest_names("%OpenID", methods), vec!["%OpenId"]);
+        assert!(closest_names("Refresh", methods).is_empty());
+    }
+
+    #[test]
+    fn test_abbreviated_commands_expand_in_nested_blocks_keeping_their_case() {
+        let content = "Class MyApp.Orders\n{\n\nMethod Total()\n{\n    I x {\n        \
+                       s y = 1\n        D:y ..Run()\n    }\n    Set z = 2\n    Q\n}\n}\n";
+        let tree = parse_cls(content);
+        // (start, replaced length, new text) of each edit
+        let edits: Vec<(Position, u32, String)> = command_abbreviation_edits(content, &tree, None)
+            .into_iter()
+            .map(|edit| {
+                let length = edit.range.end.character - edit.range.start.character;
+                (edit.range.start, length, edit.new_text)
+            })
+            .collect();
+        assert_eq!(
+            edits,
+            vec![
+                (Position::new(5, 4), 1, "IF".to_string()),
+                (Position::new(6, 8), 1, "set".to_string()),
+                // the postconditional is left alone
+                (Position::new(7, 8), 1, "DO".to_string()),
+                (Position::new(10, 4), 1, "QUIT".to_string()),
+            ]
+        );
+        // the command keyword of a nested block is on its own row
+        let nested: Vec<String> = command_abbreviation_edits(content, &tree, Some(6..=6))
+            .into_iter()
+            .map(|edit| edit.new_text)
+            .collect();
+        assert_eq!(nested, ["set"]);
+    }
+
+    #[test]
+    fn test_range_formatting_only_expands_commands_on_the_selected_rows() {
+        let content =
+            "Class MyApp.Orders\n{\n\nMethod Total()\n{\n    s x = 1\n    W x\n    q\n}\n}\n";
+        let tree = parse_cls(content);
+        let edits: Vec<(u32, String)> = command_abbreviation_edits(content, &tree, Some(5..=6))
+            .into_iter()
+            .map(|edit| (edit.range.start.line, edit.new_text))
+            .collect();
+        assert_eq!(
+            edits,
+            vec![(5, "set".to_string()), (6, "WRITE".to_string())]
+        );
+        assert_eq!(command_abbreviation_edits(content, &tree, None).len(), 3);
+    }
+
+    #[test]
+    fn test_on_type_indentation_follows_block_levels() {
+        let content = "Class MyApp.Orders\n{\n\nMethod Total()\n{\n  if x {\n set y = 1\nDone  quit\n      }\n\n}\n}\n";
+        let tree = parse_cls(content);
+        // (row, replaced whitespace length, new indentation length) of each edit
+        let edits = |row, closed_block| -> Vec<(u32, u32, usize)> {
+            block_indentation_edits(content, &tree, None, row, closed_block, "    ")
+                .into_iter()
+                .map(|edit| {
+                    assert_eq!(edit.range.start.character, 0);
+                    (
+                        edit.range.start.line,
+                        edit.range.end.character,
+                        edit.new_text.len(),
+                    )
+                })
+                .collect()
+        };
+        // closing the `if` block re-indents it, but leaves the label in the first column
+        assert_eq!(edits(8, true), vec![(6, 1, 8), (8, 6, 4)]);
+        // a new line after the block is at the level of the method body
+        assert_eq!(edits(9, false), vec![(9, 0, 4)]);
+        // closing the method body re-indents all of it
+        assert_eq!(edits(10, true), vec![(5, 2, 4), (6, 1, 8), (8, 6, 4)]);
+        // lines outside method bodies are left alone
+        assert!(edits(2, false).is_empty());
+    }
+
+    #[test]
+    fn test_variable_occurrences_separate_writes_from_reads() {
+        let content = "Class MyApp.Orders\n{\n\nMethod Total(count As %Integer)\n{\n    new total\n    set total = 0, items(count) = 1\n    write total + count, !\n    quit total\n}\n}\n";
+        let tree = parse_cls(content);
+        let occurrences = |row, column| -> Vec<(usize, usize, bool)> {
+            variable_occurrences(content, &tree, Point::new(row, column))
+                .into_iter()
+                .map(|occurrence| {
+                    let start = occurrence.range.start_point;
+                    (start.row, start.column, occurrence.is_write)
+                })
+                .collect()
+        };
+        assert_eq!(
+            occurrences(7, 11),
+            vec![(5, 8, true), (6, 8, true), (7, 10, false), (8, 9, false)]
+        );
+        assert_eq!(
+            occurrences(3, 14),
+            vec![(3, 13, true), (6, 25, false), (7, 18, false)]
+        );
+        // not a variable
+        assert!(occurrences(7, 5).is_empty());
+    }
+
+    #[test]
+    fn test_linked_label_ranges_cover_the_label_and_its_local_calls() {
+        let jobs = "ROUTINE MyApp.Jobs\nMain\n    do Run\n    goto Run:done\n    set Run = 1\n    do Run^MyApp.Other\nRun\n    quit\n";
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Jobs.mac").unwrap();
+        state.handle_document_opened(url.clone(), jobs.to_string(), FileType::Mac, 1);
+        let data = state.data.read();
+        let lines = |ranges: Vec<LspRange>| -> Vec<(u32, u32)> {
+            ranges
+                .iter()
+                .map(|range| (range.start.line, range.start.character))
+                .collect()
+        };
+        let expected = [(2, 7), (3, 9), (6, 0)];
+        let on_declaration = data.get_linked_label_ranges(&url, Position::new(6, 1));
+        assert_eq!(on_declaration.map(lines), Some(expected.to_vec()));
+        let on_call = data.get_linked_label_ranges(&url, Position::new(3, 10));
+        assert_eq!(on_call.map(lines), Some(expected.to_vec()));
+        // a variable spelled like the label, and a call of another routine's label
+        let on_variable = data.get_linked_label_ranges(&url, Position::new(4, 9));
+        assert_eq!(on_variable, None);
+        let on_other_routine = data.get_linked_label_ranges(&url, Position::new(5, 8));
+        assert_eq!(on_other_routine, None);
+    }
+
+    #[test]
+    fn test_index_dump_lists_classes_and_overrides() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            root.join("Test/Base.cls").unwrap(),
+            "Class Test.Base\n{\n\nProperty Name As %String;\n\nMethod Describe() As %String\n{\n    quit \"\"\n}\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Sub.cls").unwrap(),
+            "Class Test.Sub Extends Test.Base\n{\n\nMethod Describe() As %String\n{\n    quit \"sub\"\n}\n}\n".to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let dump = state.data.read().get_index_dump_json();
+
+        let sub_id = dump["classTable"]["Test.Sub"].as_u64().unwrap() as usize;
+        let sub = &dump["classes"][sub_id];
+        assert_eq!(sub["name"], "Test.Sub");
+        assert_eq!(sub["superclasses"], serde_json::json!(["Test.Base"]));
+        assert_eq!(sub["publicMethods"][0]["name"], "Describe");
+        assert_eq!(sub["publicMethods"][0]["returnType"], "%String");
+        assert_eq!(
+            dump["overrides"],
+            serde_json::json!([{ "method": "Test.Sub.Describe", "overrides": "Test.Base.Describe" }])
+        );
+        assert_eq!(
+            dump["effectiveMethods"]["Test.Sub"]["Describe"],
+            "Test.Sub.Describe"
+        );
+    }
+
+    #[test]
+    fn test_index_progress_counts_files_then_inheritance_build() {
+        let progress = IndexProgress::new();
+        assert_eq!(progress.report(), ("0/0 files".to_string(), 0));
+        progress.start(1890);
+        for _ in 0..412 {
+            progress.file_indexed();
+        }
+        assert_eq!(progress.report(), ("412/1890 files".to_string(), 19));
+        progress.start_inheritance_build();
+        assert_eq!(
+            progress.report(),
+            ("Building class inheritance (1890 files)".to_string(), 90)
+        );
+        // a new indexing starts over
+        progress.start(10);
+        assert_eq!(progress.report(), ("0/10 files".to_string(), 0));
+    }
+
+    #[tokio::test]
+    async fn test_dropping_a_cancellable_request_cancels_its_token() {
+        let done = run_cancellable(|cancel| cancel.is_cancelled()).await;
+        assert_eq!(done, Some(false));
+
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (stopped_tx, stopped_rx) = std::sync::mpsc::channel();
+        let request = tokio::spawn(run_cancellable(move |cancel| {
+            started_tx.send(()).unwrap();
+            while !cancel.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            stopped_tx.send(()).unwrap();
+        }));
+        while started_rx.try_recv().is_err() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // what tower-lsp does with the future of a request on `$/cancelRequest`
+        request.abort();
+        assert!(request.await.unwrap_err().is_cancelled());
+        assert!(stopped_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        let token = CancellationToken::new();
+        drop(token.cancel_on_drop());
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn test_interned_names_share_one_allocation_and_look_up_by_str() {
+        let names = Interner::new();
+        let first = names.intern("%OnNew");
+        let second = names.clone().intern("%OnNew");
+        assert!(std::ptr::eq(first.as_str(), second.as_str()));
+        assert_ne!(first, names.intern("%onnew"));
+
+        let methods = std::collections::HashMap::from([(first, 1)]);
+        assert_eq!(methods.get("%OnNew"), Some(&1));
+
+        // only the names nothing holds any more are released
+        assert_eq!(names.purge(), 1);
+        let kept = names.intern("%OnNew");
+        assert!(std::ptr::eq(kept.as_str(), second.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_debouncer_only_runs_the_last_task_for_a_key() {
+        let debouncer = Debouncer::new();
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let record = |key: &'static str, value: i32| {
+            let runs = Arc::clone(&runs);
+            async move { runs.lock().push((key, value)) }
+        };
+        let delay = Duration::from_millis(20);
+        for value in 0..3 {
+            debouncer.schedule("a", delay, record("a", value));
+        }
+        debouncer.schedule("b", delay, record("b", 0));
+        debouncer.schedule("c", delay, record("c", 0));
+        debouncer.cancel(&"c");
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut runs = runs.lock().clone();
+        runs.sort();
+        assert_eq!(runs, vec![("a", 2), ("b", 0)]);
+    }
+
+    #[test]
+    fn test_hover_is_advertised_whatever_the_config() {
+        let config = Config {
+            heuristic_oref_methods: false,
+            method_metrics: false,
+            element_type_hover: false,
+            system_api_stubs: false,
+            ..Config::default()
+        };
+        let capabilities = server_capabilities(&[config], None);
+        assert!(capabilities.hover_provider.is_some());
+    }
+
+    #[test]
+    fn test_capabilities_follow_the_client_options_and_any_workspace_config() {
+        let client_options = Config {
+            sync_kind: SyncKind::Full,
+            ..Config::default()
+        };
+        let sync = |capabilities: &ServerCapabilities| match &capabilities.text_document_sync {
+            Some(TextDocumentSyncCapability::Options(options)) => options.change,
+            _ => None,
+        };
+
+        // nothing enables a lens or abbreviation expansion by default
+        let capabilities = server_capabilities(&[client_options.clone(), Config::default()], None);
+        assert_eq!(sync(&capabilities), Some(TextDocumentSyncKind::FULL));
+        assert!(capabilities.code_lens_provider.is_none());
+        assert!(capabilities.document_formatting_provider.is_none());
+        assert!(capabilities.code_action_provider.is_some());
+
+        // one workspace enabling a feature is enough, and the sync kind stays the client's
+        let workspace = Config {
+            class_stats_lens: true,
+            expand_command_abbreviations: true,
+            ..Config::default()
+        };
+        let capabilities =
+            server_capabilities(&[client_options, Config::default(), workspace], None);
+        assert_eq!(sync(&capabilities), Some(TextDocumentSyncKind::FULL));
+        assert!(capabilities.code_lens_provider.is_some());
+        assert_eq!(
+            capabilities.document_formatting_provider,
+            Some(OneOf::Left(true))
+        );
+        assert!(capabilities.document_range_formatting_provider.is_some());
+        assert!(capabilities.document_on_type_formatting_provider.is_some());
+    }
+
+    #[test]
+    fn test_dynamically_registered_features_are_left_out_of_initialize() {
+        let client = TextDocumentClientCapabilities {
+            code_lens: Some(CodeLensClientCapabilities {
+                dynamic_registration: Some(true),
+            }),
+            formatting: Some(DocumentFormattingClientCapabilities {
+                dynamic_registration: Some(true),
+            }),
+            hover: Some(HoverClientCapabilities {
+                dynamic_registration: Some(false),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = Config {
+            class_stats_lens: true,
+            expand_command_abbreviations: true,
+            ..Config::default()
+        };
+        let capabilities = server_capabilities(std::slice::from_ref(&config), Some(&client));
+        // registered after `initialized` instead, even though the config enables them
+        assert!(capabilities.code_lens_provider.is_none());
+        assert!(capabilities.document_formatting_provider.is_none());
+        // features the client can't register dynamically are still advertised
+        assert!(capabilities.hover_provider.is_some());
+        assert!(capabilities.code_action_provider.is_some());
+        assert!(capabilities.document_range_formatting_provider.is_some());
+
+        let dynamic: Vec<&str> = DYNAMIC_FEATURES
+            .iter()
+            .filter(|feature| feature.is_dynamic(Some(&client)))
+            .map(|feature| feature.method)
+            .collect();
+        assert_eq!(
+            dynamic,
+            [CODE_LENS_FEATURE.method, FORMATTING_FEATURE.method]
+        );
+        assert!(!HOVER_FEATURE.is_dynamic(Some(&client)));
+        assert!(!CODE_ACTION_FEATURE.is_dynamic(None));
+        // a registered lens is unregistered once no workspace enables it any more
+        assert!(CODE_LENS_FEATURE.enabled_in(&[Config::default(), config]));
+        assert!(!CODE_LENS_FEATURE.enabled_in(&[Config::default()]));
+    }
+
+    #[test]
+    fn test_renames_and_duplicate_members_follow_the_case_rules() {
+        let classes = ["A.Foo".to_string(), "A.Bar".to_string()];
+        assert!(check_rename(IdentifierKind::Class, "A.Foo", "A.foo", &classes).is_ok());
+        assert_eq!(
+            check_rename(IdentifierKind::Class, "A.Bar", "A.foo", &classes).unwrap_err(),
+            "\"A.foo\" conflicts with existing name \"A.Foo\""
+        );
+        assert!(check_rename(IdentifierKind::Variable, "x", "x", &classes[..0]).is_err());
+        assert!(names_match(IdentifierKind::Command, "set", "SET"));
+        assert!(!names_match(IdentifierKind::Method, "Save", "save"));
+
+        let content = "Method Save() {}\nMethod save() {}\nIndex I;\nIndex i;\n";
+        let url = Url::parse("file:///workspace/Sample/Dup.cls").unwrap();
+        let member = |name: &str, kind, start| {
+            let range = byte_range(content, start, start + name.len());
+            ClassMember {
+                name: name.to_string(),
+                kind,
+                range,
+                declaration_range: range,
+                visibility: MemberVisibility::default(),
+            }
+        };
+        let members = [
+            member("Save", MemberKind::Method, 7),
+            member("save", MemberKind::Method, 24),
+            member("I", MemberKind::Index, 40),
+            member("i", MemberKind::Index, 49),
+        ];
+        let diagnostics = duplicate_member_diagnostics(content, &url, &members);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Method \"save\" is already declared in this class"
+        );
+    }
+
+    #[test]
+    fn test_symbol_index_follows_document_updates() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let url = root.join("Test/A.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            url.clone(),
+            "Class Test.A\n{\n\nClassMethod Run()\n{\n    quit\n}\n}\n".to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        // on `Run` in `ClassMethod Run()`
+        assert!(matches!(
+            state.data.read().get_symbol_at(&url, Point::new(3, 13)),
+            Some(SymbolRef::Global(_))
+        ));
+
+        let content = "Class Test.A\n{\n\n\n\nClassMethod Run()\n{\n    quit\n}\n}\n";
+        state.update_document(
+            url.clone(),
+            parse_cls(content),
+            FileType::Cls,
+            2,
+            content.into(),
+        );
+        let data = state.data.read();
+        assert!(matches!(
+            data.get_symbol_at(&url, Point::new(5, 13)),
+            Some(SymbolRef::Global(_))
+        ));
+        assert!(data.get_symbol_at(&url, Point::new(3, 0)).is_none());
+    }
+
+    #[test]
+    fn test_renaming_a_superclass_rebuilds_the_classes_extending_its_old_name() {
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let base_url = root.join("Test/Base.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            base_url.clone(),
+            "Class Test.Base [ Not ProcedureBlock ]\n{\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Sub.cls").unwrap(),
+            "Class Test.Sub Extends Test.Base\n{\n}\n".to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let (base_id, sub_id) = {
+            let data = state.data.read();
+            (data.classes["Test.Base"], data.classes["Test.Sub"])
+        };
+
+        state.handle_document_opened(
+            base_url,
+            "Class Test.Renamed [ Not ProcedureBlock ]\n{\n}\n".to_string(),
+            FileType::Cls,
+            2,
+        );
+        let data = state.data.read();
+        assert_eq!(data.dependent_classes(base_id), [base_id]);
+        let sub = &data.global_semantic_model.classes[sub_id];
+        assert!(sub.inherited_classes.is_empty());
+        assert_eq!(sub.is_procedure_block, None);
+    }
+
+    #[test]
+    fn test_generated_classes_match_patterns_or_generated_by() {
+        assert!(glob_matches("*.Gen.cls", "Person.gen.CLS"));
+        assert!(glob_matches("Demo.WS*", "Demo.WSClient.Service"));
+        assert!(glob_matches("Demo.?", "Demo.A"));
+        assert!(!glob_matches("Demo.?", "Demo.AB"));
+        assert!(!glob_matches("*.Gen.cls", "Person.cls"));
+        assert!(glob_matches("*", ""));
+
+        let config = Config {
+            generated_class_patterns: vec!["*.Gen.cls".to_string(), "Demo.WS.*".to_string()],
+            ..Config::default()
+        };
+        let generated = |class_name: &str, file_name: &str, generated_by: bool| {
+            let url = Url::parse(&format!("file:///ws/{file_name}")).unwrap();
+            is_generated_class(&config, class_name, &url, generated_by)
+        };
+        assert!(generated("Demo.Person", "Person.Gen.cls", false));
+        assert!(generated("Demo.WS.Client", "Client.cls", false));
+        assert!(generated("Demo.Person", "Person.cls", true));
+        assert!(!generated("Demo.Person", "Person.cls", false));
+    }
+
+    #[test]
+    fn test_snapshot_diff_reports_added_removed_and_changed_members() {
+        let index = |content: &str| {
+            let state = ProjectState::new();
+            let url = Url::parse("file:///ws/MyApp.Orders.cls").unwrap();
+            state.handle_document_opened(url, content.to_string(), FileType::Cls, 1);
+            let gsm = state.data.read().global_semantic_model.clone();
+            gsm
+        };
+        let before = index(
+            "Class MyApp.Orders\n{\n\nProperty Name;\n\n\
+             Method Total() As %Integer\n{\n    quit 1\n}\n}\n",
+        );
+        let after = index(
+            "Class MyApp.Orders\n{\n\nMethod Total() As %String\n{\n    quit 1\n}\n\n\
+             Method Cancel()\n{\n}\n}\n",
+        );
+        let mut snapshots = IndexSnapshots::new();
+        assert_eq!(snapshots.take(&before, "before".to_string(), 2), Some(1));
+        assert_eq!(snapshots.take(&after, "after".to_string(), 2), Some(2));
+        assert_eq!(snapshots.latest_pair(), Some((1, 2)));
+
+        let diff = snapshots.diff(1, 2).unwrap();
+        assert_eq!(diff.added, ["Method MyApp.Orders.Cancel"]);
+        assert_eq!(diff.removed, ["Property MyApp.Orders.Name"]);
+        let changed: Vec<&str> = diff
+            .changed
+            .iter()
+            .map(|(name, ..)| name.as_str())
+            .collect();
+        assert_eq!(changed, ["Method MyApp.Orders.Total"]);
+        assert!(snapshots.diff(2, 2).unwrap().changed.is_empty());
+
+        // the oldest snapshot is dropped past the limit, and can no longer be diffed
+        assert_eq!(snapshots.take(&after, "again".to_string(), 2), Some(3));
+        assert!(snapshots.diff(1, 3).is_none());
+        assert_eq!(snapshots.take(&after, "disabled".to_string(), 0), None);
+    }
+
+    #[test]
+    fn test_semantic_diff_reports_api_changes_rather_than_lines() {
+        let old_text = "Class MyApp.Orders Extends MyApp.Base\n{\n\nParameter LIMIT = 10;\n\n\
+                        Property Total;\n\nMethod Run(a) As %Status\n{\n    quit 1\n}\n\n\
+                        Method Gone()\n{\n}\n}\n";
+        let new_text = "/// Orders.\nClass MyApp.Orders Extends (MyApp.Base, MyApp.Audit) \
+                        [ ProcedureBlock = 0 ]\n{\n\nProperty Total;\n\nProperty Count;\n\n\
+                        Method Run(a, b) As %Status\n{\n    // reworded body\n    quit 2\n}\n\n\
+                        ClassMethod Added()\n{\n}\n}\n";
+        let diff = semantic_diff(old_text, new_text).unwrap();
+        assert_eq!(diff["className"]["after"], "MyApp.Orders");
+        assert_eq!(
+            diff["superclasses"]["after"],
+            serde_json::json!(["MyApp.Base", "MyApp.Audit"])
+        );
+        assert_eq!(diff["superclasses"]["changed"], true);
+        assert_eq!(diff["keywords"][0]["name"], "ProcedureBlock");
+        assert_eq!(diff["methods"]["added"], serde_json::json!(["Added"]));
+        assert_eq!(diff["methods"]["removed"], serde_json::json!(["Gone"]));
+        // only the signature of `Run` counts, not its body
+        let changed = diff["methods"]["changed"].as_array().unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0]["name"], "Run");
+        assert_eq!(diff["properties"]["added"], serde_json::json!(["Count"]));
+        assert_eq!(diff["parameters"]["removed"], serde_json::json!(["LIMIT"]));
+
+        let unchanged = semantic_diff(old_text, old_text).unwrap();
+        assert_eq!(unchanged["superclasses"]["changed"], false);
+        assert_eq!(unchanged["methods"]["changed"], serde_json::json!([]));
+        // the request is rejected when a text doesn't define a class
+        assert!(semantic_diff(old_text, "ROUTINE Orders\n").is_none());
+    }
+
+    #[test]
+    fn test_sql_surface_lists_sql_procedures_by_their_sql_names() {
+        assert_eq!(default_schema("MyApp.Data.Person"), "MyApp_Data");
+        assert_eq!(default_schema("User.Person"), "SQLUser");
+
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/User.Person.cls").unwrap();
+        let content = "Class User.Person\n{\n\nClassMethod Lookup(id) [ SqlProc ]\n{\n}\n\n\
+                       ClassMethod Named() [ SqlProc, SqlName = FindPerson ]\n{\n}\n\n\
+                       ClassMethod Hidden() [ SqlProc = 0 ]\n{\n}\n\n\
+                       Method Instance() [ SqlProc ]\n{\n}\n\n\
+                       Query All() As %SQLQuery [ SqlProc ]\n{\nSELECT ID FROM User.Person\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        // (SQL name, kind, row of the member name)
+        let mut surface: Vec<(String, &str, usize)> = data
+            .get_sql_projections(None)
+            .into_iter()
+            .map(|(uri, projection)| {
+                assert_eq!(uri, url);
+                let row = projection.range.start_point.row;
+                (projection.qualified_name(), projection.kind.label(), row)
+            })
+            .collect();
+        surface.sort();
+        assert_eq!(
+            surface,
+            vec![
+                ("SQLUser.FindPerson".to_string(), "procedure", 7),
+                ("SQLUser.Person_All".to_string(), "query", 19),
+                ("SQLUser.Person_Lookup".to_string(), "procedure", 3),
+            ]
+        );
+        // SQL identifiers are matched ignoring case and quotes, qualified or not
+        for query in ["person_lookup", "\"SQLUser\".\"Person_Lookup\""] {
+            let found: Vec<String> = data
+                .get_sql_projections(Some(query))
+                .into_iter()
+                .map(|(_, projection)| projection.member_name)
+                .collect();
+            assert_eq!(found, ["Lookup"]);
+        }
+        assert!(data.get_sql_projections(Some("Person_Hidden")).is_empty());
+    }
+
+    #[test]
+    fn test_override_chain_starts_from_the_base_method() {
+        let root = Url::parse("file:///ws/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        for (name, extends) in [
+            ("Base", ""),
+            ("Mid", " Extends Test.Base"),
+            ("Leaf", " Extends Test.Mid"),
+            ("Other", " Extends Test.Base"),
+        ] {
+            sources.insert(
+                root.join(&format!("Test/{name}.cls")).unwrap(),
+                format!("Class Test.{name}{extends}\n{{\n\nMethod Run()\n{{\n}}\n}}\n"),
+            );
+        }
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        let chain = |name: &str| -> Vec<String> {
+            let url = root.join(&format!("Test/{name}.cls")).unwrap();
+            data.get_override_chain(&url, "Run")
+                .into_iter()
+                .map(|(url, range)| {
+                    assert_eq!(range.start_point.row, 3);
+                    url.path().to_string()
+                })
+                .collect()
+        };
+        // the base method first, then its overrides breadth first
+        let mut from_leaf = chain("Leaf");
+        assert_eq!(from_leaf[0], "/ws/Test/Base.cls");
+        assert_eq!(from_leaf[3], "/ws/Test/Leaf.cls");
+        from_leaf[1..3].sort();
+        assert_eq!(from_leaf[1..3], ["/ws/Test/Mid.cls", "/ws/Test/Other.cls"]);
+        // every method of the chain reports the same chain
+        assert_eq!(chain("Base").len(), 4);
+        assert_eq!(chain("Other")[0], "/ws/Test/Base.cls");
+
+        let url = root.join("Test/Base.cls").unwrap();
+        assert!(data.get_override_chain(&url, "Missing").is_empty());
+    }
+
+    #[test]
+    fn test_untyped_oref_methods_match_public_methods_by_name() {
+        let root = Url::parse("file:///ws/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        for (name, method) in [
+            ("Order", "Method Save()"),
+            ("Invoice", "ClassMethod Save()"),
+            ("Audit", "Method Save() [ Private ]"),
+            ("Report", "Method Print()"),
+        ] {
+            sources.insert(
+                root.join(&format!("App/{name}.cls")).unwrap(),
+                format!("Class App.{name}\n{{\n\n{method}\n{{\n}}\n}}\n"),
+            );
+        }
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        state.data.write().config.heuristic_oref_methods = false;
+        assert!(state
+            .data
+            .read()
+            .get_heuristic_method_candidates("Save")
+            .is_empty());
+
+        state.data.write().config.heuristic_oref_methods = true;
+        let data = state.data.read();
+        let candidates: Vec<(String, String)> = data
+            .get_heuristic_method_candidates("Save")
+            .into_iter()
+            .map(|(class_name, url, range)| {
+                assert_eq!(range.start_point.row, 3);
+                (class_name, url.path().to_string())
+            })
+            .collect();
+        // private methods can't be called on another object
+        assert_eq!(
+            candidates,
+            vec![
+                ("App.Invoice".to_string(), "/ws/App/Invoice.cls".to_string()),
+                ("App.Order".to_string(), "/ws/App/Order.cls".to_string()),
+            ]
+        );
+        assert!(data.get_heuristic_method_candidates("Missing").is_empty());
+    }
+
+    #[test]
+    fn test_method_metrics_count_decisions_and_nesting() {
+        let content = "Class App.Metrics\n{\n\nMethod Simple()\n{\n    quit 1\n}\n\n\
+                       Method Busy(x)\n{\n    if x {\n        for i=1:1:3 {\n            \
+                       quit:i=2\n        }\n    }\n    set y = $select(x:1, 1:2)\n    \
+                       while y {\n        set y = 0\n    }\n}\n}\n";
+        let tree = parse_cls(content);
+        let metrics = method_metrics(content, &tree);
+        let summary: Vec<(&str, usize, usize)> = metrics
+            .iter()
+            .map(|m| (m.name.as_str(), m.cyclomatic, m.nesting_depth))
+            .collect();
+        // `if`, `for`, the postconditional, `$select` and `while` are decision points
+        assert_eq!(summary, [("Simple", 1, 0), ("Busy", 6, 2)]);
+        assert_eq!(
+            metrics[1].title(),
+            "Cyclomatic complexity 6 · nesting depth 2"
+        );
+
+        let messages = |max_complexity, max_nesting| -> Vec<String> {
+            complexity_diagnostics(content, &metrics, max_complexity, max_nesting)
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect()
+        };
+        assert_eq!(
+            messages(5, 1),
+            ["Method \"Busy\": cyclomatic complexity 6 exceeds 5, nesting depth 2 exceeds 1"]
+        );
+        assert_eq!(
+            messages(0, 1),
+            ["Method \"Busy\": nesting depth 2 exceeds 1"]
+        );
+        // a threshold of 0 disables its warning
+        assert!(messages(0, 0).is_empty());
+        assert!(messages(6, 2).is_empty());
+    }
+
+    #[test]
+    fn test_override_stub_of_a_word_after_a_non_ascii_character() {
+        let root = Url::parse("file:///ws/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            root.join("Test/Base.cls").unwrap(),
+            "Class Test.Base\n{\n\nMethod Run()\n{\n}\n\nMethod Stop()\n{\n}\n}\n".to_string(),
+        );
+        let url = root.join("Test/Sub.cls").unwrap();
+        sources.insert(
+            url.clone(),
+            "Class Test.Sub Extends Test.Base\n{\n\n/// éRun\nMethod Other()\n{\n}\n}\n"
+                .to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        // the cursor is inside `Run`, right after the `é`
+        let stubs = state
+            .data
+            .read()
+            .get_override_stubs(&url, Position::new(3, 6));
+        let names: Vec<&str> = stubs
+            .iter()
+            .map(|(method, ..)| method.name.as_str())
+            .collect();
+        assert_eq!(names, ["Run"]);
+    }
+
+    #[test]
+    fn test_variable_renames_conflict_with_the_other_variables_of_their_scope() {
+        let state = ProjectState::new();
+        let url = Url::parse("file:///ws/MyApp.Vars.cls").unwrap();
+        let content = "Class MyApp.Vars\n{\n\nMethod Run(a)\n{\n    set total = 1, count = 2\n    \
+                       write total, count, a\n}\n}\n";
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let data = state.data.read();
+        // from the `total` read on the next line
+        let (name, names) = data
+            .get_private_variable_names(&url, Point::new(6, 11))
+            .unwrap();
+        assert_eq!(name, "total");
+        assert!(names.contains(&"count".to_string()));
+        assert_eq!(
+            check_rename(IdentifierKind::Variable, &name, "count", &names).unwrap_err(),
+            "\"count\" conflicts with existing name \"count\""
+        );
+        // local variable names are case-sensitive
+        assert!(check_rename(IdentifierKind::Variable, &name, "Count", &names).is_ok());
+        assert!(data
+            .get_private_variable_names(&url, Point::new(3, 2))
+            .is_none());
+    }
+
+    #[test]
+    fn test_changed_export_archive_is_watched_and_reindexed() {
+        let root = Url::parse("file:///ws/").unwrap();
+        let patterns: Vec<GlobPattern> = FsSourceProvider
+            .watch(&root)
+            .into_iter()
+            .map(|watcher| watcher.glob_pattern)
+            .collect();
+        assert!(patterns.contains(&GlobPattern::String("**/*.xml".to_string())));
+
+        let export = |class: &str| {
+            format!(
+                "<Export generator=\"IRIS\" version=\"26\">\n<Class name=\"{class}\">\n</Class>\n</Export>\n"
+            )
+        };
+        let archive = root.join("export.xml").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(archive.clone(), export("Demo.Old"));
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        assert!(state.data.read().classes.contains_key("Demo.Old"));
+
+        // a changed archive can't be applied incrementally, so it asks for a re-index
+        sources.insert(archive.clone(), export("Demo.New"));
+        assert!(!state.apply_watched_file_change(&sources, &archive, FileChangeType::CHANGED));
+        state.reindex_workspace_scope(&sources, &root);
+        let data = state.data.read();
+        assert!(data.classes.contains_key("Demo.New"));
+        assert!(!data.classes.contains_key("Demo.Old"));
+    }
+
+    #[test]
+    fn test_word_at_offset_steps_over_whole_characters() {
+        let is_name = |c: char| c.is_ascii_alphanumeric() || c == '%';
+        let content = "set é=%Run(ü)";
+        let run = content.find("%Run").unwrap();
+        assert_eq!(word_at_offset(content, run + 2, is_name), run..run + 4);
+        // the word may end at the offset, or border a non-ASCII character
+        assert_eq!(word_at_offset(content, run + 4, is_name), run..run + 4);
+        assert_eq!(word_at_offset(content, run, is_name), run..run + 4);
+        // an offset inside a multi-byte character moves back to its start
+        let e = content.find('é').unwrap();
+        assert_eq!(word_at_offset(content, e + 1, is_name), e..e);
+        assert_eq!(
+            word_at_offset(content, e + 1, char::is_alphanumeric),
+            e..e + 2
+        );
+        assert_eq!(
+            word_at_offset(content, 100, is_name),
+            content.len()..content.len()
+        );
+        // the scanners built on it return `None` rather than slicing into a character
+        assert!(macro_name_at("set x = \"ü\"", 10).is_none());
+        assert!(label_reference_at("do ü", 4).is_none());
+    }
+
+    #[test]
+    fn test_debug_write_replacement_formats_the_written_expressions() {
+        let template = "$$$LOGINFO({message})";
+        // format controls are dropped; the remaining expressions are concatenated
+        assert_eq!(
+            debug_write_replacement("write !,\"x: \",x,#,?5,/CUP(1,1)", false, template),
+            Some("$$$LOGINFO(\"x: \"_x)".to_string())
+        );
+        assert_eq!(
+            debug_write_replacement("zw x,obj.Name", true, template),
+            Some("$$$LOGINFO(\"x=\"_x_\", \"_\"obj.Name=\"_obj.Name)".to_string())
+        );
+        // postconditionals, argumentless writes and writes of format controls only aren't
+        // converted
+        assert_eq!(debug_write_replacement("w:debug x", false, template), None);
+        assert_eq!(debug_write_replacement("write", false, template), None);
+        assert_eq!(debug_write_replacement("write !,!", false, template), None);
+    }
+
+    #[test]
+    fn test_split_top_level_arguments_skips_nested_commas() {
+        assert_eq!(
+            split_top_level_arguments("a, \"b,c\", $piece(s,\",\",2),,d"),
+            ["a", "\"b,c\"", "$piece(s,\",\",2)", "d"]
+        );
+        assert!(split_top_level_arguments("").is_empty());
+    }
+
+    #[test]
+    fn test_find_debug_writes_and_their_fixes() {
+        let content = "Class Test.Debug\n{\n\nMethod Run(x)\n{\n    write \"x=\",x,!\n    zwrite x\n    w:x>1 x\n    set y = x\n}\n}\n";
+        let tree = parse_cls(content);
+        let writes = find_debug_writes(content, &tree, "do ##class(Log).Info({message})");
+        let found: Vec<(usize, Option<&str>)> = writes
+            .iter()
+            .map(|write| (write.range.start_point.row, write.replacement.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (5, Some("do ##class(Log).Info(\"x=\"_x)")),
+                (6, Some("do ##class(Log).Info(\"x=\"_x)")),
+                (7, None),
+            ]
+        );
+
+        let url = Url::parse("file:///ws/Test/Debug.cls").unwrap();
+        let state = ProjectState::new();
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let line = |row| LspRange::new(Position::new(row, 0), Position::new(row, 1));
+        // the lint is opt-in
+        assert!(state
+            .data
+            .read()
+            .get_debug_write_fixes(&url, line(5))
+            .is_empty());
+        state.data.write().config.lint_debug_writes = true;
+        let fixes = state.data.read().get_debug_write_fixes(&url, line(5));
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].0.start, Position::new(5, 4));
+        assert_eq!(fixes[0].1, "$$$LOGINFO(\"x=\"_x)");
+        // a postconditional write is reported, but has no fix
+        assert!(state
+            .data
+            .read()
+            .get_debug_write_fixes(&url, line(7))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_config_load_workspace_file_overrides_initialization_options() {
+        let root = env::temp_dir().join(format!("objectscript-lsp-config-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join(WORKSPACE_CONFIG_FILE),
+            r#"{"lint_debug_writes": false, "debug_write_replacement": "$$$LOGDEBUG({message})"}"#,
+        )
+        .unwrap();
+        let options = serde_json::json!({"lint_debug_writes": true, "enable_lint": true});
+
+        let config = Config::load(Some(options.clone()), Some(&root));
+        assert!(!config.lint_debug_writes);
+        assert_eq!(config.debug_write_replacement, "$$$LOGDEBUG({message})");
+        // keys the file doesn't set keep the client's value
+        assert!(config.enable_lint);
+
+        // without a workspace file, the client's settings apply
+        std::fs::remove_dir_all(&root).unwrap();
+        let config = Config::load(Some(options), Some(&root));
+        assert!(config.lint_debug_writes);
+        assert_eq!(config.debug_write_replacement, "$$$LOGINFO({message})");
+    }
+
+    #[test]
+    fn test_method_call_hover_resolves_from_the_symbol_index() {
+        let root = Url::parse("file:///ws/").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            root.join("Test/A.cls").unwrap(),
+            "Class Test.A\n{\n\nClassMethod Run(x As %String)\n{\n    quit\n}\n}\n".to_string(),
+        );
+        let url = root.join("Test/B.cls").unwrap();
+        sources.insert(
+            url.clone(),
+            "Class Test.B Extends Test.A\n{\n\nMethod Go()\n{\n    do ##class(Test.A).Run(1)\n    do ..Run(2)\n}\n}\n"
+                .to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        let data = state.data.read();
+
+        for (point, start) in [
+            (Point::new(5, 24), Point::new(5, 23)),
+            (Point::new(6, 10), Point::new(6, 9)),
+        ] {
+            assert!(matches!(
+                data.get_symbol_at(&url, point),
+                Some(SymbolRef::MethodCall(_))
+            ));
+            let (range, hover) = data.get_method_signature_hover(&url, point).unwrap();
+            assert_eq!(
+                (range.start_point, range.end_point.column),
+                (start, start.column + 3)
+            );
+            assert!(hover.contains("Run("));
+            assert!(hover.ends_with("Declared in `Test.A`"));
+        }
+        // the class name of the call is not the method
+        assert!(data
+            .get_method_signature_hover(&url, Point::new(5, 17))
+            .is_none());
+    }
+
+    #[test]
+    fn test_scope_tree_json_lists_nested_scopes_and_ranges() {
+        let url = Url::parse("file:///ws/Test/Scopes.cls").unwrap();
+        let content = "Class Test.Scopes\n{\n\nMethod Run()\n{\n    set x = 1\n    write x\n}\n\nMethod Helper() [ Private ]\n{\n    quit\n}\n}\n";
+        let state = ProjectState::new();
+        state.handle_document_opened(url.clone(), content.to_string(), FileType::Cls, 1);
+        let json = state.data.read().get_scope_tree_json(&url).unwrap();
+        assert_eq!(json["uri"], url.as_str());
+        assert_eq!(json["className"], "Test.Scopes");
+        let point = |row: usize, column: usize| serde_json::json!({ "row": row, "column": column });
+
+        let private_methods = json["privateMethods"].as_array().unwrap();
+        assert_eq!(private_methods.len(), 1);
+        assert_eq!(private_methods[0]["name"], "Helper");
+        assert_eq!(private_methods[0]["definition"]["start"]["row"], 9);
+
+        // the root scope holds the class; each method body is a child scope
+        let scopes = json["scopes"].as_array().unwrap();
+        let root = &scopes[0];
+        assert_eq!(root["id"], json["root"]);
+        assert!(root["parent"].is_null());
+        let run = scopes
+            .iter()
+            .find(|scope| !scope["privateVariables"].as_array().unwrap().is_empty())
+            .unwrap();
+        assert_eq!(run["parent"], root["id"]);
+        assert!(root["children"].as_array().unwrap().contains(&run["id"]));
+        assert!(run["start"]["row"].as_u64().unwrap() <= 5);
+        assert!(run["end"]["row"].as_u64().unwrap() >= 6);
+
+        let x = &run["privateVariables"][0];
+        assert_eq!(x["name"], "x");
+        assert_eq!(x["definition"]["start"], point(5, 8));
+        assert_eq!(x["definition"]["end"], point(5, 9));
+        // only embedded SQL host variables are recorded as references
+        assert_eq!(x["references"], serde_json::json!([]));
+        assert!(run["publicVariables"].as_array().unwrap().is_empty());
+    }
+
+    /// Language server that only answers `initialize`, giving tests a `Client` of their own.
+    struct NullServer;
+
+    #[tower_lsp::async_trait]
+    impl LanguageServer for NullServer {
+        async fn initialize(&self, _: InitializeParams) -> jsonrpc::Result<InitializeResult> {
+            Ok(InitializeResult::default())
+        }
+
+        async fn shutdown(&self) -> jsonrpc::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Returns an initialized `Client` whose requests are answered by `respond` (called with the
+    /// method and params), and a stream of the notifications sent to it, as method and params.
+    async fn test_client(
+        respond: impl Fn(&str, &serde_json::Value) -> jsonrpc::Result<serde_json::Value>
+            + Send
+            + 'static,
+    ) -> (Client, mpsc::UnboundedReceiver<(String, serde_json::Value)>) {
+        let mut client = None;
+        let (mut service, socket) = LspService::new(|c| {
+            client = Some(c);
+            NullServer
+        });
+        let initialize = jsonrpc::Request::build("initialize")
+            .params(serde_json::json!({ "capabilities": {} }))
+            .id(1)
+            .finish();
+        std::future::poll_fn(|cx| service.poll_ready(cx))
+            .await
+            .unwrap();
+        service.call(initialize).await.unwrap();
+
+        let (notify, notifications) = mpsc::unbounded();
+        let (mut requests, mut responses) = socket.split();
+        tokio::spawn(async move {
+            // the socket closes when the service is dropped
+            let _service = service;
+            while let Some(request) = requests.next().await {
+                let params = request.params().cloned().unwrap_or_default();
+                match request.id().cloned() {
+                    Some(id) => {
+                        let response =
+                            jsonrpc::Response::from_parts(id, respond(request.method(), &params));
+                        responses.send(response).await.unwrap();
+                    }
+                    None => {
+                        let _ = notify.unbounded_send((request.method().to_string(), params));
+                    }
+                }
+            }
+        });
+        (client.unwrap(), notifications)
+    }
+
+    /// Returns a `BackendTester` with the `isfs://` workspace `root`, read through `client`.
+    fn client_workspace(root: &Url, client: Client) -> BackendTester {
+        let state = ProjectState::new();
+        state.project_root_uri.set(root.clone()).unwrap();
+        state
+            .source_provider
+            .set(Arc::new(ClientSourceProvider::new(client)))
+            .unwrap();
+        let backend = BackendTester::new();
+        backend.add_project(root.clone(), state);
+        backend
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_client_source_provider_lists_and_reads_through_the_client() {
+        let (client, _) = test_client(|method, params| match (method, params["uri"].as_str()) {
+            ("objectscript/listFiles", Some("isfs://server/user/")) => Ok(serde_json::json!([
+                "isfs://server/user/Demo/Person.cls",
+                "isfs://server/user/notes.txt",
+            ])),
+            ("objectscript/readFile", Some("isfs://server/user/Demo/Person.cls")) => {
+                Ok(serde_json::json!("Class Demo.Person\n{\n}\n"))
+            }
+            _ => Err(jsonrpc::Error::invalid_params("unexpected request")),
+        })
+        .await;
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let backend = client_workspace(&root, client);
+        backend.index_workspace(&root).await;
+
+        let project = backend.get_project(&root).unwrap();
+        let data = project.data.read();
+        assert_eq!(data.documents.len(), 1);
+        assert!(data.classes.contains_key("Demo.Person"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_client_source_provider_falls_back_to_opened_documents() {
+        // a client without the extension requests answers MethodNotFound
+        let (client, mut notifications) =
+            test_client(|_, _| Err(jsonrpc::Error::method_not_found())).await;
+        let root = Url::parse("isfs://server/user/").unwrap();
+        let backend = client_workspace(&root, client);
+        backend.index_workspace(&root).await;
+
+        let (method, params) = notifications.next().await.unwrap();
+        assert_eq!(method, "window/logMessage");
+        assert!(params["message"]
+            .as_str()
+            .unwrap()
+            .contains("doesn't implement objectscript/listFiles"));
+        let project = backend.get_project(&root).unwrap();
+        assert!(project.data.read().documents.is_empty());
+
+        // the documents the client opens are analyzed from their didOpen content
+        let url = root.join("Demo/Person.cls").unwrap();
+        backend.handle_did_open(
+            url.clone(),
+            "Class Demo.Person\n{\n}\n".to_string(),
+            FileType::Cls,
+            1,
+        );
+        let data = project.data.read();
+        assert!(data.documents.contains_key(&url));
+        assert!(data.classes.contains_key("Demo.Person"));
+    }
+
+    #[test]
+    fn test_class_stats_lens_counts_members_lines_and_subclasses() {
+        let root = Url::parse("file:///ws/").unwrap();
+        let base = root.join("Test/Base.cls").unwrap();
+        let sub = root.join("Test/Sub1.cls").unwrap();
+        let mut sources = MemorySourceProvider::new();
+        sources.insert(
+            base.clone(),
+            "Class Test.Base\n{\n\nProperty Name As %String;\n\n\
+             Property Secret As %String [ Private ];\n\nMethod Run()\n{\n    quit\n}\n\n\
+             ClassMethod Build()\n{\n    quit\n}\n\nMethod Helper() [ Private ]\n{\n    quit\n}\n}\n"
+                .to_string(),
+        );
+        sources.insert(
+            sub.clone(),
+            "Class Test.Sub1 Extends Test.Base\n{\n\nMethod Run()\n{\n    quit\n}\n}\n".to_string(),
+        );
+        sources.insert(
+            root.join("Test/Sub2.cls").unwrap(),
+            "Class Test.Sub2 Extends Test.Base\n{\n}\n".to_string(),
+        );
+        let state = ProjectState::new();
+        state.index_workspace_scope(&sources, &root);
+        // the lens is opt-in
+        assert!(state.data.read().get_class_stats_lens(&base).is_none());
+
+        state.data.write().config.class_stats_lens = true;
+        let data = state.data.read();
+        let title = |url: &Url| {
+            let lens = data.get_class_stats_lens(url).unwrap();
+            assert_eq!(lens.range.start, Position::new(0, 0));
+            lens.command.unwrap().title
+        };
+        // public and private members both count
+        assert_eq!(
+            title(&base),
+            "3 methods · 2 properties · 22 lines · 2 subclasses"
+        );
+        assert_eq!(
+            title(&sub),
+            "1 method · 0 properties · 8 lines · 0 subclasses"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reparse_with_errors_is_logged_without_the_tree() {
+        let (client, mut notifications) =
+            test_client(|_, _| Err(jsonrpc::Error::method_not_found())).await;
+        let backend = BackendWrapper::new(client);
+        let root = Url::parse("file:///ws/").unwrap();
+        let url = root.join("Test/Edit.cls").unwrap();
+        let state = ProjectState::new();
+        state.project_root_uri.set(root.clone()).unwrap();
+        state.handle_document_opened(
+            url.clone(),
+            "Class Test.Edit\n{\n\nMethod Run()\n{\n    set x = 1\n}\n}\n".to_string(),
+            FileType::Cls,
+            1,
+        );
+        backend.0.add_project(root, state);
+
+        // leave an unbalanced parenthesis in the method body
+        backend
+            .did_change(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(url.clone(), 2),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: Some(LspRange::new(Position::new(5, 12), Position::new(5, 13))),
+                    range_length: None,
+                    text: "(1".to_string(),
+                }],
+            })
+            .await;
+
+        // the revalidation after the edit logs the errors, then publishes them
+        let mut logged = Vec::new();
+        let published = loop {
+            let (method, params) = notifications.next().await.unwrap();
+            match method.as_str() {
+                "window/logMessage" => logged.push(params["message"].as_str().unwrap().to_string()),
+                "textDocument/publishDiagnostics" => break params,
+                _ => {}
+            }
+        };
+        let errors = "New Tree has Errors; the index keeps the last error-free version";
+        assert!(logged.iter().any(|message| message == errors));
+        // printing the tree would walk the whole file on every keystroke
+        assert!(logged
+            .iter()
+            .all(|message| !message.contains("(ERROR") && !message.contains("(MISSING")));
+        assert_eq!(published["uri"], url.as_str());
+        assert_eq!(published["version"], 2);
+        assert!(!published["diagnostics"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_open_documents_are_republished_when_a_dependency_changes_on_disk() {
+        let (client, mut notifications) =
+            test_client(|_, _| Err(jsonrpc::Error::method_not_found())).await;
+        let backend = BackendWrapper::new(client);
+        let dir = env::temp_dir().join(format!("objectscript-lsp-watch-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Test")).unwrap();
+        std::fs::write(dir.join("Test/Base.cls"), "Class Test.Base\n{\n}\n").unwrap();
+        let root = Url::from_directory_path(&dir).unwrap();
+        let base = root.join("Test/Base.cls").unwrap();
+        let sub = root.join("Test/Sub.cls").unwrap();
+        let state = ProjectState::new();
+        state.project_root_uri.set(root.clone()).unwrap();
+        state.index_workspace_scope(&FsSourceProvider, &root);
+        state.handle_document_opened(
+            sub.clone(),
+            "Class Test.Sub Extends Test.Base\n{\n}\n".to_string(),
+            FileType::Cls,
+            1,
+        );
+        assert!(state.data.read().get_document_diagnostics(&sub).is_empty());
+        backend.0.add_project(root, state);
+
+        // the superclass changes on disk, closing an inheritance cycle through the open class
+        std::fs::write(
+            dir.join("Test/Base.cls"),
+            "Class Test.Base Extends Test.Sub\n{\n}\n",
+        )
+        .unwrap();
+        let reindex = backend
+            .0
+            .apply_watched_files(vec![FileEvent::new(base, FileChangeType::CHANGED)])
+            .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(reindex.is_empty());
+
+        let published = loop {
+            let (method, params) = notifications.next().await.unwrap();
+            if method == "textDocument/publishDiagnostics" {
+                break params;
+            }
+        };
+        assert_eq!(published["uri"], sub.as_str());
+        assert_eq!(published["version"], 1);
+        let messages: Vec<&str> = published["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|diagnostic| diagnostic["message"].as_str())
+            .collect();
+        assert!(messages
+            .iter()
+            .any(|message| message.contains("inherits from itself")));
+    }
 }
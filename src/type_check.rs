@@ -0,0 +1,334 @@
+use crate::common::{
+    get_node_children, method_definition_nodes, start_of_function, successful_exit,
+    ts_range_to_lsp_range,
+};
+use crate::diagnostics::DIAGNOSTIC_SOURCE;
+use crate::method::initial_build_method;
+use crate::oref_types::dim_type;
+use crate::parse_structures::{ArgumentMode, MethodCallSite, VarType};
+use crate::rules::{rule_code, rule_code_description, TYPE_MISMATCH_RULE};
+use crate::variable::{argument_mode, declared_argument_name};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use tree_sitter::{Node, Range, Tree};
+
+/// Kinds of values strict mode tells apart. ObjectScript converts strings and numbers freely,
+/// so only conversions that lose the value (or make no sense) are mismatches.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TypeFamily {
+    Text,
+    Integer,
+    Number,
+    DynamicObject,
+    DynamicArray,
+    /// `%DynamicAbstractObject`, either of the two above.
+    DynamicEntity,
+}
+
+impl TypeFamily {
+    /// Returns the family of the declared type `type_name`, if it is one strict mode checks.
+    fn of_declared(type_name: &str) -> Option<Self> {
+        let lower = type_name.to_ascii_lowercase();
+        let name = match lower.strip_prefix("%library.") {
+            Some(short_name) => format!("%{short_name}"),
+            None => lower,
+        };
+        match name.as_str() {
+            "%string" | "%exactstring" | "%enumstring" | "%char" | "%text" | "%name"
+            | "%rawstring" | "%list" => Some(Self::Text),
+            "%integer" | "%smallint" | "%bigint" | "%tinyint" | "%boolean" | "%counter" => {
+                Some(Self::Integer)
+            }
+            "%numeric" | "%double" | "%float" | "%decimal" | "%currency" => Some(Self::Number),
+            "%dynamicobject" => Some(Self::DynamicObject),
+            "%dynamicarray" => Some(Self::DynamicArray),
+            "%dynamicabstractobject" => Some(Self::DynamicEntity),
+            _ => None,
+        }
+    }
+
+    /// Returns the family of the literal `text`, whose inferred type is `var_type`. The empty
+    /// string is ObjectScript's null value, so it has none, and strings holding a number count
+    /// as that number.
+    fn of_value(var_type: &VarType, text: &str) -> Option<Self> {
+        match var_type {
+            VarType::String => {
+                let value = text.get(1..text.len() - 1)?;
+                if value.is_empty() {
+                    None
+                } else if is_numeric_literal(value) {
+                    Some(Self::of_number(value))
+                } else {
+                    Some(Self::Text)
+                }
+            }
+            VarType::Number => Some(Self::of_number(text)),
+            VarType::JsonObjectLiteral => Some(Self::DynamicObject),
+            VarType::JsonArrayLiteral => Some(Self::DynamicArray),
+            _ => None,
+        }
+    }
+
+    fn of_number(text: &str) -> Self {
+        if text.contains(['.', 'e', 'E']) {
+            Self::Number
+        } else {
+            Self::Integer
+        }
+    }
+
+    /// Returns `true` if a value of the family `value` can be used where `self` is declared.
+    fn accepts(self, value: Self) -> bool {
+        match self {
+            Self::Text => matches!(value, Self::Text | Self::Integer | Self::Number),
+            Self::Integer => value == Self::Integer,
+            Self::Number => matches!(value, Self::Integer | Self::Number),
+            Self::DynamicObject => value == Self::DynamicObject,
+            Self::DynamicArray => value == Self::DynamicArray,
+            Self::DynamicEntity => matches!(value, Self::DynamicObject | Self::DynamicArray),
+        }
+    }
+
+    /// Name of the type reported for a value of the family.
+    fn type_name(self) -> &'static str {
+        match self {
+            Self::Text => "%String",
+            Self::Integer => "%Integer",
+            Self::Number => "%Numeric",
+            Self::DynamicObject | Self::DynamicEntity => "%DynamicObject",
+            Self::DynamicArray => "%DynamicArray",
+        }
+    }
+}
+
+/// Where a value meets the declared type it doesn't match.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TypeMismatchKind {
+    /// Passed as the argument `argument` of a call to `method`.
+    Argument { method: String, argument: String },
+    /// Set into the variable `variable`, declared as an argument or with `#dim`.
+    Assignment { variable: String },
+    /// Returned by `quit` or `return` from `method`.
+    Return { method: String },
+}
+
+/// A value whose type doesn't match the type declared where it is used.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypeMismatch {
+    pub kind: TypeMismatchKind,
+    /// Type of the value, e.g. `%String`.
+    pub passed: String,
+    /// Declared type, as written.
+    pub expected: String,
+    /// Range of the value.
+    pub range: Range,
+}
+
+impl TypeMismatch {
+    /// Renders the message of the warning, e.g.
+    /// `Passing %String where %Integer expected by argument "count" of "Total"`.
+    pub fn message(&self) -> String {
+        let (verb, target) = match &self.kind {
+            TypeMismatchKind::Argument { method, argument } => {
+                ("Passing", format!("argument {argument:?} of {method:?}"))
+            }
+            TypeMismatchKind::Assignment { variable } => {
+                ("Assigning", format!("variable {variable:?}"))
+            }
+            TypeMismatchKind::Return { method } => ("Returning", format!("method {method:?}")),
+        };
+        format!(
+            "{verb} {} where {} expected by {target}",
+            self.passed, self.expected
+        )
+    }
+}
+
+/// Infers the type of the expression `text` when it is a single literal: a string, a number, or
+/// a JSON object or array.
+pub fn literal_var_type(text: &str) -> Option<VarType> {
+    let text = text.trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        // a `"` inside the literal is doubled
+        let inner = &text[1..text.len() - 1];
+        return (inner.replace("\"\"", "").find('"').is_none()).then_some(VarType::String);
+    }
+    if is_numeric_literal(text) {
+        return Some(VarType::Number);
+    }
+    if text.starts_with('{') && text.ends_with('}') {
+        return Some(VarType::JsonObjectLiteral);
+    }
+    if text.starts_with('[') && text.ends_with(']') {
+        return Some(VarType::JsonArrayLiteral);
+    }
+    None
+}
+
+/// Returns `true` if `text` is a number written in decimal, e.g. `-12`, `.5`, or `1E3`.
+fn is_numeric_literal(text: &str) -> bool {
+    let digits = text.trim_start_matches(['-', '+']);
+    digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'))
+        && digits.parse::<f64>().is_ok()
+}
+
+/// Returns the type of the argument declaration `declaration` (e.g. `count As %Integer = 0`),
+/// without its parameters.
+pub fn declared_type(declaration: &str) -> Option<&str> {
+    let as_index = declaration.to_ascii_lowercase().find(" as ")?;
+    let type_name = declaration[as_index + 4..].trim_start();
+    let length = type_name
+        .find(|c: char| c.is_whitespace() || c == '(' || c == '=')
+        .unwrap_or(type_name.len());
+    (length > 0).then(|| &type_name[..length])
+}
+
+/// Checks the expression at `range`, when it is a literal, against the declared type `expected`.
+fn check_literal(
+    content: &str,
+    range: Range,
+    expected: &str,
+    kind: TypeMismatchKind,
+) -> Option<TypeMismatch> {
+    let text = content.get(range.start_byte..range.end_byte)?.trim();
+    let var_type = literal_var_type(text)?;
+    let expected_family = TypeFamily::of_declared(expected)?;
+    let value_family = TypeFamily::of_value(&var_type, text)?;
+    (!expected_family.accepts(value_family)).then(|| TypeMismatch {
+        kind,
+        passed: value_family.type_name().to_string(),
+        expected: expected.to_string(),
+        range,
+    })
+}
+
+/// Finds the literal arguments of `call_sites` that don't match the declared type of the
+/// argument they are passed as. `declarations` returns the argument declarations of the
+/// method a call site resolves to (see `Method::argument_declarations`); `Output` arguments
+/// are skipped, since their value is never read.
+pub fn argument_mismatches(
+    content: &str,
+    call_sites: &[MethodCallSite],
+    declarations: impl Fn(&MethodCallSite) -> Option<Vec<String>>,
+) -> Vec<TypeMismatch> {
+    start_of_function("TYPE CHECK (no struct)", "argument_mismatches");
+    let mut mismatches = Vec::new();
+    for call_site in call_sites {
+        let Some(declarations) = declarations(call_site) else {
+            continue;
+        };
+        for (range, declaration) in call_site.arg_ranges.iter().zip(&declarations) {
+            if argument_mode(declaration) == ArgumentMode::Output {
+                continue;
+            }
+            let Some(expected) = declared_type(declaration) else {
+                continue;
+            };
+            let argument = declared_argument_name(declaration).unwrap_or_default();
+            let kind = TypeMismatchKind::Argument {
+                method: call_site.callee_method.clone(),
+                argument: argument.to_string(),
+            };
+            mismatches.extend(check_literal(content, *range, expected, kind));
+        }
+    }
+    successful_exit("TYPE CHECK (no struct)", "argument_mismatches");
+    mismatches
+}
+
+/// Finds the literals the methods of `tree` return (with `quit` or `return`) that don't match
+/// their return type, and the literals they `set` into a typed variable (an argument, or a
+/// `#dim` name) that don't match its type.
+pub fn body_mismatches(content: &str, tree: &Tree) -> Vec<TypeMismatch> {
+    start_of_function("TYPE CHECK (no struct)", "body_mismatches");
+    let mut mismatches = Vec::new();
+    for (method_definition, method_type) in method_definition_nodes(tree.root_node()) {
+        let Some((method, _)) = initial_build_method(method_definition, method_type, content)
+        else {
+            continue;
+        };
+        let argument_type = |name: &str| {
+            method
+                .arguments
+                .iter()
+                .zip(&method.argument_declarations)
+                .find(|(argument, _)| *argument == name)
+                .and_then(|(_, declaration)| declared_type(declaration))
+                .map(str::to_string)
+        };
+        let mut stack = vec![method_definition];
+        while let Some(node) = stack.pop() {
+            match node.kind() {
+                "command_quit" | "command_return" => {
+                    if let (Some(return_type), Some(value)) =
+                        (&method.return_type_name, command_value(node, content))
+                    {
+                        let kind = TypeMismatchKind::Return {
+                            method: method.name.clone(),
+                        };
+                        mismatches.extend(check_literal(content, value.range(), return_type, kind));
+                    }
+                }
+                "command_set" => {
+                    // child(0) is the keyword; each set_argument is `target = value`
+                    for set_argument in get_node_children(node).into_iter().skip(1) {
+                        let (Some(target), Some(value)) =
+                            (set_argument.named_child(0), set_argument.named_child(1))
+                        else {
+                            continue;
+                        };
+                        let Some(name) = content.get(target.byte_range()) else {
+                            continue;
+                        };
+                        let expected = argument_type(name).or_else(|| {
+                            let before = content
+                                .get(method_definition.start_byte()..set_argument.start_byte())?;
+                            dim_type(before, name)
+                        });
+                        let Some(expected) = expected else {
+                            continue;
+                        };
+                        let kind = TypeMismatchKind::Assignment {
+                            variable: name.to_string(),
+                        };
+                        mismatches.extend(check_literal(content, value.range(), &expected, kind));
+                    }
+                }
+                _ => {}
+            }
+            let mut cursor = node.walk();
+            stack.extend(node.children(&mut cursor));
+        }
+    }
+    mismatches.sort_by_key(|mismatch| mismatch.range.start_byte);
+    successful_exit("TYPE CHECK (no struct)", "body_mismatches");
+    mismatches
+}
+
+/// Returns the value of a `quit value` or `return value` command; `None` without one, or with a
+/// postconditional.
+fn command_value<'a>(command: Node<'a>, content: &str) -> Option<Node<'a>> {
+    let keyword = command.named_child(0)?;
+    if content.get(keyword.end_byte()..)?.starts_with(':') {
+        return None;
+    }
+    command.named_child(1)
+}
+
+/// Reports each type mismatch as a warning.
+pub fn type_mismatch_diagnostics(content: &str, mismatches: &[TypeMismatch]) -> Vec<Diagnostic> {
+    mismatches
+        .iter()
+        .map(|mismatch| Diagnostic {
+            range: ts_range_to_lsp_range(content, mismatch.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(TYPE_MISMATCH_RULE),
+            code_description: rule_code_description(TYPE_MISMATCH_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: mismatch.message(),
+            ..Default::default()
+        })
+        .collect()
+}
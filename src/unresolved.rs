@@ -0,0 +1,358 @@
+use crate::case_rules::{names_match, IdentifierKind};
+use crate::common::{find_class_definition, get_node_children, ts_range_to_lsp_range};
+use crate::diagnostics::{byte_range, skip_string_or_comment, Dialect, DIAGNOSTIC_SOURCE};
+use crate::parse_structures::FileType;
+use crate::rules::{rule_code, rule_code_description, UNRESOLVED_REFERENCE_RULE};
+use serde::Deserialize;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, TextEdit, Url};
+use tree_sitter::{Range, Tree};
+
+/// Commands whose arguments may call a routine (`do ^Rtn`, `job tag^Rtn`, `goto tag^Rtn`).
+const ROUTINE_COMMANDS: [&str; 6] = ["do", "d", "job", "j", "goto", "g"];
+
+/// Parameters of the `objectscript/unresolved` request.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedParams {
+    /// Only report references in this document.
+    #[serde(default)]
+    pub uri: Option<Url>,
+    /// Only report references of this kind (`class`, `method`, `include`, or `routine`).
+    #[serde(default)]
+    pub kind: Option<String>,
+}
+
+/// What an `UnresolvedReference` refers to.
+///
+/// Macros aren't tracked: system include files (e.g. `%occStatus`, which every class gets
+/// implicitly) aren't available to the server, so every system macro would be reported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UnresolvedKind {
+    /// A class named by `##class()` or `Extends`.
+    Class,
+    /// A method called on a workspace class.
+    Method,
+    /// An `Include` file.
+    Include,
+    /// A routine called with `^`.
+    Routine,
+}
+
+impl UnresolvedKind {
+    /// Name of the kind in `objectscript/unresolved` responses and requests.
+    pub fn label(&self) -> &'static str {
+        match self {
+            UnresolvedKind::Class => "class",
+            UnresolvedKind::Method => "method",
+            UnresolvedKind::Include => "include",
+            UnresolvedKind::Routine => "routine",
+        }
+    }
+}
+
+/// A reference the resolver couldn't bind to anything in the workspace.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnresolvedReference {
+    pub kind: UnresolvedKind,
+    /// Referenced name; methods are qualified by their class (`Pkg.Cls.Method`).
+    pub name: String,
+    /// Range of the reference.
+    pub range: Range,
+}
+
+/// Returns `true` if `name` refers to a system class, include file, routine, or method (one
+/// starting with `%`), which are never in the workspace.
+pub fn is_system_name(name: &str) -> bool {
+    name.starts_with('%')
+}
+
+/// Returns the full name of the class `name`: a system class without a package, e.g.
+/// `%Persistent`, is short for one of `%Library`.
+pub fn qualified_class_name(name: &str) -> String {
+    match name.strip_prefix('%') {
+        Some(short_name) if !short_name.contains('.') => format!("%Library.{short_name}"),
+        _ => name.to_string(),
+    }
+}
+
+/// Returns the dotted path (without extension) and type of a `.mac` or `.inc` file, e.g.
+/// `src.MyApp.Utils` for `file:///src/MyApp/Utils.inc`.
+pub fn routine_path_name(url: &Url) -> Option<(String, FileType)> {
+    let (path, ext) = url.path().rsplit_once('.')?;
+    let file_type = match ext {
+        "inc" => FileType::Inc,
+        "mac" => FileType::Mac,
+        _ => return None,
+    };
+    let name = path.trim_start_matches('/').replace('/', ".");
+    Some((name, file_type))
+}
+
+/// Returns `true` if the routine or include file at the dotted `path_name` (see
+/// `routine_path_name`) is named `name`, whether it is stored in package folders or not.
+pub fn routine_name_matches(path_name: &str, name: &str) -> bool {
+    path_name.eq_ignore_ascii_case(name)
+        || path_name
+            .to_ascii_lowercase()
+            .ends_with(&format!(".{}", name.to_ascii_lowercase()))
+}
+
+/// Finds the superclasses named in the `Extends` clause of the class defined by `tree`.
+pub fn superclass_names(content: &str, tree: &Tree) -> Vec<(String, Range)> {
+    let Some(class_definition) = find_class_definition(tree.root_node()) else {
+        return Vec::new();
+    };
+    get_node_children(class_definition)
+        .into_iter()
+        .filter(|node| node.kind() == "class_extends")
+        // child(0) is the `Extends` keyword
+        .flat_map(|node| get_node_children(node).into_iter().skip(1))
+        .filter_map(|node| Some((content.get(node.byte_range())?.to_string(), node.range())))
+        .collect()
+}
+
+/// Finds the include files named by the `Include` statements before the class definition.
+///
+/// This is a lexical scan of the lines before the class definition, accepting `Include Name`
+/// and `Include (A, B)`.
+pub fn include_names(content: &str, tree: &Tree) -> Vec<(String, Range)> {
+    header_names(content, tree, "Include")
+}
+
+/// Finds the include files named by the `IncludeGenerator` statements before the class
+/// definition, whose macros are available to method generators.
+pub fn include_generator_names(content: &str, tree: &Tree) -> Vec<(String, Range)> {
+    header_names(content, tree, "IncludeGenerator")
+}
+
+/// Finds the packages named by the `Import` statements before the class definition, whose
+/// classes can be referenced without their package.
+pub fn import_names(content: &str, tree: &Tree) -> Vec<(String, Range)> {
+    header_names(content, tree, "Import")
+}
+
+/// Builds the edit adding `Import package` to the header of the class defined by `tree`: on a
+/// line after the last `Import` or `Include` statement, or on a line of its own at the top of
+/// the document if there are none.
+pub fn import_edit(content: &str, tree: &Tree, package: &str) -> TextEdit {
+    let header_end = find_class_definition(tree.root_node()).map_or(0, |node| node.start_byte());
+    let mut insert_at = None;
+    let mut line_start = 0;
+    for line in content[..header_end].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let keyword_end = trimmed
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(trimmed.len());
+        let is_header_statement = ["Import", "Include", "IncludeGenerator"]
+            .iter()
+            .any(|keyword| trimmed[..keyword_end].eq_ignore_ascii_case(keyword));
+        line_start += line.len();
+        if is_header_statement && line.ends_with('\n') {
+            insert_at = Some(line_start);
+        }
+    }
+    let (offset, new_text) = match insert_at {
+        Some(offset) => (offset, format!("Import {package}\n")),
+        None => (0, format!("Import {package}\n\n")),
+    };
+    TextEdit {
+        range: ts_range_to_lsp_range(content, byte_range(content, offset, offset)),
+        new_text,
+    }
+}
+
+/// Finds the names listed by the header statements starting with `keyword` (case-insensitively)
+/// before the class definition, e.g. `Include (A, B)`.
+fn header_names(content: &str, tree: &Tree, keyword: &str) -> Vec<(String, Range)> {
+    let header_end = find_class_definition(tree.root_node()).map_or(0, |node| node.start_byte());
+    let mut includes = Vec::new();
+    let mut line_start = 0;
+    for line in content[..header_end].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let keyword_end = trimmed
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(trimmed.len());
+        if trimmed[..keyword_end].eq_ignore_ascii_case(keyword) {
+            let mut offset = line_start + indent + keyword_end;
+            for part in trimmed[keyword_end..].split(',') {
+                let name_start = part
+                    .find(|c: char| c.is_ascii_alphanumeric() || c == '%')
+                    .map(|n| offset + n);
+                let name: String = part
+                    .trim_start_matches(|c: char| c.is_whitespace() || c == '(')
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '%')
+                    .collect();
+                if let Some(start) = name_start.filter(|_| !name.is_empty()) {
+                    let range = byte_range(content, start, start + name.len());
+                    includes.push((name, range));
+                }
+                offset += part.len() + 1;
+            }
+        }
+        line_start += line.len();
+    }
+    includes
+}
+
+/// Finds the routines called with `^` in `content`: `do ^Rtn`, `do tag^Rtn`, `$$tag^Rtn()`,
+/// `$$^Rtn()`, and the same after `job` and `goto`.
+///
+/// This is a lexical scan like `bracket_diagnostics`; strings and comments are skipped, and a
+/// `^Name` that doesn't follow `$$` or a routine command is a global.
+pub fn routine_references(content: &str) -> Vec<(String, Range)> {
+    let bytes = content.as_bytes();
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(next) = skip_string_or_comment(content, i, Dialect::ObjectScript) {
+            i = next;
+            continue;
+        }
+        let b = bytes[i];
+        if b != b'^' {
+            i += 1;
+            continue;
+        }
+        let name_start = i + 1;
+        let name_length = content[name_start..]
+            .char_indices()
+            .take_while(|&(n, c)| c.is_ascii_alphanumeric() || c == '.' || (n == 0 && c == '%'))
+            .count();
+        i = name_start + name_length;
+        if name_length == 0
+            || !(is_extrinsic(content, name_start)
+                || follows_routine_command(content, name_start - 1))
+        {
+            continue;
+        }
+        references.push((
+            content[name_start..i].to_string(),
+            byte_range(content, name_start, i),
+        ));
+    }
+    references
+}
+
+/// Finds the routine calls of `content` like `routine_references`, each with the label called
+/// before the `^` (`None` for `do ^Rtn`), the routine name, and the range of the whole call,
+/// e.g. `Run^MyApp.Jobs`.
+pub fn routine_label_calls(content: &str) -> Vec<(Option<String>, String, Range)> {
+    routine_references(content)
+        .into_iter()
+        .map(|(name, range)| {
+            let caret = range.start_byte - 1;
+            let start = label_start(content, caret);
+            let label = (start < caret).then(|| content[start..caret].to_string());
+            (label, name, byte_range(content, start, range.end_byte))
+        })
+        .collect()
+}
+
+/// Finds the calls of labels without a routine in `content`, which call a label of the same
+/// routine: `do Label`, `goto Label`, `job Label`, and `$$Label()`.
+///
+/// This is a lexical scan like `routine_references`; names followed by `^` (routine calls) or
+/// `.` (object references) aren't labels.
+pub fn local_label_calls(content: &str) -> Vec<(String, Range)> {
+    let bytes = content.as_bytes();
+    let is_name = |b: u8| b.is_ascii_alphanumeric() || b == b'%';
+    let mut calls = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(next) = skip_string_or_comment(content, i, Dialect::ObjectScript) {
+            i = next;
+            continue;
+        }
+        let b = bytes[i];
+        if !is_name(b) {
+            i += 1;
+            continue;
+        }
+        let name_start = i;
+        while i < bytes.len() && is_name(bytes[i]) {
+            i += 1;
+        }
+        let next = bytes.get(i).copied();
+        let previous = name_start.checked_sub(1).map(|n| bytes[n]);
+        if matches!(next, Some(b'^' | b'.')) || matches!(previous, Some(b'.' | b'^' | b'#')) {
+            continue;
+        }
+        let before_name = &content[..name_start];
+        let is_extrinsic = before_name.ends_with("$$") && !before_name.ends_with("$$$");
+        if is_extrinsic || follows_routine_command(content, i) {
+            calls.push((
+                content[name_start..i].to_string(),
+                byte_range(content, name_start, i),
+            ));
+        }
+    }
+    calls
+}
+
+/// Returns the byte offset where the label before the `^` at `caret` starts (the `^` itself
+/// when there's no label).
+fn label_start(content: &str, caret: usize) -> usize {
+    caret
+        - content[..caret]
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '%')
+            .count()
+}
+
+/// Returns `true` if the routine name at `name_start` is part of an extrinsic function call
+/// (`$$tag^Rtn` or `$$^Rtn`).
+fn is_extrinsic(content: &str, name_start: usize) -> bool {
+    content[..label_start(content, name_start - 1)].ends_with("$$")
+}
+
+/// Returns `true` if the `^` at `caret` (with its label) is an argument of a `do`, `job`, or
+/// `goto` command, possibly with a postconditional.
+fn follows_routine_command(content: &str, caret: usize) -> bool {
+    let mut arguments = &content[..label_start(content, caret)];
+    // skip the earlier arguments of the command: `do ^Setup, Run^Jobs`
+    while let Some(rest) = arguments.trim_end_matches([' ', '\t']).strip_suffix(',') {
+        arguments = rest
+            .trim_end_matches([' ', '\t'])
+            .trim_end_matches(|c: char| !c.is_whitespace() && c != ',');
+    }
+    let before = arguments.trim_end_matches([' ', '\t']);
+    if before.len() == arguments.len() {
+        // the arguments must be separated from the command
+        return false;
+    }
+    // skip a postconditional: `do:cond ^Rtn`
+    let command_end = before
+        .rfind(|c: char| c.is_whitespace())
+        .map_or(0, |n| n + 1);
+    let word = &before[command_end..];
+    let command = word.split_once(':').map_or(word, |(command, _)| command);
+    ROUTINE_COMMANDS
+        .iter()
+        .any(|routine_command| names_match(IdentifierKind::Command, routine_command, command))
+}
+
+/// Reports each unresolved reference as a warning.
+pub fn unresolved_diagnostics(
+    content: &str,
+    references: &[UnresolvedReference],
+) -> Vec<Diagnostic> {
+    references
+        .iter()
+        .map(|reference| Diagnostic {
+            range: ts_range_to_lsp_range(content, reference.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code(UNRESOLVED_REFERENCE_RULE),
+            code_description: rule_code_description(UNRESOLVED_REFERENCE_RULE),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+            message: format!(
+                "Unresolved {} {:?}: not found in the workspace",
+                reference.kind.label(),
+                reference.name
+            ),
+            ..Default::default()
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+use serde_json::{json, Value};
+
+/// Kind of a `UsageEntry`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UsageKind {
+    Class,
+    Method,
+}
+
+impl UsageKind {
+    fn label(&self) -> &'static str {
+        match self {
+            UsageKind::Class => "class",
+            UsageKind::Method => "method",
+        }
+    }
+}
+
+/// How often a class or public method is referenced across the workspace.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsageEntry {
+    pub kind: UsageKind,
+    /// Class name, or `Class.Method` for methods.
+    pub name: String,
+    /// Resolved call sites referencing it; for a class, the calls to any of its public methods.
+    pub references: usize,
+    /// The `references` made from other classes.
+    pub external_references: usize,
+    /// Classes extending it directly, for classes.
+    pub subclasses: Option<usize>,
+    /// Path of the file defining it, relative to the workspace root when possible.
+    pub path: String,
+    /// 1-based line of the definition.
+    pub line: usize,
+}
+
+/// Sorts `entries` from the most to the least referenced, so hot APIs come first and unused
+/// ones last.
+fn sort_entries(entries: &mut [UsageEntry]) {
+    entries.sort_by(|a, b| {
+        b.references
+            .cmp(&a.references)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// Renders `entries` as a JSON array for the `objectscript.exportUsage` command.
+pub fn render_usage_json(mut entries: Vec<UsageEntry>) -> Value {
+    sort_entries(&mut entries);
+    Value::Array(
+        entries
+            .into_iter()
+            .map(|entry| {
+                json!({
+                    "kind": entry.kind.label(),
+                    "name": entry.name,
+                    "references": entry.references,
+                    "externalReferences": entry.external_references,
+                    "subclasses": entry.subclasses,
+                    "path": entry.path,
+                    "line": entry.line,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Renders `entries` as CSV with a header row; `subclasses` is empty for methods.
+pub fn render_usage_csv(mut entries: Vec<UsageEntry>) -> String {
+    sort_entries(&mut entries);
+    let mut csv = String::from("kind,name,references,external_references,subclasses,path,line\n");
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            entry.kind.label(),
+            entry.name,
+            entry.references,
+            entry.external_references,
+            entry.subclasses.map_or(String::new(), |n| n.to_string()),
+            csv_field(&entry.path),
+            entry.line
+        ));
+    }
+    csv
+}
+
+/// Quotes `field` if it contains a separator, quote, or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,9 +1,11 @@
 use crate::common::{
     find_return_type, find_var_type_from_expression, generic_skipping_statements,
-    get_node_children, get_string_at_byte_range, start_of_function, successful_exit,
+    get_node_children, get_string_at_byte_range, method_definition_nodes, point_to_byte,
+    start_of_function, successful_exit, word_at_offset,
 };
-use crate::parse_structures::{ReturnType, VarType, Variable};
-use tree_sitter::{Node, Range};
+use crate::diagnostics::{byte_range, is_simple_variable_name, lvn_base_name};
+use crate::parse_structures::{ArgumentMode, ReturnType, VarType, Variable};
+use tree_sitter::{Node, Point, Range, Tree};
 
 /// Build a `Variable` from the RHS expression of a `set` argument.
 ///
@@ -187,12 +189,165 @@ pub fn build_variable_from_argument(
         "Building Variable (No Struct)",
         "build_variable_from_argument",
     );
-    (
-        Variable::new(var_name, argument_type, argument_value, is_public),
-        var_name_range,
-        var_refs,
-        property_refs,
-    )
+    let mut variable = Variable::new(var_name, argument_type, argument_value, is_public);
+    variable.argument_mode = Some(argument_mode(
+        content.get(node.byte_range()).unwrap_or_default(),
+    ));
+    (variable, var_name_range, var_refs, property_refs)
+}
+
+/// Returns how the method argument declared as `declaration` (e.g. `ByRef count As %Integer`)
+/// is passed; modifiers are case-insensitive.
+pub fn argument_mode(declaration: &str) -> ArgumentMode {
+    match declaration.split_whitespace().next() {
+        Some(modifier) if modifier.eq_ignore_ascii_case("byref") => ArgumentMode::ByRef,
+        Some(modifier) if modifier.eq_ignore_ascii_case("output") => ArgumentMode::Output,
+        _ => ArgumentMode::ByValue,
+    }
+}
+
+/// Returns the argument name of the argument declaration `declaration` (e.g. `count` for
+/// `ByRef count As %Integer = 0`), keeping the `...` of a variable number of arguments.
+pub fn declared_argument_name(declaration: &str) -> Option<&str> {
+    let word = declaration.split_whitespace().find(|word| {
+        !["byref", "output", "byval"]
+            .iter()
+            .any(|modifier| word.eq_ignore_ascii_case(modifier))
+    })?;
+    let length = word
+        .find(|c: char| !(c.is_alphanumeric() || c == '%' || c == '.'))
+        .unwrap_or(word.len());
+    (length > 0).then(|| &word[..length])
+}
+
+/// Returns the name node of the method argument node `argument`, skipping a leading `ByRef`,
+/// `Output`, or `ByVal` modifier.
+pub fn argument_name_node<'a>(argument: Node<'a>, content: &str) -> Option<Node<'a>> {
+    let first = argument.named_child(0)?;
+    let is_modifier = content.get(first.byte_range()).is_some_and(|text| {
+        ["byref", "output", "byval"]
+            .iter()
+            .any(|modifier| text.eq_ignore_ascii_case(modifier))
+    });
+    if is_modifier {
+        argument.named_child(1)
+    } else {
+        Some(first)
+    }
+}
+
+/// An occurrence of a local variable found by `variable_occurrences`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariableOccurrence {
+    /// Range of the variable name, without its subscripts.
+    pub range: Range,
+    /// `true` for an argument declaration or a `set` or `new` target, `false` for a read.
+    pub is_write: bool,
+}
+
+/// Finds every occurrence of the local variable at `point` in the method containing it, in
+/// document order: its argument declaration and `set` and `new` targets are writes, and every
+/// other reference is a read. Subscripted references count as the variable (`x(1)` is `x`).
+///
+/// Returns an empty vector if `point` isn't on a local variable of a method.
+pub fn variable_occurrences(content: &str, tree: &Tree, point: Point) -> Vec<VariableOccurrence> {
+    start_of_function("VARIABLE (no struct)", "variable_occurrences");
+    let Some(method_definition) = method_definition_nodes(tree.root_node())
+        .into_iter()
+        .map(|(node, _)| node)
+        .find(|node| node.start_position() <= point && point <= node.end_position())
+    else {
+        successful_exit("VARIABLE (no struct)", "variable_occurrences");
+        return Vec::new();
+    };
+    let offset = point_to_byte(content, point);
+    let word = word_at_offset(content, offset, |c| c.is_alphanumeric() || c == '%');
+    let name = &content[word];
+    if !is_simple_variable_name(name) {
+        successful_exit("VARIABLE (no struct)", "variable_occurrences");
+        return Vec::new();
+    }
+
+    let name_range = |node: Node| {
+        let start = node.start_byte();
+        byte_range(content, start, start + name.len())
+    };
+    let mut occurrences = Vec::new();
+    let mut stack = vec![method_definition];
+    while let Some(node) = stack.pop() {
+        let text = content.get(node.byte_range()).unwrap_or_default();
+        match node.kind() {
+            // the declared arguments, not the arguments of a call
+            "arguments" if node.parent() == Some(method_definition) => {
+                for argument in get_node_children(node) {
+                    let Some(argument_name) = argument_name_node(argument, content) else {
+                        continue;
+                    };
+                    if content.get(argument_name.byte_range()) == Some(name) {
+                        occurrences.push(VariableOccurrence {
+                            range: argument_name.range(),
+                            is_write: true,
+                        });
+                    }
+                    // default values may reference other arguments
+                    stack.extend(get_node_children(argument).into_iter().skip(1));
+                }
+                continue;
+            }
+            "command_set" => {
+                // child(0) is the keyword; each set_argument is `target = value`
+                for set_argument in get_node_children(node).into_iter().skip(1) {
+                    let target = set_argument.named_child(0);
+                    let target_text = target
+                        .and_then(|target| content.get(target.byte_range()))
+                        .unwrap_or_default();
+                    match (target, set_argument.named_child(1)) {
+                        (Some(target), Some(value)) if lvn_base_name(target_text) == name => {
+                            occurrences.push(VariableOccurrence {
+                                range: name_range(target),
+                                is_write: true,
+                            });
+                            // the subscripts of the target are reads
+                            stack.extend(get_node_children(target).into_iter().skip(1));
+                            stack.push(value);
+                        }
+                        _ => stack.push(set_argument),
+                    }
+                }
+                continue;
+            }
+            "command_new" => {
+                for argument in get_node_children(node).into_iter().skip(1) {
+                    if content.get(argument.byte_range()) == Some(name) {
+                        occurrences.push(VariableOccurrence {
+                            range: argument.range(),
+                            is_write: true,
+                        });
+                    }
+                }
+                continue;
+            }
+            "lvn" if lvn_base_name(text) == name => {
+                occurrences.push(VariableOccurrence {
+                    range: name_range(node),
+                    is_write: false,
+                });
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+    occurrences.sort_by_key(|occurrence| occurrence.range.start_byte);
+    occurrences.dedup_by_key(|occurrence| occurrence.range.start_byte);
+    let at_point = occurrences.iter().any(|occurrence| {
+        occurrence.range.start_byte <= offset && offset <= occurrence.range.end_byte
+    });
+    if !at_point {
+        occurrences.clear();
+    }
+    successful_exit("VARIABLE (no struct)", "variable_occurrences");
+    occurrences
 }
 
 impl Variable {
@@ -211,6 +366,7 @@ impl Variable {
             arg_type,
             var_type,
             is_public,
+            argument_mode: None,
         }
     }
 }
@@ -1,26 +1,105 @@
+use crate::atelier::{is_read_only_url, AtelierConfig};
+use crate::call_graph::{
+    recursion_diagnostics, CallEdge, CallGraph, CallGraphNode, RecursiveMethod,
+};
+use crate::cancellation::CancellationToken;
+use crate::class_reference::{
+    documatic_markdown, method_declaration, reference_class, system_class_reference_at,
+};
+use crate::code_lens::{reference_count_lens, ClassStats};
 use crate::common::{
     build_method_calls, build_method_calls_from_unresolved, find_class_definition,
     generic_exit_statements, generic_skipping_statements, get_class_name_from_root,
-    get_node_children, initial_build_scope_tree, print_statements_exit_method_overrides_fn,
-    start_of_function, successful_exit,
+    get_node_children, initial_build_scope_tree, method_definition_nodes,
+    method_name_from_identifier_node, point_to_byte, point_to_lsp_position, position_to_point,
+    print_statements_exit_method_overrides_fn, resolve_method_call_sites, start_of_function,
+    successful_exit, ts_range_to_lsp_range, word_at_offset,
 };
 use crate::config::Config;
-use crate::document::Document;
+use crate::diagnostics::{
+    arity_diagnostics, bracket_diagnostics, byte_range, case_mismatch_diagnostics, class_members,
+    dead_store_diagnostics, debug_write_diagnostics, dispatch_mismatch_diagnostics,
+    downgrade_generated_diagnostics, duplicate_member_diagnostics, find_arity_mismatches,
+    find_dead_stores, find_debug_writes, find_unreachable_code, find_unused_variables,
+    inheritance_cycle_diagnostics, is_generated_class, member_collision_diagnostics,
+    syntax_diagnostics, unreachable_code_diagnostics, unused_variable_diagnostics, xdata_blocks,
+    ArityMismatch, DispatchMismatch, InheritedMember, MemberKind,
+};
+use crate::document::{Document, TextBuffer};
+use crate::element_types::element_type_hover;
+use crate::embedded_sql::{
+    find_sql_references, private_host_variables, undefined_host_variable_diagnostics, SqlReference,
+    SqlReferenceKind,
+};
+use crate::file_events::BulkChangeDetector;
+use crate::folding::folding_ranges;
+use crate::formatting::{block_indentation_edits, command_abbreviation_edits};
+use crate::fuzzy::{closest_names, fuzzy_score};
 use crate::global_semantic::GlobalSemanticModel;
+use crate::index_progress::IndexProgress;
+use crate::interner::Name;
+use crate::macros::{include_macros, macro_name_at, IncludeMacros, MacroDefinition};
+use crate::method::initial_build_method;
+use crate::metrics::{complexity_diagnostics, method_metrics, MethodMetrics};
+use crate::new_class::new_class_url;
+use crate::oref_types::{infer_oref_type, ChainMember};
 use crate::override_index::OverrideIndex;
+use crate::package_rename::{
+    class_rename_edits, imports_package, is_class_name, package_rename_edits, renamed_class_name,
+    renamed_class_path, PackageRename,
+};
 use crate::parse_structures::{
-    Class, ClassId, FileType, Language, LocalSemanticModelId, MethodCallSite,
-    PrivateMethodId, PublicMethodId, PublicMethodRef,
+    ArgumentMode, Class, ClassId, ClassMemberDefinition, ClassParameter, ClassProperty,
+    ClassStatements, ClassXData, FileType, Language, LocalSemanticModelId, MemberDefinitionId,
+    Method, MethodCallSite, MethodHandle, MethodRef, MethodType, ParameterId, PrivateMethodId,
+    PropertyId, PublicMethodId, PublicMethodRef, XDataId,
 };
+use crate::python::{python_bodies, python_symbols, PythonBody};
+use crate::reference_index::ReferenceIndex;
+use crate::response_cache::ResponseCache;
+use crate::routine::{label_reference_at, routine_symbols, RoutineLabel, RoutineModel};
 use crate::scope_structures::{
-    ClassGlobalSymbolId, MethodGlobalSymbol, MethodGlobalSymbolId, VariableGlobalSymbolId,
+    ClassGlobalSymbolId, GlobalSymbolId, MethodGlobalSymbol, MethodGlobalSymbolId,
+    VariableGlobalSymbolId,
+};
+use crate::semantic_tokens::{
+    encode_tokens, syntax_tokens, CachedTokens, RawToken, SemanticTokensCache, TokenType,
+    DECLARATION,
+};
+use crate::snapshot::IndexSnapshots;
+use crate::source_provider::{FsSourceProvider, MemorySourceProvider, SourceProvider};
+use crate::sql_surface::{default_schema, sql_projections, SqlProjection};
+use crate::symbol_index::{SymbolEntry, SymbolLocationIndex, SymbolRef};
+use crate::system_api::{
+    find_system_references, system_api_diagnostics, system_api_hover, system_api_signature_help,
+};
+use crate::tags::{TagEntry, TagKind};
+use crate::type_check::{
+    argument_mismatches, body_mismatches, type_mismatch_diagnostics, TypeMismatch,
 };
-use parking_lot::{Mutex, RwLock};
+use crate::unresolved::{
+    import_edit, import_names, include_generator_names, include_names, is_system_name,
+    local_label_calls, qualified_class_name, routine_label_calls, routine_name_matches,
+    routine_path_name, routine_references, superclass_names, unresolved_diagnostics,
+    UnresolvedKind, UnresolvedReference,
+};
+use crate::usage::{UsageEntry, UsageKind};
+use crate::variable::{argument_mode, variable_occurrences};
+use crate::xml_export::{is_archive_url, parse_export, ExportLineMap};
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::io;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
-use std::sync::OnceLock;
-use tower_lsp::lsp_types::Url;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeLens, Diagnostic,
+    DocumentSymbol, FileChangeType, FoldingRange, Location, Position, Range as LspRange,
+    SemanticToken, SignatureHelp, SymbolInformation, SymbolKind, TextEdit, Url,
+};
 use tree_sitter::{Node, Parser, Point, Range, Tree};
 use tree_sitter_objectscript::{LANGUAGE_OBJECTSCRIPT, LANGUAGE_OBJECTSCRIPT_CORE};
 use crate::local_semantic::LocalSemanticModel;
@@ -76,15 +155,28 @@ pub struct ProjectData {
     /// Stores all semantic information for this Workspace.
     pub(crate) global_semantic_model: GlobalSemanticModel,
     /// Maps class name -> ClassId(index) for each class in this workspace.
-    pub(crate) classes: HashMap<String, ClassId>,
+    pub(crate) classes: HashMap<Name, ClassId>,
     /// Maps class name -> ClassGlobalSymbolId(Index) for each class in this workspace.
-    pub(crate) class_defs: HashMap<String, ClassGlobalSymbolId>,
+    pub(crate) class_defs: HashMap<Name, ClassGlobalSymbolId>,
     /// Maps Class Name -> another hashmap which maps Method Name -> MethodGlobalSymbolId for all public methods
-    pub(crate) pub_method_defs: HashMap<String, HashMap<String, MethodGlobalSymbolId>>,
+    pub(crate) pub_method_defs: HashMap<Name, HashMap<Name, MethodGlobalSymbolId>>,
     /// Maps Var Name -> another hashmap which maps Class name -> a list of VariableGlobalSymbolId for that variable.
     pub(crate) pub_var_defs: HashMap<String, HashMap<String, Vec<VariableGlobalSymbolId>>>,
     /// Holds the OverrideIndex for the workspace.
     pub(crate) override_index: OverrideIndex,
+    /// Holds the incrementally maintained call-site references for each public method.
+    pub(crate) reference_index: ReferenceIndex,
+    /// Maps each document extracted from an XML export archive back into its archive.
+    pub(crate) export_line_maps: HashMap<Url, ExportLineMap>,
+    /// Bounded history of the project's symbols, for diffing before/after large changes.
+    pub(crate) snapshots: IndexSnapshots,
+    /// Maps the dotted path of each `.mac` and `.inc` file in the workspace to its type, so
+    /// references to routines and include files can be resolved.
+    pub(crate) routine_files: HashMap<String, FileType>,
+    /// Maps the dotted path of each `.inc` file in the workspace to the macros it defines.
+    pub(crate) include_macros: HashMap<String, IncludeMacros>,
+    /// Holds the labels of each `.mac` routine in the workspace, by url.
+    pub(crate) routines: HashMap<Url, RoutineModel>,
 }
 
 /// Concurrency wrapper for a workspace’s state and parsers.
@@ -94,12 +186,28 @@ pub struct ProjectData {
 /// entry point for workspace-level operations (open/update/index).
 #[derive(Debug)]
 pub struct ProjectState {
-    /// Workspace root path (set once during initialize()).
+    /// Workspace root path (set once during initialize()); `None` for non-`file://` workspaces.
     pub(crate) project_root_path: OnceLock<Option<PathBuf>>,
+    /// Workspace root uri (set once during initialize()).
+    pub(crate) project_root_uri: OnceLock<Url>,
+    /// Access to the workspace's source files (set once during initialize()).
+    pub(crate) source_provider: OnceLock<Arc<dyn SourceProvider>>,
     /// Lock-protected workspace data (documents, semantics, symbols, indexes).
     pub(crate) data: RwLock<ProjectData>,
     /// Reusable parsers for `.cls` and routine files.
     pub(crate) parsers: WorkspaceParsers,
+    /// Recent watched-file events, to detect bulk changes such as branch switches.
+    pub(crate) file_events: Mutex<BulkChangeDetector>,
+    /// Whether a background re-index (`reindex_workspace_scope`) is running.
+    pub(crate) reindexing: AtomicBool,
+    /// Whether files changed during the running re-index, so it must run again.
+    pub(crate) reindex_pending: AtomicBool,
+    /// Progress of the running `index_workspace_scope`, reported to the client by the server.
+    pub(crate) index_progress: IndexProgress,
+    /// Responses of pure requests, cleared whenever `data` changes.
+    pub(crate) response_cache: ResponseCache,
+    /// The semantic tokens last sent for each document, the base of delta requests.
+    pub(crate) semantic_tokens: SemanticTokensCache,
 }
 
 impl ProjectData {
@@ -109,7 +217,7 @@ impl ProjectData {
     /// are cloned so callers can use them without holding a borrow on `ProjectData`.
     ///
     /// Returns `None` if the document is not currently tracked.
-    pub fn get_document_info(&self, url: &Url) -> Option<(FileType, String, i32, Tree)> {
+    pub fn get_document_info(&self, url: &Url) -> Option<(FileType, TextBuffer, i32, Tree)> {
         // start_of_function("ProjectData", "get_document_info");
         let Some(document) = self.get_document(url) else {
             generic_exit_statements("ProjectData", "get_document_info");
@@ -132,7 +240,7 @@ impl ProjectData {
     pub fn add_document_if_absent(
         &mut self,
         url: Url,
-        code: String,
+        code: TextBuffer,
         tree: Tree,
         filetype: FileType,
         class_name: String,
@@ -163,7 +271,7 @@ impl ProjectData {
     pub fn add_document(
         &mut self,
         url: Url,
-        code: String,
+        code: TextBuffer,
         tree: Tree,
         filetype: FileType,
         class_name: String,
@@ -182,7 +290,13 @@ impl ProjectData {
             // build vec of public methods to add to gsm at the end
             let mut gsm_methods = Vec::new();
             let mut class = Class::new(class_name.clone());
-            let methods = class.initial_build(node, content);
+            let ClassStatements {
+                methods,
+                properties,
+                parameters,
+                xdata_blocks,
+                member_definitions,
+            } = class.initial_build(node, content);
             let class_symbol_id = self.global_semantic_model.new_class_symbol(
                 class_name.clone(),
                 class_range,
@@ -222,10 +336,11 @@ impl ProjectData {
                         continue;
                     };
                     // add method symbol
+                    let names = &self.global_semantic_model.names;
                     self.pub_method_defs
-                        .entry(class_name.clone())
+                        .entry(names.intern(&class_name))
                         .or_insert_with(HashMap::new)
-                        .insert(method_name.clone(), method_symbol_id);
+                        .insert(names.intern(&method_name), method_symbol_id);
                 } else {
                     // add method to local semantic model
                     let method_id = local_semantic_model.new_method(method);
@@ -238,6 +353,32 @@ impl ProjectData {
                         .new_method_symbol(method_name.clone(), range);
                 }
             }
+            // public and private properties both live in the local semantic model
+            for property in properties {
+                let property_name = property.name.clone();
+                let is_public = property.is_public;
+                let property_id = local_semantic_model.new_property(property);
+                if is_public {
+                    class.public_properties.insert(property_name, property_id);
+                } else {
+                    class.private_properties.insert(property_name, property_id);
+                }
+            }
+            for parameter in parameters {
+                let parameter_name = parameter.name.clone();
+                let parameter_id = local_semantic_model.new_parameter(parameter);
+                class.parameters.insert(parameter_name, parameter_id);
+            }
+            for xdata in xdata_blocks {
+                let xdata_name = xdata.name.clone();
+                let xdata_id = local_semantic_model.new_xdata(xdata);
+                class.xdata_blocks.insert(xdata_name, xdata_id);
+            }
+            for definition in member_definitions {
+                let key = (definition.kind, definition.name.clone());
+                let definition_id = local_semantic_model.new_member_definition(definition);
+                class.member_definitions.insert(key, definition_id);
+            }
             // add class to global semantic model
             let class_id = self.global_semantic_model.new_class(class);
             for method in gsm_methods {
@@ -245,7 +386,8 @@ impl ProjectData {
             }
 
             // add class id corresponding to class struct
-            self.classes.insert(class_name.clone(), class_id);
+            let name = self.global_semantic_model.names.intern(&class_name);
+            self.classes.insert(name.clone(), class_id);
 
             let local_semantic_id = self
                 .global_semantic_model
@@ -254,7 +396,8 @@ impl ProjectData {
             document.local_semantic_model_id = Some(local_semantic_id);
             document.class_id = Some(class_id);
             self.documents.insert(url.clone(), document);
-            self.class_defs.insert(class_name.clone(), class_symbol_id);
+            self.class_defs.insert(name, class_symbol_id);
+            self.rebuild_symbol_index(&url);
 
             successful_exit("ProjectData", "add_document");
         }
@@ -262,7 +405,8 @@ impl ProjectData {
 
     /// Update a tracked document after text edits or reparse.
     ///
-    /// This function:
+    /// `code` becomes the document's text as is, so a buffer taken from the document keeps its
+    /// rope. This function:
     /// - Re-parses/derives the current class name from the new `tree` + `content`
     /// - Rebuilds the document's scope tree
     /// - Clears old symbol/semantic state for the document (class/method/variable symbols, local model)
@@ -275,9 +419,10 @@ impl ProjectData {
         tree: Tree,
         file_type: FileType,
         version: i32,
-        content: &str,
+        code: TextBuffer,
     ) {
         start_of_function("ProjectData", "update_document");
+        let content = code.as_str();
         // println!("---------------------------------");
         // println!("Before Update:");
         // println!("---------------------------------");
@@ -338,12 +483,13 @@ impl ProjectData {
             local_semantic_id,
         );
 
-        self.class_defs.remove(&old_class_name);
-        self.pub_method_defs.remove(&old_class_name);
-        self.classes.remove(&old_class_name);
+        self.class_defs.remove(old_class_name.as_str());
+        self.pub_method_defs.remove(old_class_name.as_str());
+        self.classes.remove(old_class_name.as_str());
 
-        self.classes.insert(class_name.clone(), class_id);
-        self.class_defs.insert(class_name.clone(), class_symbol_id);
+        let name = self.global_semantic_model.names.intern(&class_name);
+        self.classes.insert(name.clone(), class_id);
+        self.class_defs.insert(name, class_symbol_id);
 
         {
             for (_, class_map) in &mut self.pub_var_defs {
@@ -386,9 +532,12 @@ impl ProjectData {
             document.version = Some(version);
             document.file_type = file_type;
             document.tree = tree;
-            document.content = content.to_string();
+            document.content = code;
             document.class_name = class_name;
         }
+        // The symbols moved, so the index is rebuilt even if the dependency rebuild below skips
+        // this document.
+        self.rebuild_symbol_index(&url);
 
         // Recompute inheritance/override/calls/vars
         self.build_inheritance_and_variables(Some(url), Vec::new());
@@ -396,6 +545,65 @@ impl ProjectData {
         successful_exit("ProjectData", "update_document");
     }
 
+    /// Remove the document at `url` and everything derived from it.
+    ///
+    /// The class is unregistered from the name maps, its global symbols, public methods and
+    /// variables, local semantic model, and reference edges are dropped (see
+    /// `GlobalSemanticModel::remove_class`), and its slots are freed for the next added document.
+    /// Imports and inheritance are recomputed for the classes that extended or imported it, and
+    /// calls and variables for those classes only; the call sites of the other classes are
+    /// re-resolved, as they may have called it.
+    ///
+    /// Returns `false` if the document isn't tracked.
+    pub fn remove_document(&mut self, url: &Url) -> bool {
+        start_of_function("ProjectData", "remove_document");
+        let Some(document) = self.documents.remove(url) else {
+            generic_exit_statements("ProjectData", "remove_document");
+            return false;
+        };
+        self.export_line_maps.remove(url);
+        let class_symbol_id = document.scope_tree.class_def;
+        // the classes extending or importing the removed class, found while it can still be named
+        let mut dependents = None;
+        if let (Some(class_id), Some(local_semantic_model_id)) =
+            (document.class_id, document.local_semantic_model_id)
+        {
+            let mut class_dependents = self.dependent_classes(class_id);
+            class_dependents.retain(|&dependent| dependent != class_id);
+            dependents = Some(class_dependents);
+            self.global_semantic_model.remove_class(
+                class_id,
+                class_symbol_id,
+                local_semantic_model_id,
+            );
+            self.reference_index.remove_class(class_id);
+            if self.classes.get(document.class_name.as_str()) == Some(&class_id) {
+                self.classes.remove(document.class_name.as_str());
+            }
+        }
+        // another document may declare a class of the same name
+        if self.class_defs.get(document.class_name.as_str()) == Some(&class_symbol_id) {
+            self.class_defs.remove(document.class_name.as_str());
+            self.pub_method_defs.remove(document.class_name.as_str());
+            for class_map in self.pub_var_defs.values_mut() {
+                class_map.remove(&document.class_name);
+            }
+            self.pub_var_defs
+                .retain(|_, class_map| !class_map.is_empty());
+        }
+
+        match dependents {
+            Some(dependents) => {
+                self.recompute_dependent_links(dependents.iter().copied());
+                let classes_to_exclude = self.classes_other_than(&dependents);
+                self.rebuild_inheritance_and_variables(classes_to_exclude, Some(dependents));
+            }
+            None => self.build_inheritance_and_variables(None, Vec::new()),
+        }
+        successful_exit("ProjectData", "remove_document");
+        true
+    }
+
     /// Rebuild class + method header semantics for a document after a reparse.
     ///
     /// This reconstructs the `Class` for `class_id` from the given class definition `node`, then:
@@ -428,7 +636,41 @@ impl ProjectData {
         let mut lsm_methods = Vec::new();
         // Create a new class, will reassign the class at class_id to this new class.
         let mut class = Class::new(class_name.clone());
-        let methods = class.initial_build(node, content);
+        let ClassStatements {
+            methods,
+            properties: lsm_properties,
+            parameters: lsm_parameters,
+            xdata_blocks: lsm_xdata_blocks,
+            member_definitions: lsm_member_definitions,
+        } = class.initial_build(node, content);
+        for (index, property) in lsm_properties.iter().enumerate() {
+            let property_id = PropertyId(index);
+            if property.is_public {
+                class
+                    .public_properties
+                    .insert(property.name.clone(), property_id);
+            } else {
+                class
+                    .private_properties
+                    .insert(property.name.clone(), property_id);
+            }
+        }
+        for (index, parameter) in lsm_parameters.iter().enumerate() {
+            class
+                .parameters
+                .insert(parameter.name.clone(), ParameterId(index));
+        }
+        for (index, xdata) in lsm_xdata_blocks.iter().enumerate() {
+            class
+                .xdata_blocks
+                .insert(xdata.name.clone(), XDataId(index));
+        }
+        for (index, definition) in lsm_member_definitions.iter().enumerate() {
+            class.member_definitions.insert(
+                (definition.kind, definition.name.clone()),
+                MemberDefinitionId(index),
+            );
+        }
         self.global_semantic_model.update_class_symbol(
             class_name.clone(),
             node.range(),
@@ -456,10 +698,11 @@ impl ProjectData {
                     continue;
                 };
                 // add method symbol
+                let names = &self.global_semantic_model.names;
                 self.pub_method_defs
-                    .entry(class_name.clone())
+                    .entry(names.intern(&class_name))
                     .or_insert_with(HashMap::new)
-                    .insert(method_name.clone(), method_symbol_id);
+                    .insert(names.intern(&method_name), method_symbol_id);
                 // add methodId to class public methods field
                 class.public_methods.insert(method_name.clone(), method_id);
             } else {
@@ -479,12 +722,12 @@ impl ProjectData {
             }
         }
 
-        if let Some(slot) = self.global_semantic_model.classes.get_mut(class_id.0) {
+        if let Some(slot) = self.global_semantic_model.classes.get_mut(class_id) {
             *slot = class;
         } else {
             eprintln!(
-                "Warning: tried to assign class to classes vec in global semantic model, but index {:?} is out of bounds (len={})",
-                class_id.0,
+                "Warning: tried to assign class to classes arena in global semantic model, but class {:?} was removed (len={})",
+                class_id,
                 self.global_semantic_model.classes.len()
             );
             generic_exit_statements("ProjectData", "rebuild_semantics");
@@ -505,6 +748,18 @@ impl ProjectData {
         for method in lsm_methods {
             local_semantic_model.new_method(method);
         }
+        for property in lsm_properties {
+            local_semantic_model.new_property(property);
+        }
+        for parameter in lsm_parameters {
+            local_semantic_model.new_parameter(parameter);
+        }
+        for xdata in lsm_xdata_blocks {
+            local_semantic_model.new_xdata(xdata);
+        }
+        for definition in lsm_member_definitions {
+            local_semantic_model.new_member_definition(definition);
+        }
         local_semantic_model.active = true;
         let Some(doc) = self.get_document_mut(&url) else {
             generic_exit_statements("ProjectData", "rebuild_semantics");
@@ -516,17 +771,23 @@ impl ProjectData {
         successful_exit("ProjectData", "rebuild_semantics");
     }
 
-    /// Compute imports, inheritance, override resolution, call sites, and variable symbols.
+    /// Compute imports, include files, inheritance, override resolution, call sites, and variable
+    /// symbols.
     ///
-    /// If `only` is provided, only that document is scanned for import/extends changes; the
-    /// inheritance/override index is still rebuilt globally, and method calls/variables are
-    /// recomputed for all classes in the semantic model.
+    /// If `only` is provided, only that document is scanned for import/include/extends changes,
+    /// and the classes depending on it (see `dependent_classes`) for extends/import changes.
+    /// Keyword inheritance, method calls, and variables are recomputed for those classes only;
+    /// the override index is still rebuilt globally, and the call sites of the other classes are
+    /// re-resolved against it.
     pub fn build_inheritance_and_variables(&mut self, only: Option<Url>, exclude: Vec<Url>) {
         start_of_function("ProjectData", "build_inheritance_and_variables");
-        let mut indices_to_exclude = Vec::new();
+        let mut classes_to_exclude = Vec::new();
+        // the classes whose keyword inheritance is recomputed, all of them if `None`
+        let mut rebuilt_classes = None;
         // Which documents should update imports/extends?
         if let Some(url) = only {
             self.recompute_imports_for_url(&url);
+            self.recompute_includes_for_url(&url);
             self.recompute_extends_for_url(&url);
             if exclude.contains(&url) {
                 eprintln!("Error: Url specified as only one to change is also included in the exclude field.");
@@ -546,9 +807,11 @@ impl ProjectData {
                 generic_exit_statements("ProjectData", "build_inheritance_and_variables");
                 return;
             };
-            indices_to_exclude = (0..self.global_semantic_model.classes.len())
-                .filter(|&i| i != index.0)
-                .collect();
+            let dependents = self.dependent_classes(index);
+            // a superclass or imported class may have been renamed, added, or removed
+            self.recompute_dependent_links(dependents.iter().copied().filter(|&id| id != index));
+            classes_to_exclude = self.classes_other_than(&dependents);
+            rebuilt_classes = Some(dependents);
         } else {
             let urls: Vec<Url> = self
                 .documents
@@ -559,6 +822,7 @@ impl ProjectData {
                 .collect();
             for url in &urls {
                 self.recompute_imports_for_url(url);
+                self.recompute_includes_for_url(url);
                 self.recompute_extends_for_url(url);
             }
             for url in &exclude {
@@ -581,26 +845,92 @@ impl ProjectData {
                     continue;
                 };
 
-                indices_to_exclude.push(index.0);
+                classes_to_exclude.push(index);
             }
         }
+        self.rebuild_inheritance_and_variables(classes_to_exclude, rebuilt_classes);
+        successful_exit("ProjectData", "build_inheritance_and_variables");
+    }
+
+    /// Recomputes the imports and superclasses of the classes `dependents`, which extend or import
+    /// a class that changed or was removed.
+    fn recompute_dependent_links(&mut self, dependents: impl Iterator<Item = ClassId>) {
+        let dependent_urls: Vec<Url> = dependents
+            .filter_map(|dependent| self.global_semantic_model.classes.get(dependent))
+            .filter_map(|class| self.get_class_url(&class.name))
+            .cloned()
+            .collect();
+        for dependent_url in &dependent_urls {
+            self.recompute_imports_for_url(dependent_url);
+            self.recompute_extends_for_url(dependent_url);
+        }
+    }
+
+    /// Returns the ids of the classes that aren't in `class_ids`.
+    fn classes_other_than(&self, class_ids: &[ClassId]) -> Vec<ClassId> {
+        let classes = self.global_semantic_model.classes.ids().into_iter();
+        classes
+            .filter(|class_id| !class_ids.contains(class_id))
+            .collect()
+    }
 
+    /// Recomputes keyword inheritance, the override index, call sites, and variable symbols once
+    /// imports and superclasses are up to date (see `build_inheritance_and_variables`).
+    ///
+    /// Call sites and variables of the classes `classes_to_exclude` are kept, their call sites
+    /// only re-resolved against the new override index. Keyword inheritance is recomputed for
+    /// `rebuilt_classes` only, or for every class if `None`.
+    fn rebuild_inheritance_and_variables(
+        &mut self,
+        classes_to_exclude: Vec<ClassId>,
+        rebuilt_classes: Option<Vec<ClassId>>,
+    ) {
+        start_of_function("ProjectData", "rebuild_inheritance_and_variables");
         // Recompute inheritance + override index
-        self.global_semantic_model.class_keyword_inheritance();
-        let idx = self.global_semantic_model.build_override_index();
+        self.global_semantic_model
+            .class_keyword_inheritance(rebuilt_classes.as_deref());
+        let local_models: HashMap<ClassId, LocalSemanticModelId> = self
+            .documents
+            .values()
+            .filter_map(|document| Some((document.class_id?, document.local_semantic_model_id?)))
+            .collect();
+        let properties: HashMap<ClassId, Vec<String>> = self
+            .documents
+            .values()
+            .filter_map(|document| {
+                let names = class_members(document.content.as_str(), &document.tree)
+                    .into_iter()
+                    .filter(|member| member.kind == MemberKind::Property)
+                    .map(|member| member.name)
+                    .collect();
+                Some((document.class_id?, names))
+            })
+            .collect();
+        let idx = self
+            .global_semantic_model
+            .build_override_index(&local_models, &properties);
         self.override_index = idx.clone();
+        // the replaced index may have held the last references to removed names
+        self.global_semantic_model.names.purge();
 
-        // need to calculate which classes to actually rebuild semantics for
+        let class_ids = self.global_semantic_model.classes.ids();
+        let classes_map = self.classes.clone();
 
-        // TODO: update this
-        for c in &mut self.global_semantic_model.classes {
-            c.method_calls.clear();
+        // Classes that are rebuilt get fresh call sites below. Excluded classes keep their call
+        // sites, but their callees may have been re-indexed, so re-resolve them against the new
+        // override index and refresh their reference edges if anything moved.
+        for &i in &class_ids {
+            let class = &mut self.global_semantic_model.classes[i];
+            if classes_to_exclude.contains(&i) {
+                if resolve_method_call_sites(&classes_map, &idx, &mut class.method_calls) {
+                    self.reference_index.update_class(i, &class.method_calls);
+                }
+            } else {
+                class.method_calls.clear();
+            }
         }
 
-        let class_len = self.global_semantic_model.classes.len();
-        let classes_map = self.classes.clone();
-
-        for i in 0..class_len {
+        for &i in &class_ids {
             let (
                 class_name,
                 public_method_ids,
@@ -640,7 +970,7 @@ impl ProjectData {
                 )
             };
 
-            if indices_to_exclude.contains(&i) {
+            if classes_to_exclude.contains(&i) {
                 generic_skipping_statements(
                     "build_inheritance_and_variables",
                     class_name.as_str(),
@@ -649,7 +979,7 @@ impl ProjectData {
                 continue;
             }
 
-            let class_symbol_id = match self.class_defs.get(&class_name).copied() {
+            let class_symbol_id = match self.class_defs.get(class_name.as_str()).copied() {
                 Some(id) => id,
                 None => {
                     eprintln!(
@@ -664,7 +994,7 @@ impl ProjectData {
                     continue;
                 }
             };
-            let class_id = match self.classes.get(&class_name).copied() {
+            let class_id = match self.classes.get(class_name.as_str()).copied() {
                 Some(id) => id,
                 None => {
                     eprintln!(
@@ -683,7 +1013,7 @@ impl ProjectData {
             let url = {
                 let Some(class_global_symbol) = self
                     .global_semantic_model
-                    .get_class_symbol(class_symbol_id.0, class_name.as_str())
+                    .get_class_symbol(class_symbol_id, class_name.as_str())
                 else {
                     generic_skipping_statements(
                         "build_inheritance_and_variables",
@@ -748,7 +1078,7 @@ impl ProjectData {
                     method.update_keywords(is_procedure_block, default_language.clone());
                 }
 
-                let (method_name, loc) = {
+                let (method_name, loc, is_python) = {
                     let Some(method) = self.global_semantic_model.get_method(
                         class_id,
                         class_name.as_str(),
@@ -762,6 +1092,7 @@ impl ProjectData {
                         continue;
                     };
                     let method_name = method.name.clone();
+                    let is_python = method.is_python();
                     let Some(sym) = self.get_public_method_symbol(
                         class_name.as_str(),
                         method_name.as_str(),
@@ -775,7 +1106,7 @@ impl ProjectData {
                         continue;
                     };
 
-                    (method_name, sym.location)
+                    (method_name, sym.location, is_python)
                 };
 
                 let method_name = method_name.as_str();
@@ -800,8 +1131,12 @@ impl ProjectData {
                     continue;
                 };
 
-                // method Calls
-                let calls = build_method_calls(&class_name, method_definition_node, content);
+                // method Calls, except in Python bodies
+                let calls = if is_python {
+                    Vec::new()
+                } else {
+                    build_method_calls(&class_name, method_definition_node, content)
+                };
 
                 let new_sites: Vec<MethodCallSite> = build_method_calls_from_unresolved(
                     classes_map.clone(),
@@ -979,7 +1314,7 @@ impl ProjectData {
                     // inherit class keywords if not explicitly assigned
                     method.update_keywords(is_procedure_block, default_language.clone());
                 }
-                let (method_name, loc) = {
+                let (method_name, loc, is_python) = {
                     let Some(lsm) = self
                         .global_semantic_model
                         .get_local_semantic(local_semantic_id)
@@ -1010,7 +1345,7 @@ impl ProjectData {
                         continue;
                     };
 
-                    (m.name.clone(), sym.location)
+                    (m.name.clone(), sym.location, m.is_python())
                 };
                 eprintln!(
                     "Info: Building inheritance for variables in private method {:?}",
@@ -1032,7 +1367,11 @@ impl ProjectData {
                     continue;
                 };
 
-                let calls = build_method_calls(&class_name, method_definition_node, content);
+                let calls = if is_python {
+                    Vec::new()
+                } else {
+                    build_method_calls(&class_name, method_definition_node, content)
+                };
 
                 let new_sites: Vec<MethodCallSite> = build_method_calls_from_unresolved(
                     classes_map.clone(),
@@ -1206,7 +1545,279 @@ impl ProjectData {
                 }
             }
         }
-        successful_exit("ProjectData", "build_inheritance_and_variables");
+
+        // Refresh reference edges only for the classes whose call sites were rebuilt above.
+        for &i in class_ids.iter().filter(|i| !classes_to_exclude.contains(i)) {
+            self.reference_index
+                .update_class(i, &self.global_semantic_model.classes[i].method_calls);
+        }
+
+        // Symbols of rebuilt documents moved, so refresh their location indexes.
+        let rebuilt_urls: Vec<Url> = self
+            .documents
+            .iter()
+            .filter(|(_, doc)| {
+                doc.class_id
+                    .is_some_and(|class_id| !classes_to_exclude.contains(&class_id))
+            })
+            .map(|(url, _)| url.clone())
+            .collect();
+        for url in rebuilt_urls {
+            self.link_host_variables(&url);
+            self.rebuild_symbol_index(&url);
+        }
+        successful_exit("ProjectData", "rebuild_inheritance_and_variables");
+    }
+
+    /// Adds the private host variables of the embedded SQL in the document at `url` to its
+    /// `ScopeTree`.
+    ///
+    /// A host variable referring to a variable defined in its scope becomes a reference to it, so
+    /// it is highlighted and renamed together with the ObjectScript variable. An `INTO` host
+    /// variable without a definition defines the variable.
+    fn link_host_variables(&mut self, url: &Url) {
+        start_of_function("ProjectData", "link_host_variables");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "link_host_variables");
+            return;
+        };
+        let class_procedure_block = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id))
+            .and_then(|class| class.is_procedure_block)
+            .unwrap_or(true);
+        let host_variables = private_host_variables(
+            document.content.as_str(),
+            &document.tree,
+            class_procedure_block,
+        );
+        let Some(document) = self.get_document_mut(url) else {
+            generic_exit_statements("ProjectData", "link_host_variables");
+            return;
+        };
+        for host_variable in host_variables {
+            if document
+                .scope_tree
+                .add_variable_reference(host_variable.name.as_str(), host_variable.range)
+                || !host_variable.is_write
+            {
+                continue;
+            }
+            document.scope_tree.new_variable_symbol(
+                host_variable.name,
+                host_variable.range,
+                Vec::new(),
+                Vec::new(),
+            );
+        }
+        successful_exit("ProjectData", "link_host_variables");
+    }
+
+    /// Rebuilds the `SymbolLocationIndex` of the document at `url`.
+    ///
+    /// The index covers the document's global symbols (class, public methods, public variables),
+    /// the private method and variable symbols in its `ScopeTree`, and the class' method calls.
+    /// It is rebuilt whenever the document is added or updated, and again after the dependency
+    /// rebuild links host variables and call sites.
+    fn rebuild_symbol_index(&mut self, url: &Url) {
+        start_of_function("ProjectData", "rebuild_symbol_index");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "rebuild_symbol_index");
+            return;
+        };
+        let mut entries = document.scope_tree.symbol_entries();
+        for &symbol_id in self.global_semantic_model.get_document_symbol_ids(url) {
+            let Some(range) = self.global_semantic_model.get_global_symbol_range(symbol_id) else {
+                continue;
+            };
+            entries.push(SymbolEntry {
+                range,
+                symbol: SymbolRef::Global(symbol_id),
+            });
+        }
+        if let Some(class) = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id))
+        {
+            for (i, call_site) in class.method_calls.iter().enumerate() {
+                entries.push(SymbolEntry {
+                    range: call_site.call_range,
+                    symbol: SymbolRef::MethodCall(i),
+                });
+            }
+        }
+        let Some(document) = self.get_document_mut(url) else {
+            generic_exit_statements("ProjectData", "rebuild_symbol_index");
+            return;
+        };
+        document.symbol_index = SymbolLocationIndex::from_entries(entries);
+        successful_exit("ProjectData", "rebuild_symbol_index");
+    }
+
+    /// Resolve a `SymbolRef` found in the document at `url` to its definition location.
+    ///
+    /// Definitions resolve to themselves; private variable references resolve to their scope's
+    /// definition; method calls resolve to the callee's public method symbol.
+    pub fn get_symbol_definition(&self, url: &Url, symbol: &SymbolRef) -> Option<(Url, Range)> {
+        start_of_function("ProjectData", "get_symbol_definition");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_symbol_definition");
+            return None;
+        };
+        let result = match symbol {
+            SymbolRef::Global(symbol_id) => self
+                .global_semantic_model
+                .get_global_symbol_range(*symbol_id)
+                .map(|range| (url.clone(), range)),
+            SymbolRef::PrivateMethod(name) => document
+                .scope_tree
+                .get_private_method_symbol(name)
+                .map(|sym| (url.clone(), sym.location)),
+            SymbolRef::PrivateVariable(scope_id, var_id)
+            | SymbolRef::PrivateVariableReference(scope_id, var_id) => document
+                .scope_tree
+                .scopes
+                .get(scope_id)
+                .and_then(|scope| scope.variable_symbols.get(var_id.0))
+                .map(|sym| (url.clone(), sym.location)),
+            SymbolRef::MethodCall(i) => {
+                let callee = document
+                    .class_id
+                    .and_then(|class_id| self.global_semantic_model.classes.get(class_id))
+                    .and_then(|class| class.method_calls.get(*i))
+                    .and_then(|call_site| call_site.callee_symbol);
+                callee.and_then(|callee| {
+                    let class = self.global_semantic_model.classes.get(callee.class)?;
+                    let method = self.global_semantic_model.get_method(
+                        callee.class,
+                        class.name.as_str(),
+                        callee.id.0,
+                    )?;
+                    let &class_symbol_id = self.class_defs.get(class.name.as_str())?;
+                    let sym = self.get_public_method_symbol(
+                        class.name.as_str(),
+                        method.name.as_str(),
+                        class_symbol_id,
+                    )?;
+                    Some((sym.url.clone(), sym.location))
+                })
+            }
+        };
+        if result.is_none() {
+            generic_exit_statements("ProjectData", "get_symbol_definition");
+            return None;
+        }
+        successful_exit("ProjectData", "get_symbol_definition");
+        result
+    }
+
+    /// Return the innermost symbol or reference located at `point` in the document at `url`.
+    ///
+    /// This is served from the document's `SymbolLocationIndex` and does not walk the syntax tree.
+    pub fn get_symbol_at(&self, url: &Url, point: Point) -> Option<SymbolRef> {
+        let document = self.get_document(url)?;
+        let byte = point_to_byte(document.content.as_str(), point);
+        document
+            .symbol_index
+            .symbol_at(byte)
+            .map(|entry| entry.symbol.clone())
+    }
+
+    /// Return the definitions and references of the private variable at `point` in the document
+    /// at `url`, as `(definitions, references)`.
+    ///
+    /// Every definition of the name in the variable's scope is included (each `set` defines the
+    /// variable anew), along with their references, such as embedded SQL host variables.
+    pub fn get_private_variable_occurrences(
+        &self,
+        url: &Url,
+        point: Point,
+    ) -> Option<(Vec<Range>, Vec<Range>)> {
+        start_of_function("ProjectData", "get_private_variable_occurrences");
+        let (SymbolRef::PrivateVariable(scope_id, var_id)
+        | SymbolRef::PrivateVariableReference(scope_id, var_id)) =
+            self.get_symbol_at(url, point)?
+        else {
+            generic_exit_statements("ProjectData", "get_private_variable_occurrences");
+            return None;
+        };
+        let document = self.get_document(url)?;
+        let scope = document.scope_tree.scopes.get(&scope_id)?;
+        let name = scope.variable_symbols.get(var_id.0)?.name.clone();
+        let mut definitions = Vec::new();
+        let mut references = Vec::new();
+        for variable_symbol in scope.variable_symbols.iter().filter(|sym| sym.name == name) {
+            definitions.push(variable_symbol.location);
+            references.extend(variable_symbol.references.iter().copied());
+        }
+        successful_exit("ProjectData", "get_private_variable_occurrences");
+        Some((definitions, references))
+    }
+
+    /// Return the name of the private variable at `point` in the document at `url`, and the names
+    /// of every variable defined in its scope (its own included), which a rename of the variable
+    /// must not take.
+    pub fn get_private_variable_names(
+        &self,
+        url: &Url,
+        point: Point,
+    ) -> Option<(String, Vec<String>)> {
+        start_of_function("ProjectData", "get_private_variable_names");
+        let (SymbolRef::PrivateVariable(scope_id, var_id)
+        | SymbolRef::PrivateVariableReference(scope_id, var_id)) =
+            self.get_symbol_at(url, point)?
+        else {
+            generic_exit_statements("ProjectData", "get_private_variable_names");
+            return None;
+        };
+        let scope = self.get_document(url)?.scope_tree.scopes.get(&scope_id)?;
+        let name = scope.variable_symbols.get(var_id.0)?.name.clone();
+        let mut names: Vec<String> = scope
+            .variable_symbols
+            .iter()
+            .map(|symbol| symbol.name.clone())
+            .chain(scope.public_var_defs.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        successful_exit("ProjectData", "get_private_variable_names");
+        Some((name, names))
+    }
+
+    /// Returns every occurrence of the local variable at `point` in its method in the document at
+    /// `url` (see `variable_occurrences`), in document order, each flagged `true` if it writes the
+    /// variable.
+    ///
+    /// Embedded SQL host variables linked to the variable (see `link_host_variables`) are
+    /// included too, as reads, or as writes for those defining it.
+    pub fn get_variable_highlights(&self, url: &Url, point: Point) -> Vec<(Range, bool)> {
+        start_of_function("ProjectData", "get_variable_highlights");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_variable_highlights");
+            return Vec::new();
+        };
+        let mut highlights: Vec<(Range, bool)> =
+            variable_occurrences(document.content.as_str(), &document.tree, point)
+                .into_iter()
+                .map(|occurrence| (occurrence.range, occurrence.is_write))
+                .collect();
+        if let Some((definitions, references)) = self.get_private_variable_occurrences(url, point) {
+            let linked = definitions
+                .into_iter()
+                .map(|range| (range, true))
+                .chain(references.into_iter().map(|range| (range, false)));
+            for (range, is_write) in linked {
+                if !highlights
+                    .iter()
+                    .any(|(other, _)| other.start_byte == range.start_byte)
+                {
+                    highlights.push((range, is_write));
+                }
+            }
+        }
+        highlights.sort_by_key(|(range, _)| range.start_byte);
+        successful_exit("ProjectData", "get_variable_highlights");
+        highlights
     }
 
     /// Recomputes the import list for the class defined in `url`.
@@ -1228,7 +1839,7 @@ impl ProjectData {
             }
         };
 
-        let class_id = match self.classes.get(&class_name).copied() {
+        let class_id = match self.classes.get(class_name.as_str()).copied() {
             Some(id) => id,
             None => {
                 eprintln!("Failed to get class id for class named {:?}", class_name);
@@ -1247,7 +1858,7 @@ impl ProjectData {
                     for imported_class in get_node_children(include_clause) {
                         let imported_name =
                             content.as_str()[imported_class.byte_range()].to_string();
-                        if let Some(id) = self.classes.get(&imported_name).copied() {
+                        if let Some(id) = self.classes.get(imported_name.as_str()).copied() {
                             imports.push(id);
                         }
                     }
@@ -1255,12 +1866,81 @@ impl ProjectData {
             }
         }
 
-        if let Some(class) = self.global_semantic_model.classes.get_mut(class_id.0) {
+        if let Some(class) = self.global_semantic_model.classes.get_mut(class_id) {
             class.imports = imports;
         }
         successful_exit("ProjectData", "recompute_imports_for_url");
     }
 
+    /// Recomputes the `Include` and `IncludeGenerator` lists for the class defined in `url`.
+    ///
+    /// Names are stored as written; they are resolved to the workspace's include files when
+    /// the include chain is followed (see `get_include_chain`), so files added later are found.
+    fn recompute_includes_for_url(&mut self, url: &Url) {
+        start_of_function("ProjectData", "recompute_includes_for_url");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "recompute_includes_for_url");
+            return;
+        };
+        let content = document.content.as_str();
+        let names = |found: Vec<(String, Range)>| -> Vec<String> {
+            found.into_iter().map(|(name, _)| name).collect()
+        };
+        let includes = names(include_names(content, &document.tree));
+        let include_generators = names(include_generator_names(content, &document.tree));
+        let Some(class) = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get_mut(class_id))
+        else {
+            eprintln!("Failed to get class for document {:?}", url.path());
+            generic_exit_statements("ProjectData", "recompute_includes_for_url");
+            return;
+        };
+        class.includes = includes;
+        class.include_generators = include_generators;
+        successful_exit("ProjectData", "recompute_includes_for_url");
+    }
+
+    /// Returns the class `class_id` and the classes depending on it, transitively, in id order:
+    /// the classes naming it in their `Extends` list and the classes importing it, whose
+    /// inherited keywords, overrides, and call resolution change along with it.
+    ///
+    /// The superclasses resolved by the last build count too, so a class renamed away from the
+    /// name its subclasses extend still brings them along to drop the stale link.
+    ///
+    /// Classes using an include file don't depend on it here: macros are looked up through the
+    /// include chain when needed (see `get_include_chain`), so nothing is rebuilt for them.
+    pub(crate) fn dependent_classes(&self, class_id: ClassId) -> Vec<ClassId> {
+        // reverse edges: class -> the classes extending or importing it
+        let mut edges: HashMap<ClassId, Vec<ClassId>> = HashMap::new();
+        let classes = self.global_semantic_model.classes.iter();
+        for (i, class) in classes.filter(|(_, class)| class.active) {
+            let superclasses = class
+                .superclass_names
+                .iter()
+                .filter_map(|name| self.get_class_id(name))
+                .chain(class.inherited_classes.iter().copied());
+            for dependency in superclasses.chain(class.imports.iter().copied()) {
+                let dependents = edges.entry(dependency).or_default();
+                if !dependents.contains(&i) {
+                    dependents.push(i);
+                }
+            }
+        }
+        let mut dependents = vec![class_id];
+        let mut pending = vec![class_id];
+        while let Some(class_id) = pending.pop() {
+            for &dependent in edges.get(&class_id).into_iter().flatten() {
+                if !dependents.contains(&dependent) {
+                    dependents.push(dependent);
+                    pending.push(dependent);
+                }
+            }
+        }
+        dependents.sort_by_key(|class_id| class_id.0);
+        dependents
+    }
+
     /// Recompute direct `extends` (inheritance) dependencies for the class defined in `url`.
     ///
     /// Parses the class definition's `class_extends` entries and updates `class.inherited_classes`
@@ -1276,7 +1956,7 @@ impl ProjectData {
             }
         };
 
-        let class_id = match self.classes.get(&class_name).copied() {
+        let class_id = match self.classes.get(class_name.as_str()).copied() {
             Some(id) => id,
             None => {
                 eprintln!("Failed to get class id for class named {:?}", class_name);
@@ -1301,7 +1981,7 @@ impl ProjectData {
                     let inherited_nodes = get_node_children(*node);
                     for inh in inherited_nodes[1..].iter() {
                         let name = content.as_str()[inh.byte_range()].to_string();
-                        if let Some(id) = self.classes.get(&name).copied() {
+                        if let Some(id) = self.get_class_id(&name) {
                             inherited.push(id);
                         }
                     }
@@ -1309,7 +1989,7 @@ impl ProjectData {
             }
         }
 
-        if let Some(class) = self.global_semantic_model.classes.get_mut(class_id.0) {
+        if let Some(class) = self.global_semantic_model.classes.get_mut(class_id) {
             class.inherited_classes = inherited;
         }
         successful_exit("ProjectData", "recompute_extends_for_url");
@@ -1362,7 +2042,7 @@ impl ProjectData {
 
         let Some(sym) =
             self.global_semantic_model
-                .get_method_symbol(class_symbol_id, class_name, sym_id.0)
+                .get_method_symbol(class_symbol_id, class_name, sym_id)
         else {
             return None;
         };
@@ -1401,7 +2081,7 @@ impl ProjectData {
 
         let Some(var_symbol) = self.global_semantic_model.get_variable_symbol(
             &class_symbol_id,
-            var_symbol_id.0,
+            var_symbol_id,
             class_name,
         ) else {
             generic_exit_statements("ProjectData", "get_pub_var_symbol_from_current_scope");
@@ -1441,7 +2121,7 @@ impl ProjectData {
             return locations;
         };
         for (class_name, symbols_defs) in symbol_defs_by_class {
-            let Some(class_symbol_id) = self.class_defs.get(class_name) else {
+            let Some(class_symbol_id) = self.class_defs.get(class_name.as_str()) else {
                 eprintln!(
                     "Couldn't find class symbol id in class defs for class named {:?}",
                     class_name
@@ -1456,7 +2136,7 @@ impl ProjectData {
             for def in symbols_defs {
                 let Some(symbol) = self.global_semantic_model.get_variable_symbol(
                     class_symbol_id,
-                    def.0,
+                    *def,
                     class_name,
                 ) else {
                     generic_skipping_statements(
@@ -1504,7 +2184,7 @@ impl ProjectData {
             }
         };
 
-        let Some(class) = self.global_semantic_model.get_class(class_id.0) else {
+        let Some(class) = self.global_semantic_model.get_class(class_id) else {
             generic_exit_statements("ProjectData", "get_variable_symbol_location");
             return locations;
         };
@@ -1579,7 +2259,7 @@ impl ProjectData {
             // variable is private
             let Some(range) = document
                 .scope_tree
-                .get_variable_definition(point, symbol_name.as_str())
+                .find_declaration(point, symbol_name.as_str())
             else {
                 generic_exit_statements("ProjectData", "get_variable_symbol_location");
                 return locations;
@@ -1596,8 +2276,9 @@ impl ProjectData {
 
     /// Return locations of methods that override a given public method.
     ///
-    /// Looks up the current document's class, confirms `method_name` is a public method, then uses
-    /// `override_index.overridden_by` to find overriding methods (public or private) in subclasses.
+    /// Looks up the current document's class and its method `method_name` (public or private), then
+    /// uses `override_index.overridden_by` to find overriding methods (public or private) in
+    /// subclasses.
     ///
     /// Each returned `(Url, Range)` points to the overriding method's definition location.
     pub fn get_method_overrides(&self, url: Url, method_name: String) -> Vec<(Url, Range)> {
@@ -1635,7 +2316,7 @@ impl ProjectData {
             }
         };
 
-        let Some(class) = self.global_semantic_model.get_class(class_id.0) else {
+        let Some(class) = self.global_semantic_model.get_class(class_id) else {
             eprintln!("Aborting function early");
             print_statements_exit_method_overrides_fn(
                 method_name_str,
@@ -1645,8 +2326,12 @@ impl ProjectData {
             return locations;
         };
 
-        let Some(&public_method_id) = class.get_public_method_id(method_name.as_str()) else {
-            eprintln!("Aborting function early, method either DNE or is not public");
+        let Some(method_ref) = MethodHandle::from_method_ref(MethodRef {
+            class: class_id,
+            pub_id: class.get_public_method_id(method_name_str).copied(),
+            priv_id: class.private_methods.get(method_name_str).copied(),
+        }) else {
+            eprintln!("Aborting function early, method DNE");
             print_statements_exit_method_overrides_fn(
                 method_name_str,
                 class.name.as_str(),
@@ -1655,11 +2340,6 @@ impl ProjectData {
             return locations;
         };
 
-        let method_ref = PublicMethodRef {
-            class: class_id,
-            id: public_method_id,
-        };
-
         // ---- overridden-by list ----
         let overrides = match self.override_index.overridden_by.get(&method_ref) {
             Some(v) => v,
@@ -1674,9 +2354,7 @@ impl ProjectData {
         };
 
         for override_method_ref in overrides {
-            let Some(class) = self
-                .global_semantic_model
-                .get_class(override_method_ref.class.0)
+            let Some(location) = self.get_method_ref_location(override_method_ref, method_name_str)
             else {
                 generic_skipping_statements(
                     "get_method_overrides",
@@ -1685,84 +2363,3891 @@ impl ProjectData {
                 );
                 continue;
             };
+            locations.push(location);
+        }
+        successful_exit("ProjectData", "get_method_overrides");
+        locations
+    }
 
-            let cls_name = &class.name;
+    /// Returns the definition location of the method `method_name` referenced by `method_ref`,
+    /// public or private.
+    fn get_method_ref_location(
+        &self,
+        method_ref: &MethodRef,
+        method_name: &str,
+    ) -> Option<(Url, Range)> {
+        let class = self.global_semantic_model.get_class(method_ref.class)?;
+        let cls_name = &class.name;
+        let class_symbol_id = self.class_defs.get(cls_name.as_str()).copied()?;
+        if method_ref.pub_id.is_some() {
+            let sym =
+                self.get_public_method_symbol(cls_name.as_str(), method_name, class_symbol_id)?;
+            return Some((sym.url.clone(), sym.location));
+        }
+        // private methods are resolved through the scope tree of their class document
+        let cls_symbol = self
+            .global_semantic_model
+            .get_class_symbol(class_symbol_id, cls_name.as_str())?;
+        let cls_url = &cls_symbol.url;
+        let sym = self
+            .get_document(cls_url)?
+            .scope_tree
+            .get_private_method_symbol(method_name)?;
+        Some((cls_url.clone(), sym.location))
+    }
 
-            let overriding_subclass_class_symbol_id = match self.class_defs.get(cls_name).copied() {
-                Some(id) => id,
-                None => {
-                    generic_skipping_statements(
-                        "get_method_overrides",
-                        method_name_str,
-                        "Override method ref of the method named",
-                    );
-                    continue;
+    /// Resolve the method called at `point` in the document at `url`, when `point` is on the
+    /// method name of a `##class(Pkg.Cls).Method()` call (`class_method_call`) or of a
+    /// `..Method()` call (`relative_dot_method`, e.g. in an `oref_chain_expr`).
+    ///
+    /// The method is looked up among the methods the class declares (`pub_method_defs`, then the
+    /// private methods of the class document's `ScopeTree`), then among the methods it inherits
+    /// (`OverrideIndex::effective_methods`). Calls on other object references are left to the
+    /// heuristic lookup, since their class usually can't be inferred.
+    ///
+    /// Returns the method, its name, and the range of the name at the call site.
+    fn resolve_method_call(&self, url: &Url, point: Point) -> Option<(MethodRef, String, Range)> {
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let node = document
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)?;
+        let mut call = None;
+        let mut current = node;
+        // the method name is an identifier (possibly wrapped) directly below the call node
+        for _ in 0..4 {
+            match current.kind() {
+                "class_method_call" => {
+                    let class_name = current.named_child(0)?.named_child(1)?;
+                    let class_name = content.get(class_name.byte_range())?.to_string();
+                    call = Some((class_name, current.named_child(1)?));
+                    break;
                 }
-            };
+                "oref_method" if current.parent()?.kind() == "relative_dot_method" => {
+                    call = Some((document.class_name.clone(), current.named_child(0)?));
+                    break;
+                }
+                _ => current = current.parent()?,
+            }
+        }
+        let (class_name, name_node) = call.filter(|(_, name_node)| {
+            name_node.start_byte() <= node.start_byte() && node.end_byte() <= name_node.end_byte()
+        })?;
+        let method_name = content.get(name_node.byte_range())?;
+        let class_id = *self.classes.get(class_name.as_str())?;
+        let class = self.global_semantic_model.get_class(class_id)?;
+        let declared = MethodRef {
+            class: class_id,
+            pub_id: class.public_methods.get(method_name).copied(),
+            priv_id: class.private_methods.get(method_name).copied(),
+        };
+        let method_ref = if declared.pub_id.is_some() || declared.priv_id.is_some() {
+            declared
+        } else {
+            self.override_index
+                .effective_methods
+                .get(&class_id)?
+                .get(method_name)?
+                .to_method_ref()
+        };
+        Some((method_ref, method_name.to_string(), name_node.range()))
+    }
 
-            if let Some(_) = override_method_ref.pub_id {
-                let Some(sym) = self.get_public_method_symbol(
-                    cls_name.as_str(),
-                    method_name.as_str(),
-                    overriding_subclass_class_symbol_id,
-                ) else {
-                    generic_skipping_statements(
-                        "get_method_overrides",
-                        method_name_str,
-                        "Override method ref of the method named",
-                    );
-                    continue;
-                };
-                locations.push((sym.url.clone(), sym.location));
-            } else {
-                // method that is overriding is private.
-                let Some(overriding_cls_symbol) = self
-                    .global_semantic_model
-                    .get_class_symbol(overriding_subclass_class_symbol_id.0, cls_name.as_str())
-                else {
-                    generic_skipping_statements(
-                        "get_method_overrides",
-                        method_name_str,
-                        "Override method ref of the method named",
-                    );
-                    continue;
-                };
-                let cls_url = &overriding_cls_symbol.url;
+    /// Return the declaration of the method called at `point` in the document at `url`; see
+    /// `resolve_method_call` for the calls resolved.
+    pub fn get_method_call_definition(&self, url: &Url, point: Point) -> Option<(Url, Range)> {
+        start_of_function("ProjectData", "get_method_call_definition");
+        let Some((method_ref, method_name, _)) = self.resolve_method_call(url, point) else {
+            generic_exit_statements("ProjectData", "get_method_call_definition");
+            return None;
+        };
+        let location = self.get_method_ref_location(&method_ref, &method_name);
+        successful_exit("ProjectData", "get_method_call_definition");
+        location
+    }
 
-                let doc = match self.get_document(cls_url) {
-                    Some(d) => d,
-                    None => continue,
-                };
+    /// Returns the `Method` `method_ref` points to. Private methods are stored in the local
+    /// semantic model of their class document.
+    fn get_method_by_ref(&self, method_ref: &MethodRef) -> Option<&Method> {
+        let class = self.global_semantic_model.get_class(method_ref.class)?;
+        if let Some(id) = method_ref.pub_id {
+            return self.global_semantic_model.get_method(
+                method_ref.class,
+                class.name.as_str(),
+                id.0,
+            );
+        }
+        let class_symbol_id = self.class_defs.get(class.name.as_str()).copied()?;
+        let cls_symbol = self
+            .global_semantic_model
+            .get_class_symbol(class_symbol_id, class.name.as_str())?;
+        let local_semantic_model_id = self
+            .get_document(&cls_symbol.url)?
+            .local_semantic_model_id?;
+        self.global_semantic_model
+            .get_local_semantic(local_semantic_model_id)?
+            .get_method(method_ref.priv_id?.0)
+    }
 
-                let Some(sym) = doc
-                    .scope_tree
-                    .get_private_method_symbol(method_name.as_str())
-                else {
-                    generic_skipping_statements(
-                        "get_method_overrides",
-                        method_name_str,
-                        "Override method ref of the method named",
-                    );
-                    continue;
+    /// Resolve the method named at `point` in the document at `url`, either in its declaration
+    /// or at a call site (see `resolve_method_call`), returning its name and the name's range.
+    fn resolve_method_at(&self, url: &Url, point: Point) -> Option<(MethodRef, String, Range)> {
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let declaration = method_definition_nodes(document.tree.root_node())
+            .into_iter()
+            .filter_map(|(method_definition, _)| method_definition.named_child(0))
+            .find(|name| name.start_position() <= point && point <= name.end_position())
+            .and_then(|name| {
+                let method_name = content.get(name.byte_range())?;
+                let class_id = document.class_id?;
+                let class = self.global_semantic_model.get_class(class_id)?;
+                let method_ref = MethodRef {
+                    class: class_id,
+                    pub_id: class.public_methods.get(method_name).copied(),
+                    priv_id: class.private_methods.get(method_name).copied(),
                 };
-                locations.push((cls_url.clone(), sym.location));
-            }
+                Some((method_ref, method_name.to_string(), name.range()))
+            });
+        declaration.or_else(|| self.resolve_method_call(url, point))
+    }
+
+    /// Resolve the method called at `point` in the document at `url` from the document's
+    /// `SymbolLocationIndex`, without walking the syntax tree: the call sites recorded in
+    /// `Class::method_calls` whose callee is resolved, when `point` is on the method name.
+    ///
+    /// Returns the callee and the range of its name at the call site.
+    fn indexed_method_call_at(&self, url: &Url, point: Point) -> Option<(MethodRef, Range)> {
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let SymbolRef::MethodCall(i) = self.get_symbol_at(url, point)? else {
+            return None;
+        };
+        let call_site = self
+            .global_semantic_model
+            .classes
+            .get(document.class_id?)?
+            .method_calls
+            .get(i)?;
+        let callee = call_site.callee_symbol?;
+        // the name follows the `.` before the argument list, e.g. `Run` in `##class(A).Run(1)`
+        let call_start = call_site.call_range.start_byte;
+        let text = content.get(call_start..call_site.call_range.end_byte)?;
+        let needle = format!(".{}", call_site.callee_method);
+        let (dot, _) = text.match_indices(needle.as_str()).find(|&(dot, _)| {
+            matches!(text[dot + needle.len()..].chars().next(), None | Some('('))
+        })?;
+        let name_start = call_start + dot + 1;
+        let range = byte_range(
+            content,
+            name_start,
+            name_start + call_site.callee_method.len(),
+        );
+        if point < range.start_point || range.end_point < point {
+            return None;
         }
-        successful_exit("ProjectData", "get_method_overrides");
-        locations
+        let method_ref = MethodRef {
+            class: callee.class,
+            pub_id: Some(callee.id),
+            priv_id: None,
+        };
+        Some((method_ref, range))
     }
-}
 
-impl ProjectState {
-    /// Create a new `ProjectState` with default configuration and empty indexing state.
+    /// Build the signature hover of the method named at `point` in the document at `url`.
     ///
-    /// Initializes shared parsers, an empty `ProjectData` store, and leaves `project_root_path`
-    /// unset (expected to be populated during LSP initialization).
-    pub fn new() -> Self {
-        Self {
-            project_root_path: OnceLock::new(),
-            parsers: WorkspaceParsers::new(),
-            data: RwLock::new(ProjectData {
+    /// Calls recorded in the document's `SymbolLocationIndex` are resolved from it (see
+    /// `indexed_method_call_at`); declarations and the calls it doesn't record, e.g. in
+    /// expressions or to private methods, fall back to `resolve_method_at`. Methods declared in
+    /// another class, e.g. inherited ones, name their class.
+    pub fn get_method_signature_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_method_signature_hover");
+        let document = self.get_document(url)?;
+        let resolved = self.indexed_method_call_at(url, point).or_else(|| {
+            self.resolve_method_at(url, point)
+                .map(|(method_ref, _, range)| (method_ref, range))
+        });
+        let Some((method_ref, range)) = resolved else {
+            generic_exit_statements("ProjectData", "get_method_signature_hover");
+            return None;
+        };
+        let method = self.get_method_by_ref(&method_ref)?;
+        let mut value = format!("```objectscript\n{}\n```", method.signature());
+        if document.class_id != Some(method_ref.class) {
+            let class = self.global_semantic_model.get_class(method_ref.class)?;
+            value.push_str(&format!("\n\nDeclared in `{}`", class.name));
+        }
+        successful_exit("ProjectData", "get_method_signature_hover");
+        Some((range, value))
+    }
+
+    /// Return the call sites, across every class of the workspace, of the public method named at
+    /// `point` in the document at `url` (see `resolve_method_at`), and its declaration if
+    /// `include_declaration` is set.
+    ///
+    /// Calls are those whose resolved `callee_symbol` is the method, as recorded in the
+    /// `ReferenceIndex`; private methods have none.
+    pub fn get_method_references_at(
+        &self,
+        url: &Url,
+        point: Point,
+        include_declaration: bool,
+    ) -> Vec<(Url, Range)> {
+        start_of_function("ProjectData", "get_method_references_at");
+        let Some((method_ref, method_name, _)) = self.resolve_method_at(url, point) else {
+            generic_exit_statements("ProjectData", "get_method_references_at");
+            return Vec::new();
+        };
+        let Some(id) = method_ref.pub_id else {
+            generic_exit_statements("ProjectData", "get_method_references_at");
+            return Vec::new();
+        };
+        let mut locations = Vec::new();
+        if include_declaration {
+            locations.extend(self.get_method_ref_location(&method_ref, &method_name));
+        }
+        locations.extend(self.get_method_references(&PublicMethodRef {
+            class: method_ref.class,
+            id,
+        }));
+        successful_exit("ProjectData", "get_method_references_at");
+        locations
+    }
+
+    /// Returns the workspace class named at `point` in the document at `url`, e.g. in an
+    /// `Extends` list, a `##class()` reference, or a property type, with the range of its name.
+    /// Names without a package are looked up in the package of the document's class too.
+    fn class_at(&self, url: &Url, point: Point) -> Option<(&Class, Range)> {
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let package = document
+            .class_name
+            .rsplit_once('.')
+            .map(|(package, _)| package);
+        let mut node = document
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)?;
+        // the node at `point` may be one identifier of the qualified name
+        for _ in 0..3 {
+            let name = content.get(node.byte_range()).unwrap_or_default().trim();
+            let qualified = package.map(|package| format!("{package}.{name}"));
+            if let Some(class) = std::iter::once(name.to_string())
+                .chain(qualified)
+                .find_map(|name| self.get_class_id(&name))
+                .and_then(|class_id| self.global_semantic_model.get_class(class_id))
+                .filter(|class| class.active)
+            {
+                return Some((class, node.range()));
+            }
+            node = node.parent()?;
+        }
+        None
+    }
+
+    /// Build the summary hover of the workspace class named at `point` in the document at `url`
+    /// (see `class_at`).
+    pub fn get_class_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_class_hover");
+        let Some((class, range)) = self.class_at(url, point) else {
+            generic_exit_statements("ProjectData", "get_class_hover");
+            return None;
+        };
+        successful_exit("ProjectData", "get_class_hover");
+        Some((range, class.hover_summary()))
+    }
+
+    /// Returns the name of the workspace class named at `point` in the document at `url` (see
+    /// `class_at`).
+    pub fn get_class_name_at(&self, url: &Url, point: Point) -> Option<String> {
+        self.class_at(url, point)
+            .map(|(class, _)| class.name.clone())
+    }
+
+    /// Returns every location of the override chain of the method `method_name` declared in the
+    /// class of `url`: the base method the chain starts from, followed by every method that
+    /// (transitively) overrides it.
+    ///
+    /// This backs the `objectscript/overrideChain` request, which lets a client show the whole
+    /// chain in one peek view. Returns an empty vector if the method is unknown.
+    pub fn get_override_chain(&self, url: &Url, method_name: &str) -> Vec<(Url, Range)> {
+        start_of_function("ProjectData", "get_override_chain");
+        let Some(class_id) = self
+            .get_document(url)
+            .and_then(|document| document.class_id)
+        else {
+            generic_exit_statements("ProjectData", "get_override_chain");
+            return Vec::new();
+        };
+        let Some(class) = self.global_semantic_model.get_class(class_id) else {
+            generic_exit_statements("ProjectData", "get_override_chain");
+            return Vec::new();
+        };
+        let method_ref = MethodRef {
+            class: class_id,
+            pub_id: class.public_methods.get(method_name).copied(),
+            priv_id: class.private_methods.get(method_name).copied(),
+        };
+        if method_ref.pub_id.is_none() && method_ref.priv_id.is_none() {
+            generic_exit_statements("ProjectData", "get_override_chain");
+            return Vec::new();
+        }
+
+        // walk up to the method the chain starts from
+        let mut base = method_ref;
+        let mut visited = vec![base];
+        while let Some(parent) = self.override_index.overrides.get(&base) {
+            let parent = parent.to_method_ref();
+            if visited.contains(&parent) {
+                eprintln!("Warning: cycle in override chain of method {method_name:?}");
+                break;
+            }
+            visited.push(parent);
+            base = parent;
+        }
+
+        // then collect every override below it, breadth first
+        let mut chain = vec![base];
+        let mut next = 0;
+        while next < chain.len() {
+            let current = chain[next];
+            next += 1;
+            let Some(handle) = MethodHandle::from_method_ref(current) else {
+                continue;
+            };
+            for child in self
+                .override_index
+                .overridden_by
+                .get(&handle)
+                .into_iter()
+                .flatten()
+            {
+                if !chain.contains(child) {
+                    chain.push(*child);
+                }
+            }
+        }
+
+        let locations = chain
+            .iter()
+            .filter_map(|method_ref| self.get_method_ref_location(method_ref, method_name))
+            .collect();
+        successful_exit("ProjectData", "get_override_chain");
+        locations
+    }
+
+    /// Find the property at `point` in the document at `url`: a `Property` definition name, or a
+    /// relative property reference (`..Name`). Returns the range of the name, the class declaring
+    /// the property (the document's class or its nearest superclass declaring it), the property
+    /// name, and the location of its declaration.
+    fn get_property_at(
+        &self,
+        url: &Url,
+        point: Point,
+    ) -> Option<(Range, ClassId, String, Location)> {
+        let document = self.get_document(url)?;
+        let class_id = document.class_id?;
+        let content = document.content.as_str();
+        let members = class_members(content, &document.tree);
+        let declared = members.iter().find(|member| {
+            member.kind == MemberKind::Property
+                && member.range.start_point <= point
+                && point <= member.range.end_point
+        });
+        let (range, name) = match declared {
+            Some(member) => (member.range, member.name.clone()),
+            None => {
+                let cursor = point_to_byte(content, point);
+                // names are ASCII, so counting chars counts bytes
+                let is_name = |c: &char| c.is_ascii_alphanumeric() || *c == '%';
+                let before = content[..cursor].chars().rev().take_while(is_name);
+                let start = cursor - before.count();
+                let end = cursor + content[cursor..].chars().take_while(is_name).count();
+                // a relative property reference, not a `..Method()` call
+                if start == end
+                    || !content[..start].ends_with("..")
+                    || content[end..].starts_with('(')
+                {
+                    return None;
+                }
+                let name = content[start..end].to_string();
+                (byte_range(content, start, end), name)
+            }
+        };
+        if let Some(member) = members
+            .iter()
+            .find(|member| member.kind == MemberKind::Property && member.name == name)
+        {
+            let location = Location {
+                uri: url.clone(),
+                range: ts_range_to_lsp_range(content, member.range),
+            };
+            return Some((range, class_id, name, location));
+        }
+        let inherited = self
+            .get_inherited_members(class_id)
+            .into_iter()
+            .find(|inherited| {
+                inherited.member.kind == MemberKind::Property && inherited.member.name == name
+            })?;
+        let owner = *self.classes.get(inherited.class_name.as_str())?;
+        Some((range, owner, name, inherited.location))
+    }
+
+    /// Collect the custom accessors (`<Prop>Get`/`<Prop>Set`) of the property `name` declared by
+    /// `owner`, as their qualified names and definition locations.
+    fn get_property_accessors(&self, owner: ClassId, name: &str) -> Vec<(String, Location)> {
+        let key = (owner, name.to_string());
+        let mut accessors = Vec::new();
+        for accessor in self
+            .override_index
+            .property_accessors
+            .get(&key)
+            .into_iter()
+            .flatten()
+        {
+            let (Some(method_name), Some(class)) = (
+                self.get_method_ref_name(accessor),
+                self.global_semantic_model.get_class(accessor.class),
+            ) else {
+                continue;
+            };
+            let Some((uri, range)) = self.get_method_ref_location(accessor, method_name) else {
+                continue;
+            };
+            let Some(document) = self.get_document(&uri) else {
+                continue;
+            };
+            let range = ts_range_to_lsp_range(document.content.as_str(), range);
+            accessors.push((
+                format!("{}.{}", class.name, method_name),
+                Location { uri, range },
+            ));
+        }
+        accessors.sort_by(|a, b| a.0.cmp(&b.0));
+        accessors
+    }
+
+    /// Build the hover of the method argument named at `point` in the document at `url`, in its
+    /// declaration or in the method body: the argument as declared (with its `ByRef` or `Output`
+    /// modifier, type, and default) and the method it belongs to.
+    pub fn get_argument_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_argument_hover");
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let entry = document
+            .symbol_index
+            .symbol_at(point_to_byte(content, point))?;
+        let is_variable = matches!(
+            entry.symbol,
+            SymbolRef::PrivateVariable(..)
+                | SymbolRef::PrivateVariableReference(..)
+                | SymbolRef::Global(GlobalSymbolId::Variable(..))
+        );
+        if !is_variable {
+            generic_exit_statements("ProjectData", "get_argument_hover");
+            return None;
+        }
+        let name = content.get(entry.range.start_byte..entry.range.end_byte)?;
+        let (method, _) = method_definition_nodes(document.tree.root_node())
+            .into_iter()
+            .find(|(method_definition, _)| {
+                method_definition.start_position() <= point
+                    && point <= method_definition.end_position()
+            })
+            .and_then(|(method_definition, method_type)| {
+                initial_build_method(method_definition, method_type, content)
+            })?;
+        let Some(index) = method
+            .arguments
+            .iter()
+            .position(|argument| argument == name)
+        else {
+            generic_exit_statements("ProjectData", "get_argument_hover");
+            return None;
+        };
+        let declaration = method.argument_declarations.get(index)?;
+        let mut value = format!(
+            "```objectscript\n{declaration}\n```\n\nArgument {} of `{}`",
+            index + 1,
+            method.name
+        );
+        match argument_mode(declaration) {
+            ArgumentMode::ByValue => {}
+            ArgumentMode::ByRef => value.push_str(", passed by reference"),
+            ArgumentMode::Output => value.push_str(", returning a value to the caller"),
+        }
+        successful_exit("ProjectData", "get_argument_hover");
+        Some((entry.range, value))
+    }
+
+    /// Build the hover of the property at `point` in the document at `url`, naming the class
+    /// declaring it, its declaration (type and `Private` keyword), its custom accessors, and its
+    /// `Internal` and `ServerOnly` keywords.
+    pub fn get_property_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_property_hover");
+        let Some((range, owner, name, declaration)) = self.get_property_at(url, point) else {
+            generic_exit_statements("ProjectData", "get_property_hover");
+            return None;
+        };
+        let class = self.global_semantic_model.get_class(owner)?;
+        let mut value = format!("**Property** `{name}` of `{}`", class.name);
+        if let Some(property) = self.get_class_property(&class.name, &name) {
+            value.push_str(&format!(
+                "\n\n```objectscript\n{}\n```",
+                property.signature()
+            ));
+        }
+        let accessors = self.get_property_accessors(owner, &name);
+        if !accessors.is_empty() {
+            let names: Vec<String> = accessors
+                .iter()
+                .map(|(accessor, _)| format!("`{accessor}`"))
+                .collect();
+            value.push_str(&format!("\n\nCustom accessors: {}", names.join(", ")));
+        }
+        if let Some(note) = self.get_member_visibility_note(&declaration.uri, &name) {
+            value.push_str("\n\n");
+            value.push_str(&note);
+        }
+        successful_exit("ProjectData", "get_property_hover");
+        Some((range, value))
+    }
+
+    /// Find the class parameter at `point` in the document at `url`: a `Parameter` definition
+    /// name, or a reference (`..#NAME` or `##class(Pkg.Cls).#NAME`). Returns the range of the
+    /// name, the class declaring the parameter (the referenced class or its nearest superclass
+    /// declaring it), the parameter name, and the location of its declaration.
+    fn get_parameter_at(
+        &self,
+        url: &Url,
+        point: Point,
+    ) -> Option<(Range, ClassId, String, Location)> {
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let declared = class_members(content, &document.tree)
+            .into_iter()
+            .find(|member| {
+                member.kind == MemberKind::Parameter
+                    && member.range.start_point <= point
+                    && point <= member.range.end_point
+            });
+        let (range, name, class_name) = match declared {
+            Some(member) => (member.range, member.name, document.class_name.clone()),
+            None => {
+                let cursor = point_to_byte(content, point);
+                // names are ASCII, so counting chars counts bytes
+                let is_name = |c: &char| c.is_ascii_alphanumeric() || *c == '%';
+                let before = content[..cursor].chars().rev().take_while(is_name);
+                let start = cursor - before.count();
+                let end = cursor + content[cursor..].chars().take_while(is_name).count();
+                if start == end {
+                    return None;
+                }
+                let qualifier = content[..start].strip_suffix('#')?;
+                let class_name = if qualifier.ends_with("..") {
+                    document.class_name.clone()
+                } else {
+                    let qualifier = qualifier.strip_suffix(").")?;
+                    let class_start =
+                        qualifier.to_ascii_lowercase().rfind("##class(")? + "##class(".len();
+                    let name = qualifier[class_start..].trim();
+                    // names without a package are looked up in the package of the document's
+                    // class
+                    let package = document
+                        .class_name
+                        .rsplit_once('.')
+                        .map(|(package, _)| package);
+                    std::iter::once(name.to_string())
+                        .chain(package.map(|package| format!("{package}.{name}")))
+                        .find(|name| self.classes.contains_key(name.as_str()))?
+                };
+                let name = content[start..end].to_string();
+                (byte_range(content, start, end), name, class_name)
+            }
+        };
+        let class_id = *self.classes.get(class_name.as_str())?;
+        if let Some(parameter) = self.get_class_parameter(&class_name, &name) {
+            let uri = self.get_class_url(&class_name)?;
+            let declaring_document = self.get_document(uri)?;
+            let location = Location {
+                uri: uri.clone(),
+                range: ts_range_to_lsp_range(declaring_document.content.as_str(), parameter.range),
+            };
+            return Some((range, class_id, name, location));
+        }
+        let inherited = self
+            .get_inherited_members(class_id)
+            .into_iter()
+            .find(|inherited| {
+                inherited.member.kind == MemberKind::Parameter && inherited.member.name == name
+            })?;
+        let owner = *self.classes.get(inherited.class_name.as_str())?;
+        Some((range, owner, name, inherited.location))
+    }
+
+    /// Build the hover of the class parameter at `point` in the document at `url`, naming the
+    /// class declaring it and showing its declaration (type and value).
+    pub fn get_parameter_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_parameter_hover");
+        let Some((range, owner, name, _)) = self.get_parameter_at(url, point) else {
+            generic_exit_statements("ProjectData", "get_parameter_hover");
+            return None;
+        };
+        let class = self.global_semantic_model.get_class(owner)?;
+        let mut value = format!("**Parameter** `{name}` of `{}`", class.name);
+        if let Some(parameter) = self.get_class_parameter(&class.name, &name) {
+            value.push_str(&format!(
+                "\n\n```objectscript\n{}\n```",
+                parameter.signature()
+            ));
+        }
+        successful_exit("ProjectData", "get_parameter_hover");
+        Some((range, value))
+    }
+
+    /// Return the declaration of the class parameter at `point` in the document at `url`.
+    pub fn get_parameter_definition(&self, url: &Url, point: Point) -> Option<Location> {
+        start_of_function("ProjectData", "get_parameter_definition");
+        let Some((_, _, _, declaration)) = self.get_parameter_at(url, point) else {
+            generic_exit_statements("ProjectData", "get_parameter_definition");
+            return None;
+        };
+        successful_exit("ProjectData", "get_parameter_definition");
+        Some(declaration)
+    }
+
+    /// Return the definitions of the property at `point` in the document at `url`: its
+    /// declaration followed by its custom accessors.
+    pub fn get_property_definitions(&self, url: &Url, point: Point) -> Vec<Location> {
+        start_of_function("ProjectData", "get_property_definitions");
+        let Some((_, owner, name, declaration)) = self.get_property_at(url, point) else {
+            generic_exit_statements("ProjectData", "get_property_definitions");
+            return Vec::new();
+        };
+        let mut locations = vec![declaration];
+        locations.extend(
+            self.get_property_accessors(owner, &name)
+                .into_iter()
+                .map(|(_, location)| location),
+        );
+        successful_exit("ProjectData", "get_property_definitions");
+        locations
+    }
+
+    /// Build the method-level call graph of the workspace from the call sites of every class.
+    ///
+    /// Calls are bound to the class declaring the called method, so a call to an inherited
+    /// method is an edge to the superclass method. Calls that don't resolve are left out.
+    pub fn get_call_graph(&self) -> CallGraph {
+        start_of_function("ProjectData", "get_call_graph");
+        let mut edges = Vec::new();
+        for &class_id in self.classes.values() {
+            let Some(class) = self.global_semantic_model.get_class(class_id) else {
+                continue;
+            };
+            for call_site in &class.method_calls {
+                let Some(callee) =
+                    self.get_call_graph_node(&call_site.callee_class, &call_site.callee_method)
+                else {
+                    continue;
+                };
+                edges.push(CallEdge {
+                    caller: CallGraphNode {
+                        class: class_id,
+                        method: call_site.caller_method.clone(),
+                    },
+                    callee,
+                    call_range: call_site.call_range,
+                });
+            }
+        }
+        successful_exit("ProjectData", "get_call_graph");
+        CallGraph::new(edges)
+    }
+
+    /// Resolve the method `method_name` of `class_name`, declared or inherited, to the call graph
+    /// node of the class declaring it.
+    fn get_call_graph_node(&self, class_name: &str, method_name: &str) -> Option<CallGraphNode> {
+        let class_id = self.classes.get(class_name)?;
+        let handle = self
+            .override_index
+            .effective_methods
+            .get(class_id)?
+            .get(method_name)?;
+        Some(CallGraphNode {
+            class: handle.class(),
+            method: method_name.to_string(),
+        })
+    }
+
+    /// Build the call hierarchy item of `node`, flagged as recursive if it is in `recursive`.
+    ///
+    /// The item's `data` holds the class and method name, so later incoming/outgoing call
+    /// requests can find the node again, and the `recursive` flag.
+    fn get_call_hierarchy_item(
+        &self,
+        node: &CallGraphNode,
+        recursive: &HashMap<CallGraphNode, Vec<CallGraphNode>>,
+    ) -> Option<CallHierarchyItem> {
+        let class = self.global_semantic_model.get_class(node.class)?;
+        let method_ref = MethodRef {
+            class: node.class,
+            pub_id: class.public_methods.get(&node.method).copied(),
+            priv_id: class.private_methods.get(&node.method).copied(),
+        };
+        let (uri, range) = self.get_method_ref_location(&method_ref, &node.method)?;
+        let range = ts_range_to_lsp_range(self.get_document(&uri)?.content.as_str(), range);
+        let is_recursive = recursive.contains_key(node);
+        Some(CallHierarchyItem {
+            name: node.method.clone(),
+            kind: SymbolKind::METHOD,
+            tags: None,
+            detail: Some(if is_recursive {
+                format!("{} (recursive)", class.name)
+            } else {
+                class.name.clone()
+            }),
+            uri,
+            range,
+            selection_range: range,
+            data: Some(json!({
+                "className": class.name,
+                "method": node.method,
+                "recursive": is_recursive,
+            })),
+        })
+    }
+
+    /// Find the call graph node of a call hierarchy item built by `get_call_hierarchy_item`.
+    fn get_call_hierarchy_node(&self, item: &CallHierarchyItem) -> Option<CallGraphNode> {
+        let data = item.data.as_ref()?;
+        let class_name = data.get("className")?.as_str()?;
+        let method_name = data.get("method")?.as_str()?;
+        self.get_call_graph_node(class_name, method_name)
+    }
+
+    /// Prepare the call hierarchy at `point` in the document at `url`: the method called at
+    /// `point`, or else the method whose definition contains it.
+    pub fn prepare_call_hierarchy(&self, url: &Url, point: Point) -> Vec<CallHierarchyItem> {
+        start_of_function("ProjectData", "prepare_call_hierarchy");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "prepare_call_hierarchy");
+            return Vec::new();
+        };
+        let Some(class) = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.get_class(class_id))
+        else {
+            generic_exit_statements("ProjectData", "prepare_call_hierarchy");
+            return Vec::new();
+        };
+        let called = class
+            .method_calls
+            .iter()
+            .find(|call_site| {
+                call_site.call_range.start_point <= point && point <= call_site.call_range.end_point
+            })
+            .and_then(|call_site| {
+                self.get_call_graph_node(&call_site.callee_class, &call_site.callee_method)
+            });
+        let node = called.or_else(|| {
+            let method_name = document
+                .tree
+                .root_node()
+                .named_descendant_for_point_range(point, point)
+                .and_then(|node| {
+                    method_name_from_identifier_node(node, document.content.as_str(), 0)
+                })?;
+            self.get_call_graph_node(&class.name, &method_name)
+        });
+        let Some(node) = node else {
+            successful_exit("ProjectData", "prepare_call_hierarchy");
+            return Vec::new();
+        };
+        let recursive = self.get_call_graph().recursive_methods();
+        let items = self
+            .get_call_hierarchy_item(&node, &recursive)
+            .into_iter()
+            .collect();
+        successful_exit("ProjectData", "prepare_call_hierarchy");
+        items
+    }
+
+    /// Collect the methods calling the method of `item`, with the ranges of their calls.
+    ///
+    /// Stops with what was collected so far once `cancel` is cancelled.
+    pub fn get_incoming_calls(
+        &self,
+        item: &CallHierarchyItem,
+        cancel: &CancellationToken,
+    ) -> Vec<CallHierarchyIncomingCall> {
+        start_of_function("ProjectData", "get_incoming_calls");
+        let Some(node) = self.get_call_hierarchy_node(item) else {
+            generic_exit_statements("ProjectData", "get_incoming_calls");
+            return Vec::new();
+        };
+        let graph = self.get_call_graph();
+        if cancel.is_cancelled() {
+            generic_exit_statements("ProjectData", "get_incoming_calls");
+            return Vec::new();
+        }
+        let recursive = graph.recursive_methods();
+        let mut calls: Vec<CallHierarchyIncomingCall> = Vec::new();
+        let mut callers: Vec<&CallGraphNode> = Vec::new();
+        for edge in graph.incoming.get(&node).into_iter().flatten() {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let Some(range) = self.get_call_lsp_range(edge) else {
+                continue;
+            };
+            match callers.iter().position(|caller| **caller == edge.caller) {
+                Some(i) => calls[i].from_ranges.push(range),
+                None => {
+                    let Some(from) = self.get_call_hierarchy_item(&edge.caller, &recursive) else {
+                        continue;
+                    };
+                    callers.push(&edge.caller);
+                    calls.push(CallHierarchyIncomingCall {
+                        from,
+                        from_ranges: vec![range],
+                    });
+                }
+            }
+        }
+        successful_exit("ProjectData", "get_incoming_calls");
+        calls
+    }
+
+    /// Collect the methods called by the method of `item`, with the ranges of the calls.
+    pub fn get_outgoing_calls(&self, item: &CallHierarchyItem) -> Vec<CallHierarchyOutgoingCall> {
+        start_of_function("ProjectData", "get_outgoing_calls");
+        let Some(node) = self.get_call_hierarchy_node(item) else {
+            generic_exit_statements("ProjectData", "get_outgoing_calls");
+            return Vec::new();
+        };
+        let graph = self.get_call_graph();
+        let recursive = graph.recursive_methods();
+        let mut calls: Vec<CallHierarchyOutgoingCall> = Vec::new();
+        let mut callees: Vec<&CallGraphNode> = Vec::new();
+        for edge in graph.outgoing.get(&node).into_iter().flatten() {
+            let Some(range) = self.get_call_lsp_range(edge) else {
+                continue;
+            };
+            match callees.iter().position(|callee| **callee == edge.callee) {
+                Some(i) => calls[i].from_ranges.push(range),
+                None => {
+                    let Some(to) = self.get_call_hierarchy_item(&edge.callee, &recursive) else {
+                        continue;
+                    };
+                    callees.push(&edge.callee);
+                    calls.push(CallHierarchyOutgoingCall {
+                        to,
+                        from_ranges: vec![range],
+                    });
+                }
+            }
+        }
+        successful_exit("ProjectData", "get_outgoing_calls");
+        calls
+    }
+
+    /// Convert the range of the call `edge` using the document of the caller's class.
+    fn get_call_lsp_range(&self, edge: &CallEdge) -> Option<LspRange> {
+        let class = self.global_semantic_model.get_class(edge.caller.class)?;
+        let document = self.get_class_document(&class.name)?;
+        Some(ts_range_to_lsp_range(
+            document.content.as_str(),
+            edge.call_range,
+        ))
+    }
+
+    /// Collect the methods of the class in `url` that call themselves, directly or through a
+    /// cycle of calls, in document order.
+    pub fn get_recursive_methods(&self, url: &Url) -> Vec<RecursiveMethod> {
+        start_of_function("ProjectData", "get_recursive_methods");
+        let Some(class_id) = self
+            .get_document(url)
+            .and_then(|document| document.class_id)
+        else {
+            generic_exit_statements("ProjectData", "get_recursive_methods");
+            return Vec::new();
+        };
+        let qualified_name = |node: &CallGraphNode| {
+            let class = self.global_semantic_model.get_class(node.class)?;
+            Some(format!("{}.{}", class.name, node.method))
+        };
+        let mut methods = Vec::new();
+        for (node, cycle) in self.get_call_graph().recursive_methods() {
+            if node.class != class_id {
+                continue;
+            }
+            let Some(class) = self.global_semantic_model.get_class(class_id) else {
+                continue;
+            };
+            let method_ref = MethodRef {
+                class: class_id,
+                pub_id: class.public_methods.get(&node.method).copied(),
+                priv_id: class.private_methods.get(&node.method).copied(),
+            };
+            let Some((_, range)) = self.get_method_ref_location(&method_ref, &node.method) else {
+                continue;
+            };
+            methods.push(RecursiveMethod {
+                name: node.method.clone(),
+                range,
+                cycle: cycle.iter().filter_map(qualified_name).collect(),
+            });
+        }
+        methods.sort_by_key(|method| method.range.start_byte);
+        successful_exit("ProjectData", "get_recursive_methods");
+        methods
+    }
+
+    /// Collect the literals of the document at `url` that don't match the type declared where
+    /// they are used: arguments of resolved method calls, values set into typed variables, and
+    /// values returned (see `type_check`), in document order.
+    pub fn get_type_mismatches(&self, url: &Url) -> Vec<TypeMismatch> {
+        start_of_function("ProjectData", "get_type_mismatches");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_type_mismatches");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let mut mismatches = body_mismatches(content, &document.tree);
+        let class = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id));
+        if let Some(class) = class {
+            mismatches.extend(argument_mismatches(
+                content,
+                &class.method_calls,
+                |call_site| self.get_callee_argument_declarations(call_site),
+            ));
+        }
+        mismatches.sort_by_key(|mismatch| mismatch.range.start_byte);
+        successful_exit("ProjectData", "get_type_mismatches");
+        mismatches
+    }
+
+    /// Returns the argument declarations of the method `call_site` resolves to.
+    fn get_callee_argument_declarations(&self, call_site: &MethodCallSite) -> Option<Vec<String>> {
+        let method_ref = call_site.callee_symbol.as_ref()?;
+        let class = self.global_semantic_model.get_class(method_ref.class)?;
+        let method = self.global_semantic_model.get_method(
+            method_ref.class,
+            class.name.as_str(),
+            method_ref.id.0,
+        )?;
+        Some(method.argument_declarations.clone())
+    }
+
+    /// Collect the calls of the document at `url` passing more arguments than the called
+    /// method declares or leaving out required ones (see `find_arity_mismatches`).
+    pub fn get_arity_mismatches(&self, url: &Url) -> Vec<ArityMismatch> {
+        start_of_function("ProjectData", "get_arity_mismatches");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_arity_mismatches");
+            return Vec::new();
+        };
+        let Some(class) = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id))
+        else {
+            generic_exit_statements("ProjectData", "get_arity_mismatches");
+            return Vec::new();
+        };
+        let mismatches = find_arity_mismatches(
+            document.content.as_str(),
+            &class.method_calls,
+            |call_site| self.get_callee_argument_declarations(call_site),
+        );
+        successful_exit("ProjectData", "get_arity_mismatches");
+        mismatches
+    }
+
+    /// Render the `ScopeTree` of the document at `url` as JSON (see `ScopeTree::to_json`).
+    ///
+    /// Returns `None` if the document is not currently tracked.
+    pub fn get_scope_tree_json(&self, url: &Url) -> Option<Value> {
+        start_of_function("ProjectData", "get_scope_tree_json");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_scope_tree_json");
+            return None;
+        };
+        let mut value = document.scope_tree.to_json();
+        if let Value::Object(map) = &mut value {
+            map.insert("uri".to_string(), Value::String(url.to_string()));
+            map.insert(
+                "className".to_string(),
+                Value::String(document.class_name.clone()),
+            );
+        }
+        successful_exit("ProjectData", "get_scope_tree_json");
+        Some(value)
+    }
+
+    /// Build the class statistics code lens for the document at `url`.
+    ///
+    /// Returns `None` unless `Config::class_stats_lens` is enabled and the document is a tracked
+    /// class.
+    pub fn get_class_stats_lens(&self, url: &Url) -> Option<CodeLens> {
+        start_of_function("ProjectData", "get_class_stats_lens");
+        if !self.config.class_stats_lens {
+            generic_exit_statements("ProjectData", "get_class_stats_lens");
+            return None;
+        }
+        let document = self.get_document(url)?;
+        let class_id = document.class_id?;
+        let class = self.global_semantic_model.classes.get(class_id)?;
+        let class_definition = find_class_definition(document.tree.root_node())?;
+        let stats = ClassStats {
+            methods: class.public_methods.len() + class.private_methods.len(),
+            properties: class.public_properties.len() + class.private_properties.len(),
+            lines: document.content.as_str().lines().count(),
+            subclasses: self
+                .global_semantic_model
+                .classes
+                .values()
+                .filter(|other| other.active && other.inherited_classes.contains(&class_id))
+                .count(),
+        };
+        let position =
+            point_to_lsp_position(document.content.as_str(), class_definition.start_position());
+        successful_exit("ProjectData", "get_class_stats_lens");
+        Some(stats.to_code_lens(position))
+    }
+
+    /// Build a code lens with the number of call sites above each public method declared in the
+    /// document at `url`.
+    ///
+    /// Returns no lenses unless `Config::reference_count_lens` is enabled. Counts come from the
+    /// `ReferenceIndex`, so this doesn't scan the call sites of the workspace.
+    pub fn get_reference_count_lenses(&self, url: &Url) -> Vec<CodeLens> {
+        start_of_function("ProjectData", "get_reference_count_lenses");
+        if !self.config.reference_count_lens {
+            generic_exit_statements("ProjectData", "get_reference_count_lenses");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_reference_count_lenses");
+            return Vec::new();
+        };
+        let Some(class_id) = document.class_id else {
+            generic_exit_statements("ProjectData", "get_reference_count_lenses");
+            return Vec::new();
+        };
+        let Some(class) = self.global_semantic_model.get_class(class_id) else {
+            generic_exit_statements("ProjectData", "get_reference_count_lenses");
+            return Vec::new();
+        };
+        let mut lenses: Vec<CodeLens> = class
+            .public_methods
+            .iter()
+            .filter_map(|(method_name, &id)| {
+                let method_ref = MethodRef {
+                    class: class_id,
+                    pub_id: Some(id),
+                    priv_id: None,
+                };
+                let (_, range) = self.get_method_ref_location(&method_ref, method_name)?;
+                let count = self.get_method_reference_count(&PublicMethodRef {
+                    class: class_id,
+                    id,
+                });
+                let position = point_to_lsp_position(document.content.as_str(), range.start_point);
+                Some(reference_count_lens(count, position))
+            })
+            .collect();
+        lenses.sort_by_key(|lens| (lens.range.start.line, lens.range.start.character));
+        successful_exit("ProjectData", "get_reference_count_lenses");
+        lenses
+    }
+
+    /// Compute the complexity metrics of the methods of the document at `url`.
+    ///
+    /// Returns an empty list unless `Config::method_metrics` is enabled.
+    pub fn get_method_metrics(&self, url: &Url) -> Vec<MethodMetrics> {
+        start_of_function("ProjectData", "get_method_metrics");
+        if !self.config.method_metrics {
+            generic_exit_statements("ProjectData", "get_method_metrics");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_method_metrics");
+            return Vec::new();
+        };
+        let metrics = method_metrics(document.content.as_str(), &document.tree);
+        successful_exit("ProjectData", "get_method_metrics");
+        metrics
+    }
+
+    /// Build the element type hover for the `$list`/`$piece` call at `point` in the document at
+    /// `url`.
+    ///
+    /// Returns `None` unless `Config::element_type_hover` is enabled and the element's value can
+    /// be inferred.
+    pub fn get_element_type_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_element_type_hover");
+        if !self.config.element_type_hover {
+            generic_exit_statements("ProjectData", "get_element_type_hover");
+            return None;
+        }
+        let document = self.get_document(url)?;
+        let hover = element_type_hover(document.content.as_str(), &document.tree, point);
+        successful_exit("ProjectData", "get_element_type_hover");
+        hover
+    }
+
+    /// Build the `$SYSTEM` API hover for the reference at `point`: the docs of the `%SYSTEM` class
+    /// or method it names.
+    pub fn get_system_api_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_system_api_hover");
+        if !self.config.system_api_stubs {
+            generic_exit_statements("ProjectData", "get_system_api_hover");
+            return None;
+        }
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let hover = system_api_hover(content, point_to_byte(content, point));
+        successful_exit("ProjectData", "get_system_api_hover");
+        hover
+    }
+
+    /// Build the class reference hover for the system class named at `point`, or the method
+    /// called on one (see `system_class_reference_at`): its declaration and description.
+    ///
+    /// Classes downloaded from the server (see `ProjectState::fetch_remote_classes`) are
+    /// documented from their source, falling back to the bundled `CLASS_REFERENCE` snapshot.
+    /// Downloaded classes themselves are left to `get_class_hover`.
+    pub fn get_class_reference_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_class_reference_hover");
+        if !self.config.class_reference_hover {
+            generic_exit_statements("ProjectData", "get_class_reference_hover");
+            return None;
+        }
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let reference = system_class_reference_at(content, point_to_byte(content, point))?;
+        let range = byte_range(content, reference.range.start, reference.range.end);
+        let class_name = qualified_class_name(&reference.class_name);
+        let Some(method_name) = reference.method else {
+            if self.get_class_id(&class_name).is_some() {
+                generic_exit_statements("ProjectData", "get_class_reference_hover");
+                return None;
+            }
+            let class = reference_class(&class_name)?;
+            successful_exit("ProjectData", "get_class_reference_hover");
+            return Some((range, class.hover()));
+        };
+        // the method may be inherited, so search the superclasses too
+        let mut queue = vec![class_name];
+        let mut visited = Vec::new();
+        while let Some(class_name) = queue.pop() {
+            if visited.contains(&class_name) {
+                continue;
+            }
+            visited.push(class_name.clone());
+            let found = match self.get_class_document(&class_name) {
+                Some(document) => {
+                    let content = document.content.as_str();
+                    queue.extend(
+                        superclass_names(content, &document.tree)
+                            .into_iter()
+                            .map(|(name, _)| qualified_class_name(&name)),
+                    );
+                    method_declaration(content, &method_name)
+                }
+                None => {
+                    let Some(class) = reference_class(&class_name) else {
+                        continue;
+                    };
+                    queue.extend(class.superclass.map(str::to_string));
+                    class
+                        .method(&method_name)
+                        .map(|method| (method.signature.to_string(), Some(method.doc.to_string())))
+                }
+            };
+            let Some((signature, doc)) = found else {
+                continue;
+            };
+            let mut value =
+                format!("```objectscript\n{signature}\n```\n\nDeclared in `{class_name}`");
+            if let Some(doc) = doc {
+                value.push_str("\n\n---\n\n");
+                value.push_str(&documatic_markdown(&doc));
+            }
+            successful_exit("ProjectData", "get_class_reference_hover");
+            return Some((range, value));
+        }
+        generic_exit_statements("ProjectData", "get_class_reference_hover");
+        None
+    }
+
+    /// Build the signature help for the `$SYSTEM` method call around `point`.
+    pub fn get_system_api_signature_help(&self, url: &Url, point: Point) -> Option<SignatureHelp> {
+        start_of_function("ProjectData", "get_system_api_signature_help");
+        if !self.config.system_api_stubs {
+            generic_exit_statements("ProjectData", "get_system_api_signature_help");
+            return None;
+        }
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let help = system_api_signature_help(content, point_to_byte(content, point));
+        successful_exit("ProjectData", "get_system_api_signature_help");
+        help
+    }
+
+    /// Record a snapshot of the project's symbols, keeping at most
+    /// `Config::index_snapshot_limit` snapshots.
+    ///
+    /// Returns the id of the new snapshot, or `None` if snapshots are disabled.
+    pub fn take_snapshot(&mut self, label: String) -> Option<u64> {
+        let limit = self.config.index_snapshot_limit;
+        self.snapshots
+            .take(&self.global_semantic_model, label, limit)
+    }
+
+    /// List the retained snapshots as JSON, oldest first.
+    pub fn get_snapshots_json(&self) -> Value {
+        Value::Array(self.snapshots.list().map(|s| s.to_json()).collect())
+    }
+
+    /// Render what the project has indexed as JSON, for debugging: the classes of the global
+    /// semantic model with their members, the class table (class name -> `ClassId`), and the
+    /// overrides and effective methods of the override index. Names are sorted so dumps can be
+    /// diffed.
+    pub fn get_index_dump_json(&self) -> Value {
+        start_of_function("ProjectData", "get_index_dump_json");
+        let model = &self.global_semantic_model;
+        let class_name = |id: ClassId| model.classes.get(id).map(|class| class.name.as_str());
+        let class_names = |ids: &[ClassId]| -> Vec<&str> {
+            ids.iter().filter_map(|id| class_name(*id)).collect()
+        };
+        let sorted_names = |names: Vec<&String>| -> Vec<String> {
+            let mut names: Vec<String> = names.into_iter().cloned().collect();
+            names.sort();
+            names
+        };
+        let classes: Vec<Value> = model
+            .classes
+            .iter()
+            .map(|(id, class)| {
+                let url = self
+                    .class_defs
+                    .get(class.name.as_str())
+                    .and_then(|symbol_id| model.class_defs.get(*symbol_id))
+                    .map(|symbol| symbol.url.to_string());
+                let mut public_methods: Vec<&Method> = model
+                    .methods
+                    .get(&id)
+                    .map(|methods| methods.iter().collect())
+                    .unwrap_or_default();
+                public_methods.sort_by(|a, b| a.name.cmp(&b.name));
+                let public_methods: Vec<Value> = public_methods
+                    .into_iter()
+                    .map(|method| {
+                        json!({
+                            "name": method.name,
+                            "classMethod": method.method_type == MethodType::ClassMethod,
+                            "arguments": method.argument_declarations,
+                            "returnType": method.return_type_name,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "id": id.0,
+                    "generation": id.1,
+                    "name": class.name,
+                    "active": class.active,
+                    "url": url,
+                    "superclasses": class_names(&class.inherited_classes),
+                    "superclassNames": class.superclass_names,
+                    "imports": class_names(&class.imports),
+                    "includes": class.includes,
+                    "publicMethods": public_methods,
+                    "privateMethods": sorted_names(class.private_methods.keys().collect()),
+                    "publicProperties": sorted_names(class.public_properties.keys().collect()),
+                    "privateProperties": sorted_names(class.private_properties.keys().collect()),
+                    "parameters": sorted_names(class.parameters.keys().collect()),
+                })
+            })
+            .collect();
+        let class_table: serde_json::Map<String, Value> = self
+            .classes
+            .iter()
+            .map(|(name, id)| (name.to_string(), Value::from(id.0)))
+            .collect();
+        // methods as `Class.Method`
+        let method_name = |handle: MethodHandle| -> Option<String> {
+            let class = class_name(handle.class())?;
+            let method = self.get_method_ref_name(&handle.to_method_ref())?;
+            Some(format!("{class}.{method}"))
+        };
+        let mut overrides: Vec<(String, String)> = self
+            .override_index
+            .overrides
+            .iter()
+            .filter_map(|(method_ref, overridden)| {
+                let method = MethodHandle::from_method_ref(*method_ref)?;
+                Some((method_name(method)?, method_name(*overridden)?))
+            })
+            .collect();
+        overrides.sort();
+        let overrides: Vec<Value> = overrides
+            .into_iter()
+            .map(|(method, overridden)| json!({ "method": method, "overrides": overridden }))
+            .collect();
+        let effective_methods: serde_json::Map<String, Value> = self
+            .override_index
+            .effective_methods
+            .iter()
+            .filter_map(|(class_id, methods)| {
+                let methods: serde_json::Map<String, Value> = methods
+                    .iter()
+                    .filter_map(|(name, handle)| {
+                        Some((name.to_string(), Value::String(method_name(*handle)?)))
+                    })
+                    .collect();
+                Some((class_name(*class_id)?.to_string(), Value::Object(methods)))
+            })
+            .collect();
+        successful_exit("ProjectData", "get_index_dump_json");
+        json!({
+            "classes": classes,
+            "classTable": class_table,
+            "overrides": overrides,
+            "effectiveMethods": effective_methods,
+        })
+    }
+
+    /// Diff snapshot `from` against snapshot `to` as JSON, defaulting to the two most recent
+    /// snapshots.
+    ///
+    /// Returns `None` if a requested snapshot is not retained (or fewer than two exist).
+    pub fn get_snapshot_diff_json(&self, from: Option<u64>, to: Option<u64>) -> Option<Value> {
+        start_of_function("ProjectData", "get_snapshot_diff_json");
+        let (from, to) = match (from, to) {
+            (Some(from), Some(to)) => (from, to),
+            (Some(from), None) => (from, self.snapshots.latest_pair()?.1),
+            (None, _) => self.snapshots.latest_pair()?,
+        };
+        let Some(diff) = self.snapshots.diff(from, to) else {
+            generic_exit_statements("ProjectData", "get_snapshot_diff_json");
+            return None;
+        };
+        let mut value = diff.to_json();
+        if let Value::Object(map) = &mut value {
+            map.insert("from".to_string(), Value::from(from));
+            map.insert("to".to_string(), Value::from(to));
+        }
+        successful_exit("ProjectData", "get_snapshot_diff_json");
+        Some(value)
+    }
+
+    /// Compute the diagnostics to publish for the document at `url`.
+    ///
+    /// Syntax errors caused by unbalanced `##class(`, `&sql(`, or `$select(` constructs are reported
+    /// with targeted messages. Diagnostics of generated classes (see
+    /// `Config::generated_class_patterns`) are downgraded or suppressed according to
+    /// `Config::generated_class_severity`; their symbols are unaffected. Nothing is reported
+    /// inside the bodies of Python methods.
+    pub fn get_document_diagnostics(&self, url: &Url) -> Vec<Diagnostic> {
+        start_of_function("ProjectData", "get_document_diagnostics");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_document_diagnostics");
+            return Vec::new();
+        };
+        let mut diagnostics = syntax_diagnostics(document.content.as_str(), &document.tree);
+        if document.tree.root_node().has_error() {
+            let bracket_diagnostics = bracket_diagnostics(document.content.as_str());
+            // an unterminated construct derails the parse from its opener on, so its targeted
+            // message replaces the generic syntax errors that follow it
+            if let Some(first) = bracket_diagnostics.iter().map(|d| d.range.start).min() {
+                diagnostics.retain(|d| d.range.start < first);
+            }
+            diagnostics.extend(bracket_diagnostics);
+        }
+        diagnostics.extend(duplicate_member_diagnostics(
+            document.content.as_str(),
+            url,
+            &class_members(document.content.as_str(), &document.tree),
+        ));
+        let cycle = document
+            .class_id
+            .and_then(|class_id| self.override_index.inheritance_cycles.get(&class_id));
+        if let Some(cycle) = cycle {
+            let names: Vec<&str> = cycle
+                .iter()
+                .filter_map(|class_id| self.global_semantic_model.get_class(*class_id))
+                .map(|class| class.name.as_str())
+                .collect();
+            diagnostics.extend(inheritance_cycle_diagnostics(
+                document.content.as_str(),
+                &superclass_names(document.content.as_str(), &document.tree),
+                &names,
+            ));
+        }
+        let class = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id));
+        if self.config.enable_lint {
+            if let Some(class) = class {
+                diagnostics.extend(case_mismatch_diagnostics(
+                    document.content.as_str(),
+                    &class.method_calls,
+                    &self.classes,
+                    &self.override_index,
+                ));
+                diagnostics.extend(undefined_host_variable_diagnostics(
+                    document.content.as_str(),
+                    &private_host_variables(
+                        document.content.as_str(),
+                        &document.tree,
+                        class.is_procedure_block.unwrap_or(true),
+                    ),
+                    &document.scope_tree,
+                ));
+                let unused_variables = find_unused_variables(
+                    document.content.as_str(),
+                    &document.tree,
+                    class.is_procedure_block.unwrap_or(true),
+                );
+                // a variable never read is reported once, as unused, rather than as dead stores
+                let mut dead_stores = find_dead_stores(
+                    document.content.as_str(),
+                    &document.tree,
+                    class.is_procedure_block.unwrap_or(true),
+                );
+                dead_stores.retain(|store| {
+                    !unused_variables
+                        .iter()
+                        .any(|variable| variable.range == store.range)
+                });
+                diagnostics.extend(dead_store_diagnostics(
+                    document.content.as_str(),
+                    url,
+                    &dead_stores,
+                ));
+                diagnostics.extend(unused_variable_diagnostics(
+                    document.content.as_str(),
+                    &unused_variables,
+                ));
+                diagnostics.extend(unreachable_code_diagnostics(
+                    document.content.as_str(),
+                    &find_unreachable_code(document.content.as_str(), &document.tree),
+                ));
+                if let Some(class_id) = document.class_id {
+                    diagnostics.extend(member_collision_diagnostics(
+                        document.content.as_str(),
+                        &class_members(document.content.as_str(), &document.tree),
+                        &self.get_inherited_members(class_id),
+                    ));
+                    diagnostics.extend(dispatch_mismatch_diagnostics(
+                        document.content.as_str(),
+                        &self.get_dispatch_mismatches(class_id),
+                    ));
+                }
+                diagnostics.extend(arity_diagnostics(
+                    document.content.as_str(),
+                    &self.get_arity_mismatches(url),
+                ));
+                if self.config.lint_recursion {
+                    diagnostics.extend(recursion_diagnostics(
+                        document.content.as_str(),
+                        &self.get_recursive_methods(url),
+                    ));
+                }
+                if self.config.enable_strict_mode {
+                    diagnostics.extend(type_mismatch_diagnostics(
+                        document.content.as_str(),
+                        &self.get_type_mismatches(url),
+                    ));
+                }
+            }
+            if self.config.lint_unresolved_references {
+                diagnostics.extend(unresolved_diagnostics(
+                    document.content.as_str(),
+                    &self.get_unresolved_references(url),
+                ));
+            }
+            if self.config.system_api_stubs {
+                diagnostics.extend(system_api_diagnostics(
+                    document.content.as_str(),
+                    &find_system_references(document.content.as_str()),
+                ));
+            }
+            if self.config.max_method_complexity > 0 || self.config.max_method_nesting > 0 {
+                diagnostics.extend(complexity_diagnostics(
+                    document.content.as_str(),
+                    &method_metrics(document.content.as_str(), &document.tree),
+                    self.config.max_method_complexity,
+                    self.config.max_method_nesting,
+                ));
+            }
+        }
+        if self.config.lint_debug_writes {
+            let writes = find_debug_writes(
+                document.content.as_str(),
+                &document.tree,
+                self.config.debug_write_replacement.as_str(),
+            );
+            diagnostics.extend(debug_write_diagnostics(document.content.as_str(), &writes));
+        }
+        // Python method bodies aren't ObjectScript, so nothing reported inside them is meaningful
+        let python_ranges: Vec<LspRange> = self
+            .get_python_bodies(url)
+            .iter()
+            .map(|body| ts_range_to_lsp_range(document.content.as_str(), body.range))
+            .collect();
+        diagnostics.retain(|diagnostic| {
+            !python_ranges.iter().any(|range| {
+                range.start <= diagnostic.range.start && diagnostic.range.end <= range.end
+            })
+        });
+        let generated_by = class.is_some_and(|class| class.generated_by);
+        if is_generated_class(
+            &self.config,
+            document.class_name.as_str(),
+            url,
+            generated_by,
+        ) {
+            successful_exit("ProjectData", "get_document_diagnostics");
+            return downgrade_generated_diagnostics(
+                diagnostics,
+                self.config.generated_class_severity,
+            );
+        }
+        successful_exit("ProjectData", "get_document_diagnostics");
+        diagnostics
+    }
+
+    /// Collect the references in `url` that can't be resolved to a class, method, include file,
+    /// or routine of the workspace, in document order.
+    ///
+    /// System (`%`) names are skipped, as are method calls on classes inheriting from a class
+    /// outside the workspace, whose methods aren't known. Class names without a package are
+    /// also looked up in the package of the document's class and in its imported packages.
+    pub fn get_unresolved_references(&self, url: &Url) -> Vec<UnresolvedReference> {
+        start_of_function("ProjectData", "get_unresolved_references");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_unresolved_references");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let resolve = self.class_reference_resolver(document);
+        let mut unresolved = Vec::new();
+        let mut push = |kind, name: String, range| {
+            if !is_system_name(&name) {
+                unresolved.push(UnresolvedReference { kind, name, range });
+            }
+        };
+        let include_files = include_names(content, &document.tree)
+            .into_iter()
+            .chain(include_generator_names(content, &document.tree));
+        for (name, range) in include_files {
+            if !self.has_routine_file(&name, FileType::Inc) {
+                push(UnresolvedKind::Include, name, range);
+            }
+        }
+        for (name, range) in superclass_names(content, &document.tree) {
+            if resolve(&name).is_none() {
+                push(UnresolvedKind::Class, name, range);
+            }
+        }
+        let class = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id));
+        let call_sites = class.map(|class| class.method_calls.as_slice());
+        for call_site in call_sites.unwrap_or_default() {
+            if is_system_name(&call_site.callee_class) {
+                continue;
+            }
+            match resolve(&call_site.callee_class) {
+                None => push(
+                    UnresolvedKind::Class,
+                    call_site.callee_class.clone(),
+                    call_site.call_range,
+                ),
+                Some(class_id) => {
+                    if call_site.callee_symbol.is_none()
+                        && !is_system_name(&call_site.callee_method)
+                        && !self.has_external_superclass(class_id)
+                        && !self.declares_method(class_id, &call_site.callee_method)
+                    {
+                        push(
+                            UnresolvedKind::Method,
+                            format!("{}.{}", call_site.callee_class, call_site.callee_method),
+                            call_site.call_range,
+                        );
+                    }
+                }
+            }
+        }
+        for (name, range) in routine_references(content) {
+            if !self.has_routine_file(&name, FileType::Mac) {
+                push(UnresolvedKind::Routine, name, range);
+            }
+        }
+        unresolved.sort_by_key(|reference| reference.range.start_byte);
+        successful_exit("ProjectData", "get_unresolved_references");
+        unresolved
+    }
+
+    /// Returns a function resolving the class names referenced in `document` to workspace
+    /// classes: names without a package are also looked up in the package of the document's
+    /// class and in its imported packages.
+    fn class_reference_resolver<'a>(
+        &'a self,
+        document: &Document,
+    ) -> impl Fn(&str) -> Option<ClassId> + 'a {
+        let packages: Vec<String> = document
+            .class_name
+            .rsplit_once('.')
+            .map(|(package, _)| package.to_string())
+            .into_iter()
+            .chain(
+                import_names(document.content.as_str(), &document.tree)
+                    .into_iter()
+                    .map(|(package, _)| package),
+            )
+            .collect();
+        move |name: &str| {
+            self.classes.get(name).copied().or_else(|| {
+                packages
+                    .iter()
+                    .filter(|_| !name.contains('.'))
+                    .find_map(|package| {
+                        self.classes
+                            .get(format!("{package}.{name}").as_str())
+                            .copied()
+                    })
+            })
+        }
+    }
+
+    /// Builds the quick fixes of the unresolved class names without a package in `url` that
+    /// overlap `range`: for each workspace class of that name, one importing its package and one
+    /// replacing the reference by its full name. Each fix is returned with the range of the
+    /// unresolved reference and its title.
+    ///
+    /// Returns an empty vector unless `lint_unresolved_references` is enabled.
+    pub fn get_class_name_fixes(
+        &self,
+        url: &Url,
+        range: LspRange,
+    ) -> Vec<(LspRange, String, Vec<TextEdit>)> {
+        start_of_function("ProjectData", "get_class_name_fixes");
+        if !self.config.lint_unresolved_references {
+            generic_exit_statements("ProjectData", "get_class_name_fixes");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_class_name_fixes");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let mut fixes = Vec::new();
+        for reference in self.get_unresolved_references(url) {
+            if reference.kind != UnresolvedKind::Class || reference.name.contains('.') {
+                continue;
+            }
+            let reference_range = ts_range_to_lsp_range(content, reference.range);
+            if reference_range.start > range.end || range.start > reference_range.end {
+                continue;
+            }
+            // a call's range covers the whole call, so find the class name in it
+            let Some(name_start) = content
+                .get(reference.range.start_byte..reference.range.end_byte)
+                .and_then(|text| text.find(reference.name.as_str()))
+                .map(|n| reference.range.start_byte + n)
+            else {
+                continue;
+            };
+            let name_range = ts_range_to_lsp_range(
+                content,
+                byte_range(content, name_start, name_start + reference.name.len()),
+            );
+            let mut candidates: Vec<&Name> = self
+                .classes
+                .keys()
+                .filter(|class_name| {
+                    class_name
+                        .rsplit_once('.')
+                        .is_some_and(|(_, short_name)| short_name == reference.name)
+                })
+                .collect();
+            candidates.sort();
+            for class_name in candidates {
+                let Some((package, _)) = class_name.rsplit_once('.') else {
+                    continue;
+                };
+                fixes.push((
+                    reference_range,
+                    format!("Add Import {package}"),
+                    vec![import_edit(content, &document.tree, package)],
+                ));
+                fixes.push((
+                    reference_range,
+                    format!("Change to {class_name}"),
+                    vec![TextEdit {
+                        range: name_range,
+                        new_text: class_name.to_string(),
+                    }],
+                ));
+            }
+        }
+        successful_exit("ProjectData", "get_class_name_fixes");
+        fixes
+    }
+
+    /// Builds the quick fixes of the calls in `url` overlapping `range` to methods their class
+    /// doesn't have: one renaming the method to each of the closest public methods of the class
+    /// (see `closest_names`). Each fix is returned with the range of the call and the suggested
+    /// name.
+    ///
+    /// Returns an empty vector unless `lint_unresolved_references` is enabled.
+    pub fn get_method_name_fixes(
+        &self,
+        url: &Url,
+        range: LspRange,
+    ) -> Vec<(LspRange, String, TextEdit)> {
+        start_of_function("ProjectData", "get_method_name_fixes");
+        if !self.config.lint_unresolved_references {
+            generic_exit_statements("ProjectData", "get_method_name_fixes");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_method_name_fixes");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let resolve = self.class_reference_resolver(document);
+        let mut fixes = Vec::new();
+        for reference in self.get_unresolved_references(url) {
+            if reference.kind != UnresolvedKind::Method {
+                continue;
+            }
+            let call_range = ts_range_to_lsp_range(content, reference.range);
+            if call_range.start > range.end || range.start > call_range.end {
+                continue;
+            }
+            let Some((class_name, method_name)) = reference.name.rsplit_once('.') else {
+                continue;
+            };
+            let Some(methods) = resolve(class_name)
+                .and_then(|class_id| self.override_index.effective_public_methods.get(&class_id))
+            else {
+                continue;
+            };
+            // the method name follows a `.` in the call, e.g. `##class(Pkg.Cls).Method()`
+            let call = content
+                .get(reference.range.start_byte..reference.range.end_byte)
+                .unwrap_or_default();
+            let name_start = call
+                .match_indices(&format!(".{method_name}"))
+                .map(|(n, _)| n + 1)
+                .find(|&n| {
+                    !call[n + method_name.len()..]
+                        .starts_with(|c: char| c.is_ascii_alphanumeric() || c == '%')
+                });
+            let Some(name_start) = name_start.map(|n| reference.range.start_byte + n) else {
+                continue;
+            };
+            let name_range = ts_range_to_lsp_range(
+                content,
+                byte_range(content, name_start, name_start + method_name.len()),
+            );
+            for name in closest_names(method_name, methods.keys().map(Name::as_str)) {
+                fixes.push((
+                    call_range,
+                    name.to_string(),
+                    TextEdit {
+                        range: name_range,
+                        new_text: name.to_string(),
+                    },
+                ));
+            }
+        }
+        successful_exit("ProjectData", "get_method_name_fixes");
+        fixes
+    }
+
+    /// Returns the full names of the unresolved classes referenced in `url` that overlap `range`,
+    /// each with the range of its reference, for the classes a quick fix can create.
+    ///
+    /// Returns an empty vector unless `lint_unresolved_references` is enabled.
+    pub fn get_missing_class_references(
+        &self,
+        url: &Url,
+        range: LspRange,
+    ) -> Vec<(LspRange, String)> {
+        start_of_function("ProjectData", "get_missing_class_references");
+        if !self.config.lint_unresolved_references {
+            generic_exit_statements("ProjectData", "get_missing_class_references");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_missing_class_references");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let references = self
+            .get_unresolved_references(url)
+            .into_iter()
+            .filter(|reference| {
+                reference.kind == UnresolvedKind::Class && is_class_name(&reference.name)
+            })
+            .map(|reference| {
+                (
+                    ts_range_to_lsp_range(content, reference.range),
+                    reference.name,
+                )
+            })
+            .filter(|(reference_range, _)| {
+                reference_range.start <= range.end && range.start <= reference_range.end
+            })
+            .collect();
+        successful_exit("ProjectData", "get_missing_class_references");
+        references
+    }
+
+    /// Returns the uri of the file a new class `class_name` referenced from `url` is created in
+    /// (see `new_class_url`), or `None` if the class exists already.
+    pub fn get_new_class_url(
+        &self,
+        url: &Url,
+        class_name: &str,
+        root: Option<&Url>,
+    ) -> Option<Url> {
+        if self.classes.contains_key(class_name) {
+            return None;
+        }
+        let document = self.get_document(url)?;
+        new_class_url(url, &document.class_name, class_name, root)
+    }
+
+    /// Collect the unresolved references of every class document (or only of `uri`), optionally
+    /// only of the kind labeled `kind`, sorted by document.
+    pub fn get_unresolved_table(
+        &self,
+        uri: Option<&Url>,
+        kind: Option<&str>,
+    ) -> Vec<(Url, UnresolvedReference)> {
+        start_of_function("ProjectData", "get_unresolved_table");
+        let mut urls: Vec<&Url> = self
+            .documents
+            .iter()
+            .filter(|(url, document)| {
+                document.file_type == FileType::Cls && uri.is_none_or(|uri| uri == *url)
+            })
+            .map(|(url, _)| url)
+            .collect();
+        urls.sort();
+        let table = urls
+            .into_iter()
+            .flat_map(|url| {
+                self.get_unresolved_references(url)
+                    .into_iter()
+                    .filter(|reference| kind.is_none_or(|kind| reference.kind.label() == kind))
+                    .map(|reference| (url.clone(), reference))
+            })
+            .collect();
+        successful_exit("ProjectData", "get_unresolved_table");
+        table
+    }
+
+    /// Returns `true` if the workspace has a routine (`FileType::Mac`) or include file
+    /// (`FileType::Inc`) named `name`.
+    fn has_routine_file(&self, name: &str, file_type: FileType) -> bool {
+        self.routine_files
+            .iter()
+            .any(|(path_name, ty)| *ty == file_type && routine_name_matches(path_name, name))
+    }
+
+    /// Returns the id of the class `name`, also finding a `%Library` class by its short name
+    /// (e.g. `%Persistent`).
+    fn get_class_id(&self, name: &str) -> Option<ClassId> {
+        self.classes
+            .get(name)
+            .or_else(|| self.classes.get(qualified_class_name(name).as_str()))
+            .copied()
+    }
+
+    /// Collect the full names of the classes the workspace extends or calls but doesn't
+    /// define, system classes included, sorted.
+    ///
+    /// Superclasses are taken from every class document, so classes downloaded from a server
+    /// (see `ProjectState::fetch_remote_classes`) contribute theirs too.
+    pub fn get_missing_class_names(&self) -> Vec<String> {
+        start_of_function("ProjectData", "get_missing_class_names");
+        let mut names = Vec::new();
+        for document in self.documents.values() {
+            if document.file_type != FileType::Cls {
+                continue;
+            }
+            let superclasses = superclass_names(document.content.as_str(), &document.tree)
+                .into_iter()
+                .map(|(name, _)| name);
+            let class = document
+                .class_id
+                .and_then(|class_id| self.global_semantic_model.classes.get(class_id));
+            let callees = class
+                .map(|class| class.method_calls.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .map(|call_site| call_site.callee_class.clone());
+            for name in superclasses.chain(callees) {
+                let name = qualified_class_name(&name);
+                if !name.is_empty()
+                    && !self.classes.contains_key(name.as_str())
+                    && !names.contains(&name)
+                {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        successful_exit("ProjectData", "get_missing_class_names");
+        names
+    }
+
+    /// Returns `true` if the class `class_name` was downloaded from a server, so its document
+    /// can't be edited.
+    pub fn is_read_only_class(&self, class_name: &str) -> bool {
+        self.get_class_url(class_name).is_some_and(is_read_only_url)
+    }
+
+    /// Fetch the url of the document defining the class `class_name`.
+    fn get_class_url(&self, class_name: &str) -> Option<&Url> {
+        let id = self.class_defs.get(class_name)?;
+        let symbol = self
+            .global_semantic_model
+            .get_class_symbol(*id, class_name)?;
+        Some(&symbol.url)
+    }
+
+    /// Fetch the document defining the class `class_name`.
+    fn get_class_document(&self, class_name: &str) -> Option<&Document> {
+        self.get_document(self.get_class_url(class_name)?)
+    }
+
+    /// Fetch the property `property_name` declared by the class `class_name` from the class's
+    /// local semantic model.
+    pub fn get_class_property(
+        &self,
+        class_name: &str,
+        property_name: &str,
+    ) -> Option<&ClassProperty> {
+        let class_id = self.classes.get(class_name)?;
+        let class = self.global_semantic_model.get_class(*class_id)?;
+        let property_id = class.get_property_id(property_name)?;
+        let local_semantic_model_id = self
+            .get_class_document(class_name)?
+            .local_semantic_model_id?;
+        self.global_semantic_model
+            .get_local_semantic(local_semantic_model_id)?
+            .get_property(*property_id)
+    }
+
+    /// Fetch the parameter `parameter_name` declared by the class `class_name` from the class's
+    /// local semantic model.
+    pub fn get_class_parameter(
+        &self,
+        class_name: &str,
+        parameter_name: &str,
+    ) -> Option<&ClassParameter> {
+        let class_id = self.classes.get(class_name)?;
+        let class = self.global_semantic_model.get_class(*class_id)?;
+        let parameter_id = class.get_parameter_id(parameter_name)?;
+        let local_semantic_model_id = self
+            .get_class_document(class_name)?
+            .local_semantic_model_id?;
+        self.global_semantic_model
+            .get_local_semantic(local_semantic_model_id)?
+            .get_parameter(*parameter_id)
+    }
+
+    /// Fetch the XData block `xdata_name` declared by the class `class_name` from the class's
+    /// local semantic model.
+    pub fn get_class_xdata(&self, class_name: &str, xdata_name: &str) -> Option<&ClassXData> {
+        let class_id = self.classes.get(class_name)?;
+        let class = self.global_semantic_model.get_class(*class_id)?;
+        let xdata_id = class.get_xdata_id(xdata_name)?;
+        let local_semantic_model_id = self
+            .get_class_document(class_name)?
+            .local_semantic_model_id?;
+        self.global_semantic_model
+            .get_local_semantic(local_semantic_model_id)?
+            .get_xdata(*xdata_id)
+    }
+
+    /// Fetch the index, foreign key, trigger, query, projection, or storage definition `name` of
+    /// kind `kind` declared by the class `class_name` from the class's local semantic model.
+    pub fn get_class_member_definition(
+        &self,
+        class_name: &str,
+        kind: MemberKind,
+        name: &str,
+    ) -> Option<&ClassMemberDefinition> {
+        let class_id = self.classes.get(class_name)?;
+        let class = self.global_semantic_model.get_class(*class_id)?;
+        let definition_id = class.get_member_definition_id(kind, name)?;
+        let local_semantic_model_id = self
+            .get_class_document(class_name)?
+            .local_semantic_model_id?;
+        self.global_semantic_model
+            .get_local_semantic(local_semantic_model_id)?
+            .get_member_definition(*definition_id)
+    }
+
+    /// Returns `true` if `class_id` or one of its superclasses extends a class that isn't in the
+    /// workspace (e.g. `%Persistent`), so it may inherit methods the server doesn't know.
+    fn has_external_superclass(&self, class_id: ClassId) -> bool {
+        let mut visited = Vec::new();
+        let mut queue = vec![class_id];
+        while let Some(class_id) = queue.pop() {
+            if visited.contains(&class_id) {
+                continue;
+            }
+            visited.push(class_id);
+            let Some(class) = self.global_semantic_model.get_class(class_id) else {
+                continue;
+            };
+            let Some(document) = self.get_class_document(class.name.as_str()) else {
+                continue;
+            };
+            for (name, _) in superclass_names(document.content.as_str(), &document.tree) {
+                match self.get_class_id(&name) {
+                    Some(superclass_id) => queue.push(superclass_id),
+                    None => return true,
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns `true` if `class_id` declares or inherits a method named `method_name`, private
+    /// methods included.
+    fn declares_method(&self, class_id: ClassId, method_name: &str) -> bool {
+        self.override_index
+            .effective_methods
+            .get(&class_id)
+            .is_some_and(|methods| methods.contains_key(method_name))
+    }
+
+    /// Returns the name of the method `method_ref` of its class.
+    fn get_method_ref_name(&self, method_ref: &MethodRef) -> Option<&str> {
+        let class = self.global_semantic_model.get_class(method_ref.class)?;
+        let name = if let Some(id) = method_ref.pub_id {
+            class
+                .public_methods
+                .iter()
+                .find(|(_, other)| **other == id)?
+                .0
+        } else {
+            let id = method_ref.priv_id?;
+            class
+                .private_methods
+                .iter()
+                .find(|(_, other)| **other == id)?
+                .0
+        };
+        Some(name.as_str())
+    }
+
+    /// Collect the methods of `class_id` overriding an inherited method of the other kind (class
+    /// vs instance method).
+    pub fn get_dispatch_mismatches(&self, class_id: ClassId) -> Vec<DispatchMismatch> {
+        start_of_function("ProjectData", "get_dispatch_mismatches");
+        let mut mismatches = Vec::new();
+        for (method_ref, (base, method_type)) in &self.override_index.dispatch_mismatches {
+            if method_ref.class != class_id {
+                continue;
+            }
+            let Some(name) = self.get_method_ref_name(method_ref) else {
+                continue;
+            };
+            let base_ref = base.to_method_ref();
+            let (Some((_, range)), Some((base_uri, base_range)), Some(base_class)) = (
+                self.get_method_ref_location(method_ref, name),
+                self.get_method_ref_location(&base_ref, name),
+                self.global_semantic_model.get_class(base.class()),
+            ) else {
+                generic_skipping_statements("get_dispatch_mismatches", name, "Method");
+                continue;
+            };
+            let Some(base_document) = self.get_document(&base_uri) else {
+                continue;
+            };
+            mismatches.push(DispatchMismatch {
+                name: name.to_string(),
+                method_type: method_type.clone(),
+                range,
+                base_class: base_class.name.clone(),
+                base_location: Location {
+                    range: ts_range_to_lsp_range(base_document.content.as_str(), base_range),
+                    uri: base_uri,
+                },
+            });
+        }
+        mismatches.sort_by_key(|mismatch| mismatch.range.start_byte);
+        successful_exit("ProjectData", "get_dispatch_mismatches");
+        mismatches
+    }
+
+    /// Describe the `Internal` and `ServerOnly` keywords of the member `member_name` declared in
+    /// the class in `url`, or `None` if it has neither.
+    pub fn get_member_visibility_note(&self, url: &Url, member_name: &str) -> Option<String> {
+        let document = self.get_document(url)?;
+        class_members(document.content.as_str(), &document.tree)
+            .into_iter()
+            .find(|member| member.name == member_name)?
+            .visibility
+            .hover_note(&document.class_name)
+    }
+
+    /// Describe how the method `method_name` of the class in `url` takes part in overriding: the
+    /// inherited method it overrides or shadows (public or private), and how many subclass
+    /// methods override it. Returns `None` if it neither overrides nor is overridden.
+    pub fn get_override_summary(&self, url: &Url, method_name: &str) -> Option<String> {
+        let class_id = self.get_document(url)?.class_id?;
+        let class = self.global_semantic_model.get_class(class_id)?;
+        let method_ref = MethodRef {
+            class: class_id,
+            pub_id: class.public_methods.get(method_name).copied(),
+            priv_id: class.private_methods.get(method_name).copied(),
+        };
+        let handle = MethodHandle::from_method_ref(method_ref)?;
+        let mut lines = Vec::new();
+        if let Some(base) = self.override_index.overrides.get(&method_ref) {
+            let base_class = &self.global_semantic_model.get_class(base.class())?.name;
+            let verb = match (handle, base) {
+                (MethodHandle::Local { .. }, MethodHandle::Local { .. }) => {
+                    "Shadows private method"
+                }
+                (_, MethodHandle::Local { .. }) => "Overrides private method",
+                (_, MethodHandle::Public(_)) => "Overrides",
+            };
+            let mut line = format!("{verb} `{base_class}.{method_name}`");
+            let dispatch_mismatches = &self.override_index.dispatch_mismatches;
+            if dispatch_mismatches.contains_key(&method_ref) {
+                line.push_str(" (class vs instance method mismatch)");
+            }
+            lines.push(line);
+        }
+        if let Some((owner, property)) = self.override_index.accessor_properties.get(&method_ref) {
+            let owner = &self.global_semantic_model.get_class(*owner)?.name;
+            lines.push(format!("Custom accessor of property `{owner}.{property}`"));
+        }
+        let overridden = self
+            .override_index
+            .overridden_by
+            .get(&handle)
+            .map_or(0, Vec::len);
+        if overridden > 0 {
+            lines.push(format!(
+                "Overridden in {overridden} subclass{}",
+                if overridden == 1 { "" } else { "es" }
+            ));
+        }
+        (!lines.is_empty()).then(|| lines.join("\n\n"))
+    }
+
+    /// Collect the members declared by every superclass of `class_id`, nearest superclass first.
+    fn get_inherited_members(&self, class_id: ClassId) -> Vec<InheritedMember> {
+        let mut inherited = Vec::new();
+        let mut visited = vec![class_id];
+        let mut queue: Vec<ClassId> = self
+            .global_semantic_model
+            .get_class(class_id)
+            .map(|class| class.inherited_classes.clone())
+            .unwrap_or_default();
+        let mut next = 0;
+        while next < queue.len() {
+            let ancestor_id = queue[next];
+            next += 1;
+            if visited.contains(&ancestor_id) {
+                continue;
+            }
+            visited.push(ancestor_id);
+            let Some(ancestor) = self.global_semantic_model.get_class(ancestor_id) else {
+                continue;
+            };
+            queue.extend(ancestor.inherited_classes.iter().copied());
+            let Some(url) = self
+                .class_defs
+                .get(ancestor.name.as_str())
+                .and_then(|id| {
+                    self.global_semantic_model
+                        .get_class_symbol(*id, ancestor.name.as_str())
+                })
+                .map(|symbol| symbol.url.clone())
+            else {
+                continue;
+            };
+            let Some(document) = self.get_document(&url) else {
+                continue;
+            };
+            let content = document.content.as_str();
+            for member in class_members(content, &document.tree) {
+                let location = Location {
+                    uri: url.clone(),
+                    range: ts_range_to_lsp_range(content, member.range),
+                };
+                inherited.push(InheritedMember {
+                    class_name: ancestor.name.clone(),
+                    member,
+                    location,
+                });
+            }
+        }
+        inherited
+    }
+
+    /// Return the uris of the documents open in the editor, whose diagnostics are kept up to
+    /// date.
+    pub fn get_open_document_urls(&self) -> Vec<Url> {
+        self.documents
+            .iter()
+            .filter(|(_, document)| document.version.is_some())
+            .map(|(url, _)| url.clone())
+            .collect()
+    }
+
+    /// Return the uris of the XML export archives that documents were extracted from.
+    pub fn get_archive_urls(&self) -> Vec<Url> {
+        let mut archives: Vec<Url> = Vec::new();
+        for map in self.export_line_maps.values() {
+            if !archives.contains(&map.archive) {
+                archives.push(map.archive.clone());
+            }
+        }
+        archives
+    }
+
+    /// Compute the diagnostics of every document extracted from the export `archive`, with
+    /// their ranges mapped back into the archive file.
+    pub fn get_archive_diagnostics(&self, archive: &Url) -> Vec<Diagnostic> {
+        start_of_function("ProjectData", "get_archive_diagnostics");
+        let mut diagnostics = Vec::new();
+        for (url, line_map) in &self.export_line_maps {
+            if &line_map.archive != archive {
+                continue;
+            }
+            diagnostics.extend(self.get_document_diagnostics(url).into_iter().map(
+                |mut diagnostic| {
+                    diagnostic.range = line_map.to_archive_range(diagnostic.range);
+                    diagnostic
+                },
+            ));
+        }
+        successful_exit("ProjectData", "get_archive_diagnostics");
+        diagnostics
+    }
+
+    /// Returns the quick fixes converting the debugging `write`/`zwrite` statements of `url`
+    /// that overlap `range` into the configured logging call.
+    ///
+    /// Returns an empty vector unless `lint_debug_writes` is enabled.
+    pub fn get_debug_write_fixes(&self, url: &Url, range: LspRange) -> Vec<(LspRange, String)> {
+        start_of_function("ProjectData", "get_debug_write_fixes");
+        if !self.config.lint_debug_writes {
+            generic_exit_statements("ProjectData", "get_debug_write_fixes");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_debug_write_fixes");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let fixes = find_debug_writes(
+            content,
+            &document.tree,
+            self.config.debug_write_replacement.as_str(),
+        )
+        .into_iter()
+        .filter_map(|write| {
+            let write_range = ts_range_to_lsp_range(content, write.range);
+            if write_range.start > range.end || range.start > write_range.end {
+                return None;
+            }
+            Some((write_range, write.replacement?))
+        })
+        .collect();
+        successful_exit("ProjectData", "get_debug_write_fixes");
+        fixes
+    }
+
+    /// Returns a stub overriding each method the class at `url` inherits, with its declaring
+    /// class and the edit inserting it before the class's closing brace, sorted by name.
+    ///
+    /// When `position` is on the name of an inherited method, only that method is returned;
+    /// otherwise stubs are only offered between the methods of the class body.
+    pub fn get_override_stubs(
+        &self,
+        url: &Url,
+        position: Position,
+    ) -> Vec<(Method, String, TextEdit)> {
+        start_of_function("ProjectData", "get_override_stubs");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_override_stubs");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let root = document.tree.root_node();
+        let (Some(class_definition), Some(class_id)) = (
+            find_class_definition(root),
+            self.classes.get(document.class_name.as_str()),
+        ) else {
+            generic_exit_statements("ProjectData", "get_override_stubs");
+            return Vec::new();
+        };
+        let Some(methods) = self.override_index.effective_public_methods.get(class_id) else {
+            generic_exit_statements("ProjectData", "get_override_stubs");
+            return Vec::new();
+        };
+        let mut inherited: Vec<(Method, String)> = methods
+            .values()
+            .filter(|method_ref| method_ref.class != *class_id)
+            .filter_map(|method_ref| {
+                let class = self.global_semantic_model.classes.get(method_ref.class)?;
+                let method = self.global_semantic_model.get_method(
+                    method_ref.class,
+                    class.name.as_str(),
+                    method_ref.id.0,
+                )?;
+                Some((method.clone(), class.name.clone()))
+            })
+            .collect();
+        inherited.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+        let offset = point_to_byte(content, position_to_point(content, position));
+        let word =
+            &content[word_at_offset(content, offset, |c| c.is_ascii_alphanumeric() || c == '%')];
+        if let Some(index) = inherited
+            .iter()
+            .position(|(method, _)| !word.is_empty() && method.name == word)
+        {
+            inherited = vec![inherited.swap_remove(index)];
+        } else {
+            let in_method = method_definition_nodes(root)
+                .iter()
+                .any(|(node, _)| node.byte_range().contains(&offset));
+            if in_method || !class_definition.byte_range().contains(&offset) {
+                successful_exit("ProjectData", "get_override_stubs");
+                return Vec::new();
+            }
+        }
+
+        // the class definition ends with its closing brace
+        let end = class_definition.end_position();
+        let brace = Point::new(end.row, end.column.saturating_sub(1));
+        let position = point_to_lsp_position(content, brace);
+        let stubs = inherited
+            .into_iter()
+            .map(|(method, class_name)| {
+                let edit = TextEdit {
+                    range: LspRange::new(position, position),
+                    new_text: format!("\n{}\n", method.override_stub()),
+                };
+                (method, class_name, edit)
+            })
+            .collect();
+        successful_exit("ProjectData", "get_override_stubs");
+        stubs
+    }
+
+    /// Collect a ctags entry for every class and class member defined in the project.
+    ///
+    /// Paths are made relative to `root` when the document is under it.
+    pub fn get_tag_entries(&self, root: Option<&Url>) -> Vec<TagEntry> {
+        start_of_function("ProjectData", "get_tag_entries");
+        let mut entries = Vec::new();
+        for (url, document) in &self.documents {
+            if document.file_type != FileType::Cls {
+                continue;
+            }
+            let Some(class_definition) = find_class_definition(document.tree.root_node()) else {
+                continue;
+            };
+            let path = root
+                .and_then(|root| root.make_relative(url))
+                .filter(|relative| !relative.starts_with("../"))
+                .unwrap_or_else(|| url.path().to_string());
+            entries.push(TagEntry {
+                name: document.class_name.clone(),
+                path: path.clone(),
+                line: class_definition.start_position().row + 1,
+                kind: TagKind::Class,
+                class_name: None,
+            });
+            for member in class_members(document.content.as_str(), &document.tree) {
+                entries.push(TagEntry {
+                    name: member.name,
+                    path: path.clone(),
+                    line: member.range.start_point.row + 1,
+                    kind: TagKind::Member(member.kind),
+                    class_name: Some(document.class_name.clone()),
+                });
+            }
+        }
+        successful_exit("ProjectData", "get_tag_entries");
+        entries
+    }
+
+    /// Finds the classes, public methods, other named class members, routine labels, and public
+    /// variables of the project whose qualified name (`MyApp.Person`, or `MyApp.Person:Greet`
+    /// for members and labels) fuzzy-matches `query` (see `fuzzy_score`), as `(score, symbol)`
+    /// pairs sorted best first. At most `workspace_symbol_limit` symbols are returned, unless the
+    /// limit is 0. Nothing is returned once `cancel` is cancelled.
+    pub fn get_workspace_symbols(
+        &self,
+        query: &str,
+        cancel: &CancellationToken,
+    ) -> Vec<(i32, SymbolInformation)> {
+        start_of_function("ProjectData", "get_workspace_symbols");
+        let mut candidates = Vec::new();
+        for (class_name, class_symbol_id) in &self.class_defs {
+            if cancel.is_cancelled() {
+                generic_exit_statements("ProjectData", "get_workspace_symbols");
+                return Vec::new();
+            }
+            let Some(class_symbol) = self
+                .global_semantic_model
+                .get_class_symbol(*class_symbol_id, class_name)
+                .filter(|class_symbol| class_symbol.alive)
+            else {
+                continue;
+            };
+            candidates.push((
+                class_name.to_string(),
+                class_name.to_string(),
+                None,
+                SymbolKind::CLASS,
+                class_symbol.url.clone(),
+                class_symbol.location,
+            ));
+            let methods = self.pub_method_defs.get(class_name).into_iter().flatten();
+            for (method_name, method_symbol_id) in methods {
+                let Some(method_symbol) = self.global_semantic_model.get_method_symbol(
+                    *class_symbol_id,
+                    class_name,
+                    *method_symbol_id,
+                ) else {
+                    continue;
+                };
+                candidates.push((
+                    format!("{class_name}:{method_name}"),
+                    method_name.to_string(),
+                    Some(class_name.to_string()),
+                    SymbolKind::METHOD,
+                    method_symbol.url.clone(),
+                    method_symbol.location,
+                ));
+            }
+            let Some(class) = self
+                .classes
+                .get(class_name)
+                .and_then(|class_id| self.global_semantic_model.get_class(*class_id))
+            else {
+                continue;
+            };
+            for (kind, name) in class.member_definitions.keys() {
+                let Some(definition) = self.get_class_member_definition(class_name, *kind, name)
+                else {
+                    continue;
+                };
+                candidates.push((
+                    format!("{class_name}:{name}"),
+                    name.clone(),
+                    Some(class_name.to_string()),
+                    kind.symbol_kind(),
+                    class_symbol.url.clone(),
+                    definition.range,
+                ));
+            }
+        }
+        for (url, routine) in &self.routines {
+            for label in &routine.labels {
+                candidates.push((
+                    format!("{}:{}", routine.name, label.name),
+                    label.name.clone(),
+                    Some(routine.name.clone()),
+                    SymbolKind::FUNCTION,
+                    url.clone(),
+                    label.range,
+                ));
+            }
+        }
+        for (variable_name, definitions) in &self.pub_var_defs {
+            for (class_name, variable_symbol_ids) in definitions {
+                let Some(class_symbol_id) = self.class_defs.get(class_name.as_str()) else {
+                    continue;
+                };
+                let Some(variable_symbol) = variable_symbol_ids.first().and_then(|id| {
+                    self.global_semantic_model
+                        .get_variable_symbol(class_symbol_id, *id, class_name)
+                }) else {
+                    continue;
+                };
+                candidates.push((
+                    format!("{class_name}:{variable_name}"),
+                    variable_name.clone(),
+                    Some(class_name.clone()),
+                    SymbolKind::VARIABLE,
+                    variable_symbol.url.clone(),
+                    variable_symbol.location,
+                ));
+            }
+        }
+
+        let mut symbols: Vec<(i32, SymbolInformation)> = candidates
+            .into_iter()
+            .take_while(|_| !cancel.is_cancelled())
+            .filter_map(|(qualified_name, name, container_name, kind, url, range)| {
+                let score = fuzzy_score(query, &qualified_name)?;
+                let content = match self.routines.get(&url) {
+                    Some(routine) => routine.content.as_str(),
+                    None => self.get_document(&url)?.content.as_str(),
+                };
+                #[allow(deprecated)]
+                let symbol = SymbolInformation {
+                    name,
+                    kind,
+                    tags: None,
+                    deprecated: None,
+                    location: Location {
+                        range: ts_range_to_lsp_range(content, range),
+                        uri: url,
+                    },
+                    container_name,
+                };
+                Some((score, symbol))
+            })
+            .collect();
+        symbols.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.container_name.cmp(&b.container_name))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        if cancel.is_cancelled() {
+            generic_exit_statements("ProjectData", "get_workspace_symbols");
+            return Vec::new();
+        }
+        if self.config.workspace_symbol_limit > 0 {
+            symbols.truncate(self.config.workspace_symbol_limit);
+        }
+        successful_exit("ProjectData", "get_workspace_symbols");
+        symbols
+    }
+
+    /// Count how often each class and public method of the project is referenced, from the
+    /// resolved call sites in the `ReferenceIndex` and the `Extends` lists.
+    ///
+    /// Paths are made relative to `root` when the document is under it.
+    pub fn get_usage_entries(&self, root: Option<&Url>) -> Vec<UsageEntry> {
+        start_of_function("ProjectData", "get_usage_entries");
+        let mut subclasses: HashMap<ClassId, usize> = HashMap::new();
+        let classes = self.global_semantic_model.classes.values();
+        for class in classes.filter(|class| class.active) {
+            for superclass in &class.inherited_classes {
+                *subclasses.entry(*superclass).or_default() += 1;
+            }
+        }
+        let mut entries = Vec::new();
+        for (url, document) in &self.documents {
+            let Some(class_id) = document.class_id else {
+                continue;
+            };
+            let Some(class) = self.global_semantic_model.get_class(class_id) else {
+                continue;
+            };
+            let Some(class_definition) = find_class_definition(document.tree.root_node()) else {
+                continue;
+            };
+            let path = root
+                .and_then(|root| root.make_relative(url))
+                .filter(|relative| !relative.starts_with("../"))
+                .unwrap_or_else(|| url.path().to_string());
+            let (mut class_references, mut class_external_references) = (0, 0);
+            for (method_name, &id) in &class.public_methods {
+                let method = PublicMethodRef {
+                    class: class_id,
+                    id,
+                };
+                let edges = self.reference_index.references(&method);
+                let external_references = edges
+                    .iter()
+                    .filter(|edge| edge.caller_class != class_id)
+                    .count();
+                class_references += edges.len();
+                class_external_references += external_references;
+                let method_ref = MethodRef {
+                    class: class_id,
+                    pub_id: Some(id),
+                    priv_id: None,
+                };
+                let line = self
+                    .get_method_ref_location(&method_ref, method_name)
+                    .map_or(0, |(_, range)| range.start_point.row + 1);
+                entries.push(UsageEntry {
+                    kind: UsageKind::Method,
+                    name: format!("{}.{method_name}", class.name),
+                    references: edges.len(),
+                    external_references,
+                    subclasses: None,
+                    path: path.clone(),
+                    line,
+                });
+            }
+            entries.push(UsageEntry {
+                kind: UsageKind::Class,
+                name: class.name.clone(),
+                references: class_references,
+                external_references: class_external_references,
+                subclasses: Some(subclasses.get(&class_id).copied().unwrap_or(0)),
+                path,
+                line: class_definition.start_position().row + 1,
+            });
+        }
+        successful_exit("ProjectData", "get_usage_entries");
+        entries
+    }
+
+    /// Build the outline of the class in `url`: the class, with its parameters, properties,
+    /// methods, and XData blocks as children, in source order. The range of each symbol covers
+    /// its whole declaration, and its selection range its name. The detail of a member lists its
+    /// `Internal` and `ServerOnly` keywords, and that of an XData block its `MimeType`.
+    ///
+    /// The outline of a routine lists its labels (see `routine_symbols`).
+    pub fn get_document_symbols(&self, url: &Url) -> Option<Vec<DocumentSymbol>> {
+        start_of_function("ProjectData", "get_document_symbols");
+        if let Some(routine) = self.routines.get(url) {
+            successful_exit("ProjectData", "get_document_symbols");
+            return Some(routine_symbols(routine));
+        }
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let class_definition = find_class_definition(document.tree.root_node())?;
+        let class_range = ts_range_to_lsp_range(content, class_definition.range());
+        let class_name_range = class_definition.named_child(1).map_or(class_range, |name| {
+            ts_range_to_lsp_range(content, name.range())
+        });
+        #[allow(deprecated)]
+        let symbol = |name, detail, kind, range: Range, selection_range: Range| DocumentSymbol {
+            name,
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: ts_range_to_lsp_range(content, range),
+            selection_range: ts_range_to_lsp_range(content, selection_range),
+            children: None,
+        };
+        let mut members: Vec<(usize, DocumentSymbol)> = class_members(content, &document.tree)
+            .into_iter()
+            .map(|member| {
+                let labels = member.visibility.labels();
+                let kind = member.kind.symbol_kind();
+                let detail = (!labels.is_empty()).then(|| labels.join(", "));
+                let start = member.declaration_range.start_byte;
+                (
+                    start,
+                    symbol(
+                        member.name,
+                        detail,
+                        kind,
+                        member.declaration_range,
+                        member.range,
+                    ),
+                )
+            })
+            .collect();
+        for (name, name_range, range) in xdata_blocks(content, &document.tree) {
+            let detail = self
+                .get_class_xdata(&document.class_name, &name)
+                .and_then(|xdata| xdata.mime_type.clone());
+            let xdata = symbol(name, detail, SymbolKind::STRUCT, range, name_range);
+            members.push((range.start_byte, xdata));
+        }
+        // the top-level names of Python bodies are listed under their methods
+        for body in self.get_python_bodies(url) {
+            if let Some((_, method)) = members
+                .iter_mut()
+                .find(|(_, member)| member.name == body.method_name)
+            {
+                method.children = Some(python_symbols(content, &body));
+            }
+        }
+        members.sort_by_key(|(start, _)| *start);
+        successful_exit("ProjectData", "get_document_symbols");
+        #[allow(deprecated)]
+        Some(vec![DocumentSymbol {
+            name: document.class_name.clone(),
+            detail: None,
+            kind: SymbolKind::CLASS,
+            tags: None,
+            deprecated: None,
+            range: class_range,
+            selection_range: class_name_range,
+            children: Some(members.into_iter().map(|(_, member)| member).collect()),
+        }])
+    }
+
+    /// Computes the folding ranges of the document at `url` (see `folding_ranges`).
+    pub fn get_folding_ranges(&self, url: &Url) -> Option<Vec<FoldingRange>> {
+        start_of_function("ProjectData", "get_folding_ranges");
+        let document = self.get_document(url)?;
+        let ranges = folding_ranges(document.content.as_str(), &document.tree);
+        successful_exit("ProjectData", "get_folding_ranges");
+        Some(ranges)
+    }
+
+    /// Computes the semantic tokens of the class in `url`: the tokens of its syntax tree (see
+    /// `syntax_tokens`), with the declarations of its members, and its local variables marked
+    /// `public` when they are `%` variables or public variables of the class. With a `range`, only
+    /// the tokens overlapping it are kept.
+    pub fn get_semantic_tokens(
+        &self,
+        url: &Url,
+        range: Option<LspRange>,
+    ) -> Option<Vec<SemanticToken>> {
+        start_of_function("ProjectData", "get_semantic_tokens");
+        let document = self.get_document(url)?;
+        let content = document.content.as_str();
+        let class_name = &document.class_name;
+        let is_public_variable = |name: &str| {
+            name.starts_with('%')
+                || self
+                    .pub_var_defs
+                    .get(name)
+                    .is_some_and(|classes| classes.contains_key(class_name))
+        };
+        let mut tokens = syntax_tokens(content, &document.tree, is_public_variable);
+        for member in class_members(content, &document.tree) {
+            let token_type = match member.kind {
+                MemberKind::Method | MemberKind::Query => TokenType::Method,
+                MemberKind::Property => TokenType::Property,
+                MemberKind::Parameter => TokenType::Parameter,
+                _ => continue,
+            };
+            tokens.push(RawToken::new(member.range, token_type, DECLARATION));
+        }
+        if let Some(range) = range {
+            let start = point_to_byte(content, position_to_point(content, range.start));
+            let end = point_to_byte(content, position_to_point(content, range.end));
+            tokens.retain(|token| token.range.start_byte < end && token.range.end_byte > start);
+        }
+        successful_exit("ProjectData", "get_semantic_tokens");
+        Some(encode_tokens(content, tokens))
+    }
+
+    /// Collects the members of the project's classes projected to SQL as stored procedures (see
+    /// `sql_projections`), keeping those named `query` when given.
+    pub fn get_sql_projections(&self, query: Option<&str>) -> Vec<(Url, SqlProjection)> {
+        start_of_function("ProjectData", "get_sql_projections");
+        let mut projections = Vec::new();
+        for (url, document) in &self.documents {
+            if document.file_type != FileType::Cls {
+                continue;
+            }
+            projections.extend(
+                sql_projections(
+                    document.content.as_str(),
+                    &document.tree,
+                    document.class_name.as_str(),
+                )
+                .into_iter()
+                .filter(|projection| query.is_none_or(|query| projection.matches(query)))
+                .map(|projection| (url.clone(), projection)),
+            );
+        }
+        successful_exit("ProjectData", "get_sql_projections");
+        projections
+    }
+
+    /// Finds the class projected to the SQL table `table`: the class whose table name (see
+    /// `Class::sql_table_name`) matches, in the schema derived from its package when `table` is
+    /// qualified. An unqualified name prefers a class of the default `SQLUser` schema, then takes
+    /// the first matching class by name. SQL identifiers are case-insensitive.
+    ///
+    /// Superclasses like `%Persistent` are usually outside the workspace, so the class isn't
+    /// checked to be persistent.
+    pub fn get_sql_table_class(&self, table: &str) -> Option<String> {
+        let table = table.replace('"', "");
+        let (schema, table_name) = match table.rsplit_once('.') {
+            Some((schema, table_name)) => (Some(schema), table_name),
+            None => (None, table.as_str()),
+        };
+        let mut candidates: Vec<&Name> = self
+            .classes
+            .iter()
+            .filter(|(class_name, class_id)| {
+                self.global_semantic_model
+                    .get_class(**class_id)
+                    .is_some_and(|class| {
+                        class.active && class.sql_table_name().eq_ignore_ascii_case(table_name)
+                    })
+                    && schema.is_none_or(|schema| {
+                        default_schema(class_name).eq_ignore_ascii_case(schema)
+                    })
+            })
+            .map(|(class_name, _)| class_name)
+            .collect();
+        candidates.sort_by_key(|class_name| (default_schema(class_name) != "SQLUser", *class_name));
+        candidates.first().map(|class_name| class_name.to_string())
+    }
+
+    /// Finds the property of `class_name`, declared or inherited, projected to the SQL column
+    /// `column` (see `ClassProperty::sql_field_name`), with the class declaring it.
+    pub fn get_sql_column_property(
+        &self,
+        class_name: &str,
+        column: &str,
+    ) -> Option<(ClassProperty, String)> {
+        self.get_completion_properties(class_name)
+            .into_iter()
+            .find(|(property, _)| property.sql_field_name().eq_ignore_ascii_case(column))
+    }
+
+    /// Maps an identifier of embedded SQL to the class it refers to and, for a column, the
+    /// property projecting it with its declaring class. `references` are the identifiers of the
+    /// same scan (see `find_sql_references`): a column is looked up in the table its qualifier
+    /// names or aliases, or else in each table of its statement in turn.
+    pub fn resolve_sql_reference(
+        &self,
+        references: &[SqlReference],
+        reference: &SqlReference,
+    ) -> Option<(String, Option<(ClassProperty, String)>)> {
+        if reference.kind == SqlReferenceKind::Table {
+            return Some((self.get_sql_table_class(&reference.name)?, None));
+        }
+        let tables = references.iter().filter(|table| {
+            table.kind == SqlReferenceKind::Table
+                && table.block == reference.block
+                && reference.qualifier.as_ref().is_none_or(|qualifier| {
+                    table
+                        .qualifier
+                        .as_ref()
+                        .is_some_and(|alias| alias.eq_ignore_ascii_case(qualifier))
+                        || table.name.eq_ignore_ascii_case(qualifier)
+                        || table
+                            .name
+                            .rsplit('.')
+                            .next()
+                            .is_some_and(|name| name.eq_ignore_ascii_case(qualifier))
+                })
+        });
+        tables
+            .filter_map(|table| self.get_sql_table_class(&table.name))
+            .find_map(|class_name| {
+                let property = self.get_sql_column_property(&class_name, &reference.name)?;
+                Some((class_name, Some(property)))
+            })
+    }
+
+    /// Build the hover of the table or column of embedded SQL at `point` in the document at
+    /// `url`, naming the class projecting it and, for a column, the property's declaration.
+    pub fn get_embedded_sql_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_embedded_sql_hover");
+        let content = self.get_document(url)?.content.as_str();
+        let references = find_sql_references(content, 0..content.len());
+        let reference = references.iter().find(|reference| {
+            reference.range.start_point <= point && point <= reference.range.end_point
+        })?;
+        let (class_name, property) = self.resolve_sql_reference(&references, reference)?;
+        let class_id = self.classes.get(class_name.as_str())?;
+        let class = self.global_semantic_model.get_class(*class_id)?;
+        let table = format!("{}.{}", default_schema(&class_name), class.sql_table_name());
+        let value = match property {
+            None => format!("**SQL table** `{table}`\n\nProjected from class `{class_name}`"),
+            Some((property, owner)) => format!(
+                "**SQL column** `{}` of `{table}`\n\n```objectscript\n{}\n```\n\nDeclared in `{owner}`",
+                property.sql_field_name(),
+                property.signature()
+            ),
+        };
+        successful_exit("ProjectData", "get_embedded_sql_hover");
+        Some((reference.range, value))
+    }
+
+    /// Returns the public methods `class_name` declares or inherits, sorted by name; with
+    /// `class_methods_only`, instance methods are left out.
+    pub fn get_completion_methods(
+        &self,
+        class_name: &str,
+        class_methods_only: bool,
+    ) -> Vec<Method> {
+        let Some(methods) = self
+            .classes
+            .get(class_name)
+            .and_then(|class_id| self.override_index.effective_public_methods.get(class_id))
+        else {
+            return Vec::new();
+        };
+        let mut methods: Vec<Method> = methods
+            .values()
+            .filter_map(|method_ref| {
+                let class = self.global_semantic_model.classes.get(method_ref.class)?;
+                self.global_semantic_model
+                    .get_method(method_ref.class, class.name.as_str(), method_ref.id.0)
+                    .cloned()
+            })
+            .filter(|method| !class_methods_only || method.method_type == MethodType::ClassMethod)
+            .collect();
+        methods.sort_by(|a, b| a.name.cmp(&b.name));
+        methods
+    }
+
+    /// Returns the names of the members of `kind` that `class_name` declares or inherits, each
+    /// with its declaring class; a member redeclared by a subclass is listed once.
+    fn get_visible_members(&self, class_name: &str, kind: MemberKind) -> Vec<(String, String)> {
+        let Some(&class_id) = self.classes.get(class_name) else {
+            return Vec::new();
+        };
+        let declared = self
+            .get_class_document(class_name)
+            .map(|document| class_members(document.content.as_str(), &document.tree))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|member| (class_name.to_string(), member));
+        let inherited = self
+            .get_inherited_members(class_id)
+            .into_iter()
+            .map(|inherited| (inherited.class_name, inherited.member));
+        let mut members: Vec<(String, String)> = Vec::new();
+        for (owner, member) in declared.chain(inherited) {
+            if member.kind == kind && !members.iter().any(|(name, _)| *name == member.name) {
+                members.push((member.name, owner));
+            }
+        }
+        members
+    }
+
+    /// Returns the properties `class_name` declares or inherits with their declaring class,
+    /// sorted by name; a property redeclared by a subclass is listed once.
+    pub fn get_completion_properties(&self, class_name: &str) -> Vec<(ClassProperty, String)> {
+        let mut properties: Vec<(ClassProperty, String)> = self
+            .get_visible_members(class_name, MemberKind::Property)
+            .into_iter()
+            .filter_map(|(name, owner)| {
+                Some((self.get_class_property(&owner, &name)?.clone(), owner))
+            })
+            .collect();
+        properties.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        properties
+    }
+
+    /// Returns the parameters `class_name` declares or inherits with their declaring class,
+    /// sorted by name; a parameter redeclared by a subclass is listed once.
+    pub fn get_completion_parameters(&self, class_name: &str) -> Vec<(ClassParameter, String)> {
+        let mut parameters: Vec<(ClassParameter, String)> = self
+            .get_visible_members(class_name, MemberKind::Parameter)
+            .into_iter()
+            .filter_map(|(name, owner)| {
+                Some((self.get_class_parameter(&owner, &name)?.clone(), owner))
+            })
+            .collect();
+        parameters.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        parameters
+    }
+
+    /// Infers the class of the object held by the local variable `variable` at `point` in the
+    /// document at `url` (see `infer_oref_type`). Names without a package are looked up in the
+    /// package of the class that uses them; returns `None` unless the class is in the workspace.
+    pub fn get_oref_class(&self, url: &Url, point: Point, variable: &str) -> Option<String> {
+        start_of_function("ProjectData", "get_oref_class");
+        let document = self.get_document(url)?;
+        // `type_name` as written in `context_class`, resolved to the name of a workspace class
+        let resolve = |type_name: &str, context_class: &str| {
+            let qualified = context_class
+                .rsplit_once('.')
+                .map(|(package, _)| format!("{package}.{type_name}"));
+            std::iter::once(type_name.to_string())
+                .chain(qualified)
+                .chain(std::iter::once(qualified_class_name(type_name)))
+                .find(|class_name| self.classes.contains_key(class_name.as_str()))
+        };
+        let member_type = |type_name: &str, member_name: &str, kind: ChainMember| {
+            let class_name = resolve(type_name, document.class_name.as_str())?;
+            let (owner, member_type) = match kind {
+                ChainMember::Method => {
+                    let method_ref = self
+                        .override_index
+                        .effective_public_methods
+                        .get(self.classes.get(class_name.as_str())?)?
+                        .get(member_name)?;
+                    let class = self.global_semantic_model.get_class(method_ref.class)?;
+                    let method = self.global_semantic_model.get_method(
+                        method_ref.class,
+                        class.name.as_str(),
+                        method_ref.id.0,
+                    )?;
+                    (class.name.clone(), method.return_type_name.clone()?)
+                }
+                ChainMember::Property => {
+                    let (_, owner) = self
+                        .get_visible_members(&class_name, MemberKind::Property)
+                        .into_iter()
+                        .find(|(name, _)| name == member_name)?;
+                    let property = self.get_class_property(&owner, member_name)?;
+                    (owner, property.property_type.clone()?)
+                }
+            };
+            Some(resolve(&member_type, &owner).unwrap_or(member_type))
+        };
+        let Some(type_name) = infer_oref_type(
+            document.content.as_str(),
+            &document.tree,
+            point,
+            variable,
+            document.class_name.as_str(),
+            member_type,
+        ) else {
+            generic_exit_statements("ProjectData", "get_oref_class");
+            return None;
+        };
+        let class_name = resolve(&type_name, document.class_name.as_str());
+        successful_exit("ProjectData", "get_oref_class");
+        class_name
+    }
+
+    /// Re-scans the macros of the include file at `url` (see `include_macros`); other files are
+    /// ignored.
+    pub fn update_include_macros(&mut self, url: &Url, content: &str) {
+        if let Some((name, FileType::Inc)) = routine_path_name(url) {
+            let mut file = include_macros(content);
+            file.url = Some(url.clone());
+            self.include_macros.insert(name, file);
+        }
+    }
+
+    /// Re-scans the labels of the routine at `url` (see `RoutineModel`); other files are
+    /// ignored.
+    pub fn update_routine(&mut self, url: &Url, content: &str) {
+        if let Some((name, FileType::Mac)) = routine_path_name(url) {
+            self.routines
+                .insert(url.clone(), RoutineModel::new(name, content.to_string()));
+        }
+    }
+
+    /// Returns the content of the class document or routine at `url`.
+    fn get_source_content(&self, url: &Url) -> Option<&str> {
+        match self.routines.get(url) {
+            Some(routine) => Some(routine.content.as_str()),
+            None => Some(self.documents.get(url)?.content.as_str()),
+        }
+    }
+
+    /// Resolves the label call at `position` in the document or routine at `url` (`do Label`,
+    /// `$$Label^Routine()`, `do ^Routine`) to the routine of the workspace it calls and the
+    /// label, `None` for `^Routine` alone. Also returns the byte range of the call.
+    ///
+    /// A label without a routine is only resolved inside a routine, among its own labels.
+    fn get_label_call_at(
+        &self,
+        url: &Url,
+        position: Position,
+    ) -> Option<(
+        &Url,
+        &RoutineModel,
+        Option<&RoutineLabel>,
+        std::ops::Range<usize>,
+    )> {
+        let content = self.get_source_content(url)?;
+        let offset = point_to_byte(content, position_to_point(content, position));
+        let reference = label_reference_at(content, offset)?;
+        let (routine_url, routine) = match &reference.routine {
+            Some(name) => {
+                // a `^Name` that isn't called is a global
+                let is_call = routine_references(content)
+                    .iter()
+                    .any(|(_, range)| range.end_byte == reference.range.end);
+                if !is_call {
+                    return None;
+                }
+                self.routines
+                    .iter()
+                    .find(|(_, routine)| routine_name_matches(&routine.name, name))?
+            }
+            None => self.routines.get_key_value(url)?,
+        };
+        let label = match &reference.label {
+            Some(label) => Some(routine.get_label(label)?),
+            None => None,
+        };
+        Some((routine_url, routine, label, reference.range))
+    }
+
+    /// Resolves the label call at `position` in the document or routine at `url` to the
+    /// label's declaration (see `get_label_call_at`); `^Routine` alone goes to the start of the
+    /// routine.
+    pub fn get_label_definition(&self, url: &Url, position: Position) -> Option<Location> {
+        start_of_function("ProjectData", "get_label_definition");
+        let Some((routine_url, routine, label, _)) = self.get_label_call_at(url, position) else {
+            generic_exit_statements("ProjectData", "get_label_definition");
+            return None;
+        };
+        let range = label.map_or_else(LspRange::default, |label| {
+            ts_range_to_lsp_range(&routine.content, label.range)
+        });
+        successful_exit("ProjectData", "get_label_definition");
+        Some(Location {
+            uri: routine_url.clone(),
+            range,
+        })
+    }
+
+    /// Build the hover of the label call at `position` in the document or routine at `url`
+    /// (see `get_label_call_at`): the label with its parameters and the routine declaring it.
+    pub fn get_label_hover(&self, url: &Url, position: Position) -> Option<(LspRange, String)> {
+        start_of_function("ProjectData", "get_label_hover");
+        let Some((_, routine, label, call_range)) = self.get_label_call_at(url, position) else {
+            generic_exit_statements("ProjectData", "get_label_hover");
+            return None;
+        };
+        let value = match label {
+            Some(label) => format!(
+                "```objectscript\n{}\n```\n\nLabel of routine `{}`",
+                label.signature(),
+                routine.name
+            ),
+            None => format!("Routine `{}`", routine.name),
+        };
+        let content = self.get_source_content(url)?;
+        let range = byte_range(content, call_range.start, call_range.end);
+        successful_exit("ProjectData", "get_label_hover");
+        Some((ts_range_to_lsp_range(content, range), value))
+    }
+
+    /// Find the calls of the label at `position` in the document or routine at `url` (see
+    /// `get_label_call_at`) across the class documents and routines of the workspace: the
+    /// `Label^Routine` calls, and the calls without a routine inside the routine itself. The
+    /// label's declaration is included if `include_declaration` is set. Nothing is returned once
+    /// `cancel` is cancelled.
+    pub fn get_label_references(
+        &self,
+        url: &Url,
+        position: Position,
+        include_declaration: bool,
+        cancel: &CancellationToken,
+    ) -> Vec<Location> {
+        start_of_function("ProjectData", "get_label_references");
+        let Some((routine_url, routine, Some(label), _)) = self.get_label_call_at(url, position)
+        else {
+            generic_exit_statements("ProjectData", "get_label_references");
+            return Vec::new();
+        };
+        let sources = self
+            .documents
+            .iter()
+            .map(|(url, document)| (url, document.content.as_str()))
+            .chain(
+                self.routines
+                    .iter()
+                    .map(|(url, routine)| (url, routine.content.as_str())),
+            );
+        let mut locations = Vec::new();
+        for (source_url, content) in sources {
+            if cancel.is_cancelled() {
+                generic_exit_statements("ProjectData", "get_label_references");
+                return Vec::new();
+            }
+            let mut ranges: Vec<Range> = routine_label_calls(content)
+                .into_iter()
+                .filter(|(called_label, routine_name, _)| {
+                    called_label.as_deref() == Some(label.name.as_str())
+                        && routine_name_matches(&routine.name, routine_name)
+                })
+                .map(|(_, _, range)| range)
+                .collect();
+            if source_url == routine_url {
+                ranges.extend(
+                    local_label_calls(content)
+                        .into_iter()
+                        .filter(|(name, _)| *name == label.name)
+                        .map(|(_, range)| range),
+                );
+                if include_declaration {
+                    ranges.push(label.range);
+                }
+            }
+            ranges.sort_by_key(|range| range.start_byte);
+            locations.extend(ranges.into_iter().map(|range| Location {
+                uri: source_url.clone(),
+                range: ts_range_to_lsp_range(content, range),
+            }));
+        }
+        successful_exit("ProjectData", "get_label_references");
+        locations
+    }
+
+    /// Finds the ranges edited together with the label name at `position` in the routine at
+    /// `url`: the label's declaration and its calls without a routine (`do Label`,
+    /// `goto Label`, ...) inside the routine. `None` if the cursor isn't on one of them.
+    pub fn get_linked_label_ranges(&self, url: &Url, position: Position) -> Option<Vec<LspRange>> {
+        start_of_function("ProjectData", "get_linked_label_ranges");
+        let Some(routine) = self.routines.get(url) else {
+            generic_exit_statements("ProjectData", "get_linked_label_ranges");
+            return None;
+        };
+        let content = routine.content.as_str();
+        let offset = point_to_byte(content, position_to_point(content, position));
+        let label = label_reference_at(content, offset)
+            .filter(|reference| reference.routine.is_none())
+            .and_then(|reference| routine.get_label(reference.label.as_deref()?));
+        let Some(label) = label else {
+            generic_exit_statements("ProjectData", "get_linked_label_ranges");
+            return None;
+        };
+        let mut ranges: Vec<Range> = local_label_calls(content)
+            .into_iter()
+            .filter(|(name, _)| *name == label.name)
+            .map(|(_, range)| range)
+            .collect();
+        ranges.push(label.range);
+        // a name that merely spells the label, e.g. a variable, isn't linked
+        if !ranges
+            .iter()
+            .any(|range| range.start_byte <= offset && offset <= range.end_byte)
+        {
+            generic_exit_statements("ProjectData", "get_linked_label_ranges");
+            return None;
+        }
+        ranges.sort_by_key(|range| range.start_byte);
+        successful_exit("ProjectData", "get_linked_label_ranges");
+        Some(
+            ranges
+                .into_iter()
+                .map(|range| ts_range_to_lsp_range(content, range))
+                .collect(),
+        )
+    }
+
+    /// Resolves the include file `name`, as written in an `Include` statement or `#include`
+    /// line, to the dotted path of an `.inc` file of the workspace.
+    fn resolve_include_file(&self, name: &str) -> Option<&String> {
+        self.include_macros
+            .keys()
+            .find(|path_name| routine_name_matches(path_name, name))
+    }
+
+    /// Follows the include chain of the class `class_name`: the include files named by its
+    /// `Include` statements and by those of its superclasses, then the files those `#include`
+    /// in turn, depth first.
+    ///
+    /// Returns each file reached as its dotted path in the workspace paired with its name as
+    /// written, once; names that don't resolve to a workspace file are skipped.
+    pub fn get_include_chain(&self, class_name: &str) -> Vec<(&String, String)> {
+        start_of_function("ProjectData", "get_include_chain");
+        let mut include_files = Vec::new();
+        let mut visited_classes = Vec::new();
+        let mut class_names = vec![class_name];
+        while let Some(class_name) = class_names.pop() {
+            if visited_classes.contains(&class_name) {
+                continue;
+            }
+            visited_classes.push(class_name);
+            let Some(class) = self
+                .classes
+                .get(class_name)
+                .and_then(|class_id| self.global_semantic_model.get_class(*class_id))
+            else {
+                continue;
+            };
+            include_files.extend(class.includes.iter().cloned());
+            class_names.extend(class.superclass_names.iter().map(String::as_str));
+        }
+        let chain = self.follow_includes(include_files);
+        successful_exit("ProjectData", "get_include_chain");
+        chain
+    }
+
+    /// Resolves the include files `names` and the files they `#include` in turn, depth first,
+    /// to their dotted paths in the workspace, each paired with its name as written.
+    fn follow_includes(&self, mut include_files: Vec<String>) -> Vec<(&String, String)> {
+        // follow the files in the order they are named
+        include_files.reverse();
+        let mut chain: Vec<(&String, String)> = Vec::new();
+        while let Some(name) = include_files.pop() {
+            let Some(path_name) = self.resolve_include_file(&name) else {
+                continue;
+            };
+            if chain.iter().any(|(visited, _)| *visited == path_name) {
+                continue;
+            }
+            if let Some(file) = self.include_macros.get(path_name) {
+                include_files.extend(file.includes.iter().rev().cloned());
+            }
+            chain.push((path_name, name));
+        }
+        chain
+    }
+
+    /// Finds the definition of the macro `name` as seen from the document or routine at `url`,
+    /// returning it with the url of the file defining it.
+    ///
+    /// A routine's own macros come first, then those of the include chain of the class (see
+    /// `get_include_chain`) or of the routine's `#include`s. Macros defined elsewhere in the
+    /// workspace are a fallback, searched by file path.
+    fn find_macro_definition(&self, url: &Url, name: &str) -> Option<(&MacroDefinition, &Url)> {
+        let (own_macros, chain) = match self.routines.get_key_value(url) {
+            Some((routine_url, routine)) => (
+                Some((&routine.macros, routine_url)),
+                self.follow_includes(routine.macros.includes.clone()),
+            ),
+            None => (
+                None,
+                self.get_include_chain(&self.documents.get(url)?.class_name),
+            ),
+        };
+        let chain_files = chain
+            .into_iter()
+            .filter_map(|(path_name, _)| self.include_macros.get(path_name));
+        let mut other_files: Vec<(&String, &IncludeMacros)> = self.include_macros.iter().collect();
+        other_files.sort_by_key(|(path_name, _)| *path_name);
+        let files = chain_files.chain(other_files.into_iter().map(|(_, file)| file));
+        own_macros
+            .into_iter()
+            .chain(files.filter_map(|file| Some((file, file.url.as_ref()?))))
+            .find_map(|(file, file_url)| {
+                let definition = file.macros.iter().find(|macro_| macro_.name == name)?;
+                Some((definition, file_url))
+            })
+    }
+
+    /// Return the `#define` line of the `$$$Name` macro at `position` in the document or
+    /// routine at `url` (see `find_macro_definition`).
+    pub fn get_macro_definition(&self, url: &Url, position: Position) -> Option<Location> {
+        start_of_function("ProjectData", "get_macro_definition");
+        let content = match self.routines.get(url) {
+            Some(routine) => routine.content.as_str(),
+            None => self.documents.get(url)?.content.as_str(),
+        };
+        let offset = point_to_byte(content, position_to_point(content, position));
+        let (name, _) = macro_name_at(content, offset)?;
+        let (definition, file_url) = self.find_macro_definition(url, &name)?;
+        let start = Position::new(definition.line as u32, 0);
+        successful_exit("ProjectData", "get_macro_definition");
+        Some(Location {
+            uri: file_url.clone(),
+            range: LspRange::new(start, start),
+        })
+    }
+
+    /// Find the bodies of the Python methods of the class document at `url` (see
+    /// `python_bodies`), given the class's own or inherited `Language` keyword.
+    pub fn get_python_bodies(&self, url: &Url) -> Vec<PythonBody> {
+        let Some(document) = self.get_document(url) else {
+            return Vec::new();
+        };
+        let class_language = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.get_class(class_id))
+            .and_then(|class| class.default_language.as_ref());
+        python_bodies(document.content.as_str(), &document.tree, class_language)
+    }
+
+    /// Build the hover of a name defined at the top level of the Python method body at `point`
+    /// in the document at `url`, showing the line defining it.
+    pub fn get_python_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_python_hover");
+        let content = self.get_document(url)?.content.as_str();
+        let body = self
+            .get_python_bodies(url)
+            .into_iter()
+            .find(|body| body.range.start_point <= point && point <= body.range.end_point)?;
+        let offset = point_to_byte(content, point);
+        let std::ops::Range { start, end } =
+            word_at_offset(content, offset, |c| c == '_' || c.is_alphanumeric());
+        let name = &content[start..end];
+        let identifier = body
+            .identifiers
+            .iter()
+            .find(|identifier| identifier.name == name)?;
+        let value = format!(
+            "```python\n{}\n```\n\nDefined in Python method `{}`",
+            identifier.definition, body.method_name
+        );
+        successful_exit("ProjectData", "get_python_hover");
+        Some((byte_range(content, start, end), value))
+    }
+
+    /// Build the hover of the `$$$Name` macro at `point` in the document at `url`, showing its
+    /// `#define` directive and the file defining it.
+    pub fn get_macro_hover(&self, url: &Url, point: Point) -> Option<(Range, String)> {
+        start_of_function("ProjectData", "get_macro_hover");
+        let content = self.get_document(url)?.content.as_str();
+        let (name, range) = macro_name_at(content, point_to_byte(content, point))?;
+        let (definition, file_url) = self.find_macro_definition(url, &name)?;
+        let file_name = file_url.path().rsplit('/').next().unwrap_or_default();
+        let value = format!(
+            "```objectscript\n{}\n```\n\nDefined in `{file_name}`",
+            definition.directive()
+        );
+        successful_exit("ProjectData", "get_macro_hover");
+        Some((byte_range(content, range.start, range.end), value))
+    }
+
+    /// Returns the macros visible in the class document at `url`, each paired with the name of
+    /// the include file defining it.
+    ///
+    /// These are the macros of the files of the class's include chain (see
+    /// `get_include_chain`). When several files define a macro, the first one found wins.
+    pub fn get_visible_macros(&self, url: &Url) -> Vec<(&MacroDefinition, String)> {
+        start_of_function("ProjectData", "get_visible_macros");
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_visible_macros");
+            return Vec::new();
+        };
+        let mut macros: Vec<(&MacroDefinition, String)> = Vec::new();
+        for (path_name, name) in self.get_include_chain(&document.class_name) {
+            let Some(file) = self.include_macros.get(path_name) else {
+                continue;
+            };
+            for definition in &file.macros {
+                if !macros
+                    .iter()
+                    .any(|(known, _)| known.name == definition.name)
+                {
+                    macros.push((definition, name.clone()));
+                }
+            }
+        }
+        successful_exit("ProjectData", "get_visible_macros");
+        macros
+    }
+
+    /// Returns the edits applying every auto-fixable diagnostic of `url` (the `source.fixAll`
+    /// action): the debug-write quick fixes, the removal of unused variables (see
+    /// `get_unused_variable_removals`), and the imports of unresolved class names (see
+    /// `get_import_fixes`), in document order.
+    ///
+    /// Edits overlapping an earlier one are left for the next run, since a workspace edit can't
+    /// hold overlapping edits.
+    pub fn get_fix_all_edits(&self, url: &Url) -> Vec<TextEdit> {
+        let whole_document = LspRange {
+            start: Position::new(0, 0),
+            end: Position::new(u32::MAX, 0),
+        };
+        let mut candidates: Vec<TextEdit> = self
+            .get_debug_write_fixes(url, whole_document)
+            .into_iter()
+            .map(|(range, new_text)| TextEdit { range, new_text })
+            .chain(self.get_unused_variable_removals(url))
+            .chain(self.get_import_fixes(url))
+            .collect();
+        candidates.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        let mut edits: Vec<TextEdit> = Vec::new();
+        for edit in candidates {
+            if edits
+                .last()
+                .is_some_and(|last| edit.range.start < last.range.end)
+            {
+                continue;
+            }
+            edits.push(edit);
+        }
+        edits
+    }
+
+    /// Returns the edits removing the assignments of the variables of `url` that are set but
+    /// never used (see `find_unused_variables`) whose values can't have side effects.
+    ///
+    /// Returns an empty vector unless `enable_lint` is enabled.
+    pub fn get_unused_variable_removals(&self, url: &Url) -> Vec<TextEdit> {
+        start_of_function("ProjectData", "get_unused_variable_removals");
+        let Some(document) = self.get_document(url).filter(|_| self.config.enable_lint) else {
+            generic_exit_statements("ProjectData", "get_unused_variable_removals");
+            return Vec::new();
+        };
+        let Some(class) = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.classes.get(class_id))
+        else {
+            generic_exit_statements("ProjectData", "get_unused_variable_removals");
+            return Vec::new();
+        };
+        let content = document.content.as_str();
+        let edits = find_unused_variables(
+            content,
+            &document.tree,
+            class.is_procedure_block.unwrap_or(true),
+        )
+        .into_iter()
+        .filter_map(|variable| {
+            Some(TextEdit {
+                range: ts_range_to_lsp_range(content, variable.removal?),
+                new_text: String::new(),
+            })
+        })
+        .collect();
+        successful_exit("ProjectData", "get_unused_variable_removals");
+        edits
+    }
+
+    /// Returns the edits importing the package of each unresolved class name without a package
+    /// in `url` that exactly one workspace class has, one edit per package.
+    ///
+    /// Returns an empty vector unless `lint_unresolved_references` is enabled.
+    pub fn get_import_fixes(&self, url: &Url) -> Vec<TextEdit> {
+        start_of_function("ProjectData", "get_import_fixes");
+        let Some(document) = self
+            .get_document(url)
+            .filter(|_| self.config.lint_unresolved_references)
+        else {
+            generic_exit_statements("ProjectData", "get_import_fixes");
+            return Vec::new();
+        };
+        let mut packages: Vec<&str> = Vec::new();
+        for reference in self.get_unresolved_references(url) {
+            if reference.kind != UnresolvedKind::Class || reference.name.contains('.') {
+                continue;
+            }
+            let mut candidates = self.classes.keys().filter_map(|class_name| {
+                class_name
+                    .rsplit_once('.')
+                    .filter(|(_, short_name)| *short_name == reference.name)
+                    .map(|(package, _)| package)
+            });
+            // ambiguous names are left to the quick fixes of `get_class_name_fixes`
+            if let (Some(package), None) = (candidates.next(), candidates.next()) {
+                if !packages.contains(&package) {
+                    packages.push(package);
+                }
+            }
+        }
+        packages.sort();
+        let edits = packages
+            .into_iter()
+            .map(|package| import_edit(document.content.as_str(), &document.tree, package))
+            .collect();
+        successful_exit("ProjectData", "get_import_fixes");
+        edits
+    }
+
+    /// Builds the edits renaming the package `from` (and its sub-packages) to `to`: class
+    /// headers, `Extends` and `Import` lists, `##class()` and `As` references in every class, and
+    /// the moves of the class files.
+    ///
+    /// Only class documents are indexed, so references from routines and include files aren't
+    /// renamed.
+    pub fn get_package_rename(&self, from: &str, to: &str) -> PackageRename {
+        start_of_function("ProjectData", "get_package_rename");
+        let mut rename = PackageRename {
+            from: from.to_string(),
+            to: to.to_string(),
+            ..Default::default()
+        };
+        for (url, document) in &self.documents {
+            // classes downloaded from a server can't be edited
+            if document.file_type != FileType::Cls || is_read_only_url(url) {
+                continue;
+            }
+            let edits = package_rename_edits(document.content.as_str(), from, to);
+            if !edits.is_empty() {
+                rename.edits.push((url.clone(), edits));
+            }
+            let Some(new_class_name) = renamed_class_name(&document.class_name, from, to) else {
+                continue;
+            };
+            // a class moving under `from` is renamed too, so its old name is no conflict
+            if self.classes.contains_key(new_class_name.as_str())
+                && renamed_class_name(&new_class_name, from, to).is_none()
+            {
+                rename.conflicts.push(new_class_name.clone());
+            }
+            let Some(new_path) =
+                renamed_class_path(url.path(), &document.class_name, &new_class_name)
+            else {
+                continue;
+            };
+            let mut new_url = url.clone();
+            new_url.set_path(&new_path);
+            rename.file_renames.push((url.clone(), new_url));
+        }
+        rename.edits.sort_by(|a, b| a.0.cmp(&b.0));
+        rename.file_renames.sort();
+        rename.conflicts.sort();
+        successful_exit("ProjectData", "get_package_rename");
+        rename
+    }
+
+    /// Builds the edits renaming the class `from` to `to`: its header, `Extends` lists,
+    /// `##class()`, `As` and `Of` references in every class (see `class_references`), and the
+    /// move of its file.
+    ///
+    /// As for `get_package_rename`, references from routines and include files aren't renamed.
+    pub fn get_class_rename(&self, from: &str, to: &str) -> PackageRename {
+        start_of_function("ProjectData", "get_class_rename");
+        let mut rename = PackageRename {
+            from: from.to_string(),
+            to: to.to_string(),
+            ..Default::default()
+        };
+        if self.classes.contains_key(to) {
+            rename.conflicts.push(to.to_string());
+        }
+        let package = from.rsplit_once('.').map_or("", |(package, _)| package);
+        for (url, document) in &self.documents {
+            if document.file_type != FileType::Cls || is_read_only_url(url) {
+                continue;
+            }
+            // names without a package refer to classes of the same or an imported package
+            let short_names = document
+                .class_name
+                .rsplit_once('.')
+                .is_some_and(|(document_package, _)| document_package == package)
+                || imports_package(document.content.as_str(), package);
+            let edits = class_rename_edits(document.content.as_str(), from, to, short_names);
+            if !edits.is_empty() {
+                rename.edits.push((url.clone(), edits));
+            }
+            if document.class_name != from {
+                continue;
+            }
+            if let Some(new_path) = renamed_class_path(url.path(), from, to) {
+                let mut new_url = url.clone();
+                new_url.set_path(&new_path);
+                rename.file_renames.push((url.clone(), new_url));
+            }
+        }
+        rename.edits.sort_by(|a, b| a.0.cmp(&b.0));
+        successful_exit("ProjectData", "get_class_rename");
+        rename
+    }
+
+    /// Heuristically resolves the method `method_name` called on an object reference whose class
+    /// can't be inferred, by matching the name against the public methods of every class in the
+    /// workspace.
+    ///
+    /// Returns `(class name, url, range)` of each candidate definition, sorted by class name, or
+    /// an empty vector unless `heuristic_oref_methods` is enabled.
+    pub fn get_heuristic_method_candidates(&self, method_name: &str) -> Vec<(String, Url, Range)> {
+        start_of_function("ProjectData", "get_heuristic_method_candidates");
+        if !self.config.heuristic_oref_methods {
+            generic_exit_statements("ProjectData", "get_heuristic_method_candidates");
+            return Vec::new();
+        }
+        let mut candidates: Vec<(String, Url, Range)> = self
+            .pub_method_defs
+            .iter()
+            .filter(|(_, methods)| methods.contains_key(method_name))
+            .filter_map(|(class_name, _)| {
+                let class_symbol_id = self.class_defs.get(class_name).copied()?;
+                let sym =
+                    self.get_public_method_symbol(class_name, method_name, class_symbol_id)?;
+                Some((class_name.to_string(), sym.url.clone(), sym.location))
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+        successful_exit("ProjectData", "get_heuristic_method_candidates");
+        candidates
+    }
+
+    /// Returns the edits expanding abbreviated commands in `url`, limited to the commands starting
+    /// on `rows` when they are given.
+    ///
+    /// Returns an empty vector unless both `enable_formatting` and `expand_command_abbreviations`
+    /// are enabled.
+    pub fn get_command_abbreviation_edits(
+        &self,
+        url: &Url,
+        rows: Option<RangeInclusive<usize>>,
+    ) -> Vec<TextEdit> {
+        start_of_function("ProjectData", "get_command_abbreviation_edits");
+        if !(self.config.enable_formatting && self.config.expand_command_abbreviations) {
+            generic_exit_statements("ProjectData", "get_command_abbreviation_edits");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_command_abbreviation_edits");
+            return Vec::new();
+        };
+        // classes downloaded from a server can't be edited
+        if is_read_only_url(url) {
+            generic_exit_statements("ProjectData", "get_command_abbreviation_edits");
+            return Vec::new();
+        }
+        let edits = command_abbreviation_edits(document.content.as_str(), &document.tree, rows);
+        successful_exit("ProjectData", "get_command_abbreviation_edits");
+        edits
+    }
+
+    /// Returns the edits indenting `row` of `url` to the level of its block, or with
+    /// `closed_block` the lines of the block closed by the `}` typed on `row` (see
+    /// `block_indentation_edits`).
+    ///
+    /// Returns an empty vector unless both `enable_formatting` and `indent_on_type` are enabled.
+    pub fn get_indentation_edits(
+        &self,
+        url: &Url,
+        row: usize,
+        closed_block: bool,
+        indent: &str,
+    ) -> Vec<TextEdit> {
+        start_of_function("ProjectData", "get_indentation_edits");
+        if !(self.config.enable_formatting && self.config.indent_on_type) || is_read_only_url(url) {
+            generic_exit_statements("ProjectData", "get_indentation_edits");
+            return Vec::new();
+        }
+        let Some(document) = self.get_document(url) else {
+            generic_exit_statements("ProjectData", "get_indentation_edits");
+            return Vec::new();
+        };
+        let class_language = document
+            .class_id
+            .and_then(|class_id| self.global_semantic_model.get_class(class_id))
+            .and_then(|class| class.default_language.as_ref());
+        let edits = block_indentation_edits(
+            document.content.as_str(),
+            &document.tree,
+            class_language,
+            row,
+            closed_block,
+            indent,
+        );
+        successful_exit("ProjectData", "get_indentation_edits");
+        edits
+    }
+
+    /// Return the number of call sites in the workspace that resolve to `method_ref`.
+    ///
+    /// Served from the incrementally maintained `ReferenceIndex`, so this is O(1).
+    pub fn get_method_reference_count(&self, method_ref: &PublicMethodRef) -> usize {
+        self.reference_index.reference_count(method_ref)
+    }
+
+    /// Return the locations of every call site that resolves to `method_ref`.
+    ///
+    /// Each edge in the `ReferenceIndex` is mapped back to its caller's document url; the cost is
+    /// proportional to the number of references, not the size of the workspace.
+    pub fn get_method_references(&self, method_ref: &PublicMethodRef) -> Vec<(Url, Range)> {
+        start_of_function("ProjectData", "get_method_references");
+        let mut locations = Vec::new();
+        for edge in self.reference_index.references(method_ref) {
+            let Some(class) = self.global_semantic_model.get_class(edge.caller_class) else {
+                generic_skipping_statements(
+                    "get_method_references",
+                    edge.caller_method.as_str(),
+                    "Reference in method",
+                );
+                continue;
+            };
+            let Some(&class_symbol_id) = self.class_defs.get(class.name.as_str()) else {
+                generic_skipping_statements("get_method_references", class.name.as_str(), "Class");
+                continue;
+            };
+            let Some(class_symbol) = self
+                .global_semantic_model
+                .get_class_symbol(class_symbol_id, class.name.as_str())
+            else {
+                generic_skipping_statements("get_method_references", class.name.as_str(), "Class");
+                continue;
+            };
+            locations.push((class_symbol.url.clone(), edge.call_range));
+        }
+        successful_exit("ProjectData", "get_method_references");
+        locations
+    }
+}
+
+/// A source file read, and parsed if it is a class, by a worker of
+/// `ProjectState::index_workspace_scope`.
+enum LoadedFile {
+    Include {
+        url: Url,
+        code: String,
+    },
+    Routine {
+        url: Url,
+        code: String,
+    },
+    Class {
+        url: Url,
+        code: String,
+        tree: Tree,
+        class_name: String,
+    },
+}
+
+/// Reads the source file at `url` from `archive_items` or `sources`, and parses it with
+/// `cls_parser` if it is a class. Files that aren't ObjectScript sources, can't be read, or
+/// don't define a class are skipped.
+fn load_source_file(
+    url: &Url,
+    archive_items: &MemorySourceProvider,
+    sources: &dyn SourceProvider,
+    cls_parser: &mut Parser,
+) -> Option<LoadedFile> {
+    let (_, ext) = url.path().rsplit_once('.')?;
+    let filetype = match ext {
+        "cls" => FileType::Cls,
+        "inc" => FileType::Inc,
+        "mac" => FileType::Mac,
+        _ => return None,
+    };
+    let code = match archive_items
+        .read_file(url)
+        .or_else(|_| sources.read_file(url))
+    {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: Failed to read file contents: {url}: {e}");
+            generic_skipping_statements(
+                "index_workspace_scope",
+                url.as_str(),
+                "File contents for the following url",
+            );
+            return None;
+        }
+    };
+    let url = url.clone();
+    match filetype {
+        FileType::Inc => return Some(LoadedFile::Include { url, code }),
+        FileType::Mac => return Some(LoadedFile::Routine { url, code }),
+        FileType::Cls => {}
+    }
+
+    let Some(tree) = cls_parser.parse(&code, None) else {
+        eprintln!("Failed to parse file: {url}");
+        generic_skipping_statements("index_workspace_scope", code.as_str(), "File contents");
+        return None;
+    };
+    let Some(class_name) = get_class_name_from_root(code.as_str(), tree.root_node()) else {
+        eprintln!("No class Name");
+        return None;
+    };
+    Some(LoadedFile::Class {
+        url,
+        code,
+        tree,
+        class_name,
+    })
+}
+
+impl ProjectState {
+    /// Create a new `ProjectState` with default configuration and empty indexing state.
+    ///
+    /// Initializes shared parsers, an empty `ProjectData` store, and leaves `project_root_path`
+    /// unset (expected to be populated during LSP initialization).
+    pub fn new() -> Self {
+        Self {
+            project_root_path: OnceLock::new(),
+            project_root_uri: OnceLock::new(),
+            source_provider: OnceLock::new(),
+            parsers: WorkspaceParsers::new(),
+            file_events: Mutex::new(BulkChangeDetector::new()),
+            reindexing: AtomicBool::new(false),
+            reindex_pending: AtomicBool::new(false),
+            index_progress: IndexProgress::new(),
+            response_cache: ResponseCache::new(),
+            semantic_tokens: SemanticTokensCache::new(),
+            data: RwLock::new(ProjectData {
                 config: Config::default(),
                 documents: HashMap::new(),
                 global_semantic_model: GlobalSemanticModel::new(),
@@ -1771,10 +6256,49 @@ impl ProjectState {
                 pub_method_defs: HashMap::new(),
                 pub_var_defs: HashMap::new(),
                 override_index: OverrideIndex::new(),
+                reference_index: ReferenceIndex::new(),
+                export_line_maps: HashMap::new(),
+                snapshots: IndexSnapshots::new(),
+                routine_files: HashMap::new(),
+                include_macros: HashMap::new(),
+                routines: HashMap::new(),
             }),
         }
     }
 
+    /// Lock the project data for writing.
+    ///
+    /// Every change to the project goes through here, so the cached responses are cleared while
+    /// the lock is held, before any reader can see the change.
+    pub fn data_mut(&self) -> RwLockWriteGuard<'_, ProjectData> {
+        let data = self.data.write();
+        self.response_cache.clear();
+        data
+    }
+
+    /// Return the semantic tokens of the document at `url`, reusing the ones last sent if
+    /// neither the document nor the project changed since, and record them as the base of the
+    /// next delta request.
+    pub fn semantic_tokens(&self, url: &Url) -> Option<CachedTokens> {
+        start_of_function("ProjectState", "semantic_tokens");
+        // taken first, so a change while computing makes the next request recompute
+        let generation = self.response_cache.generation();
+        let data = self.data.read();
+        let version = data.get_document(url)?.version;
+        if let Some(cached) = self.semantic_tokens.get(url) {
+            if cached.version == version && cached.generation == generation {
+                successful_exit("ProjectState", "semantic_tokens");
+                return Some(cached);
+            }
+        }
+        let computed = data.get_semantic_tokens(url, None)?;
+        let tokens = self
+            .semantic_tokens
+            .insert(url, version, generation, computed);
+        successful_exit("ProjectState", "semantic_tokens");
+        Some(tokens)
+    }
+
     /// Handle an LSP `textDocument/didOpen` by parsing and committing the document.
     ///
     /// Parses the text with the appropriate Tree-sitter grammar, derives the class name for `.cls`
@@ -1789,6 +6313,18 @@ impl ProjectState {
         version: i32,
     ) {
         start_of_function("ProjectState", "handle_document_opened");
+        if file_type == FileType::Inc {
+            // include files are only scanned for their macros
+            self.data_mut().update_include_macros(&url, &text);
+            successful_exit("ProjectState", "handle_document_opened");
+            return;
+        }
+        if file_type == FileType::Mac {
+            // routines are only scanned for their labels
+            self.data_mut().update_routine(&url, &text);
+            successful_exit("ProjectState", "handle_document_opened");
+            return;
+        }
         // Parse OUTSIDE lock
         let tree = if file_type == FileType::Cls {
             match self.parsers.cls.lock().parse(&text, None) {
@@ -1820,7 +6356,7 @@ impl ProjectState {
         };
 
         // Commit INSIDE one lock
-        let mut data = self.data.write();
+        let mut data = self.data_mut();
 
         let existing_snapshot = data
             .documents
@@ -1831,7 +6367,7 @@ impl ProjectState {
             None => {
                 data.add_document(
                     url.clone(),
-                    text,
+                    text.into(),
                     tree,
                     file_type,
                     class_name,
@@ -1841,8 +6377,8 @@ impl ProjectState {
                 data.build_inheritance_and_variables(Some(url), Vec::new());
             }
             Some((old_text, old_type)) => {
-                if old_text != text || old_type != file_type {
-                    data.update_document(url, tree, file_type, version, &text);
+                if old_text.as_str() != text || old_type != file_type {
+                    data.update_document(url, tree, file_type, version, text.into());
                 } else {
                     if let Some(doc) = data.documents.get_mut(&url) {
                         doc.version = Some(version);
@@ -1855,10 +6391,19 @@ impl ProjectState {
     }
 
     /// Wrapper to read document info from the inner `ProjectData`.
-    pub fn get_document_info(&self, url: &Url) -> Option<(FileType, String, i32, Tree)> {
+    pub fn get_document_info(&self, url: &Url) -> Option<(FileType, TextBuffer, i32, Tree)> {
         self.data.read().get_document_info(url)
     }
 
+    /// Remove the document at `url` from the project; see `ProjectData::remove_document`.
+    ///
+    /// Returns `false` if the document isn't tracked.
+    pub fn remove_document(&self, url: &Url) -> bool {
+        let removed = self.data_mut().remove_document(url);
+        self.semantic_tokens.remove(url);
+        removed
+    }
+
     /// Wrapper to update a document inside the inner `ProjectData`
     pub fn update_document(
         &self,
@@ -1866,15 +6411,442 @@ impl ProjectState {
         tree: Tree,
         file_type: FileType,
         version: i32,
-        content: &str,
+        code: TextBuffer,
     ) {
         self.data
             .write()
-            .update_document(url, tree, file_type, version, content);
+            .update_document(url, tree, file_type, version, code);
+    }
+
+    /// Index every source file `sources` lists under `root`.
+    ///
+    /// XML export archives are expanded into one document per exported class or routine (see
+    /// `parse_export`), indexed under `ExportItem::url`. The files are read, and class files
+    /// parsed, by a pool of worker threads with a parser each; the class documents are then
+    /// inserted into the document store if absent, in the order the files were listed, and
+    /// include files and routines are scanned for their macros and labels. After the scan,
+    /// inheritance and variable information is built once. File access may block (e.g. on a
+    /// remote `SourceProvider`), so call this from a blocking thread.
+    pub fn index_workspace_scope(&self, sources: &dyn SourceProvider, root: &Url) {
+        start_of_function("ProjectState", "index_workspace_scope");
+        if Parser::new()
+            .set_language(&LANGUAGE_OBJECTSCRIPT.into())
+            .is_err()
+        {
+            eprintln!("Failed to load ObjectScript grammar");
+            generic_exit_statements("ProjectState", "index_workspace_scope");
+            return;
+        }
+
+        // expand export archives into in-memory documents so they index like any other source
+        let mut archive_items = MemorySourceProvider::new();
+        let mut files = Vec::new();
+        for url in sources.list_files(root) {
+            if !is_archive_url(&url) {
+                files.push(url);
+                continue;
+            }
+            let archive = match sources.read_file(&url) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error: Failed to read export archive: {url}: {e}");
+                    continue;
+                }
+            };
+            let mut data = self.data_mut();
+            data.export_line_maps.retain(|_, map| map.archive != url);
+            for item in parse_export(&archive) {
+                let item_url = item.url(&url);
+                data.export_line_maps
+                    .insert(item_url.clone(), ExportLineMap::new(url.clone(), &item));
+                archive_items.insert(item_url.clone(), item.content);
+                files.push(item_url);
+            }
+        }
+
+        for url in &files {
+            if let Some((name, file_type)) = routine_path_name(url) {
+                self.data_mut().routine_files.insert(name, file_type);
+            }
+        }
+
+        // read and parse the files on a pool of workers, each with its own parser
+        self.index_progress.start(files.len());
+        let next_file = AtomicUsize::new(0);
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |workers| workers.get())
+            .min(files.len())
+            .max(1);
+        let mut loaded: Vec<(usize, LoadedFile)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut cls_parser = Parser::new();
+                        if cls_parser
+                            .set_language(&LANGUAGE_OBJECTSCRIPT.into())
+                            .is_err()
+                        {
+                            eprintln!("Failed to load ObjectScript grammar");
+                            return Vec::new();
+                        }
+                        let mut loaded = Vec::new();
+                        loop {
+                            let index = next_file.fetch_add(1, Ordering::Relaxed);
+                            let Some(url) = files.get(index) else {
+                                break;
+                            };
+                            self.index_progress.file_indexed();
+                            let file =
+                                load_source_file(url, &archive_items, sources, &mut cls_parser);
+                            loaded.extend(file.map(|file| (index, file)));
+                        }
+                        loaded
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        eprintln!("Error: an index_workspace_scope worker panicked");
+                        Vec::new()
+                    })
+                })
+                .collect()
+        });
+
+        // commit the files in the order they were listed, so class ids don't depend on timing
+        loaded.sort_by_key(|(index, _)| *index);
+        let mut documents_already_existing = Vec::new();
+        for (_, file) in loaded {
+            match file {
+                // include files and routines aren't documents; only their macros and labels are
+                // kept
+                LoadedFile::Include { url, code } => {
+                    self.data_mut().update_include_macros(&url, &code);
+                }
+                LoadedFile::Routine { url, code } => {
+                    self.data_mut().update_routine(&url, &code);
+                }
+                LoadedFile::Class {
+                    url,
+                    code,
+                    tree,
+                    class_name,
+                } => {
+                    // Commit inside the ProjectData lock
+                    let already_exists = self.data_mut().add_document_if_absent(
+                        url.clone(),
+                        code.into(),
+                        tree,
+                        FileType::Cls,
+                        class_name,
+                        None,
+                    );
+                    if already_exists {
+                        documents_already_existing.push(url);
+                    }
+                }
+            }
+        }
+        self.index_progress.start_inheritance_build();
+        let mut data = self.data_mut();
+        data.build_inheritance_and_variables(None, documents_already_existing);
+        data.take_snapshot("workspace indexed".to_string());
+        successful_exit("ProjectState", "index_workspace_scope");
+    }
+
+    /// Download the classes the workspace extends or calls but doesn't define (see
+    /// `ProjectData::get_missing_class_names`) through `remote`, and index them as read-only
+    /// documents at the urls of `AtelierConfig::document_url`.
+    ///
+    /// The superclasses of downloaded classes are downloaded too. A class is only requested
+    /// once, and classes the server doesn't have are logged and skipped. Once the server can't
+    /// be reached, nothing more is requested. Requests block, so call this from a blocking
+    /// thread, after indexing the workspace.
+    pub fn fetch_remote_classes(&self, remote: &dyn SourceProvider, config: &AtelierConfig) {
+        start_of_function("ProjectState", "fetch_remote_classes");
+        let mut cls_parser = Parser::new();
+        if cls_parser
+            .set_language(&LANGUAGE_OBJECTSCRIPT.into())
+            .is_err()
+        {
+            eprintln!("Failed to load ObjectScript grammar");
+            generic_exit_statements("ProjectState", "fetch_remote_classes");
+            return;
+        }
+        let mut requested: Vec<String> = Vec::new();
+        let mut fetched = false;
+        'fetch: loop {
+            let missing: Vec<String> = self
+                .data
+                .read()
+                .get_missing_class_names()
+                .into_iter()
+                .filter(|name| !requested.contains(name))
+                .collect();
+            if missing.is_empty() {
+                break;
+            }
+            for class_name in missing {
+                requested.push(class_name.clone());
+                let Some(url) = config.document_url(&class_name) else {
+                    continue;
+                };
+                let code = match remote.read_file(&url) {
+                    Ok(code) => code,
+                    Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                        eprintln!("Warning: Stopped fetching classes from server: {e}");
+                        break 'fetch;
+                    }
+                    Err(e) => {
+                        eprintln!("Warning: Failed to fetch class {class_name} from server: {e}");
+                        continue;
+                    }
+                };
+                let Some(tree) = cls_parser.parse(&code, None) else {
+                    eprintln!("Failed to parse file: {url}");
+                    continue;
+                };
+                let Some(name) = get_class_name_from_root(code.as_str(), tree.root_node()) else {
+                    eprintln!("No class Name");
+                    continue;
+                };
+                self.data_mut().add_document_if_absent(
+                    url,
+                    code.into(),
+                    tree,
+                    FileType::Cls,
+                    name,
+                    None,
+                );
+                fetched = true;
+            }
+        }
+        if fetched {
+            self.data_mut()
+                .build_inheritance_and_variables(None, Vec::new());
+        }
+        successful_exit("ProjectState", "fetch_remote_classes");
+    }
+
+    /// Handle an LSP `textDocument/didClose`.
+    ///
+    /// The editor discards unsaved edits when closing a document, so it stops being synced and
+    /// goes back to the contents of its file, re-read through `sources` like a watched-file
+    /// change. If the file can't be read (e.g. it was never saved), the last synced text is
+    /// kept. File access may block, so call this from a blocking thread.
+    pub fn handle_document_closed(&self, sources: &dyn SourceProvider, url: &Url) {
+        start_of_function("ProjectState", "handle_document_closed");
+        if let Some(document) = self.data_mut().documents.get_mut(url) {
+            document.version = None;
+        }
+        self.semantic_tokens.remove(url);
+        self.apply_watched_file_change(sources, url, FileChangeType::CHANGED);
+        successful_exit("ProjectState", "handle_document_closed");
+    }
+
+    /// Apply one watched-file event to the index, re-reading the file through `sources`.
+    ///
+    /// Documents open in the editor are left alone, since the client syncs their text. Returns
+    /// `false` if the event can't be applied incrementally and needs a full re-index: changed
+    /// export archives.
+    pub fn apply_watched_file_change(
+        &self,
+        sources: &dyn SourceProvider,
+        url: &Url,
+        change: FileChangeType,
+    ) -> bool {
+        start_of_function("ProjectState", "apply_watched_file_change");
+        let tracked = self
+            .data
+            .read()
+            .documents
+            .get(url)
+            .map(|d| d.version.is_some());
+        if tracked == Some(true) {
+            successful_exit("ProjectState", "apply_watched_file_change");
+            return true;
+        }
+        if is_archive_url(url) {
+            generic_exit_statements("ProjectState", "apply_watched_file_change");
+            return false;
+        }
+        if let Some((name, file_type)) = routine_path_name(url) {
+            // read before taking the lock, as reading may block
+            let text = (change != FileChangeType::DELETED)
+                .then(|| sources.read_file(url).ok())
+                .flatten();
+            let mut data = self.data_mut();
+            if change == FileChangeType::DELETED {
+                data.routine_files.remove(&name);
+                data.include_macros.remove(&name);
+                data.routines.remove(url);
+            } else {
+                data.routine_files.insert(name, file_type);
+                if let Some(text) = text {
+                    data.update_include_macros(url, &text);
+                    data.update_routine(url, &text);
+                }
+            }
+            successful_exit("ProjectState", "apply_watched_file_change");
+            return true;
+        }
+        if change == FileChangeType::DELETED {
+            // deleting a file that was never indexed changes nothing
+            if tracked.is_some() {
+                self.remove_document(url);
+            }
+            successful_exit("ProjectState", "apply_watched_file_change");
+            return true;
+        }
+        // like `handle_document_opened`, only class files are indexed incrementally
+        if !url.path().ends_with(".cls") {
+            generic_exit_statements("ProjectState", "apply_watched_file_change");
+            return true;
+        }
+        match sources.read_file(url) {
+            Ok(text) => self.index_class_file(url, text),
+            Err(e) => eprintln!("Error: Failed to read changed file: {url}: {e}"),
+        }
+        successful_exit("ProjectState", "apply_watched_file_change");
+        true
+    }
+
+    /// Index the text of the class file at `url`, which isn't open in the editor, replacing the
+    /// indexed copy if there is one.
+    pub fn index_class_file(&self, url: &Url, text: String) {
+        start_of_function("ProjectState", "index_class_file");
+        let Some(tree) = self.parsers.cls.lock().parse(&text, None) else {
+            eprintln!("Failed to parse file: {url}");
+            generic_exit_statements("ProjectState", "index_class_file");
+            return;
+        };
+        let Some(class_name) = get_class_name_from_root(&text, tree.root_node()) else {
+            generic_exit_statements("ProjectState", "index_class_file");
+            return;
+        };
+        let mut data = self.data_mut();
+        if data.documents.contains_key(url) {
+            data.update_document(url.clone(), tree, FileType::Cls, 0, text.into());
+            // the file is still closed in the editor
+            if let Some(document) = data.documents.get_mut(url) {
+                document.version = None;
+            }
+        } else {
+            data.add_document(
+                url.clone(),
+                text.into(),
+                tree,
+                FileType::Cls,
+                class_name,
+                None,
+            );
+            data.build_inheritance_and_variables(Some(url.clone()), Vec::new());
+        }
+        successful_exit("ProjectState", "index_class_file");
+    }
+
+    /// Re-index every source file `sources` lists under `root` into fresh project data, then swap
+    /// it in atomically.
+    ///
+    /// Requests keep being served from the current data while the scan runs. Documents open in
+    /// the editor keep their synced text, including edits made during the scan, and the classes
+    /// downloaded from the server (see `fetch_remote_classes`), the config and the snapshot
+    /// history carry over. File access may block, so call this from a blocking thread.
+    pub fn reindex_workspace_scope(&self, sources: &dyn SourceProvider, root: &Url) {
+        start_of_function("ProjectState", "reindex_workspace_scope");
+        let fresh = ProjectState::new();
+        let open_documents: Vec<(Url, String, FileType, i32)> = {
+            let data = self.data.read();
+            fresh.data_mut().config = data.config.clone();
+            // downloaded classes aren't listed by `sources`; open ones are seeded below
+            for (url, document) in &data.documents {
+                if is_read_only_url(url) && document.version.is_none() {
+                    fresh.data_mut().add_document(
+                        url.clone(),
+                        document.content.clone(),
+                        document.tree.clone(),
+                        FileType::Cls,
+                        document.class_name.clone(),
+                        None,
+                    );
+                }
+            }
+            data.documents
+                .iter()
+                .filter_map(|(url, document)| {
+                    Some((
+                        url.clone(),
+                        document.content.as_str().to_string(),
+                        document.file_type.clone(),
+                        document.version?,
+                    ))
+                })
+                .collect()
+        };
+        for (url, text, file_type, version) in &open_documents {
+            fresh.handle_document_opened(url.clone(), text.clone(), file_type.clone(), *version);
+        }
+        fresh.index_workspace_scope(sources, root);
+
+        let mut fresh_data = fresh.data.into_inner();
+        let mut data = self.data_mut();
+        // replay edits the editor synced while the scan was running
+        for (url, document) in &data.documents {
+            let Some(version) = document.version else {
+                continue;
+            };
+            let seeded = open_documents
+                .iter()
+                .any(|(seeded_url, _, _, seeded_version)| {
+                    seeded_url == url && *seeded_version == version
+                });
+            if seeded {
+                continue;
+            }
+            if fresh_data.documents.contains_key(url) {
+                fresh_data.update_document(
+                    url.clone(),
+                    document.tree.clone(),
+                    document.file_type.clone(),
+                    version,
+                    document.content.clone(),
+                );
+            } else {
+                fresh_data.add_document(
+                    url.clone(),
+                    document.content.clone(),
+                    document.tree.clone(),
+                    document.file_type.clone(),
+                    document.class_name.clone(),
+                    Some(version),
+                );
+                fresh_data.build_inheritance_and_variables(Some(url.clone()), Vec::new());
+            }
+        }
+        fresh_data.config = data.config.clone();
+        fresh_data.snapshots = std::mem::take(&mut data.snapshots);
+        fresh_data.take_snapshot("workspace re-indexed".to_string());
+        *data = fresh_data;
+        successful_exit("ProjectState", "reindex_workspace_scope");
     }
 
     /// Return the project root path, if initialized.
     pub fn root_path(&self) -> Option<&std::path::Path> {
         self.project_root_path.get().and_then(|o| o.as_deref())
     }
+
+    /// Return the project root uri, if initialized.
+    pub fn root_uri(&self) -> Option<&Url> {
+        self.project_root_uri.get()
+    }
+
+    /// Return the project's `SourceProvider`, defaulting to the local filesystem.
+    pub fn sources(&self) -> Arc<dyn SourceProvider> {
+        Arc::clone(
+            self.source_provider
+                .get_or_init(|| Arc::new(FsSourceProvider)),
+        )
+    }
 }
@@ -0,0 +1,602 @@
+use crate::common::{position_encoding, start_of_function, successful_exit, PositionEncoding};
+use tower_lsp::lsp_types::{Position, Range, Url};
+
+/// File extensions of Studio/`$system.OBJ.Export` XML export archives.
+pub const ARCHIVE_EXTENSIONS: [&str; 1] = ["xml"];
+
+/// Returns `true` if the path of `url` ends in one of `ARCHIVE_EXTENSIONS`.
+pub fn is_archive_url(url: &Url) -> bool {
+    url.path()
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| ARCHIVE_EXTENSIONS.contains(&ext))
+}
+
+/// A class or routine extracted from an XML export archive.
+#[derive(Clone, Debug)]
+pub struct ExportItem {
+    /// Document name with its source extension, e.g. `Pkg.Person.cls` or `Util.mac`.
+    pub document_name: String,
+    /// UDL source reconstructed from the export.
+    pub content: String,
+    /// For each line of `content`: the zero-based line of the export file it came from, and the
+    /// column (in the negotiated position encoding) its text starts at, or `None` for lines
+    /// synthesized from element metadata (class and method headers, braces).
+    pub lines: Vec<(u32, Option<u32>)>,
+}
+
+impl ExportItem {
+    /// Returns the uri the item is indexed under: the archive uri with the document name appended
+    /// as an extra path segment (e.g. `file:///ws/export.xml/Pkg.Person.cls`).
+    pub fn url(&self, archive: &Url) -> Url {
+        let mut url = archive.clone();
+        url.set_path(&format!("{}/{}", archive.path(), self.document_name));
+        url
+    }
+}
+
+/// Maps positions in a document extracted from an export archive back into the archive.
+#[derive(Clone, Debug)]
+pub struct ExportLineMap {
+    /// Uri of the XML export the document was extracted from.
+    pub archive: Url,
+    /// See `ExportItem::lines`.
+    lines: Vec<(u32, Option<u32>)>,
+}
+
+impl ExportLineMap {
+    /// Creates the line map of `item`, extracted from `archive`.
+    pub fn new(archive: Url, item: &ExportItem) -> Self {
+        Self {
+            archive,
+            lines: item.lines.clone(),
+        }
+    }
+
+    /// Maps a position of the extracted document to the corresponding archive position.
+    ///
+    /// Positions on synthesized lines map to the start of the element they were built from.
+    pub fn to_archive_position(&self, position: Position) -> Position {
+        let Some(&(line, column)) = self.lines.get(position.line as usize).or(self.lines.last())
+        else {
+            return position;
+        };
+        Position {
+            line,
+            character: column.map_or(0, |column| column + position.character),
+        }
+    }
+
+    /// Maps a range of the extracted document to the corresponding archive range.
+    pub fn to_archive_range(&self, range: Range) -> Range {
+        Range {
+            start: self.to_archive_position(range.start),
+            end: self.to_archive_position(range.end),
+        }
+    }
+}
+
+/// Extracts the classes and routines of an XML export.
+///
+/// Class members are reassembled into UDL (`Class ... { Method ... { } }`) so they can be parsed
+/// like `.cls` files; only parameters, properties, and methods are carried over. Returns an empty
+/// vector if `content` is not an `<Export>` document.
+pub fn parse_export(content: &str) -> Vec<ExportItem> {
+    start_of_function("XML EXPORT (no struct)", "parse_export");
+    let Some(root) = XmlReader::new(content).read_document() else {
+        successful_exit("XML EXPORT (no struct)", "parse_export");
+        return Vec::new();
+    };
+    if root.name != "Export" {
+        successful_exit("XML EXPORT (no struct)", "parse_export");
+        return Vec::new();
+    }
+    let items = root
+        .children
+        .iter()
+        .filter_map(|item| match item.name.as_str() {
+            "Class" => class_item(item),
+            "Routine" => routine_item(item),
+            _ => None,
+        })
+        .collect();
+    successful_exit("XML EXPORT (no struct)", "parse_export");
+    items
+}
+
+/// A parsed XML element.
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlElement>,
+    /// Decoded character data and CDATA sections directly inside the element.
+    text: String,
+    /// Line and column (in the negotiated position encoding) of the first character of `text`.
+    text_start: (u32, u32),
+    /// Line of the element's start tag.
+    line: u32,
+}
+
+impl XmlElement {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name)
+            .map(|child| child.text.trim())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Minimal XML reader for export files: elements, attributes, character data, CDATA sections,
+/// and the predefined/numeric entities. Comments, processing instructions, and the doctype are
+/// skipped.
+struct XmlReader<'a> {
+    src: &'a str,
+    pos: usize,
+    line: u32,
+    column: u32,
+    encoding: PositionEncoding,
+}
+
+impl<'a> XmlReader<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 0,
+            column: 0,
+            encoding: position_encoding(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// Advances `n` bytes, keeping `line` and `column` up to date.
+    fn advance(&mut self, n: usize) {
+        let end = (self.pos + n).min(self.src.len());
+        for c in self.src[self.pos..end].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += self.encoding.char_len(c) as u32;
+            }
+        }
+        self.pos = end;
+    }
+
+    /// Advances past the next occurrence of `end`, returning the text before it.
+    fn take_until(&mut self, end: &str) -> Option<&'a str> {
+        let offset = self.rest().find(end)?;
+        let text = &self.rest()[..offset];
+        self.advance(offset + end.len());
+        Some(text)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().len() - self.rest().trim_start().len();
+        self.advance(trimmed);
+    }
+
+    /// Skips a comment, processing instruction, or doctype at the cursor, if there is one.
+    fn skip_markup(&mut self) -> Option<bool> {
+        let end = if self.rest().starts_with("<!--") {
+            "-->"
+        } else if self.rest().starts_with("<?") {
+            "?>"
+        } else if self.rest().starts_with("<!DOCTYPE") {
+            ">"
+        } else {
+            return Some(false);
+        };
+        self.take_until(end)?;
+        Some(true)
+    }
+
+    /// Reads the prolog and returns the root element.
+    fn read_document(&mut self) -> Option<XmlElement> {
+        loop {
+            self.skip_whitespace();
+            if !self.skip_markup()? {
+                break;
+            }
+        }
+        self.rest().starts_with('<').then_some(())?;
+        self.read_element()
+    }
+
+    fn read_name(&mut self) -> &'a str {
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+            .unwrap_or(self.rest().len());
+        let name = &self.rest()[..len];
+        self.advance(len);
+        name
+    }
+
+    /// Reads the element whose start tag begins at the cursor.
+    fn read_element(&mut self) -> Option<XmlElement> {
+        let mut element = XmlElement {
+            line: self.line,
+            ..Default::default()
+        };
+        self.advance(1);
+        element.name = self.read_name().to_string();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.advance(2);
+                return Some(element);
+            }
+            if self.rest().starts_with('>') {
+                self.advance(1);
+                break;
+            }
+            let key = self.read_name().to_string();
+            if key.is_empty() {
+                return None;
+            }
+            self.skip_whitespace();
+            self.rest().starts_with('=').then_some(())?;
+            self.advance(1);
+            self.skip_whitespace();
+            let quote = self
+                .rest()
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '"' | '\''))?;
+            self.advance(1);
+            let value = self.take_until(&quote.to_string())?;
+            element.attributes.push((key, decode_entities(value)));
+        }
+
+        loop {
+            if self.rest().starts_with("</") {
+                self.take_until(">")?;
+                return Some(element);
+            }
+            if self.rest().starts_with("<![CDATA[") {
+                self.advance("<![CDATA[".len());
+                let start = (self.line, self.column);
+                let text = self.take_until("]]>")?;
+                Self::push_text(&mut element, text.to_string(), start);
+            } else if self.skip_markup()? {
+                continue;
+            } else if self.rest().starts_with('<') {
+                let child = self.read_element()?;
+                element.children.push(child);
+            } else if self.rest().is_empty() {
+                eprintln!("Warning: unterminated <{}> element in export", element.name);
+                return None;
+            } else {
+                let start = (self.line, self.column);
+                let len = self.rest().find('<').unwrap_or(self.rest().len());
+                let text = &self.rest()[..len];
+                self.advance(len);
+                Self::push_text(&mut element, decode_entities(text), start);
+            }
+        }
+    }
+
+    /// Appends character data to `element.text`; whitespace that precedes the first real text
+    /// (typically the indentation before a CDATA section) is dropped.
+    fn push_text(element: &mut XmlElement, text: String, start: (u32, u32)) {
+        if element.text.trim().is_empty() {
+            element.text = text;
+            element.text_start = start;
+        } else {
+            element.text.push_str(&text);
+        }
+    }
+}
+
+/// Decodes the predefined and numeric character entities of `text`.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Accumulates reconstructed UDL together with its line map.
+#[derive(Default)]
+struct UdlBuilder {
+    content: String,
+    lines: Vec<(u32, Option<u32>)>,
+}
+
+impl UdlBuilder {
+    /// Appends a line synthesized from the element starting at `xml_line`.
+    fn synthesized(&mut self, text: &str, xml_line: u32) {
+        self.content.push_str(text);
+        self.content.push('\n');
+        self.lines.push((xml_line, None));
+    }
+
+    /// Appends the lines of `element.text` verbatim (without the empty first and last lines that
+    /// surround a CDATA block), prefixing each with `prefix`.
+    fn verbatim(&mut self, element: &XmlElement, prefix: &str) {
+        let (first_line, first_column) = element.text_start;
+        let mut lines: Vec<&str> = element.text.split('\n').collect();
+        let mut skipped = 0;
+        if lines.first().is_some_and(|line| line.trim().is_empty()) {
+            lines.remove(0);
+            skipped = 1;
+        }
+        if lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        for (i, line) in lines.into_iter().enumerate() {
+            let index = (i + skipped) as u32;
+            let column = if index == 0 { first_column } else { 0 };
+            self.content.push_str(prefix);
+            self.content.push_str(line.trim_end_matches('\r'));
+            self.content.push('\n');
+            // columns of prefixed lines no longer line up with the archive
+            let column = prefix.is_empty().then_some(column);
+            self.lines.push((first_line + index, column));
+        }
+    }
+
+    fn finish(self, document_name: String) -> ExportItem {
+        ExportItem {
+            document_name,
+            content: self.content,
+            lines: self.lines,
+        }
+    }
+}
+
+/// How a keyword element is rendered in a UDL keyword list.
+enum KeywordStyle {
+    /// `<Abstract>1</Abstract>` -> `Abstract`, `0` -> `Not Abstract`.
+    Flag,
+    /// `<Language>tsql</Language>` -> `Language = tsql`.
+    Value,
+    /// `<PublicList>a,b</PublicList>` -> `PublicList = (a, b)`.
+    List,
+}
+
+const CLASS_KEYWORDS: [(&str, KeywordStyle); 5] = [
+    ("Abstract", KeywordStyle::Flag),
+    ("Final", KeywordStyle::Flag),
+    ("GeneratedBy", KeywordStyle::Value),
+    ("Language", KeywordStyle::Value),
+    ("ProcedureBlock", KeywordStyle::Flag),
+];
+
+const METHOD_KEYWORDS: [(&str, KeywordStyle); 7] = [
+    ("Abstract", KeywordStyle::Flag),
+    ("CodeMode", KeywordStyle::Value),
+    ("Final", KeywordStyle::Flag),
+    ("Language", KeywordStyle::Value),
+    ("Private", KeywordStyle::Flag),
+    ("ProcedureBlock", KeywordStyle::Flag),
+    ("PublicList", KeywordStyle::List),
+];
+
+/// Renders the keyword elements of `element` as a UDL keyword list (` [ A, Not B ]`).
+fn keyword_list(element: &XmlElement, keywords: &[(&str, KeywordStyle)]) -> String {
+    let rendered: Vec<String> = keywords
+        .iter()
+        .filter_map(|(name, style)| {
+            let value = element.child_text(name)?;
+            Some(match style {
+                KeywordStyle::Flag if value == "0" => format!("Not {name}"),
+                KeywordStyle::Flag => name.to_string(),
+                KeywordStyle::Value => format!("{name} = {value}"),
+                KeywordStyle::List => {
+                    let items: Vec<&str> = value.split(',').map(str::trim).collect();
+                    format!("{name} = ({})", items.join(", "))
+                }
+            })
+        })
+        .collect();
+    if rendered.is_empty() {
+        String::new()
+    } else {
+        format!(" [ {} ]", rendered.join(", "))
+    }
+}
+
+/// Converts an export `FormalSpec` (`&a:%String,b:%Integer=5`) into a UDL argument list
+/// (`ByRef a As %String, b As %Integer = 5`).
+fn formal_spec_to_udl(spec: &str) -> String {
+    let mut arguments = Vec::new();
+    let (mut depth, mut in_string, mut start) = (0usize, false, 0);
+    for (i, c) in spec.char_indices().chain([(spec.len(), ',')]) {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                let argument = spec[start..i].trim();
+                if !argument.is_empty() {
+                    arguments.push(argument_to_udl(argument));
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.join(", ")
+}
+
+/// Converts a single `FormalSpec` argument into UDL.
+fn argument_to_udl(argument: &str) -> String {
+    let (prefix, argument) = if let Some(rest) = argument.strip_prefix('&') {
+        ("ByRef ", rest)
+    } else if let Some(rest) = argument.strip_prefix('*') {
+        ("Output ", rest)
+    } else {
+        ("", argument)
+    };
+    let (declaration, default) = match top_level_find(argument, '=') {
+        Some(i) => (&argument[..i], Some(&argument[i + 1..])),
+        None => (argument, None),
+    };
+    let mut udl = match declaration.split_once(':') {
+        Some((name, type_name)) => format!("{prefix}{name} As {type_name}"),
+        None => format!("{prefix}{declaration}"),
+    };
+    if let Some(default) = default {
+        udl.push_str(" = ");
+        udl.push_str(default);
+    }
+    udl
+}
+
+/// Finds `needle` outside of strings and parentheses.
+fn top_level_find(text: &str, needle: char) -> Option<usize> {
+    let (mut depth, mut in_string) = (0usize, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            c if c == needle && !in_string && depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Appends a member's `<Description>` as `///` comment lines.
+fn description(udl: &mut UdlBuilder, member: &XmlElement) {
+    if let Some(description) = member.child("Description") {
+        udl.verbatim(description, "/// ");
+    }
+}
+
+/// Reconstructs the UDL of an exported `<Class>`.
+fn class_item(class: &XmlElement) -> Option<ExportItem> {
+    let name = class.attribute("name")?;
+    let mut udl = UdlBuilder::default();
+    description(&mut udl, class);
+    let mut header = format!("Class {name}");
+    if let Some(super_classes) = class.child_text("Super") {
+        if super_classes.contains(',') {
+            let supers: Vec<&str> = super_classes.split(',').map(str::trim).collect();
+            header.push_str(&format!(" Extends ({})", supers.join(", ")));
+        } else {
+            header.push_str(&format!(" Extends {super_classes}"));
+        }
+    }
+    header.push_str(&keyword_list(class, &CLASS_KEYWORDS));
+    udl.synthesized(&header, class.line);
+    udl.synthesized("{", class.line);
+
+    for member in &class.children {
+        let Some(member_name) = member.attribute("name") else {
+            continue;
+        };
+        match member.name.as_str() {
+            "Parameter" => {
+                description(&mut udl, member);
+                let line = match member.child_text("Default") {
+                    Some(value) if value.parse::<f64>().is_ok() => {
+                        format!("Parameter {member_name} = {value};")
+                    }
+                    Some(value) => format!(
+                        "Parameter {member_name} = \"{}\";",
+                        value.replace('"', "\"\"")
+                    ),
+                    None => format!("Parameter {member_name};"),
+                };
+                udl.synthesized(&line, member.line);
+            }
+            "Property" => {
+                description(&mut udl, member);
+                let line = match (member.child_text("Type"), member.child_text("Collection")) {
+                    (Some(type_name), Some(collection)) => {
+                        format!("Property {member_name} As {collection} Of {type_name};")
+                    }
+                    (Some(type_name), None) => format!("Property {member_name} As {type_name};"),
+                    _ => format!("Property {member_name};"),
+                };
+                udl.synthesized(&line, member.line);
+            }
+            "Method" => {
+                description(&mut udl, member);
+                let kind = if member.child_text("ClassMethod") == Some("1") {
+                    "ClassMethod"
+                } else {
+                    "Method"
+                };
+                let arguments = member
+                    .child_text("FormalSpec")
+                    .map(formal_spec_to_udl)
+                    .unwrap_or_default();
+                let mut header = format!("{kind} {member_name}({arguments})");
+                if let Some(return_type) = member.child_text("ReturnType") {
+                    header.push_str(&format!(" As {return_type}"));
+                }
+                header.push_str(&keyword_list(member, &METHOD_KEYWORDS));
+                udl.synthesized(&header, member.line);
+                udl.synthesized("{", member.line);
+                if let Some(implementation) = member.child("Implementation") {
+                    udl.verbatim(implementation, "");
+                }
+                udl.synthesized("}", member.line);
+            }
+            _ => {}
+        }
+    }
+    udl.synthesized("}", class.line);
+    Some(udl.finish(format!("{name}.cls")))
+}
+
+/// Extracts an exported `<Routine>`; only MAC/INT routines and INC include files are supported.
+fn routine_item(routine: &XmlElement) -> Option<ExportItem> {
+    let name = routine.attribute("name")?;
+    let extension = match routine.attribute("type")?.to_ascii_uppercase().as_str() {
+        "MAC" | "INT" => "mac",
+        "INC" => "inc",
+        _ => return None,
+    };
+    let mut udl = UdlBuilder::default();
+    udl.verbatim(routine, "");
+    Some(udl.finish(format!("{name}.{extension}")))
+}